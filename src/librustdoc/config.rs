@@ -22,6 +22,7 @@
 
 use crate::core::new_handler;
 use crate::externalfiles::ExternalHtml;
+use crate::fuzz_targets_gen::fuzz_options::FuzzOptions;
 use crate::html;
 use crate::html::markdown::IdMap;
 use crate::html::render::StylePath;
@@ -39,8 +40,9 @@ pub(crate) enum OutputFormat {
 }
 
 impl Default for OutputFormat {
+    /// FRIES默认生成fuzz target，需要文档时用`--output-format html`
     fn default() -> OutputFormat {
-        OutputFormat::Html
+        OutputFormat::TarGen
     }
 }
 
@@ -278,6 +280,8 @@ pub(crate) struct RenderOptions {
     pub(crate) call_locations: AllCallLocations,
     /// If `true`, Context::init will not emit shared files.
     pub(crate) no_emit_shared: bool,
+    /// 生成fuzz target的选项
+    pub(crate) fuzz_options: FuzzOptions,
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
@@ -694,8 +698,12 @@ fn println_condition(condition: Condition) {
         let runtool = matches.opt_str("runtool");
         let runtool_args = matches.opt_strs("runtool-arg");
         let enable_per_target_ignores = matches.opt_present("enable-per-target-ignores");
-        let document_private = matches.opt_present("document-private-items");
-        let document_hidden = matches.opt_present("document-hidden-items");
+        // FRIES在crate内部生成fuzz target时也要保留私有的item
+        let document_private =
+            matches.opt_present("document-private-items") || matches.opt_present("fuzz-internal");
+        // FRIES要生成doc(hidden)的API时，不能在pass里把它们去掉
+        let document_hidden = matches.opt_present("document-hidden-items")
+            || matches.opt_present("include-doc-hidden");
         let run_check = matches.opt_present("check");
         let generate_redirect_map = matches.opt_present("generate-redirect-map");
         let show_type_layout = matches.opt_present("show-type-layout");
@@ -715,6 +723,7 @@ fn println_condition(condition: Condition) {
         let scrape_examples_options = ScrapeExamplesOptions::new(matches, &diag)?;
         let with_examples = matches.opt_strs("with-examples");
         let call_locations = crate::scrape_examples::load_call_locations(with_examples, &diag)?;
+        let fuzz_options = FuzzOptions::new(matches, &diag)?;
 
         let unstable_features =
             rustc_feature::UnstableFeatures::from_environment(crate_name.as_deref());
@@ -785,6 +794,7 @@ fn println_condition(condition: Condition) {
             generate_link_to_definition,
             call_locations,
             no_emit_shared: false,
+            fuzz_options,
         };
         Ok((options, render_options))
     }
@@ -0,0 +1,152 @@
+//! afl的字典
+//! 解析器一类的crate经常把输入和固定的字符串、魔数比较，随机变异很难撞上，
+//! 所以遍历crate的HIR，收集这些常量写成afl的字典（`afl-fuzz -x <crate>.dict`）：
+//! 1. 函数体里的字符串、字节串和字符字面量
+//! 2. 比较运算和match模式里的整数字面量，按能放下它的最小宽度分别写小端和大端
+//! 3. 枚举显式写出的判别值
+
+use rustc_ast::LitKind;
+use rustc_data_structures::fx::FxHashSet;
+use rustc_hir::{
+    self as hir,
+    intravisit::{self, Visitor},
+};
+use rustc_middle::hir::nested_filter;
+use rustc_middle::ty::TyCtxt;
+
+/// afl允许的字典项的最大长度
+static MAX_DICT_ENTRY_LENGTH: usize = 128;
+
+/// 字典项太多的时候afl会变慢，只保留前面的这么多项
+static MAX_DICT_ENTRIES: usize = 4096;
+
+struct LiteralCollector<'tcx> {
+    tcx: TyCtxt<'tcx>,
+    entries: Vec<Vec<u8>>,
+    seen: FxHashSet<Vec<u8>>,
+}
+
+impl<'tcx> LiteralCollector<'tcx> {
+    fn add_entry(&mut self, entry: Vec<u8>) {
+        if entry.is_empty()
+            || entry.len() > MAX_DICT_ENTRY_LENGTH
+            || self.entries.len() >= MAX_DICT_ENTRIES
+        {
+            return;
+        }
+        if self.seen.insert(entry.clone()) {
+            self.entries.push(entry);
+        }
+    }
+
+    /// 字符串一类的字面量，不管出现在哪里都收集
+    fn add_text_literal(&mut self, lit_kind: &LitKind) {
+        match lit_kind {
+            LitKind::Str(symbol, _) => self.add_entry(symbol.as_str().as_bytes().to_vec()),
+            LitKind::ByteStr(bytes) => self.add_entry(bytes.to_vec()),
+            LitKind::Char(c) if !c.is_ascii() => self.add_entry(c.to_string().as_bytes().to_vec()),
+            _ => {}
+        }
+    }
+
+    /// 出现在比较里的字面量，整数也收集
+    fn add_compared_expr(&mut self, expr: &hir::Expr<'_>) {
+        if let hir::ExprKind::Lit(lit) = &expr.kind {
+            match lit.node {
+                LitKind::Int(value, _) => self.add_integer(value),
+                LitKind::Byte(byte) => self.add_entry(vec![byte]),
+                LitKind::Char(c) => self.add_entry(c.to_string().as_bytes().to_vec()),
+                _ => self.add_text_literal(&lit.node),
+            }
+        }
+    }
+
+    /// 0和1这样的小整数变异时随时会出现，不写进字典
+    fn add_integer(&mut self, value: u128) {
+        if value <= 1 {
+            return;
+        }
+        let width = [1, 2, 4, 8, 16]
+            .into_iter()
+            .find(|width| *width == 16 || value < 1u128 << (width * 8))
+            .unwrap();
+        let le_bytes = value.to_le_bytes()[..width].to_vec();
+        let mut be_bytes = le_bytes.clone();
+        be_bytes.reverse();
+        self.add_entry(le_bytes);
+        self.add_entry(be_bytes);
+    }
+}
+
+impl<'tcx> Visitor<'tcx> for LiteralCollector<'tcx> {
+    type NestedFilter = nested_filter::OnlyBodies;
+
+    fn nested_visit_map(&mut self) -> Self::Map {
+        self.tcx.hir()
+    }
+
+    fn visit_expr(&mut self, ex: &'tcx hir::Expr<'tcx>) {
+        match &ex.kind {
+            hir::ExprKind::Lit(lit) => self.add_text_literal(&lit.node),
+            hir::ExprKind::Binary(op, lhs, rhs) if op.node.is_comparison() => {
+                self.add_compared_expr(lhs);
+                self.add_compared_expr(rhs);
+            }
+            _ => {}
+        }
+        intravisit::walk_expr(self, ex);
+    }
+
+    fn visit_pat(&mut self, p: &'tcx hir::Pat<'tcx>) {
+        match p.kind {
+            hir::PatKind::Lit(expr) => self.add_compared_expr(expr),
+            hir::PatKind::Range(start, end, _) => {
+                for expr in start.into_iter().chain(end) {
+                    self.add_compared_expr(expr);
+                }
+            }
+            _ => {}
+        }
+        intravisit::walk_pat(self, p);
+    }
+
+    fn visit_variant(&mut self, v: &'tcx hir::Variant<'tcx>) {
+        if let Some(disr_expr) = &v.disr_expr {
+            let body = self.tcx.hir().body(disr_expr.body);
+            self.add_compared_expr(body.value);
+        }
+        intravisit::walk_variant(self, v);
+    }
+}
+
+/// 收集本地crate里的字典项，按第一次出现的顺序
+pub(crate) fn collect_dictionary(tcx: TyCtxt<'_>) -> Vec<Vec<u8>> {
+    let mut collector = LiteralCollector { tcx, entries: Vec::new(), seen: FxHashSet::default() };
+    tcx.hir().visit_all_item_likes_in_crate(&mut collector);
+    collector.entries
+}
+
+/// afl字典文件的文件名
+pub(crate) fn dictionary_file_name(crate_name: &str) -> String {
+    format!("{}.dict", crate_name)
+}
+
+/// 写成afl字典的格式，每行一个`name="value"`，不可打印的字节写成`\xNN`
+pub(crate) fn render_dictionary(entries: &[Vec<u8>]) -> String {
+    let mut res = String::new();
+    for (i, entry) in entries.iter().enumerate() {
+        res.push_str(format!("lit_{}=\"", i).as_str());
+        for byte in entry {
+            match byte {
+                b'"' | b'\\' => {
+                    res.push('\\');
+                    res.push(*byte as char);
+                }
+                0x20..=0x7e => res.push(*byte as char),
+                _ => res.push_str(format!("\\x{:02x}", byte).as_str()),
+            }
+        }
+        res.push_str("\"\n");
+    }
+    res
+}
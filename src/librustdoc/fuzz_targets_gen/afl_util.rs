@@ -1,4 +1,5 @@
 use crate::clean::PrimitiveType;
+use crate::fuzz_targets_gen::collection_param::CollectionKind;
 use crate::fuzz_targets_gen::fuzz_type::FuzzableType;
 use rustc_data_structures::fx::FxHashSet;
 
@@ -21,6 +22,8 @@ pub(crate) enum _AflHelpers {
     _U64,
     _I64,
     _F64,
+    _CornerF32,
+    _CornerF64,
     _U128,
     _I128,
     _Usize,
@@ -28,8 +31,12 @@ pub(crate) enum _AflHelpers {
     _Char,
     _Bool,
     _Str,
+    _ByteReader, //依次读取输入的_ByteReader，不对应参数类型
     _Slice(Box<_AflHelpers>),
     _Tuple(Vec<Box<_AflHelpers>>),
+    _Array(Box<_AflHelpers>, usize),
+    _Enum(Vec<Vec<Box<_AflHelpers>>>), //每个变体的字段
+    _Collection(CollectionKind, Vec<Box<_AflHelpers>>), //(集合种类, [键, 值])
 }
 
 impl _AflHelpers {
@@ -56,6 +63,11 @@ pub(crate) fn _new_from_fuzzable(fuzzable: &FuzzableType) -> Self {
                 PrimitiveType::F64 => _AflHelpers::_F64,
                 _ => _AflHelpers::_NoHelper,
             },
+            FuzzableType::CornerFloat(primitive_type) => match primitive_type {
+                PrimitiveType::F32 => _AflHelpers::_CornerF32,
+                PrimitiveType::F64 => _AflHelpers::_CornerF64,
+                _ => _AflHelpers::_NoHelper,
+            },
             FuzzableType::RefSlice(inner_fuzzable) => {
                 let inner_afl_helper = _AflHelpers::_new_from_fuzzable(inner_fuzzable);
                 _AflHelpers::_Slice(Box::new(inner_afl_helper))
@@ -67,6 +79,30 @@ pub(crate) fn _new_from_fuzzable(fuzzable: &FuzzableType) -> Self {
                     .collect();
                 _AflHelpers::_Tuple(inner_afl_helpers)
             }
+            FuzzableType::Array(inner_fuzzable, length) => {
+                let inner_afl_helper = _AflHelpers::_new_from_fuzzable(inner_fuzzable);
+                _AflHelpers::_Array(Box::new(inner_afl_helper), *length)
+            }
+            FuzzableType::Enum(_, variants) => {
+                let variant_afl_helpers = variants
+                    .iter()
+                    .map(|variant| {
+                        variant
+                            .fields
+                            .iter()
+                            .map(|(_, field)| Box::new(_AflHelpers::_new_from_fuzzable(field)))
+                            .collect::<Vec<_>>()
+                    })
+                    .collect();
+                _AflHelpers::_Enum(variant_afl_helpers)
+            }
+            FuzzableType::Collection(kind, item_types) => {
+                let item_afl_helpers = item_types
+                    .iter()
+                    .map(|item_type| Box::new(_AflHelpers::_new_from_fuzzable(item_type)))
+                    .collect();
+                _AflHelpers::_Collection(*kind, item_afl_helpers)
+            }
         }
     }
 
@@ -81,16 +117,48 @@ pub(crate) fn _get_all_dependent_afl_helpers(&self) -> Vec<_AflHelpers> {
                 let mut inner_dependent = afl_helper._get_all_dependent_afl_helpers();
                 helpers.append(&mut inner_dependent);
             }
+        } else if let _AflHelpers::_Array(inner_helper, _) = self {
+            //数组也不需要单独的函数，每个元素用元素类型的函数解码
+            helpers.append(&mut inner_helper._get_all_dependent_afl_helpers());
+        } else if let _AflHelpers::_Collection(_, item_afl_helpers) = self {
+            //逐项解码，不需要单独的函数
+            for afl_helper in item_afl_helpers {
+                helpers.append(&mut afl_helper._get_all_dependent_afl_helpers());
+            }
+        } else if let _AflHelpers::_Enum(variant_afl_helpers) = self {
+            //和tuple一样不需要单独的函数，选择变体要用_to_u8
+            helpers.append(&mut _AflHelpers::_U8._get_all_dependent_afl_helpers());
+            for inner_helpers in variant_afl_helpers {
+                for afl_helper in inner_helpers {
+                    let mut inner_dependent = afl_helper._get_all_dependent_afl_helpers();
+                    helpers.append(&mut inner_dependent);
+                }
+            }
         } else {
             helpers.push(self.clone());
             match self {
                 _AflHelpers::_U8
                 | _AflHelpers::_I8
                 | _AflHelpers::_NoHelper
-                | _AflHelpers::_Slice(..)
+                | _AflHelpers::_ByteReader
                 | _AflHelpers::_Str
                 | _AflHelpers::_F32
-                | _AflHelpers::_F64 => {}
+                | _AflHelpers::_F64
+                | _AflHelpers::_Char => {}
+                _AflHelpers::_Slice(inner_afl_helper) => {
+                    //元素长度可变的时候元素用自己的函数解码，定长的元素直接用_to_slice
+                    if matches!(**inner_afl_helper, _AflHelpers::_Str | _AflHelpers::_Slice(..)) {
+                        helpers.append(&mut inner_afl_helper._get_all_dependent_afl_helpers());
+                    }
+                }
+                _AflHelpers::_CornerF32 => {
+                    let mut f32_dependency = _AflHelpers::_F32._get_all_dependent_afl_helpers();
+                    helpers.append(&mut f32_dependency);
+                }
+                _AflHelpers::_CornerF64 => {
+                    let mut f64_dependency = _AflHelpers::_F64._get_all_dependent_afl_helpers();
+                    helpers.append(&mut f64_dependency);
+                }
                 _AflHelpers::_Bool => {
                     let mut u8_dependency = _AflHelpers::_U8._get_all_dependent_afl_helpers();
                     helpers.append(&mut u8_dependency);
@@ -135,11 +203,10 @@ pub(crate) fn _get_all_dependent_afl_helpers(&self) -> Vec<_AflHelpers> {
                     let mut i64_dependency = _AflHelpers::_I64._get_all_dependent_afl_helpers();
                     helpers.append(&mut i64_dependency);
                 }
-                _AflHelpers::_Char => {
-                    let mut u32_dependency = _AflHelpers::_U32._get_all_dependent_afl_helpers();
-                    helpers.append(&mut u32_dependency);
-                }
-                _AflHelpers::_Tuple(..) => {}
+                _AflHelpers::_Tuple(..)
+                | _AflHelpers::_Array(..)
+                | _AflHelpers::_Enum(..)
+                | _AflHelpers::_Collection(..) => {}
             }
         }
         helpers
@@ -158,6 +225,8 @@ pub(crate) fn _to_full_function(&self) -> &'static str {
             _AflHelpers::_U64 => _data_to_u64(),
             _AflHelpers::_I64 => _data_to_i64(),
             _AflHelpers::_F64 => _data_to_f64(),
+            _AflHelpers::_CornerF32 => _data_to_corner_f32(),
+            _AflHelpers::_CornerF64 => _data_to_corner_f64(),
             _AflHelpers::_U128 => _data_to_u128(),
             _AflHelpers::_I128 => _data_to_i128(),
             _AflHelpers::_Usize => _data_to_usize(),
@@ -165,8 +234,12 @@ pub(crate) fn _to_full_function(&self) -> &'static str {
             _AflHelpers::_Char => _data_to_char(),
             _AflHelpers::_Bool => _data_to_bool(),
             _AflHelpers::_Str => _data_to_str(),
+            _AflHelpers::_ByteReader => _byte_reader(),
             _AflHelpers::_Slice(..) => _data_to_slice(),
-            _AflHelpers::_Tuple(..) => "",
+            _AflHelpers::_Tuple(..)
+            | _AflHelpers::_Array(..)
+            | _AflHelpers::_Enum(..)
+            | _AflHelpers::_Collection(..) => "",
         }
     }
 
@@ -179,10 +252,10 @@ pub(crate) fn _type_name(&self) -> String {
             _AflHelpers::_I16 => "i16".to_string(),
             _AflHelpers::_U32 => "u32".to_string(),
             _AflHelpers::_I32 => "i32".to_string(),
-            _AflHelpers::_F32 => "f32".to_string(),
+            _AflHelpers::_F32 | _AflHelpers::_CornerF32 => "f32".to_string(),
             _AflHelpers::_U64 => "u64".to_string(),
             _AflHelpers::_I64 => "i64".to_string(),
-            _AflHelpers::_F64 => "f64".to_string(),
+            _AflHelpers::_F64 | _AflHelpers::_CornerF64 => "f64".to_string(),
             _AflHelpers::_U128 => "u128".to_string(),
             _AflHelpers::_I128 => "i128".to_string(),
             _AflHelpers::_Usize => "usize".to_string(),
@@ -190,7 +263,13 @@ pub(crate) fn _type_name(&self) -> String {
             _AflHelpers::_Bool => "bool".to_string(),
             _AflHelpers::_Char => "char".to_string(),
             _AflHelpers::_Str => "str".to_string(),
+            _AflHelpers::_ByteReader => "byte reader".to_string(),
             _AflHelpers::_Slice(..) => "slice".to_string(),
+            _AflHelpers::_Enum(..) => "enum".to_string(),
+            _AflHelpers::_Collection(..) => "collection".to_string(),
+            _AflHelpers::_Array(inner_afl_helper, length) => {
+                format!("[{}; {}]", inner_afl_helper._type_name(), length)
+            }
             _AflHelpers::_Tuple(inner_afl_helpers) => {
                 let mut type_name = "(".to_string();
                 let inner_afl_helpers_length = inner_afl_helpers.len();
@@ -220,7 +299,14 @@ pub(crate) fn _to_function_name(&self) -> String {
                     inner_type_name = inner_type_name
                 )
             }
-            _AflHelpers::_Tuple(..) => String::new(),
+            _AflHelpers::_Tuple(..)
+            | _AflHelpers::_Array(..)
+            | _AflHelpers::_Enum(..)
+            | _AflHelpers::_Collection(..) => String::new(),
+            _AflHelpers::_ByteReader => "_ByteReader::new".to_string(),
+            //类型名和普通的浮点数一样
+            _AflHelpers::_CornerF32 => "_to_corner_f32".to_string(),
+            _AflHelpers::_CornerF64 => "_to_corner_f64".to_string(),
             _ => {
                 format!("_to_{type_name}", type_name = self._type_name())
             }
@@ -245,6 +331,7 @@ pub(crate) fn _print_all() {
         println!("{}", _data_to_bool());
         println!("{}", _data_to_str());
         println!("{}", _data_to_slice());
+        println!("{}", _byte_reader());
         println!("{}", _data_to_f32());
         println!("{}", _data_to_f64());
     }
@@ -278,11 +365,6 @@ pub(crate) fn _is_tuple(&self) -> bool {
     pub(crate) fn _generate_param_initial_statement(
         &self,
         param_index: usize,
-        fixed_start_index: usize,
-        dynamic_start_index: usize,
-        dynamic_param_index: usize,
-        total_dynamic_param_numbers: usize,
-        dynamic_param_length: &String,
         origin_fuzzable_type: &FuzzableType,
     ) -> String {
         match self {
@@ -290,27 +372,93 @@ pub(crate) fn _generate_param_initial_statement(
                 format!("No helper")
             }
             _ => {
-                let rhs = self._generate_param_initial_rhs(
-                    fixed_start_index,
-                    dynamic_start_index,
-                    dynamic_param_index,
-                    total_dynamic_param_numbers,
-                    dynamic_param_length,
-                    origin_fuzzable_type,
-                );
+                let rhs = self._generate_reader_rhs(origin_fuzzable_type);
                 format!("let _param{param_index} = {rhs};", param_index = param_index, rhs = rhs)
             }
         }
     }
 
-    //生成参数初始化rhs
+    //生成从_reader依次读取参数的rhs，见_byte_reader。
+    //定长的参数一次取出需要的字节，再按偏移解码；长度可变的参数读带长度前缀的一段
+    pub(crate) fn _generate_reader_rhs(&self, origin_fuzzable_type: &FuzzableType) -> String {
+        if origin_fuzzable_type._is_fixed_length() {
+            let length = origin_fuzzable_type._min_length();
+            //空元组不占输入
+            if length == 0 {
+                return self._generate_param_initial_rhs(0, origin_fuzzable_type);
+            }
+            //取出来的数组遮住data，里面的偏移都从0开始
+            return format!(
+                "{{ let data = &_reader._fixed::<{length}>(); {rhs} }}",
+                length = length,
+                rhs = self._generate_param_initial_rhs(0, origin_fuzzable_type)
+            );
+        }
+        match self {
+            //元素长度可变的切片：先读元素个数，每个元素再按自己的长度前缀解码，收集到Vec里再借用
+            _AflHelpers::_Slice(inner_afl_helper)
+                if matches!(**inner_afl_helper, _AflHelpers::_Str | _AflHelpers::_Slice(..)) =>
+            {
+                if let FuzzableType::RefSlice(inner_fuzzable) = origin_fuzzable_type {
+                    format!(
+                        "&{{ let mut _items = Vec::new(); for _ in 0.._reader._count() {{ _items.push({inner_rhs}); }} _items }}[..]",
+                        inner_rhs = inner_afl_helper._generate_reader_rhs(inner_fuzzable)
+                    )
+                } else {
+                    "Type not match in afl_util".to_string()
+                }
+            }
+            _AflHelpers::_Str | _AflHelpers::_Slice(..) => {
+                format!(
+                    "{{ let data = _reader._dynamic(); {afl_function_name}(data, 0, data.len()) }}",
+                    afl_function_name = self._to_function_name(),
+                )
+            }
+            _AflHelpers::_Collection(kind, item_afl_helpers) => {
+                if let FuzzableType::Collection(_, item_types) = origin_fuzzable_type {
+                    //循环变量遮住data，每一项的键和值都从这一项的开头按定长变量解码
+                    let mut items = Vec::new();
+                    let mut item_start_index = 0;
+                    for (item_afl_helper, item_type) in item_afl_helpers.iter().zip(item_types) {
+                        items.push(
+                            item_afl_helper
+                                ._generate_param_initial_rhs(item_start_index, item_type),
+                        );
+                        item_start_index = item_start_index + item_type._min_length();
+                    }
+                    format!(
+                        "{{ let mut _collection = {path_name}::new(); for data in _reader._dynamic().chunks_exact({item_length}) {{ _collection.insert({items}); }} _collection }}",
+                        path_name = kind.path_name(),
+                        item_length = item_start_index,
+                        items = items.join(", "),
+                    )
+                } else {
+                    "Type not match in afl_util".to_string()
+                }
+            }
+            //元组的元素按顺序读取，元素的求值顺序就是读取的顺序
+            _AflHelpers::_Tuple(inner_afl_helpers) => {
+                if let FuzzableType::Tuple(inner_fuzzables) = origin_fuzzable_type {
+                    let inner_rhss = inner_afl_helpers
+                        .iter()
+                        .zip(inner_fuzzables)
+                        .map(|(inner_afl_helper, inner_fuzzable)| {
+                            inner_afl_helper._generate_reader_rhs(inner_fuzzable)
+                        })
+                        .collect::<Vec<_>>();
+                    format!("({})", inner_rhss.join(", "))
+                } else {
+                    "Type not match in afl_util".to_string()
+                }
+            }
+            _ => "Type not match in afl_util".to_string(),
+        }
+    }
+
+    //生成定长参数的rhs，从data的fixed_start_index开始按偏移解码
     pub(crate) fn _generate_param_initial_rhs(
         &self,
         fixed_start_index: usize,
-        dynamic_start_index: usize,
-        dynamic_param_index: usize,
-        total_dynamic_param_numbers: usize,
-        dynamic_param_length: &String,
         origin_fuzzable_type: &FuzzableType,
     ) -> String {
         match self {
@@ -329,40 +477,21 @@ pub(crate) fn _generate_param_initial_rhs(
             | _AflHelpers::_Usize
             | _AflHelpers::_Isize
             | _AflHelpers::_F32
-            | _AflHelpers::_F64 => {
+            | _AflHelpers::_F64
+            | _AflHelpers::_CornerF32
+            | _AflHelpers::_CornerF64 => {
                 format!(
                     "{afl_function_name}(data, {fixed_start_index})",
                     afl_function_name = self._to_function_name(),
                     fixed_start_index = fixed_start_index
                 )
             }
-            _AflHelpers::_Str | _AflHelpers::_Slice(..) => {
-                let latter_index = if dynamic_param_index == total_dynamic_param_numbers - 1 {
-                    format!("data.len()")
-                } else {
-                    format!(
-                        "{dynamic_start_index} + {dynamic_param_index} * {dynamic_param_length}",
-                        dynamic_start_index = dynamic_start_index,
-                        dynamic_param_index = dynamic_param_index + 1,
-                        dynamic_param_length = dynamic_param_length
-                    )
-                };
-                format!(
-                    "{afl_function_name}(data, {dynamic_start_index} + {dynamic_param_index} * {dynamic_param_length}, {latter_index})",
-                    afl_function_name = self._to_function_name(),
-                    dynamic_start_index = dynamic_start_index,
-                    dynamic_param_index = dynamic_param_index,
-                    dynamic_param_length = dynamic_param_length,
-                    latter_index = latter_index
-                )
-            }
             _AflHelpers::_Tuple(inner_afl_helpers) => {
                 if let FuzzableType::Tuple(inner_fuzzables) = origin_fuzzable_type {
                     let mut res = "(".to_string();
                     let inner_afl_helpers_number = inner_afl_helpers.len();
 
                     let mut inner_fixed_start_index = fixed_start_index;
-                    let mut inner_dynamic_param_index = dynamic_param_index;
                     for i in 0..inner_afl_helpers_number {
                         if i != 0 {
                             res.push_str(", ");
@@ -371,17 +500,11 @@ pub(crate) fn _generate_param_initial_rhs(
                         let inner_origin_fuzzable_type = &inner_fuzzables[i];
                         let inner_rhs = inner_afl_helper._generate_param_initial_rhs(
                             inner_fixed_start_index,
-                            dynamic_start_index,
-                            inner_dynamic_param_index,
-                            total_dynamic_param_numbers,
-                            dynamic_param_length,
                             inner_origin_fuzzable_type,
                         );
                         res.push_str(inner_rhs.as_str());
                         inner_fixed_start_index = inner_fixed_start_index
                             + inner_origin_fuzzable_type._fixed_part_length();
-                        inner_dynamic_param_index = inner_dynamic_param_index
-                            + inner_origin_fuzzable_type._dynamic_length_param_number();
                     }
                     res.push_str(")");
                     res
@@ -389,7 +512,62 @@ pub(crate) fn _generate_param_initial_rhs(
                     "Type not match in afl_util".to_string()
                 }
             }
-            _AflHelpers::_NoHelper => {
+            _AflHelpers::_Array(inner_afl_helper, length) => {
+                if let FuzzableType::Array(inner_fuzzable, _) = origin_fuzzable_type {
+                    //元素都是定长的基本类型，第i个元素从fixed_start_index + i * 元素长度开始
+                    format!(
+                        "std::array::from_fn::<{inner_type_name}, {length}, _>(|_i| {afl_function_name}(data, {fixed_start_index} + _i * {element_length}))",
+                        inner_type_name = inner_afl_helper._type_name(),
+                        length = length,
+                        afl_function_name = inner_afl_helper._to_function_name(),
+                        fixed_start_index = fixed_start_index,
+                        element_length = inner_fuzzable._min_length(),
+                    )
+                } else {
+                    "Type not match in afl_util".to_string()
+                }
+            }
+            _AflHelpers::_Enum(variant_afl_helpers) => {
+                if let FuzzableType::Enum(type_name, variants) = origin_fuzzable_type {
+                    //第一个字节选择变体，每个变体的字段都从下一个字节开始
+                    let variant_number = variants.len();
+                    let mut res = format!(
+                        "match _to_u8(data, {}) % {} {{",
+                        fixed_start_index, variant_number
+                    );
+                    for i in 0..variant_number {
+                        let variant = &variants[i];
+                        let inner_afl_helpers = &variant_afl_helpers[i];
+                        let mut fields = Vec::new();
+                        let mut inner_fixed_start_index = fixed_start_index + 1;
+                        for (j, (field_name, field)) in variant.fields.iter().enumerate() {
+                            let inner_rhs = inner_afl_helpers[j]
+                                ._generate_param_initial_rhs(inner_fixed_start_index, field);
+                            fields.push(format!("{}: {}", field_name, inner_rhs));
+                            inner_fixed_start_index =
+                                inner_fixed_start_index + field._fixed_part_length();
+                        }
+                        let pattern =
+                            if i == variant_number - 1 { "_".to_string() } else { i.to_string() };
+                        let value = if fields.is_empty() {
+                            format!("{}::{} {{}}", type_name, variant.name)
+                        } else {
+                            format!("{}::{} {{ {} }}", type_name, variant.name, fields.join(", "))
+                        };
+                        res.push_str(format!(" {} => {},", pattern, value).as_str());
+                    }
+                    res.push_str(" }");
+                    res
+                } else {
+                    "Type not match in afl_util".to_string()
+                }
+            }
+            //长度可变的参数从_reader读取，见_generate_reader_rhs
+            _AflHelpers::_NoHelper
+            | _AflHelpers::_ByteReader
+            | _AflHelpers::_Str
+            | _AflHelpers::_Slice(..)
+            | _AflHelpers::_Collection(..) => {
                 format!("No helper")
             }
         }
@@ -401,6 +579,10 @@ pub(crate) fn _get_all_dependent_afl_helpers_of_sequence(
     fuzzable_params: &Vec<FuzzableType>,
 ) -> FxHashSet<_AflHelpers> {
     let mut res = FxHashSet::default();
+    //所有参数都从_ByteReader读取
+    if !fuzzable_params.is_empty() {
+        res.insert(_AflHelpers::_ByteReader);
+    }
     for fuzzable_param in fuzzable_params {
         let afi_helper = _AflHelpers::_new_from_fuzzable(fuzzable_param);
         let dependencies = afi_helper._get_all_dependent_afl_helpers();
@@ -423,10 +605,11 @@ pub(crate) fn _get_afl_helpers_functions_of_sequence(
 
     let mut contains_slice_flag = false;
     for afl_helper in afl_helpers {
-        if !contains_slice_flag && afl_helper._is_slice() {
+        if afl_helper._is_slice() {
+            if contains_slice_flag {
+                continue;
+            }
             contains_slice_flag = true;
-            afl_helper_functions.push(afl_helper._to_full_function().to_string());
-            continue;
         }
         afl_helper_functions.push(afl_helper._to_full_function().to_string())
     }
@@ -534,6 +717,39 @@ pub(crate) fn _data_to_f64() -> &'static str {
 }\n"
 }
 
+//第一个字节小于8的时候（大约3%的输入）换成特殊值，否则从后面的字节解码
+pub(crate) fn _data_to_corner_f32() -> &'static str {
+    "fn _to_corner_f32(data:&[u8], index: usize) -> f32 {
+    match data[index] {
+        0 => f32::NAN,
+        1 => f32::INFINITY,
+        2 => f32::NEG_INFINITY,
+        3 => -0.0,
+        4 => f32::MIN_POSITIVE,
+        5 => f32::from_bits(1),
+        6 => f32::MAX,
+        7 => f32::MIN,
+        _ => _to_f32(data, index + 1),
+    }
+}\n"
+}
+
+pub(crate) fn _data_to_corner_f64() -> &'static str {
+    "fn _to_corner_f64(data:&[u8], index: usize) -> f64 {
+    match data[index] {
+        0 => f64::NAN,
+        1 => f64::INFINITY,
+        2 => f64::NEG_INFINITY,
+        3 => -0.0,
+        4 => f64::MIN_POSITIVE,
+        5 => f64::from_bits(1),
+        6 => f64::MAX,
+        7 => f64::MIN,
+        _ => _to_f64(data, index + 1),
+    }
+}\n"
+}
+
 pub(crate) fn _data_to_u128() -> &'static str {
     "fn _to_u128(data:&[u8], index:usize)->u128 {
     let data0 = _to_u64(data, index) as u128;
@@ -564,8 +780,12 @@ pub(crate) fn _data_to_isize() -> &'static str {
 
 pub(crate) fn _data_to_char() -> &'static str {
     "fn _to_char(data:&[u8], index: usize)->char {
-    let char_value = _to_u32(data,index);
-    match char::from_u32(char_value) {
+    let char_data = &data[index..index + 4];
+    let valid_length = match std::str::from_utf8(char_data) {
+        Ok(_)=>4,
+        Err(e)=>e.valid_up_to(),
+    };
+    match std::str::from_utf8(&char_data[..valid_length]).ok().and_then(|s| s.chars().next()) {
         Some(c)=>c,
         None=>{
             use std::process;
@@ -600,6 +820,56 @@ pub(crate) fn _data_to_str() -> &'static str {
 }\n"
 }
 
+//参数按顺序从输入里读取：定长的参数取出固定的字节数，输入不够的部分是0；
+//长度可变的参数前面是LEB128编码的长度（最多4个字节），超过剩下的输入时截断，输入用完之后得到的都是空的
+pub(crate) fn _byte_reader() -> &'static str {
+    "struct _ByteReader<'a> {
+    data: &'a [u8],
+    index: usize,
+}
+
+impl<'a> _ByteReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        _ByteReader { data, index: 0 }
+    }
+
+    fn _fixed<const N: usize>(&mut self) -> [u8; N] {
+        let mut bytes = [0u8; N];
+        let end = (self.index + N).min(self.data.len());
+        bytes[..end - self.index].copy_from_slice(&self.data[self.index..end]);
+        self.index = end;
+        bytes
+    }
+
+    fn _length(&mut self) -> usize {
+        let mut length = 0usize;
+        let mut shift = 0;
+        while self.index < self.data.len() && shift < 28 {
+            let byte = self.data[self.index];
+            self.index += 1;
+            length |= ((byte & 0x7f) as usize) << shift;
+            if byte & 0x80 == 0 {
+                break;
+            }
+            shift += 7;
+        }
+        length
+    }
+
+    fn _dynamic(&mut self) -> &'a [u8] {
+        let length = self._length();
+        let start = self.index;
+        let end = start + length.min(self.data.len() - start);
+        self.index = end;
+        &self.data[start..end]
+    }
+
+    fn _count(&mut self) -> usize {
+        self._length().min(self.data.len() - self.index)
+    }
+}\n"
+}
+
 //会有big endian和 little endian的问题，不过只是去fuzz的话，应该没啥影响
 pub(crate) fn _data_to_slice() -> &'static str {
     "fn _to_slice<T>(data:&[u8], start_index: usize, end_index: usize)->&[T] {
@@ -0,0 +1,616 @@
+//! fuzz_targets_gen对外的接口
+//! 可以通过Analyzer一步一步地驱动生成过程：
+//!     Analyzer::from_rustdoc_json -> run_traversal -> select -> emit -> write
+//! rustdoc pass之外的工具和测试从`rustdoc --output-format json`的输出开始；
+//! rustdoc pass里用Analyzer::from_rustdoc_output，它需要rustdoc内部的Context，所以是pub(crate)的。
+//! 生成时的参数用GenerationConfig::default()加上with_*设置
+//! 对外只暴露字符串和数量，ApiGraph、ApiSequence等内部结构仍然是pub(crate)的
+
+use crate::clean;
+use crate::formats::FormatRenderer;
+use crate::fuzz_targets_gen::api_graph::ApiGraph;
+use crate::fuzz_targets_gen::api_sequence::ApiSequence;
+use crate::fuzz_targets_gen::diagnostics;
+use crate::fuzz_targets_gen::error::FuzzGenError;
+use crate::fuzz_targets_gen::file_util::{self, FileHelper};
+use crate::fuzz_targets_gen::json_frontend::JsonCrate;
+use crate::fuzz_targets_gen::sequence_builder::SequenceBuilder;
+use crate::fuzz_targets_gen::{
+    Context, afl_dict, api_util, const_producer, conversion, fries_config, global_state,
+    graph_cache, graph_export, impl_util, invariant, maybe_uninit, mir_complexity, mod_visibility,
+    oracle, sequence_serde, size_cap, timing, unsafe_usage,
+};
+use rustc_hir::def_id::LOCAL_CRATE;
+use rustc_middle::ty::TyCtxt;
+
+pub use crate::fuzz_targets_gen::api_graph::{GraphTraverseAlgorithm, SequenceSelection};
+pub use crate::fuzz_targets_gen::numeric_cast::NumericCast;
+pub use crate::fuzz_targets_gen::path_param::PathMode;
+
+/// 生成序列时的参数
+#[derive(Debug, Clone)]
+pub struct GenerationConfig {
+    /// 生成序列的算法
+    pub(crate) strategy: GraphTraverseAlgorithm,
+    /// 最多选出多少个序列
+    pub(crate) max_num: usize,
+    /// 序列的最大长度
+    pub(crate) max_len: usize,
+    /// 是否支持泛型函数
+    pub(crate) support_generic: bool,
+    /// 随机游走的步数，None表示用算法的默认值
+    pub(crate) walk_steps: Option<usize>,
+    /// BFS的最大深度，None表示用算法的默认值
+    pub(crate) bfs_max_len: Option<usize>,
+    /// 遍历时覆盖这么多函数就停止，None表示要覆盖所有函数
+    pub(crate) cover_nodes: Option<usize>,
+    /// 替换泛型参数的候选类型，None表示用generic_candidate.rs里的默认值
+    pub(crate) generic_candidates: Option<Vec<String>>,
+    /// 遗传算法的种群大小，None表示用算法的默认值
+    pub(crate) population_size: Option<usize>,
+    /// 遗传算法演化的代数，None表示用算法的默认值
+    pub(crate) generations: Option<usize>,
+    /// 束搜索每层保留的序列数，None表示用算法的默认值
+    pub(crate) beam_width: Option<usize>,
+    /// 选序列时语料里API出现频率的指数，None表示用默认值1
+    pub(crate) frequency_exponent: Option<f64>,
+    /// 生成和选序列时偏向内部用到unsafe的API，见unsafe_usage.rs
+    pub(crate) prioritize_unsafe: bool,
+    /// 不过滤简单的getter/setter，见ApiGraph::filter_trivial_accessors
+    pub(crate) keep_trivial_accessors: bool,
+    /// 只保留全路径匹配其中一个glob模式的API，空表示不限制，见api_pattern.rs
+    pub(crate) include: Vec<String>,
+    /// 去掉全路径匹配其中一个glob模式的API
+    pub(crate) exclude: Vec<String>,
+    /// 收集API时保留`#[deprecated]`的函数
+    pub(crate) include_deprecated: bool,
+    /// 收集API时保留`#[doc(hidden)]`的函数
+    pub(crate) include_doc_hidden: bool,
+    /// 整数返回值传给宽度不同的整数参数时怎么转换，见numeric_cast.rs
+    pub(crate) numeric_cast: NumericCast,
+    /// 怎么从fuzz输入构造Path和PathBuf参数，见path_param.rs
+    pub(crate) path_mode: PathMode,
+    /// f32和f64参数的一部分输入换成NaN、±Inf、次正规数这些特殊值
+    pub(crate) float_corners: bool,
+    /// 给`&mut MaybeUninit<T>`和`*mut T`出参合成未初始化的槽位，写过之后assume_init，见maybe_uninit.rs
+    pub(crate) allow_unsafe_synthesis: bool,
+    /// 在待测crate内部生成fuzz target，按crate根的子模块能否访问过滤API，见internal_mode.rs
+    pub(crate) internal: bool,
+    /// 选序列的方法，None表示按生成序列的算法决定
+    pub(crate) selection: Option<SequenceSelection>,
+    /// 从缓存读取找好依赖的图，见graph_cache.rs
+    pub(crate) load_graph: bool,
+    /// 找好依赖之后保存到缓存
+    pub(crate) save_graph: bool,
+    /// 语料的位置（文件或者目录），None表示用环境变量FRIES_CORPUS_PATH或者实验目录
+    pub(crate) corpus_path: Option<String>,
+}
+
+impl Default for GenerationConfig {
+    fn default() -> Self {
+        GenerationConfig {
+            strategy: GraphTraverseAlgorithm::_UseRealWorld,
+            max_num: 100,
+            max_len: 15,
+            support_generic: false,
+            walk_steps: None,
+            bfs_max_len: None,
+            cover_nodes: None,
+            generic_candidates: None,
+            population_size: None,
+            generations: None,
+            beam_width: None,
+            frequency_exponent: None,
+            prioritize_unsafe: false,
+            keep_trivial_accessors: false,
+            include: Vec::new(),
+            exclude: Vec::new(),
+            include_deprecated: false,
+            include_doc_hidden: false,
+            numeric_cast: NumericCast::default(),
+            path_mode: PathMode::default(),
+            float_corners: false,
+            allow_unsafe_synthesis: false,
+            internal: false,
+            selection: None,
+            load_graph: false,
+            save_graph: false,
+            corpus_path: None,
+        }
+    }
+}
+
+/// 外部的工具用with_*设置参数，比如`GenerationConfig::default().with_max_len(5)`
+impl GenerationConfig {
+    /// 生成序列的算法
+    pub fn with_strategy(mut self, strategy: GraphTraverseAlgorithm) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    /// 最多选出多少个序列
+    pub fn with_max_num(mut self, max_num: usize) -> Self {
+        self.max_num = max_num;
+        self
+    }
+
+    /// 序列的最大长度
+    pub fn with_max_len(mut self, max_len: usize) -> Self {
+        self.max_len = max_len;
+        self
+    }
+
+    /// 是否支持泛型函数
+    pub fn with_support_generic(mut self, support_generic: bool) -> Self {
+        self.support_generic = support_generic;
+        self
+    }
+
+    /// 随机游走的步数，None表示用算法的默认值
+    pub fn with_walk_steps(mut self, walk_steps: Option<usize>) -> Self {
+        self.walk_steps = walk_steps;
+        self
+    }
+
+    /// BFS的最大深度，None表示用算法的默认值
+    pub fn with_bfs_max_len(mut self, bfs_max_len: Option<usize>) -> Self {
+        self.bfs_max_len = bfs_max_len;
+        self
+    }
+
+    /// 遍历时覆盖这么多函数就停止，None表示要覆盖所有函数
+    pub fn with_cover_nodes(mut self, cover_nodes: Option<usize>) -> Self {
+        self.cover_nodes = cover_nodes;
+        self
+    }
+
+    /// 替换泛型参数的候选类型，None表示用generic_candidate.rs里的默认值
+    pub fn with_generic_candidates(mut self, generic_candidates: Option<Vec<String>>) -> Self {
+        self.generic_candidates = generic_candidates;
+        self
+    }
+
+    /// 遗传算法的种群大小，None表示用算法的默认值
+    pub fn with_population_size(mut self, population_size: Option<usize>) -> Self {
+        self.population_size = population_size;
+        self
+    }
+
+    /// 遗传算法演化的代数，None表示用算法的默认值
+    pub fn with_generations(mut self, generations: Option<usize>) -> Self {
+        self.generations = generations;
+        self
+    }
+
+    /// 束搜索每层保留的序列数，None表示用算法的默认值
+    pub fn with_beam_width(mut self, beam_width: Option<usize>) -> Self {
+        self.beam_width = beam_width;
+        self
+    }
+
+    /// 选序列时语料里API出现频率的指数，None表示用默认值1
+    pub fn with_frequency_exponent(mut self, frequency_exponent: Option<f64>) -> Self {
+        self.frequency_exponent = frequency_exponent;
+        self
+    }
+
+    /// 生成和选序列时偏向内部用到unsafe的API，见unsafe_usage.rs
+    pub fn with_prioritize_unsafe(mut self, prioritize_unsafe: bool) -> Self {
+        self.prioritize_unsafe = prioritize_unsafe;
+        self
+    }
+
+    /// 不过滤简单的getter/setter，见ApiGraph::filter_trivial_accessors
+    pub fn with_keep_trivial_accessors(mut self, keep_trivial_accessors: bool) -> Self {
+        self.keep_trivial_accessors = keep_trivial_accessors;
+        self
+    }
+
+    /// 只保留全路径匹配其中一个glob模式的API，空表示不限制，见api_pattern.rs
+    pub fn with_include(mut self, include: Vec<String>) -> Self {
+        self.include = include;
+        self
+    }
+
+    /// 去掉全路径匹配其中一个glob模式的API
+    pub fn with_exclude(mut self, exclude: Vec<String>) -> Self {
+        self.exclude = exclude;
+        self
+    }
+
+    /// 收集API时保留`#[deprecated]`的函数
+    pub fn with_include_deprecated(mut self, include_deprecated: bool) -> Self {
+        self.include_deprecated = include_deprecated;
+        self
+    }
+
+    /// 收集API时保留`#[doc(hidden)]`的函数
+    pub fn with_include_doc_hidden(mut self, include_doc_hidden: bool) -> Self {
+        self.include_doc_hidden = include_doc_hidden;
+        self
+    }
+
+    /// 整数返回值传给宽度不同的整数参数时怎么转换，见numeric_cast.rs
+    pub fn with_numeric_cast(mut self, numeric_cast: NumericCast) -> Self {
+        self.numeric_cast = numeric_cast;
+        self
+    }
+
+    /// 怎么从fuzz输入构造Path和PathBuf参数，见path_param.rs
+    pub fn with_path_mode(mut self, path_mode: PathMode) -> Self {
+        self.path_mode = path_mode;
+        self
+    }
+
+    /// f32和f64参数的一部分输入换成NaN、±Inf、次正规数这些特殊值
+    pub fn with_float_corners(mut self, float_corners: bool) -> Self {
+        self.float_corners = float_corners;
+        self
+    }
+
+    /// 给`&mut MaybeUninit<T>`和`*mut T`出参合成未初始化的槽位，写过之后assume_init，见maybe_uninit.rs
+    pub fn with_allow_unsafe_synthesis(mut self, allow_unsafe_synthesis: bool) -> Self {
+        self.allow_unsafe_synthesis = allow_unsafe_synthesis;
+        self
+    }
+
+    /// 在待测crate内部生成fuzz target，按crate根的子模块能否访问过滤API，见internal_mode.rs
+    pub fn with_internal(mut self, internal: bool) -> Self {
+        self.internal = internal;
+        self
+    }
+
+    /// 选序列的方法，None表示按生成序列的算法决定
+    pub fn with_selection(mut self, selection: Option<SequenceSelection>) -> Self {
+        self.selection = selection;
+        self
+    }
+
+    /// 从缓存读取找好依赖的图，见graph_cache.rs
+    pub fn with_load_graph(mut self, load_graph: bool) -> Self {
+        self.load_graph = load_graph;
+        self
+    }
+
+    /// 找好依赖之后保存到缓存
+    pub fn with_save_graph(mut self, save_graph: bool) -> Self {
+        self.save_graph = save_graph;
+        self
+    }
+
+    /// 语料的位置（文件或者目录），None表示用环境变量FRIES_CORPUS_PATH或者实验目录
+    pub fn with_corpus_path(mut self, corpus_path: Option<String>) -> Self {
+        self.corpus_path = corpus_path;
+        self
+    }
+}
+
+/// 解析好的API依赖图，以及生成时的参数
+pub struct Analyzer<'a> {
+    graph: ApiGraph<'a>,
+    config: GenerationConfig,
+}
+
+/// select选出来的序列
+pub struct SelectedSequences {
+    sequences: Vec<ApiSequence>,
+    descriptions: Vec<String>,
+}
+
+impl SelectedSequences {
+    pub fn len(&self) -> usize {
+        self.sequences.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sequences.is_empty()
+    }
+
+    /// 每个序列的文字描述，和print_sequence的输出一致
+    pub fn descriptions(&self) -> &[String] {
+        &self.descriptions
+    }
+}
+
+/// emit生成的测试文件内容
+pub struct GeneratedTargets {
+    helper: FileHelper,
+}
+
+impl GeneratedTargets {
+    /// 测试文件写入的目录
+    pub fn test_dir(&self) -> &str {
+        &self.helper.test_dir
+    }
+
+    /// afl的fuzz target
+    pub fn test_files(&self) -> &[String] {
+        &self.helper.test_files
+    }
+
+    /// 每个fuzz target的名字，包含按模块划分的子目录，和test_files、reproduce_files一一对应
+    pub fn target_names(&self) -> &[String] {
+        &self.helper.target_names
+    }
+
+    /// 复现crash用的文件
+    pub fn reproduce_files(&self) -> &[String] {
+        &self.helper.reproduce_files
+    }
+
+    /// 解释器模式的fuzz target
+    pub fn interpreter_files(&self) -> &[String] {
+        &self.helper.interpreter_files
+    }
+
+    /// 写入test_dir，会先清空原来的内容
+    pub fn write(&self) {
+        self.helper.write_files();
+    }
+}
+
+impl<'a> Analyzer<'a> {
+    /// 从rustdoc的解析结果构建API依赖图，包括解析函数、过滤、找依赖
+    pub(crate) fn from_rustdoc_output(
+        cx: &'a Context<'_>,
+        krate: &clean::Crate,
+        tcx: TyCtxt<'_>,
+        config: GenerationConfig,
+    ) -> Self {
+        timing::reset();
+        diagnostics::reset();
+        let mut graph = timing::time_phase("graph build", || {
+            let mut graph = ApiGraph::new(&krate.name(tcx).to_string(), cx.cache());
+            graph.include_deprecated = config.include_deprecated;
+            graph.include_doc_hidden = config.include_doc_hidden;
+            graph.numeric_cast = config.numeric_cast;
+            graph.emit_options.path_mode = config.path_mode;
+            graph.emit_options.float_corners = config.float_corners;
+            if let Some(names) = &config.generic_candidates {
+                graph.set_generic_candidates(names);
+            }
+            graph.resolve_generic_bounds(tcx);
+            let mut full_name_map = impl_util::FullNameMap::new();
+            impl_util::_record_enum_variants(&krate.module, &mut full_name_map);
+            mod_visibility::record_public_paths(tcx, &mut full_name_map);
+            impl_util::extract_impls_from_cache(cx.cache(), tcx, &mut full_name_map, &mut graph);
+            graph.dictionary = afl_dict::collect_dictionary(tcx);
+            let _ret = cx.clone().add_bare_functions_into_api_graph(tcx, krate, &mut graph);
+            impl_util::instantiate_generic_impls(cx.cache(), tcx, &full_name_map, &mut graph);
+            unsafe_usage::mark_unsafe_functions(tcx, &mut graph);
+            mir_complexity::compute_complexities(tcx, &mut graph);
+            graph.filter_functions(&config);
+            api_util::_add_from_str_producers(&mut graph);
+            api_util::_add_default_producers(&mut graph);
+            const_producer::add_const_producers(tcx, &mut graph);
+            if config.allow_unsafe_synthesis {
+                maybe_uninit::add_uninit_slots(&mut graph);
+            }
+            graph
+        });
+        let crate_hash = tcx.crate_hash(LOCAL_CRATE).to_string();
+        if !(config.load_graph
+            && graph_cache::load_graph(&mut graph, &crate_hash, config.support_generic))
+        {
+            timing::time_phase("dependency analysis", || {
+                graph.find_all_dependencies(config.support_generic)
+            });
+            if config.save_graph {
+                graph_cache::save_graph(&graph, &crate_hash, config.support_generic);
+            }
+        }
+        Analyzer { graph, config }
+    }
+
+    /// 从json文档构建API依赖图，过滤、找依赖和图缓存都和from_rustdoc_output一样，
+    /// 图缓存用json文档的哈希代替crate hash。json里没有MIR，不会加上常量生产者，也没有复杂度和unsafe信息
+    pub fn from_rustdoc_json(json_crate: &'a JsonCrate, config: GenerationConfig) -> Self {
+        timing::reset();
+        diagnostics::reset();
+        let mut graph = timing::time_phase("graph build", || {
+            let mut graph = json_crate.build_api_graph(&config);
+            graph.numeric_cast = config.numeric_cast;
+            graph.emit_options.path_mode = config.path_mode;
+            graph.emit_options.float_corners = config.float_corners;
+            graph.filter_functions(&config);
+            api_util::_add_from_str_producers(&mut graph);
+            api_util::_add_default_producers(&mut graph);
+            if config.allow_unsafe_synthesis {
+                maybe_uninit::add_uninit_slots(&mut graph);
+            }
+            graph
+        });
+        let crate_hash = json_crate.crate_hash();
+        if !(config.load_graph
+            && graph_cache::load_graph(&mut graph, crate_hash, config.support_generic))
+        {
+            timing::time_phase("dependency analysis", || {
+                graph.find_all_dependencies(config.support_generic)
+            });
+            if config.save_graph {
+                graph_cache::save_graph(&graph, crate_hash, config.support_generic);
+            }
+        }
+        Analyzer { graph, config }
+    }
+
+    pub(crate) fn api_graph_mut(&mut self) -> &mut ApiGraph<'a> {
+        &mut self.graph
+    }
+
+    pub fn crate_name(&self) -> &str {
+        &self.graph._crate_name
+    }
+
+    pub fn config(&self) -> &GenerationConfig {
+        &self.config
+    }
+
+    /// 换成新的参数，之后的run_traversal和select按新的参数进行
+    pub fn set_config(&mut self, config: GenerationConfig) {
+        self.config = config;
+    }
+
+    /// 过滤之后剩下的所有函数的全路径
+    pub fn function_names(&self) -> Vec<String> {
+        self.graph.api_functions.iter().map(|api_function| api_function.full_name.clone()).collect()
+    }
+
+    fn function_names_of(&self, indexes: Vec<usize>) -> Vec<String> {
+        indexes.into_iter().map(|index| self.graph.api_functions[index].full_name.clone()).collect()
+    }
+
+    /// 返回值是type_name的函数
+    pub fn producers_of(&self, type_name: &str) -> Vec<String> {
+        self.function_names_of(self.graph.producers_of(type_name))
+    }
+
+    /// 参数里有type_name的函数
+    pub fn consumers_of(&self, type_name: &str) -> Vec<String> {
+        self.function_names_of(self.graph.consumers_of(type_name))
+    }
+
+    /// 函数from到函数to之间的依赖路径，函数名不存在的话返回空
+    pub fn paths_between(&self, from: &str, to: &str, max_len: usize) -> Vec<Vec<String>> {
+        let index_of = |name: &str| {
+            self.graph.api_functions.iter().position(|api_function| api_function.full_name == name)
+        };
+        let (from, to) = match (index_of(from), index_of(to)) {
+            (Some(from), Some(to)) => (from, to),
+            _ => return Vec::new(),
+        };
+        self.graph
+            .paths_between(from, to, max_len)
+            .into_iter()
+            .map(|path| self.function_names_of(path))
+            .collect()
+    }
+
+    /// Err/None时跳过依赖它的语句，而不是直接退出
+    pub fn set_continue_on_error(&mut self, continue_on_error: bool) {
+        self.graph.emit_options.continue_on_error = continue_on_error;
+    }
+
+    /// 每个API调用的超时时间（毫秒），None表示不开启watchdog
+    pub fn set_call_timeout_ms(&mut self, call_timeout_ms: Option<u64>) {
+        self.graph.emit_options.call_timeout_ms = call_timeout_ms;
+    }
+
+    /// 是否额外生成解释器模式的fuzz target
+    pub fn set_interpreter_mode(&mut self, interpreter_mode: bool) {
+        self.graph.emit_options.interpreter_mode = interpreter_mode;
+    }
+
+    /// 从目录里读取invariants.txt、oracles.txt、size_caps.txt、conversions.txt、global_states.txt，
+    /// 不存在的文件会被忽略
+    /// conversions.txt里的转换会马上加入依赖边，fries.toml里的调优参数覆盖config
+    pub fn load_config_dir(&mut self, config_dir: &str) {
+        if let Some(tuning) = fries_config::read_tuning_file(
+            format!("{}/{}", config_dir, fries_config::FRIES_CONFIG_FILE).as_str(),
+        ) {
+            tuning.apply_to(&mut self.config);
+        }
+        let mut conversions = Vec::new();
+        conversion::load_conversions_from_file(
+            format!("{}/conversions.txt", config_dir).as_str(),
+            &mut conversions,
+        );
+        conversion::add_conversion_dependencies(&mut self.graph, &conversions);
+        global_state::load_global_states_from_file(
+            format!("{}/global_states.txt", config_dir).as_str(),
+            &mut self.graph.global_states,
+        );
+        let emit_options = &mut self.graph.emit_options;
+        invariant::load_invariants_from_file(
+            format!("{}/invariants.txt", config_dir).as_str(),
+            &mut emit_options.invariants,
+        );
+        oracle::load_oracles_from_file(
+            format!("{}/oracles.txt", config_dir).as_str(),
+            &mut emit_options.oracles,
+        );
+        size_cap::load_size_caps_from_file(
+            format!("{}/size_caps.txt", config_dir).as_str(),
+            &mut emit_options.size_caps,
+        );
+    }
+
+    /// 按照config里的算法生成序列，返回生成的序列数量
+    /// 算法需要的输入（比如语料文件）不可用时返回错误
+    pub fn run_traversal(&mut self) -> Result<usize, FuzzGenError> {
+        self.graph.api_sequences.clear();
+        let lib_name = self.graph._crate_name.replace("_", "-");
+        self.graph.generate_sequences(lib_name.as_str(), &self.config)?;
+        Ok(self.graph.api_sequences.len())
+    }
+
+    /// 从生成的序列中选出要写成测试文件的序列
+    pub fn select(&self) -> SelectedSequences {
+        let sequences = timing::time_phase("selection", || {
+            file_util::choose_sequences(
+                &self.graph,
+                self.config.strategy,
+                self.config.max_num,
+                self.config.max_len,
+            )
+        });
+        let descriptions =
+            sequences.iter().map(|sequence| sequence.print_sequence(&self.graph, true)).collect();
+        SelectedSequences { sequences, descriptions }
+    }
+
+    /// 手写序列的builder，用add_sequence检查之后加入选出来的序列
+    pub fn sequence_builder(&self) -> SequenceBuilder<'_, 'a> {
+        SequenceBuilder::new(&self.graph)
+    }
+
+    /// 把手写的序列加入选出来的序列，序列不合法的话返回原因
+    pub fn add_sequence(
+        &self,
+        selected: &mut SelectedSequences,
+        builder: &SequenceBuilder<'_, 'a>,
+    ) -> Result<(), String> {
+        let sequence = builder.build()?;
+        selected.descriptions.push(sequence.print_sequence(&self.graph, true));
+        selected.sequences.push(sequence);
+        Ok(())
+    }
+
+    /// 把选出来的序列导出成json
+    pub fn export_sequences(&self, selected: &SelectedSequences) -> String {
+        sequence_serde::sequences_to_json(&self.graph, &selected.sequences)
+    }
+
+    /// 把所有函数、依赖和选出来的序列导出成json，格式见graph_export.rs
+    pub fn export_graph(&self, selected: &SelectedSequences) -> String {
+        graph_export::graph_to_json(&self.graph, &selected.sequences)
+    }
+
+    /// 导入export_sequences导出（或者手动修改过）的序列
+    pub fn import_sequences(&self, json: &str) -> Result<SelectedSequences, String> {
+        let sequences = sequence_serde::sequences_from_json(&self.graph, json)?;
+        let descriptions =
+            sequences.iter().map(|sequence| sequence.print_sequence(&self.graph, true)).collect();
+        Ok(SelectedSequences { sequences, descriptions })
+    }
+
+    /// 把选出来的序列翻译成测试文件，不写入磁盘
+    pub fn emit(&self, selected: &SelectedSequences) -> GeneratedTargets {
+        GeneratedTargets {
+            helper: timing::time_phase("emission", || {
+                FileHelper::from_sequences(&self.graph, self.config.strategy, &selected.sequences)
+            }),
+        }
+    }
+
+    /// 取出生成过程中的警告（不支持的类型、不可见的API、被跳过的序列等）
+    /// rustdoc pass里这些警告由rustc的诊断接口输出，从json文档生成时由调用者自己打印
+    pub fn take_warnings(&self) -> Vec<String> {
+        diagnostics::take_warnings().iter().map(|warning| warning.to_string()).collect()
+    }
+
+    /// 每个阶段的墙上时间和CPU时间，格式和phase_timing.txt一致
+    pub fn timing_report(&self) -> String {
+        timing::timing_report()
+    }
+}
@@ -1,23 +1,37 @@
 //! 摘要，这部分是跟API依赖图中ApiFunction有关的API
-//! 1. ApiUnsafety：跟安全性有关，不说了
+//! 1. ApiUnsafety：跟安全性有关，不说了；ApiAbi：是否是extern "C"之类的非Rust ABI；ApiAsyncness：是否是async fn
 //! 2. ApiFunction：
 //!     [`_is_start_function`] 是否是开始函数
 //!     [`_is_end_function`] 是否是终结函数
 //!     [`contains_mut_borrow`] 是否参数包含可变借用
 //!     [`is_not_defined_on_prelude_type`] 是否有Option Result
 //!     [`_is_generic_function`] 是否是泛型函数
+//!     [`_has_only_impl_trait_generics`] 泛型参数是否都来自参数位置的impl Trait
 //!     [`_has_no_output`] 是否没有输出
+//!     [`_turbofish_string`] 调用泛型函数时显式写出的替换类型
+//!     [`_call_path`] 调用时写的路径，trait方法是`<Type as Trait>::method`
 //!     [`contains_unsupported_fuzzable_type`] 是否包含未支持的fuzzable类型，比如多维可变长度参数
+//!     [`_is_global_state_function`] 是否会修改进程全局状态
+//!     [`_is_diverging`] 是否不会返回（`!`或者结束进程）
+//!     [`_is_trivial_accessor`] 是否是简单的getter/setter
+//!     [`contains_non_ffi_safe_type`] 非Rust ABI的函数是否包含不能跨过C ABI边界的类型
 //!     [`_pretty_print`]：打印
 
 use crate::formats::cache::Cache;
 use crate::fuzz_targets_gen::api_util;
 use crate::fuzz_targets_gen::call_type::CallType;
+use crate::fuzz_targets_gen::emit_options::EmitOptions;
+use crate::fuzz_targets_gen::ffi;
 use crate::fuzz_targets_gen::fuzz_type::{self, FuzzableType};
+use crate::fuzz_targets_gen::global_state;
 use crate::fuzz_targets_gen::impl_util::FullNameMap;
+use crate::fuzz_targets_gen::mir_complexity::ApiComplexity;
 use rustc_data_structures::fx::{FxHashMap, FxHashSet};
+use rustc_hir::def_id::DefId;
 use rustc_hir::{self, Mutability};
 use rustc_middle::ty::Visibility;
+use rustc_span::Span;
+use rustc_target::spec::abi::Abi;
 
 use crate::clean;
 
@@ -47,6 +61,56 @@ pub(crate) fn _is_unsafe(&self) -> bool {
     }
 }
 
+/// 用来标识API的调用约定
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub(crate) enum ApiAbi {
+    Rust,
+    Foreign, //extern "C"、extern "system"等
+}
+
+impl ApiAbi {
+    pub(crate) fn _get_abi_from_fnheader(fn_header: &rustc_hir::FnHeader) -> Self {
+        match fn_header.abi {
+            Abi::Rust
+            | Abi::RustCall
+            | Abi::RustIntrinsic
+            | Abi::PlatformIntrinsic
+            | Abi::RustCold => ApiAbi::Rust,
+            _ => ApiAbi::Foreign,
+        }
+    }
+
+    pub(crate) fn _is_foreign(&self) -> bool {
+        match self {
+            ApiAbi::Foreign => true,
+            ApiAbi::Rust => false,
+        }
+    }
+}
+
+/// 用来标识API是否是async fn，async fn的返回值要await之后才能使用
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq, Ord, PartialOrd)]
+pub(crate) enum ApiAsyncness {
+    Async,
+    NotAsync,
+}
+
+impl ApiAsyncness {
+    pub(crate) fn _get_asyncness_from_fnheader(fn_header: &rustc_hir::FnHeader) -> Self {
+        match fn_header.asyncness {
+            rustc_hir::IsAsync::Async => ApiAsyncness::Async,
+            rustc_hir::IsAsync::NotAsync => ApiAsyncness::NotAsync,
+        }
+    }
+
+    pub(crate) fn _is_async(&self) -> bool {
+        match self {
+            ApiAsyncness::Async => true,
+            ApiAsyncness::NotAsync => false,
+        }
+    }
+}
+
 /// 用来标识API图中的API
 #[derive(Clone, Debug)]
 pub(crate) struct ApiFunction {
@@ -57,7 +121,14 @@ pub(crate) struct ApiFunction {
     pub(crate) output: Option<clean::Type>, //返回值
     pub(crate) _trait_full_path: Option<String>, //Trait的全限定路径,因为使用trait::fun来调用函数的时候，需要将trait的全路径引入
     pub(crate) _unsafe_tag: ApiUnsafety,         //是否unsafe
+    pub(crate) _abi: ApiAbi,                     //调用约定
+    pub(crate) _asyncness: ApiAsyncness,         //是否是async fn
     pub(crate) visibility: Visibility,           //可见性
+    pub(crate) span: Option<Span>,               //函数签名的位置，用来输出警告
+    pub(crate) def_id: Option<DefId>, //函数的定义，用来分析函数体，合成的函数和从json读进来的函数没有
+    pub(crate) _uses_unsafe: bool, //函数体里（包括调用的本crate函数）是否用到unsafe，见unsafe_usage.rs
+    pub(crate) complexity: Option<ApiComplexity>, //从MIR算出来的复杂度，没有MIR的是None，见mir_complexity.rs
+    pub(crate) trait_dispatch: Option<String>, //调用时的限定类型：trait方法是`<Type as Trait>`，实例化的泛型impl是`<Wrapper<u8>>`，见_call_path
 }
 
 impl ApiFunction {
@@ -87,6 +158,10 @@ pub(crate) fn _is_end_function(
         full_name_map: &FullNameMap,
         support_generic: bool,
     ) -> bool {
+        //不会返回的函数一定是终结函数
+        if self._is_diverging() {
+            return true;
+        }
         if self.contains_mut_borrow() {
             return false;
         }
@@ -157,6 +232,96 @@ pub(crate) fn _is_generic_function(&self) -> bool {
         return false;
     }
 
+    /// 泛型类型参数是否都来自参数位置的impl Trait
+    pub(crate) fn _has_only_impl_trait_generics(&self) -> bool {
+        self._generics
+            .params
+            .iter()
+            .filter(|param| param.kind.is_type())
+            .all(|param| param.is_synthetic_type_param())
+    }
+
+    /// 是否会修改进程全局状态，这类函数在一个序列里只能出现一次，global_states见ApiGraph::global_states
+    pub(crate) fn _is_global_state_function(
+        &self,
+        global_states: &FxHashMap<String, bool>,
+    ) -> bool {
+        global_state::is_global_state_function(&self.full_name, global_states)
+    }
+
+    /// 是否是简单的访问函数：MIR只有一个基本块（不调用别的函数，也没有分支），并且名字是
+    /// get_/set_/as_/is_开头，或者形状像getter（只有一个不可变借用参数，有返回值）、
+    /// setter（第一个参数是可变借用，一共两个参数，没有返回值）。没有MIR的函数都不算
+    pub(crate) fn _is_trivial_accessor(&self) -> bool {
+        let Some(complexity) = self.complexity else {
+            return false;
+        };
+        if complexity.basic_blocks > 1 {
+            return false;
+        }
+        let function_name = self.full_name.rsplit("::").next().unwrap_or_default();
+        let named_accessor =
+            ["get_", "set_", "as_", "is_"].iter().any(|prefix| function_name.starts_with(prefix));
+        let is_borrow_of = |ty: &clean::Type, expected: Mutability| match ty {
+            clean::Type::BorrowedRef { mutability, .. } => *mutability == expected,
+            _ => false,
+        };
+        let getter_shape = self.inputs.len() == 1
+            && is_borrow_of(&self.inputs[0], Mutability::Not)
+            && self.output.is_some();
+        let setter_shape = self.inputs.len() == 2
+            && is_borrow_of(&self.inputs[0], Mutability::Mut)
+            && self.output.is_none();
+        named_accessor || getter_shape || setter_shape
+    }
+
+    /// 是否不会返回：返回值是`!`，或者没有返回值并且名字看起来会直接结束进程
+    /// 这类函数后面的语句都不会执行，只能放在序列的最后
+    pub(crate) fn _is_diverging(&self) -> bool {
+        match &self.output {
+            Some(clean::Type::Primitive(clean::PrimitiveType::Never)) => true,
+            Some(_) => false,
+            None => global_state::is_process_exit_function(&self.full_name),
+        }
+    }
+
+    /// 调用泛型函数时显式写出替换的类型，比如`foo::<u8>`，只在返回值里出现的泛型参数不写出来就推断不了
+    /// 有impl Trait参数或者const泛型参数时不能这样写，返回空字符串交给编译器推断，
+    /// 替换成Read/Write的参数也一样
+    pub(crate) fn _turbofish_string(&self, cache: &Cache, full_name_map: &FullNameMap) -> String {
+        let mut type_names = Vec::new();
+        for param in &self._generics.params {
+            match &param.kind {
+                clean::GenericParamDefKind::Lifetime { .. } => {}
+                clean::GenericParamDefKind::Type { synthetic: false, .. } => {
+                    match self.generic_substitutions.get(&param.name.to_string()) {
+                        //Read/Write参数的类型从传入的Cursor或者Vec<u8>推断
+                        Some(clean::Type::ImplTrait(..)) => return String::new(),
+                        Some(type_) => {
+                            type_names.push(api_util::_type_name(type_, cache, full_name_map))
+                        }
+                        None => return String::new(),
+                    }
+                }
+                _ => return String::new(),
+            }
+        }
+        if type_names.is_empty() { String::new() } else { format!("::<{}>", type_names.join(", ")) }
+    }
+
+    /// 生成代码里调用这个函数时写的路径。trait方法写成全限定的`<Type as Trait>::method`，
+    /// 外部trait的方法（Read::read、Iterator::next等）和同一个类型实现了多次的泛型trait（From<u8>、From<u16>）
+    /// 用类型名或者trait名调用都可能有歧义；实例化过的泛型impl的方法写成`<Wrapper<u8>>::method`
+    pub(crate) fn _call_path(&self) -> String {
+        match &self.trait_dispatch {
+            Some(trait_dispatch) => {
+                let method_name = self.full_name.rsplit("::").next().unwrap_or(&self.full_name);
+                format!("{}::{}", trait_dispatch, method_name)
+            }
+            None => self.full_name.clone(),
+        }
+    }
+
     /// 是否有返回值
     pub(crate) fn _has_no_output(&self) -> bool {
         self.output.is_none()
@@ -168,18 +333,24 @@ pub(crate) fn contains_unsupported_fuzzable_type(
         &self,
         cache: &Cache,
         full_name_map: &FullNameMap,
+        emit_options: &EmitOptions,
     ) -> bool {
         for input_ty_ in &self.inputs {
             // 意思是
             // 如果有fuzzable_type，就进去判断一下，包含多为动态数组或者不兼容的调用类型的，就不行
             // 否则，就可能是结构体，这种应该pass
-            if api_util::is_fuzzable_type(input_ty_, cache, full_name_map, None) {
+            if api_util::is_fuzzable_type(input_ty_, cache, full_name_map, emit_options, None) {
                 // !!!!!!!!!!
                 // 从fuzzable_call_type来生成fuzzable_type和call_type
 
                 //这一行返回的是用substitution替换后的FuzzableCallType
-                let fuzzable_call_type =
-                    fuzz_type::fuzzable_call_type(input_ty_, cache, full_name_map, None);
+                let fuzzable_call_type = fuzz_type::fuzzable_call_type(
+                    input_ty_,
+                    cache,
+                    full_name_map,
+                    emit_options,
+                    None,
+                );
                 //这一行是使用替换后的FuzzableCallType来生成Fuzzable_type和CallType
                 let (fuzzable_type, call_type) =
                     fuzzable_call_type.generate_fuzzable_type_and_call_type();
@@ -192,7 +363,7 @@ pub(crate) fn contains_unsupported_fuzzable_type(
                     _ => {}
                 }
 
-                if fuzzable_type._is_multiple_dynamic_length() {
+                if fuzzable_type._is_too_deep_dynamic_length() {
                     return true;
                 }
 
@@ -208,6 +379,21 @@ pub(crate) fn contains_unsupported_fuzzable_type(
         return false;
     }
 
+    /// 非Rust ABI的函数，参数或者返回值里有不能跨过C ABI边界的类型（切片、str、元组、String等）
+    pub(crate) fn contains_non_ffi_safe_type(
+        &self,
+        cache: &Cache,
+        full_name_map: &FullNameMap,
+    ) -> bool {
+        if !self._abi._is_foreign() {
+            return false;
+        }
+        self.inputs
+            .iter()
+            .chain(self.output.iter())
+            .any(|ty_| !ffi::is_ffi_safe_type(ty_, cache, full_name_map))
+    }
+
     /// 打印函数(包含泛型函数)
     pub(crate) fn _pretty_print(&self, cache: &Cache, full_name_map: &FullNameMap) -> String {
         let generic_part = if self._generics.params.len() > 0 {
@@ -1,42 +1,49 @@
 use super::api_sequence::ReverseApiSequence;
+use super::builder;
 use super::fuzz_type;
-use crate::clean::{self, types};
+use super::timing;
+use super::traversal;
+use crate::clean;
 use crate::formats::cache::Cache;
+use crate::fuzz_targets_gen::analyzer::GenerationConfig;
 use crate::fuzz_targets_gen::api_function::ApiFunction;
+use crate::fuzz_targets_gen::api_pattern::ApiPatterns;
 use crate::fuzz_targets_gen::api_sequence::{ApiCall, ApiSequence, ParamType};
 use crate::fuzz_targets_gen::api_util::{self};
 use crate::fuzz_targets_gen::call_type::CallType;
+use crate::fuzz_targets_gen::diagnostics::{self, WarningKind};
+use crate::fuzz_targets_gen::emit_options::EmitOptions;
+use crate::fuzz_targets_gen::error::{self, FuzzGenError};
+use crate::fuzz_targets_gen::file_util;
+use crate::fuzz_targets_gen::foreign_trait;
 use crate::fuzz_targets_gen::fuzz_type::FuzzableType;
+use crate::fuzz_targets_gen::generic_candidate::{self, GenericCandidate};
+use crate::fuzz_targets_gen::greedy_selection::{self, CostRatio};
 use crate::fuzz_targets_gen::impl_util::FullNameMap;
+use crate::fuzz_targets_gen::internal_mode;
+use crate::fuzz_targets_gen::item_attributes;
+use crate::fuzz_targets_gen::maybe_uninit;
 use crate::fuzz_targets_gen::mod_visibility::ModVisibity;
+use crate::fuzz_targets_gen::numeric_cast::{self, NumericCast};
+use crate::fuzz_targets_gen::opaque_type;
 use crate::fuzz_targets_gen::prelude_type::{self, PreludeType};
+use crate::fuzz_targets_gen::progress::Progress;
 use itertools::Itertools;
 use rand::thread_rng;
 use rand::Rng;
 use rustc_data_structures::fx::{FxHashMap, FxHashSet};
-use rustc_middle::ty::Visibility;
-use std::time::Duration;
+use rustc_hir::def_id::{DefId, LocalDefId};
+use rustc_middle::ty::{TyCtxt, Visibility};
+use serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
 //use super::generic_function::GenericFunction;
 
-lazy_static! {
-    static ref RANDOM_WALK_STEPS: FxHashMap<&'static str, usize> = {
-        let mut m = FxHashMap::default();
-        m.insert("regex", 10000);
-        m.insert("url", 10000);
-        m.insert("time", 10000);
-        m
-    };
-}
-
-lazy_static! {
-    static ref CAN_COVER_NODES: FxHashMap<&'static str, usize> = {
-        let mut m = FxHashMap::default();
-        m.insert("regex", 96);
-        m.insert("serde_json", 41);
-        m.insert("clap", 66);
-        m
-    };
-}
+/// 选序列时API频率权重的默认指数，见ApiGraph::_frequency_weight
+static DEFAULT_FREQUENCY_EXPONENT: f64 = 1.0;
+/// 语料序列拼接最多生成这么多个新序列，见ApiGraph::_splice_sequences
+static MAX_SPLICED_SEQUENCES: usize = 1000;
+/// 开启prioritize_unsafe时，选序列时覆盖一个内部用到unsafe的API算几个节点，见ApiGraph::_node_weight
+static UNSAFE_NODE_WEIGHT: usize = 3;
 
 #[derive(Clone, Debug)]
 pub(crate) struct ApiGraph<'a> {
@@ -52,6 +59,10 @@ pub(crate) struct ApiGraph<'a> {
     /// 根据函数签名解析出的API依赖关系
     pub(crate) api_dependencies: Vec<ApiDependency>,
 
+    /// (output_fun, input_fun, input_param_index) -> 依赖在api_dependencies中的下标，
+    /// check_dependency用它代替线性扫描，修改api_dependencies之后要保持一致
+    pub(crate) dependency_index: FxHashMap<(usize, usize, usize), Vec<usize>>,
+
     /// 生成的一切可能的API序列
     pub(crate) api_sequences: Vec<ApiSequence>,
 
@@ -65,11 +76,68 @@ pub(crate) struct ApiGraph<'a> {
     //pub(crate) generic_functions: Vec<GenericFunction>,
     pub(crate) functions_with_unsupported_fuzzable_types: FxHashSet<String>,
     pub(crate) cache: &'a Cache,
+
+    /// 类型全路径 -> 该类型实现的trait的全路径，来自impl Trait for Type
+    pub(crate) type_trait_impls: FxHashMap<String, FxHashSet<String>>,
+
+    /// 生成测试文件时的选项
+    pub(crate) emit_options: EmitOptions,
+
+    /// 遍历时覆盖这么多函数就可以停止，None表示要覆盖所有函数，来自fries.toml
+    pub(crate) cover_nodes: Option<usize>,
+
+    /// 函数全路径 -> 是否会修改全局状态，来自global_states.txt，优先于global_state.rs里的默认规则
+    pub(crate) global_states: FxHashMap<String, bool>,
+
+    /// 替换泛型参数的候选类型，见generic_candidate.rs
+    pub(crate) generic_candidates: Vec<GenericCandidate>,
+
+    /// 结构体全路径 -> 字段名，这些结构体有一个同名的合成生产者函数，生成代码时写成结构体字面量，
+    /// 见api_util::_add_struct_literal_producers
+    pub(crate) struct_literals: FxHashMap<String, Vec<String>>,
+
+    /// 合成的FromStr生产者函数名 -> 目标类型名，生成代码时写成`.parse::<T>()`，解析失败时提前返回，
+    /// 见api_util::_add_from_str_producers
+    pub(crate) from_str_producers: FxHashMap<String, String>,
+
+    /// 合成的常量生产者函数名 -> 生成代码里写的表达式（`Duration::MAX`、`&crate::GLOBAL`），
+    /// 见const_producer.rs
+    pub(crate) const_producers: FxHashMap<String, String>,
+
+    /// 合成的`MaybeUninit::<T>::assume_init`函数名，槽位被出参写过之后才能调用，见maybe_uninit.rs
+    pub(crate) assume_init_functions: FxHashSet<String>,
+
+    /// 合成的`<T as Default>::default`生产者函数名，它们不是crate的API，
+    /// 不算进节点覆盖和选择序列的统计，--fuzz-explain和--fuzz-export-json里也不出现，
+    /// 见api_util::_add_default_producers
+    pub(crate) default_producers: FxHashSet<String>,
+
+    /// crate里收集到的字面量，写成afl的字典，见afl_dict.rs
+    pub(crate) dictionary: Vec<Vec<u8>>,
+
+    /// 函数全路径 -> 在语料里出现的次数，来自funcinfo.txt
+    pub(crate) function_frequencies: FxHashMap<String, usize>,
+
+    /// _heuristic_choose里频率权重的指数，0表示不考虑频率
+    pub(crate) frequency_exponent: f64,
+
+    /// 生成和选序列时偏向内部用到unsafe的API，见unsafe_usage.rs
+    pub(crate) prioritize_unsafe: bool,
+
+    /// 选序列的方法，None表示按生成序列的算法决定
+    pub(crate) selection: Option<SequenceSelection>,
+
+    /// 收集API时保留`#[deprecated]`的函数，见item_attributes.rs
+    pub(crate) include_deprecated: bool,
+
+    /// 收集API时保留`#[doc(hidden)]`的函数
+    pub(crate) include_doc_hidden: bool,
+    /// 整数返回值传给宽度不同的整数参数时怎么转换，见numeric_cast.rs
+    pub(crate) numeric_cast: NumericCast,
     //pub(crate) _sequences_of_all_algorithm : FxFxHashMap<GraphTraverseAlgorithm, Vec<ApiSequence>>
 }
 
 use core::fmt::Debug;
-use std::thread::sleep;
 
 impl Debug for Cache {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
@@ -78,7 +146,7 @@ fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
 }
 
 #[derive(Clone, Copy, Debug, Hash, Eq, PartialEq, Ord, PartialOrd)]
-pub(crate) enum GraphTraverseAlgorithm {
+pub enum GraphTraverseAlgorithm {
     _Default,
     _Bfs,
     _FastBfs,
@@ -89,18 +157,83 @@ pub(crate) enum GraphTraverseAlgorithm {
     _TryDeepBfs,
     _DirectBackwardSearch,
     _UseRealWorld, //当前的方法，使用解析出来的sequence
+    _MarkovWalk,   //按语料里API的转移概率随机游走，见markov_walk.rs
+    _Genetic,      //遗传算法，见genetic.rs
+    _Mcts,         //蒙特卡洛树搜索，见mcts.rs
+    _BeamSearch,   //束搜索，见beam_search.rs
     _Fudge,
 }
 
+impl GraphTraverseAlgorithm {
+    /// 从命令行的名字得到算法，不区分大小写，忽略`_`和`-`，
+    /// 所以`_BfsEndPoint`、`bfs-end-point`都可以，另外`real-world`和`backward-search`是简写
+    pub fn from_name(name: &str) -> Option<Self> {
+        let normalized = name.to_lowercase().replace(|c| c == '_' || c == '-', "");
+        let algorithm = match normalized.as_str() {
+            "default" => GraphTraverseAlgorithm::_Default,
+            "bfs" => GraphTraverseAlgorithm::_Bfs,
+            "fastbfs" => GraphTraverseAlgorithm::_FastBfs,
+            "bfsendpoint" => GraphTraverseAlgorithm::_BfsEndPoint,
+            "fastbfsendpoint" => GraphTraverseAlgorithm::_FastBfsEndPoint,
+            "randomwalk" => GraphTraverseAlgorithm::_RandomWalk,
+            "randomwalkendpoint" => GraphTraverseAlgorithm::_RandomWalkEndPoint,
+            "trydeepbfs" => GraphTraverseAlgorithm::_TryDeepBfs,
+            "directbackwardsearch" | "backwardsearch" => {
+                GraphTraverseAlgorithm::_DirectBackwardSearch
+            }
+            "userealworld" | "realworld" => GraphTraverseAlgorithm::_UseRealWorld,
+            "fudge" => GraphTraverseAlgorithm::_Fudge,
+            "markovwalk" | "markov" => GraphTraverseAlgorithm::_MarkovWalk,
+            "genetic" => GraphTraverseAlgorithm::_Genetic,
+            "mcts" => GraphTraverseAlgorithm::_Mcts,
+            "beamsearch" | "beam" => GraphTraverseAlgorithm::_BeamSearch,
+            _ => return None,
+        };
+        Some(algorithm)
+    }
+}
+
+/// 从生成的序列里选出要写成fuzz target的序列的方法，--fuzz-selection
+/// 不指定的时候按生成序列的算法决定，见file_util::choose_sequences
+#[derive(Clone, Copy, Debug, Hash, Eq, PartialEq)]
+pub enum SequenceSelection {
+    /// _first_choose：按生成的顺序选
+    First,
+    /// _heuristic_choose：贪心地覆盖更多节点和边
+    Heuristic,
+    /// _random_choose
+    Random,
+    /// _pair_coverage_choose：贪心地覆盖更多相邻API对
+    PairCoverage,
+    /// _set_cover_choose：以序列长度为代价的带权集合覆盖
+    SetCover,
+    /// _complexity_choose：贪心地覆盖MIR复杂度更高的API
+    Complexity,
+}
+
+impl SequenceSelection {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "first" => Some(SequenceSelection::First),
+            "heuristic" => Some(SequenceSelection::Heuristic),
+            "random" => Some(SequenceSelection::Random),
+            "pair-coverage" => Some(SequenceSelection::PairCoverage),
+            "set-cover" => Some(SequenceSelection::SetCover),
+            "complexity" => Some(SequenceSelection::Complexity),
+            _ => None,
+        }
+    }
+}
+
 #[allow(dead_code)]
-#[derive(Debug, Clone, Hash, Eq, PartialEq, Copy)]
+#[derive(Debug, Clone, Hash, Eq, PartialEq, Copy, Serialize, Deserialize)]
 pub(crate) enum ApiType {
     BareFunction,
     GenericFunction, //currently not support now
 }
 
 //函数的依赖关系
-#[derive(Debug, Clone, Hash, Eq, PartialEq)]
+#[derive(Debug, Clone, Hash, Eq, PartialEq, Serialize, Deserialize)]
 pub(crate) struct ApiDependency {
     pub(crate) output_fun: (ApiType, usize), //the index of first func
     pub(crate) input_fun: (ApiType, usize),  //the index of second func
@@ -117,12 +250,31 @@ pub(crate) fn new(_crate_name: &String, cache: &'a Cache) -> Self {
             api_functions: Vec::new(),
             api_functions_visited: Vec::new(),
             api_dependencies: Vec::new(),
+            dependency_index: FxHashMap::default(),
             api_sequences: Vec::new(),
             full_name_map: FullNameMap::new(),
             mod_visibility: ModVisibity::new(_crate_name),
             //generic_functions: Vec::new(),
             functions_with_unsupported_fuzzable_types: FxHashSet::default(),
             cache,
+            type_trait_impls: FxHashMap::default(),
+            emit_options: EmitOptions::new(),
+            cover_nodes: None,
+            global_states: FxHashMap::default(),
+            generic_candidates: generic_candidate::default_candidates(cache),
+            struct_literals: FxHashMap::default(),
+            from_str_producers: FxHashMap::default(),
+            const_producers: FxHashMap::default(),
+            assume_init_functions: FxHashSet::default(),
+            default_producers: FxHashSet::default(),
+            dictionary: Vec::new(),
+            function_frequencies: FxHashMap::default(),
+            frequency_exponent: DEFAULT_FREQUENCY_EXPONENT,
+            prioritize_unsafe: false,
+            selection: None,
+            include_deprecated: false,
+            include_doc_hidden: false,
+            numeric_cast: NumericCast::default(),
         }
     }
 
@@ -132,35 +284,103 @@ pub(crate) fn add_api_function(&mut self, mut api_fun: ApiFunction) {
             let generic_function = GenericFunction::from(api_fun);
             // self.generic_functions.push(generic_function);
         } else*/
-        //泛型函数不会单独考虑
-        if api_fun.contains_unsupported_fuzzable_type(self.cache, &self.full_name_map) {
+        //泛型函数不会单独考虑，参数位置的impl Trait先换成泛型参数
+        generic_candidate::name_impl_trait_params(&mut api_fun);
+        if api_fun.contains_unsupported_fuzzable_type(
+            self.cache,
+            &self.full_name_map,
+            &self.emit_options,
+        ) {
+            diagnostics::warn(
+                WarningKind::UnsupportedType,
+                api_fun.span,
+                format!("`{}` has a parameter that can not be fuzzed, skipped", api_fun.full_name),
+            );
             self.functions_with_unsupported_fuzzable_types.insert(api_fun.full_name.clone());
         } else {
-            // FIXME:新加入泛型
-            //既然支持了泛型函数，就要初始化generic_substitution
-            for generic_arg in &api_fun._generics.params {
-                //当这个是泛型类型（而不是生命周期等）
-                if let types::GenericParamDefKind::Type { .. } = generic_arg.kind {
-                    let generic_name = generic_arg.name.to_string();
-                    //暂时只支持把泛型替换成i32
-                    api_fun
-                        .generic_substitutions
-                        .insert(generic_name, clean::Type::Primitive(clean::PrimitiveType::I32));
-                }
+            //每种满足trait bound的候选类型组合注册一个实例，不是泛型函数的时候只有一个空的替换
+            let instantiations =
+                generic_candidate::instantiations(&api_fun, &self.generic_candidates);
+            if instantiations.is_empty() {
+                diagnostics::warn(
+                    WarningKind::UnsupportedType,
+                    api_fun.span,
+                    format!(
+                        "no generic candidate satisfies the bounds of `{}`, skipped",
+                        api_fun.full_name
+                    ),
+                );
+                return;
+            }
+            for generic_substitutions in instantiations {
+                let mut instance = api_fun.clone();
+                instance.generic_substitutions = generic_substitutions;
+                self.api_functions.push(instance);
+            }
+            //返回值是不透明类型的时候，为它加上Iterator、Read、Write的辅助函数
+            if let Some(output_type @ clean::Type::ImplTrait(..)) = &api_fun.output {
+                foreign_trait::add_opaque_surface(output_type, self);
             }
-            self.api_functions.push(api_fun);
         }
     }
 
+    /// 收集API时是否跳过这个item，见item_attributes.rs
+    pub(crate) fn skips_item(&self, tcx: TyCtxt<'_>, item: &clean::Item) -> bool {
+        (!self.include_doc_hidden && item_attributes::is_doc_hidden(tcx, item))
+            || (!self.include_deprecated && item_attributes::is_deprecated_item(tcx, item))
+    }
+
+    /// 和skips_item一样，用于没有clean::Item的常量
+    pub(crate) fn skips_def_id(&self, tcx: TyCtxt<'_>, def_id: DefId) -> bool {
+        (!self.include_doc_hidden && item_attributes::is_doc_hidden_def(tcx, def_id))
+            || (!self.include_deprecated && item_attributes::is_deprecated_def(tcx, def_id))
+    }
+
+    /// 用rustc的trait solver判断泛型候选类型满足哪些bound，要在加入函数之前调用
+    pub(crate) fn resolve_generic_bounds(&mut self, tcx: TyCtxt<'_>) {
+        generic_candidate::resolve_traits(&mut self.generic_candidates, tcx, self.cache);
+    }
+
+    /// 用配置里的名字替换默认的泛型候选类型，要在加入函数之前调用
+    pub(crate) fn set_generic_candidates(&mut self, names: &[String]) {
+        self.generic_candidates = generic_candidate::parse_candidates(names, self.cache);
+    }
+
     /// 遍历到某个mod的时候，添加mod的可见性，为过滤出可见的api做准备
-    pub(crate) fn add_mod_visibility(&mut self, mod_name: &String, visibility: &Visibility) {
-        self.mod_visibility.add_one_mod(mod_name, visibility);
+    pub(crate) fn add_mod_visibility(
+        &mut self,
+        mod_name: &String,
+        visibility: &Visibility,
+        def_id: Option<LocalDefId>,
+    ) {
+        self.mod_visibility.add_one_mod(mod_name, visibility, def_id);
     }
 
-    /// 根据prelude type和可见性来过滤api
-    pub(crate) fn filter_functions(&mut self, support_generic: bool) {
+    /// 添加类型或者trait的可见性，它们的方法也要按这个过滤
+    pub(crate) fn add_item_visibility(&mut self, item_name: &String, visibility: &Visibility) {
+        self.mod_visibility.add_one_item(item_name, visibility);
+    }
+
+    /// 根据prelude type和可见性来过滤api，还会按cfg去掉简单的getter/setter和不符合include/exclude的api
+    pub(crate) fn filter_functions(&mut self, cfg: &GenerationConfig) {
+        let support_generic = cfg.support_generic;
         self.filter_functions_defined_on_prelude_type();
-        self.filter_api_functions_by_mod_visibility();
+        self.filter_functions_by_patterns(&ApiPatterns::new(&cfg.include, &cfg.exclude));
+        //在crate内部生成的时候，按fuzz target所在的模块能否访问过滤
+        let viewer = if cfg.internal {
+            Some(internal_mode::viewer_module(self._crate_name.as_str()))
+        } else {
+            None
+        };
+        if let Err(e) = self.filter_api_functions_by_mod_visibility(viewer.as_deref()) {
+            //没有mod的信息，只按照函数自身的可见性过滤
+            error::warn(&e, "only keep functions declared pub");
+            self.api_functions.retain(|api_function| api_function.visibility.is_public());
+        }
+        self.filter_non_ffi_safe_functions();
+        if !cfg.keep_trivial_accessors {
+            self.filter_trivial_accessors();
+        }
 
         /*for (idx, api) in self.api_functions.iter().enumerate() {
             println!(
@@ -175,7 +395,8 @@ pub(crate) fn filter_functions(&mut self, support_generic: bool) {
             let mut new_api_function = Vec::new();
             for func in &self.api_functions {
                 //if func._generics.params.len() == 0 {
-                if !func._is_generic_function()
+                //只有impl Trait参数的函数替换成具体类型之后就能直接调用，不算泛型函数
+                if (!func._is_generic_function() || func._has_only_impl_trait_generics())
                     && !func.full_name.contains("from_static")
                     && !func.full_name.contains("with_capacity")
                     && !func.full_name.contains("TimeDelta")
@@ -208,65 +429,186 @@ pub(crate) fn filter_functions_defined_on_prelude_type(&mut self) {
             .collect();
     }
 
-    /// 过滤api，根据可见性进行过滤，不是pub就过滤掉
-    /// FIXME:  是否必要
-    pub(crate) fn filter_api_functions_by_mod_visibility(&mut self) {
-        if self.mod_visibility.inner.is_empty() {
-            panic!("No mod!!!!!!");
+    /// 过滤api，只保留全路径符合--fuzz-include/--fuzz-exclude的，见api_pattern.rs
+    pub(crate) fn filter_functions_by_patterns(&mut self, patterns: &ApiPatterns) {
+        if patterns.is_empty() {
+            return;
+        }
+        let before = self.api_functions.len();
+        self.api_functions.retain(|api_function| patterns.allows(&api_function.full_name));
+        println!("include/exclude patterns filtered {} apis", before - self.api_functions.len());
+    }
+
+    /// 过滤api，去掉简单的getter/setter（见ApiFunction::_is_trivial_accessor），它们几乎不会有bug，
+    /// 却会让序列的数量成倍增加。剩下的函数如果有参数只能由某个去掉的函数提供，这个函数留下来当生产者
+    pub(crate) fn filter_trivial_accessors(&mut self) {
+        let trivial = self
+            .api_functions
+            .iter()
+            .map(|api_function| api_function._is_trivial_accessor())
+            .collect_vec();
+        let cache = self.cache;
+        let full_name_map = &self.full_name_map;
+        let emit_options = &self.emit_options;
+        let api_functions = &self.api_functions;
+        let produces = |producer: &ApiFunction, input_type: &clean::Type| {
+            producer.output.as_ref().map_or(false, |output_type| {
+                !matches!(
+                    api_util::_same_type(output_type, input_type, true, cache, full_name_map),
+                    CallType::_NotCompatible
+                )
+            })
+        };
+        let mut needed = vec![false; api_functions.len()];
+        for (index, api_function) in api_functions.iter().enumerate() {
+            if trivial[index] {
+                continue;
+            }
+            for input_type in &api_function.inputs {
+                if api_util::is_fuzzable_type(input_type, cache, full_name_map, emit_options, None)
+                    || api_functions.iter().enumerate().any(|(producer_index, producer)| {
+                        !trivial[producer_index] && produces(producer, input_type)
+                    })
+                {
+                    continue;
+                }
+                for (producer_index, producer) in api_functions.iter().enumerate() {
+                    if trivial[producer_index] && produces(producer, input_type) {
+                        needed[producer_index] = true;
+                    }
+                }
+            }
         }
+        let trivial_number = trivial.iter().filter(|is_trivial| **is_trivial).count();
+        let producer_number = needed.iter().filter(|is_needed| **is_needed).count();
+        let mut index = 0;
+        self.api_functions.retain(|_| {
+            let keep = !trivial[index] || needed[index];
+            index = index + 1;
+            keep
+        });
+        println!(
+            "filtered {} trivial accessors, {} of them kept as producers",
+            trivial_number - producer_number,
+            producer_number
+        );
+    }
 
-        let invisible_mods = self.mod_visibility.get_invisible_mods();
+    /// 过滤api，extern "C"之类的函数只保留参数和返回值都能跨过C ABI边界的
+    pub(crate) fn filter_non_ffi_safe_functions(&mut self) {
+        let cache = self.cache;
+        let full_name_map = &self.full_name_map;
+        self.api_functions.retain(|api_function| {
+            let unsafe_ffi = api_function.contains_non_ffi_safe_type(cache, full_name_map);
+            if unsafe_ffi {
+                diagnostics::warn(
+                    WarningKind::UnsupportedType,
+                    api_function.span,
+                    format!(
+                        "`{}` uses a type that can not cross the C ABI, skipped",
+                        api_function.full_name
+                    ),
+                );
+            }
+            !unsafe_ffi
+        });
+    }
+
+    /// 过滤api，根据可见性进行过滤：函数自己、所在的模块、方法所属的类型和trait都要从viewer模块可见，
+    /// viewer为None表示crate外面，这时pub(crate)、pub(super)、pub(in path)和私有的都访问不到
+    pub(crate) fn filter_api_functions_by_mod_visibility(
+        &mut self,
+        viewer: Option<&str>,
+    ) -> Result<(), FuzzGenError> {
+        if self.mod_visibility.inner.is_empty() {
+            return Err(FuzzGenError::NoModule);
+        }
 
+        let mod_visibility = &self.mod_visibility;
         let mut new_api_functions = Vec::new();
 
         //遍历api_graph中的所有的api
         for api_func in &self.api_functions {
             let api_func_name = &api_func.full_name;
-            let trait_full_path = &api_func._trait_full_path;
-            let mut invisible_flag = false;
-            for invisible_mod in &invisible_mods {
-                // 两种情况下api不可见：
-                // 1. crate::m1::m2::api中的某个mod不可见
-                // 2. api实现了某个trait，同时trait不可见
-                if api_func_name.as_str().starts_with(invisible_mod.as_str()) {
-                    invisible_flag = true;
-                    break;
-                }
-                if api_func_name.as_str().ends_with("lossy_normalization")
-                    || api_func_name.as_str().ends_with(":TokenizerBuilder::new")
-                {
-                    invisible_flag = true;
-                    break;
-                }
-
-                if let Some(trait_full_path) = trait_full_path {
-                    if trait_full_path.as_str().starts_with(invisible_mod) {
-                        invisible_flag = true;
-                        break;
-                    }
-                }
+            if api_func_name.as_str().ends_with("lossy_normalization")
+                || api_func_name.as_str().ends_with(":TokenizerBuilder::new")
+            {
+                continue;
             }
-
-            // parent所在mod可见
-            if !invisible_flag && api_func.visibility.is_public() {
-                new_api_functions.push(api_func.clone());
+            //函数自己访问不到的本来就不是API，不用提示
+            if !mod_visibility.is_visible_from(&api_func.visibility, viewer) {
+                continue;
+            }
+            // 两种情况下api不可见：
+            // 1. crate::m1::m2::Type::api中的某个mod或者Type不可见
+            // 2. api实现了某个trait，同时trait不可见
+            let invisible =
+                mod_visibility.invisible_ancestor(api_func_name, viewer).or_else(|| {
+                    api_func._trait_full_path.as_ref().and_then(|trait_full_path| {
+                        mod_visibility.invisible_path(trait_full_path, viewer)
+                    })
+                });
+            match invisible {
+                None => new_api_functions.push(api_func.clone()),
+                Some((path, visibility)) => {
+                    diagnostics::warn(
+                        WarningKind::InvisibleApi,
+                        api_func.span,
+                        format!(
+                            "`{}` is visible itself but `{}` is {}, skipped",
+                            api_func_name,
+                            path,
+                            mod_visibility.describe(&visibility)
+                        ),
+                    );
+                }
             }
         }
         self.api_functions = new_api_functions;
+        Ok(())
     }
 
     pub(crate) fn set_full_name_map(&mut self, full_name_map: &FullNameMap) {
         self.full_name_map = full_name_map.clone();
     }
 
+    /// 记录类型实现了某个trait
+    pub(crate) fn add_type_trait_impl(
+        &mut self,
+        type_full_name: &String,
+        trait_full_name: &String,
+    ) {
+        self.type_trait_impls
+            .entry(type_full_name.clone())
+            .or_insert_with(FxHashSet::default)
+            .insert(trait_full_name.clone());
+    }
+
+    /// 类型是否实现了某个trait
+    pub(crate) fn type_implements_trait(
+        &self,
+        type_full_name: &String,
+        trait_full_name: &str,
+    ) -> bool {
+        match self.type_trait_impls.get(type_full_name) {
+            Some(traits) => traits.contains(trait_full_name),
+            None => false,
+        }
+    }
+
     ///找到所有可能的依赖关系，存在api_dependencies中，供后续使用
     pub(crate) fn find_all_dependencies(&mut self, support_generic: bool) {
         println!("find_dependencies");
         self.api_dependencies.clear();
+        self.dependency_index.clear();
+        let mut progress = Progress::new("find dependencies", Some(self.api_functions.len()));
+        let mut opaque_dependencies = Vec::new();
 
         // 两个api_function之间的dependency
         // 其中i和j分别是first_fun和second_fun在api_graph的index
         for (i, first_fun) in self.api_functions.iter().enumerate() {
+            progress.inc(1);
+            progress.set_sequences(self.api_dependencies.len());
             if first_fun._is_end_function(self.cache, &self.full_name_map, support_generic) {
                 //如果第一个函数是终止节点，就不寻找这样的依赖
                 continue;
@@ -317,6 +659,20 @@ pub(crate) fn find_all_dependencies(&mut self, support_generic: bool) {
                     // 下面开始正题
                     // 对于second_fun的每个参数，看看first_fun的返回值是否对应得上
                     for (k, input_type) in second_fun.inputs.iter().enumerate() {
+                        //返回值是不透明类型的时候不能替换泛型，也不能和具体类型比较，见opaque_type.rs
+                        if let clean::Type::ImplTrait(..) = &output_type {
+                            let call_type =
+                                opaque_type::opaque_call_type(&output_type, second_fun, k);
+                            if !matches!(call_type, CallType::_NotCompatible) {
+                                opaque_dependencies.push(ApiDependency {
+                                    output_fun: (ApiType::BareFunction, i),
+                                    input_fun: (ApiType::BareFunction, j),
+                                    input_param_index: k,
+                                    call_type,
+                                });
+                            }
+                            continue;
+                        }
                         let mut input_type = input_type.clone();
                         //为了添加泛型支持，在这里先替换
                         /*println!(
@@ -406,6 +762,10 @@ pub(crate) fn find_all_dependencies(&mut self, support_generic: bool) {
                                     input_param_index: k,
                                     call_type: call_type.clone(),
                                 };
+                                self.dependency_index
+                                    .entry((i, j, k))
+                                    .or_default()
+                                    .push(self.api_dependencies.len());
                                 self.api_dependencies.push(one_dependency);
                             }
                         }
@@ -413,6 +773,12 @@ pub(crate) fn find_all_dependencies(&mut self, support_generic: bool) {
                 }
             }
         }
+        for dependency in opaque_dependencies {
+            self.push_dependency(dependency);
+        }
+        numeric_cast::add_numeric_dependencies(self);
+        progress.set_sequences(self.api_dependencies.len());
+        progress.finish();
 
         println!(
             "find_dependencies finished! Num of dependencies is {}.",
@@ -420,7 +786,27 @@ pub(crate) fn find_all_dependencies(&mut self, support_generic: bool) {
         );
     }
 
-    pub(crate) fn _default_generate_sequences(&mut self, lib_name: &str) {
+    /// 加入一条依赖，同时更新dependency_index
+    pub(crate) fn push_dependency(&mut self, dependency: ApiDependency) {
+        let key = (dependency.output_fun.1, dependency.input_fun.1, dependency.input_param_index);
+        self.dependency_index.entry(key).or_default().push(self.api_dependencies.len());
+        self.api_dependencies.push(dependency);
+    }
+
+    /// 整体替换api_dependencies之后重建dependency_index
+    pub(crate) fn rebuild_dependency_index(&mut self) {
+        self.dependency_index.clear();
+        for (index, dependency) in self.api_dependencies.iter().enumerate() {
+            let key =
+                (dependency.output_fun.1, dependency.input_fun.1, dependency.input_param_index);
+            self.dependency_index.entry(key).or_default().push(index);
+        }
+    }
+
+    pub(crate) fn _default_generate_sequences(
+        &mut self,
+        lib_name: &str,
+    ) -> Result<(), FuzzGenError> {
         //BFS + backward search
         self.generate_all_possoble_sequences(
             GraphTraverseAlgorithm::_BfsEndPoint,
@@ -428,11 +814,12 @@ pub(crate) fn _default_generate_sequences(&mut self, lib_name: &str) {
             300,
             200,
             false,
-        );
+        )?;
         self._try_to_cover_unvisited_nodes();
 
         // backward search
         //self.generate_all_possoble_sequences(GraphTraverseAlgorithm::_DirectBackwardSearch);
+        Ok(())
     }
 
     pub(crate) fn generate_all_possoble_sequences(
@@ -442,74 +829,30 @@ pub(crate) fn generate_all_possoble_sequences(
         max_num: usize,
         max_len: usize,
         support_generic: bool,
-    ) {
-        //BFS序列的最大长度：即为函数的数量,或者自定义
-        //let bfs_max_len = self.api_functions.len();
-        let bfs_max_len = 5;
-        //random walk的最大步数
-
-        /*
-        let random_walk_max_size = if RANDOM_WALK_STEPS.contains_key(self._crate_name.as_str()) {
-            RANDOM_WALK_STEPS.get(self._crate_name.as_str()).unwrap().clone()
-        } else {
-            100000
-        };*/
-
-        let random_walk_max_size = 100000;
-
-        //no depth bound
-        let random_walk_max_depth = 0;
-        //try deep sequence number
-        let max_sequence_number = 100000;
-        match algorithm {
-            GraphTraverseAlgorithm::_Bfs => {
-                println!("using bfs");
-                self.bfs(bfs_max_len, false, false);
-            }
-            GraphTraverseAlgorithm::_FastBfs => {
-                println!("using fastbfs");
-                self.bfs(bfs_max_len, false, true);
-            }
-            GraphTraverseAlgorithm::_BfsEndPoint | GraphTraverseAlgorithm::_Default => {
-                println!("using bfs end point");
-                self.bfs(bfs_max_len, true, false);
-            }
-            GraphTraverseAlgorithm::_FastBfsEndPoint => {
-                println!("using fast bfs end point");
-                self.bfs(bfs_max_len, true, true);
-            }
-            GraphTraverseAlgorithm::_TryDeepBfs => {
-                println!("using try deep bfs");
-                //self.real_world(lib_name);
-                self._try_deep_bfs(max_sequence_number);
-            }
-
-            GraphTraverseAlgorithm::_RandomWalkEndPoint => {
-                println!("using random walk end point");
-                self.random_walk(random_walk_max_size, true, random_walk_max_depth);
-            }
+    ) -> Result<(), FuzzGenError> {
+        let cfg = GenerationConfig {
+            strategy: algorithm,
+            max_num,
+            max_len,
+            support_generic,
+            ..GenerationConfig::default()
+        };
+        self.generate_sequences(lib_name, &cfg)
+    }
 
-            GraphTraverseAlgorithm::_DirectBackwardSearch => {
-                println!("using backward search");
-                self.api_sequences.clear();
-                self.reset_visited();
-                self._try_to_cover_unvisited_nodes();
-            }
-            GraphTraverseAlgorithm::_RandomWalk => {
-                println!("using random walk");
-                self.random_walk(max_num, false, max_len);
-            }
-            GraphTraverseAlgorithm::_UseRealWorld => {
-                println!("using realworld to generate");
-                //self.real_world(lib_name);
-                self.my_method(lib_name, max_num, max_len, support_generic);
-                //self._try_to_cover_unvisited_nodes();
-            }
-            GraphTraverseAlgorithm::_Fudge => {
-                println!("using realworld to generate");
-                self.fudge(lib_name);
-            }
-        }
+    /// 按照cfg里的算法和参数生成序列
+    pub(crate) fn generate_sequences(
+        &mut self,
+        lib_name: &str,
+        cfg: &GenerationConfig,
+    ) -> Result<(), FuzzGenError> {
+        self.cover_nodes = cfg.cover_nodes;
+        self.frequency_exponent = cfg.frequency_exponent.unwrap_or(DEFAULT_FREQUENCY_EXPONENT);
+        self.prioritize_unsafe = cfg.prioritize_unsafe;
+        self.selection = cfg.selection;
+        timing::time_phase(format!("traversal {:?}", cfg.strategy).as_str(), || {
+            traversal::strategy_for(cfg.strategy, lib_name).generate(self, cfg)
+        })
     }
 
     pub(crate) fn reset_visited(&mut self) {
@@ -529,15 +872,10 @@ pub(crate) fn check_all_visited(&self) -> bool {
                 visited_nodes = visited_nodes + 1;
             }
         }
-        /*
-        if CAN_COVER_NODES.contains_key(self._crate_name.as_str()) {
-            let to_cover_nodes = CAN_COVER_NODES.get(self._crate_name.as_str()).unwrap().clone();
-            if visited_nodes == to_cover_nodes {
-                return true;
-            } else {
-                return false;
-            }
-        }*/
+        //有些函数无论如何都覆盖不到，fries.toml里可以给出能覆盖的数量
+        if let Some(cover_nodes) = self.cover_nodes {
+            return visited_nodes >= cover_nodes;
+        }
 
         if visited_nodes == self.api_functions_visited.len() {
             return true;
@@ -548,9 +886,11 @@ pub(crate) fn check_all_visited(&self) -> bool {
 
     //已经访问过的节点数量,用来快速判断bfs是否还需要run下去：如果一轮下来，bfs的长度没有发生变化，那么也可直接quit了
     pub(crate) fn _visited_nodes_num(&self) -> usize {
-        let visited: Vec<&bool> =
-            (&self.api_functions_visited).into_iter().filter(|x| **x == true).collect();
-        visited.len()
+        (0..self.api_functions.len())
+            .filter(|index| {
+                self.api_functions_visited[*index] && !self._is_default_producer(*index)
+            })
+            .count()
     }
 
     //生成函数序列，且指定调用的参数
@@ -570,7 +910,9 @@ pub(crate) fn bfs(&mut self, max_len: usize, stop_at_end_function: bool, fast_mo
         self.api_sequences.push(api_sequence);
 
         //接下来开始从长度1一直到max_len遍历
+        let mut progress = Progress::new("bfs", Some(max_len));
         for len in 0..max_len {
+            progress.inc(1);
             let mut tmp_sequences = Vec::new();
             for sequence in &self.api_sequences {
                 if stop_at_end_function && self.is_sequence_ended(sequence, false) {
@@ -595,6 +937,7 @@ pub(crate) fn bfs(&mut self, max_len: usize, stop_at_end_function: bool, fast_mo
                     {
                         self.api_sequences.push(new_sequence);
                         self.api_functions_visited[api_func_index] = true;
+                        progress.set_sequences(self.api_sequences.len());
 
                         //bfs fast，如果都已经别访问过，直接退出
                         if self.check_all_visited() {
@@ -605,6 +948,7 @@ pub(crate) fn bfs(&mut self, max_len: usize, stop_at_end_function: bool, fast_mo
                 }
             }
         }
+        progress.finish();
 
         println!("There are total {} sequences after bfs", self.api_sequences.len());
         /*if !stop_at_end_function {
@@ -657,7 +1001,7 @@ pub(crate) fn _try_deep_bfs(&mut self, max_sequence_number: usize) {
                     if let Some(new_sequence) =
                         self.is_fun_satisfied(&api_type, api_func_index, sequence)
                     {
-                        let covered_nodes = new_sequence._get_contained_api_functions();
+                        let covered_nodes = self._api_nodes(&new_sequence);
                         for covered_node in &covered_nodes {
                             if !already_covered_nodes.contains(covered_node) {
                                 already_covered_nodes.insert(*covered_node);
@@ -706,8 +1050,11 @@ pub(crate) fn random_walk(
         //start random work
         let function_len = self.api_functions.len();
         let mut rng = thread_rng();
+        //开启prioritize_unsafe时，一半的步数只从内部用到unsafe的API里选
+        let unsafe_functions = self._unsafe_functions();
 
         let mut seq_num = 0;
+        let mut progress = Progress::new("random walk", Some(max_size + 1));
         // max_size是api序列的最大数量
         loop {
             let current_sequence_len = self.api_sequences.len();
@@ -720,7 +1067,11 @@ pub(crate) fn random_walk(
 
             //如果深度没有很深，就继续加
 
-            let chosen_fun_index = rng.gen_range(0, function_len);
+            let chosen_fun_index = if !unsafe_functions.is_empty() && rng.gen_bool(0.5) {
+                unsafe_functions[rng.gen_range(0, unsafe_functions.len())]
+            } else {
+                rng.gen_range(0, function_len)
+            };
             //let chosen_fun = &self.api_functions[chosen_fun_index];
             let fun_type = ApiType::BareFunction;
             if let Some(new_sequence) =
@@ -730,9 +1081,11 @@ pub(crate) fn random_walk(
 
                 self.api_functions_visited[chosen_fun_index] = true;
 
+                progress.set_sequences(self.api_sequences.len());
                 if new_sequence.len() >= max_len {
                     //println!("api_functions {}", new_sequence.len());
                     seq_num += 1;
+                    progress.inc(1);
                     if seq_num > max_size {
                         break;
                     }
@@ -745,9 +1098,89 @@ pub(crate) fn random_walk(
                 // }
             }
         }
+        progress.finish();
+    }
+
+    /// 读取语料里API的出现次数，给_frequency_weight用，文件不存在或者格式不对的时候没有频率信息
+    pub(crate) fn load_function_frequencies(&mut self, lib_name: &str, corpus_path: Option<&str>) {
+        let corpus_path = file_util::corpus_path(
+            corpus_path,
+            format!("{}/{}", file_util::experiment_root(), lib_name),
+        );
+        let funcinfo_file_path =
+            file_util::corpus_info_dir(&corpus_path).join("funcinfo.txt").display().to_string();
+        self.function_frequencies = read_func_info(&funcinfo_file_path).unwrap_or_else(|e| {
+            error::warn(&e, "ignore function frequency info");
+            FxHashMap::default()
+        });
+    }
+
+    /// 语料序列的拼接（crossover）：序列A的第i个调用和序列B的第j个调用返回同样的类型时，
+    /// 把A的前i+1个调用和B第j个调用之后的部分接起来，B后面用到B[j]返回值的地方可以改用A[i]的返回值
+    /// 拼出来的序列从头重新用is_fun_satisfied检查，加不进去的丢掉，基本类型不作为拼接点
+    pub(crate) fn _splice_sequences(&mut self, max_len: usize) {
+        let corpus_sequences = self
+            .api_sequences
+            .iter()
+            .map(|sequence| sequence.functions.iter().map(|api_call| api_call.func.1).collect_vec())
+            .collect_vec();
+        let mut seen: FxHashSet<Vec<usize>> = corpus_sequences.iter().cloned().collect();
+        let mut spliced = Vec::new();
+        'splice: for (former_index, former) in corpus_sequences.iter().enumerate() {
+            for (latter_index, latter) in corpus_sequences.iter().enumerate() {
+                if former_index == latter_index {
+                    continue;
+                }
+                for (i, former_function) in former.iter().enumerate() {
+                    let cut_type = match &self.api_functions[*former_function].output {
+                        Some(clean::Type::Primitive(_)) | None => continue,
+                        Some(cut_type) => cut_type,
+                    };
+                    //B的后半段不能为空
+                    for (j, latter_function) in
+                        latter.iter().enumerate().take(latter.len().saturating_sub(1))
+                    {
+                        if self.api_functions[*latter_function].output.as_ref() != Some(cut_type) {
+                            continue;
+                        }
+                        let candidate =
+                            former[..=i].iter().chain(&latter[j + 1..]).copied().collect_vec();
+                        if candidate.len() > max_len || !seen.insert(candidate.clone()) {
+                            continue;
+                        }
+                        if let Some(sequence) = self._replay_functions(&candidate) {
+                            spliced.push(sequence);
+                            if spliced.len() >= MAX_SPLICED_SEQUENCES {
+                                break 'splice;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        println!("拼接语料序列得到 {} 个新序列", spliced.len());
+        for sequence in &spliced {
+            for index in sequence._get_contained_api_functions() {
+                self.api_functions_visited[index] = true;
+            }
+        }
+        self.api_sequences.extend(spliced);
+    }
+
+    /// 从空序列开始依次加入functions里的函数，有一个加不进去就返回None
+    fn _replay_functions(&self, functions: &[usize]) -> Option<ApiSequence> {
+        let mut sequence = ApiSequence::new();
+        for function_index in functions {
+            sequence = self.is_fun_satisfied(&ApiType::BareFunction, *function_index, &sequence)?;
+        }
+        Some(sequence)
     }
 
-    pub(crate) fn fudge(&mut self, lib_name: &str) {
+    pub(crate) fn fudge(
+        &mut self,
+        lib_name: &str,
+        corpus_path: Option<&str>,
+    ) -> Result<(), FuzzGenError> {
         use std::fs::File;
         use std::io::{BufRead, BufReader};
 
@@ -756,50 +1189,83 @@ pub(crate) fn fudge(&mut self, lib_name: &str) {
         //在语料库中所有API
         let mut apis_existing_in_corpus_map = FxHashMap::default();
 
-        let seq_file_path = format!(
-            "/home/yxz/workspace/fuzz/experiment_root/{}/seq-dedup.ans",
+        //语料可以是一个文件，也可以是放了多个.ans文件的目录，见file_util::corpus_path
+        let default_corpus = format!(
+            "{}/{}/seq-dedup.ans",
+            file_util::experiment_root(),
             lib_name.to_string().replace("-", "_")
         );
-        println!("{}", seq_file_path);
-        let file = File::open(seq_file_path).unwrap();
-        let reader = BufReader::new(file);
-        for line in reader.lines() {
-            let line = line.unwrap();
-            let fields = line.split("|").into_iter().map(|x| x.to_string()).collect_vec();
-
-            // 1.解析出序列频率
-
-            let freq = fields.get(1).unwrap();
-            let cnt_str: String = freq.chars().filter(|c| c.is_digit(10)).collect();
-            let parsed_number: i32 = cnt_str.parse().unwrap();
-
-            // 2.解析sequence
-
-            let sequence = fields.last().unwrap().clone();
-            //获得api的名字
-            let functions: Vec<String> = sequence
-                .split(" ")
-                .map(|x| x.to_string())
-                .filter(|x| x.len() > 1) //过滤""
-                .collect();
-
-            for func in functions.clone() {
-                if apis_existing_in_corpus_map.contains_key(&func) {
-                    //包含这个func，就加上去
-                    apis_existing_in_corpus_map.insert(
-                        func.clone(),
-                        apis_existing_in_corpus_map.get(&func).unwrap() + parsed_number,
-                    );
-                } else {
-                    //如果没有，就创建这个entry
-                    apis_existing_in_corpus_map.insert(func, parsed_number);
+        let corpus_path = file_util::corpus_path(corpus_path, default_corpus);
+        for seq_file in file_util::corpus_sequence_files(&corpus_path)? {
+            let seq_file_path = seq_file.display().to_string();
+            println!("{}", seq_file_path);
+            let file = File::open(&seq_file_path)
+                .map_err(|error| FuzzGenError::Io { path: seq_file_path.clone(), error })?;
+            let reader = BufReader::new(file);
+            for (line_index, line) in reader.lines().enumerate() {
+                let line =
+                    line.map_err(|error| FuzzGenError::Io { path: seq_file_path.clone(), error })?;
+                let malformed = || FuzzGenError::MalformedLine {
+                    path: seq_file_path.clone(),
+                    line: line_index + 1,
+                    content: line.clone(),
+                };
+                let fields = line.split("|").into_iter().map(|x| x.to_string()).collect_vec();
+
+                // 1.解析出序列频率
+
+                let freq = fields.get(1).ok_or_else(malformed)?;
+                let cnt_str: String = freq.chars().filter(|c| c.is_digit(10)).collect();
+                let parsed_number: i32 = cnt_str.parse().map_err(|_| malformed())?;
+
+                // 2.解析sequence
+
+                let sequence = fields.last().ok_or_else(malformed)?.clone();
+                //获得api的名字
+                let functions: Vec<String> = sequence
+                    .split(" ")
+                    .map(|x| x.to_string())
+                    .filter(|x| x.len() > 1) //过滤""
+                    .collect();
+
+                for func in functions.clone() {
+                    if apis_existing_in_corpus_map.contains_key(&func) {
+                        //包含这个func，就加上去
+                        apis_existing_in_corpus_map.insert(
+                            func.clone(),
+                            apis_existing_in_corpus_map.get(&func).unwrap() + parsed_number,
+                        );
+                    } else {
+                        //如果没有，就创建这个entry
+                        apis_existing_in_corpus_map.insert(func, parsed_number);
+                    }
+                }
+
+                //不会返回的函数出现在中间，后面的语句在语料里也执行不到
+                if let Some((_, last_functions)) = functions.split_last() {
+                    for func in last_functions {
+                        if self.api_functions.iter().any(|api_function| {
+                            api_function.full_name == *func && api_function._is_diverging()
+                        }) {
+                            diagnostics::warn(
+                                WarningKind::DeadStatements,
+                                None,
+                                format!(
+                                    "corpus sequence at {}:{} calls `{}` before its end, later calls never run",
+                                    seq_file_path,
+                                    line_index + 1,
+                                    func
+                                ),
+                            );
+                        }
+                    }
                 }
-            }
 
-            sequences.push(functions.clone());
+                sequences.push(functions.clone());
 
-            //打印出名字
-            println!("被解析出来的合理的序列: {:?}", functions);
+                //打印出名字
+                println!("被解析出来的合理的序列: {:?}", functions);
+            }
         }
 
         // check一下有没有corpus都在里面
@@ -813,7 +1279,7 @@ pub(crate) fn fudge(&mut self, lib_name: &str) {
         self.reset_visited();
         let max_len = 4;
         if max_len < 1 {
-            return;
+            return Ok(());
         }
 
         self.api_sequences.clear();
@@ -821,7 +1287,7 @@ pub(crate) fn fudge(&mut self, lib_name: &str) {
 
         //没有函数的话，直接return
         if self.api_functions.len() <= 0 {
-            return;
+            return Ok(());
         }
 
         //加入一个长度为0的序列
@@ -868,6 +1334,7 @@ pub(crate) fn fudge(&mut self, lib_name: &str) {
         }
         //****************************************************************************** */
         println!("sequences len is {}", self.api_sequences.len());
+        Ok(())
     }
 
     pub(crate) fn my_method(
@@ -876,128 +1343,32 @@ pub(crate) fn my_method(
         max_num: usize,
         max_len: usize,
         support_generic: bool,
-    ) {
-        use std::fs::File;
-        use std::io::{BufRead, BufReader};
-
-        let mut depinfo = FxHashMap::default();
-        let mut orderinfo = FxHashMap::default();
-        let mut funcinfo = FxHashMap::default();
-
+        corpus_path: Option<&str>,
+    ) -> Result<(), FuzzGenError> {
         self.reset_visited();
 
-        //依赖信息
-        {
-            let depinfo_file_path =
-                format!("/home/yxz/workspace/fuzz/experiment_root/{}/depinfo.txt", lib_name);
-            match File::open(depinfo_file_path) {
-                Ok(file) => {
-                    let reader = BufReader::new(file);
-                    for line in reader.lines() {
-                        let parts = line
-                            .unwrap()
-                            .split("  |  ")
-                            .into_iter()
-                            .map(|x| x.to_string())
-                            .collect_vec();
-                        let cnt = parts[1].parse::<usize>().unwrap();
-                        let (func1, func2) = parts[2]
-                            .split("   ")
-                            .into_iter()
-                            .map(|x| x.to_string())
-                            .collect_tuple()
-                            .unwrap();
-
-                        //把 (func2, cnt) 存入 func1对应的后续列表中
-
-                        if depinfo.contains_key(&func1) {
-                            let inner_map: &mut FxHashMap<String, usize> =
-                                depinfo.get_mut(&func1).unwrap();
-                            inner_map
-                                .entry(func2.clone())
-                                .and_modify(|value| *value += cnt)
-                                .or_insert(cnt);
-                        } else {
-                            let mut inner_map = FxHashMap::default();
-                            inner_map.insert(func2.clone(), cnt);
-                            depinfo.insert(func1.clone(), inner_map);
-                        }
-                    }
-                }
-                _ => {}
-            }
-        }
-
-        //解析顺序信息
-        {
-            let orderinfo_file_path =
-                format!("/home/yxz/workspace/fuzz/experiment_root/{}/orderinfo.txt", lib_name);
-            match File::open(orderinfo_file_path) {
-                Ok(file) => {
-                    let reader = BufReader::new(file);
-                    for line in reader.lines() {
-                        let parts = line
-                            .unwrap()
-                            .split("  |  ")
-                            .into_iter()
-                            .map(|x| x.to_string())
-                            .collect_vec();
-                        let cnt = parts[1].parse::<usize>().unwrap();
-                        let (func1, func2) = parts[2]
-                            .split("   ")
-                            .into_iter()
-                            .map(|x| x.to_string())
-                            .collect_tuple()
-                            .unwrap();
-
-                        //把 (func2, cnt) 存入 func1对应的后续列表中
-
-                        if orderinfo.contains_key(&func1) {
-                            let inner_map: &mut FxHashMap<String, usize> =
-                                orderinfo.get_mut(&func1).unwrap();
-                            inner_map
-                                .entry(func2.clone())
-                                .and_modify(|value| *value += cnt)
-                                .or_insert(cnt);
-                        } else {
-                            let mut inner_map = FxHashMap::default();
-                            inner_map.insert(func2.clone(), cnt);
-                            orderinfo.insert(func1.clone(), inner_map);
-                        }
-                    }
-                }
-                _ => {}
-            }
-        }
-
-        //解析函数频率信息（暂时没用）
-        {
-            let funcinfo_file_path =
-                format!("/home/yxz/workspace/fuzz/experiment_root/{}/funcinfo.txt", lib_name);
-            match File::open(funcinfo_file_path) {
-                Ok(file) => {
-                    let reader = BufReader::new(file);
-                    for line in reader.lines() {
-                        let parts = line
-                            .unwrap()
-                            .split("  |  ")
-                            .into_iter()
-                            .map(|x| x.to_string())
-                            .collect_vec();
-                        let cnt = parts[1].parse::<usize>().unwrap();
-                        let func = &parts[2].clone();
-
-                        //把 (func, cnt)存入funcindo中
-
-                        funcinfo
-                            .entry(func.clone())
-                            .and_modify(|value| *value += cnt)
-                            .or_insert(cnt);
-                    }
-                }
-                _ => {}
-            }
-        }
+        //依赖信息、顺序信息、函数频率信息（暂时没用）
+        //语料文件不存在就当作没有信息，格式不对的话忽略整个文件
+        let corpus_path = file_util::corpus_path(
+            corpus_path,
+            format!("{}/{}", file_util::experiment_root(), lib_name),
+        );
+        let corpus_dir = file_util::corpus_info_dir(&corpus_path);
+        let depinfo_file_path = corpus_dir.join("depinfo.txt").display().to_string();
+        let depinfo = read_pair_info(&depinfo_file_path).unwrap_or_else(|e| {
+            error::warn(&e, "ignore dependency info");
+            FxHashMap::default()
+        });
+        let orderinfo_file_path = corpus_dir.join("orderinfo.txt").display().to_string();
+        let orderinfo = read_pair_info(&orderinfo_file_path).unwrap_or_else(|e| {
+            error::warn(&e, "ignore order info");
+            FxHashMap::default()
+        });
+        let funcinfo_file_path = corpus_dir.join("funcinfo.txt").display().to_string();
+        let funcinfo = read_func_info(&funcinfo_file_path).unwrap_or_else(|e| {
+            error::warn(&e, "ignore function frequency info");
+            FxHashMap::default()
+        });
 
         let print = true;
         //打印各种信息
@@ -1037,6 +1408,7 @@ pub(crate) fn my_method(
             }
             println!("");
         }
+        self.function_frequencies = funcinfo;
 
         let mut covered_function = FxHashSet::default();
 
@@ -1093,6 +1465,10 @@ pub(crate) fn my_method(
 
         let _start_functions = extract_start_function(self, support_generic);
         println!("这里有{}个start function", _start_functions.len());
+        //没有start function的话，下面的循环永远生成不出序列
+        if _start_functions.is_empty() {
+            return Err(FuzzGenError::EmptyCandidates("start functions"));
+        }
         for start in &_start_functions {
             println!(
                 "Start function: {}",
@@ -1103,6 +1479,7 @@ pub(crate) fn my_method(
         let mut sequences: Vec<ApiSequence> = Vec::new();
 
         let mut start_index_polling = 0;
+        let mut progress = Progress::new("real world walk", Some(max_num));
 
         //FIXME: 在这里编写逻辑
         loop {
@@ -1152,7 +1529,6 @@ pub(crate) fn my_method(
                     //有1/(len+5)的概率接触到new
                     //获得随机start函数在全局的index
                     //println!("选择start");
-                    assert!(_start_functions.len() > 0);
                     let start_idx = _start_functions[rand_num(0, _start_functions.len())];
                     sequence =
                         match self.is_fun_satisfied(&ApiType::BareFunction, start_idx, &sequence) {
@@ -1260,7 +1636,10 @@ pub(crate) fn my_method(
             }
 
             sequences.push(sequence);
+            progress.inc(1);
+            progress.set_sequences(sequences.len());
         }
+        progress.finish();
 
         for index in covered_function.iter() {
             self.api_functions_visited[*index] = true;
@@ -1268,12 +1647,17 @@ pub(crate) fn my_method(
 
         //最后赋值给graph.api_sequences
         self.api_sequences = sequences;
+        //拼接语料序列，得到语料里没有原样出现过的API组合
+        self._splice_sequences(max_len);
 
+        let covered_api_num =
+            covered_function.iter().filter(|index| !self._is_default_producer(**index)).count();
         println!(
             "覆盖的API数量: {}, API覆盖率: {}",
-            covered_function.len(),
-            (covered_function.len() as f32) / (self.api_functions.len() as f32)
+            covered_api_num,
+            (covered_api_num as f32) / (self._valid_api_number() as f32)
         );
+        return Ok(());
 
         /// 归一化函数
         /// 导致每个差距都在20以内
@@ -1493,6 +1877,7 @@ pub(crate) fn _try_to_cover_unvisited_nodes(&mut self) {
             }
         }
         let mut covered_node_this_iteration = FxHashSet::default();
+        let mut progress = Progress::new("backward search", Some(unvisited_nodes.len()));
         //最多循环没访问到的节点的数量
         for _ in 0..unvisited_nodes.len() {
             covered_node_this_iteration.clear();
@@ -1507,25 +1892,27 @@ pub(crate) fn _try_to_cover_unvisited_nodes(&mut self) {
                 let input_param_num = inputs.len();
                 for i in 0..input_param_num {
                     let input_type = &inputs[i];
-                    if api_util::is_fuzzable_type(input_type, self.cache, &self.full_name_map, None)
-                    {
+                    if api_util::is_fuzzable_type(
+                        input_type,
+                        self.cache,
+                        &self.full_name_map,
+                        &self.emit_options,
+                        None,
+                    ) {
                         continue;
                     }
                     let mut can_find_dependency_flag = false;
                     let mut tmp_dependent_index = -1;
+                    let producers = self
+                        .producers_for_param(*unvisited_node, i)
+                        .into_iter()
+                        .map(|(output_index, _)| output_index)
+                        .collect::<FxHashSet<_>>();
                     for candidate_sequence_index in &candidate_sequences {
-                        let output_type = ApiType::BareFunction;
-                        let input_type = ApiType::BareFunction;
                         let candidate_sequence = &self.api_sequences[*candidate_sequence_index];
                         let output_index = candidate_sequence._last_api_func_index().unwrap();
 
-                        if let Some(_) = self.check_dependency(
-                            &output_type,
-                            output_index,
-                            &input_type,
-                            *unvisited_node,
-                            i,
-                        ) {
+                        if producers.contains(&output_index) {
                             can_find_dependency_flag = true;
                             //dependent_sequence_indexes.push(*candidate_sequence_index);
                             tmp_dependent_index = *candidate_sequence_index as i32;
@@ -1565,6 +1952,8 @@ pub(crate) fn _try_to_cover_unvisited_nodes(&mut self) {
                     }
                 }
             }
+            progress.inc(covered_node_this_iteration.len());
+            progress.set_sequences(self.api_sequences.len());
             if covered_node_this_iteration.len() == 0 {
                 println!("reverse search can not cover more nodes");
                 break;
@@ -1574,6 +1963,7 @@ pub(crate) fn _try_to_cover_unvisited_nodes(&mut self) {
                 }
             }
         }
+        progress.finish();
 
         let mut totol_sequences_number = 0;
         let mut total_length = 0;
@@ -1586,7 +1976,7 @@ pub(crate) fn _try_to_cover_unvisited_nodes(&mut self) {
             }
             totol_sequences_number = totol_sequences_number + 1;
             total_length = total_length + sequence.len();
-            let cover_nodes = sequence._get_contained_api_functions();
+            let cover_nodes = self._api_nodes(sequence);
             for cover_node in &cover_nodes {
                 covered_nodes.insert(*cover_node);
             }
@@ -1611,7 +2001,7 @@ pub(crate) fn _naive_choose_sequence(&self, max_sequence_size: usize) -> Vec<Api
         let mut to_cover_nodes = Vec::new();
         let function_len = self.api_functions.len();
         for i in 0..function_len {
-            if self.api_functions_visited[i] {
+            if self.api_functions_visited[i] && !self._is_default_producer(i) {
                 to_cover_nodes.push(i);
             }
         }
@@ -1633,7 +2023,7 @@ pub(crate) fn _naive_choose_sequence(&self, max_sequence_size: usize) -> Vec<Api
 
         for i in 0..prepared_sequence_number {
             let api_sequence = &self.api_sequences[i];
-            let contains_nodes = api_sequence._get_contained_api_functions();
+            let contains_nodes = self._api_nodes(api_sequence);
             for node in contains_nodes {
                 if let Some(v) = node_candidate_sequences.get_mut(&node) {
                     if !v.contains(&i) {
@@ -1644,19 +2034,28 @@ pub(crate) fn _naive_choose_sequence(&self, max_sequence_size: usize) -> Vec<Api
         }
 
         let mut rng = thread_rng();
-        for _ in 0..max_sequence_size {
+        while res.len() < max_sequence_size {
             if to_cover_nodes.len() == 0 {
                 println!("all {} nodes need to be covered is covered", to_cover_nodes_number);
                 break;
             }
             //println!("need_to_cover_nodes:{:?}", to_cover_nodes);
-            let next_cover_node = to_cover_nodes.first().unwrap();
+            let next_cover_node = to_cover_nodes[0];
             let candidate_sequences =
-                node_candidate_sequences.get(next_cover_node).unwrap().clone();
+                node_candidate_sequences.get(&next_cover_node).cloned().unwrap_or_default();
             let unvisited_candidate_sequences = candidate_sequences
                 .into_iter()
                 .filter(|node| chosen_sequence_flag[*node] == false)
                 .collect::<Vec<_>>();
+            //没有还没选过的序列能覆盖这个节点，跳过这个节点
+            if unvisited_candidate_sequences.is_empty() {
+                error::warn(
+                    &FuzzGenError::EmptyCandidates("sequences covering the node"),
+                    format!("skip {}", self.api_functions[next_cover_node].full_name).as_str(),
+                );
+                to_cover_nodes.remove(0);
+                continue;
+            }
             let candidate_number = unvisited_candidate_sequences.len();
             let random_index = rng.gen_range(0, candidate_number);
             let chosen_index = unvisited_candidate_sequences[random_index];
@@ -1664,10 +2063,10 @@ pub(crate) fn _naive_choose_sequence(&self, max_sequence_size: usize) -> Vec<Api
             let chosen_sequence = &self.api_sequences[chosen_index];
             //println!("{:}",chosen_sequence._to_well_written_function(self, 0, 0));
 
-            let covered_nodes = chosen_sequence._get_contained_api_functions();
+            let covered_nodes = self._api_nodes(chosen_sequence);
             to_cover_nodes =
                 to_cover_nodes.into_iter().filter(|node| !covered_nodes.contains(node)).collect();
-            chosen_sequence_flag[random_index] = true;
+            chosen_sequence_flag[chosen_index] = true;
             res.push(chosen_sequence.clone());
         }
         res
@@ -1699,7 +2098,7 @@ pub(crate) fn _random_choose(&self, max_size: usize) -> Vec<ApiSequence> {
             res.push(sequence.clone());
             sequence_indexes.remove(chosen_index);
 
-            for covered_node in sequence._get_contained_api_functions() {
+            for covered_node in self._api_nodes(sequence) {
                 covered_nodes.insert(covered_node);
             }
 
@@ -1732,10 +2131,13 @@ pub(crate) fn _first_choose(&self, max_size: usize, max_len: usize) -> Vec<ApiSe
             if sequence.len() < max_len {
                 continue;
             }
+            if sequence._violates_global_state_constraint(self) {
+                continue;
+            }
 
             res.push(sequence.clone());
 
-            let covered_nodes = sequence._get_contained_api_functions();
+            let covered_nodes = self._api_nodes(sequence);
             for cover_node in covered_nodes {
                 already_covered_nodes.insert(cover_node);
             }
@@ -1750,12 +2152,7 @@ pub(crate) fn _first_choose(&self, max_size: usize, max_len: usize) -> Vec<ApiSe
             }
         }
 
-        let mut valid_api_number = 0;
-        for api_function_ in &self.api_functions {
-            if !api_function_.contains_unsupported_fuzzable_type(self.cache, &self.full_name_map) {
-                valid_api_number = valid_api_number + 1;
-            }
-        }
+        let valid_api_number = self._valid_api_number();
 
         let binding = already_covered_nodes.clone();
         let mut acn = binding.iter().collect_vec();
@@ -1768,7 +2165,7 @@ pub(crate) fn _first_choose(&self, max_size: usize, max_len: usize) -> Vec<ApiSe
         }
 
         println!("-----------STATISTICS-----------");
-        let total_functions_number = self.api_functions.len();
+        let total_functions_number = self._api_node_number();
         println!("total nodes: {}", total_functions_number);
 
         let total_dependencies_number = self.api_dependencies.len();
@@ -1789,6 +2186,193 @@ pub(crate) fn _first_choose(&self, max_size: usize, max_len: usize) -> Vec<ApiSe
         res
     }
 
+    /// 为每个builder类型生成一个 构造函数 -> setter -> 终结方法 的序列
+    pub(crate) fn _add_builder_sequences(&self, sequences: &mut Vec<ApiSequence>) {
+        for builder_info in builder::find_builders(self) {
+            match builder::builder_sequence(self, &builder_info) {
+                Some(sequence) => {
+                    println!(
+                        "add builder sequence for {} with {} calls",
+                        builder_info.type_name,
+                        sequence.len()
+                    );
+                    sequences.push(sequence);
+                }
+                None => println!("can't generate builder sequence for {}", builder_info.type_name),
+            }
+        }
+    }
+
+    /// 配置了参考实现的API如果没有被选中的序列覆盖，单独为它生成一个序列，
+    /// 保证每个oracle都有对应的差分测试
+    pub(crate) fn _add_oracle_sequences(&self, sequences: &mut Vec<ApiSequence>) {
+        if self.emit_options.oracles.is_empty() {
+            return;
+        }
+        for (api_function_index, api_function) in self.api_functions.iter().enumerate() {
+            if !self.emit_options.oracles.contains_key(&api_function.full_name) {
+                continue;
+            }
+            if sequences.iter().any(|sequence| sequence._contains_api_function(api_function_index))
+            {
+                continue;
+            }
+            match self.is_fun_satisfied(
+                &ApiType::BareFunction,
+                api_function_index,
+                &ApiSequence::new(),
+            ) {
+                Some(sequence) if !sequence._has_no_fuzzables() => {
+                    println!("add oracle sequence for {}", api_function.full_name);
+                    sequences.push(sequence);
+                }
+                _ => println!("can't generate oracle sequence for {}", api_function.full_name),
+            }
+        }
+    }
+
+    /// 节点和单条边的覆盖很快就饱和了，这里贪心地选能覆盖最多新的相邻API对（2-gram）的序列，
+    /// 一样多的时候选覆盖新节点多的，再选短的，更接近真实代码里用法的多样性
+    pub(crate) fn _pair_coverage_choose(&self, max_size: usize) -> Vec<ApiSequence> {
+        let candidates = self._selection_candidates();
+        let (res, coverage) = self._greedy_choose(&candidates, max_size, |candidate, coverage| {
+            let new_pairs = coverage.new_pair_count(candidate);
+            let new_nodes = coverage.new_nodes(candidate).count();
+            if new_pairs == 0 && new_nodes == 0 {
+                return None;
+            }
+            Some((new_pairs, new_nodes, Reverse(candidate.sequence.len())))
+        });
+        let total_pairs = candidates.iter().flat_map(|candidate| &candidate.pairs).unique().count();
+        greedy_selection::print_selection_statistics(
+            "Pair coverage",
+            &res,
+            &coverage,
+            format!("api pairs in candidates = {}", total_pairs),
+        );
+        res
+    }
+
+    /// 把选序列看成带权集合覆盖：要覆盖的元素是节点和边，每个序列的代价是它的长度，
+    /// 用标准的贪心算法，每次选 新覆盖的元素数/代价 最大的序列，
+    /// 一样的时候选新覆盖元素多的，再选短的，再选生成得早的
+    pub(crate) fn _set_cover_choose(&self, max_size: usize) -> Vec<ApiSequence> {
+        let candidates = self._selection_candidates();
+        let (res, coverage) = self._greedy_choose(&candidates, max_size, |candidate, coverage| {
+            let elements =
+                coverage.new_nodes(candidate).count() + coverage.new_edge_count(candidate);
+            if elements == 0 {
+                return None;
+            }
+            Some(CostRatio { elements, cost: candidate.sequence.len().max(1) })
+        });
+        let total_cost: usize = res.iter().map(|sequence| sequence.len().max(1)).sum();
+        greedy_selection::print_selection_statistics(
+            "Set cover",
+            &res,
+            &coverage,
+            format!("total cost (length) = {}", total_cost),
+        );
+        res
+    }
+
+    /// API的复杂度分数，没有MIR的（合成的函数、从json读进来的函数）算1，这样退化成数节点
+    pub(crate) fn _complexity_score(&self, index: usize) -> usize {
+        self.api_functions[index].complexity.map_or(1, |complexity| complexity.score())
+    }
+
+    /// 和_heuristic_choose一样贪心地选，但是按新覆盖的API的复杂度分数之和而不是个数比较，
+    /// 这样简单的getter不会占用fuzz的时间；一样的时候选新覆盖边多的，再选短的，再选生成得早的
+    pub(crate) fn _complexity_choose(&self, max_size: usize) -> Vec<ApiSequence> {
+        let candidates = self._selection_candidates();
+        let (res, coverage) = self._greedy_choose(&candidates, max_size, |candidate, coverage| {
+            let new_complexity: usize =
+                coverage.new_nodes(candidate).map(|node| self._complexity_score(node)).sum();
+            let new_edges = coverage.new_edge_count(candidate);
+            if new_complexity == 0 && new_edges == 0 {
+                return None;
+            }
+            Some((new_complexity, new_edges, Reverse(candidate.sequence.len())))
+        });
+        let total_complexity: usize =
+            coverage.nodes.iter().map(|node| self._complexity_score(*node)).sum();
+        greedy_selection::print_selection_statistics(
+            "Complexity",
+            &res,
+            &coverage,
+            format!("covered total complexity = {}", total_complexity),
+        );
+        res
+    }
+
+    /// 序列里每个API在语料里出现次数的frequency_exponent次方之和，
+    /// 指数越大越偏向最常用的几个API，没有语料或者指数是0的时候都是0
+    pub(crate) fn _frequency_weight(&self, sequence: &ApiSequence) -> f64 {
+        if self.frequency_exponent == 0.0 {
+            return 0.0;
+        }
+        sequence
+            ._get_contained_api_functions()
+            .iter()
+            .filter_map(|index| {
+                self.function_frequencies.get(&self.api_functions[*index].full_name)
+            })
+            .map(|frequency| (*frequency as f64).powf(self.frequency_exponent))
+            .sum()
+    }
+
+    /// 是否是合成的`<T as Default>::default`生产者
+    pub(crate) fn _is_default_producer(&self, index: usize) -> bool {
+        self.default_producers.contains(&self.api_functions[index].full_name)
+    }
+
+    /// 序列里调用的API，不算合成的Default生产者，统计节点覆盖和选择序列时用
+    pub(crate) fn _api_nodes(&self, sequence: &ApiSequence) -> Vec<usize> {
+        sequence
+            ._get_contained_api_functions()
+            .into_iter()
+            .filter(|index| !self._is_default_producer(*index))
+            .collect()
+    }
+
+    /// API图里crate自己的API数量
+    pub(crate) fn _api_node_number(&self) -> usize {
+        (0..self.api_functions.len()).filter(|index| !self._is_default_producer(*index)).count()
+    }
+
+    /// 参数类型都能支持的API数量，节点覆盖率的分母
+    pub(crate) fn _valid_api_number(&self) -> usize {
+        (0..self.api_functions.len())
+            .filter(|index| {
+                !self._is_default_producer(*index)
+                    && !self.api_functions[*index].contains_unsupported_fuzzable_type(
+                        self.cache,
+                        &self.full_name_map,
+                        &self.emit_options,
+                    )
+            })
+            .count()
+    }
+
+    /// 覆盖一个API算几个节点，开启prioritize_unsafe时内部用到unsafe的API算UNSAFE_NODE_WEIGHT个
+    pub(crate) fn _node_weight(&self, index: usize) -> usize {
+        if self.prioritize_unsafe && self.api_functions[index]._uses_unsafe {
+            UNSAFE_NODE_WEIGHT
+        } else {
+            1
+        }
+    }
+
+    /// 内部用到unsafe的API的下标，没有开启prioritize_unsafe时为空
+    pub(crate) fn _unsafe_functions(&self) -> Vec<usize> {
+        if !self.prioritize_unsafe {
+            return Vec::new();
+        }
+        (0..self.api_functions.len())
+            .filter(|index| self.api_functions[*index]._uses_unsafe)
+            .collect()
+    }
+
     pub(crate) fn _heuristic_choose(
         &self,
         max_size: usize,
@@ -1810,7 +2394,7 @@ pub(crate) fn _heuristic_choose(
             if !fixed_sequence._has_no_fuzzables()
                 && !fixed_sequence._contains_dead_code_except_last_one(self)
             {
-                let covered_nodes = fixed_sequence._get_contained_api_functions();
+                let covered_nodes = self._api_nodes(fixed_sequence);
                 for covered_node in &covered_nodes {
                     fixed_covered_nodes.insert(*covered_node);
                 }
@@ -1830,7 +2414,9 @@ pub(crate) fn _heuristic_choose(
         //println!("There are toatl {} sequences.", total_sequence_number);
         let mut valid_fuzz_sequence_count = 0;
         for sequence in &self.api_sequences {
-            if !sequence._has_no_fuzzables() && !sequence._contains_dead_code_except_last_one(self)
+            if !sequence._has_no_fuzzables()
+                && !sequence._contains_dead_code_except_last_one(self)
+                && !sequence._violates_global_state_constraint(self)
             {
                 valid_fuzz_sequence_count = valid_fuzz_sequence_count + 1;
             }
@@ -1848,11 +2434,14 @@ pub(crate) fn _heuristic_choose(
         let mut fixed_fuzzale_length_sequences_count = 0;
 
         let mut try_to_find_dynamic_length_flag = true;
+        let mut progress = Progress::new("select sequences", Some(max_size + 1));
         for _ in 0..max_size + 1 {
+            progress.inc(1);
             let mut current_chosen_sequence_index = 0;
             let mut current_max_covered_nodes = 0;
             let mut current_max_covered_edges = 0;
             let mut current_chosen_sequence_len = 0;
+            let mut current_max_frequency_weight = 0.0;
 
             for j in 0..total_sequence_number {
                 if already_chosen_sequences.contains(&j) {
@@ -1862,6 +2451,7 @@ pub(crate) fn _heuristic_choose(
 
                 if api_sequence._has_no_fuzzables()
                     || api_sequence._contains_dead_code_except_last_one(self)
+                    || api_sequence._violates_global_state_constraint(self)
                 {
                     continue;
                 }
@@ -1876,12 +2466,14 @@ pub(crate) fn _heuristic_choose(
                     continue;
                 }
 
-                let covered_nodes = api_sequence._get_contained_api_functions();
+                let covered_nodes = self._api_nodes(api_sequence);
+                //开启prioritize_unsafe时，内部用到unsafe的API按_node_weight多算几个节点
                 let mut uncovered_nodes_by_former_sequence_count = 0;
                 for covered_node in &covered_nodes {
                     if !already_covered_nodes.contains(covered_node) {
                         uncovered_nodes_by_former_sequence_count =
-                            uncovered_nodes_by_former_sequence_count + 1;
+                            uncovered_nodes_by_former_sequence_count
+                                + self._node_weight(*covered_node);
                     }
                 }
 
@@ -1902,17 +2494,24 @@ pub(crate) fn _heuristic_choose(
                     continue;
                 }
                 let sequence_len = api_sequence.len();
+                //覆盖的节点和边一样多时，优先选语料里常用的API，再选短的
+                let frequency_weight = self._frequency_weight(api_sequence);
+                let same_coverage = uncovered_nodes_by_former_sequence_count
+                    == current_max_covered_nodes
+                    && uncovered_edges_by_former_sequence_count == current_max_covered_edges;
                 if (uncovered_nodes_by_former_sequence_count > current_max_covered_nodes)
                     || (uncovered_nodes_by_former_sequence_count == current_max_covered_nodes
                         && uncovered_edges_by_former_sequence_count > current_max_covered_edges)
-                    || (uncovered_nodes_by_former_sequence_count == current_max_covered_nodes
-                        && uncovered_edges_by_former_sequence_count == current_max_covered_edges
+                    || (same_coverage && frequency_weight > current_max_frequency_weight)
+                    || (same_coverage
+                        && frequency_weight == current_max_frequency_weight
                         && sequence_len < current_chosen_sequence_len)
                 {
                     current_chosen_sequence_index = j;
                     current_max_covered_nodes = uncovered_nodes_by_former_sequence_count;
                     current_max_covered_edges = uncovered_edges_by_former_sequence_count;
                     current_chosen_sequence_len = sequence_len;
+                    current_max_frequency_weight = frequency_weight;
                 }
             }
 
@@ -1931,6 +2530,7 @@ pub(crate) fn _heuristic_choose(
             }
             already_chosen_sequences.insert(current_chosen_sequence_index);
             sorted_chosen_sequences.push(current_chosen_sequence_index);
+            progress.set_sequences(sorted_chosen_sequences.len());
 
             if try_to_find_dynamic_length_flag {
                 dynamic_fuzzable_length_sequences_count =
@@ -1941,7 +2541,7 @@ pub(crate) fn _heuristic_choose(
 
             let chosen_sequence = &self.api_sequences[current_chosen_sequence_index];
 
-            let covered_nodes = chosen_sequence._get_contained_api_functions();
+            let covered_nodes = self._api_nodes(chosen_sequence);
             for cover_node in covered_nodes {
                 already_covered_nodes.insert(cover_node);
             }
@@ -1968,20 +2568,13 @@ pub(crate) fn _heuristic_choose(
             }
             //println!("no fuzzable count = {}", no_fuzzable_count);
         }
+        progress.finish();
 
-        let total_functions_number = self.api_functions.len();
+        let total_functions_number = self._api_node_number();
         println!("-----------STATISTICS-----------");
         println!("total nodes: {}", total_functions_number);
 
-        let mut valid_api_number = 0;
-        for api_function_ in &self.api_functions {
-            if !api_function_.contains_unsupported_fuzzable_type(self.cache, &self.full_name_map) {
-                valid_api_number = valid_api_number + 1;
-            }
-            //else {
-            //    println!("{}", api_function_._pretty_print(&self.full_name_map));
-            //}
-        }
+        let valid_api_number = self._valid_api_number();
         //println!("total valid nodes: {}", valid_api_number);
 
         let total_dependencies_number = self.api_dependencies.len();
@@ -2059,6 +2652,22 @@ pub(crate) fn is_fun_satisfied(
                 //函数
                 let input_function = &self.api_functions[input_fun_index];
 
+                //不会返回的函数后面的语句都执行不到
+                if sequence
+                    .functions
+                    .iter()
+                    .any(|api_call| self.api_functions[api_call.func.1]._is_diverging())
+                {
+                    return None;
+                }
+
+                //修改全局状态的函数互斥：序列里已经有这类函数的话，就不能再加入
+                if input_function._is_global_state_function(&self.global_states)
+                    && sequence._global_state_call_count(self) > 0
+                {
+                    return None;
+                }
+
                 //如果是个unsafe函数，给sequence添加unsafe标记
                 if input_function._unsafe_tag._is_unsafe() {
                     new_sequence.set_unsafe();
@@ -2073,6 +2682,9 @@ pub(crate) fn is_fun_satisfied(
                 let input_params = &input_function.inputs;
                 if input_params.is_empty() {
                     //无需输入参数，直接是可满足的
+                    if numeric_cast::leaves_pending_producer(self, sequence, &api_call) {
+                        return None;
+                    }
                     new_sequence._add_fn(api_call);
                     return Some(new_sequence);
                 }
@@ -2089,10 +2701,25 @@ pub(crate) fn is_fun_satisfied(
                         None => current_ty.clone(),
                     };
 
+                    //整数参数优先用序列里还没被用到的整数返回值，见numeric_cast.rs
+                    if let Some((function_index, dependency_index)) = numeric_cast::pending_producer(
+                        self,
+                        &new_sequence,
+                        &api_call,
+                        input_fun_index,
+                        i,
+                    ) {
+                        let call_type = self.api_dependencies[dependency_index].call_type.clone();
+                        new_sequence._add_dependency(dependency_index);
+                        api_call._add_param(ParamType::_FunctionReturn, function_index, call_type);
+                        continue;
+                    }
+
                     if api_util::is_fuzzable_type(
                         current_ty,
                         self.cache,
                         &self.full_name_map,
+                        &self.emit_options,
                         Some(&input_function.generic_substitutions),
                     ) {
                         /*
@@ -2106,6 +2733,7 @@ pub(crate) fn is_fun_satisfied(
                             current_ty,
                             self.cache,
                             &self.full_name_map,
+                            &self.emit_options,
                             Some(&input_function.generic_substitutions),
                         );
                         let (fuzzable_type, call_type) =
@@ -2124,8 +2752,10 @@ pub(crate) fn is_fun_satisfied(
                             _ => {}
                         }
 
-                        //判断要不要加mut tag
-                        if api_util::_need_mut_tag(&call_type) {
+                        //判断要不要加mut tag，裸指针用的是复制出来的缓冲区，参数本身不需要mut
+                        if api_util::_need_mut_tag(&call_type)
+                            && !matches!(fuzzable_type, FuzzableType::RefSlice(..))
+                        {
                             new_sequence._insert_fuzzable_mut_tag(current_fuzzable_index);
                         }
 
@@ -2358,13 +2988,19 @@ pub(crate) fn is_fun_satisfied(
                 }
 
                 //所有参数都可以找到依赖，那么这个函数就可以加入序列
+                if numeric_cast::leaves_pending_producer(self, sequence, &api_call) {
+                    return None;
+                }
+                if maybe_uninit::reads_uninitialized_slot(self, sequence, &api_call) {
+                    return None;
+                }
                 new_sequence._add_fn(api_call);
                 new_sequence._moved = _moved_indexes;
                 //new_sequence._mut_borrow = global_mut_borrow;
                 //new_sequence._borrow = global_borrow;
 
-                if new_sequence._contains_multi_dynamic_length_fuzzable() {
-                    //如果新生成的序列包含多维可变的参数，就不把这个序列加进去
+                if new_sequence._contains_too_deep_dynamic_length_fuzzable() {
+                    //如果新生成的序列包含没法解码的多维可变参数，就不把这个序列加进去
                     return None;
                 }
                 return Some(new_sequence);
@@ -2412,7 +3048,7 @@ pub(crate) fn is_fun_satisfied(
                 }
                 //对于每个参数进行遍历
                 for (i, current_ty) in input_params.iter().enumerate() {
-                    if api_util::is_fuzzable_type(current_ty, self.cache, &self.full_name_map, None)
+                    if api_util::is_fuzzable_type(current_ty, self.cache, &self.full_name_map, &self.emit_options, None)
                     {
                         //如果当前参数是fuzzable的
                         let current_fuzzable_index = new_sequence.fuzzable_params.len();
@@ -2420,6 +3056,7 @@ pub(crate) fn is_fun_satisfied(
                             current_ty,
                             self.cache,
                             &self.full_name_map,
+                            &self.emit_options,
                             None,
                         );
                         let (fuzzable_type, call_type) =
@@ -2438,8 +3075,10 @@ pub(crate) fn is_fun_satisfied(
                             _ => {}
                         }
 
-                        //判断要不要加mut tag
-                        if api_util::_need_mut_tag(&call_type) {
+                        //判断要不要加mut tag，裸指针用的是复制出来的缓冲区，参数本身不需要mut
+                        if api_util::_need_mut_tag(&call_type)
+                            && !matches!(fuzzable_type, FuzzableType::RefSlice(..))
+                        {
                             new_sequence._insert_fuzzable_mut_tag(current_fuzzable_index);
                         }
 
@@ -2547,8 +3186,8 @@ pub(crate) fn is_fun_satisfied(
                 for move_index in _moved_indexes {
                     new_sequence._insert_move_index(move_index);
                 }
-                if new_sequence._contains_multi_dynamic_length_fuzzable() {
-                    //如果新生成的序列包含多维可变的参数，就不把这个序列加进去
+                if new_sequence._contains_too_deep_dynamic_length_fuzzable() {
+                    //如果新生成的序列包含没法解码的多维可变参数，就不把这个序列加进去
                     return None;
                 }
                 return Some(new_sequence);
@@ -2585,7 +3224,6 @@ pub(crate) fn _reverse_construct(
                 if print {
                     println!("name: {}", input_fun.full_name);
                 }
-                sleep(Duration::from_millis(20));
 
                 //对于当前函数的param，有依赖
                 let mut param_reverse_sequences = Vec::new();
@@ -2595,14 +3233,20 @@ pub(crate) fn _reverse_construct(
                 for (input_param_index_, current_ty) in params.iter().enumerate() {
                     /*********************************************************************************************************/
                     //如果当前参数是可fuzz的
-                    if api_util::is_fuzzable_type(current_ty, self.cache, &self.full_name_map, None)
-                    {
+                    if api_util::is_fuzzable_type(
+                        current_ty,
+                        self.cache,
+                        &self.full_name_map,
+                        &self.emit_options,
+                        None,
+                    ) {
                         //如果当前参数是fuzzable的
                         let current_fuzzable_index = new_reverse_sequence.fuzzable_params.len();
                         let fuzzable_call_type = fuzz_type::fuzzable_call_type(
                             current_ty,
                             self.cache,
                             &self.full_name_map,
+                            &self.emit_options,
                             None,
                         );
                         let (fuzzable_type, call_type) =
@@ -2634,75 +3278,66 @@ pub(crate) fn _reverse_construct(
                     //如果当前参数不可由afl提供，只能去找依赖
                     else {
                         let mut dependency_flag = false;
-                        //遍历函数，看看哪个函数的output可以作为当前的param
-                        for (output_fun_index, _output_fun) in self.api_functions.iter().enumerate()
+                        //看看哪个函数的output可以作为当前的param
+                        //output_fun -> struct -> input_fun
+                        for (output_fun_index, dependency_index) in
+                            self.producers_for_param(input_fun_index, input_param_index_)
                         {
                             //防止死循环
-                            if output_fun_index == input_fun_index {
+                            if output_fun_index >= input_fun_index {
                                 break;
                             }
 
-                            //检查前后是否有依赖关系
-                            //output_fun -> struct -> input_fun
-                            if let Some(dependency_index) = self.check_dependency(
+                            let param_seq = match self._reverse_construct(
                                 &ApiType::BareFunction,
                                 output_fun_index,
-                                &api_call.func.0,
-                                input_fun_index,
-                                input_param_index_,
+                                false,
                             ) {
-                                let param_seq = match self._reverse_construct(
-                                    &ApiType::BareFunction,
-                                    output_fun_index,
-                                    false,
-                                ) {
-                                    Some(seq) => seq,
-                                    None => {
-                                        //没找到通路，那就看其他的api
-                                        continue;
-                                    }
-                                };
+                                Some(seq) => seq,
+                                None => {
+                                    //没找到通路，那就看其他的api
+                                    continue;
+                                }
+                            };
 
-                                //下面是找到了通路
-                                param_reverse_sequences.push(param_seq.clone());
+                            //下面是找到了通路
+                            param_reverse_sequences.push(param_seq.clone());
 
-                                //根据dependency_index找到对应的dependency
-                                let dependency_ = self.api_dependencies[dependency_index].clone();
+                            //根据dependency_index找到对应的dependency
+                            let dependency_ = self.api_dependencies[dependency_index].clone();
 
-                                //将覆盖到的边加入到新的sequence中去
-                                //好像没啥用
-                                new_reverse_sequence._add_dependency(dependency_index);
+                            //将覆盖到的边加入到新的sequence中去
+                            //好像没啥用
+                            new_reverse_sequence._add_dependency(dependency_index);
 
-                                //找到了依赖，当前参数是可以被满足的，设置flag并退出循环
-                                dependency_flag = true;
+                            //找到了依赖，当前参数是可以被满足的，设置flag并退出循环
+                            dependency_flag = true;
 
-                                //参数需要加mut 标记的话
-                                if api_util::_need_mut_tag(&dependency_.call_type) {
-                                    new_reverse_sequence
-                                        ._insert_function_mut_tag(current_param_index);
-                                }
-                                //如果call type是unsafe的，那么给sequence加上unsafe标记
-                                if dependency_.call_type.unsafe_call_type()._is_unsafe() {
-                                    new_reverse_sequence._set_unsafe();
-                                }
+                            //参数需要加mut 标记的话
+                            if api_util::_need_mut_tag(&dependency_.call_type) {
+                                new_reverse_sequence._insert_function_mut_tag(current_param_index);
+                            }
+                            //如果call type是unsafe的，那么给sequence加上unsafe标记
+                            if dependency_.call_type.unsafe_call_type()._is_unsafe() {
+                                new_reverse_sequence._set_unsafe();
+                            }
 
-                                //为api_call添加依赖
-                                api_call._add_param(
-                                    ParamType::_FunctionReturn,
-                                    current_param_index,
-                                    dependency_.call_type,
+                            //为api_call添加依赖
+                            api_call._add_param(
+                                ParamType::_FunctionReturn,
+                                current_param_index,
+                                dependency_.call_type,
+                            );
+                            current_param_index += param_seq.functions.len();
+
+                            if print {
+                                println!(
+                                    "找到了依赖，{}的返回值给{}",
+                                    self.api_functions[output_fun_index].full_name,
+                                    self.api_functions[input_fun_index].full_name
                                 );
-                                current_param_index += param_seq.functions.len();
-
-                                if print {
-                                    println!(
-                                        "找到了依赖，{}的返回值给{}",
-                                        self.api_functions[output_fun_index].full_name,
-                                        self.api_functions[input_fun_index].full_name
-                                    );
-                                }
-                                break;
                             }
+                            break;
                         }
                         //如果所有函数都无法作为当前函数的前驱。。。
                         if !dependency_flag {
@@ -2742,27 +3377,21 @@ pub(crate) fn check_dependency(
         input_index: usize,
         input_param_index_: usize,
     ) -> Option<usize> {
-        let dependency_num = self.api_dependencies.len();
-        for index in 0..dependency_num {
-            let dependency = &self.api_dependencies[index];
-            //FIXME: 直接比较每一项内容是否可以节省点时间？
-            let tmp_dependency = ApiDependency {
-                output_fun: (*output_type, output_index),
-                input_fun: (*input_type, input_index),
-                input_param_index: input_param_index_,
-                call_type: dependency.call_type.clone(),
-            };
-            if tmp_dependency == *dependency {
-                //存在依赖
-                return Some(index);
-            }
-        }
-        //没找到依赖
-        return None;
+        //先用下标找到候选的依赖，再比较ApiType，返回下标最小的那个
+        let candidates =
+            self.dependency_index.get(&(output_index, input_index, input_param_index_))?;
+        candidates.iter().copied().find(|index| {
+            let dependency = &self.api_dependencies[*index];
+            dependency.output_fun.0 == *output_type && dependency.input_fun.0 == *input_type
+        })
     }
 
     //判断一个调用序列是否已经到达终止端点
-    fn is_sequence_ended(&self, api_sequence: &ApiSequence, support_generic: bool) -> bool {
+    pub(crate) fn is_sequence_ended(
+        &self,
+        api_sequence: &ApiSequence,
+        support_generic: bool,
+    ) -> bool {
         let functions = &api_sequence.functions;
         let last_fun = functions.last();
         match last_fun {
@@ -2771,6 +3400,10 @@ fn is_sequence_ended(&self, api_sequence: &ApiSequence, support_generic: bool) -
                 let (api_type, index) = &api_call.func;
                 match api_type {
                     ApiType::BareFunction => {
+                        //返回整数的函数后面还可以接用上这个返回值的调用
+                        if numeric_cast::ends_with_producer(self, api_sequence) {
+                            return false;
+                        }
                         let last_func = &self.api_functions[*index];
                         if last_func._is_end_function(
                             self.cache,
@@ -2788,3 +3421,63 @@ fn is_sequence_ended(&self, api_sequence: &ApiSequence, support_generic: bool) -
         }
     }
 }
+
+/// 读取语料中的依赖信息或者顺序信息，每行的格式为`序号  |  次数  |  func1   func2`，
+/// 把(func2, 次数)存入func1对应的后继表中。文件不存在时返回空表
+fn read_pair_info(
+    file_path: &str,
+) -> Result<FxHashMap<String, FxHashMap<String, usize>>, FuzzGenError> {
+    let mut pair_info: FxHashMap<String, FxHashMap<String, usize>> = FxHashMap::default();
+    for (line_index, parts) in read_corpus_info_lines(file_path)? {
+        let malformed = || FuzzGenError::MalformedLine {
+            path: file_path.to_string(),
+            line: line_index + 1,
+            content: parts.join("  |  "),
+        };
+        let cnt = parts.get(1).and_then(|cnt| cnt.parse::<usize>().ok()).ok_or_else(malformed)?;
+        let (func1, func2) = parts
+            .get(2)
+            .and_then(|pair| pair.split("   ").map(|x| x.to_string()).collect_tuple())
+            .ok_or_else(malformed)?;
+        pair_info
+            .entry(func1)
+            .or_default()
+            .entry(func2)
+            .and_modify(|value| *value += cnt)
+            .or_insert(cnt);
+    }
+    Ok(pair_info)
+}
+
+/// 读取语料中的函数频率信息，每行的格式为`序号  |  次数  |  func`。文件不存在时返回空表
+fn read_func_info(file_path: &str) -> Result<FxHashMap<String, usize>, FuzzGenError> {
+    let mut func_info = FxHashMap::default();
+    for (line_index, parts) in read_corpus_info_lines(file_path)? {
+        let malformed = || FuzzGenError::MalformedLine {
+            path: file_path.to_string(),
+            line: line_index + 1,
+            content: parts.join("  |  "),
+        };
+        let cnt = parts.get(1).and_then(|cnt| cnt.parse::<usize>().ok()).ok_or_else(malformed)?;
+        let func = parts.get(2).ok_or_else(malformed)?;
+        func_info.entry(func.clone()).and_modify(|value| *value += cnt).or_insert(cnt);
+    }
+    Ok(func_info)
+}
+
+/// 按`  |  `切分语料文件的每一行，文件不存在时没有任何行
+fn read_corpus_info_lines(file_path: &str) -> Result<Vec<(usize, Vec<String>)>, FuzzGenError> {
+    use std::fs::File;
+    use std::io::{BufRead, BufReader};
+
+    let file = match File::open(file_path) {
+        Ok(file) => file,
+        Err(_) => return Ok(Vec::new()),
+    };
+    let mut lines = Vec::new();
+    for (line_index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(|error| FuzzGenError::Io { path: file_path.to_string(), error })?;
+        lines.push((line_index, line.split("  |  ").map(|x| x.to_string()).collect_vec()));
+    }
+    Ok(lines)
+}
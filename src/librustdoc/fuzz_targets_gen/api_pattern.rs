@@ -0,0 +1,48 @@
+//! --fuzz-include/--fuzz-exclude以及fries.toml里include/exclude的glob模式
+//! 模式和函数的全路径整体匹配，`*`匹配任意多个字符（包括`::`），`?`匹配一个字符，比如：
+//!     regex::bytes::*        只生成regex::bytes模块里的API
+//!     *::exit                去掉所有叫exit的函数
+//! 只设置了exclude的时候保留所有不匹配的函数；设置了include的时候只保留匹配的，再去掉匹配exclude的
+
+use regex::Regex;
+
+/// 编译好的一组glob模式
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ApiPatterns {
+    include: Vec<Regex>,
+    exclude: Vec<Regex>,
+}
+
+/// glob模式转换成整体匹配的正则表达式
+fn glob_to_regex(pattern: &str) -> Regex {
+    let mut res = String::from("^");
+    for c in pattern.trim().chars() {
+        match c {
+            '*' => res.push_str(".*"),
+            '?' => res.push('.'),
+            _ => res.push_str(regex::escape(c.to_string().as_str()).as_str()),
+        }
+    }
+    res.push('$');
+    //除了*和?，其他字符都转义过了，不会编译失败
+    Regex::new(res.as_str()).unwrap()
+}
+
+impl ApiPatterns {
+    pub(crate) fn new(include: &[String], exclude: &[String]) -> Self {
+        ApiPatterns {
+            include: include.iter().map(|pattern| glob_to_regex(pattern)).collect(),
+            exclude: exclude.iter().map(|pattern| glob_to_regex(pattern)).collect(),
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    /// 全路径为full_name的函数是否保留
+    pub(crate) fn allows(&self, full_name: &str) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|regex| regex.is_match(full_name)))
+            && !self.exclude.iter().any(|regex| regex.is_match(full_name))
+    }
+}
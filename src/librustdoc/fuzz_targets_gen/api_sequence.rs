@@ -1,24 +1,36 @@
+use crate::clean;
 use crate::fuzz_targets_gen::afl_util::{self, _AflHelpers};
 use crate::fuzz_targets_gen::api_graph::{ApiGraph, ApiType};
 use crate::fuzz_targets_gen::api_util;
+use crate::fuzz_targets_gen::async_runtime::await_if_async;
 use crate::fuzz_targets_gen::call_type::CallType;
+use crate::fuzz_targets_gen::encoding::{self, EncodingVariant};
+use crate::fuzz_targets_gen::ffi;
+use crate::fuzz_targets_gen::foreign_trait;
 use crate::fuzz_targets_gen::fuzz_type::FuzzableType;
+use crate::fuzz_targets_gen::invariant;
+use crate::fuzz_targets_gen::iterator_law;
+use crate::fuzz_targets_gen::opaque_type;
+use crate::fuzz_targets_gen::panic_filter;
 use crate::fuzz_targets_gen::prelude_type;
 use crate::fuzz_targets_gen::replay_util;
+use crate::fuzz_targets_gen::size_cap;
+use crate::fuzz_targets_gen::watchdog;
 use rustc_data_structures::fx::{FxHashMap, FxHashSet};
+use serde::{Deserialize, Serialize};
 
 use super::prelude_type::PreludeType;
 
 /// ApiCall里面的参数类型，可能是
 /// 1. 其他API的返回值
 /// 2. fuzzable类型
-#[derive(Clone, Debug, Hash, Eq, PartialEq)]
+#[derive(Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize)]
 pub(crate) enum ParamType {
     _FunctionReturn,
     _FuzzableType,
 }
 /// ApiSequence中的每个API对应一个ApiCall
-#[derive(Clone, Debug, Hash, Eq, PartialEq)]
+#[derive(Clone, Debug, Hash, Eq, PartialEq, Serialize, Deserialize)]
 pub(crate) struct ApiCall {
     //要调用的函数类型，以及在对应数组中的位置（第一个参数可以忽略，因为都是barefunction）
     pub(crate) func: (ApiType, usize),
@@ -225,7 +237,7 @@ pub(crate) fn _is_ok(&self, graph: &ApiGraph<'_>) -> bool {
 }
 
 //function call sequences
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub(crate) struct ApiSequence {
     //FIXME: 如何表示函数调用序列？
     pub(crate) functions: Vec<ApiCall>,             //函数调用序列
@@ -240,6 +252,8 @@ pub(crate) struct ApiSequence {
     pub(crate) _covered_dependencies: FxHashSet<usize>, //表示用到了哪些dependency,即边覆盖率
 
     pub(crate) careful_pairs: FxHashMap<usize, Vec<usize>>,
+    //由fuzz输入决定是否调用的语句（builder的setter），调用下标 -> 对应的bool参数的下标
+    pub(crate) _fuzz_selected_calls: FxHashMap<usize, usize>,
 }
 
 impl ApiSequence {
@@ -255,6 +269,7 @@ pub(crate) fn new() -> Self {
         let _function_mut_tag = FxHashSet::default();
         let _covered_dependencies = FxHashSet::default();
         let careful_pairs = FxHashMap::default();
+        let _fuzz_selected_calls = FxHashMap::default();
         ApiSequence {
             functions,
             fuzzable_params,
@@ -267,6 +282,7 @@ pub(crate) fn new() -> Self {
             _function_mut_tag,
             _covered_dependencies,
             careful_pairs,
+            _fuzz_selected_calls,
         }
     }
 
@@ -354,6 +370,11 @@ pub(crate) fn _merge_another_sequence(&self, other: &ApiSequence) -> Self {
         for function_mut_tag in other_sequence._function_mut_tag {
             res._function_mut_tag.insert(function_mut_tag + first_func_number);
         }
+        //fuzz selected calls
+        for (call_index, selector_index) in other_sequence._fuzz_selected_calls {
+            res._fuzz_selected_calls
+                .insert(call_index + first_func_number, selector_index + first_fuzzable_number);
+        }
         res
     }
 
@@ -391,6 +412,51 @@ pub(crate) fn _get_contained_api_functions(&self) -> Vec<usize> {
         }
         res
     }
+    /// 相邻两个调用的(前一个函数, 后一个函数)，也就是序列里的API 2-gram，去重，按出现的顺序
+    pub(crate) fn _api_pairs(&self) -> Vec<(usize, usize)> {
+        let mut res = Vec::new();
+        for window in self.functions.windows(2) {
+            let pair = (window[0].func.1, window[1].func.1);
+            if !res.contains(&pair) {
+                res.push(pair);
+            }
+        }
+        res
+    }
+
+    //序列里修改全局状态的函数调用次数
+    pub(crate) fn _global_state_call_count(&self, _api_graph: &ApiGraph<'_>) -> usize {
+        let mut count = 0;
+        for api_call in &self.functions {
+            let (_, func_index) = &api_call.func;
+            if _api_graph.api_functions[*func_index]
+                ._is_global_state_function(&_api_graph.global_states)
+            {
+                count = count + 1;
+            }
+        }
+        count
+    }
+
+    //序列里是否调用了async fn，是的话测试函数的函数体要放到运行时里执行，见async_runtime.rs
+    pub(crate) fn _contains_async_call(&self, _api_graph: &ApiGraph<'_>) -> bool {
+        self.functions
+            .iter()
+            .any(|api_call| _api_graph.api_functions[api_call.func.1]._asyncness._is_async())
+    }
+
+    //修改全局状态的函数互斥，一个序列里最多出现一次
+    pub(crate) fn _violates_global_state_constraint(&self, _api_graph: &ApiGraph<'_>) -> bool {
+        self._global_state_call_count(_api_graph) > 1
+    }
+
+    /// 让第call_index个语句由fuzz输入决定是否调用，为它加一个bool类型的fuzzable参数
+    pub(crate) fn _make_call_fuzz_selected(&mut self, call_index: usize) {
+        let selector_index = self.fuzzable_params.len();
+        self.fuzzable_params.push(FuzzableType::Primitive(clean::PrimitiveType::Bool));
+        self._fuzz_selected_calls.insert(call_index, selector_index);
+    }
+
     //判断序列里的index函数返回值是否被move
     pub(crate) fn _is_moved(&self, index: usize) -> bool {
         if self._moved.contains(&index) { true } else { false }
@@ -445,9 +511,9 @@ pub(crate) fn _fuzzables_min_length(&self) -> usize {
         total_length
     }
 
-    pub(crate) fn _contains_multi_dynamic_length_fuzzable(&self) -> bool {
+    pub(crate) fn _contains_too_deep_dynamic_length_fuzzable(&self) -> bool {
         for fuzzable_param in &self.fuzzable_params {
-            if fuzzable_param._is_multiple_dynamic_length() {
+            if fuzzable_param._is_too_deep_dynamic_length() {
                 return true;
             }
         }
@@ -543,38 +609,107 @@ pub(crate) fn _to_replay_crash_file(
         _api_graph: &ApiGraph<'_>,
         test_index: usize,
     ) -> String {
-        let mut res = self._to_afl_except_main(_api_graph, test_index);
+        self._to_replay_crash_file_with_encoding(_api_graph, test_index, EncodingVariant::RawSplit)
+    }
+
+    pub(crate) fn _to_afl_test_file(&self, _api_graph: &ApiGraph<'_>, test_index: usize) -> String {
+        self._to_afl_test_file_with_encoding(_api_graph, test_index, EncodingVariant::RawSplit)
+    }
+
+    pub(crate) fn _to_libfuzzer_test_file(
+        &self,
+        _api_graph: &ApiGraph<'_>,
+        test_index: usize,
+    ) -> String {
+        self._to_libfuzzer_test_file_with_encoding(
+            _api_graph,
+            test_index,
+            EncodingVariant::RawSplit,
+        )
+    }
+
+    pub(crate) fn _to_replay_crash_file_with_encoding(
+        &self,
+        _api_graph: &ApiGraph<'_>,
+        test_index: usize,
+        encoding: EncodingVariant,
+    ) -> String {
+        let mut res = self._to_afl_except_main(_api_graph, test_index, encoding);
         res = res.replace("#[macro_use]\nextern crate afl;\n", "");
         res.push_str(replay_util::_read_crash_file_data());
         res.push('\n');
-        res.push_str(self._reproduce_main_function(test_index).as_str());
+        res.push_str(self._reproduce_main_function(test_index, encoding).as_str());
         res
     }
 
-    pub(crate) fn _to_afl_test_file(&self, _api_graph: &ApiGraph<'_>, test_index: usize) -> String {
-        let mut res = self._to_afl_except_main(_api_graph, test_index);
-        res.push_str(self._afl_main_function(test_index).as_str());
+    pub(crate) fn _to_afl_test_file_with_encoding(
+        &self,
+        _api_graph: &ApiGraph<'_>,
+        test_index: usize,
+        encoding: EncodingVariant,
+    ) -> String {
+        let mut res = self._to_afl_except_main(_api_graph, test_index, encoding);
+        res.push_str(self._afl_main_function(test_index, encoding).as_str());
         res
     }
 
-    pub(crate) fn _to_libfuzzer_test_file(
+    pub(crate) fn _to_libfuzzer_test_file_with_encoding(
         &self,
         _api_graph: &ApiGraph<'_>,
         test_index: usize,
+        encoding: EncodingVariant,
     ) -> String {
-        let mut res = self._to_afl_except_main(_api_graph, test_index);
+        let mut res = self._to_afl_except_main(_api_graph, test_index, encoding);
         res = res.replace(
             "#[macro_use]\nextern crate afl;\n",
             format!("#![no_main]\n#[macro_use]\nextern crate libfuzzer_sys;\n").as_str(),
         );
-        res.push_str(self._libfuzzer_fuzz_main(test_index).as_str());
+        res.push_str(self._libfuzzer_fuzz_main(test_index, encoding).as_str());
+        res
+    }
+
+    pub(crate) fn _to_honggfuzz_test_file_with_encoding(
+        &self,
+        _api_graph: &ApiGraph<'_>,
+        test_index: usize,
+        encoding: EncodingVariant,
+    ) -> String {
+        let mut res = self._to_afl_except_main(_api_graph, test_index, encoding);
+        res = res.replace(
+            "#[macro_use]\nextern crate afl;\n",
+            "#[macro_use]\nextern crate honggfuzz;\n",
+        );
+        res.push_str(self._honggfuzz_main_function(test_index, encoding).as_str());
+        res
+    }
+
+    //honggfuzz的fuzz!每次只执行一个输入，需要放在循环里
+    pub(crate) fn _honggfuzz_main_function(
+        &self,
+        test_index: usize,
+        encoding: EncodingVariant,
+    ) -> String {
+        let mut res = String::new();
+        let loop_indent = _generate_indent(4);
+        let fuzz_indent = _generate_indent(8);
+        res.push_str("fn main() {\n");
+        res.push_str(format!("{}loop {{\n", loop_indent).as_str());
+        res.push_str(format!("{}fuzz!(|data: &[u8]| {{\n", fuzz_indent).as_str());
+        res.push_str(self._afl_closure_body(8, test_index, encoding).as_str());
+        res.push_str(format!("{}}});\n", fuzz_indent).as_str());
+        res.push_str(format!("{}}}\n", loop_indent).as_str());
+        res.push_str("}\n");
         res
     }
 
-    pub(crate) fn _libfuzzer_fuzz_main(&self, test_index: usize) -> String {
+    pub(crate) fn _libfuzzer_fuzz_main(
+        &self,
+        test_index: usize,
+        encoding: EncodingVariant,
+    ) -> String {
         let mut res = String::new();
         res.push_str("fuzz_target!(|data: &[u8]| {\n");
-        res.push_str(self._afl_closure_body(0, test_index).as_str());
+        res.push_str(self._afl_closure_body(0, test_index, encoding).as_str());
         res.push_str("});\n");
         res
     }
@@ -583,6 +718,7 @@ pub(crate) fn _to_afl_except_main(
         &self,
         _api_graph: &ApiGraph<'_>,
         test_index: usize,
+        encoding: EncodingVariant,
     ) -> String {
         let mut res = String::new();
         //加入可能需要开启的feature gate
@@ -595,18 +731,51 @@ pub(crate) fn _to_afl_except_main(
             }
         }*/
 
+        res.push_str(GENERATED_LINT_ALLOWS);
         res.push_str("#[macro_use]\n");
         res.push_str("extern crate afl;\n");
         res.push_str(format!("extern crate {};\n", _api_graph._crate_name).as_str());
+        if encoding == EncodingVariant::Arbitrary {
+            res.push_str("extern crate arbitrary;\n");
+        }
+        if self._contains_async_call(_api_graph) {
+            res.push_str(_api_graph.emit_options.async_runtime.extern_crate_line());
+        }
 
         let prelude_helper_functions = self._prelude_helper_functions();
         if let Some(prelude_functions) = prelude_helper_functions {
             res.push_str(prelude_functions.as_str());
         }
 
-        let afl_helper_functions = self._afl_helper_functions();
+        //Arbitrary编码不需要按字节解码的辅助函数
+        let afl_helper_functions = match encoding {
+            EncodingVariant::Arbitrary => None,
+            _ => self._afl_helper_functions(),
+        };
         if let Some(afl_functions) = afl_helper_functions {
-            res.push_str(afl_functions.as_str());
+            if encoding == EncodingVariant::LossyStr {
+                res.push_str(
+                    afl_functions
+                        .replace(afl_util::_data_to_str(), encoding::_data_to_lossy_str())
+                        .as_str(),
+                );
+            } else {
+                res.push_str(afl_functions.as_str());
+            }
+        }
+        if let Some(timeout_ms) = _api_graph.emit_options.call_timeout_ms {
+            res.push_str(watchdog::_watchdog_helper_functions(timeout_ms).as_str());
+        }
+        if let Some(timeout_ms) = _api_graph.emit_options.target_timeout_ms {
+            res.push_str(watchdog::_target_timeout_helper_functions(timeout_ms).as_str());
+        }
+        if let Some(crash_panic_classes) = &_api_graph.emit_options.crash_panic_classes {
+            res.push_str(
+                panic_filter::_panic_filter_helper_functions(crash_panic_classes).as_str(),
+            );
+        }
+        if let Some(trait_helpers) = foreign_trait::helper_functions(self, _api_graph) {
+            res.push_str(trait_helpers.as_str());
         }
         res.push_str(self._to_well_written_function(_api_graph, test_index, 0).as_str());
         res.push('\n');
@@ -650,20 +819,28 @@ pub(crate) fn _afl_helper_functions(&self) -> Option<String> {
         }
     }
 
-    pub(crate) fn _afl_main_function(&self, test_index: usize) -> String {
+    pub(crate) fn _afl_main_function(
+        &self,
+        test_index: usize,
+        encoding: EncodingVariant,
+    ) -> String {
         let mut res = String::new();
         let indent = _generate_indent(4);
         res.push_str("fn main() {\n");
         res.push_str(indent.as_str());
         res.push_str("fuzz!(|data: &[u8]| {\n");
-        res.push_str(self._afl_closure_body(4, test_index).as_str());
+        res.push_str(self._afl_closure_body(4, test_index, encoding).as_str());
         res.push_str(indent.as_str());
         res.push_str("});\n");
         res.push_str("}\n");
         res
     }
 
-    pub(crate) fn _reproduce_main_function(&self, test_index: usize) -> String {
+    pub(crate) fn _reproduce_main_function(
+        &self,
+        test_index: usize,
+        encoding: EncodingVariant,
+    ) -> String {
         format!(
             "fn main() {{
     let _content = _read_data();
@@ -672,15 +849,25 @@ pub(crate) fn _reproduce_main_function(&self, test_index: usize) -> String {
     println!(\"data len = {{:?}}\", data.len());
 {}
 }}",
-            self._afl_closure_body(0, test_index)
+            self._afl_closure_body(0, test_index, encoding)
         )
     }
 
-    pub(crate) fn _afl_closure_body(&self, outer_indent: usize, test_index: usize) -> String {
+    pub(crate) fn _afl_closure_body(
+        &self,
+        outer_indent: usize,
+        test_index: usize,
+        encoding: EncodingVariant,
+    ) -> String {
         let extra_indent = 4;
         let mut res = String::new();
         let indent = _generate_indent(outer_indent + extra_indent);
         res.push_str(format!("{indent}//actual body emit\n", indent = indent).as_str());
+        if encoding == EncodingVariant::Arbitrary {
+            res.push_str(encoding::arbitrary_param_lines(self, &indent).as_str());
+            res.push_str(self._test_function_call_line(test_index, &indent).as_str());
+            return res;
+        }
 
         let op = if self._is_fuzzables_fixed_length() { "!=" } else { "<" };
         let min_len = self._fuzzables_min_length();
@@ -694,42 +881,20 @@ pub(crate) fn _afl_closure_body(&self, outer_indent: usize, test_index: usize) -
             .as_str(),
         );
 
-        let dynamic_param_start_index = self._fuzzable_fixed_part_length();
-        let dynamic_param_number = self._dynamic_length_param_number();
-        let dynamic_length_name = "dynamic_length";
-        let every_dynamic_length = format!(
-            "let {dynamic_length_name} = (data.len() - {dynamic_param_start_index}) / {dynamic_param_number}",
-            dynamic_length_name = dynamic_length_name,
-            dynamic_param_start_index = dynamic_param_start_index,
-            dynamic_param_number = dynamic_param_number
-        );
-        if !self._is_fuzzables_fixed_length() {
+        //参数按顺序从_reader读取，见afl_util::_byte_reader
+        if !self.fuzzable_params.is_empty() {
             res.push_str(
-                format!(
-                    "{indent}{every_dynamic_length};\n",
-                    indent = indent,
-                    every_dynamic_length = every_dynamic_length
-                )
-                .as_str(),
+                format!("{indent}let mut _reader = _ByteReader::new(data);\n", indent = indent)
+                    .as_str(),
             );
         }
 
-        let mut fixed_start_index = 0; //当前固定长度的变量开始分配的位置
-        let mut dynamic_param_index = 0; //当前这是第几个动态长度的变量
-
         let fuzzable_param_number = self.fuzzable_params.len();
         for i in 0..fuzzable_param_number {
             let fuzzable_param = &self.fuzzable_params[i];
             let afl_helper = _AflHelpers::_new_from_fuzzable(fuzzable_param);
-            let param_initial_line = afl_helper._generate_param_initial_statement(
-                i,
-                fixed_start_index,
-                dynamic_param_start_index,
-                dynamic_param_index,
-                dynamic_param_number,
-                &dynamic_length_name.to_string(),
-                fuzzable_param,
-            );
+            let param_initial_line =
+                afl_helper._generate_param_initial_statement(i, fuzzable_param);
             res.push_str(
                 format!(
                     "{indent}{param_initial_line}\n",
@@ -738,23 +903,24 @@ pub(crate) fn _afl_closure_body(&self, outer_indent: usize, test_index: usize) -
                 )
                 .as_str(),
             );
-            fixed_start_index = fixed_start_index + fuzzable_param._fixed_part_length();
-            dynamic_param_index =
-                dynamic_param_index + fuzzable_param._dynamic_length_param_number();
         }
 
+        res.push_str(self._test_function_call_line(test_index, &indent).as_str());
+
+        res
+    }
+
+    fn _test_function_call_line(&self, test_index: usize, indent: &String) -> String {
         let mut test_function_call =
             format!("{indent}test_function{test_index}(", indent = indent, test_index = test_index);
-        for i in 0..fuzzable_param_number {
+        for i in 0..self.fuzzable_params.len() {
             if i != 0 {
                 test_function_call.push_str(" ,");
             }
             test_function_call.push_str(format!("_param{}", i).as_str());
         }
         test_function_call.push_str(");\n");
-        res.push_str(test_function_call.as_str());
-
-        res
+        test_function_call
     }
 
     pub(crate) fn _to_well_written_function(
@@ -780,33 +946,77 @@ pub(crate) fn _to_well_written_function(
             param_prefix,
         );
         res.push_str(function_header.as_str());
+        //用?取出Option/Result的时候，测试函数返回Option<()>
+        let unwrap_strategy = _api_graph.emit_options.unwrap_strategy;
+        res.push_str(unwrap_strategy.return_type());
 
         //加入函数体开头的大括号
         res.push_str("{\n");
 
-        //加入函数体
-        if self._unsafe_tag {
-            let unsafe_indent = _generate_indent(indent_size + 4);
-            res.push_str(unsafe_indent.as_str());
-            res.push_str("unsafe {\n");
-            let unsafe_function_body = self._generate_function_body_string(
-                _api_graph,
-                indent_size + 4,
-                param_prefix,
-                local_param_prefix,
+        //捕获panic的时候，函数体放到catch_unwind的闭包里，见panic_filter.rs
+        let catch_panics = _api_graph.emit_options.crash_panic_classes.is_some();
+        let catch_indent = _generate_indent(indent_size + 4);
+        let indent_size = if catch_panics {
+            res.push_str(
+                panic_filter::_catch_unwind_prefix(&catch_indent, unwrap_strategy.return_type())
+                    .as_str(),
             );
-            res.push_str(unsafe_function_body.as_str());
-            res.push_str(unsafe_indent.as_str());
-            res.push_str("}\n");
+            indent_size + 4
         } else {
-            let function_body = self._generate_function_body_string(
-                _api_graph,
-                indent_size,
-                param_prefix,
-                local_param_prefix,
+            indent_size
+        };
+        //整个序列的超时，函数体放到单独的线程里执行，见watchdog.rs
+        let target_timeout = _api_graph.emit_options.target_timeout_ms.is_some();
+        let timeout_indent = _generate_indent(indent_size + 4);
+        let indent_size = if target_timeout {
+            res.push_str(
+                watchdog::_run_with_timeout_prefix(&timeout_indent, unwrap_strategy.return_type())
+                    .as_str(),
             );
-            res.push_str(function_body.as_str());
+            indent_size + 4
+        } else {
+            indent_size
+        };
+
+        //加入函数体，unsafe块只包住需要的调用和参数，见_wrap_unsafe
+        //调用了async fn的话，函数体放到async块里由运行时执行
+        let is_async = self._contains_async_call(_api_graph);
+        let runtime_indent = _generate_indent(indent_size + 4);
+        if is_async {
+            res.push_str(runtime_indent.as_str());
+            res.push_str(_api_graph.emit_options.async_runtime.block_on_prefix());
+            res.push_str("async move {\n");
+        }
+        let function_body = self._generate_function_body_string(
+            _api_graph,
+            if is_async { indent_size + 4 } else { indent_size },
+            param_prefix,
+            local_param_prefix,
+        );
+        res.push_str(function_body.as_str());
+        let tail_expression = unwrap_strategy.tail_expression();
+        if !tail_expression.is_empty() {
+            let tail_indent = if is_async { indent_size + 8 } else { indent_size + 4 };
+            res.push_str(_generate_indent(tail_indent).as_str());
+            res.push_str(tail_expression);
+        }
+        if is_async {
+            res.push_str(runtime_indent.as_str());
+            //Try的时候async块的结果就是测试函数的返回值
+            res.push_str(if tail_expression.is_empty() { "});\n" } else { "})\n" });
         }
+        let indent_size = if target_timeout {
+            res.push_str(format!("{}}})\n", timeout_indent).as_str());
+            indent_size - 4
+        } else {
+            indent_size
+        };
+        let indent_size = if catch_panics {
+            res.push_str(panic_filter::_catch_unwind_suffix(&catch_indent).as_str());
+            indent_size - 4
+        } else {
+            indent_size
+        };
 
         //加入函数体结尾的大括号
         let braket_indent = _generate_indent(indent_size);
@@ -822,7 +1032,8 @@ pub(crate) fn _generate_using_traits_string(&self, indent_size: usize) -> String
         //using trait需要去重
         let mut has_used_traits = FxHashSet::default();
         for using_trait_ in &self._using_traits {
-            if has_used_traits.contains(using_trait_) {
+            //prelude里的trait（Iterator等）不用引入
+            if has_used_traits.contains(using_trait_) || !using_trait_.contains("::") {
                 continue;
             } else {
                 has_used_traits.insert(using_trait_.clone());
@@ -832,10 +1043,26 @@ pub(crate) fn _generate_using_traits_string(&self, indent_size: usize) -> String
             res.push_str(using_trait_.as_str());
             res.push_str(";\n");
         }
+        //Borrow不在prelude里，参数用.borrow()转换的时候需要引入
+        if !has_used_traits.contains(BORROW_TRAIT) && self._contains_borrow_call_type() {
+            res.push_str(indent.as_str());
+            res.push_str("use ");
+            res.push_str(BORROW_TRAIT);
+            res.push_str(";\n");
+        }
         res.push('\n');
         res
     }
 
+    fn _contains_borrow_call_type(&self) -> bool {
+        self.functions.iter().flat_map(|api_call| &api_call.params).any(|(_, _, call_type)| {
+            call_type
+                ._call_type_to_array()
+                .iter()
+                .any(|call_type| matches!(call_type, CallType::_Borrow(..)))
+        })
+    }
+
     //outer_indent:上层的缩进
     //extra_indent:本块需要的额外缩进
     pub(crate) fn _generate_function_header_string(
@@ -896,20 +1123,86 @@ pub(crate) fn _generate_function_body_string(
         let extra_indent = 4;
         let mut res = String::new();
         let body_indent = _generate_indent(outer_indent + extra_indent);
+        let guard_indent = _generate_indent(outer_indent + extra_indent + 4);
 
         let dead_code = self._dead_code(_api_graph);
+        let continue_on_error = _api_graph.emit_options.continue_on_error;
+        //返回值可能不存在（Err/None，或者依赖了不存在的返回值）的语句，这些变量的类型是Option
+        let optional_locals = if continue_on_error {
+            self._optional_locals(_api_graph, &dead_code)
+        } else {
+            FxHashSet::default()
+        };
+
+        //限制解码出来的长度和数值
+        res.push_str(self._size_cap_lines(_api_graph, param_prefix, &body_indent).as_str());
 
+        let watchdog_enabled = _api_graph.emit_options.call_timeout_ms.is_some();
         //api_calls
         let api_calls_num = self.functions.len();
         let full_name_map = &_api_graph.full_name_map;
         for i in 0..api_calls_num {
             let api_call = &self.functions[i];
+            let api_function_index = api_call.func.1;
+            let api_function = &_api_graph.api_functions[api_function_index];
+
+            //如果依赖了可能不存在的返回值，需要先把它们取出来，取不出来就跳过当前语句
+            let guards = self._optional_param_guards(i, _api_graph, &optional_locals);
+            let statement_indent = if guards.is_empty() { &body_indent } else { &guard_indent };
 
             //准备参数
+            let mut helper_lines = String::new();
+            let mut raw_buffers = Vec::new();
             let param_size = api_call.params.len();
             let mut param_strings = Vec::new();
+            //有参数转换成裸指针的话，整个调用放在unsafe块里，参数本身就不用再包一层
+            let unsafe_call = api_function._unsafe_tag._is_unsafe()
+                || api_call.params.iter().any(|(_, _, call_type)| call_type._is_raw_pointer_cast());
             for j in 0..param_size {
                 let (param_type, index, call_type) = &api_call.params[j];
+                //裸指针参数：把fuzz输入复制到一段缓冲区里，缓冲区一直活到函数结束
+                if let Some(mutable) = self._raw_buffer_mutability(param_type, *index, call_type) {
+                    let buffer_name = format!("{}{}_rawbuf{}", local_param_prefix, i, j);
+                    //指向c_char的指针当成C字符串，结尾加上0
+                    let c_string = match call_type {
+                        CallType::_ConstRawPointer(_, pointee)
+                        | CallType::_MutRawPointer(_, pointee) => ffi::is_c_char_alias(pointee),
+                        _ => false,
+                    };
+                    let buffer = if c_string {
+                        format!("[{}{}, &[0]].concat()", param_prefix, index)
+                    } else {
+                        format!("{}{}.to_vec()", param_prefix, index)
+                    };
+                    //只有*mut指针需要可变的缓冲区。输入为空时长度就是0，
+                    //空Vec的as_ptr()/as_mut_ptr()是悬垂但非空的指针，不需要补一个元素
+                    res.push_str(
+                        format!(
+                            "{}let {}{} = {};\n",
+                            body_indent,
+                            if mutable { "mut " } else { "" },
+                            buffer_name,
+                            buffer
+                        )
+                        .as_str(),
+                    );
+                    let ptr_method = if mutable { "as_mut_ptr" } else { "as_ptr" };
+                    //C类型别名需要转换成参数要求的指针类型
+                    let cast = match call_type {
+                        CallType::_ConstRawPointer(inner_call_type, _)
+                        | CallType::_MutRawPointer(inner_call_type, _) => {
+                            if let CallType::_AsConvert(..) = **inner_call_type {
+                                ".cast()"
+                            } else {
+                                ""
+                            }
+                        }
+                        _ => "",
+                    };
+                    param_strings.push(format!("{}.{}(){}", buffer_name, ptr_method, cast));
+                    raw_buffers.push((j, buffer_name, c_string));
+                    continue;
+                }
                 let call_type_array = call_type._split_at_unwrap_call_type();
                 //println!("call_type_array = {:?}",call_type_array);
                 let param_name = match param_type {
@@ -929,7 +1222,10 @@ pub(crate) fn _generate_function_body_string(
                     let call_type = &call_type_array[0];
                     let param_string =
                         call_type._to_call_string(&param_name, _api_graph.cache, full_name_map);
-                    param_strings.push(param_string);
+                    param_strings.push(_wrap_unsafe(
+                        param_string,
+                        call_type._contains_unsafe_call_type() && !unsafe_call,
+                    ));
                 } else {
                     let mut former_param_name = param_name.clone();
                     let mut helper_index = 1;
@@ -942,19 +1238,22 @@ pub(crate) fn _generate_function_body_string(
                         );
                         let helper_line = format!(
                             "{}let mut {} = {};\n",
-                            body_indent,
+                            statement_indent,
                             helper_name,
-                            call_type._to_call_string(
-                                &former_param_name,
-                                _api_graph.cache,
-                                full_name_map
+                            _wrap_unsafe(
+                                call_type._to_call_string(
+                                    &former_param_name,
+                                    _api_graph.cache,
+                                    full_name_map
+                                ),
+                                call_type._contains_unsafe_call_type()
                             )
                         );
                         if helper_index > 1 {
                             if !api_util::_need_mut_tag(call_type) {
                                 former_helper_line = former_helper_line.replace("let mut ", "let ");
                             }
-                            res.push_str(former_helper_line.as_str());
+                            helper_lines.push_str(former_helper_line.as_str());
                         }
                         helper_index = helper_index + 1;
                         former_param_name = helper_name;
@@ -964,89 +1263,723 @@ pub(crate) fn _generate_function_body_string(
                     if !api_util::_need_mut_tag(last_call_type) {
                         former_helper_line = former_helper_line.replace("let mut ", "let ");
                     }
-                    res.push_str(former_helper_line.as_str());
+                    helper_lines.push_str(former_helper_line.as_str());
                     let param_string = last_call_type._to_call_string(
                         &former_param_name,
                         _api_graph.cache,
                         full_name_map,
                     );
-                    param_strings.push(param_string);
+                    param_strings.push(_wrap_unsafe(
+                        param_string,
+                        last_call_type._contains_unsafe_call_type() && !unsafe_call,
+                    ));
                 }
             }
-            res.push_str(body_indent.as_str());
-            //如果不是最后一个调用
-            let api_function_index = api_call.func.1;
-            let api_function = &_api_graph.api_functions[api_function_index];
-            if dead_code[i] || api_function._has_no_output() {
-                res.push_str("let _ = ");
+
+            //裸指针后面紧跟的usize参数一般是缓冲区的长度，直接用缓冲区的实际长度
+            //C字符串的长度不包括结尾的0
+            for (j, buffer_name, c_string) in &raw_buffers {
+                if let Some((ParamType::_FuzzableType, index, length_call_type)) =
+                    api_call.params.get(j + 1)
+                {
+                    if let FuzzableType::Primitive(clean::PrimitiveType::Usize) =
+                        self.fuzzable_params[*index]
+                    {
+                        let length = if *c_string {
+                            format!("{}.len() - 1", buffer_name)
+                        } else {
+                            format!("{}.len()", buffer_name)
+                        };
+                        param_strings[j + 1] = match length_call_type {
+                            CallType::_DirectCall => length,
+                            CallType::_AsConvert(..) => format!("({}) as _", length),
+                            _ => continue,
+                        };
+                    }
+                }
+            }
+
+            //函数调用本身
+            let mut call_string = String::new();
+            let (api_type, function_index) = &api_call.func;
+            match api_type {
+                ApiType::BareFunction => {
+                    let api_function = &_api_graph.api_functions[*function_index];
+                    call_string.push_str(api_function._call_path().as_str());
+                    call_string.push_str(
+                        api_function
+                            ._turbofish_string(_api_graph.cache, &_api_graph.full_name_map)
+                            .as_str(),
+                    );
+                }
+                ApiType::GenericFunction => todo!(),
+            }
+            //结构体字面量的合成生产者，见api_util::_add_struct_literal_producers
+            if let Some(field_names) = _api_graph.struct_literals.get(&api_function.full_name) {
+                call_string = api_util::_struct_literal_string(
+                    &api_function.full_name,
+                    field_names,
+                    &param_strings,
+                );
+            } else if let Some(expression) = _api_graph.const_producers.get(&api_function.full_name)
+            {
+                //常量生产者，见const_producer.rs
+                call_string = expression.clone();
+            } else if let Some(type_name) =
+                _api_graph.from_str_producers.get(&api_function.full_name)
+            {
+                //FromStr的合成生产者，见api_util::_add_from_str_producers
+                call_string = _api_graph
+                    .emit_options
+                    .unwrap_strategy
+                    .parse_expression(&param_strings[0], type_name);
             } else {
-                let mut_tag = if self._is_function_need_mut_tag(i) { "mut " } else { "" };
-                res.push_str(format!("let {}{}{} = ", mut_tag, local_param_prefix, i).as_str());
+                call_string.push('(');
+                let param_size = param_strings.len();
+                for k in 0..param_size {
+                    if k != 0 {
+                        call_string.push_str(" ,");
+                    }
+
+                    let param_string = &param_strings[k];
+                    call_string.push_str(param_string.as_str());
+                }
+                call_string.push_str(")");
+            }
+            let mut call_string = _wrap_unsafe(
+                await_if_async(call_string, api_function._asyncness._is_async()),
+                unsafe_call,
+            );
+            //差分测试：调用之前给参考实现复制一份参数，调用之后比较，后面的语句使用同一个返回值
+            if let Some((copy_lines, check_lines)) =
+                self._oracle_check_lines(i, _api_graph, param_prefix, &call_string, &body_indent)
+            {
+                res.push_str(copy_lines.as_str());
+                helper_lines.push_str(check_lines.as_str());
+                call_string = format!("_target_output{}", i);
             }
 
             //对于Result和Option
-            if let Some(output_type) = &api_function.output {
-                if prelude_type::_prelude_type_need_special_dealing(
-                    &output_type,
-                    _api_graph.cache,
-                    &_api_graph.full_name_map,
-                ) && !dead_code[i]
+            let output_prelude_type = match &api_function.output {
+                Some(output_type)
+                    if prelude_type::_prelude_type_need_special_dealing(
+                        &output_type,
+                        _api_graph.cache,
+                        &_api_graph.full_name_map,
+                    ) && !dead_code[i] =>
                 {
-                    let prelude_type = PreludeType::from_type(
+                    Some(PreludeType::from_type(
                         output_type,
                         _api_graph.cache,
                         &_api_graph.full_name_map,
+                    ))
+                }
+                _ => None,
+            };
+
+            let has_local = !(dead_code[i] || api_function._has_no_output());
+            //返回迭代器的API，检查迭代器的约定
+            let iterator_law = self._iterator_law_kind(i, _api_graph);
+            //返回值没有被使用的迭代器，绑定到变量上之后直接交给检查代码
+            let check_dead_iterator = !has_local
+                && guards.is_empty()
+                && matches!(iterator_law, Some((_, _, true)))
+                && !api_function._has_no_output();
+            //其他没有被使用的迭代器直接消耗掉
+            let dead_call_string = match self._dead_iterator_kind(i, _api_graph) {
+                Some(wrapped) if !has_local && !check_dead_iterator => {
+                    iterator_law::consume_iterator_expression(&call_string, wrapped)
+                }
+                _ => call_string.clone(),
+            };
+            let let_string = if has_local {
+                let need_mut = if optional_locals.contains(&i) {
+                    self._optional_local_need_mut(i, _api_graph)
+                } else {
+                    self._local_need_mut(i)
+                };
+                let mut_tag = if need_mut { "mut " } else { "" };
+                format!("let {}{}{} = ", mut_tag, local_param_prefix, i)
+            } else if check_dead_iterator {
+                format!("let {}{} = ", local_param_prefix, i)
+            } else {
+                "let _ = ".to_string()
+            };
+
+            //记录当前执行的语句，卡住的话watchdog会报告这个语句
+            if watchdog_enabled {
+                res.push_str(body_indent.as_str());
+                res.push_str(watchdog::_watchdog_enter_line(i, &api_function.full_name).as_str());
+            }
+
+            if continue_on_error && optional_locals.contains(&i) {
+                //返回值是Option，Err/None的时候后面依赖它的语句会被跳过
+                let value_string = match &output_prelude_type {
+                    Some(prelude_type) if prelude_type.is_result() => {
+                        format!("{}.ok()", call_string)
+                    }
+                    Some(_) => call_string.clone(),
+                    None => format!("Some({})", call_string),
+                };
+                if guards.is_empty() {
+                    res.push_str(helper_lines.as_str());
+                    res.push_str(body_indent.as_str());
+                    res.push_str(let_string.as_str());
+                    res.push_str(value_string.as_str());
+                    res.push_str(";\n");
+                } else {
+                    res.push_str(body_indent.as_str());
+                    res.push_str(let_string.as_str());
+                    res.push_str(self._guard_header(&guards, local_param_prefix).as_str());
+                    res.push_str(helper_lines.as_str());
+                    res.push_str(guard_indent.as_str());
+                    res.push_str(value_string.as_str());
+                    res.push('\n');
+                    res.push_str(format!("{}}} else {{\n", body_indent).as_str());
+                    res.push_str(format!("{}None\n", guard_indent).as_str());
+                    res.push_str(format!("{}}};\n", body_indent).as_str());
+                }
+                if has_local {
+                    res.push_str(
+                        self._invariant_assertion_lines(
+                            i,
+                            _api_graph,
+                            local_param_prefix,
+                            true,
+                            &body_indent,
+                        )
+                        .as_str(),
                     );
-                    if prelude_type.is_option() {
-                        res.push_str("if let Some(x) = ");
-                    } else if prelude_type.is_result() {
-                        res.push_str("if let Ok(x) = ");
+                    if let Some((true, fused, _)) = iterator_law {
+                        res.push_str(
+                            format!(
+                                "{}if let Some(x) = {}{}.as_ref() {{\n",
+                                body_indent, local_param_prefix, i
+                            )
+                            .as_str(),
+                        );
+                        res.push_str(
+                            iterator_law::iterator_law_lines(
+                                &"x.clone()".to_string(),
+                                &api_function.full_name,
+                                true,
+                                fused,
+                                &guard_indent,
+                            )
+                            .as_str(),
+                        );
+                        res.push_str(format!("{}}}\n", body_indent).as_str());
                     }
+                }
+                continue;
+            }
+
+            if !guards.is_empty() {
+                //没有返回值被使用，只需要在参数都存在的时候调用
+                res.push_str(body_indent.as_str());
+                res.push_str(self._guard_header(&guards, local_param_prefix).as_str());
+                res.push_str(helper_lines.as_str());
+                res.push_str(format!("{}let _ = {};\n", guard_indent, dead_call_string).as_str());
+                res.push_str(format!("{}}}\n", body_indent).as_str());
+                continue;
+            }
+
+            //由fuzz输入决定是否调用，不调用的时候沿用self参数原来的值
+            if let (Some(selector_index), None) =
+                (self._fuzz_selected_calls.get(&i), &output_prelude_type)
+            {
+                let selector = format!("{}{}", param_prefix, selector_index);
+                res.push_str(helper_lines.as_str());
+                res.push_str(body_indent.as_str());
+                if has_local {
+                    res.push_str(
+                        format!(
+                            "{}if {} {{ {} }} else {{ {} }};\n",
+                            let_string, selector, call_string, param_strings[0]
+                        )
+                        .as_str(),
+                    );
+                    res.push_str(
+                        self._invariant_assertion_lines(
+                            i,
+                            _api_graph,
+                            local_param_prefix,
+                            false,
+                            &body_indent,
+                        )
+                        .as_str(),
+                    );
                 } else {
-                    res.push_str("");
+                    res.push_str(
+                        format!("if {} {{ let _ = {}; }}\n", selector, dead_call_string).as_str(),
+                    );
                 }
+                continue;
             }
 
-            let (api_type, function_index) = &api_call.func;
-            match api_type {
-                ApiType::BareFunction => {
-                    let api_function_full_name =
-                        &_api_graph.api_functions[*function_index].full_name;
-                    res.push_str(api_function_full_name.as_str());
+            res.push_str(helper_lines.as_str());
+            res.push_str(body_indent.as_str());
+            let emit_options = &_api_graph.emit_options;
+            match &output_prelude_type {
+                //Option/Result在这里取出里面的值，见unwrap_strategy.rs
+                Some(prelude_type) if prelude_type.is_option() => res.push_str(
+                    emit_options
+                        .none_policy
+                        .let_statement(
+                            &let_string,
+                            &call_string,
+                            emit_options.unwrap_strategy,
+                            api_util::_implements_default(
+                                &prelude_type._get_final_type(),
+                                _api_graph,
+                            ),
+                        )
+                        .as_str(),
+                ),
+                Some(prelude_type) => res.push_str(
+                    emit_options
+                        .unwrap_strategy
+                        .let_statement(&let_string, &call_string, prelude_type.is_result())
+                        .as_str(),
+                ),
+                None => {
+                    res.push_str(let_string.as_str());
+                    res.push_str(if has_local { &call_string } else { &dead_call_string });
+                    res.push_str(";\n");
                 }
-                ApiType::GenericFunction => todo!(),
             }
-            res.push('(');
+            if has_local {
+                res.push_str(
+                    self._invariant_assertion_lines(
+                        i,
+                        _api_graph,
+                        local_param_prefix,
+                        false,
+                        &body_indent,
+                    )
+                    .as_str(),
+                );
+            }
+            //使用了的迭代器只能检查它的拷贝，没有使用的可以直接消耗掉
+            let iterator_expr = match iterator_law {
+                Some((true, _, _)) if has_local => {
+                    Some(format!("{}{}.clone()", local_param_prefix, i))
+                }
+                Some(_) if check_dead_iterator => Some(format!("{}{}", local_param_prefix, i)),
+                _ => None,
+            };
+            if let (Some(iterator_expr), Some((cloneable, fused, _))) =
+                (iterator_expr, iterator_law)
+            {
+                res.push_str(
+                    iterator_law::iterator_law_lines(
+                        &iterator_expr,
+                        &api_function.full_name,
+                        cloneable,
+                        fused,
+                        &body_indent,
+                    )
+                    .as_str(),
+                );
+            }
+        }
+        if watchdog_enabled {
+            res.push_str(body_indent.as_str());
+            res.push_str(watchdog::_watchdog_exit_line());
+        }
+        res
+    }
+
+    /// 参数是否是指向fuzz输入缓冲区的裸指针，是的话返回指针是否可变
+    fn _raw_buffer_mutability(
+        &self,
+        param_type: &ParamType,
+        index: usize,
+        call_type: &CallType,
+    ) -> Option<bool> {
+        if *param_type != ParamType::_FuzzableType {
+            return None;
+        }
+        if let FuzzableType::RefSlice(..) = self.fuzzable_params[index] {
+            match call_type {
+                CallType::_ConstRawPointer(..) => Some(false),
+                CallType::_MutRawPointer(..) => Some(true),
+                _ => None,
+            }
+        } else {
+            None
+        }
+    }
+
+    /// 第i个语句的返回值是否是迭代器，是的话返回(是否实现了Clone, 是否实现了FusedIterator,
+    /// 返回值是否直接是迭代器本身)，最后一项为false表示返回的是引用或者Option/Result
+    fn _iterator_law_kind(
+        &self,
+        i: usize,
+        _api_graph: &ApiGraph<'_>,
+    ) -> Option<(bool, bool, bool)> {
+        let api_function = &_api_graph.api_functions[self.functions[i].func.1];
+        let output_type = api_function.output.as_ref()?;
+        let type_path =
+            invariant::type_full_path(output_type, _api_graph.cache, &_api_graph.full_name_map)?;
+        if !_api_graph.type_implements_trait(&type_path, iterator_law::ITERATOR_TRAIT) {
+            return None;
+        }
+        let cloneable = _api_graph.type_implements_trait(&type_path, iterator_law::CLONE_TRAIT);
+        let fused =
+            _api_graph.type_implements_trait(&type_path, iterator_law::FUSED_ITERATOR_TRAIT);
+        let by_value = match output_type {
+            clean::Type::BorrowedRef { .. } => false,
+            _ => !prelude_type::_prelude_type_need_special_dealing(
+                output_type,
+                _api_graph.cache,
+                &_api_graph.full_name_map,
+            ),
+        };
+        Some((cloneable, fused, by_value))
+    }
+
+    /// 第i个语句的返回值（Option/Result取出里面一层之后）是不是按值返回的迭代器，
+    /// 包括返回`impl Iterator`的函数，是的话返回迭代器是否包在Option/Result里
+    fn _dead_iterator_kind(&self, i: usize, _api_graph: &ApiGraph<'_>) -> Option<bool> {
+        let api_function = &_api_graph.api_functions[self.functions[i].func.1];
+        let output_type = api_function.output.as_ref()?;
+        let cache = _api_graph.cache;
+        let full_name_map = &_api_graph.full_name_map;
+        let wrapped =
+            prelude_type::_prelude_type_need_special_dealing(output_type, cache, full_name_map);
+        let iterator_type = if wrapped {
+            PreludeType::from_type(output_type, cache, full_name_map)._get_final_type()
+        } else {
+            output_type.clone()
+        };
+        let is_iterator = match &iterator_type {
+            clean::Type::ImplTrait(..) => opaque_type::bound_trait_paths(&iterator_type, cache)
+                .iter()
+                .any(|trait_path| trait_path == iterator_law::ITERATOR_TRAIT),
+            clean::Type::BorrowedRef { .. } => false,
+            _ => invariant::type_full_path(&iterator_type, cache, full_name_map).map_or(
+                false,
+                |type_path| {
+                    _api_graph.type_implements_trait(&type_path, iterator_law::ITERATOR_TRAIT)
+                },
+            ),
+        };
+        if is_iterator { Some(wrapped) } else { None }
+    }
+
+    /// 在第i个语句之后插入用户提供的不变式断言
+    fn _invariant_assertion_lines(
+        &self,
+        i: usize,
+        _api_graph: &ApiGraph<'_>,
+        local_param_prefix: &str,
+        optional: bool,
+        indent: &String,
+    ) -> String {
+        let mut res = String::new();
+        let invariants = &_api_graph.emit_options.invariants;
+        if invariants.is_empty() {
+            return res;
+        }
+        let api_function = &_api_graph.api_functions[self.functions[i].func.1];
+        let output_type = match &api_function.output {
+            Some(output_type) => output_type,
+            None => return res,
+        };
+        let type_path = match invariant::type_full_path(
+            output_type,
+            _api_graph.cache,
+            &_api_graph.full_name_map,
+        ) {
+            Some(type_path) => type_path,
+            None => return res,
+        };
+        let assertions = match invariants.get(&type_path) {
+            Some(assertions) => assertions,
+            None => return res,
+        };
+        let binding = if optional {
+            format!("if let Some(x) = {}{}.as_ref()", local_param_prefix, i)
+        } else {
+            format!("{{ let x = &{}{};", local_param_prefix, i)
+        };
+        for assertion in assertions {
+            if optional {
+                res.push_str(
+                    format!("{}{} {{ assert!({}); }}\n", indent, binding, assertion).as_str(),
+                );
+            } else {
+                res.push_str(
+                    format!("{}{} assert!({}); }}\n", indent, binding, assertion).as_str(),
+                );
+            }
+        }
+        res
+    }
 
-            let param_size = param_strings.len();
-            for k in 0..param_size {
-                if k != 0 {
-                    res.push_str(" ,");
+    /// 在函数体开头限制fuzzable参数的长度和取值，避免OOM和超时，
+    /// 被限制的参数以注释的形式记录在测试文件里
+    fn _size_cap_lines(
+        &self,
+        _api_graph: &ApiGraph<'_>,
+        param_prefix: &str,
+        indent: &String,
+    ) -> String {
+        let mut res = String::new();
+        let size_caps = &_api_graph.emit_options.size_caps;
+        if !size_caps.enabled {
+            return res;
+        }
+        //传给长度、容量之类API的fuzzable参数
+        let mut size_like_params = FxHashSet::default();
+        for api_call in &self.functions {
+            let api_function = &_api_graph.api_functions[api_call.func.1];
+            if !size_cap::is_size_like_function(&api_function.full_name) {
+                continue;
+            }
+            for (param_type, index, _) in &api_call.params {
+                if *param_type == ParamType::_FuzzableType {
+                    size_like_params.insert(*index);
                 }
+            }
+        }
+        for (i, fuzzable_param) in self.fuzzable_params.iter().enumerate() {
+            let param_name = format!("{}{}", param_prefix, i);
+            if let Some((statement, note)) = size_cap::cap_param_statement(
+                size_caps,
+                fuzzable_param,
+                &param_name,
+                self._is_fuzzable_need_mut_tag(i),
+                size_like_params.contains(&i),
+            ) {
+                res.push_str(format!("{}//size cap: {}\n", indent, note).as_str());
+                res.push_str(format!("{}{}\n", indent, statement).as_str());
+            }
+        }
+        res
+    }
 
-                let param_string = &param_strings[k];
-                res.push_str(param_string.as_str());
+    /// 差分测试：如果第i个语句调用的API配置了参考实现，并且参数全部来自fuzz输入，
+    /// 就把参数复制一份交给参考实现，断言返回值和通过`&mut`修改的参数都一致。
+    /// 被测API只调用一次，返回值保存在`_target_output{i}`里，原来的语句直接使用它。
+    /// 返回(调用之前复制参数的语句, 调用并比较结果的语句)
+    fn _oracle_check_lines(
+        &self,
+        i: usize,
+        _api_graph: &ApiGraph<'_>,
+        param_prefix: &str,
+        call_string: &String,
+        indent: &String,
+    ) -> Option<(String, String)> {
+        let api_call = &self.functions[i];
+        let api_function = &_api_graph.api_functions[api_call.func.1];
+        let reference = _api_graph.emit_options.oracles.get(&api_function.full_name)?;
+        //只有参数全部来自fuzz输入时才能复制，需要辅助变量转换的参数比较不了调用之后的值
+        if api_call.params.iter().any(|(param_type, _, call_type)| {
+            *param_type != ParamType::_FuzzableType
+                || call_type._split_at_unwrap_call_type().len() > 1
+        }) {
+            return None;
+        }
+        //裸指针参数指向的缓冲区没法复制两份，不做差分测试
+        if api_call.params.iter().any(|(param_type, index, call_type)| {
+            self._raw_buffer_mutability(param_type, *index, call_type).is_some()
+        }) {
+            return None;
+        }
+        let full_name_map = &_api_graph.full_name_map;
+        let mut copy_lines = String::new();
+        let mut compare_lines = String::new();
+        let mut reference_params = Vec::new();
+        for (_, index, call_type) in &api_call.params {
+            let param_name = format!("{}{}", param_prefix, index);
+            let reference_name = format!("{}_oracle", param_name);
+            let need_mut = api_util::_need_mut_tag(call_type);
+            //fuzzable的参数不一定是Copy的（比如String），被测API会把它move走
+            copy_lines.push_str(
+                format!(
+                    "{}let {}{} = {}.clone();\n",
+                    indent,
+                    if need_mut { "mut " } else { "" },
+                    reference_name,
+                    param_name
+                )
+                .as_str(),
+            );
+            if need_mut {
+                compare_lines.push_str(
+                    format!(
+                        "{}assert_eq!({}, {}, \"{} modified {} differently from {}\");\n",
+                        indent,
+                        param_name,
+                        reference_name,
+                        api_function.full_name,
+                        param_name,
+                        reference
+                    )
+                    .as_str(),
+                );
             }
+            reference_params.push(call_type._to_call_string(
+                &reference_name,
+                _api_graph.cache,
+                full_name_map,
+            ));
+        }
+        //没有返回值也没有被修改的参数，没有可以比较的东西
+        if api_function._has_no_output() && compare_lines.is_empty() {
+            return None;
+        }
+        let mut check_lines = String::new();
+        check_lines
+            .push_str(format!("{}let _target_output{} = {};\n", indent, i, call_string).as_str());
+        check_lines.push_str(
+            format!(
+                "{}let _oracle_output{} = {}({});\n",
+                indent,
+                i,
+                reference,
+                reference_params.join(" ,")
+            )
+            .as_str(),
+        );
+        if !api_function._has_no_output() {
+            check_lines.push_str(
+                format!(
+                    "{}assert_eq!(_target_output{}, _oracle_output{}, \"{} disagrees with {}\");\n",
+                    indent, i, i, api_function.full_name, reference
+                )
+                .as_str(),
+            );
+        }
+        check_lines.push_str(compare_lines.as_str());
+        Some((copy_lines, check_lines))
+    }
 
-            res.push_str(")");
-            if let Some(output_type) = &api_function.output {
-                //在这里添加，unwrap
-                if prelude_type::_prelude_type_need_special_dealing(
-                    &output_type,
+    /// 找到返回值可能不存在的语句：
+    /// 1. 返回值是Result或者Option，并且会被后面的语句使用
+    /// 2. 依赖了返回值可能不存在的语句
+    pub(crate) fn _optional_locals(
+        &self,
+        _api_graph: &ApiGraph<'_>,
+        dead_code: &Vec<bool>,
+    ) -> FxHashSet<usize> {
+        let mut res = FxHashSet::default();
+        for (i, api_call) in self.functions.iter().enumerate() {
+            if dead_code[i] {
+                continue;
+            }
+            let api_function = &_api_graph.api_functions[api_call.func.1];
+            let fallible = match &api_function.output {
+                Some(output_type) => prelude_type::_prelude_type_need_special_dealing(
+                    output_type,
                     _api_graph.cache,
                     &_api_graph.full_name_map,
-                ) && !dead_code[i]
-                {
-                    res.push_str("{x} else {use std::process;process::exit(0);};\n");
-                } else {
-                    res.push_str(";\n");
+                ),
+                None => false,
+            };
+            let depends_on_optional = api_call.params.iter().any(|(param_type, index, _)| {
+                *param_type == ParamType::_FunctionReturn && res.contains(index)
+            });
+            if fallible || depends_on_optional {
+                res.insert(i);
+            }
+        }
+        res
+    }
+
+    /// 第i个语句需要从哪些可能不存在的返回值中取值，以及取值的方式
+    /// 返回(语句的index, 取值的方法, 绑定是否需要mut)
+    fn _optional_param_guards(
+        &self,
+        i: usize,
+        _api_graph: &ApiGraph<'_>,
+        optional_locals: &FxHashSet<usize>,
+    ) -> Vec<(usize, &'static str, bool)> {
+        let mut res: Vec<(usize, &'static str, bool)> = Vec::new();
+        let api_call = &self.functions[i];
+        let api_function = &_api_graph.api_functions[api_call.func.1];
+        for (j, (param_type, index, call_type)) in api_call.params.iter().enumerate() {
+            if *param_type != ParamType::_FunctionReturn || !optional_locals.contains(index) {
+                continue;
+            }
+            let (access, need_mut) = if call_type._contains_move_call_type() {
+                ("take()", api_util::_need_mut_tag(call_type))
+            } else if api_util::_is_mutable_borrow_occurs(&api_function.inputs[j], call_type)
+                || api_util::_need_mut_tag(call_type)
+            {
+                ("as_mut()", true)
+            } else {
+                ("as_ref()", false)
+            };
+            if let Some(guard) = res.iter_mut().find(|(guard_index, ..)| guard_index == index) {
+                //同一个返回值被用了两次，以更强的方式来取值
+                if access == "take()" || (access == "as_mut()" && guard.1 == "as_ref()") {
+                    guard.1 = access;
                 }
+                guard.2 = guard.2 || need_mut;
             } else {
-                res.push_str(";\n");
+                res.push((*index, access, need_mut));
             }
         }
         res
     }
+
+    /// 第local_index个返回值声明的时候是否需要mut
+    /// _function_mut_tag只看依赖的调用方式，调用方式里先unwrap的话，可变借用的是unwrap出来的值，变量本身不需要mut
+    fn _local_need_mut(&self, local_index: usize) -> bool {
+        if !self._is_function_need_mut_tag(local_index) {
+            return false;
+        }
+        self.functions[local_index + 1..].iter().any(|api_call| {
+            api_call.params.iter().any(|(param_type, index, call_type)| {
+                *param_type == ParamType::_FunctionReturn
+                    && *index == local_index
+                    && api_util::_need_mut_tag(&call_type._split_at_unwrap_call_type()[0])
+            })
+        })
+    }
+
+    /// 可能不存在的返回值如果会被take或者as_mut，声明的时候就需要带上mut
+    fn _optional_local_need_mut(&self, local_index: usize, _api_graph: &ApiGraph<'_>) -> bool {
+        let dead_code = self._dead_code(_api_graph);
+        let optional_locals = self._optional_locals(_api_graph, &dead_code);
+        for i in local_index + 1..self.functions.len() {
+            let guards = self._optional_param_guards(i, _api_graph, &optional_locals);
+            if guards
+                .iter()
+                .any(|(index, access, _)| *index == local_index && *access != "as_ref()")
+            {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// 生成 `if let Some(_local0) = _local0.as_ref() {` 或者元组形式的多个取值
+    fn _guard_header(
+        &self,
+        guards: &Vec<(usize, &'static str, bool)>,
+        local_param_prefix: &str,
+    ) -> String {
+        let patterns = guards
+            .iter()
+            .map(|(index, _, need_mut)| {
+                let mut_tag = if *need_mut { "mut " } else { "" };
+                format!("Some({}{}{})", mut_tag, local_param_prefix, index)
+            })
+            .collect::<Vec<String>>();
+        let values = guards
+            .iter()
+            .map(|(index, access, _)| format!("{}{}.{}", local_param_prefix, index, access))
+            .collect::<Vec<String>>();
+        if guards.len() == 1 {
+            format!("if let {} = {} {{\n", patterns[0], values[0])
+        } else {
+            format!("if let ({}) = ({}) {{\n", patterns.join(", "), values.join(", "))
+        }
+    }
 }
 
 pub(crate) fn _generate_indent(indent_size: usize) -> String {
@@ -1056,3 +1989,16 @@ pub(crate) fn _generate_indent(indent_size: usize) -> String {
     }
     indent
 }
+
+/// 生成的测试文件开头的lint设置，用户用`-D warnings`编译fuzz项目的时候也不会报错
+/// 变量都以下划线开头，mut标记按照实际的使用计算，剩下的只有trait方法用全路径调用时多余的use，
+/// 以及传入裸指针的safe函数外面的unsafe块
+static GENERATED_LINT_ALLOWS: &'static str = "#![allow(unused_imports, unused_unsafe)]\n";
+
+/// 参数用`.borrow()`转换的时候需要引入的trait
+static BORROW_TRAIT: &'static str = "std::borrow::Borrow";
+
+/// 需要unsafe的表达式单独包一层unsafe块，其余代码仍然由编译器检查，sanitizer报错时也容易定位到具体的调用
+pub(crate) fn _wrap_unsafe(expression: String, need_unsafe: bool) -> String {
+    if need_unsafe { format!("unsafe {{ {} }}", expression) } else { expression }
+}
@@ -3,18 +3,29 @@
 //! [`_extract_output_type`] 解析函数返回值类型
 //! [`_is_generic_type`] 判断是否是泛型
 //! [`_is_end_type`] 判断是否是基本类型
-//! [`_type_name`] 类型名字
+//! [`_type_name`] 类型名字，生成代码里可以直接使用的全路径
 //! [`substitute_type`] 替换泛型参数，在调用same_type之前就把泛型进行替换
 //! [`_same_type`]：这个是判断output_type能否通过某些CallType（比如Option、unwrap、&、*这种）转换成input_type
+//! [`_add_struct_literal_producers`]：字段全部可fuzz的公开结构体，用结构体字面量作为合成的生产者
+//! [`_add_default_producers`]：没有其他生产者、但是实现了Default的参数类型，用`<T as Default>::default`作为合成的生产者
+//! [`_add_from_str_producers`]：实现了FromStr的参数类型，从可以fuzz的`&str`解析出来
 
 use crate::clean::{self, GenericArg, GenericArgs, PrimitiveType};
 use crate::formats::cache::Cache;
+use crate::fuzz_targets_gen::api_function::{ApiAbi, ApiAsyncness, ApiFunction, ApiUnsafety};
+use crate::fuzz_targets_gen::api_graph::ApiGraph;
 use crate::fuzz_targets_gen::call_type::CallType;
+use crate::fuzz_targets_gen::emit_options::EmitOptions;
 use crate::fuzz_targets_gen::fuzz_type::{self, FuzzableCallType};
 use crate::fuzz_targets_gen::impl_util::FullNameMap;
+use crate::fuzz_targets_gen::invariant;
+use crate::fuzz_targets_gen::maybe_uninit;
 use crate::fuzz_targets_gen::prelude_type::{self, PreludeType};
-use rustc_data_structures::fx::FxHashMap;
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
+use rustc_hir::def_id::DefId;
 use rustc_hir::{self, Mutability};
+use rustc_middle::ty::Visibility;
+use rustc_span::Span;
 use thin_vec::ThinVec;
 
 /// ok
@@ -211,70 +222,217 @@ pub(crate) fn _type_name(
     cache: &Cache,
     full_name_map: &FullNameMap,
 ) -> String {
-    /*
-    if let Some(def_id) = &type_.def_id(cache) {
-        if let Some(full_name) = full_name_map._get_full_name(*def_id) {
-            return full_name.clone();
-        }
-    }*/
     match type_ {
-        clean::Type::Path { path } => {
-            let mut res = "".to_string();
-            for (idx, path_seg) in path.segments.iter().enumerate() {
-                res += path_seg.name.as_str();
-
-                if let GenericArgs::AngleBracketed { args, .. } = &path_seg.args {
-                    if args.len() > 0 {
-                        res += "<";
-                        for (index2, arg) in args.iter().enumerate() {
-                            match &arg {
-                                clean::GenericArg::Type(typ) => {
-                                    res += &_type_name(typ, cache, full_name_map);
-                                }
-                                GenericArg::Lifetime(life) => {
-                                    res += life.0.as_str();
-                                }
-                                GenericArg::Const(_) => todo!(),
-                                GenericArg::Infer => res += "_",
-                            }
-                            if index2 != args.len() - 1 {
-                                res += ", ";
-                            }
-                        }
-                        res += ">";
-                    }
-                }
-                if idx != path.segments.len() - 1 {
-                    res += "::";
-                }
-            }
-            res
-        }
+        clean::Type::Path { path } => _path_name(path, cache, full_name_map),
         clean::Type::Primitive(primitive_type) => primitive_type.as_sym().to_string(),
         clean::Type::Generic(generic) => generic.to_string(),
         clean::Type::BorrowedRef { type_, mutability, .. } => {
+            //生命周期都省略，生成的代码里没有声明这些生命周期参数
             let inner_type = &**type_;
             let inner_name = _type_name(inner_type, cache, full_name_map);
             let mut_str = if mutability.is_mut() { "mut " } else { "" };
             format!("&{}{}", mut_str, inner_name)
         }
+        clean::Type::RawPointer(mutability, type_) => {
+            let mut_str = if mutability.is_mut() { "mut" } else { "const" };
+            format!("*{} {}", mut_str, _type_name(type_, cache, full_name_map))
+        }
         clean::Type::Tuple(inner_types) => {
-            let inner_types_number = inner_types.len();
-            let mut res = "(".to_string();
-            for i in 0..inner_types_number {
-                let inner_type = &inner_types[i];
-                if i != 0 {
-                    res.push_str(" ,");
-                }
-                res.push_str(_type_name(inner_type, cache, full_name_map).as_str());
+            let inner_names = inner_types
+                .iter()
+                .map(|inner_type| _type_name(inner_type, cache, full_name_map))
+                .collect::<Vec<String>>();
+            //只有一个元素的元组需要结尾的逗号
+            if inner_names.len() == 1 {
+                format!("({},)", inner_names[0])
+            } else {
+                format!("({})", inner_names.join(", "))
             }
-            res.push(')');
-            res
         }
+        clean::Type::Slice(inner_type) => {
+            format!("[{}]", _type_name(inner_type, cache, full_name_map))
+        }
+        clean::Type::Array(inner_type, length) => {
+            format!("[{}; {}]", _type_name(inner_type, cache, full_name_map), length)
+        }
+        //关联类型总是写成<T as Trait>::Assoc的形式，避免歧义
+        clean::Type::QPath(qpath) => format!(
+            "<{} as {}>::{}",
+            _type_name(&qpath.self_type, cache, full_name_map),
+            _path_name(&qpath.trait_, cache, full_name_map),
+            qpath.assoc.name
+        ),
+        clean::Type::DynTrait(poly_traits, _) => {
+            let trait_names = poly_traits
+                .iter()
+                .map(|poly_trait| _path_name(&poly_trait.trait_, cache, full_name_map))
+                .collect::<Vec<String>>();
+            format!("dyn {}", trait_names.join(" + "))
+        }
+        clean::Type::BareFunction(bare_function) => {
+            let unsafe_str = match bare_function.unsafety {
+                rustc_hir::Unsafety::Unsafe => "unsafe ",
+                rustc_hir::Unsafety::Normal => "",
+            };
+            let input_names = _extract_input_types(&bare_function.decl.inputs)
+                .iter()
+                .map(|input_type| _type_name(input_type, cache, full_name_map))
+                .collect::<Vec<String>>();
+            let output_str = match _extract_output_type(&bare_function.decl.output) {
+                Some(output_type) => {
+                    format!(" -> {}", _type_name(&output_type, cache, full_name_map))
+                }
+                None => String::new(),
+            };
+            format!("{}fn({}){}", unsafe_str, input_names.join(", "), output_str)
+        }
+        //导入的序列里不知道具体类型的地方，交给编译器推断
+        clean::Type::Infer => "_".to_string(),
         _ => "Currently not supported".to_string(),
     }
 }
 
+/// 标准库类型定义所在的路径 -> 生成代码里使用的路径
+/// 定义所在的mod不一定是公开的（比如std::collections::hash::map），prelude里的类型直接用名字
+static STD_PUBLIC_PATHS: &'static [(&'static str, &'static str)] = &[
+    ("core::option::Option", "Option"),
+    ("core::result::Result", "Result"),
+    ("alloc::vec::Vec", "Vec"),
+    ("alloc::string::String", "String"),
+    ("alloc::boxed::Box", "Box"),
+    ("std::collections::hash::map::HashMap", "std::collections::HashMap"),
+    ("std::collections::hash::set::HashSet", "std::collections::HashSet"),
+    ("alloc::collections::btree::map::BTreeMap", "std::collections::BTreeMap"),
+    ("alloc::collections::btree::set::BTreeSet", "std::collections::BTreeSet"),
+    ("alloc::ffi::c_str::CString", "std::ffi::CString"),
+    ("core::ffi::c_str::CStr", "std::ffi::CStr"),
+    ("std::ffi::os_str::OsString", "std::ffi::OsString"),
+    ("std::ffi::os_str::OsStr", "std::ffi::OsStr"),
+    ("core::time::Duration", "std::time::Duration"),
+    ("core::mem::maybe_uninit::MaybeUninit", "std::mem::MaybeUninit"),
+    ("core::iter::traits::iterator::Iterator", "Iterator"),
+    ("core::iter::traits::collect::IntoIterator", "IntoIterator"),
+];
+
+/// 定义在私有子模块里、从上层模块重导出的std item：定义路径的前缀 -> 公开的模块
+static STD_PUBLIC_MODULES: &'static [(&'static str, &'static str)] = &[
+    ("core::iter::traits::", "std::iter"),
+    ("core::ops::", "std::ops"),
+    ("core::str::traits::", "std::str"),
+];
+
+/// 路径的名字，能找到定义的话用全路径，泛型参数只跟在最后一段后面
+pub(crate) fn _path_name(path: &clean::Path, cache: &Cache, full_name_map: &FullNameMap) -> String {
+    let full_name =
+        path.res.opt_def_id().and_then(|def_id| _def_path_name(def_id, cache, full_name_map));
+    let mut res = match full_name {
+        Some(full_name) => full_name,
+        None => path
+            .segments
+            .iter()
+            .map(|segment| segment.name.to_string())
+            .collect::<Vec<_>>()
+            .join("::"),
+    };
+    if let Some(last_segment) = path.segments.last() {
+        res.push_str(_generic_args_string(&last_segment.args, cache, full_name_map).as_str());
+    }
+    res
+}
+
+/// 生成代码里引用一个item（不带泛型参数）的路径，外部crate的item换成std下公开的路径
+pub(crate) fn _def_path_name(
+    def_id: DefId,
+    cache: &Cache,
+    full_name_map: &FullNameMap,
+) -> Option<String> {
+    let full_name = match full_name_map._get_full_name(def_id) {
+        Some(full_name) => full_name.clone(),
+        None => cache.external_paths.get(&def_id).map(|(strings, _)| {
+            strings.iter().map(|s| s.as_str()).collect::<Vec<_>>().join("::")
+        })?,
+    };
+    Some(_public_std_path(&full_name))
+}
+
+/// 外部crate的类型路径换成std下公开的路径
+fn _public_std_path(full_name: &String) -> String {
+    if let Some((_, public_path)) =
+        STD_PUBLIC_PATHS.iter().find(|(definition_path, _)| *definition_path == full_name.as_str())
+    {
+        return public_path.to_string();
+    }
+    for (definition_prefix, public_module) in STD_PUBLIC_MODULES {
+        if full_name.starts_with(definition_prefix) {
+            let item_name = full_name.rsplit("::").next().unwrap();
+            return format!("{}::{}", public_module, item_name);
+        }
+    }
+    for facade in ["core::", "alloc::"] {
+        if let Some(rest) = full_name.strip_prefix(facade) {
+            return format!("std::{}", rest);
+        }
+    }
+    full_name.clone()
+}
+
+/// 尖括号里的泛型参数，生命周期参数省略，关联类型约束写成Item = T
+fn _generic_args_string(
+    generic_args: &GenericArgs,
+    cache: &Cache,
+    full_name_map: &FullNameMap,
+) -> String {
+    match generic_args {
+        GenericArgs::AngleBracketed { args, bindings } => {
+            let mut arg_strings = Vec::new();
+            for arg in args.iter() {
+                match arg {
+                    GenericArg::Type(typ) => {
+                        arg_strings.push(_type_name(typ, cache, full_name_map))
+                    }
+                    GenericArg::Lifetime(_) => {}
+                    GenericArg::Const(constant) => arg_strings.push(match &constant.kind {
+                        clean::ConstantKind::TyConst { expr } => expr.to_string(),
+                        //没有tcx求不出表达式，交给编译器推断
+                        _ => "_".to_string(),
+                    }),
+                    GenericArg::Infer => arg_strings.push("_".to_string()),
+                }
+            }
+            for binding in bindings.iter() {
+                if let clean::TypeBindingKind::Equality { term: clean::Term::Type(typ) } =
+                    &binding.kind
+                {
+                    arg_strings.push(format!(
+                        "{} = {}",
+                        binding.assoc.name,
+                        _type_name(typ, cache, full_name_map)
+                    ));
+                }
+            }
+            if arg_strings.is_empty() {
+                String::new()
+            } else {
+                format!("<{}>", arg_strings.join(", "))
+            }
+        }
+        GenericArgs::Parenthesized { inputs, output } => {
+            let input_names = inputs
+                .iter()
+                .map(|input_type| _type_name(input_type, cache, full_name_map))
+                .collect::<Vec<String>>();
+            match output {
+                Some(output_type) => format!(
+                    "({}) -> {}",
+                    input_names.join(", "),
+                    _type_name(output_type, cache, full_name_map)
+                ),
+                None => format!("({})", input_names.join(", ")),
+            }
+        }
+    }
+}
+
 /// 重要，把泛型类型中的泛型参数都替换
 /// 如果替换不成功返回None
 #[allow(dead_code)]
@@ -407,6 +565,40 @@ pub(crate) fn _same_type_hard_mode(
         return CallType::_DirectCall;
     }
 
+    //输入类型是trait object，用实现了这个trait的类型转换过去
+    if let Some(call_type) =
+        _trait_object_in_same_type(output_type, input_type, cache, full_name_map)
+    {
+        return call_type;
+    }
+
+    //输入类型是&U，输出类型经过Deref能得到U
+    if let Some(call_type) = _deref_in_same_type(output_type, input_type, full_name_map) {
+        return call_type;
+    }
+
+    //输入类型是&[T]或者&mut [T]，输出类型是[T; N]或者Vec<T>
+    if let Some(call_type) = _slice_in_same_type(output_type, input_type) {
+        return call_type;
+    }
+
+    //输入类型是&U，输出类型实现了AsRef<U>或者Borrow<U>
+    if let Some(call_type) = _as_ref_in_same_type(output_type, input_type, cache, full_name_map) {
+        return call_type;
+    }
+
+    //输入类型B实现了From<A>，输出类型是A
+    if let Some(call_type) = _into_in_same_type(output_type, input_type, cache, full_name_map, 0) {
+        return call_type;
+    }
+
+    //输入类型是Box<T>、Rc<T>、Arc<T>、Pin<&mut T>这样的智能指针
+    if let Some(call_type) =
+        _smart_pointer_in_same_type(output_type, input_type, cache, full_name_map)
+    {
+        return call_type;
+    }
+
     // 输入类型如果是
     // 1. 引用
     // 2. 原生指针
@@ -476,6 +668,311 @@ pub(crate) fn _same_type_hard_mode(
     }
 }
 
+/// 输入类型是&dyn Trait、&mut dyn Trait或者Box<dyn Trait>的情况，不是trait object时返回None。
+/// 输出类型是trait的某个公开实现类型时，取引用或者装箱之后转换成trait object。
+/// 只检查第一个trait，后面的一般是Send、Sync这样的auto trait，索引里没有记录
+fn _trait_object_in_same_type(
+    output_type: &clean::Type,
+    input_type: &clean::Type,
+    cache: &Cache,
+    full_name_map: &FullNameMap,
+) -> Option<CallType> {
+    let (dyn_type, mutability) = match input_type {
+        clean::Type::BorrowedRef { mutability, type_, .. } => (&**type_, Some(*mutability)),
+        clean::Type::Path { path } if path.last().as_str() == "Box" => {
+            match path.segments.last().map(|segment| &segment.args) {
+                Some(GenericArgs::AngleBracketed { args, .. }) if args.len() == 1 => {
+                    match &args[0] {
+                        GenericArg::Type(inner_type) => (inner_type, None),
+                        _ => return None,
+                    }
+                }
+                _ => return None,
+            }
+        }
+        _ => return None,
+    };
+    let poly_trait = match dyn_type {
+        clean::Type::DynTrait(poly_traits, _) => poly_traits.first()?,
+        _ => return None,
+    };
+    if !matches!(output_type, clean::Type::Path { .. }) {
+        return Some(CallType::_NotCompatible);
+    }
+    //impl块里的类型和函数签名里的写法可能不同，按全路径比较
+    let output_name = _type_name(output_type, cache, full_name_map);
+    let implemented = full_name_map
+        ._get_implementors(poly_trait.trait_.def_id())
+        .iter()
+        .any(|implementor| _type_name(implementor, cache, full_name_map) == output_name);
+    if !implemented {
+        return Some(CallType::_NotCompatible);
+    }
+    let inner_call_type = match mutability {
+        Some(Mutability::Not) => CallType::_BorrowedRef(Box::new(CallType::_DirectCall)),
+        Some(Mutability::Mut) => CallType::_MutBorrowedRef(Box::new(CallType::_DirectCall)),
+        None => CallType::_DirectCall,
+    };
+    let target = _type_name(input_type, cache, full_name_map);
+    Some(CallType::_DynCoerce(Box::new(inner_call_type), target))
+}
+
+/// 输入类型是Box<T>、Rc<T>或者Arc<T>（`self: Box<Self>`这样的receiver）时，
+/// 输出类型能转换成T的话再包装一层；Pin<&mut T>、Pin<&T>用Pin::new，要求T实现了Unpin。
+/// 输出类型本身就是这个智能指针的情况前面已经直接匹配了，不是智能指针或者转换不了时返回None
+fn _smart_pointer_in_same_type(
+    output_type: &clean::Type,
+    input_type: &clean::Type,
+    cache: &Cache,
+    full_name_map: &FullNameMap,
+) -> Option<CallType> {
+    let clean::Type::Path { path } = input_type else {
+        return None;
+    };
+    let pointer_name = path.last();
+    let pointer = pointer_name.as_str();
+    if !["Box", "Rc", "Arc", "Pin"].contains(&pointer) {
+        return None;
+    }
+    let inner_type = match path.segments.last().map(|segment| &segment.args) {
+        Some(GenericArgs::AngleBracketed { args, .. }) => match &args[..] {
+            [GenericArg::Type(inner_type)] => inner_type,
+            _ => return None,
+        },
+        _ => return None,
+    };
+    if pointer == "Pin" {
+        let clean::Type::BorrowedRef { type_: pointee, .. } = inner_type else {
+            return None;
+        };
+        if !_implements_unpin(pointee, cache, full_name_map) {
+            return None;
+        }
+    }
+    let inner_call_type = _same_type_hard_mode(output_type, inner_type, cache, full_name_map);
+    if inner_call_type == CallType::_NotCompatible {
+        return None;
+    }
+    let inner_call_type = Box::new(inner_call_type);
+    Some(match pointer {
+        "Box" => CallType::_ToBox(inner_call_type),
+        "Rc" => CallType::_ToRc(inner_call_type),
+        "Arc" => CallType::_ToArc(inner_call_type),
+        _ => CallType::_PinNew(inner_call_type),
+    })
+}
+
+/// Deref链最多走几步
+static MAX_DEREF_DEPTH: usize = 3;
+
+/// 输入类型是&U，输出类型T（或者&T）经过若干次Deref能得到U的时候，写成`&*value`，每多一步多一个`*`。
+/// 比如String -> &str、Vec<u8> -> &[u8]、Box<T> -> &T，以及本crate里实现了Deref的类型
+fn _deref_in_same_type(
+    output_type: &clean::Type,
+    input_type: &clean::Type,
+    full_name_map: &FullNameMap,
+) -> Option<CallType> {
+    let clean::Type::BorrowedRef { mutability: Mutability::Not, type_: target_type, .. } =
+        input_type
+    else {
+        return None;
+    };
+    //输出类型是引用的时候先解一次引用
+    let (mut current_type, mut depth) = match output_type {
+        clean::Type::BorrowedRef { type_, .. } => ((**type_).clone(), 1),
+        _ => (output_type.clone(), 0),
+    };
+    for _ in 0..MAX_DEREF_DEPTH {
+        current_type = _deref_target(&current_type, full_name_map)?;
+        depth = depth + 1;
+        if current_type == **target_type
+            || _resolved_path_equal_without_lifetime(&current_type, target_type)
+        {
+            return Some(CallType::_DerefCoerce(Box::new(CallType::_DirectCall), depth));
+        }
+    }
+    None
+}
+
+/// 类型Deref之后得到的类型：std里的String、Vec、Box、Rc、Arc，以及FullNameMap::deref_targets里记录的本crate类型
+fn _deref_target(type_: &clean::Type, full_name_map: &FullNameMap) -> Option<clean::Type> {
+    let clean::Type::Path { path } = type_ else {
+        return None;
+    };
+    let def_id = path.res.opt_def_id()?;
+    if let Some(target_type) = full_name_map._get_deref_target(def_id) {
+        return Some(target_type.clone());
+    }
+    //本crate里同名的类型不算
+    if def_id.is_local() {
+        return None;
+    }
+    let type_args = path.generics().unwrap_or_default();
+    match path.last().as_str() {
+        "String" => Some(clean::Type::Primitive(PrimitiveType::Str)),
+        "Vec" => Some(clean::Type::Slice(Box::new((*type_args.first()?).clone()))),
+        "Box" | "Rc" | "Arc" => Some((*type_args.first()?).clone()),
+        _ => None,
+    }
+}
+
+/// 输入类型是&[T]或者&mut [T]，输出类型是数组[T; N]、Vec<T>（或者它们的引用）时借用过去，
+/// 传参时会自动unsize或者DerefMut成切片。Vec<T>到&[T]已经在_deref_in_same_type里处理了
+fn _slice_in_same_type(output_type: &clean::Type, input_type: &clean::Type) -> Option<CallType> {
+    let clean::Type::BorrowedRef { mutability, type_: input_inner_type, .. } = input_type else {
+        return None;
+    };
+    let clean::Type::Slice(element_type) = &**input_inner_type else {
+        return None;
+    };
+    let is_element = |type_: &clean::Type| {
+        type_ == &**element_type || _resolved_path_equal_without_lifetime(type_, element_type)
+    };
+    //输出类型里的元素类型，不是数组或者Vec的时候返回None
+    let output_element = |type_: &clean::Type| match type_ {
+        clean::Type::Array(inner_type, _) => Some((**inner_type).clone()),
+        clean::Type::Path { path } if path.last().as_str() == "Vec" => {
+            let def_id = path.res.opt_def_id()?;
+            if def_id.is_local() {
+                return None;
+            }
+            path.generics().and_then(|type_args| type_args.first().map(|arg| (*arg).clone()))
+        }
+        _ => None,
+    };
+    match output_type {
+        //&mut [T; N]也可以传给&[T]
+        clean::Type::BorrowedRef { mutability: output_mutability, type_, .. } => {
+            let element = output_element(type_)?;
+            if !is_element(&element) {
+                return None;
+            }
+            if *mutability == Mutability::Mut && *output_mutability == Mutability::Not {
+                return Some(CallType::_NotCompatible);
+            }
+            Some(CallType::_DirectCall)
+        }
+        _ => {
+            let element = output_element(output_type)?;
+            if !is_element(&element) {
+                return None;
+            }
+            let inner_call_type = Box::new(CallType::_DirectCall);
+            match mutability {
+                Mutability::Not => Some(CallType::_BorrowedRef(inner_call_type)),
+                Mutability::Mut => Some(CallType::_MutBorrowedRef(inner_call_type)),
+            }
+        }
+    }
+}
+
+/// std里常用的AsRef impl：类型 -> 可以as_ref得到的类型，Deref能得到的（String -> str这样的）不用列
+static STD_AS_REF_TARGETS: &'static [(&'static str, &'static [&'static str])] = &[
+    ("String", &["[u8]", "std::ffi::OsStr", "std::path::Path"]),
+    ("str", &["[u8]", "std::ffi::OsStr", "std::path::Path"]),
+    ("std::path::PathBuf", &["std::ffi::OsStr"]),
+    ("std::ffi::OsString", &["std::path::Path"]),
+    ("std::ffi::OsStr", &["std::path::Path"]),
+];
+
+/// 输入类型是&U，输出类型T（或者&T）实现了AsRef<U>或者Borrow<U>的时候，写成`.as_ref()`/`.borrow()`。
+/// 本crate的impl见FullNameMap::as_ref_targets和borrow_targets，std的见STD_AS_REF_TARGETS
+fn _as_ref_in_same_type(
+    output_type: &clean::Type,
+    input_type: &clean::Type,
+    cache: &Cache,
+    full_name_map: &FullNameMap,
+) -> Option<CallType> {
+    let clean::Type::BorrowedRef { mutability: Mutability::Not, type_: target_type, .. } =
+        input_type
+    else {
+        return None;
+    };
+    let source_type = match output_type {
+        clean::Type::BorrowedRef { type_, .. } => &**type_,
+        _ => output_type,
+    };
+    let is_target = |type_: &clean::Type| {
+        type_ == &**target_type || _resolved_path_equal_without_lifetime(type_, target_type)
+    };
+    if let Some(def_id) = source_type.def_id(cache) && def_id.is_local() {
+        if full_name_map._get_as_ref_targets(def_id).iter().any(|type_| is_target(type_)) {
+            return Some(CallType::_AsRef(Box::new(CallType::_DirectCall)));
+        }
+        if full_name_map._get_borrow_targets(def_id).iter().any(|type_| is_target(type_)) {
+            return Some(CallType::_Borrow(Box::new(CallType::_DirectCall)));
+        }
+        return None;
+    }
+    let source_name = _type_name(source_type, cache, full_name_map);
+    let target_name = _type_name(target_type, cache, full_name_map);
+    STD_AS_REF_TARGETS
+        .iter()
+        .any(|(source, targets)| *source == source_name && targets.contains(&target_name.as_str()))
+        .then(|| CallType::_AsRef(Box::new(CallType::_DirectCall)))
+}
+
+/// From转换最多连续几次
+static MAX_INTO_DEPTH: usize = 2;
+
+/// 输入类型是本crate的类型B，B实现了From<A>，输出类型是A（或者经过不超过MAX_INTO_DEPTH次From转换能得到A）。
+/// into会拿走输出的值，所以只考虑按值传递
+fn _into_in_same_type(
+    output_type: &clean::Type,
+    input_type: &clean::Type,
+    cache: &Cache,
+    full_name_map: &FullNameMap,
+    depth: usize,
+) -> Option<CallType> {
+    if depth >= MAX_INTO_DEPTH {
+        return None;
+    }
+    let clean::Type::Path { path } = input_type else {
+        return None;
+    };
+    let def_id = path.res.opt_def_id()?;
+    let target = _type_name(input_type, cache, full_name_map);
+    for source_type in full_name_map._get_from_sources(def_id) {
+        if source_type == output_type
+            || _resolved_path_equal_without_lifetime(source_type, output_type)
+        {
+            return Some(CallType::_IntoConversion(Box::new(CallType::_DirectCall), target));
+        }
+    }
+    for source_type in full_name_map._get_from_sources(def_id) {
+        if let Some(inner_call_type) =
+            _into_in_same_type(output_type, source_type, cache, full_name_map, depth + 1)
+        {
+            return Some(CallType::_IntoConversion(Box::new(inner_call_type), target));
+        }
+    }
+    None
+}
+
+/// 类型是否实现了Unpin，rustdoc合成的auto trait impl记在FullNameMap::implementors里
+fn _implements_unpin(type_: &clean::Type, cache: &Cache, full_name_map: &FullNameMap) -> bool {
+    if matches!(type_, clean::Type::Primitive(..)) {
+        return true;
+    }
+    let unpin_def_id = cache.external_paths.iter().find_map(|(def_id, (path, _))| {
+        if path.iter().map(|symbol| symbol.as_str()).eq(UNPIN_TRAIT.split("::")) {
+            Some(*def_id)
+        } else {
+            None
+        }
+    });
+    let Some(unpin_def_id) = unpin_def_id else {
+        return false;
+    };
+    let type_name = _type_name(type_, cache, full_name_map);
+    full_name_map
+        ._get_implementors(unpin_def_id)
+        .iter()
+        .any(|implementor| _type_name(implementor, cache, full_name_map) == type_name)
+}
+
+static UNPIN_TRAIT: &'static str = "core::marker::Unpin";
+
 /// ok
 /// test if types are the same type
 /// 输出类型是ResolvedPath的情况
@@ -695,6 +1192,28 @@ pub(crate) fn _raw_pointer_in_same_type(
     full_name_map: &FullNameMap,
 ) -> CallType {
     let inner_type = &**type_;
+    //输出类型是MaybeUninit<T>的槽位，传给*mut T出参
+    if mutability.is_mut()
+        && maybe_uninit::uninit_inner_type(output_type, cache, full_name_map)
+            .map_or(false, |slot_type| slot_type == inner_type)
+    {
+        return CallType::_UninitAsMutPtr(Box::new(CallType::_DirectCall));
+    }
+    //输出类型是&T或者&mut T，直接转换成裸指针。&T只能转换成*const T
+    if let clean::Type::BorrowedRef { mutability: ref_mutability, type_: ref_type, .. } =
+        output_type
+    {
+        if ref_mutability.is_mut() || !mutability.is_mut() {
+            let inner_compatible = _same_type_hard_mode(ref_type, inner_type, cache, full_name_map);
+            if let CallType::_DirectCall = inner_compatible {
+                return CallType::_RefToRawPointer(
+                    Box::new(inner_compatible),
+                    inner_type.clone(),
+                    mutability.is_mut(),
+                );
+            }
+        }
+    }
     let inner_compatible = _same_type_hard_mode(output_type, inner_type, cache, full_name_map);
     match &inner_compatible {
         CallType::_NotCompatible => {
@@ -827,9 +1346,11 @@ pub(crate) fn is_fuzzable_type(
     ty_: &clean::Type,
     cache: &Cache,
     full_name_map: &FullNameMap,
+    emit_options: &EmitOptions,
     substitution: Option<&FxHashMap<String, clean::Type>>,
 ) -> bool {
-    let fuzzable = fuzz_type::fuzzable_call_type(ty_, cache, full_name_map, substitution);
+    let fuzzable =
+        fuzz_type::fuzzable_call_type(ty_, cache, full_name_map, emit_options, substitution);
     match fuzzable {
         FuzzableCallType::NoFuzzable => false,
         _ => true,
@@ -849,9 +1370,14 @@ pub(crate) fn _is_mutable_borrow_occurs(input_type_: &clean::Type, call_type: &C
                 match call_type {
                     CallType::_DirectCall
                     | CallType::_MutBorrowedRef(..)
-                    | CallType::_MutRawPointer(..) => {
+                    | CallType::_MutRawPointer(..)
+                    | CallType::_RefToRawPointer(_, _, true)
+                    | CallType::_UninitAsMutPtr(..) => {
                         return true;
                     }
+                    CallType::_DynCoerce(inner, _) => {
+                        return matches!(**inner, CallType::_MutBorrowedRef(..));
+                    }
                     _ => {}
                 }
             }
@@ -868,9 +1394,16 @@ pub(crate) fn _is_immutable_borrow_occurs(input_type: &clean::Type, call_type: &
                 match call_type {
                     CallType::_DirectCall
                     | CallType::_BorrowedRef(..)
-                    | CallType::_ConstRawPointer(..) => {
+                    | CallType::_ConstRawPointer(..)
+                    | CallType::_RefToRawPointer(_, _, false)
+                    | CallType::_DerefCoerce(..)
+                    | CallType::_AsRef(..)
+                    | CallType::_Borrow(..) => {
                         return true;
                     }
+                    CallType::_DynCoerce(inner, _) => {
+                        return matches!(**inner, CallType::_BorrowedRef(..));
+                    }
                     _ => {}
                 }
             }
@@ -882,7 +1415,26 @@ pub(crate) fn _is_immutable_borrow_occurs(input_type: &clean::Type, call_type: &
 
 pub(crate) fn _need_mut_tag(call_type: &CallType) -> bool {
     match call_type {
-        CallType::_MutBorrowedRef(..) | CallType::_MutRawPointer(..) => true,
+        //可变借用的是转换出来的临时值，变量本身不需要mut
+        CallType::_MutBorrowedRef(inner)
+            if matches!(
+                **inner,
+                CallType::_CustomConvert(..)
+                    | CallType::_ToCursor(..)
+                    | CallType::_NewWriter
+                    | CallType::_ToDuration(..)
+                    | CallType::_ToSystemTime(..)
+                    | CallType::_InstantNow
+                    | CallType::_ToOwned(..)
+                    | CallType::_ToVec(..)
+            ) =>
+        {
+            false
+        }
+        CallType::_MutBorrowedRef(..)
+        | CallType::_MutRawPointer(..)
+        | CallType::_UninitAsMutPtr(..) => true,
+        CallType::_DynCoerce(inner, _) | CallType::_PinNew(inner) => _need_mut_tag(inner),
         _ => false,
     }
 }
@@ -958,3 +1510,308 @@ fn new_segments_without_lifetime(
     }
     new_segments_without_lifetime
 }
+
+/// 可以用结构体字面量构造的公开结构体：没有泛型参数，不是non_exhaustive，所有字段都是公开的
+#[derive(Debug, Clone)]
+pub(crate) struct StructLiteral {
+    pub(crate) def_id: DefId,
+    /// 结构体的全路径，同时也是合成函数的名字
+    pub(crate) full_name: String,
+    /// (字段名, 字段类型)，元组结构体的字段名是`0`、`1`...
+    pub(crate) fields: Vec<(String, clean::Type)>,
+    pub(crate) visibility: Visibility,
+    pub(crate) span: Option<Span>,
+}
+
+/// 字段全部可以fuzz的结构体加一个合成的生产者函数，参数是各个字段，返回值是结构体本身，
+/// 生成代码的时候写成结构体字面量，见_struct_literal_string。
+/// 同一个结构体在不同的函数签名里可能写成不同的路径（比如`Config`和`crate::Config`），
+/// 依赖分析按写法比较类型，所以参数里出现的每种写法各加一个生产者；没有函数用到的结构体不加
+pub(crate) fn _add_struct_literal_producers(
+    api_graph: &mut ApiGraph<'_>,
+    struct_literals: Vec<StructLiteral>,
+) {
+    for struct_literal in struct_literals {
+        let all_fuzzable = struct_literal.fields.iter().all(|(_, field_type)| {
+            is_fuzzable_type(
+                field_type,
+                api_graph.cache,
+                &api_graph.full_name_map,
+                &api_graph.emit_options,
+                None,
+            )
+        });
+        if !all_fuzzable {
+            continue;
+        }
+        let mut output_types: Vec<clean::Type> = Vec::new();
+        for api_function in &api_graph.api_functions {
+            for input_type in &api_function.inputs {
+                let inner_type = match input_type {
+                    clean::Type::BorrowedRef { type_, .. } => &**type_,
+                    _ => input_type,
+                };
+                if let clean::Type::Path { path } = inner_type {
+                    if path.def_id() == struct_literal.def_id
+                        && !output_types.iter().any(|output_type| {
+                            _resolved_path_equal_without_lifetime(output_type, inner_type)
+                        })
+                    {
+                        output_types.push(inner_type.clone());
+                    }
+                }
+            }
+        }
+        if output_types.is_empty() {
+            continue;
+        }
+        api_graph.struct_literals.insert(
+            struct_literal.full_name.clone(),
+            struct_literal.fields.iter().map(|(field_name, _)| field_name.clone()).collect(),
+        );
+        for output_type in output_types {
+            api_graph.add_api_function(ApiFunction {
+                full_name: struct_literal.full_name.clone(),
+                _generics: clean::Generics {
+                    params: ThinVec::new(),
+                    where_predicates: ThinVec::new(),
+                },
+                generic_substitutions: FxHashMap::default(),
+                inputs: struct_literal
+                    .fields
+                    .iter()
+                    .map(|(_, field_type)| field_type.clone())
+                    .collect(),
+                output: Some(output_type),
+                _trait_full_path: None,
+                _unsafe_tag: ApiUnsafety::Normal,
+                _abi: ApiAbi::Rust,
+                _asyncness: ApiAsyncness::NotAsync,
+                visibility: struct_literal.visibility,
+                span: struct_literal.span,
+                def_id: None,
+                _uses_unsafe: false,
+                complexity: None,
+                trait_dispatch: None,
+            });
+        }
+    }
+}
+
+/// 合成的生产者函数对应的结构体字面量，params和字段一一对应
+pub(crate) fn _struct_literal_string(
+    full_name: &str,
+    field_names: &[String],
+    params: &[String],
+) -> String {
+    let fields = field_names
+        .iter()
+        .zip(params)
+        .map(|(field_name, param)| format!("{}: {}", field_name, param))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!("{} {{ {} }}", full_name, fields)
+}
+
+/// Default trait的全路径，记录在ApiGraph::type_trait_impls里
+pub(crate) static DEFAULT_TRAIT: &'static str = "core::default::Default";
+
+/// 实现了Default、但是过滤后的API里没有任何函数能产生的参数类型，加一个合成的生产者`<T as Default>::default`，
+/// 没有参数，返回值是T，生成代码的时候直接按函数名调用，函数名记录在ApiGraph::default_producers里。
+/// 已经有函数（包括结构体字面量的合成生产者）返回这个类型的时候不加，避免序列总是选到默认值。
+/// 要在filter_functions之后、find_all_dependencies之前调用，依赖边由find_all_dependencies找出来
+pub(crate) fn _add_default_producers(api_graph: &mut ApiGraph<'_>) {
+    let cache = api_graph.cache;
+    let produced_types: FxHashSet<String> = api_graph
+        .api_functions
+        .iter()
+        .filter_map(|api_function| api_function.output.as_ref())
+        .filter_map(|output_type| {
+            invariant::type_full_path(output_type, cache, &api_graph.full_name_map)
+        })
+        .collect();
+    let mut output_types: Vec<(String, clean::Type)> = Vec::new();
+    for api_function in &api_graph.api_functions {
+        for input_type in &api_function.inputs {
+            let inner_type = match input_type {
+                clean::Type::BorrowedRef { type_, .. } => &**type_,
+                _ => input_type,
+            };
+            if !matches!(inner_type, clean::Type::Path { .. })
+                || _is_generic_type(inner_type)
+                || prelude_type::_prelude_type_need_special_dealing(
+                    inner_type,
+                    cache,
+                    &api_graph.full_name_map,
+                )
+                || is_fuzzable_type(
+                    inner_type,
+                    cache,
+                    &api_graph.full_name_map,
+                    &api_graph.emit_options,
+                    None,
+                )
+            {
+                continue;
+            }
+            let type_path =
+                match invariant::type_full_path(inner_type, cache, &api_graph.full_name_map) {
+                    Some(type_path) => type_path,
+                    None => continue,
+                };
+            if produced_types.contains(&type_path)
+                || !api_graph.type_implements_trait(&type_path, DEFAULT_TRAIT)
+                || output_types.iter().any(|(_, output_type)| {
+                    _resolved_path_equal_without_lifetime(output_type, inner_type)
+                })
+            {
+                continue;
+            }
+            //带生命周期参数的类型名不能直接写在限定路径里
+            let type_name = _type_name(inner_type, cache, &api_graph.full_name_map);
+            if type_name.contains('\'') {
+                continue;
+            }
+            output_types.push((type_name, inner_type.clone()));
+        }
+    }
+    let added = output_types.len();
+    for (type_name, output_type) in output_types {
+        let full_name = format!("<{} as Default>::default", type_name);
+        api_graph.default_producers.insert(full_name.clone());
+        api_graph.add_api_function(ApiFunction {
+            full_name,
+            _generics: clean::Generics { params: ThinVec::new(), where_predicates: ThinVec::new() },
+            generic_substitutions: FxHashMap::default(),
+            inputs: Vec::new(),
+            output: Some(output_type),
+            _trait_full_path: None,
+            _unsafe_tag: ApiUnsafety::Normal,
+            _abi: ApiAbi::Rust,
+            _asyncness: ApiAsyncness::NotAsync,
+            visibility: Visibility::Public,
+            span: None,
+            def_id: None,
+            _uses_unsafe: false,
+            complexity: None,
+            trait_dispatch: None,
+        });
+    }
+    println!("{} default producers added", added);
+}
+
+/// 类型是否实现了Default：基本的数值类型、bool、char，以及type_trait_impls里记录了Default的类型
+pub(crate) fn _implements_default(type_: &clean::Type, api_graph: &ApiGraph<'_>) -> bool {
+    match type_ {
+        clean::Type::Primitive(primitive_type) => !matches!(
+            primitive_type,
+            PrimitiveType::Str
+                | PrimitiveType::Slice
+                | PrimitiveType::Array
+                | PrimitiveType::Tuple
+                | PrimitiveType::RawPointer
+                | PrimitiveType::Reference
+                | PrimitiveType::Fn
+                | PrimitiveType::Never
+        ),
+        clean::Type::Path { .. } => {
+            match invariant::type_full_path(type_, api_graph.cache, &api_graph.full_name_map) {
+                Some(type_path) => api_graph.type_implements_trait(&type_path, DEFAULT_TRAIT),
+                None => false,
+            }
+        }
+        _ => false,
+    }
+}
+
+/// FromStr trait的全路径，记录在ApiGraph::type_trait_impls里
+pub(crate) static FROM_STR_TRAIT: &'static str = "core::str::traits::FromStr";
+
+/// 标准库里实现了FromStr的类型，它们的impl不在被测crate的cache里，type_trait_impls记录不到
+static STD_FROM_STR_TYPES: &[&str] = &[
+    "std::net::ip_addr::IpAddr",
+    "std::net::ip_addr::Ipv4Addr",
+    "std::net::ip_addr::Ipv6Addr",
+    "std::net::socket_addr::SocketAddr",
+    "std::net::socket_addr::SocketAddrV4",
+    "std::net::socket_addr::SocketAddrV6",
+];
+
+/// 实现了FromStr的参数类型加一个合成的生产者，参数是可以fuzz的`&str`，返回值是解析出来的类型，
+/// 生成代码的时候写成`.parse::<T>()`，解析失败的输入直接结束这次执行，见UnwrapStrategy::parse_expression。
+/// 和Default的生产者不同，已经有别的生产者的类型也加，解析往往能覆盖构造函数走不到的输入
+pub(crate) fn _add_from_str_producers(api_graph: &mut ApiGraph<'_>) {
+    let cache = api_graph.cache;
+    let mut output_types: Vec<(String, clean::Type)> = Vec::new();
+    for api_function in &api_graph.api_functions {
+        for input_type in &api_function.inputs {
+            let inner_type = match input_type {
+                clean::Type::BorrowedRef { type_, .. } => &**type_,
+                _ => input_type,
+            };
+            if !matches!(inner_type, clean::Type::Path { .. })
+                || _is_generic_type(inner_type)
+                || prelude_type::_prelude_type_need_special_dealing(
+                    inner_type,
+                    cache,
+                    &api_graph.full_name_map,
+                )
+                || is_fuzzable_type(
+                    inner_type,
+                    cache,
+                    &api_graph.full_name_map,
+                    &api_graph.emit_options,
+                    None,
+                )
+            {
+                continue;
+            }
+            let type_path =
+                match invariant::type_full_path(inner_type, cache, &api_graph.full_name_map) {
+                    Some(type_path) => type_path,
+                    None => continue,
+                };
+            if !api_graph.type_implements_trait(&type_path, FROM_STR_TRAIT)
+                && !STD_FROM_STR_TYPES.contains(&type_path.as_str())
+            {
+                continue;
+            }
+            let type_name = _type_name(inner_type, cache, &api_graph.full_name_map);
+            if type_name.contains('\'')
+                || output_types.iter().any(|(_, output_type)| {
+                    _resolved_path_equal_without_lifetime(output_type, inner_type)
+                })
+            {
+                continue;
+            }
+            output_types.push((type_name, inner_type.clone()));
+        }
+    }
+    let added = output_types.len();
+    for (type_name, output_type) in output_types {
+        let full_name = format!("<{} as core::str::FromStr>::from_str", type_name);
+        api_graph.from_str_producers.insert(full_name.clone(), type_name);
+        api_graph.add_api_function(ApiFunction {
+            full_name,
+            _generics: clean::Generics { params: ThinVec::new(), where_predicates: ThinVec::new() },
+            generic_substitutions: FxHashMap::default(),
+            inputs: vec![clean::Type::BorrowedRef {
+                lifetime: None,
+                mutability: Mutability::Not,
+                type_: Box::new(clean::Type::Primitive(PrimitiveType::Str)),
+            }],
+            output: Some(output_type),
+            _trait_full_path: None,
+            _unsafe_tag: ApiUnsafety::Normal,
+            _abi: ApiAbi::Rust,
+            _asyncness: ApiAsyncness::NotAsync,
+            visibility: Visibility::Public,
+            span: None,
+            def_id: None,
+            _uses_unsafe: false,
+            complexity: None,
+            trait_dispatch: None,
+        });
+    }
+    println!("{} from_str producers added", added);
+}
@@ -0,0 +1,45 @@
+//! async fn的支持
+//! async fn的返回值是Future，要await之后才能交给后面的调用，所以包含async fn的序列里，
+//! 测试函数的函数体整个放到一个async块里，由运行时block_on执行，每个async fn的调用后面加上.await。
+//! 默认用futures::executor，--async-runtime tokio可以换成tokio的单线程运行时，
+//! 有些crate的async fn依赖tokio的reactor，用futures::executor会直接panic
+
+/// 执行async块的运行时，生成的fuzz项目需要依赖对应的crate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum AsyncRuntime {
+    #[default]
+    Futures,
+    Tokio,
+}
+
+impl AsyncRuntime {
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "futures" => Some(AsyncRuntime::Futures),
+            "tokio" => Some(AsyncRuntime::Tokio),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn extern_crate_line(&self) -> &'static str {
+        match self {
+            AsyncRuntime::Futures => "extern crate futures;\n",
+            AsyncRuntime::Tokio => "extern crate tokio;\n",
+        }
+    }
+
+    /// 执行async块的表达式的开头，后面接async块和`)`
+    pub(crate) fn block_on_prefix(&self) -> &'static str {
+        match self {
+            AsyncRuntime::Futures => "futures::executor::block_on(",
+            AsyncRuntime::Tokio => {
+                "tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap().block_on("
+            }
+        }
+    }
+}
+
+/// async fn的调用后面加上.await
+pub(crate) fn await_if_async(call_string: String, is_async: bool) -> String {
+    if is_async { format!("{}.await", call_string) } else { call_string }
+}
@@ -0,0 +1,107 @@
+//! afl以外的fuzz引擎
+//! afl的fuzz target总是会生成（cargo fries依赖它们），--fuzz-backend可以为同样的序列额外生成其他引擎的target，
+//! 每个引擎的target放在测试目录下单独的cargo工程里，比如`hfuzz_project/src/bin/...`，
+//! 工程的Cargo.toml也一起生成，每个target是一个bin：
+//! 1. Honggfuzz：honggfuzz-rs，用`cargo hfuzz run <bin>`运行，解码参数的代码和afl的一样，只是换成honggfuzz的fuzz!
+//! 2. Bolero：同一个target可以用libfuzzer、afl、honggfuzz或者kani运行，见bolero_util.rs，
+//!    bolero的target是不带测试框架的test，放在`bolero_project/tests/...`
+
+use crate::fuzz_targets_gen::api_graph::ApiGraph;
+use crate::fuzz_targets_gen::api_sequence::ApiSequence;
+use crate::fuzz_targets_gen::bolero_util;
+use crate::fuzz_targets_gen::cargo_project::{self, ProjectTarget};
+use crate::fuzz_targets_gen::encoding::EncodingVariant;
+
+/// afl以外的fuzz引擎
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum FuzzBackend {
+    Honggfuzz,
+    Bolero,
+}
+
+impl FuzzBackend {
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "honggfuzz" => Some(FuzzBackend::Honggfuzz),
+            "bolero" => Some(FuzzBackend::Bolero),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            FuzzBackend::Honggfuzz => "honggfuzz",
+            FuzzBackend::Bolero => "bolero",
+        }
+    }
+
+    /// 测试目录下存放这个引擎的cargo工程的目录
+    pub(crate) fn project_dir(&self) -> &'static str {
+        match self {
+            FuzzBackend::Honggfuzz => "hfuzz_project",
+            FuzzBackend::Bolero => "bolero_project",
+        }
+    }
+
+    /// 工程里存放target的目录
+    pub(crate) fn target_dir(&self) -> &'static str {
+        match self {
+            FuzzBackend::Honggfuzz => "src/bin",
+            FuzzBackend::Bolero => "tests",
+        }
+    }
+
+    /// Cargo.toml里每个target的声明
+    fn target_section(&self, name: &str, path: &str) -> String {
+        match self {
+            FuzzBackend::Honggfuzz => {
+                format!("\n[[bin]]\nname = \"{}\"\npath = \"{}\"\n", name, path)
+            }
+            FuzzBackend::Bolero => {
+                format!("\n[[test]]\nname = \"{}\"\npath = \"{}\"\nharness = false\n", name, path)
+            }
+        }
+    }
+
+    /// 序列对应的fuzz target
+    pub(crate) fn test_file(
+        &self,
+        sequence: &ApiSequence,
+        api_graph: &ApiGraph<'_>,
+        test_index: usize,
+        encoding: EncodingVariant,
+    ) -> String {
+        match self {
+            FuzzBackend::Honggfuzz => {
+                sequence._to_honggfuzz_test_file_with_encoding(api_graph, test_index, encoding)
+            }
+            FuzzBackend::Bolero => {
+                bolero_util::bolero_test_file(sequence, api_graph, test_index, encoding)
+            }
+        }
+    }
+
+    /// 引擎本身的依赖
+    fn engine_dependency(&self) -> &'static str {
+        match self {
+            FuzzBackend::Honggfuzz => "honggfuzz = \"0.5\"",
+            FuzzBackend::Bolero => "bolero = \"0.10\"",
+        }
+    }
+}
+
+/// 引擎工程的Cargo.toml，bins是(target的名字, 相对于工程目录的路径, 文件内容)
+pub(crate) fn project_manifest(
+    backend: FuzzBackend,
+    crate_name: &str,
+    bins: &[ProjectTarget<'_>],
+) -> String {
+    let mut res = cargo_project::package_header(crate_name, backend.name(), "");
+    res.push_str(backend.engine_dependency());
+    res.push('\n');
+    res.push_str(cargo_project::extra_dependencies(bins).as_str());
+    for (bin_name, bin_path, _) in bins {
+        res.push_str(backend.target_section(bin_name, bin_path).as_str());
+    }
+    res
+}
@@ -0,0 +1,116 @@
+//! 束搜索生成序列
+//! BFS每一层都保留所有序列，层数一多就组合爆炸，_TryDeepBfs又比较随意
+//! 这里每一层只保留得分最高的beam_width个部分序列，下一层只从它们扩展，
+//! 内存和时间都和beam_width * API数量成正比，同时还能走到比较深的序列
+//! 得分 = 相对于前面几层已经覆盖的、序列新覆盖的节点和边数（节点按ApiGraph::_node_weight加权）
+//!      + 序列里的函数通过一条依赖边就能到达的、还没覆盖的函数数（以后还能覆盖多少）
+
+use crate::fuzz_targets_gen::api_graph::{ApiGraph, ApiType};
+use crate::fuzz_targets_gen::api_sequence::ApiSequence;
+use crate::fuzz_targets_gen::progress::Progress;
+use itertools::Itertools;
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
+
+/// 一个部分序列的得分
+fn score(
+    api_graph: &ApiGraph<'_>,
+    sequence: &ApiSequence,
+    covered_nodes: &FxHashSet<usize>,
+    covered_edges: &FxHashSet<usize>,
+    successors: &FxHashMap<usize, FxHashSet<usize>>,
+) -> usize {
+    let nodes = api_graph._api_nodes(sequence);
+    let new_nodes: usize = nodes
+        .iter()
+        .filter(|node| !covered_nodes.contains(*node))
+        .map(|node| api_graph._node_weight(*node))
+        .sum();
+    let new_edges =
+        sequence._covered_dependencies.iter().filter(|edge| !covered_edges.contains(*edge)).count();
+    let reachable = nodes
+        .iter()
+        .filter_map(|node| successors.get(node))
+        .flatten()
+        .filter(|successor| !covered_nodes.contains(*successor) && !nodes.contains(*successor))
+        .unique()
+        .count();
+    new_nodes + new_edges + reachable
+}
+
+/// 从空序列开始一层一层扩展到max_len，每层保留beam_width个序列，保留下来的可以fuzz的序列放到api_sequences里
+pub(crate) fn beam_search(api_graph: &mut ApiGraph<'_>, beam_width: usize, max_len: usize) {
+    api_graph.api_sequences.clear();
+    api_graph.reset_visited();
+    if api_graph.api_functions.is_empty() || beam_width == 0 {
+        return;
+    }
+
+    //函数 -> 通过依赖边能直接用上它返回值的函数
+    let mut successors: FxHashMap<usize, FxHashSet<usize>> = FxHashMap::default();
+    for dependency in &api_graph.api_dependencies {
+        successors.entry(dependency.output_fun.1).or_default().insert(dependency.input_fun.1);
+    }
+
+    let function_len = api_graph.api_functions.len();
+    let mut covered_nodes = FxHashSet::default();
+    let mut covered_edges = FxHashSet::default();
+    let mut sequences = Vec::new();
+    let mut beam = vec![ApiSequence::new()];
+    let mut progress = Progress::new("beam search", Some(max_len));
+    for _ in 0..max_len {
+        progress.inc(1);
+        let mut seen = FxHashSet::default();
+        let mut candidates = Vec::new();
+        for sequence in &beam {
+            for function_index in 0..function_len {
+                let Some(new_sequence) =
+                    api_graph.is_fun_satisfied(&ApiType::BareFunction, function_index, sequence)
+                else {
+                    continue;
+                };
+                let functions =
+                    new_sequence.functions.iter().map(|api_call| api_call.func.1).collect_vec();
+                if seen.insert(functions) {
+                    let sequence_score = score(
+                        api_graph,
+                        &new_sequence,
+                        &covered_nodes,
+                        &covered_edges,
+                        &successors,
+                    );
+                    candidates.push((sequence_score, new_sequence));
+                }
+            }
+        }
+        if candidates.is_empty() {
+            break;
+        }
+        //得分一样的时候保持生成的顺序
+        candidates.sort_by(|(x, _), (y, _)| y.cmp(x));
+        candidates.truncate(beam_width);
+        beam = candidates.into_iter().map(|(_, sequence)| sequence).collect_vec();
+
+        for sequence in &beam {
+            covered_nodes.extend(api_graph._api_nodes(sequence));
+            covered_edges.extend(sequence._covered_dependencies.iter().copied());
+            if !sequence._has_no_fuzzables()
+                && !sequence._contains_dead_code_except_last_one(api_graph)
+            {
+                sequences.push(sequence.clone());
+            }
+        }
+        progress.set_sequences(sequences.len());
+    }
+    progress.finish();
+
+    println!(
+        "beam search kept {} sequences covering {} nodes and {} edges",
+        sequences.len(),
+        covered_nodes.len(),
+        covered_edges.len()
+    );
+    for index in covered_nodes {
+        api_graph.api_functions_visited[index] = true;
+    }
+    api_graph.api_sequences = sequences;
+}
@@ -0,0 +1,39 @@
+//! 生成bolero的fuzz target
+//! bolero的check!在不同的引擎下执行同一个harness：`cargo bolero test <target> --engine libfuzzer|afl|honggfuzz`，
+//! 或者`cargo kani`做有界的模型检查，所以生成一次就可以用多个引擎跑，不需要重新生成。
+//! 函数体和参数解码沿用afl target的生成代码（_to_afl_except_main和_afl_closure_body），
+//! 只把外层的fuzz!换成check!().for_each。
+//! 每个target是工程里的一个不带测试框架（harness = false）的test，kani通过kani::proof找到入口函数
+
+use crate::fuzz_targets_gen::api_graph::ApiGraph;
+use crate::fuzz_targets_gen::api_sequence::{_generate_indent, ApiSequence};
+use crate::fuzz_targets_gen::encoding::EncodingVariant;
+
+/// 序列对应的bolero target
+pub(crate) fn bolero_test_file(
+    sequence: &ApiSequence,
+    api_graph: &ApiGraph<'_>,
+    test_index: usize,
+    encoding: EncodingVariant,
+) -> String {
+    let mut res = sequence._to_afl_except_main(api_graph, test_index, encoding);
+    res = res.replace("#[macro_use]\nextern crate afl;\n", "extern crate bolero;\n");
+    res.push_str(bolero_harness(sequence, test_index, encoding).as_str());
+    res
+}
+
+/// 入口函数：fuzz引擎通过main调用，kani直接把fuzz_target当作proof harness
+fn bolero_harness(sequence: &ApiSequence, test_index: usize, encoding: EncodingVariant) -> String {
+    let indent = _generate_indent(4);
+    let mut res = String::new();
+    res.push_str("#[cfg_attr(kani, kani::proof)]\n");
+    res.push_str("fn fuzz_target() {\n");
+    res.push_str(format!("{}bolero::check!().for_each(|data: &[u8]| {{\n", indent).as_str());
+    res.push_str(sequence._afl_closure_body(4, test_index, encoding).as_str());
+    res.push_str(format!("{}}});\n", indent).as_str());
+    res.push_str("}\n\n");
+    res.push_str("fn main() {\n");
+    res.push_str(format!("{}fuzz_target();\n", indent).as_str());
+    res.push_str("}\n");
+    res
+}
@@ -0,0 +1,163 @@
+//! Builder模式
+//! builder类型：有按值接收self、返回Self的setter，以及按值接收self的build()/finish()之类的终结方法，
+//! 通过引用接收self的方法不算，它们不会消耗builder
+//! BFS很难碰巧生成长度超过3的setter链，所以单独为每个builder生成序列：
+//!     构造函数 -> 所有能加入的setter -> 终结方法
+//! 每个setter是否调用由fuzz输入里的一个bool决定，这样fuzzer可以自己选择setter的子集
+
+use crate::clean::{self};
+use crate::fuzz_targets_gen::api_function::ApiFunction;
+use crate::fuzz_targets_gen::api_graph::{ApiGraph, ApiType};
+use crate::fuzz_targets_gen::api_sequence::{ApiSequence, ParamType};
+use crate::fuzz_targets_gen::invariant;
+use crate::fuzz_targets_gen::prelude_type;
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
+
+// 终结方法的名字（函数名的最后一段）
+lazy_static! {
+    static ref TERMINAL_NAMES: FxHashSet<&'static str> = {
+        let mut m = FxHashSet::default();
+        m.insert("build");
+        m.insert("finish");
+        m.insert("finalize");
+        m.insert("done");
+        m.insert("create");
+        m.insert("spawn");
+        m.insert("connect");
+        m.insert("open");
+        m
+    };
+}
+
+/// 一个builder类型，以及它的setter和终结方法在api_functions中的下标
+#[derive(Debug, Clone)]
+pub(crate) struct BuilderInfo {
+    pub(crate) type_name: String,
+    pub(crate) setters: Vec<usize>,
+    pub(crate) terminals: Vec<usize>,
+}
+
+/// 是不是type_name上按值接收self的方法：第一个参数不是引用，并且函数定义在这个类型上
+fn takes_self_by_value(
+    api_function: &ApiFunction,
+    self_type: &clean::Type,
+    type_name: &str,
+) -> bool {
+    matches!(self_type, clean::Type::Path { .. })
+        && api_function
+            .full_name
+            .strip_prefix(type_name)
+            .map_or(false, |method_name| method_name.starts_with("::"))
+}
+
+fn is_terminal_name(full_name: &String) -> bool {
+    match full_name.rsplit("::").next() {
+        Some(last_segment) => {
+            TERMINAL_NAMES.contains(last_segment) || last_segment.starts_with("build_")
+        }
+        None => false,
+    }
+}
+
+/// 找到所有的builder类型
+pub(crate) fn find_builders(graph: &ApiGraph<'_>) -> Vec<BuilderInfo> {
+    let mut builders: FxHashMap<String, BuilderInfo> = FxHashMap::default();
+    for (index, api_function) in graph.api_functions.iter().enumerate() {
+        let self_type = match api_function.inputs.first() {
+            Some(self_type) => self_type,
+            None => continue,
+        };
+        let type_name =
+            match invariant::type_full_path(self_type, graph.cache, &graph.full_name_map) {
+                Some(type_name) => type_name,
+                None => continue,
+            };
+        if !takes_self_by_value(api_function, self_type, &type_name) {
+            continue;
+        }
+        let output_type = match &api_function.output {
+            Some(output_type) => output_type,
+            None => continue,
+        };
+        let output_name = invariant::type_full_path(output_type, graph.cache, &graph.full_name_map);
+        let builder = builders.entry(type_name.clone()).or_insert_with(|| BuilderInfo {
+            type_name: type_name.clone(),
+            setters: Vec::new(),
+            terminals: Vec::new(),
+        });
+        if output_name.as_ref() == Some(&type_name) {
+            //返回Option/Result的不算setter，没法在不调用的时候沿用原来的值
+            if matches!(output_type, clean::Type::Path { .. })
+                && !prelude_type::_prelude_type_need_special_dealing(
+                    output_type,
+                    graph.cache,
+                    &graph.full_name_map,
+                )
+            {
+                builder.setters.push(index);
+            }
+        } else if is_terminal_name(&api_function.full_name) {
+            builder.terminals.push(index);
+        }
+    }
+    let mut res = builders
+        .into_iter()
+        .map(|(_, builder)| builder)
+        .filter(|builder| !builder.setters.is_empty() && !builder.terminals.is_empty())
+        .collect::<Vec<_>>();
+    res.sort_by(|x, y| x.type_name.cmp(&y.type_name));
+    res
+}
+
+/// 为一个builder生成序列：构造函数 -> setter -> 终结方法
+pub(crate) fn builder_sequence(graph: &ApiGraph<'_>, builder: &BuilderInfo) -> Option<ApiSequence> {
+    //构造函数：返回builder类型，而且不需要其他的返回值就能调用
+    let setters = builder.setters.iter().collect::<FxHashSet<_>>();
+    let mut sequence = None;
+    for (index, api_function) in graph.api_functions.iter().enumerate() {
+        if setters.contains(&index) {
+            continue;
+        }
+        let output_type = match &api_function.output {
+            Some(output_type) => output_type,
+            None => continue,
+        };
+        if invariant::type_full_path(output_type, graph.cache, &graph.full_name_map).as_ref()
+            != Some(&builder.type_name)
+        {
+            continue;
+        }
+        if let Some(new_sequence) =
+            graph.is_fun_satisfied(&ApiType::BareFunction, index, &ApiSequence::new())
+        {
+            sequence = Some(new_sequence);
+            break;
+        }
+    }
+    let mut sequence = sequence?;
+
+    for setter in &builder.setters {
+        let new_sequence = match graph.is_fun_satisfied(&ApiType::BareFunction, *setter, &sequence)
+        {
+            Some(new_sequence) => new_sequence,
+            None => continue,
+        };
+        sequence = new_sequence;
+        //self参数来自前面的返回值，才能在不调用的时候沿用原来的值
+        let call_index = sequence.functions.len() - 1;
+        if let Some((ParamType::_FunctionReturn, ..)) =
+            sequence.functions[call_index].params.first()
+        {
+            sequence._make_call_fuzz_selected(call_index);
+        }
+    }
+
+    for terminal in &builder.terminals {
+        if let Some(new_sequence) =
+            graph.is_fun_satisfied(&ApiType::BareFunction, *terminal, &sequence)
+        {
+            return Some(new_sequence);
+        }
+    }
+    None
+}
@@ -3,22 +3,61 @@
 use crate::fuzz_targets_gen::api_function::ApiUnsafety;
 use crate::fuzz_targets_gen::api_util::_type_name;
 use crate::fuzz_targets_gen::impl_util::FullNameMap;
+use crate::fuzz_targets_gen::sequence_serde;
+use serde::{Deserialize, Serialize};
 
-#[derive(Clone, Debug, Eq, PartialEq, Hash)]
+#[derive(Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub(crate) enum CallType {
     _NotCompatible,
-    _DirectCall,                                  //直接调用
-    _BorrowedRef(Box<CallType>),                  //取不可变引用
-    _MutBorrowedRef(Box<CallType>),               //取可变引用
-    _ConstRawPointer(Box<CallType>, clean::Type), //转换为不可变裸指针
-    _MutRawPointer(Box<CallType>, clean::Type),   //转换为可变裸指针
-    _AsConvert(String),                           //通过as进行转换
-    _UnsafeDeref(Box<CallType>),                  //解引用裸指针
-    _Deref(Box<CallType>),                        //解引用引用
-    _UnwrapResult(Box<CallType>),                 //获得result变量的ok值
-    _ToResult(Box<CallType>),                     //产生一个result类型, never used
-    _UnwrapOption(Box<CallType>),                 //获得option变量的值
-    _ToOption(Box<CallType>),                     //产生一个option类型
+    _DirectCall,                    //直接调用
+    _BorrowedRef(Box<CallType>),    //取不可变引用
+    _MutBorrowedRef(Box<CallType>), //取可变引用
+    //转换为不可变裸指针
+    _ConstRawPointer(Box<CallType>, #[serde(with = "sequence_serde::pointee_type")] clean::Type),
+    //转换为可变裸指针
+    _MutRawPointer(Box<CallType>, #[serde(with = "sequence_serde::pointee_type")] clean::Type),
+    _AsConvert(String),           //通过as进行转换
+    _UnsafeDeref(Box<CallType>),  //解引用裸指针
+    _Deref(Box<CallType>),        //解引用引用
+    _UnwrapResult(Box<CallType>), //获得result变量的ok值
+    _ToResult(Box<CallType>),     //产生一个result类型, never used
+    _UnwrapOption(Box<CallType>), //获得option变量的值
+    _ToOption(Box<CallType>),     //产生一个option类型
+    _CustomConvert(String),       //用户注册的转换表达式，见conversion.rs
+    //转换为trait object，String是目标类型：&dyn Trait、&mut dyn Trait或者Box<dyn Trait>
+    _DynCoerce(Box<CallType>, String),
+    //包装成智能指针，用于`self: Box<Self>`这样的receiver
+    _ToBox(Box<CallType>),
+    _ToRc(Box<CallType>),
+    _ToArc(Box<CallType>),
+    _PinNew(Box<CallType>), //Pin::new，内层是引用，要求被指向的类型是Unpin
+    //Deref强制转换，解引用usize次之后再取引用，比如String -> &str写成`&*s`
+    _DerefCoerce(Box<CallType>, usize),
+    _AsRef(Box<CallType>),  //通过AsRef转换，写成`.as_ref()`
+    _Borrow(Box<CallType>), //通过Borrow转换，写成`.borrow()`，需要引入std::borrow::Borrow
+    //通过From impl转换，String是目标类型，写成`Into::<B>::into(x)`，连续转换的时候中间类型也能确定
+    _IntoConversion(Box<CallType>, String),
+    //整数宽度不同的转换，String是目标类型，bool表示用try_into检查范围，否则用as，见numeric_cast.rs
+    _NumericCast(String, bool),
+    _CowBorrowed(Box<CallType>),  //包装成Cow::Borrowed
+    _CowOwned(Box<CallType>),     //to_owned之后包装成Cow::Owned，用于Cow<'static, _>
+    _ToPathBuf(Box<CallType>),    //字符串转换成PathBuf，见path_param.rs
+    _TempFilePath(Box<CallType>), //字节写进临时文件，得到它的PathBuf
+    _ToCursor(Box<CallType>),     //包装成std::io::Cursor，用于Read参数，见io_param.rs
+    _NewWriter,                   //Write参数，新建一个Vec<u8>，不用fuzz输入
+    _ToOsString(Box<CallType>),   //字节转换成OsString，unix上不经过UTF-8
+    _ToDuration(Box<CallType>),   //u64毫秒数转换成Duration，见time_param.rs
+    _ToSystemTime(Box<CallType>), //UNIX_EPOCH加上u64毫秒数
+    _InstantNow,                  //Instant参数，用当前时间，不用fuzz输入
+    _ToOwned(Box<CallType>),      //复制成owned的值，&str转换成String
+    _ToVec(Box<CallType>),        //切片复制成Vec，内层是每个元素的转换，不作用在变量上
+    //引用转换成裸指针，bool表示是否转换成*mut，只有&mut T可以转换成*mut T
+    _RefToRawPointer(
+        Box<CallType>,
+        #[serde(with = "sequence_serde::pointee_type")] clean::Type,
+        bool,
+    ),
+    _UninitAsMutPtr(Box<CallType>), //MaybeUninit<T>槽位传给*mut T出参，见maybe_uninit.rs
 }
 
 impl CallType {
@@ -56,13 +95,27 @@ pub(crate) fn _to_call_string(
             }
             CallType::_MutRawPointer(inner_, ty_) => {
                 //FIXME: 需要转换之后的类型名
-                let mut call_string = "&(".to_string();
+                let mut call_string = "&mut (".to_string();
                 let inner_call_string = inner_._to_call_string(variable_name, cache, full_name_map);
                 call_string.push_str(inner_call_string.as_str());
                 call_string.push_str(") as *mut ");
                 call_string.push_str(_type_name(ty_, cache, full_name_map).as_str());
                 call_string
             }
+            CallType::_RefToRawPointer(inner_, ty_, mutable) => {
+                let inner_call_string = inner_._to_call_string(variable_name, cache, full_name_map);
+                let type_name = _type_name(ty_, cache, full_name_map);
+                //&mut先重新借用一次，转换之后原来的引用还能接着用
+                if *mutable {
+                    format!("&mut *({}) as *mut {}", inner_call_string, type_name)
+                } else {
+                    format!("({}) as *const {}", inner_call_string, type_name)
+                }
+            }
+            CallType::_UninitAsMutPtr(inner_) => {
+                let inner_call_string = inner_._to_call_string(variable_name, cache, full_name_map);
+                format!("({}).as_mut_ptr()", inner_call_string)
+            }
             CallType::_AsConvert(str_) => {
                 //FIXME: 需要转换之后的类型名
                 let mut call_string = variable_name.to_string();
@@ -70,6 +123,20 @@ pub(crate) fn _to_call_string(
                 call_string.push_str(str_.as_str());
                 call_string
             }
+            CallType::_CustomConvert(expression) => {
+                //表达式里用x指代被转换的值
+                format!("{{ let x = {}; {} }}", variable_name, expression)
+            }
+            CallType::_NumericCast(target, checked) => {
+                if *checked {
+                    format!(
+                        "_unwrap_result(std::convert::TryInto::<{}>::try_into({}))",
+                        target, variable_name
+                    )
+                } else {
+                    format!("{} as {}", variable_name, target)
+                }
+            }
             CallType::_UnsafeDeref(inner_) | CallType::_Deref(inner_) => {
                 //FIXME: unsafe deref需要考虑unsafe标记
                 let mut call_string = "*(".to_string();
@@ -96,33 +163,177 @@ pub(crate) fn _to_call_string(
                 let inner_call_string = inner_._to_call_string(variable_name, cache, full_name_map);
                 format!("Ok({})", inner_call_string)
             }
+            CallType::_DynCoerce(inner_, target) => {
+                //引用的情况内层已经取了引用，Box的情况需要先装箱
+                let inner_call_string = inner_._to_call_string(variable_name, cache, full_name_map);
+                match **inner_ {
+                    CallType::_BorrowedRef(..) | CallType::_MutBorrowedRef(..) => {
+                        format!("({} as {})", inner_call_string, target)
+                    }
+                    _ => format!("(Box::new({}) as {})", inner_call_string, target),
+                }
+            }
+            CallType::_ToBox(inner_) => {
+                let inner_call_string = inner_._to_call_string(variable_name, cache, full_name_map);
+                format!("Box::new({})", inner_call_string)
+            }
+            CallType::_ToRc(inner_) => {
+                let inner_call_string = inner_._to_call_string(variable_name, cache, full_name_map);
+                format!("std::rc::Rc::new({})", inner_call_string)
+            }
+            CallType::_ToArc(inner_) => {
+                let inner_call_string = inner_._to_call_string(variable_name, cache, full_name_map);
+                format!("std::sync::Arc::new({})", inner_call_string)
+            }
+            CallType::_PinNew(inner_) => {
+                let inner_call_string = inner_._to_call_string(variable_name, cache, full_name_map);
+                format!("std::pin::Pin::new({})", inner_call_string)
+            }
+            CallType::_DerefCoerce(inner_, depth) => {
+                let inner_call_string = inner_._to_call_string(variable_name, cache, full_name_map);
+                format!("&{}({})", "*".repeat(*depth), inner_call_string)
+            }
+            CallType::_AsRef(inner_) => {
+                let inner_call_string = inner_._to_call_string(variable_name, cache, full_name_map);
+                format!("({}).as_ref()", inner_call_string)
+            }
+            CallType::_Borrow(inner_) => {
+                let inner_call_string = inner_._to_call_string(variable_name, cache, full_name_map);
+                format!("({}).borrow()", inner_call_string)
+            }
+            CallType::_IntoConversion(inner_, target) => {
+                let inner_call_string = inner_._to_call_string(variable_name, cache, full_name_map);
+                format!("Into::<{}>::into({})", target, inner_call_string)
+            }
+            CallType::_CowBorrowed(inner_) => {
+                let inner_call_string = inner_._to_call_string(variable_name, cache, full_name_map);
+                format!("std::borrow::Cow::Borrowed({})", inner_call_string)
+            }
+            CallType::_CowOwned(inner_) => {
+                let inner_call_string = inner_._to_call_string(variable_name, cache, full_name_map);
+                format!("std::borrow::Cow::Owned(({}).to_owned())", inner_call_string)
+            }
+            CallType::_ToCursor(inner_) => {
+                let inner_call_string = inner_._to_call_string(variable_name, cache, full_name_map);
+                format!("std::io::Cursor::new({})", inner_call_string)
+            }
+            CallType::_NewWriter => "Vec::<u8>::new()".to_string(),
+            CallType::_ToOsString(inner_) => {
+                let inner_call_string = inner_._to_call_string(variable_name, cache, full_name_map);
+                format!("_to_os_string({})", inner_call_string)
+            }
+            CallType::_ToPathBuf(inner_) => {
+                let inner_call_string = inner_._to_call_string(variable_name, cache, full_name_map);
+                format!("std::path::PathBuf::from({})", inner_call_string)
+            }
+            CallType::_ToDuration(inner_) => {
+                let inner_call_string = inner_._to_call_string(variable_name, cache, full_name_map);
+                format!("std::time::Duration::from_millis({})", inner_call_string)
+            }
+            CallType::_ToSystemTime(inner_) => {
+                let inner_call_string = inner_._to_call_string(variable_name, cache, full_name_map);
+                format!(
+                    "(std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_millis({}))",
+                    inner_call_string
+                )
+            }
+            CallType::_InstantNow => "std::time::Instant::now()".to_string(),
+            CallType::_ToOwned(inner_) => {
+                let inner_call_string = inner_._to_call_string(variable_name, cache, full_name_map);
+                format!("({}).to_owned()", inner_call_string)
+            }
+            //内层作用在每个元素上
+            CallType::_ToVec(element_call_type) => match &**element_call_type {
+                CallType::_DirectCall => format!("({}).to_vec()", variable_name),
+                _ => {
+                    let element_call_string =
+                        element_call_type._to_call_string(&"_e".to_string(), cache, full_name_map);
+                    format!(
+                        "({}).iter().cloned().map(|_e| {}).collect::<Vec<_>>()",
+                        variable_name, element_call_string
+                    )
+                }
+            },
+            CallType::_TempFilePath(inner_) => {
+                //用变量名区分同一个序列里的不同参数
+                let inner_call_string = inner_._to_call_string(variable_name, cache, full_name_map);
+                format!("_temp_file_path(\"{}\", {})", variable_name, inner_call_string)
+            }
         }
     }
 
     pub(crate) fn unsafe_call_type(&self) -> ApiUnsafety {
         match self {
             CallType::_UnsafeDeref(..) => ApiUnsafety::Unsafe,
+            //传入裸指针的调用放在unsafe块里，见_is_raw_pointer_cast
+            CallType::_ConstRawPointer(..)
+            | CallType::_MutRawPointer(..)
+            | CallType::_RefToRawPointer(..)
+            | CallType::_UninitAsMutPtr(..) => ApiUnsafety::Unsafe,
             _ => ApiUnsafety::Normal,
         }
     }
 
+    /// 是否把变量转换成裸指针传参，这样的参数所在的调用整个放在unsafe块里
+    pub(crate) fn _is_raw_pointer_cast(&self) -> bool {
+        matches!(
+            self,
+            CallType::_ConstRawPointer(..)
+                | CallType::_MutRawPointer(..)
+                | CallType::_RefToRawPointer(..)
+                | CallType::_UninitAsMutPtr(..)
+        )
+    }
+
+    //内层任意一步需要unsafe，整个表达式都需要unsafe
+    pub(crate) fn _contains_unsafe_call_type(&self) -> bool {
+        self._call_type_to_array().iter().any(|call_type| call_type.unsafe_call_type()._is_unsafe())
+    }
+
     pub(crate) fn _contains_move_call_type(&self) -> bool {
         //self._contains_unwrap_call_type()
         match self {
-            CallType::_NotCompatible | CallType::_DirectCall | CallType::_AsConvert(..) => true,
+            CallType::_NotCompatible
+            | CallType::_DirectCall
+            | CallType::_AsConvert(..)
+            | CallType::_NumericCast(..)
+            | CallType::_NewWriter
+            | CallType::_InstantNow => true,
             CallType::_UnwrapOption(..) | CallType::_UnwrapResult(..) => true,
+            //转换表达式按值拿走变量
+            CallType::_CustomConvert(..) => true,
             CallType::_ConstRawPointer(call_type, _)
             | CallType::_MutRawPointer(call_type, _)
             | CallType::_UnsafeDeref(call_type)
             | CallType::_Deref(call_type)
             | CallType::_ToOption(call_type)
-            | CallType::_ToResult(call_type) => call_type._contains_move_call_type(),
-            CallType::_BorrowedRef(call_type) | CallType::_MutBorrowedRef(call_type) => {
-                match **call_type {
-                    CallType::_DirectCall => false,
-                    _ => call_type._contains_move_call_type(),
-                }
-            }
+            | CallType::_ToResult(call_type)
+            | CallType::_DynCoerce(call_type, _)
+            | CallType::_ToBox(call_type)
+            | CallType::_ToRc(call_type)
+            | CallType::_ToArc(call_type)
+            | CallType::_PinNew(call_type)
+            | CallType::_IntoConversion(call_type, _)
+            | CallType::_CowBorrowed(call_type)
+            | CallType::_CowOwned(call_type)
+            | CallType::_ToPathBuf(call_type)
+            | CallType::_TempFilePath(call_type)
+            | CallType::_ToCursor(call_type)
+            | CallType::_ToOsString(call_type)
+            | CallType::_ToDuration(call_type)
+            | CallType::_ToSystemTime(call_type)
+            | CallType::_ToOwned(call_type)
+            | CallType::_ToVec(call_type) => call_type._contains_move_call_type(),
+            CallType::_BorrowedRef(call_type)
+            | CallType::_MutBorrowedRef(call_type)
+            | CallType::_DerefCoerce(call_type, _)
+            | CallType::_AsRef(call_type)
+            | CallType::_Borrow(call_type)
+            | CallType::_RefToRawPointer(call_type, ..)
+            | CallType::_UninitAsMutPtr(call_type) => match **call_type {
+                CallType::_DirectCall => false,
+                _ => call_type._contains_move_call_type(),
+            },
         }
     }
 
@@ -134,7 +345,13 @@ pub(crate) fn _is_unwrap_call_type(&self) -> bool {
     }
     pub(crate) fn _contains_unwrap_call_type(&self) -> bool {
         match self {
-            CallType::_NotCompatible | CallType::_DirectCall | CallType::_AsConvert(..) => false,
+            CallType::_NotCompatible
+            | CallType::_DirectCall
+            | CallType::_AsConvert(..)
+            | CallType::_CustomConvert(..)
+            | CallType::_NumericCast(..)
+            | CallType::_NewWriter
+            | CallType::_InstantNow => false,
             CallType::_UnwrapOption(..) | CallType::_UnwrapResult(..) => true,
             CallType::_BorrowedRef(call_type)
             | CallType::_MutBorrowedRef(call_type)
@@ -143,15 +360,40 @@ pub(crate) fn _contains_unwrap_call_type(&self) -> bool {
             | CallType::_UnsafeDeref(call_type)
             | CallType::_Deref(call_type)
             | CallType::_ToOption(call_type)
-            | CallType::_ToResult(call_type) => call_type._contains_move_call_type(),
+            | CallType::_ToResult(call_type)
+            | CallType::_DynCoerce(call_type, _)
+            | CallType::_ToBox(call_type)
+            | CallType::_ToRc(call_type)
+            | CallType::_ToArc(call_type)
+            | CallType::_PinNew(call_type)
+            | CallType::_DerefCoerce(call_type, _)
+            | CallType::_AsRef(call_type)
+            | CallType::_Borrow(call_type)
+            | CallType::_IntoConversion(call_type, _)
+            | CallType::_CowBorrowed(call_type)
+            | CallType::_CowOwned(call_type)
+            | CallType::_ToPathBuf(call_type)
+            | CallType::_TempFilePath(call_type)
+            | CallType::_ToCursor(call_type)
+            | CallType::_ToOsString(call_type)
+            | CallType::_ToDuration(call_type)
+            | CallType::_ToSystemTime(call_type)
+            | CallType::_ToOwned(call_type)
+            | CallType::_ToVec(call_type)
+            | CallType::_RefToRawPointer(call_type, ..)
+            | CallType::_UninitAsMutPtr(call_type) => call_type._contains_move_call_type(),
         }
     }
 
     pub(crate) fn _call_type_to_array(&self) -> Vec<CallType> {
         match self {
-            CallType::_NotCompatible | CallType::_DirectCall | CallType::_AsConvert(..) => {
-                vec![self.clone()]
-            }
+            CallType::_NotCompatible
+            | CallType::_DirectCall
+            | CallType::_AsConvert(..)
+            | CallType::_CustomConvert(..)
+            | CallType::_NumericCast(..)
+            | CallType::_NewWriter
+            | CallType::_InstantNow => vec![self.clone()],
             CallType::_UnwrapOption(call_type)
             | CallType::_UnwrapResult(call_type)
             | CallType::_BorrowedRef(call_type)
@@ -161,7 +403,28 @@ pub(crate) fn _call_type_to_array(&self) -> Vec<CallType> {
             | CallType::_UnsafeDeref(call_type)
             | CallType::_Deref(call_type)
             | CallType::_ToOption(call_type)
-            | CallType::_ToResult(call_type) => {
+            | CallType::_ToResult(call_type)
+            | CallType::_DynCoerce(call_type, _)
+            | CallType::_ToBox(call_type)
+            | CallType::_ToRc(call_type)
+            | CallType::_ToArc(call_type)
+            | CallType::_PinNew(call_type)
+            | CallType::_DerefCoerce(call_type, _)
+            | CallType::_AsRef(call_type)
+            | CallType::_Borrow(call_type)
+            | CallType::_IntoConversion(call_type, _)
+            | CallType::_CowBorrowed(call_type)
+            | CallType::_CowOwned(call_type)
+            | CallType::_ToPathBuf(call_type)
+            | CallType::_TempFilePath(call_type)
+            | CallType::_ToCursor(call_type)
+            | CallType::_ToOsString(call_type)
+            | CallType::_ToDuration(call_type)
+            | CallType::_ToSystemTime(call_type)
+            | CallType::_ToOwned(call_type)
+            | CallType::_ToVec(call_type)
+            | CallType::_RefToRawPointer(call_type, ..)
+            | CallType::_UninitAsMutPtr(call_type) => {
                 let mut call_types = vec![self.clone()];
                 let mut inner_call_types = call_type._call_type_to_array();
                 call_types.append(&mut inner_call_types);
@@ -230,7 +493,13 @@ fn _inner_array_to_call_type(call_type_array: &Vec<CallType>, start: usize) -> S
         let current_type = call_type_array[start].clone();
         let inner_type = CallType::_inner_array_to_call_type(call_type_array, start + 1);
         match current_type {
-            CallType::_DirectCall | CallType::_AsConvert(..) | CallType::_NotCompatible => {
+            CallType::_DirectCall
+            | CallType::_AsConvert(..)
+            | CallType::_CustomConvert(..)
+            | CallType::_NumericCast(..)
+            | CallType::_NewWriter
+            | CallType::_InstantNow
+            | CallType::_NotCompatible => {
                 println!("should not go to here in inner array to call type 2");
                 return CallType::_NotCompatible;
             }
@@ -248,6 +517,33 @@ fn _inner_array_to_call_type(call_type_array: &Vec<CallType>, start: usize) -> S
             CallType::_ToOption(..) => CallType::_ToOption(Box::new(inner_type)),
             CallType::_UnwrapResult(..) => CallType::_UnwrapResult(Box::new(inner_type)),
             CallType::_ToResult(..) => CallType::_ToResult(Box::new(inner_type)),
+            CallType::_DynCoerce(_, ref target) => {
+                CallType::_DynCoerce(Box::new(inner_type), target.clone())
+            }
+            CallType::_ToBox(..) => CallType::_ToBox(Box::new(inner_type)),
+            CallType::_ToRc(..) => CallType::_ToRc(Box::new(inner_type)),
+            CallType::_ToArc(..) => CallType::_ToArc(Box::new(inner_type)),
+            CallType::_PinNew(..) => CallType::_PinNew(Box::new(inner_type)),
+            CallType::_DerefCoerce(_, depth) => CallType::_DerefCoerce(Box::new(inner_type), depth),
+            CallType::_AsRef(..) => CallType::_AsRef(Box::new(inner_type)),
+            CallType::_Borrow(..) => CallType::_Borrow(Box::new(inner_type)),
+            CallType::_IntoConversion(_, ref target) => {
+                CallType::_IntoConversion(Box::new(inner_type), target.clone())
+            }
+            CallType::_CowBorrowed(..) => CallType::_CowBorrowed(Box::new(inner_type)),
+            CallType::_CowOwned(..) => CallType::_CowOwned(Box::new(inner_type)),
+            CallType::_ToPathBuf(..) => CallType::_ToPathBuf(Box::new(inner_type)),
+            CallType::_TempFilePath(..) => CallType::_TempFilePath(Box::new(inner_type)),
+            CallType::_ToCursor(..) => CallType::_ToCursor(Box::new(inner_type)),
+            CallType::_ToOsString(..) => CallType::_ToOsString(Box::new(inner_type)),
+            CallType::_ToDuration(..) => CallType::_ToDuration(Box::new(inner_type)),
+            CallType::_ToSystemTime(..) => CallType::_ToSystemTime(Box::new(inner_type)),
+            CallType::_ToOwned(..) => CallType::_ToOwned(Box::new(inner_type)),
+            CallType::_ToVec(..) => CallType::_ToVec(Box::new(inner_type)),
+            CallType::_RefToRawPointer(_, ref type_, mutable) => {
+                CallType::_RefToRawPointer(Box::new(inner_type), type_.clone(), mutable)
+            }
+            CallType::_UninitAsMutPtr(..) => CallType::_UninitAsMutPtr(Box::new(inner_type)),
         }
     }
 }
@@ -0,0 +1,111 @@
+//! 生成可以直接编译的cargo工程
+//! 测试目录本身就是一个crate：afl_files、replay_files和libfuzzer_files下的每个文件都是一个bin，
+//! 在测试目录下执行`cargo afl build`就能编译出所有的fuzz target和replay程序，
+//! libfuzzer的target放在libfuzzer feature后面，需要`cargo fuzz`的工具链，
+//! 用`cargo +nightly rustc --features libfuzzer --bin <target> -- -Zsanitizer=address`之类的方式编译。
+//! 其他fuzz引擎的工程（backend.rs）也用这里的package和依赖的写法。
+//! cargo fries（src/tools/cargo-fries）也直接在测试目录下编译这个工程
+
+/// 生成的target里引入了这些crate的话，工程也要依赖它们
+static EXTRA_DEPENDENCIES: &[(&str, &str)] = &[
+    ("extern crate arbitrary;", "arbitrary = \"1\""),
+    ("extern crate futures;", "futures = \"0.3\""),
+    ("extern crate tokio;", "tokio = { version = \"1\", features = [\"rt\"] }"),
+];
+
+/// 测试目录下的工程文件
+pub(crate) static PROJECT_MANIFEST_FILE: &'static str = "Cargo.toml";
+pub(crate) static BUILD_SCRIPT_FILE: &'static str = "build.rs";
+
+/// 工程里的一个bin或者test：(target的名字, 相对于工程目录的路径, 文件内容)
+pub(crate) type ProjectTarget<'a> = (String, String, &'a String);
+
+/// 待测crate的包名，优先用cargo传给rustdoc的CARGO_PKG_NAME
+fn package_name(crate_name: &str) -> String {
+    std::env::var("CARGO_PKG_NAME").unwrap_or_else(|_| crate_name.to_string())
+}
+
+/// [package]到[dependencies]的部分，工程名是`{包名}-{suffix}-fuzz`，
+/// 待测crate优先按CARGO_MANIFEST_DIR的路径依赖，不是cargo调用的时候按名字依赖。
+/// 每个工程都带一个空的[workspace]，不会被待测crate所在的workspace收进去
+pub(crate) fn package_header(crate_name: &str, suffix: &str, extra_package_keys: &str) -> String {
+    let package_name = package_name(crate_name);
+    let crate_dependency = match std::env::var("CARGO_MANIFEST_DIR") {
+        Ok(manifest_dir) => format!("{} = {{ path = \"{}\" }}", package_name, manifest_dir),
+        Err(_) => format!("{} = \"*\"", package_name),
+    };
+    format!(
+        "[package]\n\
+         name = \"{}-{}-fuzz\"\n\
+         version = \"0.0.0\"\n\
+         edition = \"2021\"\n\
+         publish = false\n\
+         {}\
+         \n\
+         [workspace]\n\
+         \n\
+         [dependencies]\n\
+         {}\n",
+        package_name, suffix, extra_package_keys, crate_dependency
+    )
+}
+
+/// targets里用到的额外依赖，每行一个
+pub(crate) fn extra_dependencies(targets: &[ProjectTarget<'_>]) -> String {
+    let mut res = String::new();
+    for (extern_line, dependency) in EXTRA_DEPENDENCIES {
+        if targets.iter().any(|(_, _, content)| content.contains(extern_line)) {
+            res.push_str(dependency);
+            res.push('\n');
+        }
+    }
+    res
+}
+
+fn bin_section(name: &str, path: &str) -> String {
+    format!("\n[[bin]]\nname = \"{}\"\npath = \"{}\"\n", name, path)
+}
+
+/// 测试目录的Cargo.toml，afl的target和replay程序总是编译，libfuzzer的target需要打开libfuzzer feature
+pub(crate) fn afl_project_manifest(
+    crate_name: &str,
+    afl_targets: &[ProjectTarget<'_>],
+    libfuzzer_targets: &[ProjectTarget<'_>],
+) -> String {
+    let mut res = package_header(crate_name, "fries", "build = \"build.rs\"\n");
+    res.push_str("afl = \"*\"\n");
+    if !libfuzzer_targets.is_empty() {
+        res.push_str("libfuzzer-sys = { version = \"0.4\", optional = true }\n");
+    }
+    res.push_str(extra_dependencies(afl_targets).as_str());
+    if !libfuzzer_targets.is_empty() {
+        res.push_str("\n[features]\nlibfuzzer = [\"libfuzzer-sys\"]\n");
+    }
+    for (bin_name, bin_path, _) in afl_targets {
+        res.push_str(bin_section(bin_name, bin_path).as_str());
+    }
+    for (bin_name, bin_path, _) in libfuzzer_targets {
+        res.push_str(bin_section(bin_name, bin_path).as_str());
+        res.push_str("required-features = [\"libfuzzer\"]\n");
+    }
+    res
+}
+
+/// 测试目录的build.rs
+/// cargo afl build和cargo fuzz build都会通过RUSTFLAGS加上`--cfg fuzzing`，
+/// 直接cargo build编出来的target没有插桩，afl会拒绝运行，所以在这里给出提示
+pub(crate) fn build_script() -> String {
+    let mut res = String::new();
+    res.push_str("fn main() {\n");
+    res.push_str("    println!(\"cargo:rerun-if-env-changed=CARGO_ENCODED_RUSTFLAGS\");\n");
+    res.push_str(
+        "    let rustflags = std::env::var(\"CARGO_ENCODED_RUSTFLAGS\").unwrap_or_default();\n",
+    );
+    res.push_str("    if !rustflags.split('\\x1f').any(|flag| flag == \"fuzzing\" || flag == \"--cfg=fuzzing\") {\n");
+    res.push_str(
+        "        println!(\"cargo:warning=fuzz targets are not instrumented, build them with `cargo afl build`\");\n",
+    );
+    res.push_str("    }\n");
+    res.push_str("}\n");
+    res
+}
@@ -0,0 +1,99 @@
+//! HashMap、BTreeMap、HashSet、BTreeSet参数
+//! 配置、索引类的crate很多API接收一个集合，以前这些参数只能等序列里有别的函数返回集合。
+//! 键和值都能fuzz而且长度固定的时候，集合和&[T]一样占一段动态长度的输入，
+//! 按一项(键, 值)的长度切开，逐项插入，重复的键后面的覆盖前面的。
+//! 只支持一层：键和值不能是字符串、切片或者另一个集合这样长度可变的类型。
+//! 带自定义hasher的`HashMap<K, V, S>`没法用new构造，不算
+
+use crate::clean;
+use crate::formats::cache::Cache;
+use crate::fuzz_targets_gen::api_util;
+use crate::fuzz_targets_gen::emit_options::EmitOptions;
+use crate::fuzz_targets_gen::fuzz_type::{self, FuzzableCallType};
+use crate::fuzz_targets_gen::impl_util::FullNameMap;
+use rustc_data_structures::fx::FxHashMap;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Ord, PartialOrd, Serialize, Deserialize)]
+pub(crate) enum CollectionKind {
+    HashMap,
+    BTreeMap,
+    HashSet,
+    BTreeSet,
+}
+
+impl CollectionKind {
+    fn from_path_name(path_name: &str) -> Option<Self> {
+        match path_name {
+            "std::collections::HashMap" => Some(CollectionKind::HashMap),
+            "std::collections::BTreeMap" => Some(CollectionKind::BTreeMap),
+            "std::collections::HashSet" => Some(CollectionKind::HashSet),
+            "std::collections::BTreeSet" => Some(CollectionKind::BTreeSet),
+            _ => None,
+        }
+    }
+
+    /// 生成代码里的路径
+    pub(crate) fn path_name(&self) -> &'static str {
+        match self {
+            CollectionKind::HashMap => "std::collections::HashMap",
+            CollectionKind::BTreeMap => "std::collections::BTreeMap",
+            CollectionKind::HashSet => "std::collections::HashSet",
+            CollectionKind::BTreeSet => "std::collections::BTreeSet",
+        }
+    }
+
+    /// 每一项有几个类型参数，map是键和值，set只有键
+    pub(crate) fn item_arity(&self) -> usize {
+        match self {
+            CollectionKind::HashMap | CollectionKind::BTreeMap => 2,
+            CollectionKind::HashSet | CollectionKind::BTreeSet => 1,
+        }
+    }
+}
+
+/// std::collections下的四种集合，其他类型返回None
+pub(crate) fn collection_call_type(
+    type_: &clean::Type,
+    cache: &Cache,
+    full_name_map: &FullNameMap,
+    emit_options: &EmitOptions,
+    substitution: Option<&FxHashMap<String, clean::Type>>,
+) -> Option<FuzzableCallType> {
+    let clean::Type::Path { path } = type_ else {
+        return None;
+    };
+    let path_name = api_util::_def_path_name(path.def_id(), cache, full_name_map)?;
+    let kind = CollectionKind::from_path_name(&path_name)?;
+    let Some(clean::GenericArgs::AngleBracketed { args, .. }) =
+        path.segments.last().map(|segment| &segment.args)
+    else {
+        return Some(FuzzableCallType::NoFuzzable);
+    };
+    let item_types = args
+        .iter()
+        .filter_map(|arg| match arg {
+            clean::GenericArg::Type(type_) => Some(type_),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    //多出来的是hasher或者allocator
+    if item_types.len() != kind.item_arity() {
+        return Some(FuzzableCallType::NoFuzzable);
+    }
+    let mut item_call_types = Vec::new();
+    for item_type in item_types {
+        let item_call_type = fuzz_type::fuzzable_call_type(
+            item_type,
+            cache,
+            full_name_map,
+            emit_options,
+            substitution,
+        );
+        if let FuzzableCallType::NoFuzzable = item_call_type {
+            return Some(FuzzableCallType::NoFuzzable);
+        }
+        item_call_types.push(item_call_type);
+    }
+    Some(FuzzableCallType::Collection(kind, item_call_types))
+}
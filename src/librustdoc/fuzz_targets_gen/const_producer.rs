@@ -0,0 +1,154 @@
+//! 常量生产者
+//! `Duration::MAX`、`Config::DEFAULT`这样的公开关联常量，以及crate里`pub const DEFAULT: Config`
+//! 这样的常量和静态变量，不用调用函数就能得到一个值。这里给参数里出现的类型加上没有参数的合成生产者，
+//! 依赖分析的时候和普通函数一样作为开始函数，生成代码时直接写成路径，见ApiGraph::const_producers
+//! 1. 关联常量：参数类型（包括外部crate的类型）固有impl里类型就是Self的公开常量
+//! 2. 本crate的常量和静态变量：从crate外面能访问到的（见FullNameMap::public_paths），
+//!    静态变量不能移出来，生产者返回`&T`，写成`&path`；`static mut`要unsafe，不考虑
+//! 只考虑没有类型和常量泛型参数的ADT，常量的类型要和参数类型一样
+
+use crate::clean;
+use crate::fuzz_targets_gen::api_function::{ApiAbi, ApiAsyncness, ApiFunction, ApiUnsafety};
+use crate::fuzz_targets_gen::api_graph::ApiGraph;
+use crate::fuzz_targets_gen::api_util;
+use crate::fuzz_targets_gen::prelude_type;
+use rustc_data_structures::fx::FxHashMap;
+use rustc_hir::Mutability;
+use rustc_hir::def::DefKind;
+use rustc_hir::def_id::DefId;
+use rustc_middle::ty::{self, TyCtxt, Visibility};
+use thin_vec::ThinVec;
+
+/// 一个常量生产者：(函数名, 生成代码里写的表达式, 返回类型)
+type ConstProducer = (String, String, clean::Type);
+
+/// 常量或者静态变量的类型是没有泛型参数的ADT时，返回ADT的did
+fn const_adt(tcx: TyCtxt<'_>, def_id: DefId) -> Option<DefId> {
+    tcx.type_of(def_id)
+        .ty_adt_def()
+        .map(|adt_def| adt_def.did())
+        .filter(|adt| is_plain_adt(tcx, *adt))
+}
+
+/// 没有类型和常量泛型参数的结构体、枚举、联合体
+fn is_plain_adt(tcx: TyCtxt<'_>, def_id: DefId) -> bool {
+    if !matches!(tcx.def_kind(def_id), DefKind::Struct | DefKind::Enum | DefKind::Union) {
+        return false;
+    }
+    let counts = tcx.generics_of(def_id).own_counts();
+    counts.types == 0 && counts.consts == 0
+}
+
+/// 给参数类型加上常量生产者，要在所有函数都加进图里、过滤完之后调用
+pub(crate) fn add_const_producers(tcx: TyCtxt<'_>, api_graph: &mut ApiGraph<'_>) {
+    let cache = api_graph.cache;
+    //本crate公开的常量和静态变量，按类型分组：ADT的did -> (路径, 是否是静态变量)
+    let mut crate_consts: FxHashMap<DefId, Vec<(String, bool)>> = FxHashMap::default();
+    let mut public_paths = api_graph.full_name_map.public_paths.iter().collect::<Vec<_>>();
+    public_paths.sort_by(|(_, x), (_, y)| x.cmp(y));
+    for (def_id, path) in public_paths {
+        let is_static = match tcx.def_kind(*def_id) {
+            DefKind::Const => false,
+            DefKind::Static(Mutability::Not) => true,
+            _ => continue,
+        };
+        if api_graph.skips_def_id(tcx, *def_id) {
+            continue;
+        }
+        if let Some(adt) = const_adt(tcx, *def_id) {
+            crate_consts.entry(adt).or_default().push((path.clone(), is_static));
+        }
+    }
+
+    let mut producers: Vec<ConstProducer> = Vec::new();
+    for api_function in &api_graph.api_functions {
+        for input_type in &api_function.inputs {
+            let inner_type = match input_type {
+                clean::Type::BorrowedRef { type_, .. } => &**type_,
+                _ => input_type,
+            };
+            let Some(type_did) = (match inner_type {
+                clean::Type::Path { path } => path.res.opt_def_id(),
+                _ => None,
+            }) else {
+                continue;
+            };
+            if !is_plain_adt(tcx, type_did)
+                || prelude_type::_prelude_type_need_special_dealing(
+                    inner_type,
+                    cache,
+                    &api_graph.full_name_map,
+                )
+                || api_util::is_fuzzable_type(
+                    inner_type,
+                    cache,
+                    &api_graph.full_name_map,
+                    &api_graph.emit_options,
+                    None,
+                )
+            {
+                continue;
+            }
+            //带生命周期参数的类型名不能直接写在路径里
+            let type_name = api_util::_type_name(inner_type, cache, &api_graph.full_name_map);
+            if type_name.contains('\'') {
+                continue;
+            }
+            let mut candidates = Vec::new();
+            for impl_did in tcx.inherent_impls(type_did) {
+                for assoc_item in tcx.associated_items(*impl_did).in_definition_order() {
+                    if assoc_item.kind == ty::AssocKind::Const
+                        && tcx.visibility(assoc_item.def_id).is_public()
+                        && !api_graph.skips_def_id(tcx, assoc_item.def_id)
+                        && const_adt(tcx, assoc_item.def_id) == Some(type_did)
+                    {
+                        let full_name = format!("{}::{}", type_name, assoc_item.name);
+                        candidates.push((full_name.clone(), full_name, inner_type.clone()));
+                    }
+                }
+            }
+            for (path, is_static) in crate_consts.get(&type_did).into_iter().flatten() {
+                if *is_static {
+                    let output_type = clean::Type::BorrowedRef {
+                        lifetime: None,
+                        mutability: Mutability::Not,
+                        type_: Box::new(inner_type.clone()),
+                    };
+                    candidates.push((path.clone(), format!("&{}", path), output_type));
+                } else {
+                    candidates.push((path.clone(), path.clone(), inner_type.clone()));
+                }
+            }
+            for candidate in candidates {
+                if !producers.iter().any(|(full_name, _, output_type)| {
+                    *full_name == candidate.0 && *output_type == candidate.2
+                }) {
+                    producers.push(candidate);
+                }
+            }
+        }
+    }
+
+    let added = producers.len();
+    for (full_name, expression, output_type) in producers {
+        api_graph.const_producers.insert(full_name.clone(), expression);
+        api_graph.add_api_function(ApiFunction {
+            full_name,
+            _generics: clean::Generics { params: ThinVec::new(), where_predicates: ThinVec::new() },
+            generic_substitutions: FxHashMap::default(),
+            inputs: Vec::new(),
+            output: Some(output_type),
+            _trait_full_path: None,
+            _unsafe_tag: ApiUnsafety::Normal,
+            _abi: ApiAbi::Rust,
+            _asyncness: ApiAsyncness::NotAsync,
+            visibility: Visibility::Public,
+            span: None,
+            def_id: None,
+            _uses_unsafe: false,
+            complexity: None,
+            trait_dispatch: None,
+        });
+    }
+    println!("{} const producers added", added);
+}
@@ -7,21 +7,25 @@
 
 use rustc_data_structures::fx::FxHashMap;
 //use rustc_data_structures::fx::{FxHashMap, FxHashSet};
-//use rustc_hir::def_id::DefId;
+use rustc_hir::def_id::DefId;
 use rustc_middle::ty::TyCtxt;
 use rustc_span::Symbol;
 
-use super::{api_function, api_util, impl_util};
+use super::{
+    api_function, api_util, corpus_seed, diagnostics, error, explain, fries_config, timing,
+};
 use crate::clean::{self, types as clean_types};
 use crate::config::RenderOptions;
 use crate::error::Error;
 use crate::formats::cache::Cache;
 use crate::formats::item_type::ItemType;
 use crate::formats::FormatRenderer;
+use crate::fuzz_targets_gen::analyzer::{Analyzer, GenerationConfig, GraphTraverseAlgorithm};
 use crate::fuzz_targets_gen::api_graph::ApiGraph;
 use crate::fuzz_targets_gen::extract_dep::extract_all_dependencies;
 use crate::fuzz_targets_gen::extract_info::ExtractInfo;
 use crate::fuzz_targets_gen::file_util::{self};
+use crate::fuzz_targets_gen::impl_util::FullNameMap;
 use rustc_data_structures::fx::FxHashSet;
 
 lazy_static! {
@@ -95,6 +99,18 @@ fn join_with_double_colon(syms: &[Symbol]) -> String {
         s.push_str(item.name.unwrap().as_str());
         s
     }
+
+    /// 生成代码里用的路径：有公开路径（包括`pub use`重导出的）的时候用最短的公开路径，否则用当前模块的路径
+    pub(crate) fn public_path(
+        &self,
+        item: &clean_types::Item,
+        full_name_map: &FullNameMap,
+    ) -> String {
+        match item.item_id.as_def_id().and_then(|def_id| full_name_map._get_public_path(def_id)) {
+            Some(public_path) => public_path.clone(),
+            None => self.full_path(item),
+        }
+    }
 }
 
 impl<'tcx> FormatRenderer<'tcx> for Context<'tcx> {
@@ -120,7 +136,8 @@ fn init(
             // 解析corpus program
 
             let tested_lib_name = "semver";
-            let experiment_root = "/home/yxz/workspace/fuzz/experiment_root/";
+            let experiment_root = format!("{}/", file_util::experiment_root());
+            let experiment_root = experiment_root.as_str();
 
             if !std::env::current_dir().unwrap().starts_with(experiment_root) {
                 return Ok((cx, krate));
@@ -159,7 +176,10 @@ fn init(
             // 解析tested lib
             let kname = krate.name(tcx).to_string();
 
-            if !REAL_WORLD_CRATE.contains(&kname) {
+            // cargo fries通过FRIES_TARGET_CRATE指定要生成的crate
+            let is_driver_target =
+                std::env::var("FRIES_TARGET_CRATE").map_or(false, |target| target == kname);
+            if !REAL_WORLD_CRATE.contains(&kname) && !is_driver_target {
                 println!("待测库没有这个crate {}", kname);
                 return Ok((cx, krate));
             }
@@ -169,27 +189,53 @@ fn init(
                 kname
             );
 
-            let support_generic = false;
-
-            // 新建一个API依赖图
-            let mut api_graph = ApiGraph::new(&krate.name(tcx).to_string(), cx.cache());
-            let mut full_name_map = impl_util::FullNameMap::new();
-
-            // 下面的代码块把method和bare function解析进入api_graph
-            {
-                impl_util::extract_impls_from_cache(
-                    cx.cache(),
-                    tcx,
-                    &mut full_name_map,
-                    &mut api_graph,
-                );
-                let _ret =
-                    cx.clone().add_bare_functions_into_api_graph(tcx, &krate, &mut api_graph);
-            }
-
-            api_graph.filter_functions(support_generic);
-
-            api_graph.find_all_dependencies(support_generic);
+            // 调优参数来自fries.toml，命令行可以覆盖算法和它的参数，见fuzz_options.rs
+            let fuzz_options = &options.fuzz_options;
+            let mut tuning = fries_config::load_tuning_config(&kname);
+            tuning.merge(fuzz_options.tuning_overrides());
+            let max_num = tuning.max_num.unwrap_or(100);
+            let max_len = tuning.max_len.unwrap_or(15);
+            // 没有显式设置时，配置了候选类型就实例化泛型API
+            let support_generic =
+                tuning.support_generic.unwrap_or(tuning.generic_candidates.is_some());
+
+            // 新建一个API依赖图，把method和bare function解析进入api_graph，再找出依赖关系
+            let generation_config = GenerationConfig {
+                strategy: fuzz_options.algorithm.unwrap_or(GraphTraverseAlgorithm::_UseRealWorld),
+                max_num,
+                max_len,
+                support_generic,
+                walk_steps: tuning.walk_steps,
+                bfs_max_len: tuning.bfs_max_len,
+                cover_nodes: tuning.cover_nodes,
+                generic_candidates: tuning.generic_candidates.clone(),
+                population_size: tuning.population_size,
+                generations: tuning.generations,
+                beam_width: tuning.beam_width,
+                frequency_exponent: tuning.frequency_exponent,
+                prioritize_unsafe: tuning.prioritize_unsafe.unwrap_or(false),
+                keep_trivial_accessors: tuning.keep_trivial_accessors.unwrap_or(false),
+                include: tuning.include.clone().unwrap_or_default(),
+                exclude: tuning.exclude.clone().unwrap_or_default(),
+                include_deprecated: fuzz_options.include_deprecated,
+                include_doc_hidden: fuzz_options.include_doc_hidden,
+                numeric_cast: fuzz_options.numeric_cast.unwrap_or_default(),
+                path_mode: fuzz_options.path_mode.unwrap_or_default(),
+                float_corners: fuzz_options.float_corners,
+                allow_unsafe_synthesis: fuzz_options.allow_unsafe_synthesis,
+                internal: fuzz_options.internal,
+                selection: fuzz_options.selection,
+                load_graph: fuzz_options.load_graph,
+                save_graph: fuzz_options.save_graph,
+                corpus_path: fuzz_options.corpus_path.clone(),
+            };
+            let mut analyzer = Analyzer::from_rustdoc_output(&cx, &krate, tcx, generation_config);
+            // crate目录下的配置文件，其中的fries.toml会覆盖config，所以之后再用命令行选项覆盖一次
+            analyzer.load_config_dir(&format!("{}/{}", file_util::experiment_root(), kname));
+            let mut generation_config = analyzer.config().clone();
+            fuzz_options.tuning_overrides().apply_to(&mut generation_config);
+            analyzer.set_config(generation_config.clone());
+            let api_graph = analyzer.api_graph_mut();
 
             println!("total functions in crate : {:?}", api_graph.api_functions.len());
 
@@ -202,33 +248,69 @@ fn init(
             let fudge = false;
             let fudge_test_lib = "bat";
 
-            let max_num = 100;
-            let max_len = 15;
-
+            // Err/None时跳过依赖它的语句，而不是直接退出
+            api_graph.emit_options.continue_on_error = true;
+            // 每个API调用的watchdog，None表示不开启
+            api_graph.emit_options.call_timeout_ms = fuzz_options.call_timeout_ms;
+            // 是否额外生成解释器模式的fuzz target
+            api_graph.emit_options.interpreter_mode = fuzz_options.interpreter_mode;
+            // 是否把测试函数生成到待测crate内部
+            api_graph.emit_options.internal_mode = generation_config.internal;
+            // 每个序列生成哪些编码方式的fuzz target，默认只用raw
+            if !fuzz_options.encodings.is_empty() {
+                api_graph.emit_options.encoding_variants = fuzz_options.encodings.clone();
+            }
+            // 执行async fn的运行时
+            api_graph.emit_options.async_runtime = fuzz_options.async_runtime.unwrap_or_default();
+            // afl之外的fuzz引擎
+            api_graph.emit_options.backends = fuzz_options.backends.clone();
+            // 是否导出json
+            api_graph.emit_options.export_json = fuzz_options.export_json;
+            // Option/Result的取值方式
+            api_graph.emit_options.unwrap_strategy =
+                fuzz_options.unwrap_strategy.unwrap_or_default();
+            api_graph.emit_options.none_policy = fuzz_options.none_policy.unwrap_or_default();
+            // 捕获panic，只把关心的类别当成crash
+            api_graph.emit_options.crash_panic_classes = fuzz_options.crash_panic_classes.clone();
+            // 整个序列的超时，None表示不限制
+            api_graph.emit_options.target_timeout_ms = fuzz_options.target_timeout_ms;
+            // 语料里记录的参数值，用来生成种子输入
+            let corpus_dir = file_util::corpus_info_dir(&file_util::corpus_path(
+                fuzz_options.corpus_path.as_deref(),
+                format!("{}/{}", file_util::experiment_root(), kname),
+            ));
+            corpus_seed::load_argument_values_from_file(
+                corpus_dir.join(corpus_seed::ARGUMENT_VALUES_FILE).display().to_string().as_str(),
+                &mut api_graph.emit_options.argument_values,
+            );
             if fries {
                 println!(
                     "Fries Start!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!!"
                 );
                 api_graph.api_sequences.clear();
-                //let generation_strategy = _Bfs;
-                let generation_strategy = _UseRealWorld;
-                //let generation_strategy = _RandomWalk;
-                api_graph.generate_all_possoble_sequences(
-                    generation_strategy,
+                //默认是_UseRealWorld，可以用--fuzz-algorithm选择别的算法
+                let generation_strategy = generation_config.strategy;
+                let generated = match api_graph.generate_sequences(
                     krate.name(tcx).as_str().replace("_", "-").as_str(),
-                    max_num,
-                    max_len,
-                    support_generic,
-                );
+                    &generation_config,
+                ) {
+                    Ok(()) => true,
+                    Err(e) => {
+                        error::warn(&e, "skip writing test files");
+                        false
+                    }
+                };
                 // 计算经过的时间
                 let duration = start.elapsed();
                 println!("代码执行时间: {:?}", duration);
 
-                if file_util::can_write_to_file(
-                    &api_graph._crate_name.replace("_", "-"),
-                    //&"unicode-segmentation".to_owned(),
-                    generation_strategy,
-                ) {
+                if generated
+                    && file_util::can_write_to_file(
+                        &api_graph._crate_name.replace("_", "-"),
+                        //&"unicode-segmentation".to_owned(),
+                        generation_strategy,
+                    )
+                {
                     println!("I will write test case into files");
                     //whether to use random strategy
                     let file_helper = file_util::FileHelper::new(
@@ -239,6 +321,12 @@ fn init(
                     );
                     file_helper.write_files();
                 }
+                println!("-----------PHASE TIMING-----------");
+                print!("{}", timing::timing_report());
+                // --fuzz-explain：解释为什么没有序列到达指定的API
+                if let Some(api_path) = &fuzz_options.explain {
+                    print!("{}", explain::explain_api(api_graph, api_path, support_generic));
+                }
 
                 println!("Fries! Finish to parse tested crate and generate test file.");
             }
@@ -250,24 +338,32 @@ fn init(
                 api_graph.api_sequences.clear();
                 let generation_strategy = _Fudge;
                 //let generation_strategy = _RandomWalk;
-                api_graph.generate_all_possoble_sequences(
+                let generated = match api_graph.generate_all_possoble_sequences(
                     generation_strategy,
                     fudge_test_lib.replace("_", "-").as_str(),
                     //krate.name(tcx).as_str().replace("_", "-").as_str(),
                     max_num,
                     max_len,
                     support_generic,
-                );
+                ) {
+                    Ok(()) => true,
+                    Err(e) => {
+                        error::warn(&e, "skip writing test files");
+                        false
+                    }
+                };
                 // 计算经过的时间
                 let duration = start.elapsed();
                 println!("代码执行时间: {:?}", duration);
                 println!("total functions in crate : {:?}", api_graph.api_functions.len());
 
-                if file_util::can_write_to_file(
-                    &api_graph._crate_name.replace("_", "-"),
-                    //&"unicode-segmentation".to_owned(),
-                    generation_strategy,
-                ) {
+                if generated
+                    && file_util::can_write_to_file(
+                        &api_graph._crate_name.replace("_", "-"),
+                        //&"unicode-segmentation".to_owned(),
+                        generation_strategy,
+                    )
+                {
                     println!("I will write test case into files");
                     //whether to use random strategy
                     let file_helper = file_util::FileHelper::new(
@@ -289,22 +385,30 @@ fn init(
                 api_graph.api_sequences.clear();
                 let generation_strategy = _RandomWalk;
                 //let generation_strategy = _RandomWalk;
-                api_graph.generate_all_possoble_sequences(
+                let generated = match api_graph.generate_all_possoble_sequences(
                     generation_strategy,
                     krate.name(tcx).as_str().replace("_", "-").as_str(),
                     max_num,
                     max_len,
                     support_generic,
-                );
+                ) {
+                    Ok(()) => true,
+                    Err(e) => {
+                        error::warn(&e, "skip writing test files");
+                        false
+                    }
+                };
                 // 计算经过的时间
 
                 println!("total functions in crate : {:?}", api_graph.api_functions.len());
 
-                if file_util::can_write_to_file(
-                    &api_graph._crate_name.replace("_", "-"),
-                    //&"unicode-segmentation".to_owned(),
-                    generation_strategy,
-                ) {
+                if generated
+                    && file_util::can_write_to_file(
+                        &api_graph._crate_name.replace("_", "-"),
+                        //&"unicode-segmentation".to_owned(),
+                        generation_strategy,
+                    )
+                {
                     println!("I will write test case into files");
                     //whether to use random strategy
                     let file_helper = file_util::FileHelper::new(
@@ -320,6 +424,7 @@ fn init(
             }
             let duration = start.elapsed();
             println!("代码执行时间: {:?}", duration);
+            diagnostics::emit_warnings(tcx.sess);
         }
         Ok((cx, krate))
     }
@@ -354,7 +459,7 @@ fn cache(&self) -> &Cache {
 
 impl Context<'_> {
     /// 用来把裸函数装进图中
-    fn add_bare_functions_into_api_graph(
+    pub(crate) fn add_bare_functions_into_api_graph(
         self,
         tcx: TyCtxt<'_>,
         raw_krate: &clean::Crate,
@@ -371,12 +476,18 @@ fn add_bare_functions_into_api_graph(
         //创建栈，对其进行深度优先搜索
         //let mut work = vec![(self.clone(), item)];
 
+        //可以用字面量构造的结构体，所有函数都加进去之后再决定要不要作为生产者
+        let mut struct_literals = Vec::new();
+        //已经加入的item，重导出的item被rustdoc内联之后，原来的模块里还有一份
+        let mut added_items = FxHashSet::default();
         {
             //while let Some((cx, item)) = work.pop() {
             self.add_bare_functions_into_api_graph_util(
                 tcx,
                 item,
                 &mut api_graph,
+                &mut struct_literals,
+                &mut added_items,
                 //&mut work,
                 //|conx, item| {
                 // work.push((conx.to_owned(), item))
@@ -384,6 +495,7 @@ fn add_bare_functions_into_api_graph(
             )?
             //}
         }
+        api_util::_add_struct_literal_producers(&mut api_graph, struct_literals);
         Ok(())
     }
 
@@ -393,6 +505,8 @@ fn add_bare_functions_into_api_graph_util(
         tcx: TyCtxt<'_>,
         item: clean::Item,
         api_graph: &mut ApiGraph<'_>,
+        struct_literals: &mut Vec<api_util::StructLiteral>,
+        added_items: &mut FxHashSet<DefId>,
     ) -> Result<(), Error> {
         //如果是模块，就递归进去
         if item.is_mod() {
@@ -409,7 +523,11 @@ fn add_bare_functions_into_api_graph_util(
                 self.current.iter().map(|x| x.to_string()).collect::<Vec<String>>().join("::");
 
             //添加mod的可见性
-            api_graph.add_mod_visibility(&mod_name, &item.visibility(tcx).unwrap().expect_local());
+            api_graph.add_mod_visibility(
+                &mod_name,
+                &item.visibility(tcx).unwrap().expect_local(),
+                item.item_id.as_def_id().and_then(|def_id| def_id.as_local()),
+            );
 
             let m = match *item.kind {
                 clean::StrippedItem(box clean::ModuleItem(m)) | clean::ModuleItem(m) => m,
@@ -421,7 +539,13 @@ fn add_bare_functions_into_api_graph_util(
                 //work.push((self.clone(), item));
                 //f(self, item);
                 let cx = self.clone();
-                cx.add_bare_functions_into_api_graph_util(tcx, item.clone(), api_graph)?
+                cx.add_bare_functions_into_api_graph_util(
+                    tcx,
+                    item.clone(),
+                    api_graph,
+                    struct_literals,
+                    added_items,
+                )?
             }
 
             //回溯
@@ -430,12 +554,28 @@ fn add_bare_functions_into_api_graph_util(
         }
         // 如果不是模块，但有名字
         else if item.name.is_some() {
+            //两份用的是同一个公开路径，只加一次
+            if let Some(def_id) = item.item_id.as_def_id() {
+                if !added_items.insert(def_id) {
+                    return Ok(());
+                }
+            }
             //item是函数,将函数添加到api_dependency_graph里面去
             let item_type = item.type_();
+            //类型和trait的可见性，用来过滤它们的方法
+            if let (
+                ItemType::Struct | ItemType::Enum | ItemType::Union | ItemType::Trait,
+                Some(visibility),
+            ) = (item_type, item.visibility(tcx))
+            {
+                let item_name = self.public_path(&item, &api_graph.full_name_map);
+                api_graph.add_item_visibility(&item_name, &visibility.expect_local());
+            }
             if item_type == ItemType::Function {
-                let full_name = self.full_path(&item);
+                let full_name = self.public_path(&item, &api_graph.full_name_map);
                 //println!("full_name = {}", full_name);
                 match *item.kind {
+                    clean::FunctionItem(_) if api_graph.skips_item(tcx, &item) => {}
                     clean::FunctionItem(ref func) => {
                         let decl = func.decl.clone();
                         let _generics = func.generics.clone();
@@ -443,9 +583,12 @@ fn add_bare_functions_into_api_graph_util(
                         let clean::FnDecl { inputs, output, .. } = decl;
                         let inputs = api_util::_extract_input_types(&inputs);
                         let output = api_util::_extract_output_type(&output);
-                        let api_unsafety = api_function::ApiUnsafety::_get_unsafety_from_fnheader(
-                            &item.fn_header(tcx).unwrap(),
-                        );
+                        let fn_header = item.fn_header(tcx).unwrap();
+                        let api_unsafety =
+                            api_function::ApiUnsafety::_get_unsafety_from_fnheader(&fn_header);
+                        let api_abi = api_function::ApiAbi::_get_abi_from_fnheader(&fn_header);
+                        let api_asyncness =
+                            api_function::ApiAsyncness::_get_asyncness_from_fnheader(&fn_header);
                         let api_fun = api_function::ApiFunction {
                             full_name,
                             _generics,
@@ -454,7 +597,14 @@ fn add_bare_functions_into_api_graph_util(
                             output,
                             _trait_full_path: None,
                             _unsafe_tag: api_unsafety,
+                            _abi: api_abi,
+                            _asyncness: api_asyncness,
                             visibility: item.visibility(tcx).unwrap().expect_local(),
+                            span: item.span(tcx).map(|span| span.inner()),
+                            def_id: item.item_id.as_def_id(),
+                            _uses_unsafe: false,
+                            complexity: None,
+                            trait_dispatch: None,
                         };
 
                         //let output_type = api_fun.output.clone().unwrap();
@@ -468,8 +618,55 @@ fn add_bare_functions_into_api_graph_util(
                     }
                     _ => {}
                 }
+            } else if item_type == ItemType::Struct {
+                if let Some(struct_literal) =
+                    self.struct_literal(tcx, &item, &api_graph.full_name_map)
+                {
+                    struct_literals.push(struct_literal);
+                }
             }
         }
         Ok(())
     }
+
+    /// 结构体能否在crate外面用字面量构造：没有类型和常量泛型参数，不是non_exhaustive，
+    /// 没有被rustdoc去掉的私有字段，所有字段都是pub
+    fn struct_literal(
+        &self,
+        tcx: TyCtxt<'_>,
+        item: &clean::Item,
+        full_name_map: &FullNameMap,
+    ) -> Option<api_util::StructLiteral> {
+        let clean::StructItem(ref struct_) = *item.kind else {
+            return None;
+        };
+        if item.is_non_exhaustive()
+            || struct_.has_stripped_entries()
+            || struct_.fields.is_empty()
+            || struct_
+                .generics
+                .params
+                .iter()
+                .any(|param| !matches!(param.kind, clean::GenericParamDefKind::Lifetime { .. }))
+        {
+            return None;
+        }
+        let mut fields = Vec::new();
+        for field in &struct_.fields {
+            let clean::StructFieldItem(ref field_type) = *field.kind else {
+                return None;
+            };
+            if !field.visibility(tcx).map_or(false, |visibility| visibility.is_public()) {
+                return None;
+            }
+            fields.push((field.name?.to_string(), field_type.clone()));
+        }
+        Some(api_util::StructLiteral {
+            def_id: item.item_id.as_def_id()?,
+            full_name: self.public_path(item, full_name_map),
+            fields,
+            visibility: item.visibility(tcx)?.expect_local(),
+            span: item.span(tcx).map(|span| span.inner()),
+        })
+    }
 }
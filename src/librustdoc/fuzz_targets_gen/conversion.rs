@@ -0,0 +1,142 @@
+//! 用户注册的类型转换
+//! 有些类型之间的转换FRIES推断不出来（比如需要调用某个包装函数），用户可以在配置文件里声明，
+//! 每行的格式是 `源类型全路径  |  目标类型全路径  |  转换表达式`，比如
+//!     fixture::Foo  |  fixture::Bar  |  fixture::Bar::wrap(x)
+//! 表达式里用`x`指代源类型的值（按值move进来）。
+//! 找完依赖之后，对于返回源类型的函数和参数是目标类型（或者它的引用）的函数，加入一条依赖边，
+//! 生成测试文件的时候原样输出这个表达式
+
+use crate::clean::{self};
+use crate::formats::cache::Cache;
+use crate::fuzz_targets_gen::api_graph::{ApiDependency, ApiGraph, ApiType};
+use crate::fuzz_targets_gen::call_type::CallType;
+use crate::fuzz_targets_gen::diagnostics::{self, WarningKind};
+use crate::fuzz_targets_gen::file_util;
+use crate::fuzz_targets_gen::impl_util::FullNameMap;
+use crate::fuzz_targets_gen::invariant;
+use crate::fuzz_targets_gen::prelude_type::{self, PreludeType};
+use rustc_hir::Mutability;
+
+/// 一条用户注册的转换
+#[derive(Debug, Clone)]
+pub(crate) struct CustomConversion {
+    pub(crate) from: String,
+    pub(crate) to: String,
+    pub(crate) expression: String,
+}
+
+/// 从文件读取转换，文件不存在的话什么都不做
+pub(crate) fn load_conversions_from_file(file_path: &str, conversions: &mut Vec<CustomConversion>) {
+    for (from, rest) in file_util::read_config_pairs(file_path) {
+        match rest.split_once("|") {
+            Some((to, expression)) => conversions.push(CustomConversion {
+                from,
+                to: to.trim().to_string(),
+                expression: expression.trim().to_string(),
+            }),
+            None => diagnostics::warn(
+                WarningKind::Degraded,
+                None,
+                format!("invalid conversion in {}: {}  |  {}, ignored", file_path, from, rest),
+            ),
+        }
+    }
+}
+
+/// 按照用户注册的转换加入依赖边，已经能推断出依赖的参数不再重复添加
+/// 返回加入的依赖数量
+pub(crate) fn add_conversion_dependencies(
+    graph: &mut ApiGraph<'_>,
+    conversions: &[CustomConversion],
+) -> usize {
+    if conversions.is_empty() {
+        return 0;
+    }
+    let cache = graph.cache;
+    let full_name_map = &graph.full_name_map;
+    let mut new_dependencies = Vec::new();
+    for (i, first_fun) in graph.api_functions.iter().enumerate() {
+        let output_type = match &first_fun.output {
+            Some(output_type) => output_type,
+            None => continue,
+        };
+        //和find_all_dependencies一样，Option和Result先取出里面的类型，生成的时候会单独unwrap
+        let output_type = if prelude_type::_prelude_type_need_special_dealing(
+            output_type,
+            cache,
+            full_name_map,
+        ) {
+            PreludeType::from_type(output_type, cache, full_name_map)._get_final_type()
+        } else {
+            output_type.clone()
+        };
+        let output_path = match invariant::type_full_path(&output_type, cache, full_name_map) {
+            Some(output_path) => output_path,
+            None => continue,
+        };
+        for conversion in conversions.iter().filter(|conversion| conversion.from == output_path) {
+            for (j, second_fun) in graph.api_functions.iter().enumerate() {
+                for (k, input_type) in second_fun.inputs.iter().enumerate() {
+                    let call_type =
+                        match converted_call_type(input_type, conversion, cache, full_name_map) {
+                            Some(call_type) => call_type,
+                            None => continue,
+                        };
+                    let exists = graph.dependency_index.contains_key(&(i, j, k));
+                    if !exists {
+                        new_dependencies.push(ApiDependency {
+                            output_fun: (ApiType::BareFunction, i),
+                            input_fun: (ApiType::BareFunction, j),
+                            input_param_index: k,
+                            call_type,
+                        });
+                    }
+                }
+            }
+        }
+    }
+    let added = new_dependencies.len();
+    for dependency in new_dependencies {
+        graph.push_dependency(dependency);
+    }
+    println!("{} dependencies added by custom conversions", added);
+    added
+}
+
+/// 参数是目标类型或者目标类型的引用时，返回对应的调用方式
+fn converted_call_type(
+    input_type: &clean::Type,
+    conversion: &CustomConversion,
+    cache: &Cache,
+    full_name_map: &FullNameMap,
+) -> Option<CallType> {
+    let convert = CallType::_CustomConvert(conversion.expression.clone());
+    match input_type {
+        clean::Type::BorrowedRef { mutability, type_, .. } => {
+            if !is_target_type(type_, &conversion.to, cache, full_name_map) {
+                return None;
+            }
+            match mutability {
+                Mutability::Mut => Some(CallType::_MutBorrowedRef(Box::new(convert))),
+                Mutability::Not => Some(CallType::_BorrowedRef(Box::new(convert))),
+            }
+        }
+        _ if is_target_type(input_type, &conversion.to, cache, full_name_map) => Some(convert),
+        _ => None,
+    }
+}
+
+/// Option、Result等需要特殊处理的类型不算，type_full_path会把它们拆开
+fn is_target_type(
+    type_: &clean::Type,
+    target: &str,
+    cache: &Cache,
+    full_name_map: &FullNameMap,
+) -> bool {
+    if !matches!(type_, clean::Type::Path { .. } | clean::Type::Primitive(..))
+        || prelude_type::_prelude_type_need_special_dealing(type_, cache, full_name_map)
+    {
+        return false;
+    }
+    invariant::type_full_path(type_, cache, full_name_map).as_deref() == Some(target)
+}
@@ -0,0 +1,313 @@
+//! 用语料里真实的参数值生成种子输入
+//! 语料只决定了调用哪些API，参数还是从空的输入开始变异。下游代码里调用API时写的字面量
+//! （正则表达式、格式串、端口号等）往往正好能走到有意思的路径，所以语料目录下可以额外放一个argvalues.txt，
+//! 每行的格式是 `函数全路径[参数下标]  |  字面量`，字面量按Rust的语法写，比如：
+//!
+//! ```text
+//! regex::Regex::new[0]  |  "^[a-z]+$"
+//! url::Url::parse[0]  |  "http://localhost:8080/"
+//! bytes::Bytes::copy_from_slice[0]  |  b"\x00\x01"
+//! ```
+//!
+//! 生成fuzz target时，序列里可以fuzz的参数如果对应的(函数, 参数)有记录的值，就按afl target的字节布局
+//! （见_afl_closure_body）编码成种子，写到测试目录的`seeds/<target名>/`下，作为afl-fuzz -i的输入
+
+use crate::clean;
+use crate::fuzz_targets_gen::api_graph::ApiGraph;
+use crate::fuzz_targets_gen::api_sequence::{ApiSequence, ParamType};
+use crate::fuzz_targets_gen::diagnostics::{self, WarningKind};
+use crate::fuzz_targets_gen::file_util;
+use crate::fuzz_targets_gen::fuzz_type::FuzzableType;
+use rustc_data_structures::fx::FxHashMap;
+
+/// 语料目录下记录参数值的文件
+pub(crate) static ARGUMENT_VALUES_FILE: &'static str = "argvalues.txt";
+
+/// 每个target最多生成这么多个种子
+static MAX_SEEDS_PER_TARGET: usize = 8;
+
+/// 语料里记录的参数字面量
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum ArgumentLiteral {
+    Int(i128),
+    Float(f64),
+    Bool(bool),
+    Char(char),
+    Str(String),
+    Bytes(Vec<u8>),
+}
+
+/// (函数全路径, 参数下标) -> 观察到的字面量，按文件里出现的顺序
+pub(crate) type ArgumentValues = FxHashMap<(String, usize), Vec<ArgumentLiteral>>;
+
+/// 读取argvalues.txt，文件不存在的话什么都不加，格式不对的行跳过
+pub(crate) fn load_argument_values_from_file(file_path: &str, values: &mut ArgumentValues) {
+    for (key, literal) in file_util::read_config_pairs(file_path) {
+        let parsed_key = key.strip_suffix(']').and_then(|key| key.rsplit_once('[')).and_then(
+            |(function, index)| Some((function.trim().to_string(), index.parse::<usize>().ok()?)),
+        );
+        match (parsed_key, parse_literal(&literal)) {
+            (Some(key), Some(literal)) => values.entry(key).or_default().push(literal),
+            _ => diagnostics::warn(
+                WarningKind::Degraded,
+                None,
+                format!(
+                    "invalid argument value in {}: {}  |  {}, ignored",
+                    file_path, key, literal
+                ),
+            ),
+        }
+    }
+}
+
+fn parse_literal(literal: &str) -> Option<ArgumentLiteral> {
+    match literal {
+        "true" => return Some(ArgumentLiteral::Bool(true)),
+        "false" => return Some(ArgumentLiteral::Bool(false)),
+        _ => {}
+    }
+    if let Some(content) = literal.strip_prefix("b\"").and_then(|rest| rest.strip_suffix('"')) {
+        return unescape(content).map(ArgumentLiteral::Bytes);
+    }
+    if let Some(content) = literal.strip_prefix('"').and_then(|rest| rest.strip_suffix('"')) {
+        return String::from_utf8(unescape(content)?).ok().map(ArgumentLiteral::Str);
+    }
+    if let Some(content) = literal.strip_prefix('\'').and_then(|rest| rest.strip_suffix('\'')) {
+        let text = String::from_utf8(unescape(content)?).ok()?;
+        let mut chars = text.chars();
+        return match (chars.next(), chars.next()) {
+            (Some(c), None) => Some(ArgumentLiteral::Char(c)),
+            _ => None,
+        };
+    }
+    parse_number(literal)
+}
+
+/// 整数和浮点数，可以带`_`、类型后缀和`0x`/`0o`/`0b`前缀
+fn parse_number(literal: &str) -> Option<ArgumentLiteral> {
+    let literal = literal.replace('_', "");
+    let (negative, literal) = match literal.strip_prefix('-') {
+        Some(rest) => (true, rest.to_string()),
+        None => (false, literal),
+    };
+    let sign = if negative { -1 } else { 1 };
+    for (prefix, radix) in [("0x", 16), ("0o", 8), ("0b", 2)] {
+        if let Some(digits) = literal.strip_prefix(prefix) {
+            let digits = strip_type_suffix(digits, radix);
+            return i128::from_str_radix(digits, radix)
+                .ok()
+                .map(|value| ArgumentLiteral::Int(sign * value));
+        }
+    }
+    let digits = strip_type_suffix(&literal, 10);
+    if let Ok(value) = digits.parse::<i128>() {
+        return Some(ArgumentLiteral::Int(sign * value));
+    }
+    digits.parse::<f64>().ok().map(|value| ArgumentLiteral::Float(sign as f64 * value))
+}
+
+/// 去掉`u8`、`i64`、`f32`这样的后缀，十六进制的时候a-f是数字
+fn strip_type_suffix(literal: &str, radix: u32) -> &str {
+    for suffix in [
+        "u8", "u16", "u32", "u64", "u128", "usize", "i8", "i16", "i32", "i64", "i128", "isize",
+        "f32", "f64",
+    ] {
+        if radix == 16 && suffix.starts_with('f') {
+            continue;
+        }
+        if let Some(rest) = literal.strip_suffix(suffix) {
+            return rest;
+        }
+    }
+    literal
+}
+
+/// 处理字符串字面量里的转义，返回utf8编码后的字节
+fn unescape(content: &str) -> Option<Vec<u8>> {
+    let mut res = Vec::new();
+    let mut chars = content.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut buf = [0; 4];
+            res.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            continue;
+        }
+        match chars.next()? {
+            'n' => res.push(b'\n'),
+            'r' => res.push(b'\r'),
+            't' => res.push(b'\t'),
+            '0' => res.push(0),
+            '\\' => res.push(b'\\'),
+            '"' => res.push(b'"'),
+            '\'' => res.push(b'\''),
+            'x' => {
+                let hex: String = chars.by_ref().take(2).collect();
+                res.push(u8::from_str_radix(&hex, 16).ok()?);
+            }
+            'u' => {
+                let code: String = chars.by_ref().skip(1).take_while(|c| *c != '}').collect();
+                let c = char::from_u32(u32::from_str_radix(&code, 16).ok()?)?;
+                let mut buf = [0; 4];
+                res.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            }
+            _ => return None,
+        }
+    }
+    Some(res)
+}
+
+/// 按afl_util里解码函数的方式把字面量编码成字节，类型对不上的时候返回None
+/// 整数按大端（_to_u16等先取高位），浮点数按小端，bool的偶数是true，char按UTF-8
+fn encode_literal(fuzzable: &FuzzableType, literal: &ArgumentLiteral) -> Option<Vec<u8>> {
+    match (fuzzable, literal) {
+        (FuzzableType::Primitive(clean::PrimitiveType::Bool), ArgumentLiteral::Bool(value)) => {
+            Some(vec![if *value { 0 } else { 1 }])
+        }
+        (FuzzableType::Primitive(clean::PrimitiveType::Char), ArgumentLiteral::Char(value)) => {
+            //_to_char按UTF-8解码4个字节里的第一个字符，后面补0
+            let mut buf = [0; 4];
+            value.encode_utf8(&mut buf);
+            Some(buf.to_vec())
+        }
+        (FuzzableType::Primitive(clean::PrimitiveType::F32), ArgumentLiteral::Float(value)) => {
+            Some((*value as f32).to_le_bytes().to_vec())
+        }
+        (FuzzableType::Primitive(clean::PrimitiveType::F64), ArgumentLiteral::Float(value)) => {
+            Some(value.to_le_bytes().to_vec())
+        }
+        //选择字节不是特殊值
+        (FuzzableType::CornerFloat(clean::PrimitiveType::F32), ArgumentLiteral::Float(value)) => {
+            Some([&[u8::MAX][..], &(*value as f32).to_le_bytes()].concat())
+        }
+        (FuzzableType::CornerFloat(clean::PrimitiveType::F64), ArgumentLiteral::Float(value)) => {
+            Some([&[u8::MAX][..], &value.to_le_bytes()].concat())
+        }
+        (FuzzableType::Primitive(primitive_type), ArgumentLiteral::Int(value))
+            if !matches!(
+                primitive_type,
+                clean::PrimitiveType::Bool
+                    | clean::PrimitiveType::Char
+                    | clean::PrimitiveType::F32
+                    | clean::PrimitiveType::F64
+            ) =>
+        {
+            let width = fuzzable._min_length();
+            if width == 0 {
+                return None;
+            }
+            Some((*value as u128).to_be_bytes()[16 - width..].to_vec())
+        }
+        (FuzzableType::RefStr, ArgumentLiteral::Str(value)) => Some(value.as_bytes().to_vec()),
+        (FuzzableType::RefSlice(inner), ArgumentLiteral::Bytes(value))
+            if **inner == FuzzableType::Primitive(clean::PrimitiveType::U8) =>
+        {
+            Some(value.clone())
+        }
+        (FuzzableType::Array(inner, length), ArgumentLiteral::Bytes(value))
+            if **inner == FuzzableType::Primitive(clean::PrimitiveType::U8)
+                && value.len() == *length =>
+        {
+            Some(value.clone())
+        }
+        (FuzzableType::RefSlice(inner), ArgumentLiteral::Str(value))
+            if **inner == FuzzableType::Primitive(clean::PrimitiveType::U8) =>
+        {
+            Some(value.as_bytes().to_vec())
+        }
+        _ => None,
+    }
+}
+
+/// 长度可变的参数前面的LEB128长度，和afl_util::_byte_reader对应
+fn varint_length(mut length: usize) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    loop {
+        let byte = (length & 0x7f) as u8;
+        length >>= 7;
+        if length == 0 {
+            bytes.push(byte);
+            return bytes;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+/// 每个可以fuzz的参数被序列里哪些(函数, 参数下标)使用
+fn fuzzable_consumers(
+    sequence: &ApiSequence,
+    api_graph: &ApiGraph<'_>,
+) -> Vec<Vec<(String, usize)>> {
+    let mut consumers = vec![Vec::new(); sequence.fuzzable_params.len()];
+    for api_call in &sequence.functions {
+        let full_name = &api_graph.api_functions[api_call.func.1].full_name;
+        for (param_index, (param_type, index, _)) in api_call.params.iter().enumerate() {
+            if *param_type == ParamType::_FuzzableType && *index < consumers.len() {
+                consumers[*index].push((full_name.clone(), param_index));
+            }
+        }
+    }
+    consumers
+}
+
+/// 序列对应的afl target（RawSplit编码）的种子，没有任何参数有记录的值时返回空
+/// 第k个种子用每个参数的第k个值（不够的话循环），没有值的参数保持全0
+pub(crate) fn sequence_seeds(sequence: &ApiSequence, api_graph: &ApiGraph<'_>) -> Vec<Vec<u8>> {
+    let argument_values = &api_graph.emit_options.argument_values;
+    if argument_values.is_empty() {
+        return Vec::new();
+    }
+    //每个参数可以用的编码后的值
+    let encoded_values: Vec<Vec<Vec<u8>>> = fuzzable_consumers(sequence, api_graph)
+        .iter()
+        .zip(&sequence.fuzzable_params)
+        .map(|(consumers, fuzzable)| {
+            consumers
+                .iter()
+                .filter_map(|consumer| argument_values.get(consumer))
+                .flatten()
+                .filter_map(|literal| encode_literal(fuzzable, literal))
+                .collect()
+        })
+        .collect();
+    let seed_count = encoded_values
+        .iter()
+        .map(|values| values.len())
+        .max()
+        .unwrap_or(0)
+        .min(MAX_SEEDS_PER_TARGET);
+
+    let min_length = sequence._fuzzables_min_length();
+    let mut seeds = Vec::new();
+    for k in 0..seed_count {
+        //参数按顺序排列，和_ByteReader读取的顺序一致
+        let mut seed = Vec::new();
+        for (fuzzable, values) in sequence.fuzzable_params.iter().zip(&encoded_values) {
+            let value = if values.is_empty() { None } else { Some(&values[k % values.len()]) };
+            if fuzzable._is_fixed_length() {
+                let length = fuzzable._min_length();
+                match value.filter(|value| value.len() == length) {
+                    Some(value) => seed.extend(value),
+                    None => seed.resize(seed.len() + length, 0),
+                }
+            } else if fuzzable._dynamic_length_param_number() == 1
+                && fuzzable._fixed_part_length() == 0
+            {
+                //长度前缀加上内容，&[&str]这样嵌套的参数没有记录的值，前缀0表示空
+                let value = value.cloned().unwrap_or_default();
+                seed.extend(varint_length(value.len()));
+                seed.extend(value);
+            } else {
+                //元组里带切片这样的参数布局太复杂，不生成种子
+                return Vec::new();
+            }
+        }
+        //不够最短长度的时候在后面补0，解码时读不到
+        if seed.len() < min_length {
+            seed.resize(min_length, 0);
+        }
+        if !seeds.contains(&seed) {
+            seeds.push(seed);
+        }
+    }
+    seeds
+}
@@ -0,0 +1,80 @@
+//! 生成过程中的警告
+//! 包含不支持的类型、不可见的API、被跳过的序列等情况，生成过程中先记录下来，
+//! rustdoc pass结束时通过rustc的诊断接口输出，有span的话指向函数签名，这样编辑器和`--error-format=json`都能看到。
+//! 从json文档生成时没有Session，由调用者取出之后自己打印
+
+use rustc_session::Session;
+use rustc_span::Span;
+use std::cell::RefCell;
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum WarningKind {
+    /// 参数或返回值的类型不支持，函数被跳过
+    UnsupportedType,
+    /// 函数声明为pub，但是所在的mod或者trait在crate外不可见
+    InvisibleApi,
+    /// 生成的序列没有写成测试文件
+    SkippedSequence,
+    /// 某个阶段出错之后降级处理，见error.rs
+    Degraded,
+    /// 不会返回的函数出现在序列中间，后面的语句执行不到
+    DeadStatements,
+}
+
+impl fmt::Display for WarningKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            WarningKind::UnsupportedType => "unsupported type",
+            WarningKind::InvisibleApi => "invisible api",
+            WarningKind::SkippedSequence => "skipped sequence",
+            WarningKind::Degraded => "degraded",
+            WarningKind::DeadStatements => "dead statements",
+        };
+        write!(f, "{}", kind)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct GenWarning {
+    pub(crate) kind: WarningKind,
+    pub(crate) span: Option<Span>,
+    pub(crate) message: String,
+}
+
+impl fmt::Display for GenWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "fuzz target generation: {}: {}", self.kind, self.message)
+    }
+}
+
+// Span不是Send，不能放进全局的Mutex里。生成都在rustdoc的线程上进行，用线程局部变量就够了
+thread_local! {
+    static WARNINGS: RefCell<Vec<GenWarning>> = RefCell::new(Vec::new());
+}
+
+/// 记录一条警告
+pub(crate) fn warn(kind: WarningKind, span: Option<Span>, message: String) {
+    WARNINGS.with(|warnings| warnings.borrow_mut().push(GenWarning { kind, span, message }));
+}
+
+/// 清空之前的记录，开始新的一次生成
+pub(crate) fn reset() {
+    WARNINGS.with(|warnings| warnings.borrow_mut().clear());
+}
+
+/// 取出目前记录的所有警告
+pub(crate) fn take_warnings() -> Vec<GenWarning> {
+    WARNINGS.with(|warnings| std::mem::take(&mut *warnings.borrow_mut()))
+}
+
+/// 通过rustc的诊断接口输出所有警告
+pub(crate) fn emit_warnings(sess: &Session) {
+    for warning in take_warnings() {
+        let message = warning.to_string();
+        match warning.span {
+            Some(span) => sess.struct_span_warn(span, message).emit(),
+            None => sess.struct_warn(message).emit(),
+        }
+    }
+}
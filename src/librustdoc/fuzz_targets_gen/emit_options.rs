@@ -0,0 +1,78 @@
+//! 生成测试文件时的选项，控制ApiSequence如何被翻译成fuzz target
+
+use crate::fuzz_targets_gen::async_runtime::AsyncRuntime;
+use crate::fuzz_targets_gen::backend::FuzzBackend;
+use crate::fuzz_targets_gen::corpus_seed::ArgumentValues;
+use crate::fuzz_targets_gen::encoding::EncodingVariant;
+use crate::fuzz_targets_gen::invariant;
+use crate::fuzz_targets_gen::panic_filter::PanicClass;
+use crate::fuzz_targets_gen::path_param::PathMode;
+use crate::fuzz_targets_gen::size_cap::SizeCaps;
+use crate::fuzz_targets_gen::unwrap_strategy::{NonePolicy, UnwrapStrategy};
+use rustc_data_structures::fx::FxHashMap;
+
+/// 生成fuzz target时的选项
+#[derive(Debug, Clone)]
+pub(crate) struct EmitOptions {
+    /// 中间的调用返回Err/None时不直接退出，而是跳过依赖它的语句，继续执行其他的语句
+    pub(crate) continue_on_error: bool,
+    /// 类型全路径 -> 断言表达式，产生该类型的语句后面会插入断言
+    pub(crate) invariants: FxHashMap<String, Vec<String>>,
+    /// 被测API全路径 -> 参考实现的函数路径，用来做差分测试
+    pub(crate) oracles: FxHashMap<String, String>,
+    /// 解码出来的长度和数值的上限
+    pub(crate) size_caps: SizeCaps,
+    /// 每个API调用的超时时间（毫秒），None表示不开启watchdog
+    pub(crate) call_timeout_ms: Option<u64>,
+    /// 额外为每个类型生成一个解释器模式的fuzz target，由fuzz输入决定调用顺序
+    pub(crate) interpreter_mode: bool,
+    /// 测试函数生成到待测crate内部的模块里，见internal_mode.rs
+    pub(crate) internal_mode: bool,
+    /// 每个序列用哪些编码方式生成fuzz target，对序列没有意义的编码方式会跳过，见encoding.rs
+    pub(crate) encoding_variants: Vec<EncodingVariant>,
+    /// 执行包含async fn的序列的运行时
+    pub(crate) async_runtime: AsyncRuntime,
+    /// 除了afl之外，还为哪些fuzz引擎生成target，见backend.rs
+    pub(crate) backends: Vec<FuzzBackend>,
+    /// 额外把函数、依赖和选出来的序列导出成json，见graph_export.rs
+    pub(crate) export_json: bool,
+    /// 返回Option/Result的调用怎么取出里面的值，见unwrap_strategy.rs
+    pub(crate) unwrap_strategy: UnwrapStrategy,
+    /// 返回Option的调用遇到None时怎么办
+    pub(crate) none_policy: NonePolicy,
+    /// 测试函数捕获panic，只有这些类别的panic当成crash，None表示不捕获，见panic_filter.rs
+    pub(crate) crash_panic_classes: Option<Vec<PanicClass>>,
+    /// 整个测试函数的超时时间（毫秒），None表示不限制
+    pub(crate) target_timeout_ms: Option<u64>,
+    /// 语料里记录的参数值，用来生成种子输入，见corpus_seed.rs
+    pub(crate) argument_values: ArgumentValues,
+    /// 怎么从fuzz输入构造Path参数，见path_param.rs
+    pub(crate) path_mode: PathMode,
+    /// f32和f64参数是否多用一个字节选择NaN、Inf这些特殊值
+    pub(crate) float_corners: bool,
+}
+
+impl EmitOptions {
+    pub(crate) fn new() -> Self {
+        EmitOptions {
+            continue_on_error: false,
+            invariants: invariant::default_invariants(),
+            oracles: FxHashMap::default(),
+            size_caps: SizeCaps::new(),
+            call_timeout_ms: None,
+            interpreter_mode: false,
+            internal_mode: false,
+            encoding_variants: vec![EncodingVariant::RawSplit],
+            async_runtime: AsyncRuntime::default(),
+            backends: Vec::new(),
+            export_json: false,
+            unwrap_strategy: UnwrapStrategy::default(),
+            none_policy: NonePolicy::default(),
+            crash_panic_classes: None,
+            target_timeout_ms: None,
+            argument_values: ArgumentValues::default(),
+            path_mode: PathMode::default(),
+            float_corners: false,
+        }
+    }
+}
@@ -0,0 +1,125 @@
+//! fuzz输入到参数的编码方式
+//! 同一个序列用不同的编码方式，fuzzer能到达的状态不一样，所以可以为一个序列生成多个fuzz target：
+//! 1. RawSplit：默认的方式，用_ByteReader按参数的顺序读取输入：固定长度的参数直接取字节，
+//!    动态长度的参数先读一个LEB128编码的长度前缀再取这么多字节（不够的话取到输入结尾），&str不合法直接退出
+//! 2. LossyStr：和RawSplit一样，但是&str不合法的时候取最长的合法前缀，不丢弃输入
+//! 3. Arbitrary：用arbitrary::Unstructured依次解码每个参数，生成的fuzz项目需要依赖arbitrary
+//! 用--fuzz-encoding选择编码方式，可以出现多次，默认只用RawSplit；生成的编码方式记录在fuzz_targets.txt里
+
+use crate::clean::{self};
+use crate::fuzz_targets_gen::api_sequence::ApiSequence;
+use crate::fuzz_targets_gen::fuzz_type::FuzzableType;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum EncodingVariant {
+    RawSplit,
+    LossyStr,
+    Arbitrary,
+}
+
+impl EncodingVariant {
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "raw" => Some(EncodingVariant::RawSplit),
+            "lossy" => Some(EncodingVariant::LossyStr),
+            "arbitrary" => Some(EncodingVariant::Arbitrary),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            EncodingVariant::RawSplit => "raw",
+            EncodingVariant::LossyStr => "lossy",
+            EncodingVariant::Arbitrary => "arbitrary",
+        }
+    }
+
+    /// 这个编码方式对序列是否有意义：
+    /// LossyStr只在有&str参数时和RawSplit不同；Arbitrary需要每个参数都能用arbitrary解码
+    pub(crate) fn applies_to(&self, sequence: &ApiSequence) -> bool {
+        match self {
+            EncodingVariant::RawSplit => true,
+            EncodingVariant::LossyStr => sequence.fuzzable_params.iter().any(contains_str),
+            EncodingVariant::Arbitrary => {
+                !sequence.fuzzable_params.is_empty()
+                    && sequence.fuzzable_params.iter().all(|fuzzable_param| {
+                        match fuzzable_param {
+                            //切片先解码成Vec再借用，见arbitrary_param_lines
+                            FuzzableType::RefSlice(inner) => {
+                                matches!(**inner, FuzzableType::Primitive(..))
+                            }
+                            _ => is_arbitrary(fuzzable_param),
+                        }
+                    })
+            }
+        }
+    }
+}
+
+fn contains_str(fuzzable_type: &FuzzableType) -> bool {
+    match fuzzable_type {
+        FuzzableType::RefStr => true,
+        FuzzableType::Tuple(inner_types) => inner_types.iter().any(|inner| contains_str(inner)),
+        //&[&str]的元素也用_to_str解码
+        FuzzableType::RefSlice(inner) => contains_str(inner),
+        _ => false,
+    }
+}
+
+/// 类型本身实现了Arbitrary，切片只有&[u8]实现了
+fn is_arbitrary(fuzzable_type: &FuzzableType) -> bool {
+    match fuzzable_type {
+        FuzzableType::NoFuzzable => false,
+        //arbitrary解码的浮点数没有特殊值的选择字节
+        FuzzableType::Primitive(..) | FuzzableType::CornerFloat(..) | FuzzableType::RefStr => true,
+        FuzzableType::RefSlice(inner) => {
+            matches!(**inner, FuzzableType::Primitive(clean::PrimitiveType::U8))
+        }
+        FuzzableType::Tuple(inner_types) => inner_types.iter().all(|inner| is_arbitrary(inner)),
+        FuzzableType::Array(inner, _) => is_arbitrary(inner),
+        FuzzableType::Collection(_, item_types) => item_types.iter().all(is_arbitrary),
+        //crate里的枚举没有实现Arbitrary
+        FuzzableType::Enum(..) => false,
+    }
+}
+
+/// Arbitrary编码时解码参数的语句，解码失败时直接返回
+pub(crate) fn arbitrary_param_lines(sequence: &ApiSequence, indent: &String) -> String {
+    let mut res = String::new();
+    res.push_str(
+        format!("{}let mut _unstructured = arbitrary::Unstructured::new(data);\n", indent).as_str(),
+    );
+    for (i, fuzzable_param) in sequence.fuzzable_params.iter().enumerate() {
+        let (decoded_type, borrow) = match fuzzable_param {
+            FuzzableType::RefSlice(inner) if !is_arbitrary(fuzzable_param) => {
+                (format!("Vec<{}>", inner._to_type_string()), true)
+            }
+            _ => (fuzzable_param._to_type_string(), false),
+        };
+        let decoded_name = if borrow { format!("_param{}_vec", i) } else { format!("_param{}", i) };
+        res.push_str(
+            format!(
+                "{}let {}: {} = match _unstructured.arbitrary() {{ Ok(x) => x, Err(_) => return }};\n",
+                indent, decoded_name, decoded_type
+            )
+            .as_str(),
+        );
+        if borrow {
+            res.push_str(format!("{}let _param{} = &{}[..];\n", indent, i, decoded_name).as_str());
+        }
+    }
+    res
+}
+
+/// LossyStr编码时代替afl_util::_data_to_str
+pub(crate) fn _data_to_lossy_str() -> &'static str {
+    "fn _to_str(data:&[u8], start_index: usize, end_index: usize)->&str {
+    let data_slice = &data[start_index..end_index];
+    use std::str;
+    match str::from_utf8(data_slice) {
+        Ok(s)=>s,
+        Err(e)=>str::from_utf8(&data_slice[..e.valid_up_to()]).unwrap(),
+    }
+}\n"
+}
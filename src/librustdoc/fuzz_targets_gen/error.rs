@@ -0,0 +1,38 @@
+//! 生成过程中的错误
+//! 之前遇到这些情况会直接panic，整个rustdoc调用都会中断。
+//! 现在错误沿着生成流程返回，能降级的地方（跳过一个阶段、忽略一个语料文件）打印警告之后继续
+
+use crate::fuzz_targets_gen::diagnostics::{self, WarningKind};
+use std::fmt;
+
+#[derive(Debug)]
+pub enum FuzzGenError {
+    /// 没有解析到任何mod，无法按照mod的可见性过滤api
+    NoModule,
+    /// 读取语料文件失败
+    Io { path: String, error: std::io::Error },
+    /// 语料文件中格式不正确的行，line从1开始
+    MalformedLine { path: String, line: usize, content: String },
+    /// 随机选择时候选集合为空，参数说明是哪一类候选
+    EmptyCandidates(&'static str),
+}
+
+impl fmt::Display for FuzzGenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FuzzGenError::NoModule => write!(f, "no module found in crate"),
+            FuzzGenError::Io { path, error } => write!(f, "failed to read {}: {}", path, error),
+            FuzzGenError::MalformedLine { path, line, content } => {
+                write!(f, "malformed line {} in {}: {}", line, path, content)
+            }
+            FuzzGenError::EmptyCandidates(what) => write!(f, "no {} to choose from", what),
+        }
+    }
+}
+
+impl std::error::Error for FuzzGenError {}
+
+/// 降级处理：记录错误以及接下来怎么做，和其他警告一起输出
+pub(crate) fn warn(error: &FuzzGenError, fallback: &str) {
+    diagnostics::warn(WarningKind::Degraded, None, format!("{}, {}", error, fallback));
+}
@@ -0,0 +1,484 @@
+//! --fuzz-explain：解释为什么没有序列能到达某个API
+//! 按照生成时的判断顺序逐项检查：
+//! 1. 函数是否在API图里（add_api_function和filter_functions会丢掉一部分函数）
+//! 2. 每个参数能否直接fuzz，不能的话有没有产生它的函数（find_all_dependencies），
+//!    没有的话列出被拒绝的候选函数和原因
+//! 3. 参数都有来源的时候，已有的序列为什么不能加入这个函数（is_fun_satisfied里的借用和move规则）
+
+use crate::clean::{self};
+use crate::fuzz_targets_gen::api_graph::{ApiGraph, ApiType};
+use crate::fuzz_targets_gen::api_sequence::ApiSequence;
+use crate::fuzz_targets_gen::api_util;
+use crate::fuzz_targets_gen::call_type::CallType;
+use crate::fuzz_targets_gen::file_util;
+use crate::fuzz_targets_gen::fuzz_type::{self, FuzzableType};
+use crate::fuzz_targets_gen::invariant;
+use crate::fuzz_targets_gen::prelude_type::{self, PreludeType};
+use itertools::Itertools;
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
+
+/// 每一项最多列出多少个候选函数或者序列
+static MAX_LISTED: usize = 10;
+
+/// 参数的来源
+enum ParamSource {
+    /// 可以直接由fuzz输入生成
+    Fuzzable(String),
+    /// 需要其他函数的返回值，这些函数的下标
+    Producers(Vec<usize>),
+    /// 没有函数能产生，用合成的`<T as Default>::default`得到
+    Default,
+    /// 无法得到这个参数，附带原因
+    Blocked(String),
+}
+
+/// 生成解释，api_path可以是函数的全路径，也可以是文档上的路径（trait方法用实现trait的类型）
+pub(crate) fn explain_api(
+    api_graph: &ApiGraph<'_>,
+    api_path: &str,
+    support_generic: bool,
+) -> String {
+    let mut res = format!("-----------EXPLAIN {}-----------\n", api_path);
+    let indexes = api_graph
+        .api_functions
+        .iter()
+        .enumerate()
+        .filter(|(index, api_function)| {
+            !api_graph._is_default_producer(*index)
+                && (api_function.full_name == api_path
+                    || file_util::doc_name_of(api_graph, api_function) == api_path)
+        })
+        .map(|(index, _)| index)
+        .collect_vec();
+    if indexes.is_empty() {
+        res.push_str(explain_missing(api_graph, api_path).as_str());
+        return res;
+    }
+    for index in indexes {
+        res.push_str(explain_function(api_graph, index, support_generic).as_str());
+    }
+    res
+}
+
+/// 函数不在API图里
+fn explain_missing(api_graph: &ApiGraph<'_>, api_path: &str) -> String {
+    if api_graph.functions_with_unsupported_fuzzable_types.contains(api_path) {
+        return "not in the API graph: a parameter has a type that can not be fuzzed \
+                (see the unsupported type warnings)\n"
+            .to_string();
+    }
+    let mut res = String::from(
+        "not in the API graph: it is not public, not reachable from outside the crate, \
+         defined on a prelude type, or not FFI-safe\n",
+    );
+    //名字写错的时候给出最后一段相同的函数
+    let last_segment = api_path.rsplit("::").next().unwrap_or(api_path);
+    let similar = api_graph
+        .api_functions
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| !api_graph._is_default_producer(*index))
+        .map(|(_, api_function)| file_util::doc_name_of(api_graph, api_function))
+        .filter(|name| name.rsplit("::").next() == Some(last_segment))
+        .take(MAX_LISTED)
+        .collect_vec();
+    if !similar.is_empty() {
+        res.push_str(format!("  did you mean: {}\n", similar.join(", ")).as_str());
+    }
+    res
+}
+
+fn explain_function(api_graph: &ApiGraph<'_>, index: usize, support_generic: bool) -> String {
+    let api_function = &api_graph.api_functions[index];
+    let mut res = format!("[{}] {}\n", index, api_function.full_name);
+    let reached_count = api_graph
+        .api_sequences
+        .iter()
+        .filter(|sequence| sequence.functions.iter().any(|api_call| api_call.func.1 == index))
+        .count();
+    if reached_count > 0 {
+        res.push_str(format!("  reached by {} generated sequences\n", reached_count).as_str());
+        return res;
+    }
+    if api_function._is_generic_function()
+        && !api_function._has_only_impl_trait_generics()
+        && !support_generic
+    {
+        res.push_str("  generic function, but generic support is disabled\n");
+    }
+
+    let mut blocked = false;
+    let mut unreached_producers = false;
+    for (param_index, input_type) in api_function.inputs.iter().enumerate() {
+        let type_name = api_util::_type_name(input_type, api_graph.cache, &api_graph.full_name_map);
+        match param_source(api_graph, index, param_index, support_generic) {
+            ParamSource::Fuzzable(fuzzable_type) => {
+                res.push_str(
+                    format!(
+                        "  param {} `{}`: generated from fuzz input as `{}`\n",
+                        param_index, type_name, fuzzable_type
+                    )
+                    .as_str(),
+                );
+            }
+            ParamSource::Producers(producers) => {
+                let reached = producers
+                    .iter()
+                    .filter(|producer| is_reached(api_graph, **producer))
+                    .map(|producer| api_graph.api_functions[*producer].full_name.clone())
+                    .collect_vec();
+                let unreached = producers
+                    .iter()
+                    .filter(|producer| !is_reached(api_graph, **producer))
+                    .map(|producer| api_graph.api_functions[*producer].full_name.clone())
+                    .collect_vec();
+                res.push_str(
+                    format!(
+                        "  param {} `{}`: {} producers, {} of them reached\n",
+                        param_index,
+                        type_name,
+                        producers.len(),
+                        reached.len()
+                    )
+                    .as_str(),
+                );
+                for name in reached.iter().take(MAX_LISTED) {
+                    res.push_str(format!("    reached producer: {}\n", name).as_str());
+                }
+                for name in unreached.iter().take(MAX_LISTED) {
+                    res.push_str(format!("    unreached producer: {}\n", name).as_str());
+                }
+                if reached.is_empty() {
+                    unreached_producers = true;
+                }
+            }
+            ParamSource::Default => {
+                res.push_str(
+                    format!(
+                        "  param {} `{}`: no producer in the crate, built with `Default::default()`\n",
+                        param_index, type_name
+                    )
+                    .as_str(),
+                );
+            }
+            ParamSource::Blocked(reason) => {
+                blocked = true;
+                res.push_str(
+                    format!("  param {} `{}`: {}\n", param_index, type_name, reason).as_str(),
+                );
+            }
+        }
+    }
+
+    if blocked {
+        res.push_str("  => a parameter can never be supplied\n");
+    } else if unreached_producers {
+        res.push_str(
+            "  => every producer of some parameter is itself unreached, explain those first\n",
+        );
+    } else {
+        res.push_str(explain_sequences(api_graph, index).as_str());
+    }
+    res
+}
+
+fn is_reached(api_graph: &ApiGraph<'_>, index: usize) -> bool {
+    api_graph
+        .api_sequences
+        .iter()
+        .any(|sequence| sequence.functions.iter().any(|api_call| api_call.func.1 == index))
+}
+
+/// 参数的来源，和is_fun_satisfied里的判断一致
+fn param_source(
+    api_graph: &ApiGraph<'_>,
+    index: usize,
+    param_index: usize,
+    support_generic: bool,
+) -> ParamSource {
+    let api_function = &api_graph.api_functions[index];
+    let input_type = substituted_input(api_graph, index, param_index);
+    if api_util::is_fuzzable_type(
+        &input_type,
+        api_graph.cache,
+        &api_graph.full_name_map,
+        &api_graph.emit_options,
+        Some(&api_function.generic_substitutions),
+    ) {
+        let (fuzzable_type, _) = fuzz_type::fuzzable_call_type(
+            &input_type,
+            api_graph.cache,
+            &api_graph.full_name_map,
+            &api_graph.emit_options,
+            Some(&api_function.generic_substitutions),
+        )
+        .generate_fuzzable_type_and_call_type();
+        return match fuzzable_type {
+            FuzzableType::NoFuzzable => ParamSource::Blocked(
+                "unsupported fuzzable: looks fuzzable but can not be decoded from bytes"
+                    .to_string(),
+            ),
+            _ => ParamSource::Fuzzable(fuzzable_type._to_type_string()),
+        };
+    }
+    let producers =
+        api_graph.producers_for_param(index, param_index).into_iter().map(|(producer, _)| producer);
+    let (default_producers, producers): (Vec<_>, Vec<_>) =
+        producers.partition(|producer| api_graph._is_default_producer(*producer));
+    if !producers.is_empty() {
+        return ParamSource::Producers(producers);
+    }
+    if !default_producers.is_empty() {
+        return ParamSource::Default;
+    }
+    ParamSource::Blocked(rejected_candidates(api_graph, index, &input_type, support_generic))
+}
+
+fn substituted_input(api_graph: &ApiGraph<'_>, index: usize, param_index: usize) -> clean::Type {
+    let api_function = &api_graph.api_functions[index];
+    let input_type = api_function.inputs[param_index].clone();
+    api_util::substitute_type(input_type.clone(), &api_function.generic_substitutions)
+        .unwrap_or(input_type)
+}
+
+/// 没有产生者的时候，看看返回值类型相近的函数在find_all_dependencies里为什么被拒绝
+fn rejected_candidates(
+    api_graph: &ApiGraph<'_>,
+    index: usize,
+    input_type: &clean::Type,
+    support_generic: bool,
+) -> String {
+    let cache = api_graph.cache;
+    let full_name_map = &api_graph.full_name_map;
+    let mut res = String::from("no producer");
+    if api_graph.api_functions[index]._is_start_function(cache, full_name_map, support_generic) {
+        res.push_str(
+            "; the function is treated as a start function, so no dependency edge leads into it",
+        );
+        return res;
+    }
+    let input_path = invariant::type_full_path(input_type, cache, full_name_map);
+    let mut rejected: FxHashMap<&'static str, Vec<String>> = FxHashMap::default();
+    for (candidate_index, candidate) in api_graph.api_functions.iter().enumerate() {
+        if candidate_index == index {
+            continue;
+        }
+        let Some(output_type) = &candidate.output else { continue };
+        let output_type = if prelude_type::_prelude_type_need_special_dealing(
+            output_type,
+            cache,
+            full_name_map,
+        ) {
+            PreludeType::from_type(output_type, cache, full_name_map)._get_final_type()
+        } else {
+            output_type.clone()
+        };
+        let compatible = !matches!(
+            api_util::_same_type(&output_type, input_type, true, cache, full_name_map),
+            CallType::_NotCompatible
+        );
+        let rule = if compatible {
+            if candidate._is_end_function(cache, full_name_map, support_generic) {
+                "end function (its return value is never used as an input)"
+            } else if !support_generic
+                && (api_util::_is_generic_type(&output_type)
+                    || api_util::_is_generic_type(input_type))
+            {
+                "generic type, and generic support is disabled"
+            } else {
+                continue;
+            }
+        } else if input_path.is_some()
+            && invariant::type_full_path(&output_type, cache, full_name_map) == input_path
+        {
+            //同一个类型但是无法转换，比如需要&mut却只能得到&
+            "NotCompatible (same type, but the reference kind can not be converted)"
+        } else {
+            continue;
+        };
+        rejected.entry(rule).or_default().push(candidate.full_name.clone());
+    }
+    if rejected.is_empty() {
+        res.push_str("; no public function returns this type");
+        return res;
+    }
+    for (rule, candidates) in rejected.into_iter().sorted() {
+        res.push_str(
+            format!(
+                "\n    rejected by rule `{}`: {}",
+                rule,
+                candidates.iter().take(MAX_LISTED).join(", ")
+            )
+            .as_str(),
+        );
+    }
+    res
+}
+
+/// 参数都有来源的时候，统计已有的序列不能加入这个函数的原因
+fn explain_sequences(api_graph: &ApiGraph<'_>, index: usize) -> String {
+    let mut reasons: FxHashMap<String, (usize, usize)> = FxHashMap::default();
+    let mut extendable = Vec::new();
+    for (sequence_index, sequence) in api_graph.api_sequences.iter().enumerate() {
+        if !contains_producer(api_graph, index, sequence) {
+            continue;
+        }
+        if api_graph.is_fun_satisfied(&ApiType::BareFunction, index, sequence).is_some() {
+            extendable.push(sequence_index);
+            continue;
+        }
+        let reason = rejection_reason(api_graph, index, sequence);
+        reasons.entry(reason).or_insert((0, sequence_index)).0 += 1;
+    }
+    let mut res = String::new();
+    if !extendable.is_empty() {
+        res.push_str(
+            format!(
+                "  => {} sequences can be extended with it, but the traversal stopped first \
+                 (sequence length or number limits, or the chosen algorithm)\n",
+                extendable.len()
+            )
+            .as_str(),
+        );
+        for sequence_index in extendable.iter().take(MAX_LISTED) {
+            res.push_str(
+                format!(
+                    "    {}\n",
+                    api_graph.api_sequences[*sequence_index].print_sequence(api_graph, false)
+                )
+                .as_str(),
+            );
+        }
+    }
+    if reasons.is_empty() && extendable.is_empty() {
+        res.push_str("  => no generated sequence contains a producer of its parameters\n");
+        return res;
+    }
+    for (reason, (count, example)) in reasons
+        .into_iter()
+        .sorted_by(|(x, (x_count, _)), (y, (y_count, _))| y_count.cmp(x_count).then(x.cmp(y)))
+    {
+        res.push_str(format!("  => rejected {} times: {}\n", count, reason).as_str());
+        res.push_str(
+            format!(
+                "    e.g. {}\n",
+                api_graph.api_sequences[example].print_sequence(api_graph, false)
+            )
+            .as_str(),
+        );
+    }
+    res
+}
+
+/// 序列里是否有函数能产生这个函数的某个参数
+fn contains_producer(api_graph: &ApiGraph<'_>, index: usize, sequence: &ApiSequence) -> bool {
+    let inputs_len = api_graph.api_functions[index].inputs.len();
+    sequence.functions.iter().any(|api_call| {
+        (0..inputs_len).any(|param_index| {
+            api_graph
+                .check_dependency(
+                    &api_call.func.0,
+                    api_call.func.1,
+                    &ApiType::BareFunction,
+                    index,
+                    param_index,
+                )
+                .is_some()
+        })
+    })
+}
+
+/// 按照is_fun_satisfied的顺序找出第一个不满足的规则
+fn rejection_reason(api_graph: &ApiGraph<'_>, index: usize, sequence: &ApiSequence) -> String {
+    let api_function = &api_graph.api_functions[index];
+    if sequence
+        .functions
+        .iter()
+        .any(|api_call| api_graph.api_functions[api_call.func.1]._is_diverging())
+    {
+        return "the sequence already contains a call that never returns".to_string();
+    }
+    if api_function._is_global_state_function(&api_graph.global_states)
+        && sequence._global_state_call_count(api_graph) > 0
+    {
+        return "the sequence already calls a function that modifies global state".to_string();
+    }
+    let mut moved = sequence._moved.clone();
+    let mut mut_borrowed = FxHashSet::default();
+    let mut borrowed = FxHashSet::default();
+    for param_index in 0..api_function.inputs.len() {
+        let input_type = substituted_input(api_graph, index, param_index);
+        if api_util::is_fuzzable_type(
+            &input_type,
+            api_graph.cache,
+            &api_graph.full_name_map,
+            &api_graph.emit_options,
+            Some(&api_function.generic_substitutions),
+        ) {
+            continue;
+        }
+        let mut conflicts = Vec::new();
+        let mut found = false;
+        for position in 0..sequence.functions.len() {
+            let (api_type, output_index) = &sequence.functions[position].func;
+            let Some(dependency_index) = api_graph.check_dependency(
+                api_type,
+                *output_index,
+                &ApiType::BareFunction,
+                index,
+                param_index,
+            ) else {
+                continue;
+            };
+            if moved.contains(&position) {
+                conflicts.push(format!("the value of call #{} was already moved", position));
+                continue;
+            }
+            let call_type = &api_graph.api_dependencies[dependency_index].call_type;
+            if api_util::_move_condition(&input_type, call_type) {
+                if mut_borrowed.contains(&position) || borrowed.contains(&position) {
+                    conflicts.push(format!(
+                        "borrow conflict: call #{} is borrowed by an earlier parameter, \
+                         can not move it",
+                        position
+                    ));
+                    continue;
+                }
+                moved.insert(position);
+            } else if api_util::_is_mutable_borrow_occurs(&input_type, call_type) {
+                if mut_borrowed.contains(&position) || borrowed.contains(&position) {
+                    conflicts.push(format!(
+                        "borrow conflict: call #{} is borrowed by an earlier parameter, \
+                         can not borrow it mutably",
+                        position
+                    ));
+                    continue;
+                }
+                mut_borrowed.insert(position);
+            } else if api_util::_is_immutable_borrow_occurs(&input_type, call_type) {
+                if mut_borrowed.contains(&position) {
+                    conflicts.push(format!(
+                        "borrow conflict: call #{} is mutably borrowed by an earlier parameter",
+                        position
+                    ));
+                    continue;
+                }
+                borrowed.insert(position);
+            }
+            found = true;
+            break;
+        }
+        if !found {
+            let type_name =
+                api_util::_type_name(&input_type, api_graph.cache, &api_graph.full_name_map);
+            if conflicts.is_empty() {
+                return format!(
+                    "param {}: no value of type `{}` in the sequence",
+                    param_index, type_name
+                );
+            }
+            return format!("param {}: {}", param_index, conflicts.join("; "));
+        }
+    }
+    "the sequence would have a fuzzable parameter nested too deeply to decode".to_string()
+}
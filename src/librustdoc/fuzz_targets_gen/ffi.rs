@@ -0,0 +1,108 @@
+//! extern "C"等非Rust ABI的函数
+//! 1. C_TYPE_ALIASES：c_int、c_char这类类型别名，当成对应的基本类型来fuzz，传参时用`as _`转换
+//! 2. is_ffi_safe_type：参数和返回值只能是能跨过C ABI边界的类型，否则不测试这个函数
+//! 指向c_char的裸指针会额外加上结尾的0，当成C字符串传进去
+
+use crate::clean::{self, PrimitiveType};
+use crate::formats::cache::Cache;
+use crate::fuzz_targets_gen::impl_util::FullNameMap;
+use crate::fuzz_targets_gen::prelude_type::{self, PreludeType};
+use rustc_data_structures::fx::FxHashMap;
+
+// C类型别名，左边是别名的名字，右边是fuzz时用的基本类型
+lazy_static! {
+    static ref C_TYPE_ALIASES: FxHashMap<&'static str, PrimitiveType> = {
+        let mut m = FxHashMap::default();
+        m.insert("c_char", PrimitiveType::I8);
+        m.insert("c_schar", PrimitiveType::I8);
+        m.insert("c_uchar", PrimitiveType::U8);
+        m.insert("c_short", PrimitiveType::I16);
+        m.insert("c_ushort", PrimitiveType::U16);
+        m.insert("c_int", PrimitiveType::I32);
+        m.insert("c_uint", PrimitiveType::U32);
+        m.insert("c_long", PrimitiveType::I64);
+        m.insert("c_ulong", PrimitiveType::U64);
+        m.insert("c_longlong", PrimitiveType::I64);
+        m.insert("c_ulonglong", PrimitiveType::U64);
+        m.insert("c_float", PrimitiveType::F32);
+        m.insert("c_double", PrimitiveType::F64);
+        m.insert("size_t", PrimitiveType::Usize);
+        m.insert("ssize_t", PrimitiveType::Isize);
+        m
+    };
+}
+
+/// 类型路径的最后一段，带泛型参数的路径返回None
+fn last_segment_name(type_: &clean::Type) -> Option<String> {
+    match type_ {
+        clean::Type::Path { path } => {
+            let last_segment = path.segments.last()?;
+            if let clean::GenericArgs::AngleBracketed { args, .. } = &last_segment.args {
+                if args.len() > 0 {
+                    return None;
+                }
+            }
+            Some(last_segment.name.to_string())
+        }
+        _ => None,
+    }
+}
+
+/// C类型别名对应的基本类型
+pub(crate) fn c_type_alias_primitive(type_: &clean::Type) -> Option<PrimitiveType> {
+    let name = last_segment_name(type_)?;
+    C_TYPE_ALIASES.get(name.as_str()).cloned()
+}
+
+/// 指针指向的是不是c_char，是的话需要当成C字符串，在结尾加上0
+/// c_schar、c_uchar和u8一样是字节，指向它们的指针按普通的缓冲区传，长度就是输入的长度
+pub(crate) fn is_c_char_alias(type_: &clean::Type) -> bool {
+    last_segment_name(type_).map_or(false, |name| name == "c_char")
+}
+
+/// 类型能否跨过C ABI边界
+/// 当前crate里定义的结构体没法知道是不是repr(C)，统一当成可以（一般是不透明的指针）
+pub(crate) fn is_ffi_safe_type(
+    type_: &clean::Type,
+    cache: &Cache,
+    full_name_map: &FullNameMap,
+) -> bool {
+    match type_ {
+        clean::Type::Primitive(primitive_type) => match primitive_type {
+            PrimitiveType::Str | PrimitiveType::Slice | PrimitiveType::Char => false,
+            PrimitiveType::Tuple | PrimitiveType::Array | PrimitiveType::Never => false,
+            _ => true,
+        },
+        clean::Type::RawPointer(_, inner_type) => match last_segment_name(inner_type) {
+            Some(name) if name == "c_void" => true,
+            _ => is_ffi_safe_type(inner_type, cache, full_name_map),
+        },
+        clean::Type::BorrowedRef { type_: inner_type, .. } => match &**inner_type {
+            clean::Type::Slice(..) | clean::Type::Primitive(PrimitiveType::Str) => false,
+            inner_type => is_ffi_safe_type(inner_type, cache, full_name_map),
+        },
+        clean::Type::Path { .. } => {
+            if c_type_alias_primitive(type_).is_some() {
+                return true;
+            }
+            if prelude_type::_prelude_type_need_special_dealing(type_, cache, full_name_map) {
+                //只有Option<&T>和Option<fn>能保证和指针一样的布局
+                return match PreludeType::from_type(type_, cache, full_name_map) {
+                    PreludeType::PreludeOption(inner_type) => matches!(
+                        inner_type,
+                        clean::Type::BorrowedRef { .. } | clean::Type::BareFunction(..)
+                    ),
+                    _ => false,
+                };
+            }
+            //当前crate里定义的类型，String之类的标准库类型不行
+            match type_.def_id(cache) {
+                Some(def_id) => cache.paths.contains_key(&def_id),
+                None => false,
+            }
+        }
+        clean::Type::BareFunction(..) => true,
+        clean::Type::Tuple(inner_types) => inner_types.is_empty(),
+        _ => false,
+    }
+}
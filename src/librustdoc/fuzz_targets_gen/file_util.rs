@@ -1,15 +1,73 @@
-use crate::fuzz_targets_gen::api_graph::ApiGraph;
+use crate::fuzz_targets_gen::afl_dict;
+use crate::fuzz_targets_gen::api_function::ApiFunction;
 use crate::fuzz_targets_gen::api_graph::GraphTraverseAlgorithm::*;
+use crate::fuzz_targets_gen::api_graph::{ApiGraph, SequenceSelection};
+use crate::fuzz_targets_gen::api_sequence::ApiSequence;
+use crate::fuzz_targets_gen::backend::{self, FuzzBackend};
+use crate::fuzz_targets_gen::cargo_project::{self, ProjectTarget};
+use crate::fuzz_targets_gen::diagnostics::{self, WarningKind};
+use crate::fuzz_targets_gen::encoding::EncodingVariant;
+use crate::fuzz_targets_gen::error::FuzzGenError;
+use crate::fuzz_targets_gen::{
+    corpus_seed, graph_export, internal_mode, interpreter, invariant, timing,
+};
 use itertools::Itertools;
 use rustc_data_structures::fx::FxHashMap;
 use std::fs;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use super::api_graph::GraphTraverseAlgorithm;
 
+/// 实验目录，可以用环境变量FRIES_EXPERIMENT_ROOT覆盖（cargo fries会设置）
+pub(crate) fn experiment_root() -> String {
+    std::env::var("FRIES_EXPERIMENT_ROOT")
+        .unwrap_or_else(|_| "/home/yxz/workspace/fuzz/experiment_root".to_string())
+}
+
+/// 语料的位置：命令行的--corpus-path，其次是环境变量FRIES_CORPUS_PATH，都没有的话用default
+/// 可以是一个文件，也可以是一个目录
+pub(crate) fn corpus_path(cli_path: Option<&str>, default: String) -> PathBuf {
+    match cli_path {
+        Some(path) => PathBuf::from(path),
+        None => PathBuf::from(std::env::var("FRIES_CORPUS_PATH").unwrap_or(default)),
+    }
+}
+
+/// 序列语料文件：corpus_path是目录的话是目录里所有的.ans文件，按文件名排序，否则就是它自己
+pub(crate) fn corpus_sequence_files(corpus_path: &PathBuf) -> Result<Vec<PathBuf>, FuzzGenError> {
+    if !corpus_path.is_dir() {
+        return Ok(vec![corpus_path.clone()]);
+    }
+    let io_error = |error| FuzzGenError::Io { path: corpus_path.display().to_string(), error };
+    let mut files = Vec::new();
+    for entry in fs::read_dir(corpus_path).map_err(io_error)? {
+        let path = entry.map_err(io_error)?.path();
+        if path.is_file() && path.extension().map_or(false, |extension| extension == "ans") {
+            files.push(path);
+        }
+    }
+    if files.is_empty() {
+        return Err(io_error(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "no .ans corpus file in the directory",
+        )));
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// depinfo.txt等语料统计信息所在的目录：corpus_path是文件的话用它所在的目录
+pub(crate) fn corpus_info_dir(corpus_path: &PathBuf) -> PathBuf {
+    if corpus_path.is_file() {
+        corpus_path.parent().map(|parent| parent.to_path_buf()).unwrap_or_default()
+    } else {
+        corpus_path.clone()
+    }
+}
+
 fn generate_fuzz_file_path(lib_name: &str, test_dir_path: &str) -> String {
-    format!("/home/yxz/workspace/fuzz/experiment_root/{}/fuzz_file_dir/{}", lib_name, test_dir_path)
+    format!("{}/{}/fuzz_file_dir/{}", experiment_root(), lib_name, test_dir_path)
 }
 
 lazy_static! {
@@ -92,6 +150,22 @@ pub(crate) fn get_randwalk_crate_test_dir(lib_name: &str) -> String {
     generate_fuzz_file_path(lib_name, "random_afl_work")
 }
 
+pub(crate) fn get_markov_crate_test_dir(lib_name: &str) -> String {
+    generate_fuzz_file_path(lib_name, "markov_afl_work")
+}
+
+pub(crate) fn get_genetic_crate_test_dir(lib_name: &str) -> String {
+    generate_fuzz_file_path(lib_name, "genetic_afl_work")
+}
+
+pub(crate) fn get_mcts_crate_test_dir(lib_name: &str) -> String {
+    generate_fuzz_file_path(lib_name, "mcts_afl_work")
+}
+
+pub(crate) fn get_beam_crate_test_dir(lib_name: &str) -> String {
+    generate_fuzz_file_path(lib_name, "beam_afl_work")
+}
+
 lazy_static! {
     static ref RANDOM_TEST_DIR: FxHashMap<&'static str, String> = {
         let mut m = FxHashMap::default();
@@ -129,12 +203,26 @@ pub(crate) fn get_randwalk_crate_test_dir(lib_name: &str) -> String {
 static _AFL_DIR: &'static str = "afl_files";
 static _REPRODUCE_FILE_DIR: &'static str = "replay_files";
 static _LIBFUZZER_DIR: &'static str = "libfuzzer_files";
+//语料参数值生成的种子，每个target一个子目录
+static _SEED_DIR: &'static str = "seeds";
 static MAX_TEST_FILE_NUMBER: usize = 300;
+//生成的文件名前缀，cargo fries用它找到fuzz target对应的replay程序
+static TEST_FILE_PREFIX: &'static str = "fuzz";
+static REPLAY_FILE_PREFIX: &'static str = "replay";
+static INTERPRETER_FILE_PREFIX: &'static str = "interpreter";
+static LIBFUZZER_FILE_PREFIX: &'static str = "libfuzzer";
+//每个函数被哪些fuzz target覆盖，html文档会读取这个文件
+static FUZZ_TARGET_INDEX_FILE: &'static str = "fuzz_targets.txt";
+//每个阶段的耗时，见timing.rs
+static PHASE_TIMING_FILE: &'static str = "phase_timing.txt";
+//函数、依赖和选出来的序列，见graph_export.rs
+static GRAPH_EXPORT_FILE: &'static str = "api_graph.json";
 //static DEFAULT_RANDOM_FILE_NUMBER: usize = 100;
 
 pub(crate) fn can_write_to_file(crate_name: &String, strategy: GraphTraverseAlgorithm) -> bool {
     match strategy {
         _Default => DEFAULT_CRATE_TEST_DIR.contains_key(crate_name.as_str()),
+        _MarkovWalk | _Genetic | _Mcts | _BeamSearch => true,
         _RandomWalk | _Fudge | _UseRealWorld => true, //REAL_WORLD_CRATE_TEST_DIR.contains_key(crate_name.as_str()),
         _ => false,                                   /*
                                                        _Bfs => todo!(),
@@ -171,7 +259,26 @@ pub(crate) struct FileHelper {
     pub(crate) test_dir: String,
     pub(crate) test_files: Vec<String>,
     pub(crate) reproduce_files: Vec<String>,
-    //pub(crate) libfuzzer_files: Vec<String>,
+    pub(crate) interpreter_files: Vec<String>,
+    /// test_files和reproduce_files对应的文件名（不带前缀），包含按模块划分的子目录，
+    /// 比如`regex/bytes/regex_bytes_regex_find_01`
+    pub(crate) target_names: Vec<String>,
+    /// interpreter_files对应的文件名，格式同上
+    pub(crate) interpreter_names: Vec<String>,
+    /// (函数在文档中的全路径, 覆盖它的afl测试文件的相对路径, 编码方式)
+    pub(crate) target_index: Vec<(String, String, EncodingVariant)>,
+    /// 其他fuzz引擎的target，和test_files一一对应，见backend.rs
+    pub(crate) backend_files: Vec<(FuzzBackend, Vec<String>)>,
+    /// libfuzzer的target，和test_files一一对应，测试目录的工程里需要打开libfuzzer feature才会编译
+    pub(crate) libfuzzer_files: Vec<String>,
+    /// 打开export_json时导出的json
+    pub(crate) graph_json: Option<String>,
+    /// afl的字典，crate里没有收集到字面量的时候是None
+    pub(crate) dictionary: Option<String>,
+    /// 和target_names一一对应的种子输入，没有记录参数值或者编码方式不是按字节切分的时候为空
+    pub(crate) seed_files: Vec<Vec<Vec<u8>>>,
+    /// --fuzz-internal时放进待测crate的模块，test_files等只调用里面的函数，见internal_mode.rs
+    pub(crate) internal_module: Option<String>,
 }
 
 impl FileHelper {
@@ -181,6 +288,20 @@ pub(crate) fn new(
         strategy: GraphTraverseAlgorithm,
         max_size: usize,
         max_len: usize,
+    ) -> Self {
+        let chosen_sequences = timing::time_phase("selection", || {
+            choose_sequences(api_graph, strategy, max_size, max_len)
+        });
+        timing::time_phase("emission", || {
+            FileHelper::from_sequences(api_graph, strategy, &chosen_sequences)
+        })
+    }
+
+    /// 把选出来的序列翻译成测试文件
+    pub(crate) fn from_sequences(
+        api_graph: &ApiGraph<'_>,
+        strategy: GraphTraverseAlgorithm,
+        chosen_sequences: &Vec<ApiSequence>,
     ) -> Self {
         let crate_name = api_graph._crate_name.clone().replace("_", "-");
 
@@ -194,6 +315,10 @@ pub(crate) fn new(
             //_Bfs => get_bfs_crate_test_dir(crate_name.as_str()),
             _RandomWalk => get_randwalk_crate_test_dir(crate_name.as_str()),
             _Fudge => get_fudge_crate_test_dir(crate_name.as_str()),
+            _MarkovWalk => get_markov_crate_test_dir(crate_name.as_str()),
+            _Genetic => get_genetic_crate_test_dir(crate_name.as_str()),
+            _Mcts => get_mcts_crate_test_dir(crate_name.as_str()),
+            _BeamSearch => get_beam_crate_test_dir(crate_name.as_str()),
             _ => "".to_string(),
         };
 
@@ -202,48 +327,149 @@ pub(crate) fn new(
         let mut test_files = Vec::new();
         let mut reproduce_files = Vec::new();
         let mut libfuzzer_files = Vec::new();
-        //let chosen_sequences = api_graph._naive_choose_sequence(MAX_TEST_FILE_NUMBER);
-        let _chosen_sequences = if strategy == _Fudge {
-            //api_graph.api_sequences.clone()
-            println!("sequences {}", api_graph.api_sequences.len());
-            //api_graph._heuristic_choose(10, true)
-            api_graph._first_choose(max_size, max_len)
-        } else if strategy == _UseRealWorld {
-            //api_graph.api_sequences.clone()
-            //api_graph._heuristic_choose(max_len, true)
-            api_graph._first_choose(max_size, max_len)
-        } else if strategy == _Bfs {
-            println!("Heuristic_choose");
-            api_graph._heuristic_choose(max_size, true)
-        } else {
-            api_graph._first_choose(max_size, max_len)
-        };
-
-        let mut sequence_map = FxHashMap::default();
-        for seq in _chosen_sequences {
-            let seq_str = seq.print_sequence(api_graph, true);
-            //println!("{}", seq_str);
-            sequence_map.insert(seq_str, seq);
+        let mut target_names = Vec::new();
+        let mut target_index = Vec::new();
+        let mut seed_files = Vec::new();
+        let mut name_counter = FxHashMap::default();
+        let internal = api_graph.emit_options.internal_mode;
+        let mut internal_module = String::new();
+        //其他引擎和解释器模式的target直接调用API，在crate外面访问不到内部的API
+        if internal
+            && (!api_graph.emit_options.backends.is_empty()
+                || api_graph.emit_options.interpreter_mode)
+        {
+            diagnostics::warn(
+                WarningKind::Degraded,
+                None,
+                "internal mode skips interpreter targets and other backends".to_string(),
+            );
         }
+        let backends = if internal { &[][..] } else { &api_graph.emit_options.backends[..] };
+        let mut backend_files = backends.iter().map(|backend| (*backend, Vec::new())).collect_vec();
 
-        println!("去重之后的序列集合包含 {} 个序列", sequence_map.len());
-        let mut chosen_sequences = sequence_map.iter().collect_vec();
-        chosen_sequences.sort_by(|(x, _), (y, _)| x.cmp(y));
-        let chosen_sequences = chosen_sequences.iter().map(|(_s, seq)| seq.clone()).collect_vec();
-
-        for sequence in &chosen_sequences {
+        for (sequence_index, sequence) in chosen_sequences.iter().enumerate() {
             if sequence_count >= MAX_TEST_FILE_NUMBER {
+                diagnostics::warn(
+                    WarningKind::SkippedSequence,
+                    None,
+                    format!(
+                        "{} selected sequences exceed the limit of {} test files, skipped",
+                        chosen_sequences.len() - sequence_index,
+                        MAX_TEST_FILE_NUMBER
+                    ),
+                );
                 break;
             }
-            let test_file = sequence._to_afl_test_file(api_graph, sequence_count);
-            test_files.push(test_file);
-            let reproduce_file = sequence._to_replay_crash_file(api_graph, sequence_count);
-            reproduce_files.push(reproduce_file);
-            let libfuzzer_file = sequence._to_libfuzzer_test_file(api_graph, sequence_count);
-            libfuzzer_files.push(libfuzzer_file);
-            sequence_count = sequence_count + 1;
+            let mut covered_functions = sequence
+                .functions
+                .iter()
+                .map(|api_call| doc_name_of(api_graph, &api_graph.api_functions[api_call.func.1]))
+                .collect_vec();
+            covered_functions.sort();
+            covered_functions.dedup();
+            //同一个序列的每种编码方式各生成一个fuzz target
+            for encoding in &api_graph.emit_options.encoding_variants {
+                if !encoding.applies_to(sequence) {
+                    continue;
+                }
+                let target_name =
+                    sequence_target_name(api_graph, sequence, *encoding, &mut name_counter);
+                if internal {
+                    let crate_name = api_graph._crate_name.as_str();
+                    let module_name = target_name.rsplit('/').next().unwrap();
+                    internal_module.push_str(
+                        internal_mode::target_module(
+                            sequence,
+                            api_graph,
+                            sequence_count,
+                            *encoding,
+                            module_name,
+                        )
+                        .as_str(),
+                    );
+                    test_files.push(internal_mode::afl_test_file(crate_name, module_name));
+                    reproduce_files.push(internal_mode::replay_file(crate_name, module_name));
+                    libfuzzer_files
+                        .push(internal_mode::libfuzzer_test_file(crate_name, module_name));
+                } else {
+                    let test_file = sequence._to_afl_test_file_with_encoding(
+                        api_graph,
+                        sequence_count,
+                        *encoding,
+                    );
+                    test_files.push(test_file);
+                    let reproduce_file = sequence._to_replay_crash_file_with_encoding(
+                        api_graph,
+                        sequence_count,
+                        *encoding,
+                    );
+                    reproduce_files.push(reproduce_file);
+                    let libfuzzer_file = sequence._to_libfuzzer_test_file_with_encoding(
+                        api_graph,
+                        sequence_count,
+                        *encoding,
+                    );
+                    libfuzzer_files.push(libfuzzer_file);
+                }
+                for (backend, files) in &mut backend_files {
+                    files.push(backend.test_file(sequence, api_graph, sequence_count, *encoding));
+                }
+                let test_file_name = target_file_name(TEST_FILE_PREFIX, &target_name);
+                for function_name in &covered_functions {
+                    target_index.push((function_name.clone(), test_file_name.clone(), *encoding));
+                }
+                target_names.push(target_name);
+                //Arbitrary的输入布局和参数值对不上，不生成种子
+                seed_files.push(if *encoding == EncodingVariant::Arbitrary {
+                    Vec::new()
+                } else {
+                    corpus_seed::sequence_seeds(sequence, api_graph)
+                });
+                sequence_count = sequence_count + 1;
+            }
+        }
+
+        //解释器模式：每个类型一个fuzz target
+        let mut interpreter_files = Vec::new();
+        let mut interpreter_names = Vec::new();
+        if api_graph.emit_options.interpreter_mode && !internal {
+            let interpreters = interpreter::find_interpreters(api_graph);
+            println!("生成 {} 个解释器模式的fuzz target", interpreters.len());
+            for info in &interpreters {
+                interpreter_files.push(interpreter::interpreter_test_file(api_graph, info));
+                interpreter_names.push(unique_target_name(
+                    api_graph,
+                    &info.type_name,
+                    &mut name_counter,
+                ));
+            }
+        }
+        let graph_json = if api_graph.emit_options.export_json {
+            Some(graph_export::graph_to_json(api_graph, chosen_sequences))
+        } else {
+            None
+        };
+        let dictionary = if api_graph.dictionary.is_empty() {
+            None
+        } else {
+            Some(afl_dict::render_dictionary(&api_graph.dictionary))
+        };
+        FileHelper {
+            crate_name,
+            test_dir,
+            test_files,
+            reproduce_files,
+            interpreter_files,
+            target_names,
+            interpreter_names,
+            target_index,
+            backend_files,
+            libfuzzer_files,
+            graph_json,
+            dictionary,
+            seed_files,
+            internal_module: if internal { Some(internal_module) } else { None },
         }
-        FileHelper { crate_name, test_dir, test_files, reproduce_files }
     }
 
     pub(crate) fn write_files(&self) {
@@ -255,10 +481,139 @@ pub(crate) fn write_files(&self) {
         ensure_empty_dir(&test_file_path);
         let reproduce_file_path = test_path.clone().join(_REPRODUCE_FILE_DIR);
         ensure_empty_dir(&reproduce_file_path);
+        let libfuzzer_file_path = test_path.clone().join(_LIBFUZZER_DIR);
+        ensure_empty_dir(&libfuzzer_file_path);
 
-        write_to_files(&self.crate_name, &test_file_path, &self.test_files, "test");
+        write_to_files(&test_file_path, &self.target_names, &self.test_files, TEST_FILE_PREFIX);
         //暂时用test file代替一下，后续改成真正的reproduce file
-        write_to_files(&self.crate_name, &reproduce_file_path, &self.reproduce_files, "replay");
+        write_to_files(
+            &reproduce_file_path,
+            &self.target_names,
+            &self.reproduce_files,
+            REPLAY_FILE_PREFIX,
+        );
+        write_to_files(
+            &test_file_path,
+            &self.interpreter_names,
+            &self.interpreter_files,
+            INTERPRETER_FILE_PREFIX,
+        );
+        write_to_files(
+            &libfuzzer_file_path,
+            &self.target_names,
+            &self.libfuzzer_files,
+            LIBFUZZER_FILE_PREFIX,
+        );
+        self.write_afl_project(&test_path);
+        for (backend, files) in &self.backend_files {
+            self.write_backend_project(&test_path, *backend, files);
+        }
+        self.write_target_index(&test_path);
+        self.write_phase_timing(&test_path);
+        if let Some(graph_json) = &self.graph_json {
+            fs::write(test_path.join(GRAPH_EXPORT_FILE), graph_json).unwrap();
+        }
+        if let Some(dictionary) = &self.dictionary {
+            fs::write(test_path.join(afl_dict::dictionary_file_name(&self.crate_name)), dictionary)
+                .unwrap();
+        }
+        self.write_seed_files(&test_path);
+        if let Some(internal_module) = &self.internal_module {
+            self.write_internal_module(&test_path, internal_module);
+        }
+    }
+
+    /// 写出放进待测crate的模块，并提示需要在lib.rs里加上的声明
+    fn write_internal_module(&self, test_path: &PathBuf, internal_module: &String) {
+        let module_path = test_path.join(internal_mode::INTERNAL_MODULE_FILE);
+        fs::write(&module_path, internal_module).unwrap();
+        let module_file = fs::canonicalize(&module_path).unwrap_or(module_path);
+        println!(
+            "internal mode: add the following declaration to the lib.rs of {}\n{}",
+            self.crate_name,
+            internal_mode::include_declaration(module_file.display().to_string().as_str())
+        );
+    }
+
+    /// 种子写到seeds/<target名>/seed_<k>，可以直接作为afl-fuzz -i的输入目录
+    fn write_seed_files(&self, test_path: &PathBuf) {
+        let seed_path = test_path.join(_SEED_DIR);
+        ensure_empty_dir(&seed_path);
+        for (target_name, seeds) in self.target_names.iter().zip(&self.seed_files) {
+            if seeds.is_empty() {
+                continue;
+            }
+            let target_seed_path = seed_path.join(target_name);
+            fs::create_dir_all(&target_seed_path).unwrap();
+            for (index, seed) in seeds.iter().enumerate() {
+                fs::write(target_seed_path.join(format!("seed_{}", index)), seed).unwrap();
+            }
+        }
+    }
+
+    /// 其他fuzz引擎的target写到各自的cargo工程里，每个target一个bin或者test
+    fn write_backend_project(
+        &self,
+        test_path: &PathBuf,
+        backend: FuzzBackend,
+        files: &Vec<String>,
+    ) {
+        let project_path = test_path.join(backend.project_dir());
+        ensure_empty_dir(&project_path);
+        let bin_path = project_path.join(backend.target_dir());
+        write_to_files(&bin_path, &self.target_names, files, TEST_FILE_PREFIX);
+        let bins =
+            project_targets(backend.target_dir(), TEST_FILE_PREFIX, &self.target_names, files);
+        let manifest = backend::project_manifest(backend, &self.crate_name, &bins);
+        fs::write(project_path.join(cargo_project::PROJECT_MANIFEST_FILE), manifest).unwrap();
+    }
+
+    /// 测试目录本身作为一个cargo工程，afl_files、replay_files和libfuzzer_files下的文件都是bin，
+    /// 见cargo_project.rs
+    fn write_afl_project(&self, test_path: &PathBuf) {
+        let mut afl_targets =
+            project_targets(_AFL_DIR, TEST_FILE_PREFIX, &self.target_names, &self.test_files);
+        afl_targets.extend(project_targets(
+            _REPRODUCE_FILE_DIR,
+            REPLAY_FILE_PREFIX,
+            &self.target_names,
+            &self.reproduce_files,
+        ));
+        afl_targets.extend(project_targets(
+            _AFL_DIR,
+            INTERPRETER_FILE_PREFIX,
+            &self.interpreter_names,
+            &self.interpreter_files,
+        ));
+        let libfuzzer_targets = project_targets(
+            _LIBFUZZER_DIR,
+            LIBFUZZER_FILE_PREFIX,
+            &self.target_names,
+            &self.libfuzzer_files,
+        );
+        let manifest =
+            cargo_project::afl_project_manifest(&self.crate_name, &afl_targets, &libfuzzer_targets);
+        fs::write(test_path.join(cargo_project::PROJECT_MANIFEST_FILE), manifest).unwrap();
+        fs::write(test_path.join(cargo_project::BUILD_SCRIPT_FILE), cargo_project::build_script())
+            .unwrap();
+    }
+
+    /// 写出每个阶段的耗时，见timing.rs
+    fn write_phase_timing(&self, test_path: &PathBuf) {
+        let mut file = fs::File::create(test_path.join(PHASE_TIMING_FILE)).unwrap();
+        file.write_all(timing::timing_report().as_bytes()).unwrap();
+    }
+
+    /// 写出每个函数被哪些测试文件覆盖，格式和其他配置文件一样是`函数 | 文件名 | 编码方式`
+    fn write_target_index(&self, test_path: &PathBuf) {
+        let mut content = String::from("# function | fuzz target | encoding\n");
+        for (function_name, file_name, encoding) in &self.target_index {
+            content.push_str(
+                format!("{} | {} | {}\n", function_name, file_name, encoding.name()).as_str(),
+            );
+        }
+        let mut file = fs::File::create(test_path.join(FUZZ_TARGET_INDEX_FILE)).unwrap();
+        file.write_all(content.as_bytes()).unwrap();
     }
     /*
     pub(crate) fn write_libfuzzer_files(&self) {
@@ -278,14 +633,213 @@ pub(crate) fn write_libfuzzer_files(&self) {
     }*/
 }
 
-// 每个contents[i]的内容，写入文件【prefix_cratenamei.rs】
-fn write_to_files(crate_name: &String, path: &PathBuf, contents: &Vec<String>, prefix: &str) {
-    let file_number = contents.len();
-    for i in 0..file_number {
-        let filename = format!("{}_{}{:0>5}.rs", prefix, crate_name, i);
-        let full_filename = path.join(filename);
+/// 写到target_dir下的文件对应的工程target，bin的名字就是去掉目录和后缀的文件名
+fn project_targets<'a>(
+    target_dir: &str,
+    prefix: &str,
+    names: &[String],
+    contents: &'a [String],
+) -> Vec<ProjectTarget<'a>> {
+    names
+        .iter()
+        .zip(contents)
+        .map(|(name, content)| {
+            let file_name = target_file_name(prefix, name);
+            let bin_name =
+                file_name.rsplit('/').next().unwrap().trim_end_matches(".rs").to_string();
+            (bin_name, format!("{}/{}", target_dir, file_name), content)
+        })
+        .collect_vec()
+}
+
+/// 按照策略从生成的序列中选出要写成测试文件的序列，去重之后按序列的字符串排序
+pub(crate) fn choose_sequences(
+    api_graph: &ApiGraph<'_>,
+    strategy: GraphTraverseAlgorithm,
+    max_size: usize,
+    max_len: usize,
+) -> Vec<ApiSequence> {
+    //let chosen_sequences = api_graph._naive_choose_sequence(MAX_TEST_FILE_NUMBER);
+    let mut _chosen_sequences = if let Some(selection) = api_graph.selection {
+        match selection {
+            SequenceSelection::First => api_graph._first_choose(max_size, max_len),
+            SequenceSelection::Heuristic => api_graph._heuristic_choose(max_size, true),
+            SequenceSelection::Random => api_graph._random_choose(max_size),
+            SequenceSelection::PairCoverage => api_graph._pair_coverage_choose(max_size),
+            SequenceSelection::SetCover => api_graph._set_cover_choose(max_size),
+            SequenceSelection::Complexity => api_graph._complexity_choose(max_size),
+        }
+    } else if strategy == _Fudge {
+        //api_graph.api_sequences.clone()
+        println!("sequences {}", api_graph.api_sequences.len());
+        //api_graph._heuristic_choose(10, true)
+        api_graph._first_choose(max_size, max_len)
+    } else if strategy == _UseRealWorld {
+        //api_graph.api_sequences.clone()
+        //api_graph._heuristic_choose(max_len, true)
+        api_graph._first_choose(max_size, max_len)
+    } else if [_Bfs, _MarkovWalk, _Genetic, _Mcts, _BeamSearch].contains(&strategy) {
+        println!("Heuristic_choose");
+        api_graph._heuristic_choose(max_size, true)
+    } else {
+        api_graph._first_choose(max_size, max_len)
+    };
+
+    api_graph._add_builder_sequences(&mut _chosen_sequences);
+    api_graph._add_oracle_sequences(&mut _chosen_sequences);
+
+    let mut sequence_map = FxHashMap::default();
+    for seq in _chosen_sequences {
+        let seq_str = seq.print_sequence(api_graph, true);
+        //println!("{}", seq_str);
+        sequence_map.insert(seq_str, seq);
+    }
+
+    println!("去重之后的序列集合包含 {} 个序列", sequence_map.len());
+    let mut chosen_sequences = sequence_map.iter().collect_vec();
+    chosen_sequences.sort_by(|(x, _), (y, _)| x.cmp(y));
+    chosen_sequences.iter().map(|(_s, seq)| (*seq).clone()).collect_vec()
+}
+
+/// 读取配置文件，每行的格式是 `key  |  value`，`#`开头的行是注释
+/// 文件不存在的话返回空列表
+pub(crate) fn read_config_pairs(file_path: impl AsRef<Path>) -> Vec<(String, String)> {
+    use std::io::{BufRead, BufReader};
+
+    let file_path = file_path.as_ref();
+    let mut res = Vec::new();
+    let file = match fs::File::open(file_path) {
+        Ok(file) => file,
+        Err(_) => return res,
+    };
+    let reader = BufReader::new(file);
+    for line in reader.lines() {
+        let line = line.unwrap();
+        let line = line.trim();
+        if line.is_empty() || line.starts_with("#") {
+            continue;
+        }
+        match line.split_once("|") {
+            Some((key, value)) => res.push((key.trim().to_string(), value.trim().to_string())),
+            None => diagnostics::warn(
+                WarningKind::Degraded,
+                None,
+                format!("invalid config line in {}: {}, ignored", file_path.display(), line),
+            ),
+        }
+    }
+    res
+}
+
+/// 读取write_files写出的索引，返回函数在文档中的全路径到测试文件名的映射
+/// 还没有生成过fuzz target的话返回空
+pub(crate) fn read_target_index(crate_name: &str) -> FxHashMap<String, Vec<String>> {
+    let test_dir = get_real_world_crate_test_dir(crate_name.replace("_", "-").as_str());
+    let index_file = PathBuf::from(test_dir).join(FUZZ_TARGET_INDEX_FILE);
+    let mut res: FxHashMap<String, Vec<String>> = FxHashMap::default();
+    for (function_name, rest) in read_config_pairs(&index_file) {
+        //后面的编码方式不需要
+        let file_name = match rest.split_once("|") {
+            Some((file_name, _)) => file_name.trim().to_string(),
+            None => rest,
+        };
+        res.entry(function_name).or_default().push(file_name);
+    }
+    res
+}
+
+/// 文档页面上函数的全路径：用trait名调用的方法换成实现trait的类型的路径
+pub(crate) fn doc_name_of(api_graph: &ApiGraph<'_>, api_function: &ApiFunction) -> String {
+    if let Some(trait_full_path) = &api_function._trait_full_path {
+        let self_type_path = api_function.inputs.first().and_then(|input_type| {
+            invariant::type_full_path(input_type, api_graph.cache, &api_graph.full_name_map)
+        });
+        let trait_prefix = format!("{}::", trait_full_path);
+        if let (Some(self_type_path), Some(method_name)) =
+            (self_type_path, api_function.full_name.strip_prefix(trait_prefix.as_str()))
+        {
+            return format!("{}::{}", self_type_path, method_name);
+        }
+    }
+    api_function.full_name.clone()
+}
+
+/// 序列的名字取自最后一个调用的函数，也就是序列要测试的API，
+/// 其他编码方式的序列在名字里加上编码方式，避免和默认编码的序列混在一起
+fn sequence_target_name(
+    api_graph: &ApiGraph<'_>,
+    sequence: &ApiSequence,
+    encoding: EncodingVariant,
+    name_counter: &mut FxHashMap<String, usize>,
+) -> String {
+    let full_name = match sequence.functions.last() {
+        Some(api_call) => {
+            let api_function = &api_graph.api_functions[api_call.func.1];
+            let doc_name = doc_name_of(api_graph, api_function);
+            //合成的辅助函数（见foreign_trait.rs）没有路径，挂到第一个参数的类型下面
+            let self_type_path = api_function.inputs.first().and_then(|input_type| {
+                invariant::type_full_path(input_type, api_graph.cache, &api_graph.full_name_map)
+            });
+            match self_type_path {
+                Some(self_type_path) if !doc_name.contains("::") => {
+                    format!("{}::{}", self_type_path, doc_name.trim_start_matches('_'))
+                }
+                _ => doc_name,
+            }
+        }
+        None => api_graph._crate_name.clone(),
+    };
+    let full_name = match encoding {
+        EncodingVariant::RawSplit => full_name,
+        _ => format!("{}::{}", full_name, encoding.name()),
+    };
+    unique_target_name(api_graph, &full_name, name_counter)
+}
+
+/// 由API的全路径得到fuzz target的名字：所在模块作为子目录，路径的每一段用下划线连起来，
+/// 最后加上两位的编号区分同名的target，比如regex::bytes::Regex::find的第一个target是
+/// `regex/bytes/regex_bytes_regex_find_01`
+fn unique_target_name(
+    api_graph: &ApiGraph<'_>,
+    full_name: &str,
+    name_counter: &mut FxHashMap<String, usize>,
+) -> String {
+    let segments = full_name.split("::").collect_vec();
+    //最长的、作为模块出现过的前缀就是所在的模块
+    let mod_len = (1..segments.len())
+        .rev()
+        .find(|len| api_graph.mod_visibility.inner.contains_key(&segments[..*len].join("::")))
+        .unwrap_or(segments.len().saturating_sub(1));
+    let mod_dir =
+        if mod_len == 0 { api_graph._crate_name.clone() } else { segments[..mod_len].join("/") };
+    let stem = segments.iter().map(|segment| sanitize_name(segment)).join("_");
+    let count = name_counter.entry(stem.clone()).or_insert(0);
+    *count = *count + 1;
+    format!("{}/{}_{:0>2}", mod_dir, stem, count)
+}
+
+/// 文件名只保留字母、数字和下划线
+fn sanitize_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+        .collect()
+}
+
+/// `regex/bytes/regex_bytes_regex_find_01` -> `regex/bytes/fuzz_regex_bytes_regex_find_01.rs`
+fn target_file_name(prefix: &str, target_name: &str) -> String {
+    match target_name.rsplit_once("/") {
+        Some((mod_dir, stem)) => format!("{}/{}_{}.rs", mod_dir, prefix, stem),
+        None => format!("{}_{}.rs", prefix, target_name),
+    }
+}
+
+// 每个contents[i]的内容，写入文件【模块目录/prefix_names[i].rs】
+fn write_to_files(path: &PathBuf, names: &Vec<String>, contents: &Vec<String>, prefix: &str) {
+    for (name, content) in names.iter().zip(contents) {
+        let full_filename = path.join(target_file_name(prefix, name));
+        fs::create_dir_all(full_filename.parent().unwrap()).unwrap();
         let mut file = fs::File::create(full_filename).unwrap();
-        file.write_all(contents[i].as_bytes()).unwrap();
+        file.write_all(content.as_bytes()).unwrap();
     }
 }
 
@@ -0,0 +1,33 @@
+//! fixture.json对应的源码，只用来说明fixture.json的内容，不参与编译
+//! 重新生成fixture.json：`rustdoc --edition 2021 -Z unstable-options --output-format json fixture.rs`，
+//! 之后去掉span等和测试无关的字段
+
+pub struct Buffer {
+    bytes: Vec<u8>,
+}
+
+impl Buffer {
+    pub fn new(data: &[u8]) -> Buffer {
+        Buffer { bytes: data.to_vec() }
+    }
+
+    pub fn push(&mut self, byte: u8) {
+        self.bytes.push(byte);
+    }
+
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    pub unsafe fn get_unchecked(&self, index: usize) -> u8 {
+        *self.bytes.get_unchecked(index)
+    }
+}
+
+pub fn checksum(data: &[u8], key: &str) -> u32 {
+    data.iter().chain(key.as_bytes()).fold(0u32, |acc, byte| acc.wrapping_mul(31) + *byte as u32)
+}
+
+pub fn pick<T>(first: T, second: T, take_second: bool) -> T {
+    if take_second { second } else { first }
+}
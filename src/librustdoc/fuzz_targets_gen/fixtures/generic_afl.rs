@@ -0,0 +1,79 @@
+#![allow(unused_imports, unused_unsafe)]
+#[macro_use]
+extern crate afl;
+extern crate fixture;
+struct _ByteReader<'a> {
+    data: &'a [u8],
+    index: usize,
+}
+
+impl<'a> _ByteReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        _ByteReader { data, index: 0 }
+    }
+
+    fn _fixed<const N: usize>(&mut self) -> [u8; N] {
+        let mut bytes = [0u8; N];
+        let end = (self.index + N).min(self.data.len());
+        bytes[..end - self.index].copy_from_slice(&self.data[self.index..end]);
+        self.index = end;
+        bytes
+    }
+
+    fn _length(&mut self) -> usize {
+        let mut length = 0usize;
+        let mut shift = 0;
+        while self.index < self.data.len() && shift < 28 {
+            let byte = self.data[self.index];
+            self.index += 1;
+            length |= ((byte & 0x7f) as usize) << shift;
+            if byte & 0x80 == 0 {
+                break;
+            }
+            shift += 7;
+        }
+        length
+    }
+
+    fn _dynamic(&mut self) -> &'a [u8] {
+        let length = self._length();
+        let start = self.index;
+        let end = start + length.min(self.data.len() - start);
+        self.index = end;
+        &self.data[start..end]
+    }
+
+    fn _count(&mut self) -> usize {
+        self._length().min(self.data.len() - self.index)
+    }
+}
+
+fn _to_u8(data:&[u8], index:usize)->u8 {
+    data[index]
+}
+
+fn _to_bool(data:&[u8], index: usize)->bool {
+    let bool_value = _to_u8(data, index);
+    if bool_value %2 == 0 {
+        true
+    } else {
+        false
+    }
+}
+
+
+fn test_function4(_param0 :u8 ,_param1 :u8 ,_param2 :bool) {
+    let _ = fixture::pick::<u8>(_param0 ,_param1 ,_param2);
+}
+
+fn main() {
+    fuzz!(|data: &[u8]| {
+        //actual body emit
+        if data.len() != 3 {return;}
+        let mut _reader = _ByteReader::new(data);
+        let _param0 = { let data = &_reader._fixed::<1>(); _to_u8(data, 0) };
+        let _param1 = { let data = &_reader._fixed::<1>(); _to_u8(data, 0) };
+        let _param2 = { let data = &_reader._fixed::<1>(); _to_bool(data, 0) };
+        test_function4(_param0 ,_param1 ,_param2);
+    });
+}
@@ -0,0 +1,79 @@
+#![allow(unused_imports, unused_unsafe)]
+#[macro_use]
+extern crate afl;
+extern crate fixture;
+struct _ByteReader<'a> {
+    data: &'a [u8],
+    index: usize,
+}
+
+impl<'a> _ByteReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        _ByteReader { data, index: 0 }
+    }
+
+    fn _fixed<const N: usize>(&mut self) -> [u8; N] {
+        let mut bytes = [0u8; N];
+        let end = (self.index + N).min(self.data.len());
+        bytes[..end - self.index].copy_from_slice(&self.data[self.index..end]);
+        self.index = end;
+        bytes
+    }
+
+    fn _length(&mut self) -> usize {
+        let mut length = 0usize;
+        let mut shift = 0;
+        while self.index < self.data.len() && shift < 28 {
+            let byte = self.data[self.index];
+            self.index += 1;
+            length |= ((byte & 0x7f) as usize) << shift;
+            if byte & 0x80 == 0 {
+                break;
+            }
+            shift += 7;
+        }
+        length
+    }
+
+    fn _dynamic(&mut self) -> &'a [u8] {
+        let length = self._length();
+        let start = self.index;
+        let end = start + length.min(self.data.len() - start);
+        self.index = end;
+        &self.data[start..end]
+    }
+
+    fn _count(&mut self) -> usize {
+        self._length().min(self.data.len() - self.index)
+    }
+}
+
+fn _to_slice<T>(data:&[u8], start_index: usize, end_index: usize)->&[T] {
+    let data_slice = &data[start_index..end_index];
+    let (_, shorts, _) = unsafe {data_slice.align_to::<T>()};
+    shorts
+}
+
+fn _to_u8(data:&[u8], index:usize)->u8 {
+    data[index]
+}
+
+
+fn test_function0(_param0 :&[u8] ,_param1 :u8) {
+    //size cap: _param0.len() <= 4096
+    let _param0 = &_param0[.._param0.len().min(4096)];
+    let mut _local0 = fixture::Buffer::new(_param0);
+    let _ = fixture::Buffer::push(&mut (_local0) ,_param1);
+    let _ = fixture::Buffer::len(&(_local0));
+}
+
+fn main() {
+    fuzz!(|data: &[u8]| {
+        //actual body emit
+        if data.len() < 2 {return;}
+        let mut _reader = _ByteReader::new(data);
+        let _param0 = { let data = _reader._dynamic(); _to_slice::<u8>(data, 0, data.len()) };
+        let _param1 = { let data = &_reader._fixed::<1>(); _to_u8(data, 0) };
+        test_function0(_param0 ,_param1);
+    });
+}
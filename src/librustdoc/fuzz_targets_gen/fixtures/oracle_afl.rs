@@ -0,0 +1,92 @@
+#![allow(unused_imports, unused_unsafe)]
+#[macro_use]
+extern crate afl;
+extern crate fixture;
+struct _ByteReader<'a> {
+    data: &'a [u8],
+    index: usize,
+}
+
+impl<'a> _ByteReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        _ByteReader { data, index: 0 }
+    }
+
+    fn _fixed<const N: usize>(&mut self) -> [u8; N] {
+        let mut bytes = [0u8; N];
+        let end = (self.index + N).min(self.data.len());
+        bytes[..end - self.index].copy_from_slice(&self.data[self.index..end]);
+        self.index = end;
+        bytes
+    }
+
+    fn _length(&mut self) -> usize {
+        let mut length = 0usize;
+        let mut shift = 0;
+        while self.index < self.data.len() && shift < 28 {
+            let byte = self.data[self.index];
+            self.index += 1;
+            length |= ((byte & 0x7f) as usize) << shift;
+            if byte & 0x80 == 0 {
+                break;
+            }
+            shift += 7;
+        }
+        length
+    }
+
+    fn _dynamic(&mut self) -> &'a [u8] {
+        let length = self._length();
+        let start = self.index;
+        let end = start + length.min(self.data.len() - start);
+        self.index = end;
+        &self.data[start..end]
+    }
+
+    fn _count(&mut self) -> usize {
+        self._length().min(self.data.len() - self.index)
+    }
+}
+
+fn _to_slice<T>(data:&[u8], start_index: usize, end_index: usize)->&[T] {
+    let data_slice = &data[start_index..end_index];
+    let (_, shorts, _) = unsafe {data_slice.align_to::<T>()};
+    shorts
+}
+
+fn _to_str(data:&[u8], start_index: usize, end_index: usize)->&str {
+    let data_slice = &data[start_index..end_index];
+    use std::str;
+    match str::from_utf8(data_slice) {
+        Ok(s)=>s,
+        Err(_)=>{
+            use std::process;
+            process::exit(0);
+        }
+    }
+}
+
+
+fn test_function3(_param0 :&[u8] ,_param1 :&str) {
+    //size cap: _param0.len() <= 4096
+    let _param0 = &_param0[.._param0.len().min(4096)];
+    //size cap: _param1.len() <= 4096
+    let _param1 = { let mut end = _param1.len().min(4096); while !_param1.is_char_boundary(end) { end -= 1; } &_param1[..end] };
+    let _param0_oracle = _param0.clone();
+    let _param1_oracle = _param1.clone();
+    let _target_output0 = fixture::checksum(_param0 ,_param1);
+    let _oracle_output0 = naive_checksum(_param0_oracle ,_param1_oracle);
+    assert_eq!(_target_output0, _oracle_output0, "fixture::checksum disagrees with naive_checksum");
+    let _ = _target_output0;
+}
+
+fn main() {
+    fuzz!(|data: &[u8]| {
+        //actual body emit
+        if data.len() < 2 {return;}
+        let mut _reader = _ByteReader::new(data);
+        let _param0 = { let data = _reader._dynamic(); _to_slice::<u8>(data, 0, data.len()) };
+        let _param1 = { let data = _reader._dynamic(); _to_str(data, 0, data.len()) };
+        test_function3(_param0 ,_param1);
+    });
+}
@@ -0,0 +1,100 @@
+#![allow(unused_imports, unused_unsafe)]
+#[macro_use]
+extern crate afl;
+extern crate fixture;
+fn _to_u64(data:&[u8], index:usize)->u64 {
+    let data0 = _to_u32(data, index) as u64;
+    let data1 = _to_u32(data, index+4) as u64;
+    data0 << 32 | data1
+}
+
+fn _to_u32(data:&[u8], index:usize)->u32 {
+    let data0 = _to_u16(data, index) as u32;
+    let data1 = _to_u16(data, index+2) as u32;
+    data0 << 16 | data1
+}
+
+fn _to_usize(data:&[u8], index:usize)->usize {
+    _to_u64(data, index) as usize
+}
+
+struct _ByteReader<'a> {
+    data: &'a [u8],
+    index: usize,
+}
+
+impl<'a> _ByteReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        _ByteReader { data, index: 0 }
+    }
+
+    fn _fixed<const N: usize>(&mut self) -> [u8; N] {
+        let mut bytes = [0u8; N];
+        let end = (self.index + N).min(self.data.len());
+        bytes[..end - self.index].copy_from_slice(&self.data[self.index..end]);
+        self.index = end;
+        bytes
+    }
+
+    fn _length(&mut self) -> usize {
+        let mut length = 0usize;
+        let mut shift = 0;
+        while self.index < self.data.len() && shift < 28 {
+            let byte = self.data[self.index];
+            self.index += 1;
+            length |= ((byte & 0x7f) as usize) << shift;
+            if byte & 0x80 == 0 {
+                break;
+            }
+            shift += 7;
+        }
+        length
+    }
+
+    fn _dynamic(&mut self) -> &'a [u8] {
+        let length = self._length();
+        let start = self.index;
+        let end = start + length.min(self.data.len() - start);
+        self.index = end;
+        &self.data[start..end]
+    }
+
+    fn _count(&mut self) -> usize {
+        self._length().min(self.data.len() - self.index)
+    }
+}
+
+fn _to_slice<T>(data:&[u8], start_index: usize, end_index: usize)->&[T] {
+    let data_slice = &data[start_index..end_index];
+    let (_, shorts, _) = unsafe {data_slice.align_to::<T>()};
+    shorts
+}
+
+fn _to_u8(data:&[u8], index:usize)->u8 {
+    data[index]
+}
+
+fn _to_u16(data:&[u8], index:usize)->u16 {
+    let data0 = _to_u8(data, index) as u16;
+    let data1 = _to_u8(data, index+1) as u16;
+    data0 << 8 | data1
+}
+
+
+fn test_function1(_param0 :&[u8] ,_param1 :usize) {
+    //size cap: _param0.len() <= 4096
+    let _param0 = &_param0[.._param0.len().min(4096)];
+    let _local0 = fixture::Buffer::new(_param0);
+    let _ = unsafe { fixture::Buffer::get_unchecked(&(_local0) ,_param1) };
+}
+
+fn main() {
+    fuzz!(|data: &[u8]| {
+        //actual body emit
+        if data.len() < 9 {return;}
+        let mut _reader = _ByteReader::new(data);
+        let _param0 = { let data = _reader._dynamic(); _to_slice::<u8>(data, 0, data.len()) };
+        let _param1 = { let data = &_reader._fixed::<8>(); _to_usize(data, 0) };
+        test_function1(_param0 ,_param1);
+    });
+}
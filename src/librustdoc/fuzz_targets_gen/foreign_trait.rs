@@ -0,0 +1,183 @@
+//! 外部trait的实现
+//! crate里的类型实现Read、Write、Iterator、Extend这些标准库trait的时候，这些impl也是公开的API，
+//! 但是trait不在当前crate里，_analyse_impl找不到trait的路径，里面的方法会被丢掉。
+//! 这里为每个这样的impl加一个合成的函数，参数是`&mut Self`和一段fuzz输入，
+//! 生成测试文件时调用对应的辅助函数（用fuzz输入决定缓冲区大小、写入或者extend的内容）
+//! 返回`impl Iterator`、`impl Read`、`impl Write`的函数也会为这个不透明类型加同样的合成函数，见add_opaque_surface
+
+use crate::clean::{self};
+use crate::fuzz_targets_gen::api_function::{ApiAbi, ApiAsyncness, ApiFunction, ApiUnsafety};
+use crate::fuzz_targets_gen::api_graph::ApiGraph;
+use crate::fuzz_targets_gen::api_sequence::ApiSequence;
+use crate::fuzz_targets_gen::iterator_law;
+use crate::fuzz_targets_gen::opaque_type;
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
+use rustc_hir::Mutability;
+use rustc_middle::ty::Visibility;
+use thin_vec::ThinVec;
+
+static READ_TRAIT: &'static str = "std::io::Read";
+static WRITE_TRAIT: &'static str = "std::io::Write";
+static EXTEND_TRAIT: &'static str = "core::iter::traits::collect::Extend";
+
+static READ_HELPER: &'static str = "_fries_read";
+static WRITE_HELPER: &'static str = "_fries_write";
+static ITERATE_HELPER: &'static str = "_fries_iterate";
+static EXTEND_HELPER: &'static str = "_fries_extend";
+
+/// 如果impl的是支持的外部trait，加入对应的合成函数
+/// 带泛型参数的impl暂时不考虑，合成函数的参数类型里会出现没有替换的泛型
+pub(crate) fn add_trait_surface(
+    impl_: &clean::Impl,
+    trait_full_name: &str,
+    api_graph: &mut ApiGraph<'_>,
+) {
+    if impl_
+        .generics
+        .params
+        .iter()
+        .any(|param| !matches!(param.kind, clean::GenericParamDefKind::Lifetime { .. }))
+    {
+        return;
+    }
+    let self_type = impl_.for_.clone();
+    let (helper, data_type) = if trait_full_name == READ_TRAIT {
+        (READ_HELPER, u8_slice())
+    } else if trait_full_name == WRITE_TRAIT {
+        (WRITE_HELPER, u8_slice())
+    } else if trait_full_name == iterator_law::ITERATOR_TRAIT {
+        //fuzz输入决定最多调用多少次next
+        (ITERATE_HELPER, clean::Type::Primitive(clean::PrimitiveType::U8))
+    } else if trait_full_name == EXTEND_TRAIT {
+        //只考虑Extend<A>里A是基本类型的情况，用fuzz输入的切片去extend
+        let element_type = match &impl_.trait_ {
+            Some(trait_) => match trait_.generics().as_deref() {
+                Some([clean::Type::Primitive(primitive)]) => *primitive,
+                _ => return,
+            },
+            None => return,
+        };
+        (
+            EXTEND_HELPER,
+            borrowed(clean::Type::Slice(Box::new(clean::Type::Primitive(element_type)))),
+        )
+    } else {
+        return;
+    };
+    api_graph.add_api_function(helper_api_function(helper, self_type, data_type));
+}
+
+/// 返回值是impl Iterator、impl Read、impl Write的时候，为这个不透明类型加合成函数，同样的类型只加一次
+pub(crate) fn add_opaque_surface(opaque_type: &clean::Type, api_graph: &mut ApiGraph<'_>) {
+    for trait_full_name in opaque_type::bound_trait_paths(opaque_type, api_graph.cache) {
+        let (helper, data_type) = if trait_full_name == READ_TRAIT {
+            (READ_HELPER, u8_slice())
+        } else if trait_full_name == WRITE_TRAIT {
+            (WRITE_HELPER, u8_slice())
+        } else if trait_full_name == iterator_law::ITERATOR_TRAIT {
+            (ITERATE_HELPER, clean::Type::Primitive(clean::PrimitiveType::U8))
+        } else {
+            continue;
+        };
+        let api_function = helper_api_function(helper, opaque_type.clone(), data_type);
+        let exists = api_graph.api_functions.iter().any(|existing| {
+            existing.full_name == api_function.full_name && existing.inputs == api_function.inputs
+        });
+        if !exists {
+            api_graph.add_api_function(api_function);
+        }
+    }
+}
+
+/// 辅助函数对应的合成函数，参数是`&mut Self`和一段fuzz输入
+fn helper_api_function(
+    helper: &str,
+    self_type: clean::Type,
+    data_type: clean::Type,
+) -> ApiFunction {
+    let self_ref = clean::Type::BorrowedRef {
+        lifetime: None,
+        mutability: Mutability::Mut,
+        type_: Box::new(self_type),
+    };
+    ApiFunction {
+        full_name: helper.to_string(),
+        _generics: clean::Generics { params: ThinVec::new(), where_predicates: ThinVec::new() },
+        generic_substitutions: FxHashMap::default(),
+        inputs: vec![self_ref, data_type],
+        output: None,
+        _trait_full_path: None,
+        _unsafe_tag: ApiUnsafety::Normal,
+        _abi: ApiAbi::Rust,
+        _asyncness: ApiAsyncness::NotAsync,
+        visibility: Visibility::Public,
+        span: None,
+        def_id: None,
+        _uses_unsafe: false,
+        complexity: None,
+        trait_dispatch: None,
+    }
+}
+
+fn borrowed(type_: clean::Type) -> clean::Type {
+    clean::Type::BorrowedRef { lifetime: None, mutability: Mutability::Not, type_: Box::new(type_) }
+}
+
+fn u8_slice() -> clean::Type {
+    borrowed(clean::Type::Slice(Box::new(clean::Type::Primitive(clean::PrimitiveType::U8))))
+}
+
+/// 合成函数对应的辅助函数的定义
+fn helper_function(helper: &str) -> Option<&'static str> {
+    if helper == READ_HELPER {
+        Some(
+            "fn _fries_read<R: std::io::Read>(reader: &mut R, data: &[u8]) {
+    let mut buf = vec![0u8; data.len()];
+    let _ = reader.read(&mut buf);
+    let _ = reader.read_exact(&mut buf);
+}\n",
+        )
+    } else if helper == WRITE_HELPER {
+        Some(
+            "fn _fries_write<W: std::io::Write>(writer: &mut W, data: &[u8]) {
+    let _ = writer.write(data);
+    let _ = writer.write_all(data);
+    let _ = writer.flush();
+}\n",
+        )
+    } else if helper == ITERATE_HELPER {
+        Some(
+            "fn _fries_iterate<I: Iterator>(iter: &mut I, steps: u8) {
+    let _ = iter.size_hint();
+    for _ in 0..steps {
+        if iter.next().is_none() {
+            break;
+        }
+    }
+}\n",
+        )
+    } else if helper == EXTEND_HELPER {
+        Some(
+            "fn _fries_extend<A: Copy, E: Extend<A>>(target: &mut E, data: &[A]) {
+    target.extend(data.iter().copied());
+}\n",
+        )
+    } else {
+        None
+    }
+}
+
+/// 序列里用到的辅助函数
+pub(crate) fn helper_functions(sequence: &ApiSequence, api_graph: &ApiGraph<'_>) -> Option<String> {
+    let mut seen = FxHashSet::default();
+    let mut res = String::new();
+    for api_call in &sequence.functions {
+        let full_name = &api_graph.api_functions[api_call.func.1].full_name;
+        if let Some(helper) = helper_function(full_name) {
+            if seen.insert(full_name.clone()) {
+                res.push_str(helper);
+            }
+        }
+    }
+    if res.is_empty() { None } else { Some(res) }
+}
@@ -0,0 +1,168 @@
+//! fries.toml：每个crate的调优参数
+//! 优先级从高到低：命令行选项 > `{experiment_root}/{crate}/fries.toml` > `{experiment_root}/fries.toml` > 内置的默认值
+//! 文件里所有的键都是可选的，比如：
+//!     walk_steps = 10000   # 随机游走的步数
+//!     cover_nodes = 96     # 遍历时覆盖这么多函数就可以停止
+//!     bfs_max_len = 5      # BFS的最大深度
+//!     max_num = 100        # 最多选出多少个序列
+//!     max_len = 15         # 序列的最大长度
+//!     support_generic = true     # 生成泛型API，用候选类型实例化，设置了generic_candidates时默认打开
+//!     generic_candidates = ["u8", "&str", "Vec<u8>"]   # 替换泛型参数的候选类型
+//!     frequency_exponent = 1.0   # 选序列时语料里API出现频率的指数，0表示不考虑频率
+//!     population_size = 100      # 遗传算法的种群大小
+//!     generations = 50           # 遗传算法演化的代数
+//!     beam_width = 50            # 束搜索每层保留的序列数
+//!     prioritize_unsafe = true   # 生成和选序列时偏向内部用到unsafe的API
+//!     keep_trivial_accessors = true   # 不过滤简单的getter/setter
+//!     include = ["regex::bytes::*"]   # 只生成全路径匹配其中一个glob模式的API
+//!     exclude = ["*::exit"]           # 不生成全路径匹配其中一个glob模式的API
+
+use crate::fuzz_targets_gen::analyzer::GenerationConfig;
+use crate::fuzz_targets_gen::diagnostics::{self, WarningKind};
+use crate::fuzz_targets_gen::file_util;
+use rustc_data_structures::fx::FxHashMap;
+use serde::Deserialize;
+use std::fs;
+
+pub(crate) static FRIES_CONFIG_FILE: &'static str = "fries.toml";
+
+lazy_static! {
+    static ref RANDOM_WALK_STEPS: FxHashMap<&'static str, usize> = {
+        let mut m = FxHashMap::default();
+        m.insert("regex", 10000);
+        m.insert("url", 10000);
+        m.insert("time", 10000);
+        m
+    };
+}
+
+lazy_static! {
+    static ref CAN_COVER_NODES: FxHashMap<&'static str, usize> = {
+        let mut m = FxHashMap::default();
+        m.insert("regex", 96);
+        m.insert("serde_json", 41);
+        m.insert("clap", 66);
+        m
+    };
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub(crate) struct TuningConfig {
+    pub(crate) walk_steps: Option<usize>,
+    pub(crate) cover_nodes: Option<usize>,
+    pub(crate) bfs_max_len: Option<usize>,
+    pub(crate) max_num: Option<usize>,
+    pub(crate) max_len: Option<usize>,
+    pub(crate) support_generic: Option<bool>,
+    pub(crate) generic_candidates: Option<Vec<String>>,
+    pub(crate) frequency_exponent: Option<f64>,
+    pub(crate) population_size: Option<usize>,
+    pub(crate) generations: Option<usize>,
+    pub(crate) beam_width: Option<usize>,
+    pub(crate) prioritize_unsafe: Option<bool>,
+    pub(crate) keep_trivial_accessors: Option<bool>,
+    pub(crate) include: Option<Vec<String>>,
+    pub(crate) exclude: Option<Vec<String>>,
+}
+
+impl TuningConfig {
+    /// 内置的默认值
+    fn builtin(crate_name: &str) -> Self {
+        TuningConfig {
+            walk_steps: RANDOM_WALK_STEPS.get(crate_name).copied(),
+            cover_nodes: CAN_COVER_NODES.get(crate_name).copied(),
+            ..TuningConfig::default()
+        }
+    }
+
+    /// other里设置了的值覆盖self
+    pub(crate) fn merge(&mut self, other: TuningConfig) {
+        self.walk_steps = other.walk_steps.or(self.walk_steps);
+        self.cover_nodes = other.cover_nodes.or(self.cover_nodes);
+        self.bfs_max_len = other.bfs_max_len.or(self.bfs_max_len);
+        self.max_num = other.max_num.or(self.max_num);
+        self.max_len = other.max_len.or(self.max_len);
+        self.support_generic = other.support_generic.or(self.support_generic);
+        self.generic_candidates = other.generic_candidates.or(self.generic_candidates.take());
+        self.frequency_exponent = other.frequency_exponent.or(self.frequency_exponent);
+        self.population_size = other.population_size.or(self.population_size);
+        self.generations = other.generations.or(self.generations);
+        self.beam_width = other.beam_width.or(self.beam_width);
+        self.prioritize_unsafe = other.prioritize_unsafe.or(self.prioritize_unsafe);
+        self.keep_trivial_accessors = other.keep_trivial_accessors.or(self.keep_trivial_accessors);
+        self.include = other.include.or(self.include.take());
+        self.exclude = other.exclude.or(self.exclude.take());
+    }
+
+    /// 设置了的值覆盖cfg里的参数
+    pub(crate) fn apply_to(&self, cfg: &mut GenerationConfig) {
+        cfg.max_num = self.max_num.unwrap_or(cfg.max_num);
+        cfg.max_len = self.max_len.unwrap_or(cfg.max_len);
+        cfg.walk_steps = self.walk_steps.or(cfg.walk_steps);
+        cfg.bfs_max_len = self.bfs_max_len.or(cfg.bfs_max_len);
+        cfg.cover_nodes = self.cover_nodes.or(cfg.cover_nodes);
+        cfg.support_generic = self.support_generic.unwrap_or(cfg.support_generic);
+        cfg.generic_candidates = self.generic_candidates.clone().or(cfg.generic_candidates.take());
+        cfg.frequency_exponent = self.frequency_exponent.or(cfg.frequency_exponent);
+        cfg.population_size = self.population_size.or(cfg.population_size);
+        cfg.generations = self.generations.or(cfg.generations);
+        cfg.beam_width = self.beam_width.or(cfg.beam_width);
+        cfg.prioritize_unsafe = self.prioritize_unsafe.unwrap_or(cfg.prioritize_unsafe);
+        cfg.keep_trivial_accessors =
+            self.keep_trivial_accessors.unwrap_or(cfg.keep_trivial_accessors);
+        if let Some(include) = &self.include {
+            cfg.include = include.clone();
+        }
+        if let Some(exclude) = &self.exclude {
+            cfg.exclude = exclude.clone();
+        }
+    }
+}
+
+/// 按优先级读取全局和crate目录下的fries.toml
+pub(crate) fn load_tuning_config(crate_name: &str) -> TuningConfig {
+    let mut config = TuningConfig::builtin(crate_name);
+    let experiment_root = file_util::experiment_root();
+    for config_dir in [experiment_root.clone(), format!("{}/{}", experiment_root, crate_name)] {
+        if let Some(file_config) =
+            read_tuning_file(format!("{}/{}", config_dir, FRIES_CONFIG_FILE).as_str())
+        {
+            config.merge(file_config);
+        }
+    }
+    config
+}
+
+/// 读取一个fries.toml，文件不存在时返回None，格式不对时给出警告并忽略
+/// load_tuning_config和Analyzer::load_config_dir都通过这里读文件，不合法的值在这里去掉
+pub(crate) fn read_tuning_file(file_path: &str) -> Option<TuningConfig> {
+    let content = fs::read_to_string(file_path).ok()?;
+    match toml::from_str::<TuningConfig>(&content) {
+        Ok(mut config) => {
+            //和--fuzz-frequency-exponent一样只接受非负的有限值，NaN会让选序列时的权重变成NaN
+            if let Some(exponent) = config.frequency_exponent {
+                if !(exponent.is_finite() && exponent >= 0.0) {
+                    diagnostics::warn(
+                        WarningKind::Degraded,
+                        None,
+                        format!(
+                            "invalid frequency_exponent in {}: {}, expects a non-negative number, ignored",
+                            file_path, exponent
+                        ),
+                    );
+                    config.frequency_exponent = None;
+                }
+            }
+            Some(config)
+        }
+        Err(e) => {
+            diagnostics::warn(
+                WarningKind::Degraded,
+                None,
+                format!("invalid {}: {}, ignored", file_path, e),
+            );
+            None
+        }
+    }
+}
@@ -0,0 +1,344 @@
+//! 命令行上和生成fuzz target相关的选项，和ScrapeExamplesOptions一样在config.rs里解析，
+//! 通过RenderOptions传给context.rs
+
+use crate::fuzz_targets_gen::api_graph::{GraphTraverseAlgorithm, SequenceSelection};
+use crate::fuzz_targets_gen::async_runtime::AsyncRuntime;
+use crate::fuzz_targets_gen::backend::FuzzBackend;
+use crate::fuzz_targets_gen::encoding::EncodingVariant;
+use crate::fuzz_targets_gen::fries_config::TuningConfig;
+use crate::fuzz_targets_gen::numeric_cast::NumericCast;
+use crate::fuzz_targets_gen::panic_filter::{self, PanicClass};
+use crate::fuzz_targets_gen::path_param::PathMode;
+use crate::fuzz_targets_gen::unwrap_strategy::{NonePolicy, UnwrapStrategy};
+use rustc_session::getopts;
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct FuzzOptions {
+    /// --fuzz-explain：解释为什么没有序列能到达这个API
+    pub(crate) explain: Option<String>,
+    /// --fuzz-algorithm：生成序列的算法，None表示用context.rs里的默认算法
+    pub(crate) algorithm: Option<GraphTraverseAlgorithm>,
+    /// --fuzz-selection：从生成的序列里选出fuzz target的方法，None表示按算法决定
+    pub(crate) selection: Option<SequenceSelection>,
+    /// --fuzz-max-num：最多选出多少个序列
+    pub(crate) max_num: Option<usize>,
+    /// --fuzz-max-len：序列的最大长度，BFS的最大深度也用它
+    pub(crate) max_len: Option<usize>,
+    /// --fuzz-walk-steps：随机游走的步数
+    pub(crate) walk_steps: Option<usize>,
+    /// --fuzz-population：遗传算法的种群大小
+    pub(crate) population_size: Option<usize>,
+    /// --fuzz-generations：遗传算法演化的代数
+    pub(crate) generations: Option<usize>,
+    /// --fuzz-beam-width：束搜索每层保留的序列数
+    pub(crate) beam_width: Option<usize>,
+    /// --fuzz-frequency-exponent：选序列时语料里API出现频率的指数
+    pub(crate) frequency_exponent: Option<f64>,
+    /// --fuzz-prioritize-unsafe：生成和选序列时偏向内部用到unsafe的API
+    pub(crate) prioritize_unsafe: bool,
+    /// --fuzz-keep-trivial-accessors：不过滤简单的getter/setter
+    pub(crate) keep_trivial_accessors: bool,
+    /// --fuzz-include：只生成全路径匹配其中一个glob模式的API，可以出现多次
+    pub(crate) include: Vec<String>,
+    /// --fuzz-exclude：不生成全路径匹配其中一个glob模式的API，可以出现多次
+    pub(crate) exclude: Vec<String>,
+    /// --include-deprecated：收集API时保留`#[deprecated]`的函数
+    pub(crate) include_deprecated: bool,
+    /// --include-doc-hidden：收集API时保留`#[doc(hidden)]`的函数，这时也不会运行strip-hidden
+    pub(crate) include_doc_hidden: bool,
+    /// --fuzz-numeric-cast：整数返回值传给宽度不同的整数参数时怎么转换，None表示用try_into检查
+    pub(crate) numeric_cast: Option<NumericCast>,
+    /// --fuzz-path-mode：怎么从fuzz输入构造Path和PathBuf参数，None表示不构造
+    pub(crate) path_mode: Option<PathMode>,
+    /// --fuzz-float-corners：f32和f64参数的一部分输入换成NaN、±Inf、次正规数这些特殊值
+    pub(crate) float_corners: bool,
+    /// --allow-unsafe-synthesis：给出参合成MaybeUninit槽位，调用之后用assume_init取出结果
+    pub(crate) allow_unsafe_synthesis: bool,
+    /// --fuzz-internal：在待测crate内部生成fuzz target，也测pub(crate)和私有模块里的API，
+    /// 这时也不会运行strip-private
+    pub(crate) internal: bool,
+    /// --corpus-path：从真实代码里挖出来的序列语料，文件或者目录
+    pub(crate) corpus_path: Option<String>,
+    /// --load-graph：从缓存读取找好依赖的图
+    pub(crate) load_graph: bool,
+    /// --save-graph：找好依赖之后保存到缓存
+    pub(crate) save_graph: bool,
+    /// --async-runtime：执行async fn的运行时，None表示用futures::executor
+    pub(crate) async_runtime: Option<AsyncRuntime>,
+    /// --fuzz-backend：除了afl之外还为哪些fuzz引擎生成target，可以出现多次
+    pub(crate) backends: Vec<FuzzBackend>,
+    /// --fuzz-encoding：每个序列用哪些编码方式生成fuzz target，可以出现多次，为空表示只用raw
+    pub(crate) encodings: Vec<EncodingVariant>,
+    /// --fuzz-export-json：把函数、依赖和选出来的序列导出到测试目录的api_graph.json
+    pub(crate) export_json: bool,
+    /// --fuzz-interpreter：额外为每个类型生成一个解释器模式的fuzz target，见interpreter.rs
+    pub(crate) interpreter_mode: bool,
+    /// --fuzz-unwrap：返回Option/Result的调用怎么取值，None表示Err/None时提前返回
+    pub(crate) unwrap_strategy: Option<UnwrapStrategy>,
+    /// --fuzz-none：返回Option的调用遇到None时怎么办，None表示提前返回
+    pub(crate) none_policy: Option<NonePolicy>,
+    /// --fuzz-catch-panics：捕获panic，只把列出的类别当成crash，None表示不捕获
+    pub(crate) crash_panic_classes: Option<Vec<PanicClass>>,
+    /// --target-timeout：生成的target执行整个序列的超时时间（毫秒）
+    pub(crate) target_timeout_ms: Option<u64>,
+    /// --call-timeout：每个API调用的超时时间（毫秒），None表示不开启watchdog
+    pub(crate) call_timeout_ms: Option<u64>,
+}
+
+impl FuzzOptions {
+    pub(crate) fn new(
+        matches: &getopts::Matches,
+        diag: &rustc_errors::Handler,
+    ) -> Result<Self, i32> {
+        let explain = matches.opt_str("fuzz-explain");
+        if let Some(api_path) = &explain {
+            if api_path.trim().is_empty() {
+                diag.err("--fuzz-explain needs the full path of an API, e.g. `regex::Regex::new`");
+                return Err(1);
+            }
+        }
+        let algorithm = match matches.opt_str("fuzz-algorithm") {
+            Some(name) => match GraphTraverseAlgorithm::from_name(&name) {
+                Some(algorithm) => Some(algorithm),
+                None => {
+                    diag.struct_err(format!("unknown fuzz algorithm `{}`", name))
+                        .help(
+                            "valid algorithms are: bfs, fast-bfs, bfs-end-point, \
+                             fast-bfs-end-point, random-walk, random-walk-end-point, \
+                             try-deep-bfs, backward-search, real-world, fudge, markov-walk, genetic, mcts, beam-search",
+                        )
+                        .emit();
+                    return Err(1);
+                }
+            },
+            None => None,
+        };
+        let selection = match matches.opt_str("fuzz-selection") {
+            Some(name) => match SequenceSelection::from_name(&name) {
+                Some(selection) => Some(selection),
+                None => {
+                    diag.struct_err(format!("unknown sequence selection `{}`", name))
+                        .help(
+                            "valid selections are: first, heuristic, random, pair-coverage, \
+                             set-cover, complexity",
+                        )
+                        .emit();
+                    return Err(1);
+                }
+            },
+            None => None,
+        };
+        let max_num = parse_count(matches, diag, "fuzz-max-num")?;
+        let max_len = parse_count(matches, diag, "fuzz-max-len")?;
+        let walk_steps = parse_count(matches, diag, "fuzz-walk-steps")?;
+        let population_size = parse_count(matches, diag, "fuzz-population")?;
+        let generations = parse_count(matches, diag, "fuzz-generations")?;
+        let beam_width = parse_count(matches, diag, "fuzz-beam-width")?;
+        let frequency_exponent = match matches.opt_str("fuzz-frequency-exponent") {
+            Some(value) => match value.parse::<f64>() {
+                Ok(exponent) if exponent.is_finite() && exponent >= 0.0 => Some(exponent),
+                _ => {
+                    diag.struct_err(format!(
+                        "--fuzz-frequency-exponent expects a non-negative number, found `{}`",
+                        value
+                    ))
+                    .help("use 0 to ignore API frequencies when selecting sequences")
+                    .emit();
+                    return Err(1);
+                }
+            },
+            None => None,
+        };
+        let prioritize_unsafe = matches.opt_present("fuzz-prioritize-unsafe");
+        let keep_trivial_accessors = matches.opt_present("fuzz-keep-trivial-accessors");
+        let include = matches.opt_strs("fuzz-include");
+        let exclude = matches.opt_strs("fuzz-exclude");
+        let include_deprecated = matches.opt_present("include-deprecated");
+        let include_doc_hidden = matches.opt_present("include-doc-hidden");
+        let numeric_cast = match matches.opt_str("fuzz-numeric-cast") {
+            Some(name) => match NumericCast::from_name(&name) {
+                Some(numeric_cast) => Some(numeric_cast),
+                None => {
+                    diag.struct_err(format!("unknown numeric cast `{}`", name))
+                        .help("valid casts are: checked, as, off")
+                        .emit();
+                    return Err(1);
+                }
+            },
+            None => None,
+        };
+        let path_mode = match matches.opt_str("fuzz-path-mode") {
+            Some(name) => match PathMode::from_name(&name) {
+                Some(path_mode) => Some(path_mode),
+                None => {
+                    diag.struct_err(format!("unknown path mode `{}`", name))
+                        .help("valid modes are: off, temp-file, string")
+                        .emit();
+                    return Err(1);
+                }
+            },
+            None => None,
+        };
+        let float_corners = matches.opt_present("fuzz-float-corners");
+        let allow_unsafe_synthesis = matches.opt_present("allow-unsafe-synthesis");
+        let internal = matches.opt_present("fuzz-internal");
+        let corpus_path = matches.opt_str("corpus-path");
+        let load_graph = matches.opt_present("load-graph");
+        let save_graph = matches.opt_present("save-graph");
+        let async_runtime = match matches.opt_str("async-runtime") {
+            Some(name) => match AsyncRuntime::from_name(&name) {
+                Some(async_runtime) => Some(async_runtime),
+                None => {
+                    diag.struct_err(format!("unknown async runtime `{}`", name))
+                        .help("valid runtimes are: futures, tokio")
+                        .emit();
+                    return Err(1);
+                }
+            },
+            None => None,
+        };
+        let mut backends = Vec::new();
+        for name in matches.opt_strs("fuzz-backend") {
+            match FuzzBackend::from_name(&name) {
+                Some(backend) if !backends.contains(&backend) => backends.push(backend),
+                Some(_) => {}
+                None => {
+                    diag.struct_err(format!("unknown fuzz backend `{}`", name))
+                        .help("valid backends are: honggfuzz, bolero")
+                        .emit();
+                    return Err(1);
+                }
+            }
+        }
+        let mut encodings = Vec::new();
+        for name in matches.opt_strs("fuzz-encoding") {
+            match EncodingVariant::from_name(&name) {
+                Some(encoding) if !encodings.contains(&encoding) => encodings.push(encoding),
+                Some(_) => {}
+                None => {
+                    diag.struct_err(format!("unknown fuzz encoding `{}`", name))
+                        .help("valid encodings are: raw, lossy, arbitrary")
+                        .emit();
+                    return Err(1);
+                }
+            }
+        }
+        let export_json = matches.opt_present("fuzz-export-json");
+        let interpreter_mode = matches.opt_present("fuzz-interpreter");
+        let unwrap_strategy = match matches.opt_str("fuzz-unwrap") {
+            Some(name) => match UnwrapStrategy::from_name(&name) {
+                Some(unwrap_strategy) => Some(unwrap_strategy),
+                None => {
+                    diag.struct_err(format!("unknown unwrap strategy `{}`", name))
+                        .help("valid strategies are: return, unwrap, try")
+                        .emit();
+                    return Err(1);
+                }
+            },
+            None => None,
+        };
+        let none_policy = match matches.opt_str("fuzz-none") {
+            Some(name) => match NonePolicy::from_name(&name) {
+                Some(none_policy) => Some(none_policy),
+                None => {
+                    diag.struct_err(format!("unknown None policy `{}`", name))
+                        .help("valid policies are: return, unwrap, default")
+                        .emit();
+                    return Err(1);
+                }
+            },
+            None => None,
+        };
+        let crash_panic_classes = match matches.opt_str("fuzz-catch-panics") {
+            Some(list) => {
+                match panic_filter::parse_panic_classes(&list) {
+                    Ok(classes) => Some(classes),
+                    Err(name) => {
+                        diag.struct_err(format!("unknown panic class `{}`", name))
+                        .help("valid classes are: assertion, index, overflow, unwrap, explicit, none")
+                        .emit();
+                        return Err(1);
+                    }
+                }
+            }
+            None => None,
+        };
+        let target_timeout_ms = parse_count(matches, diag, "target-timeout")?.map(|ms| ms as u64);
+        let call_timeout_ms = parse_count(matches, diag, "call-timeout")?.map(|ms| ms as u64);
+        Ok(FuzzOptions {
+            explain,
+            algorithm,
+            selection,
+            max_num,
+            max_len,
+            walk_steps,
+            population_size,
+            generations,
+            beam_width,
+            frequency_exponent,
+            prioritize_unsafe,
+            keep_trivial_accessors,
+            include,
+            exclude,
+            include_deprecated,
+            include_doc_hidden,
+            numeric_cast,
+            path_mode,
+            float_corners,
+            allow_unsafe_synthesis,
+            internal,
+            corpus_path,
+            load_graph,
+            save_graph,
+            async_runtime,
+            backends,
+            encodings,
+            export_json,
+            interpreter_mode,
+            unwrap_strategy,
+            none_policy,
+            crash_panic_classes,
+            target_timeout_ms,
+            call_timeout_ms,
+        })
+    }
+
+    /// 命令行设置的调优参数，优先级高于fries.toml
+    pub(crate) fn tuning_overrides(&self) -> TuningConfig {
+        TuningConfig {
+            walk_steps: self.walk_steps,
+            cover_nodes: None,
+            bfs_max_len: self.max_len,
+            max_num: self.max_num,
+            max_len: self.max_len,
+            support_generic: None,
+            generic_candidates: None,
+            frequency_exponent: self.frequency_exponent,
+            population_size: self.population_size,
+            generations: self.generations,
+            beam_width: self.beam_width,
+            prioritize_unsafe: self.prioritize_unsafe.then_some(true),
+            keep_trivial_accessors: self.keep_trivial_accessors.then_some(true),
+            include: (!self.include.is_empty()).then(|| self.include.clone()),
+            exclude: (!self.exclude.is_empty()).then(|| self.exclude.clone()),
+        }
+    }
+}
+
+/// 正整数选项
+fn parse_count(
+    matches: &getopts::Matches,
+    diag: &rustc_errors::Handler,
+    option: &str,
+) -> Result<Option<usize>, i32> {
+    match matches.opt_str(option) {
+        Some(value) => match value.parse::<usize>() {
+            Ok(count) if count > 0 => Ok(Some(count)),
+            _ => {
+                diag.err(format!("--{} expects a positive integer, found `{}`", option, value));
+                Err(1)
+            }
+        },
+        None => Ok(None),
+    }
+}
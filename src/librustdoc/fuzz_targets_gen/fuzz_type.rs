@@ -1,34 +1,75 @@
 use crate::clean::{self, PrimitiveType};
 use crate::formats::cache::Cache;
+use crate::fuzz_targets_gen::api_util;
 use crate::fuzz_targets_gen::call_type::CallType;
+use crate::fuzz_targets_gen::collection_param::{self, CollectionKind};
+use crate::fuzz_targets_gen::emit_options::EmitOptions;
+use crate::fuzz_targets_gen::ffi;
 use crate::fuzz_targets_gen::impl_util::FullNameMap;
+use crate::fuzz_targets_gen::io_param;
+use crate::fuzz_targets_gen::path_param::{self, PathMode};
 use crate::fuzz_targets_gen::prelude_type::PreludeType;
+use crate::fuzz_targets_gen::sequence_serde;
+use crate::fuzz_targets_gen::time_param::{self, TimeKind};
 use rustc_data_structures::fx::FxHashMap;
 use rustc_hir::Mutability;
+use rustc_span::symbol::{kw, sym};
+use serde::{Deserialize, Serialize};
+
+/// 从fuzz输入构造的数组最多占多少字节
+static MAX_ARRAY_BYTES: usize = 1024;
 
 //如果构造一个fuzzable的变量
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub(crate) enum FuzzableCallType {
     NoFuzzable,
     Primitive(PrimitiveType),
+    CornerFloat(PrimitiveType), //--fuzz-float-corners时的f32和f64
     Tuple(Vec<Box<FuzzableCallType>>),
     Slice(Box<FuzzableCallType>),
-    Array(Box<FuzzableCallType>),
+    Array(Box<FuzzableCallType>, usize), //(元素, 长度)
     ConstRawPoiner(Box<FuzzableCallType>, clean::Type),
     MutRawPoiner(Box<FuzzableCallType>, clean::Type),
     STR,
     BorrowedRef(Box<FuzzableCallType>),
     MutBorrowedRef(Box<FuzzableCallType>),
     ToOption(Box<FuzzableCallType>),
+    CTypeAlias(PrimitiveType), //c_int之类的C类型别名，传参时用as转换
+    Enum(String, Vec<(String, Vec<(String, FuzzableCallType)>)>), //(枚举全路径, [(变体名, [(字段名, 字段)])])
+    Cow(Box<FuzzableCallType>, bool), //Cow<'_, str>和Cow<'_, [T]>，bool表示生命周期是'static
+    PathBuf(PathMode),                //Path和PathBuf，见path_param.rs
+    Reader,                           //impl Read、dyn Read，见io_param.rs
+    Writer,                           //impl Write、dyn Write
+    OsString,                         //OsStr和OsString，&OsStr借用构造出来的OsString
+    Time(TimeKind),                   //Duration、SystemTime、Instant，见time_param.rs
+    Collection(CollectionKind, Vec<FuzzableCallType>), //(集合种类, [键, 值])，见collection_param.rs
+    Vec(Box<FuzzableCallType>),       //Vec<T>，从&[T]复制出来
+    String,                           //String，从&str复制出来
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub(crate) enum FuzzableType {
     NoFuzzable,
-    Primitive(PrimitiveType),
+    Primitive(#[serde(with = "sequence_serde::primitive_type")] PrimitiveType),
     RefSlice(Box<FuzzableType>),
     RefStr,
     Tuple(Vec<Box<FuzzableType>>),
+    /// (元素, 长度)，定长数组，元素依次从后面的字节解码
+    Array(Box<FuzzableType>, usize),
+    /// f32或f64，前面多一个字节，较小的几个值选择NaN、±Inf、次正规数这些特殊值，见--fuzz-float-corners
+    CornerFloat(#[serde(with = "sequence_serde::primitive_type")] PrimitiveType),
+    /// (枚举的全路径, 变体)，用一个字节选择变体，变体的字段接着从后面的字节解码
+    Enum(String, Vec<FuzzableVariant>),
+    /// (集合种类, [键, 值])，set只有键。占一段动态长度的输入，每一项长度固定，逐项解码后插入
+    Collection(CollectionKind, Vec<FuzzableType>),
+}
+
+/// 可以从fuzz输入构造的枚举变体，字段都是定长的，所有变体的字段共用选择字节后面的同一段输入
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub(crate) struct FuzzableVariant {
+    pub(crate) name: String,
+    /// (字段名, 字段类型)，元组变体的字段名是`0`、`1`...
+    pub(crate) fields: Vec<(String, FuzzableType)>,
 }
 
 impl FuzzableCallType {
@@ -39,6 +80,9 @@ pub(crate) fn generate_fuzzable_type_and_call_type(&self) -> (FuzzableType, Call
             FuzzableCallType::Primitive(primitive) => {
                 (FuzzableType::Primitive(primitive.clone()), CallType::_DirectCall)
             }
+            FuzzableCallType::CTypeAlias(primitive) => {
+                (FuzzableType::Primitive(primitive.clone()), CallType::_AsConvert("_".to_string()))
+            }
             FuzzableCallType::Tuple(types) => {
                 let mut fuzzable_types = Vec::new();
                 for type_ in types {
@@ -64,6 +108,17 @@ pub(crate) fn generate_fuzzable_type_and_call_type(&self) -> (FuzzableType, Call
                 } else if let CallType::_NotCompatible = inner_call_type {
                     return (FuzzableType::NoFuzzable, CallType::_NotCompatible);
                 }
+                //指向基本类型的裸指针，用fuzz输入构造一段缓冲区，把缓冲区的指针传进去
+                if let (
+                    FuzzableType::Primitive(_),
+                    CallType::_DirectCall | CallType::_AsConvert(..),
+                ) = (&fuzzable_type, &inner_call_type)
+                {
+                    return (
+                        FuzzableType::RefSlice(Box::new(fuzzable_type)),
+                        CallType::_ConstRawPointer(Box::new(inner_call_type), type_.clone()),
+                    );
+                }
                 return (
                     fuzzable_type,
                     CallType::_ConstRawPointer(Box::new(inner_call_type), type_.clone()),
@@ -77,6 +132,17 @@ pub(crate) fn generate_fuzzable_type_and_call_type(&self) -> (FuzzableType, Call
                 } else if let CallType::_NotCompatible = inner_call_type {
                     return (FuzzableType::NoFuzzable, CallType::_NotCompatible);
                 }
+                //指向基本类型的裸指针，用fuzz输入构造一段缓冲区，把缓冲区的指针传进去
+                if let (
+                    FuzzableType::Primitive(_),
+                    CallType::_DirectCall | CallType::_AsConvert(..),
+                ) = (&fuzzable_type, &inner_call_type)
+                {
+                    return (
+                        FuzzableType::RefSlice(Box::new(fuzzable_type)),
+                        CallType::_MutRawPointer(Box::new(inner_call_type), type_.clone()),
+                    );
+                }
                 return (
                     fuzzable_type,
                     CallType::_MutRawPointer(Box::new(inner_call_type), type_.clone()),
@@ -93,6 +159,10 @@ pub(crate) fn generate_fuzzable_type_and_call_type(&self) -> (FuzzableType, Call
                     } else if let CallType::_NotCompatible = inner_call_type {
                         return (FuzzableType::NoFuzzable, CallType::_NotCompatible);
                     }
+                    //切片是直接借用解码出来的数据，元素没法再转换，&[String]、&[Vec<u8>]这样的不行
+                    if inner_call_type != CallType::_DirectCall {
+                        return (FuzzableType::NoFuzzable, CallType::_NotCompatible);
+                    }
                     return (
                         FuzzableType::RefSlice(Box::new(fuzzable_type)),
                         CallType::_DirectCall,
@@ -132,7 +202,149 @@ pub(crate) fn generate_fuzzable_type_and_call_type(&self) -> (FuzzableType, Call
                 }
                 return (fuzzable_type, CallType::_ToOption(Box::new(inner_call_type)));
             }
-            FuzzableCallType::Array(_) | FuzzableCallType::Slice(_) => {
+            FuzzableCallType::Enum(type_name, variants) => {
+                let mut fuzzable_variants = Vec::new();
+                for (variant_name, fields) in variants {
+                    let mut fuzzable_fields = Vec::new();
+                    for (field_name, field_call_type) in fields {
+                        let (fuzzable_type, call_type) =
+                            field_call_type.generate_fuzzable_type_and_call_type();
+                        //和元组一样字段只能直接传入，长度可变的字段没法和别的变体共用同一段输入
+                        if let FuzzableType::NoFuzzable = fuzzable_type {
+                            return (FuzzableType::NoFuzzable, CallType::_NotCompatible);
+                        }
+                        if call_type != CallType::_DirectCall || !fuzzable_type._is_fixed_length() {
+                            return (FuzzableType::NoFuzzable, CallType::_NotCompatible);
+                        }
+                        fuzzable_fields.push((field_name.clone(), fuzzable_type));
+                    }
+                    fuzzable_variants.push(FuzzableVariant {
+                        name: variant_name.clone(),
+                        fields: fuzzable_fields,
+                    });
+                }
+                return (
+                    FuzzableType::Enum(type_name.clone(), fuzzable_variants),
+                    CallType::_DirectCall,
+                );
+            }
+            FuzzableCallType::Array(inner_fuzzable_call_type, length) => {
+                let (fuzzable_type, inner_call_type) =
+                    inner_fuzzable_call_type.generate_fuzzable_type_and_call_type();
+                //只支持基本类型的数组，太长的数组会占掉大部分输入
+                if !matches!(
+                    fuzzable_type,
+                    FuzzableType::Primitive(_) | FuzzableType::CornerFloat(_)
+                ) || inner_call_type != CallType::_DirectCall
+                    || fuzzable_type._min_length() * length > MAX_ARRAY_BYTES
+                {
+                    return (FuzzableType::NoFuzzable, CallType::_NotCompatible);
+                }
+                return (
+                    FuzzableType::Array(Box::new(fuzzable_type), *length),
+                    CallType::_DirectCall,
+                );
+            }
+            FuzzableCallType::Cow(inner_fuzzable_call_type, is_static) => {
+                //和&str、&[T]一样从输入构造，再包装成Cow
+                let (fuzzable_type, inner_call_type) = match &**inner_fuzzable_call_type {
+                    FuzzableCallType::STR => {
+                        FuzzableCallType::STR.generate_fuzzable_type_and_call_type()
+                    }
+                    FuzzableCallType::Slice(_) => {
+                        FuzzableCallType::BorrowedRef(inner_fuzzable_call_type.clone())
+                            .generate_fuzzable_type_and_call_type()
+                    }
+                    _ => return (FuzzableType::NoFuzzable, CallType::_NotCompatible),
+                };
+                if inner_call_type != CallType::_DirectCall {
+                    return (FuzzableType::NoFuzzable, CallType::_NotCompatible);
+                }
+                //借用的输入活不到'static，只能复制一份
+                let call_type = if *is_static {
+                    CallType::_CowOwned(Box::new(inner_call_type))
+                } else {
+                    CallType::_CowBorrowed(Box::new(inner_call_type))
+                };
+                return (fuzzable_type, call_type);
+            }
+            FuzzableCallType::PathBuf(path_mode) => match path_mode {
+                PathMode::TempFile => (
+                    FuzzableType::RefSlice(Box::new(FuzzableType::Primitive(PrimitiveType::U8))),
+                    CallType::_TempFilePath(Box::new(CallType::_DirectCall)),
+                ),
+                PathMode::Str => {
+                    (FuzzableType::RefStr, CallType::_ToPathBuf(Box::new(CallType::_DirectCall)))
+                }
+                PathMode::Off => (FuzzableType::NoFuzzable, CallType::_NotCompatible),
+            },
+            FuzzableCallType::Reader => (
+                FuzzableType::RefSlice(Box::new(FuzzableType::Primitive(PrimitiveType::U8))),
+                CallType::_ToCursor(Box::new(CallType::_DirectCall)),
+            ),
+            //空元组不占输入
+            FuzzableCallType::Writer => (FuzzableType::Tuple(Vec::new()), CallType::_NewWriter),
+            FuzzableCallType::CornerFloat(primitive) => {
+                (FuzzableType::CornerFloat(primitive.clone()), CallType::_DirectCall)
+            }
+            FuzzableCallType::Time(kind) => match kind {
+                TimeKind::Duration => (
+                    FuzzableType::Primitive(PrimitiveType::U64),
+                    CallType::_ToDuration(Box::new(CallType::_DirectCall)),
+                ),
+                TimeKind::SystemTime => (
+                    FuzzableType::Primitive(PrimitiveType::U64),
+                    CallType::_ToSystemTime(Box::new(CallType::_DirectCall)),
+                ),
+                //空元组不占输入
+                TimeKind::Instant => (FuzzableType::Tuple(Vec::new()), CallType::_InstantNow),
+            },
+            FuzzableCallType::Collection(kind, item_call_types) => {
+                let mut item_types = Vec::new();
+                for item_call_type in item_call_types {
+                    let (fuzzable_type, call_type) =
+                        item_call_type.generate_fuzzable_type_and_call_type();
+                    //和枚举的字段一样只能直接传入，而且长度固定，这样才能按项切开输入
+                    if let FuzzableType::NoFuzzable = fuzzable_type {
+                        return (FuzzableType::NoFuzzable, CallType::_NotCompatible);
+                    }
+                    if call_type != CallType::_DirectCall || !fuzzable_type._is_fixed_length() {
+                        return (FuzzableType::NoFuzzable, CallType::_NotCompatible);
+                    }
+                    item_types.push(fuzzable_type);
+                }
+                //一项不占输入的话没法切开
+                if item_types.iter().map(|item_type| item_type._min_length()).sum::<usize>() == 0 {
+                    return (FuzzableType::NoFuzzable, CallType::_NotCompatible);
+                }
+                (FuzzableType::Collection(*kind, item_types), CallType::_DirectCall)
+            }
+            FuzzableCallType::String => {
+                (FuzzableType::RefStr, CallType::_ToOwned(Box::new(CallType::_DirectCall)))
+            }
+            FuzzableCallType::Vec(inner_fuzzable_call_type) => {
+                let (fuzzable_type, inner_call_type) =
+                    inner_fuzzable_call_type.generate_fuzzable_type_and_call_type();
+                if let FuzzableType::NoFuzzable = fuzzable_type {
+                    return (FuzzableType::NoFuzzable, CallType::_NotCompatible);
+                }
+                //元素在map的闭包里转换，只能是复制出来的值，不能借用闭包里的临时变量
+                if !matches!(
+                    inner_call_type,
+                    CallType::_DirectCall | CallType::_ToOwned(..) | CallType::_ToVec(..)
+                ) {
+                    return (FuzzableType::NoFuzzable, CallType::_NotCompatible);
+                }
+                (
+                    FuzzableType::RefSlice(Box::new(fuzzable_type)),
+                    CallType::_ToVec(Box::new(inner_call_type)),
+                )
+            }
+            FuzzableCallType::OsString => (
+                FuzzableType::RefSlice(Box::new(FuzzableType::Primitive(PrimitiveType::U8))),
+                CallType::_ToOsString(Box::new(CallType::_DirectCall)),
+            ),
+            FuzzableCallType::Slice(_) => {
                 return (FuzzableType::NoFuzzable, CallType::_NotCompatible);
             } //_ => {
               //    return (FuzzableType::NoFuzzable, CallType::_NotCompatible);
@@ -146,6 +358,7 @@ pub(crate) fn _is_fixed_length(&self) -> bool {
         match self {
             FuzzableType::NoFuzzable => true,
             FuzzableType::Primitive(_) => true,
+            FuzzableType::CornerFloat(_) => true,
             FuzzableType::RefSlice(_) => false,
             FuzzableType::RefStr => false,
             FuzzableType::Tuple(inner_fuzzables) => {
@@ -156,6 +369,10 @@ pub(crate) fn _is_fixed_length(&self) -> bool {
                 }
                 return true;
             }
+            FuzzableType::Array(inner_fuzzable, _) => inner_fuzzable._is_fixed_length(),
+            //变体的字段都是定长的
+            FuzzableType::Enum(..) => true,
+            FuzzableType::Collection(..) => false,
         }
     }
 
@@ -185,6 +402,10 @@ pub(crate) fn _min_length(&self) -> usize {
                     _ => 0,
                 }
             }
+            //选择特殊值的一个字节
+            FuzzableType::CornerFloat(primitive_type) => {
+                1 + FuzzableType::Primitive(primitive_type.clone())._min_length()
+            }
             FuzzableType::RefSlice(inner_fuzzable) => inner_fuzzable._min_length(),
             FuzzableType::RefStr => 1,
             FuzzableType::Tuple(inner_fuzzables) => {
@@ -194,6 +415,22 @@ pub(crate) fn _min_length(&self) -> usize {
                 }
                 total_length
             }
+            FuzzableType::Array(inner_fuzzable, length) => inner_fuzzable._min_length() * length,
+            //选择变体的一个字节，加上字段最长的变体需要的字节
+            FuzzableType::Enum(_, variants) => {
+                let max_fields_length = variants
+                    .iter()
+                    .map(|variant| {
+                        variant.fields.iter().map(|(_, field)| field._min_length()).sum::<usize>()
+                    })
+                    .max()
+                    .unwrap_or(0);
+                1 + max_fields_length
+            }
+            //和切片一样至少有一项
+            FuzzableType::Collection(_, item_types) => {
+                item_types.iter().map(|item_type| item_type._min_length()).sum()
+            }
         }
     }
 
@@ -205,6 +442,7 @@ pub(crate) fn _fixed_part_length(&self) -> usize {
             match self {
                 FuzzableType::RefStr => 0,
                 FuzzableType::RefSlice(..) => 0,
+                FuzzableType::Collection(..) => 0,
                 FuzzableType::Tuple(inner_fuzzables) => {
                     let mut fixed_part = 0;
                     for inner_fuzzable in inner_fuzzables {
@@ -226,6 +464,7 @@ pub(crate) fn _dynamic_length_param_number(&self) -> usize {
             match self {
                 FuzzableType::RefStr => 1,
                 FuzzableType::RefSlice(..) => 1,
+                FuzzableType::Collection(..) => 1,
                 FuzzableType::Tuple(inner_fuzzables) => {
                     let mut inner_numbers = 0;
                     for inner_fuzzable in inner_fuzzables {
@@ -239,19 +478,18 @@ pub(crate) fn _dynamic_length_param_number(&self) -> usize {
         }
     }
 
-    //多个可变长的维度，例如&[&str], &[&[u8]]
-    pub(crate) fn _is_multiple_dynamic_length(&self) -> bool {
+    //没法解码的多个可变长的维度。&[&str]、&[&[u8]]这样的两层可以按长度前缀逐个解码，
+    //再往里嵌套，或者元素里混着定长的部分（比如&[(u8, &str)]）就不行了
+    pub(crate) fn _is_too_deep_dynamic_length(&self) -> bool {
         match self {
-            FuzzableType::RefSlice(inner_fuzzable) => {
-                if !inner_fuzzable._is_fixed_length() {
-                    true
-                } else {
-                    false
-                }
-            }
+            FuzzableType::RefSlice(inner_fuzzable) => match &**inner_fuzzable {
+                FuzzableType::RefStr => false,
+                FuzzableType::RefSlice(element) => !element._is_fixed_length(),
+                inner_fuzzable => !inner_fuzzable._is_fixed_length(),
+            },
             FuzzableType::Tuple(inner_fuzzables) => {
                 for inner_fuzzable in inner_fuzzables {
-                    if inner_fuzzable._is_multiple_dynamic_length() {
+                    if inner_fuzzable._is_too_deep_dynamic_length() {
                         return true;
                     }
                 }
@@ -264,7 +502,9 @@ pub(crate) fn _is_multiple_dynamic_length(&self) -> bool {
     pub(crate) fn _to_type_string(&self) -> String {
         match self {
             FuzzableType::NoFuzzable => "nofuzzable".to_string(),
-            FuzzableType::Primitive(primitive) => primitive.as_sym().to_string(),
+            FuzzableType::Primitive(primitive) | FuzzableType::CornerFloat(primitive) => {
+                primitive.as_sym().to_string()
+            }
             FuzzableType::RefSlice(inner_) => {
                 let inner_string = inner_._to_type_string();
                 let mut res = "&[".to_string();
@@ -292,6 +532,17 @@ pub(crate) fn _to_type_string(&self) -> String {
                 res.push_str(")");
                 res
             }
+            FuzzableType::Array(inner_type, length) => {
+                format!("[{}; {}]", inner_type._to_type_string(), length)
+            }
+            FuzzableType::Enum(type_name, _) => type_name.clone(),
+            FuzzableType::Collection(kind, item_types) => {
+                let item_type_strings = item_types
+                    .iter()
+                    .map(|item_type| item_type._to_type_string())
+                    .collect::<Vec<_>>();
+                format!("{}<{}>", kind.path_name(), item_type_strings.join(", "))
+            }
         }
     }
 }
@@ -301,6 +552,7 @@ pub(crate) fn fuzzable_call_type(
     ty_: &clean::Type,
     cache: &Cache,
     full_name_map: &FullNameMap,
+    emit_options: &EmitOptions,
     substitution: Option<&FxHashMap<String, clean::Type>>, //替换泛型的类型
 ) -> FuzzableCallType {
     match ty_ {
@@ -308,12 +560,58 @@ pub(crate) fn fuzzable_call_type(
             let prelude_type = PreludeType::from_type(ty_, cache, full_name_map);
             //result类型的变量不应该作为fuzzable的变量。只考虑作为别的函数的返回值
             match &prelude_type {
-                PreludeType::NotPrelude(..) | PreludeType::PreludeResult { .. } => {
-                    FuzzableCallType::NoFuzzable
+                PreludeType::NotPrelude(..) => {
+                    if let Some(enum_call_type) =
+                        enum_call_type(ty_, cache, full_name_map, emit_options, substitution)
+                    {
+                        return enum_call_type;
+                    }
+                    if let Some(cow_call_type) =
+                        cow_call_type(ty_, cache, full_name_map, emit_options, substitution)
+                    {
+                        return cow_call_type;
+                    }
+                    if is_os_string(ty_) {
+                        return FuzzableCallType::OsString;
+                    }
+                    if let Some(time_call_type) =
+                        time_param::time_call_type(ty_, cache, full_name_map)
+                    {
+                        return time_call_type;
+                    }
+                    if let Some(owned_call_type) =
+                        owned_call_type(ty_, cache, full_name_map, emit_options, substitution)
+                    {
+                        return owned_call_type;
+                    }
+                    if let Some(collection_call_type) = collection_param::collection_call_type(
+                        ty_,
+                        cache,
+                        full_name_map,
+                        emit_options,
+                        substitution,
+                    ) {
+                        return collection_call_type;
+                    }
+                    if let Some(path_call_type) =
+                        path_param::path_call_type(ty_, emit_options.path_mode)
+                    {
+                        return path_call_type;
+                    }
+                    match ffi::c_type_alias_primitive(ty_) {
+                        Some(primitive_type) => FuzzableCallType::CTypeAlias(primitive_type),
+                        None => FuzzableCallType::NoFuzzable,
+                    }
                 }
+                PreludeType::PreludeResult { .. } => FuzzableCallType::NoFuzzable,
                 PreludeType::PreludeOption(inner_type_) => {
-                    let inner_fuzzable_call_type =
-                        fuzzable_call_type(inner_type_, cache, full_name_map, substitution);
+                    let inner_fuzzable_call_type = fuzzable_call_type(
+                        inner_type_,
+                        cache,
+                        full_name_map,
+                        emit_options,
+                        substitution,
+                    );
                     match inner_fuzzable_call_type {
                         FuzzableCallType::NoFuzzable => {
                             return FuzzableCallType::NoFuzzable;
@@ -331,18 +629,28 @@ pub(crate) fn fuzzable_call_type(
 
             match substitution {
                 Some(substi) => {
-                    //目前泛型替换类型只支持基本类型
+                    //替换的类型来自generic_candidate.rs里的候选类型
                     let typ = match substi.get(&s.to_string()) {
                         Some(ty) => ty.to_owned(),
                         None => return FuzzableCallType::NoFuzzable,
                     };
-                    assert!(if let clean::Type::Primitive(_) = typ { true } else { false });
-                    return fuzzable_call_type(&typ, cache, full_name_map, substitution);
+                    return fuzzable_call_type(
+                        &typ,
+                        cache,
+                        full_name_map,
+                        emit_options,
+                        substitution,
+                    );
                     //这里是None也行
                 }
                 None => FuzzableCallType::NoFuzzable,
             }
         }
+        clean::Type::Primitive(primitive_type @ (PrimitiveType::F32 | PrimitiveType::F64))
+            if emit_options.float_corners =>
+        {
+            FuzzableCallType::CornerFloat(primitive_type.clone())
+        }
         clean::Type::Primitive(primitive_type) => {
             FuzzableCallType::Primitive(primitive_type.clone())
         }
@@ -352,8 +660,13 @@ pub(crate) fn fuzzable_call_type(
         clean::Type::Tuple(inner_types) => {
             let mut vec = Vec::new();
             for inner_type in inner_types {
-                let inner_fuzzable =
-                    fuzzable_call_type(inner_type, cache, full_name_map, substitution);
+                let inner_fuzzable = fuzzable_call_type(
+                    inner_type,
+                    cache,
+                    full_name_map,
+                    emit_options,
+                    substitution,
+                );
                 match inner_fuzzable {
                     FuzzableCallType::NoFuzzable => {
                         return FuzzableCallType::NoFuzzable;
@@ -367,7 +680,8 @@ pub(crate) fn fuzzable_call_type(
         }
         clean::Type::Slice(inner_type) => {
             let inner_ty_ = &**inner_type;
-            let inner_fuzzable = fuzzable_call_type(inner_ty_, cache, full_name_map, substitution);
+            let inner_fuzzable =
+                fuzzable_call_type(inner_ty_, cache, full_name_map, emit_options, substitution);
             match inner_fuzzable {
                 FuzzableCallType::NoFuzzable => {
                     return FuzzableCallType::NoFuzzable;
@@ -377,21 +691,27 @@ pub(crate) fn fuzzable_call_type(
                 }
             }
         }
-        clean::Type::Array(inner_type, ..) => {
+        clean::Type::Array(inner_type, length) => {
+            //长度是常量泛型或者表达式的时候没法确定
+            let Ok(length) = length.parse::<usize>() else {
+                return FuzzableCallType::NoFuzzable;
+            };
             let inner_ty_ = &**inner_type;
-            let inner_fuzzable = fuzzable_call_type(inner_ty_, cache, full_name_map, substitution);
+            let inner_fuzzable =
+                fuzzable_call_type(inner_ty_, cache, full_name_map, emit_options, substitution);
             match inner_fuzzable {
                 FuzzableCallType::NoFuzzable => {
                     return FuzzableCallType::NoFuzzable;
                 }
                 _ => {
-                    return FuzzableCallType::Array(Box::new(inner_fuzzable));
+                    return FuzzableCallType::Array(Box::new(inner_fuzzable), length);
                 }
             }
         }
         clean::Type::RawPointer(mutability, type_) => {
             let inner_type = &**type_;
-            let inner_fuzzable = fuzzable_call_type(inner_type, cache, full_name_map, substitution);
+            let inner_fuzzable =
+                fuzzable_call_type(inner_type, cache, full_name_map, emit_options, substitution);
             match inner_fuzzable {
                 FuzzableCallType::NoFuzzable => {
                     return FuzzableCallType::NoFuzzable;
@@ -427,7 +747,8 @@ pub(crate) fn fuzzable_call_type(
                 }
                 return FuzzableCallType::STR;
             }
-            let inner_fuzzable = fuzzable_call_type(inner_type, cache, full_name_map, substitution);
+            let inner_fuzzable =
+                fuzzable_call_type(inner_type, cache, full_name_map, emit_options, substitution);
             match inner_fuzzable {
                 FuzzableCallType::NoFuzzable => {
                     return FuzzableCallType::NoFuzzable;
@@ -445,14 +766,126 @@ pub(crate) fn fuzzable_call_type(
         clean::Type::QPath { .. } => {
             return FuzzableCallType::NoFuzzable;
         }
+        //替换成ImplTrait的Read/Write泛型参数，见io_param.rs
         clean::Type::ImplTrait(..) => {
-            return FuzzableCallType::NoFuzzable;
+            return io_param::io_call_type(ty_).unwrap_or(FuzzableCallType::NoFuzzable);
         }
         clean::Type::Infer => {
             return FuzzableCallType::NoFuzzable;
         }
         clean::Type::DynTrait(_, _) => {
-            return FuzzableCallType::NoFuzzable;
+            return io_param::io_call_type(ty_).unwrap_or(FuzzableCallType::NoFuzzable);
+        }
+    }
+}
+
+/// 记录在full_name_map里的枚举，每个变体的字段都要能fuzz，不是枚举的话返回None
+fn enum_call_type(
+    ty_: &clean::Type,
+    cache: &Cache,
+    full_name_map: &FullNameMap,
+    emit_options: &EmitOptions,
+    substitution: Option<&FxHashMap<String, clean::Type>>,
+) -> Option<FuzzableCallType> {
+    let clean::Type::Path { path } = ty_ else {
+        return None;
+    };
+    let variants = full_name_map._get_enum_variants(path.def_id())?;
+    let type_name = full_name_map._get_full_name(path.def_id())?;
+    let mut variant_call_types = Vec::new();
+    for variant in variants {
+        let mut field_call_types = Vec::new();
+        for (field_name, field_type) in &variant.fields {
+            let field_call_type =
+                fuzzable_call_type(field_type, cache, full_name_map, emit_options, substitution);
+            if let FuzzableCallType::NoFuzzable = field_call_type {
+                return Some(FuzzableCallType::NoFuzzable);
+            }
+            field_call_types.push((field_name.clone(), field_call_type));
         }
+        variant_call_types.push((variant.name.clone(), field_call_types));
     }
+    Some(FuzzableCallType::Enum(type_name.clone(), variant_call_types))
+}
+
+/// std::borrow::Cow<'a, str>和Cow<'a, [T]>，其他的Cow返回None。
+/// 参数元素的类型从输入构造，B不是str或者切片的Cow（比如Cow<'a, Path>）不能fuzz
+fn cow_call_type(
+    ty_: &clean::Type,
+    cache: &Cache,
+    full_name_map: &FullNameMap,
+    emit_options: &EmitOptions,
+    substitution: Option<&FxHashMap<String, clean::Type>>,
+) -> Option<FuzzableCallType> {
+    let clean::Type::Path { path } = ty_ else {
+        return None;
+    };
+    if path.last() != sym::Cow || path.def_id().is_local() {
+        return None;
+    }
+    let Some(clean::GenericArgs::AngleBracketed { args, .. }) =
+        path.segments.last().map(|segment| &segment.args)
+    else {
+        return None;
+    };
+    let is_static = args.iter().any(|arg| {
+        matches!(arg, clean::GenericArg::Lifetime(lifetime) if lifetime.0 == kw::StaticLifetime)
+    });
+    let borrowed_type = args.iter().find_map(|arg| match arg {
+        clean::GenericArg::Type(type_) => Some(type_),
+        _ => None,
+    })?;
+    let inner_call_type = match borrowed_type {
+        clean::Type::Primitive(PrimitiveType::Str) => FuzzableCallType::STR,
+        clean::Type::Slice(_) => {
+            fuzzable_call_type(borrowed_type, cache, full_name_map, emit_options, substitution)
+        }
+        _ => FuzzableCallType::NoFuzzable,
+    };
+    if let FuzzableCallType::NoFuzzable = inner_call_type {
+        return Some(FuzzableCallType::NoFuzzable);
+    }
+    Some(FuzzableCallType::Cow(Box::new(inner_call_type), is_static))
+}
+
+/// Vec<T>和String，其他类型返回None
+fn owned_call_type(
+    ty_: &clean::Type,
+    cache: &Cache,
+    full_name_map: &FullNameMap,
+    emit_options: &EmitOptions,
+    substitution: Option<&FxHashMap<String, clean::Type>>,
+) -> Option<FuzzableCallType> {
+    let clean::Type::Path { path } = ty_ else {
+        return None;
+    };
+    match api_util::_def_path_name(path.def_id(), cache, full_name_map)?.as_str() {
+        "String" => Some(FuzzableCallType::String),
+        "Vec" => {
+            let Some(clean::GenericArgs::AngleBracketed { args, .. }) =
+                path.segments.last().map(|segment| &segment.args)
+            else {
+                return Some(FuzzableCallType::NoFuzzable);
+            };
+            //带allocator参数的Vec不算
+            let [clean::GenericArg::Type(element_type)] = &args[..] else {
+                return Some(FuzzableCallType::NoFuzzable);
+            };
+            let element_call_type =
+                fuzzable_call_type(element_type, cache, full_name_map, emit_options, substitution);
+            if let FuzzableCallType::NoFuzzable = element_call_type {
+                return Some(FuzzableCallType::NoFuzzable);
+            }
+            Some(FuzzableCallType::Vec(Box::new(element_call_type)))
+        }
+        _ => None,
+    }
+}
+
+/// std::ffi::OsStr或者OsString
+fn is_os_string(ty_: &clean::Type) -> bool {
+    let clean::Type::Path { path } = ty_ else {
+        return false;
+    };
+    matches!(path.last(), sym::OsStr | sym::OsString) && !path.def_id().is_local()
 }
@@ -0,0 +1,472 @@
+//! 泛型参数的候选类型
+//! 以前所有的泛型参数都替换成i32，大部分泛型API得到的实例没有意义甚至编译不过。
+//! 现在每个泛型参数从一组候选类型里挑选满足trait bound的类型，每种组合注册一个ApiFunction实例。
+//! 候选类型可以在fries.toml里用`generic_candidates = ["u8", "&str", "Vec<u8>"]`配置，
+//! 支持基本类型、&T、[T]、String和Vec<T>；String和Vec需要在文档的类型路径里出现过才能构造出来
+//! 有tcx的时候（in-process模式）用rustc的trait solver判断候选类型实现了哪些trait，见resolve_traits；
+//! 没有tcx或者trait带有别的泛型参数的时候，按照trait的名字（以及简单的泛型参数，比如`AsRef<[u8]>`）查表，
+//! 查不到的bound认为不满足
+//! 参数位置的impl Trait（比如`impl AsRef<str>`）当作带bound的匿名泛型参数处理，见name_impl_trait_params
+
+use crate::clean::{self, PrimitiveType};
+use crate::formats::cache::Cache;
+use crate::formats::item_type::ItemType;
+use crate::fuzz_targets_gen::api_function::ApiFunction;
+use crate::fuzz_targets_gen::diagnostics::{self, WarningKind};
+use crate::fuzz_targets_gen::io_param;
+use itertools::Itertools;
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
+use rustc_hir::Mutability;
+use rustc_hir::def::{DefKind, Res};
+use rustc_hir::def_id::DefId;
+use rustc_infer::infer::TyCtxtInferExt;
+use rustc_middle::ty::{self, Ty, TyCtxt};
+use rustc_span::Symbol;
+use rustc_trait_selection::infer::InferCtxtExt;
+use thin_vec::ThinVec;
+
+/// 默认的候选类型，i32放在最前面，和以前的行为保持一致
+pub(crate) static DEFAULT_GENERIC_CANDIDATES: [&'static str; 9] =
+    ["i32", "u8", "u64", "bool", "char", "&str", "&[u8]", "String", "Vec<u8>"];
+
+/// 一个泛型函数最多注册多少个实例
+static MAX_INSTANTIATIONS: usize = 16;
+
+static STRING_PATH: &'static str = "alloc::string::String";
+static VEC_PATH: &'static str = "alloc::vec::Vec";
+
+/// 所有候选类型都实现的trait
+static COMMON_TRAITS: [&'static str; 9] =
+    ["Sized", "Send", "Sync", "Unpin", "Clone", "Debug", "PartialEq", "PartialOrd", "Default"];
+
+/// 基本类型里整数、bool和char额外实现的trait
+static PRIMITIVE_TRAITS: [&'static str; 7] =
+    ["Copy", "Eq", "Ord", "Hash", "Display", "ToString", "FromStr"];
+
+/// 浮点数额外实现的trait
+static FLOAT_TRAITS: [&'static str; 4] = ["Copy", "Display", "ToString", "FromStr"];
+
+#[derive(Debug, Clone)]
+pub(crate) struct GenericCandidate {
+    pub(crate) type_: clean::Type,
+    /// 按名字查表时这个类型实现的trait，见satisfies
+    traits: Vec<String>,
+    /// trait solver算出来的这个类型实现的trait，None表示没有用trait solver
+    implemented_traits: Option<FxHashSet<DefId>>,
+}
+
+impl GenericCandidate {
+    fn new(type_: clean::Type) -> Self {
+        GenericCandidate { traits: traits_of(&type_), type_, implemented_traits: None }
+    }
+
+    /// 类型是否满足所有的bound，`?Sized`和生命周期的约束不用管
+    fn satisfies(&self, bounds: &[&clean::GenericBound]) -> bool {
+        bounds.iter().all(|bound| match bound {
+            clean::GenericBound::TraitBound(_, rustc_hir::TraitBoundModifier::Maybe) => true,
+            clean::GenericBound::TraitBound(poly_trait, _) => {
+                let trait_ = &poly_trait.trait_;
+                match &self.implemented_traits {
+                    //trait solver只算了没有其他泛型参数的trait
+                    Some(implemented_traits)
+                        if trait_.generics().map_or(true, |args| args.is_empty()) =>
+                    {
+                        implemented_traits.contains(&trait_.def_id())
+                    }
+                    _ => self.traits.contains(&trait_name(trait_)),
+                }
+            }
+            clean::GenericBound::Outlives(..) => true,
+        })
+    }
+}
+
+/// 把配置里的名字解析成候选类型，解析不了的给出警告并跳过
+pub(crate) fn parse_candidates(names: &[String], cache: &Cache) -> Vec<GenericCandidate> {
+    let mut res = Vec::new();
+    for name in names {
+        match parse_type(name.trim(), cache) {
+            Some(type_) => res.push(GenericCandidate::new(type_)),
+            None => diagnostics::warn(
+                WarningKind::Degraded,
+                None,
+                format!("unsupported generic candidate `{}`, ignored", name),
+            ),
+        }
+    }
+    res
+}
+
+pub(crate) fn default_candidates(cache: &Cache) -> Vec<GenericCandidate> {
+    let names = DEFAULT_GENERIC_CANDIDATES.iter().map(|name| name.to_string()).collect_vec();
+    //String和Vec在文档里没出现过的时候构造不出来，这时候不用警告
+    names.iter().filter_map(|name| Some(GenericCandidate::new(parse_type(name, cache)?))).collect()
+}
+
+/// 用rustc的trait solver算出每个候选类型实现了文档里出现的哪些trait，
+/// 只考虑除了Self之外没有泛型参数的trait，其他trait的bound仍然按名字查表
+pub(crate) fn resolve_traits<'tcx>(
+    candidates: &mut [GenericCandidate],
+    tcx: TyCtxt<'tcx>,
+    cache: &Cache,
+) {
+    let traits = cache
+        .paths
+        .iter()
+        .chain(cache.external_paths.iter())
+        .filter(|(_, (_, item_type))| *item_type == ItemType::Trait)
+        .map(|(def_id, _)| *def_id)
+        .filter(|def_id| tcx.generics_of(*def_id).count() == 1)
+        .collect_vec();
+    let infcx = tcx.infer_ctxt().build();
+    let param_env = ty::ParamEnv::reveal_all();
+    for candidate in candidates {
+        let Some(candidate_ty) = middle_type(&candidate.type_, tcx) else {
+            continue;
+        };
+        let implemented_traits = traits
+            .iter()
+            .filter(|trait_def_id| {
+                infcx
+                    .type_implements_trait(**trait_def_id, [candidate_ty], param_env)
+                    .must_apply_modulo_regions()
+            })
+            .copied()
+            .collect();
+        candidate.implemented_traits = Some(implemented_traits);
+    }
+}
+
+/// 具体类型是否满足bound，用来检查impl块的泛型参数实例化成的类型，见impl_util::instantiate_generic_impls
+/// 和resolve_traits一样，没有其他泛型参数的trait用trait solver判断，其他的按名字查表
+pub(crate) fn type_satisfies(
+    type_: &clean::Type,
+    bounds: &[&clean::GenericBound],
+    tcx: TyCtxt<'_>,
+) -> bool {
+    let mut candidate = GenericCandidate::new(type_.clone());
+    if let Some(candidate_ty) = middle_type(type_, tcx) {
+        let infcx = tcx.infer_ctxt().build();
+        let param_env = ty::ParamEnv::reveal_all();
+        let implemented_traits = bounds
+            .iter()
+            .filter_map(|bound| bound.get_trait_path())
+            .map(|trait_| trait_.def_id())
+            .filter(|trait_def_id| tcx.generics_of(*trait_def_id).count() == 1)
+            .filter(|trait_def_id| {
+                infcx
+                    .type_implements_trait(*trait_def_id, [candidate_ty], param_env)
+                    .must_apply_modulo_regions()
+            })
+            .collect();
+        candidate.implemented_traits = Some(implemented_traits);
+    }
+    candidate.satisfies(bounds)
+}
+
+/// 候选类型对应的rustc类型，引用的生命周期都是'static，Vec这类类型剩下的泛型参数用默认值
+fn middle_type<'tcx>(type_: &clean::Type, tcx: TyCtxt<'tcx>) -> Option<Ty<'tcx>> {
+    let res = match type_ {
+        clean::Type::Primitive(primitive_type) => match primitive_type {
+            PrimitiveType::Isize => tcx.types.isize,
+            PrimitiveType::I8 => tcx.types.i8,
+            PrimitiveType::I16 => tcx.types.i16,
+            PrimitiveType::I32 => tcx.types.i32,
+            PrimitiveType::I64 => tcx.types.i64,
+            PrimitiveType::I128 => tcx.types.i128,
+            PrimitiveType::Usize => tcx.types.usize,
+            PrimitiveType::U8 => tcx.types.u8,
+            PrimitiveType::U16 => tcx.types.u16,
+            PrimitiveType::U32 => tcx.types.u32,
+            PrimitiveType::U64 => tcx.types.u64,
+            PrimitiveType::U128 => tcx.types.u128,
+            PrimitiveType::F32 => tcx.types.f32,
+            PrimitiveType::F64 => tcx.types.f64,
+            PrimitiveType::Char => tcx.types.char,
+            PrimitiveType::Bool => tcx.types.bool,
+            PrimitiveType::Str => tcx.types.str_,
+            PrimitiveType::Unit => tcx.mk_unit(),
+            _ => return None,
+        },
+        clean::Type::BorrowedRef { mutability: Mutability::Not, type_, .. } => {
+            tcx.mk_imm_ref(tcx.lifetimes.re_static, middle_type(type_, tcx)?)
+        }
+        clean::Type::Slice(inner) => tcx.mk_slice(middle_type(inner, tcx)?),
+        clean::Type::Path { path } => {
+            let def_id = path.def_id();
+            //类型别名、外部类型没有adt_def
+            if !matches!(tcx.def_kind(def_id), DefKind::Struct | DefKind::Enum | DefKind::Union) {
+                return None;
+            }
+            let args = path
+                .generics()
+                .unwrap_or_default()
+                .into_iter()
+                .map(|arg| middle_type(arg, tcx))
+                .collect::<Option<Vec<_>>>()?;
+            //args里只有类型参数，按照类型参数的顺序对应
+            let mut type_index = 0;
+            let substs = ty::InternalSubsts::for_item(tcx, def_id, |param, substs| {
+                if let ty::GenericParamDefKind::Lifetime = param.kind {
+                    return tcx.lifetimes.re_static.into();
+                }
+                type_index += 1;
+                match args.get(type_index - 1) {
+                    Some(arg) => (*arg).into(),
+                    None => tcx.bound_type_of(param.def_id).subst(tcx, substs).into(),
+                }
+            });
+            tcx.mk_adt(tcx.adt_def(def_id), substs)
+        }
+        _ => return None,
+    };
+    Some(res)
+}
+
+fn parse_type(name: &str, cache: &Cache) -> Option<clean::Type> {
+    //候选类型是按值传进去的，只考虑不可变引用
+    if let Some(inner) = name.strip_prefix('&') {
+        let type_ = parse_type(inner.trim(), cache)?;
+        return Some(clean::Type::BorrowedRef {
+            lifetime: None,
+            mutability: Mutability::Not,
+            type_: Box::new(type_),
+        });
+    }
+    if let Some(inner) = name.strip_prefix('[').and_then(|inner| inner.strip_suffix(']')) {
+        return Some(clean::Type::Slice(Box::new(parse_type(inner.trim(), cache)?)));
+    }
+    if name == "String" {
+        return path_type(STRING_PATH, Vec::new(), cache);
+    }
+    if let Some(inner) = name.strip_prefix("Vec<").and_then(|inner| inner.strip_suffix('>')) {
+        let inner = parse_type(inner.trim(), cache)?;
+        return path_type(VEC_PATH, vec![inner], cache);
+    }
+    PrimitiveType::from_symbol(Symbol::intern(name)).map(clean::Type::Primitive)
+}
+
+/// 在文档缓存里按全路径找到类型的DefId，再构造出路径类型
+pub(crate) fn path_type(
+    full_path: &str,
+    args: Vec<clean::Type>,
+    cache: &Cache,
+) -> Option<clean::Type> {
+    let def_id =
+        cache.paths.iter().chain(cache.external_paths.iter()).find_map(|(def_id, (path, _))| {
+            if path.iter().map(|symbol| symbol.as_str()).join("::") == full_path {
+                Some(*def_id)
+            } else {
+                None
+            }
+        })?;
+    let args = args.into_iter().map(clean::GenericArg::Type).collect::<Vec<_>>();
+    let segment_num = full_path.split("::").count();
+    let segments = full_path
+        .split("::")
+        .enumerate()
+        .map(|(i, name)| clean::PathSegment {
+            name: Symbol::intern(name),
+            args: clean::GenericArgs::AngleBracketed {
+                args: if i + 1 == segment_num { args.clone() } else { Vec::new() }
+                    .into_boxed_slice(),
+                bindings: ThinVec::new(),
+            },
+        })
+        .collect::<ThinVec<_>>();
+    Some(clean::Type::Path {
+        path: clean::Path { res: Res::Def(DefKind::Struct, def_id), segments },
+    })
+}
+
+/// 候选类型实现的trait
+fn traits_of(type_: &clean::Type) -> Vec<String> {
+    let mut traits = COMMON_TRAITS.iter().map(|name| name.to_string()).collect_vec();
+    match type_ {
+        clean::Type::Primitive(PrimitiveType::F32 | PrimitiveType::F64) => {
+            traits.extend(FLOAT_TRAITS.iter().map(|name| name.to_string()));
+        }
+        clean::Type::Primitive(..) => {
+            traits.extend(PRIMITIVE_TRAITS.iter().map(|name| name.to_string()));
+        }
+        clean::Type::BorrowedRef { mutability: Mutability::Not, type_: inner, .. } => {
+            traits.extend(["Copy", "Eq", "Ord", "Hash"].iter().map(|name| name.to_string()));
+            match &**inner {
+                clean::Type::Primitive(PrimitiveType::Str) => {
+                    traits.extend(
+                        ["Display", "ToString", "AsRef<str>", "AsRef<[u8]>", "Into<String>"]
+                            .iter()
+                            .map(|name| name.to_string()),
+                    );
+                }
+                clean::Type::Slice(element) => {
+                    traits.push(format!("AsRef<[{}]>", simple_type_name(element)));
+                }
+                _ => {}
+            }
+        }
+        clean::Type::Path { path } if path.last().as_str() == "String" => {
+            traits.extend(
+                [
+                    "Eq",
+                    "Ord",
+                    "Hash",
+                    "Display",
+                    "ToString",
+                    "FromStr",
+                    "AsRef<str>",
+                    "AsRef<[u8]>",
+                ]
+                .iter()
+                .map(|name| name.to_string()),
+            );
+        }
+        clean::Type::Path { path } => {
+            traits.extend(["Eq", "Ord", "Hash"].iter().map(|name| name.to_string()));
+            if let Some([element]) = path.generics().as_deref() {
+                traits.push(format!("AsRef<[{}]>", simple_type_name(element)));
+            }
+        }
+        _ => {}
+    }
+    traits
+}
+
+/// bound里trait的名字，带上简单的泛型参数，比如`AsRef<[u8]>`
+fn trait_name(trait_: &clean::Path) -> String {
+    let name = trait_.last().to_string();
+    match trait_.generics() {
+        Some(args) if !args.is_empty() => {
+            format!("{}<{}>", name, args.iter().map(|arg| simple_type_name(arg)).join(", "))
+        }
+        _ => name,
+    }
+}
+
+fn simple_type_name(type_: &clean::Type) -> String {
+    match type_ {
+        clean::Type::Primitive(primitive_type) => primitive_type.as_sym().to_string(),
+        clean::Type::Generic(name) => name.to_string(),
+        clean::Type::Slice(inner) => format!("[{}]", simple_type_name(inner)),
+        clean::Type::BorrowedRef { mutability, type_, .. } => {
+            format!("&{}{}", mutability.prefix_str(), simple_type_name(type_))
+        }
+        clean::Type::Path { path } => path.last().to_string(),
+        _ => "_".to_string(),
+    }
+}
+
+/// 泛型参数名字对应的bound，包括参数上的和where子句里的
+pub(crate) fn bounds_of<'g>(
+    generics: &'g clean::Generics,
+    param_name: Symbol,
+) -> Vec<&'g clean::GenericBound> {
+    let mut bounds = Vec::new();
+    for param in &generics.params {
+        if let clean::GenericParamDefKind::Type { bounds: param_bounds, .. } = &param.kind {
+            if param.name == param_name {
+                bounds.extend(param_bounds.iter());
+            }
+        }
+    }
+    for predicate in &generics.where_predicates {
+        if let clean::WherePredicate::BoundPredicate {
+            ty: clean::Type::Generic(name),
+            bounds: predicate_bounds,
+            ..
+        } = predicate
+        {
+            if *name == param_name {
+                bounds.extend(predicate_bounds.iter());
+            }
+        }
+    }
+    bounds
+}
+
+/// 参数位置的impl Trait换成Type::Generic，名字和rustdoc生成的synthetic泛型参数对应起来，
+/// 这样后面挑选候选类型、substitute_type、_same_type和fuzzable_call_type都和普通泛型参数一样处理
+/// synthetic参数按impl Trait在参数里出现的顺序排列；对不上的时候（比如impl Trait嵌在别的类型参数里）保持原样
+pub(crate) fn name_impl_trait_params(api_function: &mut ApiFunction) {
+    let synthetic_num = api_function
+        ._generics
+        .params
+        .iter()
+        .filter(|param| param.is_synthetic_type_param())
+        .count();
+    if synthetic_num == 0 {
+        return;
+    }
+    let mut inputs = api_function.inputs.clone();
+    let mut names = Vec::new();
+    for input in &mut inputs {
+        replace_impl_trait(input, &mut names);
+    }
+    if names.len() != synthetic_num {
+        return;
+    }
+    let mut names = names.into_iter();
+    for param in api_function._generics.params.iter_mut() {
+        if param.is_synthetic_type_param() {
+            param.name = names.next().unwrap();
+        }
+    }
+    api_function.inputs = inputs;
+}
+
+fn replace_impl_trait(type_: &mut clean::Type, names: &mut Vec<Symbol>) {
+    match type_ {
+        clean::Type::ImplTrait(..) => {
+            let name = Symbol::intern(format!("impl_trait_{}", names.len()).as_str());
+            names.push(name);
+            *type_ = clean::Type::Generic(name);
+        }
+        clean::Type::BorrowedRef { type_: inner, .. }
+        | clean::Type::Slice(inner)
+        | clean::Type::Array(inner, ..) => replace_impl_trait(inner, names),
+        clean::Type::Tuple(inners) => {
+            for inner in inners {
+                replace_impl_trait(inner, names);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// 函数所有泛型类型参数的替换方案，每个参数只用满足bound的候选类型，最多MAX_INSTANTIATIONS种
+/// 函数没有泛型类型参数时返回一个空的替换；有参数找不到合适的候选类型时返回空列表
+pub(crate) fn instantiations(
+    api_function: &ApiFunction,
+    candidates: &[GenericCandidate],
+) -> Vec<FxHashMap<String, clean::Type>> {
+    let generics = &api_function._generics;
+    let mut per_param = Vec::new();
+    for param in &generics.params {
+        if !param.kind.is_type() {
+            continue;
+        }
+        let bounds = bounds_of(generics, param.name);
+        let mut viable = candidates
+            .iter()
+            .filter(|candidate| candidate.satisfies(&bounds))
+            .map(|candidate| (param.name.to_string(), candidate.type_.clone()))
+            .collect_vec();
+        //只要求Read或者Write的参数优先用Cursor或者Vec<u8>，见io_param.rs
+        if io_param::io_role(&bounds).is_some() {
+            viable.insert(0, (param.name.to_string(), io_param::substituted_type(&bounds)));
+        }
+        if viable.is_empty() {
+            return Vec::new();
+        }
+        per_param.push(viable);
+    }
+    if per_param.is_empty() {
+        return vec![FxHashMap::default()];
+    }
+    per_param
+        .into_iter()
+        .multi_cartesian_product()
+        .take(MAX_INSTANTIATIONS)
+        .map(|substitutions| substitutions.into_iter().collect())
+        .collect()
+}
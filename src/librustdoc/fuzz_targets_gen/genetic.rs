@@ -0,0 +1,261 @@
+//! 遗传算法生成序列
+//! 种群里的个体是ApiSequence，每一代：
+//!     1. 计算适应度：相对于已经选出来的序列新覆盖的节点和边最重要，其次是自身覆盖的节点和边，
+//!        再加上语料里API频率的对数（见ApiGraph::_frequency_weight）
+//!     2. 新覆盖了节点或者边的个体加到api_sequences里
+//!     3. 保留最好的一部分个体，其余的用锦标赛选出父母，交叉之后再变异
+//! 变异有三种：在末尾加一个API、删掉一个调用、把一个调用换成返回同样类型的另一个函数（换生产者）
+//! 交叉和变异之后都从头用is_fun_satisfied重新检查，不合法的个体直接丢掉
+
+use crate::fuzz_targets_gen::api_graph::{ApiGraph, ApiType};
+use crate::fuzz_targets_gen::api_sequence::ApiSequence;
+use crate::fuzz_targets_gen::progress::Progress;
+use itertools::Itertools;
+use rand::rngs::ThreadRng;
+use rand::thread_rng;
+use rand::Rng;
+use rustc_data_structures::fx::FxHashSet;
+
+/// 每一代直接保留到下一代的个体比例
+static ELITE_RATIO: f64 = 0.1;
+/// 生成一个子代最多尝试的次数，超过之后用随机个体补上
+static MAX_OFFSPRING_ATTEMPTS: usize = 10;
+
+/// 遗传算法的参数，population_size和generations可以在fries.toml或者命令行设置
+pub(crate) struct GeneticConfig {
+    pub(crate) population_size: usize,
+    pub(crate) generations: usize,
+    pub(crate) max_len: usize,
+}
+
+struct Evolution<'g, 'a> {
+    api_graph: &'g ApiGraph<'a>,
+    rng: ThreadRng,
+    max_len: usize,
+    /// 已经加到api_sequences里的序列覆盖的节点和边
+    covered_nodes: FxHashSet<usize>,
+    covered_edges: FxHashSet<usize>,
+    /// 已经加到api_sequences里的序列，用函数下标表示
+    archived: FxHashSet<Vec<usize>>,
+}
+
+fn function_indexes(sequence: &ApiSequence) -> Vec<usize> {
+    sequence.functions.iter().map(|api_call| api_call.func.1).collect_vec()
+}
+
+impl<'g, 'a> Evolution<'g, 'a> {
+    /// 从空序列开始依次加入functions里的函数，有一个加不进去就返回None
+    fn replay(&self, functions: &[usize]) -> Option<ApiSequence> {
+        if functions.is_empty() || functions.len() > self.max_len {
+            return None;
+        }
+        let mut sequence = ApiSequence::new();
+        for function_index in functions {
+            sequence = self.api_graph.is_fun_satisfied(
+                &ApiType::BareFunction,
+                *function_index,
+                &sequence,
+            )?;
+        }
+        Some(sequence)
+    }
+
+    /// 在sequence末尾加一个随机的、能加进去的API，试几次都不行就返回None
+    fn append_random(&mut self, sequence: &ApiSequence) -> Option<ApiSequence> {
+        if sequence.len() >= self.max_len {
+            return None;
+        }
+        let function_len = self.api_graph.api_functions.len();
+        for _ in 0..MAX_OFFSPRING_ATTEMPTS {
+            let chosen_fun_index = self.rng.gen_range(0, function_len);
+            if let Some(new_sequence) =
+                self.api_graph.is_fun_satisfied(&ApiType::BareFunction, chosen_fun_index, sequence)
+            {
+                return Some(new_sequence);
+            }
+        }
+        None
+    }
+
+    /// 随机长度的随机序列，作为初始种群，以及子代生成失败时的补充
+    fn random_individual(&mut self) -> ApiSequence {
+        let target_len = self.rng.gen_range(1, self.max_len + 1);
+        let mut sequence = ApiSequence::new();
+        while sequence.len() < target_len {
+            match self.append_random(&sequence) {
+                Some(new_sequence) => sequence = new_sequence,
+                None => break,
+            }
+        }
+        sequence
+    }
+
+    fn fitness(&self, sequence: &ApiSequence) -> f64 {
+        let nodes = self.api_graph._api_nodes(sequence);
+        let new_nodes = nodes.iter().filter(|node| !self.covered_nodes.contains(*node)).count();
+        let new_edges = sequence
+            ._covered_dependencies
+            .iter()
+            .filter(|edge| !self.covered_edges.contains(*edge))
+            .count();
+        let own_coverage = nodes.len() + sequence._covered_dependencies.len();
+        let frequency = self.api_graph._frequency_weight(sequence);
+        (2 * (new_nodes + new_edges) + own_coverage) as f64 + (1.0 + frequency).ln()
+    }
+
+    /// 覆盖了新的节点或者边的话加到存档里，返回是否加入
+    fn archive(&mut self, sequence: &ApiSequence) -> bool {
+        if sequence._has_no_fuzzables()
+            || sequence._contains_dead_code_except_last_one(self.api_graph)
+        {
+            return false;
+        }
+        let nodes = self.api_graph._api_nodes(sequence);
+        let covers_new = nodes.iter().any(|node| !self.covered_nodes.contains(node))
+            || sequence._covered_dependencies.iter().any(|edge| !self.covered_edges.contains(edge));
+        if !covers_new || !self.archived.insert(function_indexes(sequence)) {
+            return false;
+        }
+        self.covered_nodes.extend(nodes);
+        self.covered_edges.extend(sequence._covered_dependencies.iter().copied());
+        true
+    }
+
+    /// 两个个体里随机选出适应度高的那个
+    fn tournament<'p>(&mut self, population: &'p [(ApiSequence, f64)]) -> &'p ApiSequence {
+        let first = &population[self.rng.gen_range(0, population.len())];
+        let second = &population[self.rng.gen_range(0, population.len())];
+        if first.1 >= second.1 { &first.0 } else { &second.0 }
+    }
+
+    /// 单点交叉：father的前半段接上mother的后半段
+    fn crossover(&mut self, father: &ApiSequence, mother: &ApiSequence) -> Option<ApiSequence> {
+        let father_functions = function_indexes(father);
+        let mother_functions = function_indexes(mother);
+        let father_cut = self.rng.gen_range(0, father_functions.len() + 1);
+        let mother_cut = self.rng.gen_range(0, mother_functions.len() + 1);
+        let child = father_functions[..father_cut]
+            .iter()
+            .chain(&mother_functions[mother_cut..])
+            .copied()
+            .collect_vec();
+        self.replay(&child)
+    }
+
+    fn mutate(&mut self, sequence: &ApiSequence) -> Option<ApiSequence> {
+        let mut functions = function_indexes(sequence);
+        match self.rng.gen_range(0, 3) {
+            //在末尾加一个API
+            0 => self.append_random(sequence),
+            //删掉一个调用，后面用到它返回值的调用会重新找生产者
+            1 => {
+                if functions.len() <= 1 {
+                    return None;
+                }
+                functions.remove(self.rng.gen_range(0, functions.len()));
+                self.replay(&functions)
+            }
+            //换生产者：换成另一个返回同样类型的函数
+            _ => {
+                if functions.is_empty() {
+                    return None;
+                }
+                let position = self.rng.gen_range(0, functions.len());
+                let output = self.api_graph.api_functions[functions[position]].output.as_ref()?;
+                let producers = self
+                    .api_graph
+                    .api_functions
+                    .iter()
+                    .enumerate()
+                    .filter(|(index, api_function)| {
+                        *index != functions[position]
+                            && api_function.output.as_ref() == Some(output)
+                    })
+                    .map(|(index, _)| index)
+                    .collect_vec();
+                if producers.is_empty() {
+                    return None;
+                }
+                functions[position] = producers[self.rng.gen_range(0, producers.len())];
+                self.replay(&functions)
+            }
+        }
+    }
+
+    fn offspring(&mut self, population: &[(ApiSequence, f64)]) -> ApiSequence {
+        for _ in 0..MAX_OFFSPRING_ATTEMPTS {
+            let father = self.tournament(population);
+            let mother = self.tournament(population);
+            let child = match self.crossover(father, mother) {
+                Some(child) => child,
+                None => continue,
+            };
+            if let Some(mutated) = self.mutate(&child) {
+                return mutated;
+            }
+            return child;
+        }
+        self.random_individual()
+    }
+}
+
+/// 演化config.generations代，覆盖了新节点或者边的个体放到api_sequences里
+pub(crate) fn evolve(api_graph: &mut ApiGraph<'_>, config: &GeneticConfig) {
+    api_graph.api_sequences.clear();
+    api_graph.reset_visited();
+    if api_graph.api_functions.is_empty() || config.population_size == 0 || config.max_len == 0 {
+        return;
+    }
+
+    let mut evolution = Evolution {
+        api_graph: &*api_graph,
+        rng: thread_rng(),
+        max_len: config.max_len,
+        covered_nodes: FxHashSet::default(),
+        covered_edges: FxHashSet::default(),
+        archived: FxHashSet::default(),
+    };
+    let mut archived_sequences = Vec::new();
+    let mut population =
+        (0..config.population_size).map(|_| evolution.random_individual()).collect_vec();
+    let elite_number = ((config.population_size as f64) * ELITE_RATIO).ceil() as usize;
+
+    let mut progress = Progress::new("genetic", Some(config.generations));
+    for _ in 0..config.generations {
+        progress.inc(1);
+        let mut scored = population
+            .into_iter()
+            .map(|sequence| {
+                let fitness = evolution.fitness(&sequence);
+                (sequence, fitness)
+            })
+            .collect_vec();
+        scored.sort_by(|(_, x), (_, y)| y.total_cmp(x));
+        for (sequence, _) in &scored {
+            if evolution.archive(sequence) {
+                archived_sequences.push(sequence.clone());
+            }
+        }
+        progress.set_sequences(archived_sequences.len());
+
+        let mut next_population =
+            scored.iter().take(elite_number).map(|(sequence, _)| sequence.clone()).collect_vec();
+        while next_population.len() < config.population_size {
+            next_population.push(evolution.offspring(&scored));
+        }
+        population = next_population;
+    }
+    progress.finish();
+
+    let covered_nodes = evolution.covered_nodes;
+    println!(
+        "genetic algorithm archived {} sequences covering {} nodes and {} edges",
+        archived_sequences.len(),
+        covered_nodes.len(),
+        evolution.covered_edges.len()
+    );
+    for index in covered_nodes {
+        api_graph.api_functions_visited[index] = true;
+    }
+    api_graph.api_sequences = archived_sequences;
+}
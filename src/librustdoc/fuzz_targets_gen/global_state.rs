@@ -0,0 +1,106 @@
+//! 会修改进程全局状态的API（环境变量、全局注册表、`set_logger`等）
+//! 这类API在一个序列里不能出现两次，也不能和其他同类API同时出现，
+//! 否则第二次调用往往直接失败或panic，掩盖掉真正的问题
+//! 1. 实验目录下的global_states.txt：用户标注的函数，优先于下面的默认规则，格式见load_global_states_from_file
+//! 2. GLOBAL_STATE_FUNCTIONS：手动标注的函数全路径
+//! 3. GLOBAL_STATE_NAME_PATTERNS：启发式规则，按照函数名最后一段来判断
+//! 4. PROCESS_EXIT_NAME_PATTERNS：会直接结束进程的函数，只能出现在序列的最后
+
+use crate::fuzz_targets_gen::diagnostics::{self, WarningKind};
+use crate::fuzz_targets_gen::file_util;
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
+
+// 手动标注的会修改全局状态的函数，左边是函数的全路径
+lazy_static! {
+    static ref GLOBAL_STATE_FUNCTIONS: FxHashSet<&'static str> = {
+        let mut m = FxHashSet::default();
+        m.insert("log::set_logger");
+        m.insert("log::set_boxed_logger");
+        m.insert("log::set_max_level");
+        m.insert("env_logger::init");
+        m.insert("env_logger::try_init");
+        m.insert("tracing::subscriber::set_global_default");
+        m.insert("rayon::ThreadPoolBuilder::build_global");
+        m
+    };
+}
+
+// 启发式规则：函数名的最后一段
+lazy_static! {
+    static ref GLOBAL_STATE_NAME_PATTERNS: FxHashSet<&'static str> = {
+        let mut m = FxHashSet::default();
+        m.insert("set_logger");
+        m.insert("set_boxed_logger");
+        m.insert("set_max_level");
+        m.insert("set_global_default");
+        m.insert("build_global");
+        m.insert("set_hook");
+        m.insert("take_hook");
+        m.insert("set_var");
+        m.insert("remove_var");
+        m.insert("set_current_dir");
+        m
+    };
+}
+
+// 会结束进程的函数名最后一段，比如xxx::exit、xxx::abort
+lazy_static! {
+    static ref PROCESS_EXIT_NAME_PATTERNS: FxHashSet<&'static str> = {
+        let mut m = FxHashSet::default();
+        m.insert("exit");
+        m.insert("abort");
+        m.insert("exit_process");
+        m
+    };
+}
+
+/// 判断一个函数是否会结束进程
+pub(crate) fn is_process_exit_function(full_name: &String) -> bool {
+    match full_name.rsplit("::").next() {
+        Some(last_segment) => PROCESS_EXIT_NAME_PATTERNS.contains(last_segment),
+        None => false,
+    }
+}
+
+/// 从文件读取全局状态函数，每行的格式是 `函数全路径  |  global` 或者 `函数全路径  |  local`，
+/// global表示会修改全局状态，local用来去掉默认规则的误判，比如名字叫set_var的普通方法
+/// 文件不存在的话什么都不做
+pub(crate) fn load_global_states_from_file(
+    file_path: &str,
+    global_states: &mut FxHashMap<String, bool>,
+) {
+    for (full_name, kind) in file_util::read_config_pairs(file_path) {
+        match kind.as_str() {
+            "global" => {
+                global_states.insert(full_name, true);
+            }
+            "local" => {
+                global_states.insert(full_name, false);
+            }
+            _ => diagnostics::warn(
+                WarningKind::Degraded,
+                None,
+                format!("invalid global state kind in {}: {}, ignored", file_path, kind),
+            ),
+        }
+    }
+}
+
+/// 判断一个函数是否会修改全局状态，global_states来自global_states.txt
+pub(crate) fn is_global_state_function(
+    full_name: &String,
+    global_states: &FxHashMap<String, bool>,
+) -> bool {
+    if let Some(is_global) = global_states.get(full_name) {
+        return *is_global;
+    }
+    if GLOBAL_STATE_FUNCTIONS.contains(full_name.as_str()) {
+        return true;
+    }
+    let last_segment = match full_name.rsplit("::").next() {
+        Some(segment) => segment,
+        None => return false,
+    };
+    // 全局初始化函数，比如xxx::set_global_xxx
+    GLOBAL_STATE_NAME_PATTERNS.contains(last_segment) || last_segment.starts_with("set_global_")
+}
@@ -0,0 +1,160 @@
+//! ApiGraph的缓存
+//! find_all_dependencies对大的crate很慢，而且每次运行都要重新算一遍。
+//! --save-graph把找好依赖的图保存到`{experiment_root}/{crate}/api_graph_{crate hash}.json`，
+//! --load-graph在之后的运行里读回来，跳过找依赖这一步，只调整序列生成的参数时就不用重新分析。
+//! api_functions里的clean::Type没法序列化，所以函数本身还是每次从rustdoc的结果解析（这一步很快），
+//! 缓存里保存每个函数的签名、依赖边以及full_name_map里的名字，读回来时逐个比较，
+//! 有任何对不上的地方都说明crate或者参数变了，缓存作废，重新找依赖
+
+use crate::fuzz_targets_gen::api_graph::{ApiDependency, ApiGraph};
+use crate::fuzz_targets_gen::diagnostics::{self, WarningKind};
+use crate::fuzz_targets_gen::file_util;
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+#[derive(Serialize, Deserialize)]
+struct CachedGraph {
+    crate_name: String,
+    crate_hash: String,
+    support_generic: bool,
+    /// 整数依赖边的转换方式，见numeric_cast.rs
+    #[serde(default)]
+    numeric_cast: String,
+    /// Path参数的构造方式，见path_param.rs
+    #[serde(default)]
+    path_mode: String,
+    /// 每个函数的签名，顺序和api_functions一致
+    functions: Vec<String>,
+    api_dependencies: Vec<ApiDependency>,
+    /// full_name_map里的(全路径, 类型)，按全路径排序
+    full_name_map: Vec<(String, String)>,
+}
+
+impl CachedGraph {
+    fn from_graph(graph: &ApiGraph<'_>, crate_hash: &str, support_generic: bool) -> Self {
+        CachedGraph {
+            crate_name: graph._crate_name.clone(),
+            crate_hash: crate_hash.to_string(),
+            support_generic,
+            numeric_cast: graph.numeric_cast.name().to_string(),
+            path_mode: graph.emit_options.path_mode.name().to_string(),
+            functions: function_signatures(graph),
+            api_dependencies: graph.api_dependencies.clone(),
+            full_name_map: full_names(graph),
+        }
+    }
+}
+
+/// 缓存文件的路径
+pub(crate) fn cache_path(crate_name: &str, crate_hash: &str) -> String {
+    format!("{}/{}/api_graph_{}.json", file_util::experiment_root(), crate_name, crate_hash)
+}
+
+fn function_signatures(graph: &ApiGraph<'_>) -> Vec<String> {
+    graph
+        .api_functions
+        .iter()
+        .map(|api_function| api_function._pretty_print(graph.cache, &graph.full_name_map))
+        .collect()
+}
+
+fn full_names(graph: &ApiGraph<'_>) -> Vec<(String, String)> {
+    let mut res: Vec<(String, String)> = graph
+        .full_name_map
+        .map
+        .values()
+        .map(|(full_name, item_type)| (full_name.clone(), item_type.as_str().to_string()))
+        .collect();
+    res.sort();
+    res
+}
+
+/// 保存找好依赖的图
+pub(crate) fn save_graph(graph: &ApiGraph<'_>, crate_hash: &str, support_generic: bool) {
+    let file_path = cache_path(&graph._crate_name, crate_hash);
+    let cached = CachedGraph::from_graph(graph, crate_hash, support_generic);
+    let res = fs::write(&file_path, serde_json::to_string(&cached).unwrap());
+    match res {
+        Ok(()) => println!("save api graph to {}", file_path),
+        Err(e) => diagnostics::warn(
+            WarningKind::Degraded,
+            None,
+            format!("cannot save api graph to {}: {}", file_path, e),
+        ),
+    }
+}
+
+/// 读取缓存的依赖边，成功时填进graph.api_dependencies并返回true，
+/// 缓存不存在或者和当前的图对不上时返回false，需要重新找依赖
+pub(crate) fn load_graph(
+    graph: &mut ApiGraph<'_>,
+    crate_hash: &str,
+    support_generic: bool,
+) -> bool {
+    let file_path = cache_path(&graph._crate_name, crate_hash);
+    let content = match fs::read_to_string(&file_path) {
+        Ok(content) => content,
+        Err(_) => {
+            println!("no cached api graph at {}, analyse dependencies", file_path);
+            return false;
+        }
+    };
+    let cached: CachedGraph = match serde_json::from_str(&content) {
+        Ok(cached) => cached,
+        Err(e) => {
+            diagnostics::warn(
+                WarningKind::Degraded,
+                None,
+                format!("invalid cached api graph {}: {}, ignored", file_path, e),
+            );
+            return false;
+        }
+    };
+    if let Some(mismatch) = mismatch_of(graph, &cached, crate_hash, support_generic) {
+        diagnostics::warn(
+            WarningKind::Degraded,
+            None,
+            format!("stale cached api graph {}: {}, ignored", file_path, mismatch),
+        );
+        return false;
+    }
+    graph.api_dependencies = cached.api_dependencies;
+    graph.rebuild_dependency_index();
+    println!("load api graph from {}", file_path);
+    true
+}
+
+/// 缓存和当前的图哪里对不上
+fn mismatch_of(
+    graph: &ApiGraph<'_>,
+    cached: &CachedGraph,
+    crate_hash: &str,
+    support_generic: bool,
+) -> Option<String> {
+    if cached.crate_name != graph._crate_name || cached.crate_hash != crate_hash {
+        return Some(format!("built for {} ({})", cached.crate_name, cached.crate_hash));
+    }
+    if cached.support_generic != support_generic {
+        return Some("built with a different generic setting".to_string());
+    }
+    if cached.numeric_cast != graph.numeric_cast.name() {
+        return Some("built with a different numeric cast".to_string());
+    }
+    if cached.path_mode != graph.emit_options.path_mode.name() {
+        return Some("built with a different path mode".to_string());
+    }
+    if cached.functions != function_signatures(graph) {
+        return Some("the api functions changed".to_string());
+    }
+    if cached.full_name_map != full_names(graph) {
+        return Some("the full name map changed".to_string());
+    }
+    let function_num = graph.api_functions.len();
+    let out_of_range = cached.api_dependencies.iter().any(|dependency| {
+        dependency.output_fun.1 >= function_num || dependency.input_fun.1 >= function_num
+    });
+    if out_of_range {
+        return Some("dependency index out of range".to_string());
+    }
+    None
+}
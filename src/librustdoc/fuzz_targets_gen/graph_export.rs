@@ -0,0 +1,65 @@
+//! 把api_functions、api_dependencies和选出来的序列导出成一个json，给外部工具做后处理、
+//! 比较不同版本的crate或者实现自己的序列选择策略。
+//! 函数的参数和返回值按生成代码里的写法导出成字符串，依赖和序列里的函数用api_functions的下标表示，
+//! 序列的格式和sequence_serde.rs导出的一样，sequences部分可以直接交给import_sequences导入。
+//! 合成的`<T as Default>::default`生产者不是crate的API，不出现在functions和dependencies里，
+//! 序列里对它们的调用保持原样，导入时按函数名找回来
+
+use crate::clean;
+use crate::fuzz_targets_gen::api_graph::{ApiDependency, ApiGraph};
+use crate::fuzz_targets_gen::api_sequence::ApiSequence;
+use crate::fuzz_targets_gen::api_util;
+use crate::fuzz_targets_gen::sequence_serde::{self, ExportedSequence};
+use serde::Serialize;
+
+/// 导出的一个函数
+#[derive(Serialize)]
+struct ExportedFunction {
+    index: usize,
+    full_name: String,
+    inputs: Vec<String>,
+    output: Option<String>,
+    trait_path: Option<String>,
+    is_unsafe: bool,
+    is_async: bool,
+}
+
+#[derive(Serialize)]
+struct ExportedGraph<'a> {
+    crate_name: &'a str,
+    functions: Vec<ExportedFunction>,
+    dependencies: Vec<&'a ApiDependency>,
+    sequences: Vec<ExportedSequence>,
+}
+
+/// 把整个图和选出来的序列导出成json
+pub(crate) fn graph_to_json(graph: &ApiGraph<'_>, sequences: &[ApiSequence]) -> String {
+    let type_name =
+        |type_: &clean::Type| api_util::_type_name(type_, graph.cache, &graph.full_name_map);
+    let functions = graph
+        .api_functions
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| !graph._is_default_producer(*index))
+        .map(|(index, api_function)| ExportedFunction {
+            index,
+            full_name: api_function.full_name.clone(),
+            inputs: api_function.inputs.iter().map(type_name).collect(),
+            output: api_function.output.as_ref().map(type_name),
+            trait_path: api_function._trait_full_path.clone(),
+            is_unsafe: api_function._unsafe_tag._is_unsafe(),
+            is_async: api_function._asyncness._is_async(),
+        })
+        .collect();
+    let exported = ExportedGraph {
+        crate_name: &graph._crate_name,
+        functions,
+        dependencies: graph
+            .api_dependencies
+            .iter()
+            .filter(|dependency| !graph._is_default_producer(dependency.output_fun.1))
+            .collect(),
+        sequences: sequence_serde::exported_sequences(graph, sequences),
+    };
+    serde_json::to_string_pretty(&exported).unwrap()
+}
@@ -0,0 +1,120 @@
+//! 依赖图上的查询
+//! 哪些函数能产生某个类型、哪些函数会用到某个类型、两个函数之间有哪些依赖路径
+//! 反向搜索和外部工具都通过这些方法来查询，不用再自己遍历api_dependencies
+
+use crate::fuzz_targets_gen::api_graph::{ApiGraph, ApiType};
+use crate::fuzz_targets_gen::invariant;
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
+
+impl<'a> ApiGraph<'a> {
+    /// 返回值类型（去掉引用、Option/Result之后）是type_name的函数
+    pub(crate) fn producers_of(&self, type_name: &str) -> Vec<usize> {
+        self.api_functions
+            .iter()
+            .enumerate()
+            .filter(|(_, api_function)| match &api_function.output {
+                Some(output_type) => {
+                    invariant::type_full_path(output_type, self.cache, &self.full_name_map)
+                        .as_deref()
+                        == Some(type_name)
+                }
+                None => false,
+            })
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// 有参数类型（去掉引用、Option/Result之后）是type_name的函数
+    pub(crate) fn consumers_of(&self, type_name: &str) -> Vec<usize> {
+        self.api_functions
+            .iter()
+            .enumerate()
+            .filter(|(_, api_function)| {
+                api_function.inputs.iter().any(|input_type| {
+                    invariant::type_full_path(input_type, self.cache, &self.full_name_map)
+                        .as_deref()
+                        == Some(type_name)
+                })
+            })
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// 返回值可以作为函数input_index第param_index个参数的函数
+    /// 返回(函数下标, 依赖在api_dependencies中的下标)，按函数下标排序，和check_dependency的结果一致
+    pub(crate) fn producers_for_param(
+        &self,
+        input_index: usize,
+        param_index: usize,
+    ) -> Vec<(usize, usize)> {
+        let mut producers: FxHashMap<usize, usize> = FxHashMap::default();
+        for (dependency_index, dependency) in self.api_dependencies.iter().enumerate() {
+            if dependency.output_fun.0 != ApiType::BareFunction
+                || dependency.input_fun != (ApiType::BareFunction, input_index)
+                || dependency.input_param_index != param_index
+            {
+                continue;
+            }
+            producers.entry(dependency.output_fun.1).or_insert(dependency_index);
+        }
+        let mut res = producers.into_iter().collect::<Vec<_>>();
+        res.sort();
+        res
+    }
+
+    /// 从函数from到函数to的所有依赖路径，路径上最多max_len个函数，不重复经过同一个函数
+    pub(crate) fn paths_between(&self, from: usize, to: usize, max_len: usize) -> Vec<Vec<usize>> {
+        let mut successors: FxHashMap<usize, Vec<usize>> = FxHashMap::default();
+        for dependency in &self.api_dependencies {
+            let next = successors.entry(dependency.output_fun.1).or_default();
+            if !next.contains(&dependency.input_fun.1) {
+                next.push(dependency.input_fun.1);
+            }
+        }
+        for next in successors.values_mut() {
+            next.sort();
+        }
+
+        let mut res = Vec::new();
+        if max_len == 0 {
+            return res;
+        }
+        let mut path = vec![from];
+        let mut on_path = FxHashSet::default();
+        on_path.insert(from);
+        Self::_paths_between_util(&successors, to, max_len, &mut path, &mut on_path, &mut res);
+        res
+    }
+
+    fn _paths_between_util(
+        successors: &FxHashMap<usize, Vec<usize>>,
+        to: usize,
+        max_len: usize,
+        path: &mut Vec<usize>,
+        on_path: &mut FxHashSet<usize>,
+        res: &mut Vec<Vec<usize>>,
+    ) {
+        let current = *path.last().unwrap();
+        if current == to {
+            res.push(path.clone());
+            return;
+        }
+        if path.len() >= max_len {
+            return;
+        }
+        let next_functions = match successors.get(&current) {
+            Some(next_functions) => next_functions,
+            None => return,
+        };
+        for next in next_functions {
+            if on_path.contains(next) {
+                continue;
+            }
+            path.push(*next);
+            on_path.insert(*next);
+            Self::_paths_between_util(successors, to, max_len, path, on_path, res);
+            on_path.remove(next);
+            path.pop();
+        }
+    }
+}
@@ -0,0 +1,155 @@
+//! 贪心选序列的公共框架
+//! _pair_coverage_choose、_set_cover_choose、_complexity_choose都是每轮选分数最高的序列，
+//! 再把它覆盖的节点、边和相邻API对加进已覆盖的集合，只有打分的方式不一样
+
+use crate::fuzz_targets_gen::api_graph::ApiGraph;
+use crate::fuzz_targets_gen::api_sequence::ApiSequence;
+use crate::fuzz_targets_gen::progress::Progress;
+use itertools::Itertools;
+use rustc_data_structures::fx::FxHashSet;
+use std::cmp::Ordering;
+
+/// 候选序列，节点和相邻API对提前算好，不用每轮重新算
+pub(crate) struct SelectionCandidate<'s> {
+    pub(crate) sequence: &'s ApiSequence,
+    pub(crate) nodes: Vec<usize>,
+    pub(crate) pairs: Vec<(usize, usize)>,
+}
+
+/// 已经选出来的序列覆盖的节点、边和相邻API对
+#[derive(Default)]
+pub(crate) struct SelectionCoverage {
+    pub(crate) nodes: FxHashSet<usize>,
+    pub(crate) edges: FxHashSet<usize>,
+    pub(crate) pairs: FxHashSet<(usize, usize)>,
+}
+
+impl SelectionCoverage {
+    /// 候选序列里还没被覆盖的节点
+    pub(crate) fn new_nodes<'c>(
+        &'c self,
+        candidate: &'c SelectionCandidate<'_>,
+    ) -> impl Iterator<Item = usize> + 'c {
+        candidate.nodes.iter().copied().filter(|node| !self.nodes.contains(node))
+    }
+
+    /// 候选序列里还没被覆盖的边的个数
+    pub(crate) fn new_edge_count(&self, candidate: &SelectionCandidate<'_>) -> usize {
+        candidate
+            .sequence
+            ._covered_dependencies
+            .iter()
+            .filter(|edge| !self.edges.contains(*edge))
+            .count()
+    }
+
+    /// 候选序列里还没被覆盖的相邻API对的个数
+    pub(crate) fn new_pair_count(&self, candidate: &SelectionCandidate<'_>) -> usize {
+        candidate.pairs.iter().filter(|pair| !self.pairs.contains(*pair)).count()
+    }
+
+    fn add(&mut self, candidate: &SelectionCandidate<'_>) {
+        self.nodes.extend(candidate.nodes.iter().copied());
+        self.edges.extend(candidate.sequence._covered_dependencies.iter().copied());
+        self.pairs.extend(candidate.pairs.iter().copied());
+    }
+}
+
+impl<'a> ApiGraph<'a> {
+    /// 可以选的序列：有可以fuzz的参数、除了最后一个调用之外没有没用到的返回值、不违反全局状态的限制
+    pub(crate) fn _selection_candidates(&self) -> Vec<SelectionCandidate<'_>> {
+        self.api_sequences
+            .iter()
+            .filter(|api_sequence| {
+                !api_sequence._has_no_fuzzables()
+                    && !api_sequence._contains_dead_code_except_last_one(self)
+                    && !api_sequence._violates_global_state_constraint(self)
+            })
+            .map(|sequence| SelectionCandidate {
+                sequence,
+                nodes: self._api_nodes(sequence),
+                pairs: sequence._api_pairs(),
+            })
+            .collect_vec()
+    }
+
+    /// 每轮给还没选的候选序列打分，选分数最大的，一样的时候选生成得早的，直到选够max_size个。
+    /// score返回None表示这个序列没有新覆盖的东西，所有序列都是None的时候提前结束
+    pub(crate) fn _greedy_choose<K: Ord>(
+        &self,
+        candidates: &[SelectionCandidate<'_>],
+        max_size: usize,
+        mut score: impl FnMut(&SelectionCandidate<'_>, &SelectionCoverage) -> Option<K>,
+    ) -> (Vec<ApiSequence>, SelectionCoverage) {
+        let mut res = Vec::new();
+        let mut already_chosen = FxHashSet::default();
+        let mut coverage = SelectionCoverage::default();
+        let mut progress = Progress::new("select sequences", Some(max_size));
+        while res.len() < max_size {
+            progress.inc(1);
+            let mut best: Option<(K, usize)> = None;
+            for (index, candidate) in candidates.iter().enumerate() {
+                if already_chosen.contains(&index) {
+                    continue;
+                }
+                let Some(key) = score(candidate, &coverage) else {
+                    continue;
+                };
+                if best.as_ref().map_or(true, |(best_key, _)| key > *best_key) {
+                    best = Some((key, index));
+                }
+            }
+            let Some((_, index)) = best else {
+                break;
+            };
+            already_chosen.insert(index);
+            coverage.add(&candidates[index]);
+            res.push(candidates[index].sequence.clone());
+            progress.set_sequences(res.len());
+        }
+        progress.finish();
+        (res, coverage)
+    }
+}
+
+/// 贪心选序列的统计信息，extra是每种算法自己关心的一行
+pub(crate) fn print_selection_statistics(
+    name: &str,
+    res: &[ApiSequence],
+    coverage: &SelectionCoverage,
+    extra: String,
+) {
+    println!("-----------STATISTICS-----------");
+    println!("{} selection selected {} targets", name, res.len());
+    println!("{} selection covered {} nodes", name, coverage.nodes.len());
+    println!("{} selection covered {} edges", name, coverage.edges.len());
+    println!("{} selection covered {} api pairs", name, coverage.pairs.len());
+    println!("{} selection {}", name, extra);
+    println!("--------------------------------");
+}
+
+/// 带权集合覆盖里一个序列的分数：新覆盖的元素数/代价，
+/// 一样的时候新覆盖元素多的大，再一样的时候代价小的大。交叉相乘比较，避免浮点数
+#[derive(PartialEq, Eq)]
+pub(crate) struct CostRatio {
+    pub(crate) elements: usize,
+    pub(crate) cost: usize,
+}
+
+impl Ord for CostRatio {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.elements * other.cost)
+            .cmp(&(other.elements * self.cost))
+            .then(self.elements.cmp(&other.elements))
+            .then(other.cost.cmp(&self.cost))
+    }
+}
+
+impl PartialOrd for CostRatio {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,30 @@
+//! 在fixture的BFS序列上检查三种贪心选序列的算法
+
+use crate::fuzz_targets_gen::analyzer::GenerationConfig;
+use crate::fuzz_targets_gen::tests::with_fixture_graph;
+use crate::fuzz_targets_gen::traversal::{Bfs, TraversalStrategy};
+use rustc_data_structures::fx::FxHashSet;
+
+/// 每种算法都选出了序列，选出来的序列覆盖了所有候选序列里的节点
+#[test]
+fn test_greedy_selection_covers_candidates() {
+    with_fixture_graph(|graph| {
+        let cfg = GenerationConfig::default().with_bfs_max_len(Some(2));
+        Bfs { stop_at_end_function: false, fast_mode: false }.generate(graph, &cfg).unwrap();
+        let candidates = graph._selection_candidates();
+        assert!(!candidates.is_empty());
+        let candidate_nodes: FxHashSet<usize> =
+            candidates.iter().flat_map(|candidate| candidate.nodes.iter().copied()).collect();
+
+        for selected in [
+            graph._pair_coverage_choose(candidates.len()),
+            graph._set_cover_choose(candidates.len()),
+            graph._complexity_choose(candidates.len()),
+        ] {
+            assert!(!selected.is_empty() && selected.len() <= candidates.len());
+            let selected_nodes: FxHashSet<usize> =
+                selected.iter().flat_map(|sequence| graph._api_nodes(sequence)).collect();
+            assert_eq!(selected_nodes, candidate_nodes);
+        }
+    });
+}
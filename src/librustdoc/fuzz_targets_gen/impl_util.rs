@@ -1,16 +1,20 @@
 use crate::clean::{self};
+use crate::formats;
 use crate::formats::cache::Cache;
 use crate::formats::item_type::ItemType;
 use crate::fuzz_targets_gen::api_function::ApiFunction;
 use crate::fuzz_targets_gen::api_util;
-use rustc_data_structures::fx::FxHashMap;
+use crate::fuzz_targets_gen::foreign_trait;
+use crate::fuzz_targets_gen::generic_candidate;
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
 use rustc_hir::def_id::DefId;
-use rustc_middle::ty::{TyCtxt, Visibility};
+use rustc_middle::ty::{DefIdTree, ImplPolarity, TyCtxt, Visibility};
 use rustc_span::Symbol;
+use rustc_span::symbol::sym;
 use thin_vec::ThinVec;
 //FIXME: 是否需要为impl里面的method重新设计数据结构？目前沿用了ApiFunction,或者直接对ApiFunction进行扩展
 //两种函数目前相差一个defaultness
-use crate::fuzz_targets_gen::api_function::ApiUnsafety;
+use crate::fuzz_targets_gen::api_function::{ApiAbi, ApiAsyncness, ApiUnsafety};
 use crate::fuzz_targets_gen::api_graph::ApiGraph;
 use crate::fuzz_targets_gen::prelude_type;
 
@@ -43,11 +47,15 @@ pub(crate) fn add_impl(&mut self, impl_: &clean::Impl) {
         //println!("impl type = {:?}", impl_.for_);
         let _impl_type = &impl_.for_;
         //println!("impl type = {:?}", _impl_type);
+        //带类型泛型参数的impl块先放着，等图里的函数都加进来之后再实例化，见instantiate_generic_impls
+        let is_generic = _has_type_generics(&impl_.generics);
         match impl_.trait_ {
+            None if is_generic => self._generic_impl.push(impl_.clone()),
             None => {
                 //println!("No trait!");
                 self.impl_types.push(impl_.clone());
             }
+            Some(_) if is_generic => self._generic_impl_for_traits.push(impl_.clone()),
             Some(ref _ty_) => {
                 //println!("trait={:?}", _ty_);
                 self.impl_trait_for_types.push(impl_.clone());
@@ -59,12 +67,49 @@ pub(crate) fn add_impl(&mut self, impl_: &clean::Impl) {
 #[derive(Debug, Clone)]
 pub(crate) struct FullNameMap {
     pub(crate) map: FxHashMap<DefId, (String, ItemType)>,
+    /// trait的did -> 实现了这个trait的公开类型，用来把具体类型的值转换成trait object
+    pub(crate) implementors: FxHashMap<DefId, Vec<clean::Type>>,
+    /// 没有泛型参数的公开枚举的did -> 变体，用来从fuzz输入构造枚举，见fuzz_type::FuzzableType::Enum
+    pub(crate) enum_variants: FxHashMap<DefId, Vec<EnumVariant>>,
+    /// 本crate的函数和类型的did -> 从crate外面能访问到的最短路径，包括`pub use`重导出的路径，
+    /// 见mod_visibility::record_public_paths
+    pub(crate) public_paths: FxHashMap<DefId, String>,
+    /// 实现了Deref的本crate类型的did -> Target，用来判断Deref强制转换，见api_util::_deref_in_same_type
+    pub(crate) deref_targets: FxHashMap<DefId, clean::Type>,
+    /// 本crate类型的did -> AsRef<T>和Borrow<T>里的T，见api_util::_as_ref_in_same_type
+    pub(crate) as_ref_targets: FxHashMap<DefId, Vec<clean::Type>>,
+    pub(crate) borrow_targets: FxHashMap<DefId, Vec<clean::Type>>,
+    /// 本crate类型B的did -> 所有From<A>里的A，见api_util::_into_in_same_type
+    pub(crate) from_sources: FxHashMap<DefId, Vec<clean::Type>>,
+}
+
+/// 枚举的一个变体，fields是(字段名, 字段类型)，元组变体的字段名是`0`、`1`...
+#[derive(Debug, Clone)]
+pub(crate) struct EnumVariant {
+    pub(crate) name: String,
+    pub(crate) fields: Vec<(String, clean::Type)>,
 }
 
 impl FullNameMap {
     pub(crate) fn new() -> Self {
         let map = FxHashMap::default();
-        FullNameMap { map }
+        let implementors = FxHashMap::default();
+        let enum_variants = FxHashMap::default();
+        let public_paths = FxHashMap::default();
+        let deref_targets = FxHashMap::default();
+        let as_ref_targets = FxHashMap::default();
+        let borrow_targets = FxHashMap::default();
+        let from_sources = FxHashMap::default();
+        FullNameMap {
+            map,
+            implementors,
+            enum_variants,
+            public_paths,
+            deref_targets,
+            as_ref_targets,
+            borrow_targets,
+            from_sources,
+        }
     }
 
     pub(crate) fn push_mapping(&mut self, def_id: DefId, full_name: &String, item_type: ItemType) {
@@ -77,6 +122,128 @@ pub(crate) fn _get_full_name(&self, def_id: DefId) -> Option<&String> {
             Some((full_name, _)) => Some(full_name),
         }
     }
+
+    pub(crate) fn push_implementor(&mut self, trait_def_id: DefId, type_: &clean::Type) {
+        let types = self.implementors.entry(trait_def_id).or_default();
+        if !types.contains(type_) {
+            types.push(type_.clone());
+        }
+    }
+
+    pub(crate) fn _get_implementors(&self, trait_def_id: DefId) -> &[clean::Type] {
+        match self.implementors.get(&trait_def_id) {
+            None => &[],
+            Some(types) => types,
+        }
+    }
+
+    pub(crate) fn _get_enum_variants(&self, enum_def_id: DefId) -> Option<&Vec<EnumVariant>> {
+        self.enum_variants.get(&enum_def_id)
+    }
+
+    /// 记录一条公开路径，只保留路径段数最少的（一样多的保留先记录的）
+    pub(crate) fn push_public_path(&mut self, def_id: DefId, path: String) {
+        let segments = path.matches("::").count();
+        match self.public_paths.get(&def_id) {
+            Some(old_path) if old_path.matches("::").count() <= segments => {}
+            _ => {
+                self.public_paths.insert(def_id, path);
+            }
+        }
+    }
+
+    pub(crate) fn _get_deref_target(&self, def_id: DefId) -> Option<&clean::Type> {
+        self.deref_targets.get(&def_id)
+    }
+
+    pub(crate) fn _get_as_ref_targets(&self, def_id: DefId) -> &[clean::Type] {
+        self.as_ref_targets.get(&def_id).map_or(&[], |targets| targets.as_slice())
+    }
+
+    pub(crate) fn _get_borrow_targets(&self, def_id: DefId) -> &[clean::Type] {
+        self.borrow_targets.get(&def_id).map_or(&[], |targets| targets.as_slice())
+    }
+
+    pub(crate) fn _get_from_sources(&self, def_id: DefId) -> &[clean::Type] {
+        self.from_sources.get(&def_id).map_or(&[], |sources| sources.as_slice())
+    }
+
+    pub(crate) fn _get_public_path(&self, def_id: DefId) -> Option<&String> {
+        self.public_paths.get(&def_id)
+    }
+
+    /// 用最短的公开路径替换cache里的定义路径。私有模块里的item在cache里没有路径，
+    /// 重导出之后可以访问的话这里补上，它们的impl才会被分析
+    pub(crate) fn apply_public_paths(&mut self, tcx: TyCtxt<'_>) {
+        for (def_id, path) in &self.public_paths {
+            match self.map.get_mut(def_id) {
+                Some((full_name, _)) => *full_name = path.clone(),
+                None => {
+                    self.map.insert(*def_id, (path.clone(), ItemType::from(tcx.def_kind(*def_id))));
+                }
+            }
+        }
+    }
+}
+
+/// 记录crate里可以在外面构造的枚举：没有类型和常量泛型参数，没有被rustdoc去掉的变体，
+/// 变体本身不是non_exhaustive（枚举上的non_exhaustive不影响构造已有的变体）
+pub(crate) fn _record_enum_variants(item: &clean::Item, full_name_map: &mut FullNameMap) {
+    match &*item.kind {
+        clean::StrippedItem(box clean::ModuleItem(module)) | clean::ModuleItem(module) => {
+            for item in &module.items {
+                _record_enum_variants(item, full_name_map);
+            }
+        }
+        clean::EnumItem(enum_) => {
+            let Some(def_id) = item.item_id.as_def_id() else {
+                return;
+            };
+            if enum_.has_stripped_entries()
+                || enum_.variants.is_empty()
+                || enum_
+                    .generics
+                    .params
+                    .iter()
+                    .any(|param| !matches!(param.kind, clean::GenericParamDefKind::Lifetime { .. }))
+            {
+                return;
+            }
+            let mut variants = Vec::new();
+            for variant_item in enum_.variants() {
+                let (Some(name), clean::VariantItem(variant)) =
+                    (variant_item.name, &*variant_item.kind)
+                else {
+                    return;
+                };
+                if variant_item.is_non_exhaustive() {
+                    return;
+                }
+                let field_items = match variant {
+                    clean::Variant::CLike(..) => Vec::new(),
+                    clean::Variant::Tuple(fields) => fields.iter().collect(),
+                    clean::Variant::Struct(variant_struct) => {
+                        if variant_struct.has_stripped_entries() {
+                            return;
+                        }
+                        variant_struct.fields.iter().collect()
+                    }
+                };
+                let mut fields = Vec::new();
+                for field in field_items {
+                    let (Some(field_name), clean::StructFieldItem(field_type)) =
+                        (field.name, &*field.kind)
+                    else {
+                        return;
+                    };
+                    fields.push((field_name.to_string(), field_type.clone()));
+                }
+                variants.push(EnumVariant { name: name.to_string(), fields });
+            }
+            full_name_map.enum_variants.insert(def_id, variants);
+        }
+        _ => {}
+    }
 }
 
 pub(crate) fn extract_impls_from_cache(
@@ -105,6 +272,10 @@ pub(crate) fn extract_impls_from_cache(
             full_name_map.push_mapping(*did, &full_name, *item_type);
         }
     }
+    full_name_map.apply_public_paths(tcx);
+
+    _record_implementors(type_impl_maps, full_name_map);
+    _record_conversion_targets(cache, full_name_map);
 
     api_graph.set_full_name_map(&full_name_map);
 
@@ -128,6 +299,11 @@ pub(crate) fn extract_impls_from_cache(
     //println!("analyse impl Trait for Type");
     for impl_ in &crate_impl_collection.impl_trait_for_types {
         _analyse_impl(impl_, cache, tcx, &full_name_map, &mut api_graph);
+        _analyse_provided_methods(impl_, cache, tcx, &full_name_map, &mut api_graph);
+        _record_type_trait_impl(impl_, cache, &full_name_map, &mut api_graph);
+    }
+    for impl_ in &crate_impl_collection._generic_impl_for_traits {
+        _record_type_trait_impl(impl_, cache, &full_name_map, &mut api_graph);
     }
     //FIXME: 如何提取trait对应的impl，impl traitA for traitB? impl dyn traitA?下面的逻辑有误
 
@@ -145,6 +321,422 @@ pub(crate) fn extract_impls_from_cache(
     //println!("{:?}", crate_impl_collection);
 }
 
+/// 带类型泛型参数的impl块实例化成图里已经出现的具体类型，得到的方法作为普通的方法加到图里：
+/// 1. blanket impl（`impl<T: Bound> Trait for T`）：rustdoc已经给每个满足bound的本crate类型合成了impl，
+///    T就是impl的类型。From<T> for T这种只返回自己的std blanket impl不要
+/// 2. 泛型类型上的impl（`impl<T> Wrapper<T>`）：在图里函数的参数和返回值里找Wrapper<u8>这样的具体类型，
+///    按位置对应得到T = u8，再检查impl上的bound
+/// 所有类型泛型参数都确定了才实例化；一个具体类型也找不到的impl块和以前一样直接分析。
+/// 要在本crate的函数都加到图里之后调用
+pub(crate) fn instantiate_generic_impls(
+    cache: &Cache,
+    tcx: TyCtxt<'_>,
+    full_name_map: &FullNameMap,
+    api_graph: &mut ApiGraph<'_>,
+) {
+    let mut crate_impl_collection = CrateImplCollection::new();
+    for (did, impls) in &cache.impls {
+        if full_name_map._get_full_name(*did) != None {
+            for impl_ in impls {
+                crate_impl_collection.add_impl(impl_.inner_impl());
+            }
+        }
+    }
+
+    let mut concrete_types = Vec::new();
+    for api_function in &api_graph.api_functions {
+        for type_ in api_function.inputs.iter().chain(api_function.output.iter()) {
+            _collect_concrete_types(type_, &mut concrete_types);
+        }
+    }
+
+    let mut instance_number = 0;
+    let generic_impls = crate_impl_collection
+        ._generic_impl
+        .iter()
+        .chain(crate_impl_collection._generic_impl_for_traits.iter());
+    for impl_ in generic_impls {
+        let substitutions_list = match &impl_.kind {
+            clean::ImplKind::Blanket(box clean::Type::Generic(name)) => {
+                let trait_did = impl_.trait_.as_ref().map(|trait_| trait_.def_id());
+                if trait_did.map_or(false, |trait_did| _is_reflexive_trait(trait_did, cache)) {
+                    continue;
+                }
+                vec![FxHashMap::from_iter([(*name, impl_.for_.clone())])]
+            }
+            clean::ImplKind::Blanket(..) | clean::ImplKind::Auto => continue,
+            _ => concrete_types
+                .iter()
+                .filter_map(|concrete_type| {
+                    let mut substitutions = FxHashMap::default();
+                    if _match_generic_type(&impl_.for_, concrete_type, &mut substitutions) {
+                        Some(substitutions)
+                    } else {
+                        None
+                    }
+                })
+                .filter(|substitutions| _satisfies_impl_bounds(impl_, substitutions, tcx))
+                .take(MAX_IMPL_INSTANTIATIONS)
+                .collect(),
+        };
+        let substitutions_list = substitutions_list
+            .into_iter()
+            .filter(|substitutions| _resolves_all_type_params(&impl_.generics, substitutions))
+            .collect::<Vec<_>>();
+        if substitutions_list.is_empty() {
+            //找不到具体类型，blanket impl的方法没法调用，泛型类型上的impl保持原来的做法
+            if !impl_.kind.is_blanket() {
+                _analyse_impl(impl_, cache, tcx, full_name_map, api_graph);
+            }
+            continue;
+        }
+        for substitutions in &substitutions_list {
+            let instance = _instantiate_impl(impl_, substitutions);
+            _analyse_impl(&instance, cache, tcx, full_name_map, api_graph);
+            if instance.trait_.is_some() {
+                _analyse_provided_methods(&instance, cache, tcx, full_name_map, api_graph);
+            }
+            instance_number = instance_number + 1;
+        }
+    }
+    println!("instantiated {} generic impl blocks", instance_number);
+}
+
+/// 一个泛型impl块最多实例化多少次
+static MAX_IMPL_INSTANTIATIONS: usize = 8;
+
+/// 对任意类型T都成立、只是把T转换成T自己的std blanket impl
+static REFLEXIVE_TRAITS: [&'static str; 4] =
+    ["core::convert::From", "core::borrow::Borrow", "core::borrow::BorrowMut", "core::any::Any"];
+
+fn _is_reflexive_trait(trait_did: DefId, cache: &Cache) -> bool {
+    match cache.external_paths.get(&trait_did) {
+        Some((strings, _)) => REFLEXIVE_TRAITS.contains(&full_path(strings).as_str()),
+        None => false,
+    }
+}
+
+/// 类型里出现的、不含泛型参数的路径类型，Wrapper<u8>、Option<Wrapper<u8>>里的Wrapper<u8>都算
+fn _collect_concrete_types(type_: &clean::Type, concrete_types: &mut Vec<clean::Type>) {
+    match type_ {
+        clean::Type::Path { path } => {
+            if !_contains_generic(type_) && !concrete_types.contains(type_) {
+                concrete_types.push(type_.clone());
+            }
+            for inner_type in path.generics().unwrap_or_default() {
+                _collect_concrete_types(inner_type, concrete_types);
+            }
+        }
+        clean::Type::BorrowedRef { type_: inner_type, .. }
+        | clean::Type::RawPointer(_, inner_type)
+        | clean::Type::Slice(inner_type)
+        | clean::Type::Array(inner_type, _) => _collect_concrete_types(inner_type, concrete_types),
+        clean::Type::Tuple(inner_types) => {
+            for inner_type in inner_types {
+                _collect_concrete_types(inner_type, concrete_types);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// 类型里有没有泛型参数（包括Self），关联类型和impl Trait也算，它们都替换不了
+fn _contains_generic(type_: &clean::Type) -> bool {
+    match type_ {
+        clean::Type::Generic(_)
+        | clean::Type::QPath(_)
+        | clean::Type::ImplTrait(_)
+        | clean::Type::Infer => true,
+        clean::Type::Path { path } => path
+            .generics()
+            .unwrap_or_default()
+            .into_iter()
+            .any(|inner_type| _contains_generic(inner_type)),
+        clean::Type::BorrowedRef { type_: inner_type, .. }
+        | clean::Type::RawPointer(_, inner_type)
+        | clean::Type::Slice(inner_type)
+        | clean::Type::Array(inner_type, _) => _contains_generic(inner_type),
+        clean::Type::Tuple(inner_types) => {
+            inner_types.iter().any(|inner_type| _contains_generic(inner_type))
+        }
+        _ => false,
+    }
+}
+
+/// 用具体类型匹配impl的类型，匹配上的时候把泛型参数对应的类型记到substitutions里
+fn _match_generic_type(
+    pattern: &clean::Type,
+    concrete_type: &clean::Type,
+    substitutions: &mut FxHashMap<Symbol, clean::Type>,
+) -> bool {
+    match (pattern, concrete_type) {
+        (clean::Type::Generic(name), _) => match substitutions.get(name) {
+            Some(substitution) => substitution == concrete_type,
+            None => {
+                substitutions.insert(*name, concrete_type.clone());
+                true
+            }
+        },
+        (clean::Type::Path { path: pattern_path }, clean::Type::Path { path: concrete_path }) => {
+            if pattern_path.def_id() != concrete_path.def_id() {
+                return false;
+            }
+            let pattern_args = pattern_path.generics().unwrap_or_default();
+            let concrete_args = concrete_path.generics().unwrap_or_default();
+            pattern_args.len() == concrete_args.len()
+                && pattern_args.iter().zip(concrete_args.iter()).all(
+                    |(pattern_arg, concrete_arg)| {
+                        _match_generic_type(pattern_arg, concrete_arg, substitutions)
+                    },
+                )
+        }
+        (
+            clean::Type::BorrowedRef { mutability: pattern_mut, type_: pattern_inner, .. },
+            clean::Type::BorrowedRef { mutability: concrete_mut, type_: concrete_inner, .. },
+        ) => {
+            pattern_mut == concrete_mut
+                && _match_generic_type(pattern_inner, concrete_inner, substitutions)
+        }
+        (clean::Type::Slice(pattern_inner), clean::Type::Slice(concrete_inner)) => {
+            _match_generic_type(pattern_inner, concrete_inner, substitutions)
+        }
+        (clean::Type::Tuple(pattern_types), clean::Type::Tuple(concrete_types)) => {
+            pattern_types.len() == concrete_types.len()
+                && pattern_types.iter().zip(concrete_types.iter()).all(
+                    |(pattern_type, concrete_type)| {
+                        _match_generic_type(pattern_type, concrete_type, substitutions)
+                    },
+                )
+        }
+        _ => pattern == concrete_type,
+    }
+}
+
+/// impl块上的类型泛型参数是不是都有了对应的具体类型，const泛型参数没法从类型里得到
+fn _resolves_all_type_params(
+    generics: &clean::Generics,
+    substitutions: &FxHashMap<Symbol, clean::Type>,
+) -> bool {
+    generics.params.iter().all(|param| match param.kind {
+        clean::GenericParamDefKind::Lifetime { .. } => true,
+        clean::GenericParamDefKind::Type { .. } => substitutions.contains_key(&param.name),
+        clean::GenericParamDefKind::Const { .. } => false,
+    })
+}
+
+/// 替换成的具体类型是否满足impl块上的bound
+fn _satisfies_impl_bounds(
+    impl_: &clean::Impl,
+    substitutions: &FxHashMap<Symbol, clean::Type>,
+    tcx: TyCtxt<'_>,
+) -> bool {
+    substitutions.iter().all(|(name, type_)| {
+        let bounds = generic_candidate::bounds_of(&impl_.generics, *name);
+        bounds.is_empty() || generic_candidate::type_satisfies(type_, &bounds, tcx)
+    })
+}
+
+/// 把impl块里的泛型参数都换成具体类型，去掉已经替换的泛型参数和where子句
+fn _instantiate_impl(
+    impl_: &clean::Impl,
+    substitutions: &FxHashMap<Symbol, clean::Type>,
+) -> clean::Impl {
+    let mut instance = impl_.clone();
+    instance.generics.params.retain(|param| !substitutions.contains_key(&param.name));
+    instance.generics.where_predicates.clear();
+    instance.for_ = _substitute_generics(&impl_.for_, substitutions);
+    instance.trait_ =
+        impl_.trait_.as_ref().map(|trait_| _substitute_path_generics(trait_, substitutions));
+    instance.kind = clean::ImplKind::Normal;
+    for item in instance.items.iter_mut() {
+        if let clean::MethodItem(function, _) = &mut *item.kind {
+            for argument in function.decl.inputs.values.iter_mut() {
+                argument.type_ = _substitute_generics(&argument.type_, substitutions);
+            }
+            if let clean::FnRetTy::Return(output) = &mut function.decl.output {
+                *output = _substitute_generics(output, substitutions);
+            }
+        }
+    }
+    instance
+}
+
+/// 把类型里的泛型参数换成substitutions里的类型，没有对应的（比如方法自己的泛型参数）保持原样
+fn _substitute_generics(
+    type_: &clean::Type,
+    substitutions: &FxHashMap<Symbol, clean::Type>,
+) -> clean::Type {
+    match type_ {
+        clean::Type::Generic(name) => match substitutions.get(name) {
+            Some(substitution) => substitution.clone(),
+            None => type_.clone(),
+        },
+        clean::Type::Path { path } => {
+            clean::Type::Path { path: _substitute_path_generics(path, substitutions) }
+        }
+        clean::Type::BorrowedRef { lifetime, mutability, type_: inner_type } => {
+            clean::Type::BorrowedRef {
+                lifetime: lifetime.clone(),
+                mutability: *mutability,
+                type_: Box::new(_substitute_generics(inner_type, substitutions)),
+            }
+        }
+        clean::Type::RawPointer(mutability, inner_type) => clean::Type::RawPointer(
+            *mutability,
+            Box::new(_substitute_generics(inner_type, substitutions)),
+        ),
+        clean::Type::Slice(inner_type) => {
+            clean::Type::Slice(Box::new(_substitute_generics(inner_type, substitutions)))
+        }
+        clean::Type::Array(inner_type, length) => clean::Type::Array(
+            Box::new(_substitute_generics(inner_type, substitutions)),
+            length.clone(),
+        ),
+        clean::Type::Tuple(inner_types) => clean::Type::Tuple(
+            inner_types
+                .iter()
+                .map(|inner_type| _substitute_generics(inner_type, substitutions))
+                .collect(),
+        ),
+        clean::Type::QPath(qpath) => {
+            let mut new_qpath = qpath.clone();
+            new_qpath.self_type = _substitute_generics(&qpath.self_type, substitutions);
+            new_qpath.trait_ = _substitute_path_generics(&qpath.trait_, substitutions);
+            clean::Type::QPath(new_qpath)
+        }
+        _ => type_.clone(),
+    }
+}
+
+fn _substitute_path_generics(
+    path: &clean::Path,
+    substitutions: &FxHashMap<Symbol, clean::Type>,
+) -> clean::Path {
+    let mut new_path = path.clone();
+    for segment in new_path.segments.iter_mut() {
+        match &mut segment.args {
+            clean::GenericArgs::AngleBracketed { args, .. } => {
+                for arg in args.iter_mut() {
+                    if let clean::GenericArg::Type(arg_type) = arg {
+                        *arg_type = _substitute_generics(arg_type, substitutions);
+                    }
+                }
+            }
+            clean::GenericArgs::Parenthesized { inputs, output } => {
+                for input_type in inputs.iter_mut() {
+                    *input_type = _substitute_generics(input_type, substitutions);
+                }
+                if let Some(output_type) = output {
+                    **output_type = _substitute_generics(output_type, substitutions);
+                }
+            }
+        }
+    }
+    new_path
+}
+
+/// 建立trait到实现类型的索引，dyn Trait参数可以用任意一个实现类型的值转换得到。
+/// 只记录公开的非泛型类型，带泛型参数的impl没法确定具体用哪个类型
+fn _record_implementors(
+    type_impl_maps: &FxHashMap<DefId, Vec<formats::Impl>>,
+    full_name_map: &mut FullNameMap,
+) {
+    for (did, impls) in type_impl_maps {
+        if full_name_map._get_full_name(*did) == None {
+            continue;
+        }
+        for impl_ in impls {
+            let impl_ = impl_.inner_impl();
+            let trait_ = match &impl_.trait_ {
+                Some(trait_) => trait_,
+                None => continue,
+            };
+            if impl_.polarity == ImplPolarity::Negative || !impl_.generics.params.is_empty() {
+                continue;
+            }
+            if let clean::Type::Path { .. } = impl_.for_ {
+                full_name_map.push_implementor(trait_.def_id(), &impl_.for_);
+            }
+        }
+    }
+}
+
+static DEREF_TRAIT: &'static str = "core::ops::deref::Deref";
+static AS_REF_TRAIT: &'static str = "core::convert::AsRef";
+static BORROW_TRAIT: &'static str = "core::borrow::Borrow";
+static FROM_TRAIT: &'static str = "core::convert::From";
+
+/// 记录本crate类型的Deref impl里的Target，以及AsRef<T>、Borrow<T>、From<T>里的T，只考虑没有泛型参数的impl
+fn _record_conversion_targets(cache: &Cache, full_name_map: &mut FullNameMap) {
+    for (did, impls) in &cache.impls {
+        if !did.is_local() || full_name_map._get_full_name(*did) == None {
+            continue;
+        }
+        for impl_ in impls {
+            let impl_ = impl_.inner_impl();
+            let Some(trait_) = &impl_.trait_ else {
+                continue;
+            };
+            if _has_type_generics(&impl_.generics) {
+                continue;
+            }
+            let Some((strings, _)) = cache.external_paths.get(&trait_.def_id()) else {
+                continue;
+            };
+            let trait_name = full_path(strings);
+            if trait_name == DEREF_TRAIT {
+                for item in &impl_.items {
+                    if let clean::AssocTypeItem(typedef, _) = &*item.kind {
+                        full_name_map.deref_targets.insert(*did, typedef.type_.clone());
+                    }
+                }
+                continue;
+            }
+            let Some(target_type) = trait_.generics().and_then(|args| args.first().cloned()) else {
+                continue;
+            };
+            if trait_name == AS_REF_TRAIT {
+                full_name_map.as_ref_targets.entry(*did).or_default().push(target_type.clone());
+            } else if trait_name == BORROW_TRAIT {
+                full_name_map.borrow_targets.entry(*did).or_default().push(target_type.clone());
+            } else if trait_name == FROM_TRAIT {
+                full_name_map.from_sources.entry(*did).or_default().push(target_type.clone());
+            }
+        }
+    }
+}
+
+/// 记录impl Trait for Type，trait可能来自外部crate（比如Iterator、Clone），
+/// 所以trait的路径要从paths和external_paths里面找
+fn _record_type_trait_impl(
+    impl_: &clean::Impl,
+    cache: &Cache,
+    full_name_map: &FullNameMap,
+    api_graph: &mut ApiGraph<'_>,
+) {
+    let trait_ = match &impl_.trait_ {
+        Some(trait_) => trait_,
+        None => return,
+    };
+    let trait_def_id = trait_.def_id();
+    let trait_full_name = match cache.paths.get(&trait_def_id) {
+        Some((strings, _)) => full_path(strings),
+        None => match cache.external_paths.get(&trait_def_id) {
+            Some((strings, _)) => full_path(strings),
+            None => return,
+        },
+    };
+    let type_full_name = match impl_.for_.def_id(cache) {
+        Some(def_id) => match full_name_map._get_full_name(def_id) {
+            Some(type_full_name) => type_full_name.clone(),
+            None => return,
+        },
+        None => return,
+    };
+    api_graph.add_type_trait_impl(&type_full_name, &trait_full_name);
+    foreign_trait::add_trait_surface(impl_, &trait_full_name, api_graph);
+}
+
 fn full_path(paths: &Vec<Symbol>) -> String {
     let mut full = String::new();
     match paths.first() {
@@ -181,9 +773,35 @@ pub(crate) fn _analyse_impl(
         Some(trait_) => {
             //println!("{:?}", trait_);
             let trait_ty_def_id = &trait_.def_id();
-            let trait_full_name = full_name_map._get_full_name(*trait_ty_def_id);
-            if let Some(trait_name) = trait_full_name { Some(trait_name.clone()) } else { None }
+            match full_name_map._get_full_name(*trait_ty_def_id) {
+                Some(trait_name) => Some(trait_name.clone()),
+                //外部crate的trait（Read、Iterator、From等），blanket impl、auto trait和derive出来的impl不管
+                None if impl_.kind.is_blanket()
+                    || impl_.kind.is_auto()
+                    || _is_automatically_derived(impl_, tcx) =>
+                {
+                    None
+                }
+                None => api_util::_def_path_name(*trait_ty_def_id, cache, full_name_map),
+            }
+        }
+    };
+    //impl块没有类型泛型参数的时候，trait方法用`<Type as Trait>::method`调用
+    //实例化过的泛型类型上的方法写成`<Wrapper<u8>>::method`，泛型参数只在返回值里出现的时候才推断得出来
+    let trait_dispatch = match &impl_.trait_ {
+        Some(trait_) if trait_full_name.is_some() && !_has_type_generics(&impl_.generics) => {
+            Some(format!(
+                "<{} as {}>",
+                api_util::_type_name(&impl_.for_, cache, full_name_map),
+                api_util::_path_name(trait_, cache, full_name_map)
+            ))
+        }
+        None if impl_.for_.generics().map_or(false, |args| !args.is_empty())
+            && !_contains_generic(&impl_.for_) =>
+        {
+            Some(format!("<{}>", api_util::_type_name(&impl_.for_, cache, full_name_map)))
         }
+        _ => None,
     };
 
     let impl_ty_def_id = &impl_.for_.def_id(cache);
@@ -207,6 +825,9 @@ pub(crate) fn _analyse_impl(
                 println!("function name in impl:{:?}", function_name);
             }
             clean::MethodItem(_method, _) => {
+                if api_graph.skips_item(tcx, item) {
+                    continue;
+                }
                 let decl = _method.decl.clone();
                 let clean::FnDecl { inputs, output, .. } = decl;
                 let _generics = _method.generics.clone();
@@ -266,8 +887,10 @@ pub(crate) fn _analyse_impl(
                 method_name.push_str(item.name.as_ref().unwrap().as_str());
                 //println!("method name in impl:{:?}", method_name);
 
-                let api_unsafety =
-                    ApiUnsafety::_get_unsafety_from_fnheader(&item.fn_header(tcx).unwrap());
+                let fn_header = item.fn_header(tcx).unwrap();
+                let api_unsafety = ApiUnsafety::_get_unsafety_from_fnheader(&fn_header);
+                let api_abi = ApiAbi::_get_abi_from_fnheader(&fn_header);
+                let api_asyncness = ApiAsyncness::_get_asyncness_from_fnheader(&fn_header);
 
                 let visibility = match item.visibility(tcx) {
                     Some(visib) => visib.expect_local(),
@@ -277,6 +900,8 @@ pub(crate) fn _analyse_impl(
                         Visibility::Public
                     }
                 };
+                let span = item.span(tcx).map(|span| span.inner());
+                let def_id = item.item_id.as_def_id();
 
                 //生成api function
                 //如果是实现了trait的话，需要把trait的全路径也包括进去
@@ -289,7 +914,14 @@ pub(crate) fn _analyse_impl(
                         output,
                         _trait_full_path: None,
                         _unsafe_tag: api_unsafety,
+                        _abi: api_abi,
+                        _asyncness: api_asyncness,
                         visibility,
+                        span,
+                        def_id,
+                        _uses_unsafe: false,
+                        complexity: None,
+                        trait_dispatch: trait_dispatch.clone(),
                     },
                     Some(_) => {
                         //println!("Method name: {}", method_name);
@@ -302,7 +934,14 @@ pub(crate) fn _analyse_impl(
                                 output,
                                 _trait_full_path: Some(real_trait_name.clone()),
                                 _unsafe_tag: api_unsafety,
+                                _abi: api_abi,
+                                _asyncness: api_asyncness,
                                 visibility,
+                                span,
+                                def_id,
+                                _uses_unsafe: false,
+                                complexity: None,
+                                trait_dispatch: trait_dispatch.clone(),
                             }
                         } else {
                             //println!("Trait not found in current crate.");
@@ -319,6 +958,66 @@ pub(crate) fn _analyse_impl(
     }
 }
 
+/// trait里有默认实现、impl块里没有重写的方法，当作impl块里的方法加到图里，
+/// 这样实现了trait的类型也有这些方法的依赖边。trait的定义从cache.traits里找，
+/// 外部crate的trait只要本crate实现了，rustdoc也会记录下来。
+/// 带类型泛型参数的trait不考虑，方法签名里的泛型参数没法替换成impl里的具体类型
+fn _analyse_provided_methods(
+    impl_: &clean::Impl,
+    cache: &Cache,
+    tcx: TyCtxt<'_>,
+    full_name_map: &FullNameMap,
+    api_graph: &mut ApiGraph<'_>,
+) {
+    let Some(trait_) = &impl_.trait_ else {
+        return;
+    };
+    if impl_.kind.is_blanket() || impl_.kind.is_auto() || _is_automatically_derived(impl_, tcx) {
+        return;
+    }
+    let Some(trait_definition) = cache.traits.get(&trait_.def_id()) else {
+        return;
+    };
+    if _has_type_generics(&trait_definition.generics) {
+        return;
+    }
+    let overridden: FxHashSet<Symbol> = impl_.items.iter().filter_map(|item| item.name).collect();
+    let provided_methods: Vec<clean::Item> = trait_definition
+        .items
+        .iter()
+        .filter(|item| match (&*item.kind, item.name) {
+            (clean::MethodItem(..), Some(name)) => !overridden.contains(&name),
+            _ => false,
+        })
+        .cloned()
+        .collect();
+    if provided_methods.is_empty() {
+        return;
+    }
+    //签名里的Self在_analyse_impl里换成impl的类型
+    let mut provided_impl = impl_.clone();
+    provided_impl.items = provided_methods;
+    _analyse_impl(&provided_impl, cache, tcx, full_name_map, api_graph);
+}
+
+/// impl块是不是`#[derive]`生成的，用impl里第一个item的父节点找到impl块
+fn _is_automatically_derived(impl_: &clean::Impl, tcx: TyCtxt<'_>) -> bool {
+    impl_
+        .items
+        .first()
+        .and_then(|item| item.item_id.as_def_id())
+        .and_then(|def_id| tcx.opt_parent(def_id))
+        .map_or(false, |impl_def_id| tcx.has_attr(impl_def_id, sym::automatically_derived))
+}
+
+/// 是否有生命周期以外的泛型参数
+fn _has_type_generics(generics: &clean::Generics) -> bool {
+    generics
+        .params
+        .iter()
+        .any(|param| !matches!(param.kind, clean::GenericParamDefKind::Lifetime { .. }))
+}
+
 //递归判断一个参数是否是self类型的
 //FIXME: 考虑在resolved path里面的括号里面可能存在self type
 fn is_param_self_type(ty_: &clean::Type) -> bool {
@@ -364,6 +1063,8 @@ fn is_param_self_type(ty_: &clean::Type) -> bool {
             }
             return false;
         }
+        //Self::Item这样的关联类型
+        clean::Type::QPath(qpath) => is_param_self_type(&qpath.self_type),
         _ => {
             return false;
         }
@@ -457,6 +1158,12 @@ fn replace_self_type(self_type: &clean::Type, impl_type: &clean::Type) -> clean:
             let new_type = clean::Type::Path { path: new_path };
             return new_type;
         }
+        //Self::Item换成<Type as Trait>::Item
+        clean::Type::QPath(qpath) => {
+            let mut new_qpath = qpath.clone();
+            new_qpath.self_type = replace_self_type(&qpath.self_type, impl_type);
+            return clean::Type::QPath(new_qpath);
+        }
         _ => {
             return self_type.clone();
         }
@@ -0,0 +1,90 @@
+//! --fuzz-internal：在待测crate内部生成fuzz target，pub(crate)、私有模块里的API也可以测
+//! 每个序列的测试函数和解码输入的代码放进测试目录下fries_internal.rs的一个子模块，
+//! 待测crate的lib.rs里加上
+//!     #[cfg(fuzzing)]
+//!     #[path = "<测试目录>/fries_internal.rs"]
+//!     pub mod fries_internal;
+//! cargo afl build会加上`--cfg fuzzing`，这个模块就会编译进待测crate，
+//! afl_files、replay_files、libfuzzer_files下的target只调用`<crate>::fries_internal::<target>::fuzz_one(data)`。
+//! fries_internal是crate根的子模块，能访问的API见ModVisibity::is_visible_from，
+//! 生成的代码里crate名换成`crate`，用到的arbitrary、tokio等依赖待测crate自己也要有
+
+use crate::fuzz_targets_gen::api_graph::ApiGraph;
+use crate::fuzz_targets_gen::api_sequence::ApiSequence;
+use crate::fuzz_targets_gen::encoding::EncodingVariant;
+use crate::fuzz_targets_gen::replay_util;
+use regex::Regex;
+
+/// 放测试函数的模块名
+pub(crate) static INTERNAL_MODULE: &'static str = "fries_internal";
+/// 测试目录下模块的文件名
+pub(crate) static INTERNAL_MODULE_FILE: &'static str = "fries_internal.rs";
+
+/// 生成代码所在的模块路径，过滤API的时候按从这里能不能访问判断
+pub(crate) fn viewer_module(crate_name: &str) -> String {
+    format!("{}::{}", crate_name, INTERNAL_MODULE)
+}
+
+/// 一个序列在fries_internal.rs里的子模块：原来fuzz target里main以外的部分，
+/// 加上解码输入、调用测试函数的`pub fn fuzz_one(data: &[u8])`
+pub(crate) fn target_module(
+    sequence: &ApiSequence,
+    api_graph: &ApiGraph<'_>,
+    test_index: usize,
+    encoding: EncodingVariant,
+    module_name: &str,
+) -> String {
+    let crate_name = api_graph._crate_name.as_str();
+    let body = sequence
+        ._to_afl_except_main(api_graph, test_index, encoding)
+        .replace("#[macro_use]\nextern crate afl;\n", "")
+        .replace(format!("extern crate {};\n", crate_name).as_str(), "");
+    //crate名开头的路径在crate内部要写成crate::
+    let crate_path = Regex::new(format!(r"\b{}::", regex::escape(crate_name)).as_str()).unwrap();
+    let body = crate_path.replace_all(body.as_str(), "crate::");
+    let mut res = format!("pub mod {} {{\n", module_name);
+    res.push_str(body.as_ref());
+    res.push_str("pub fn fuzz_one(data: &[u8]) {\n");
+    res.push_str(sequence._afl_closure_body(0, test_index, encoding).as_str());
+    res.push_str("}\n");
+    res.push_str("}\n\n");
+    res
+}
+
+/// 调用fuzz_one的表达式
+fn fuzz_one_call(crate_name: &str, module_name: &str) -> String {
+    format!("{}::{}::{}::fuzz_one(data);", crate_name, INTERNAL_MODULE, module_name)
+}
+
+/// afl的target
+pub(crate) fn afl_test_file(crate_name: &str, module_name: &str) -> String {
+    format!(
+        "#[macro_use]\nextern crate afl;\nextern crate {};\n\nfn main() {{\n    fuzz!(|data: &[u8]| {{\n        {}\n    }});\n}}\n",
+        crate_name,
+        fuzz_one_call(crate_name, module_name)
+    )
+}
+
+/// 复现crash的程序
+pub(crate) fn replay_file(crate_name: &str, module_name: &str) -> String {
+    format!(
+        "extern crate {};\n\n{}\nfn main() {{\n    let _content = _read_data();\n    let data = &_content;\n    println!(\"data = {{:?}}\", data);\n    println!(\"data len = {{:?}}\", data.len());\n    {}\n}}\n",
+        crate_name,
+        replay_util::_read_crash_file_data(),
+        fuzz_one_call(crate_name, module_name)
+    )
+}
+
+/// libfuzzer的target
+pub(crate) fn libfuzzer_test_file(crate_name: &str, module_name: &str) -> String {
+    format!(
+        "#![no_main]\n#[macro_use]\nextern crate libfuzzer_sys;\nextern crate {};\n\nfuzz_target!(|data: &[u8]| {{\n    {}\n}});\n",
+        crate_name,
+        fuzz_one_call(crate_name, module_name)
+    )
+}
+
+/// 待测crate的lib.rs里要加上的声明
+pub(crate) fn include_declaration(module_file: &str) -> String {
+    format!("#[cfg(fuzzing)]\n#[path = \"{}\"]\npub mod {};\n", module_file, INTERNAL_MODULE)
+}
@@ -0,0 +1,406 @@
+//! 解释器模式的fuzz target
+//! 普通模式下每个fuzz target固定一条调用序列，调用顺序由我们生成；
+//! 解释器模式下每个类型只生成一个fuzz target，把fuzz输入当成一串操作来解码：
+//!     选择构造函数的字节 + 构造函数的参数
+//!     (选择方法的字节 + 方法的参数) * n
+//! 这样调用顺序和调用次数交给fuzzer自己去探索
+//! 只考虑除self之外所有参数都能从输入里直接解码出来的方法
+
+use crate::clean::{self, PrimitiveType};
+use crate::fuzz_targets_gen::api_function::ApiFunction;
+use crate::fuzz_targets_gen::api_graph::ApiGraph;
+use crate::fuzz_targets_gen::api_util;
+use crate::fuzz_targets_gen::call_type::CallType;
+use crate::fuzz_targets_gen::fuzz_type::{self, FuzzableType};
+use crate::fuzz_targets_gen::invariant;
+use crate::fuzz_targets_gen::prelude_type::{_PreludeHelper, PreludeType};
+use rustc_data_structures::fx::FxHashMap;
+use rustc_hir::Mutability;
+
+// 一个fuzz target里最多执行的操作数，避免超长的输入拖慢fuzz
+static INTERPRETER_MAX_OPERATIONS: usize = 1024;
+// 选择器只有一个字节，最多分发到256个构造函数/方法
+static INTERPRETER_MAX_CHOICES: usize = 256;
+
+/// 方法怎么拿到状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StateUse {
+    Ref,
+    MutRef,
+    /// 按值传入并返回新的状态，比如`fn push(self, x) -> Self`
+    Replace,
+}
+
+/// 一个类型的解释器：构造函数和方法在api_functions中的下标
+#[derive(Debug, Clone)]
+pub(crate) struct InterpreterInfo {
+    pub(crate) type_name: String,
+    constructors: Vec<usize>,
+    methods: Vec<(usize, StateUse)>,
+}
+
+/// 从输入里解码一个参数时调用的`_Input`方法
+fn reader_method(fuzzable_type: &FuzzableType) -> Option<&'static str> {
+    match fuzzable_type {
+        FuzzableType::Primitive(primitive_type) => match primitive_type {
+            PrimitiveType::U8 => Some("_u8"),
+            PrimitiveType::I8 => Some("_i8"),
+            PrimitiveType::U16 => Some("_u16"),
+            PrimitiveType::I16 => Some("_i16"),
+            PrimitiveType::U32 => Some("_u32"),
+            PrimitiveType::I32 => Some("_i32"),
+            PrimitiveType::U64 => Some("_u64"),
+            PrimitiveType::I64 => Some("_i64"),
+            PrimitiveType::U128 => Some("_u128"),
+            PrimitiveType::I128 => Some("_i128"),
+            PrimitiveType::Usize => Some("_usize"),
+            PrimitiveType::Isize => Some("_isize"),
+            PrimitiveType::F32 => Some("_f32"),
+            PrimitiveType::F64 => Some("_f64"),
+            PrimitiveType::Bool => Some("_bool"),
+            PrimitiveType::Char => Some("_char"),
+            _ => None,
+        },
+        FuzzableType::CornerFloat(PrimitiveType::F32) => Some("_f32"),
+        FuzzableType::CornerFloat(PrimitiveType::F64) => Some("_f64"),
+        FuzzableType::RefStr => Some("_str"),
+        FuzzableType::RefSlice(inner_type) => match &**inner_type {
+            FuzzableType::Primitive(PrimitiveType::U8) => Some("_bytes"),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// 参数能否从输入里解码，能的话返回(`_Input`的方法, 传参方式)
+fn decodable_param(type_: &clean::Type, graph: &ApiGraph<'_>) -> Option<(&'static str, CallType)> {
+    let (fuzzable_type, call_type) = fuzz_type::fuzzable_call_type(
+        type_,
+        graph.cache,
+        &graph.full_name_map,
+        &graph.emit_options,
+        None,
+    )
+    .generate_fuzzable_type_and_call_type();
+    match call_type {
+        CallType::_NotCompatible
+        | CallType::_ConstRawPointer(..)
+        | CallType::_MutRawPointer(..) => return None,
+        _ => {}
+    }
+    //解释器里没有_temp_file_path这些辅助函数
+    if !_PreludeHelper::_from_call_type(&call_type).is_empty() {
+        return None;
+    }
+    Some((reader_method(&fuzzable_type)?, call_type))
+}
+
+fn params_decodable(params: &[clean::Type], graph: &ApiGraph<'_>) -> bool {
+    params.iter().all(|param| decodable_param(param, graph).is_some())
+}
+
+/// 解释器里不调用unsafe函数、泛型函数和async fn
+fn interpretable_function(api_function: &ApiFunction) -> bool {
+    !api_function._unsafe_tag._is_unsafe()
+        && !api_function._is_generic_function()
+        && !api_function._asyncness._is_async()
+}
+
+/// 方法的第一个参数是不是状态类型，以及怎么传入状态
+fn state_use(
+    api_function: &ApiFunction,
+    graph: &ApiGraph<'_>,
+    type_name: &String,
+) -> Option<StateUse> {
+    let self_type = api_function.inputs.first()?;
+    let type_path =
+        |type_: &clean::Type| invariant::type_full_path(type_, graph.cache, &graph.full_name_map);
+    match self_type {
+        clean::Type::BorrowedRef { mutability, type_, .. } => {
+            if !matches!(&**type_, clean::Type::Path { .. })
+                || type_path(type_).as_ref() != Some(type_name)
+            {
+                return None;
+            }
+            match mutability {
+                Mutability::Mut => Some(StateUse::MutRef),
+                Mutability::Not => Some(StateUse::Ref),
+            }
+        }
+        clean::Type::Path { .. } => {
+            //按值传入的话，只有返回同样类型（不是Option/Result）的才能接着用
+            let output_type = api_function.output.as_ref()?;
+            if !matches!(output_type, clean::Type::Path { .. })
+                || !matches!(
+                    PreludeType::from_type(output_type, graph.cache, &graph.full_name_map),
+                    PreludeType::NotPrelude(..)
+                )
+            {
+                return None;
+            }
+            if type_path(self_type).as_ref() == Some(type_name)
+                && type_path(output_type).as_ref() == Some(type_name)
+            {
+                Some(StateUse::Replace)
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
+/// 找到所有可以生成解释器的类型：至少有一个构造函数和一个方法
+pub(crate) fn find_interpreters(graph: &ApiGraph<'_>) -> Vec<InterpreterInfo> {
+    let mut interpreters: FxHashMap<String, InterpreterInfo> = FxHashMap::default();
+    let new_info = |type_name: &String| InterpreterInfo {
+        type_name: type_name.clone(),
+        constructors: Vec::new(),
+        methods: Vec::new(),
+    };
+
+    //构造函数：参数都能解码，返回值是T、Option<T>或者Result<T, E>
+    for (index, api_function) in graph.api_functions.iter().enumerate() {
+        if !interpretable_function(api_function) || !params_decodable(&api_function.inputs, graph) {
+            continue;
+        }
+        let output_type = match &api_function.output {
+            Some(output_type @ clean::Type::Path { .. }) => output_type,
+            _ => continue,
+        };
+        //Option/Result只解开一层，里面必须是当前crate里定义的类型
+        let state_type = PreludeType::from_type(output_type, graph.cache, &graph.full_name_map)
+            ._get_final_type();
+        if !matches!(
+            PreludeType::from_type(&state_type, graph.cache, &graph.full_name_map),
+            PreludeType::NotPrelude(..)
+        ) {
+            continue;
+        }
+        match state_type.def_id(graph.cache) {
+            Some(def_id) if graph.cache.paths.contains_key(&def_id) => {}
+            _ => continue,
+        }
+        let type_name =
+            match invariant::type_full_path(&state_type, graph.cache, &graph.full_name_map) {
+                Some(type_name) => type_name,
+                None => continue,
+            };
+        interpreters
+            .entry(type_name.clone())
+            .or_insert_with(|| new_info(&type_name))
+            .constructors
+            .push(index);
+    }
+
+    //方法：第一个参数是状态，其他参数都能解码
+    for (index, api_function) in graph.api_functions.iter().enumerate() {
+        if !interpretable_function(api_function)
+            || api_function.inputs.is_empty()
+            || !params_decodable(&api_function.inputs[1..], graph)
+        {
+            continue;
+        }
+        for (type_name, interpreter) in interpreters.iter_mut() {
+            if let Some(state_use) = state_use(api_function, graph, type_name) {
+                interpreter.methods.push((index, state_use));
+                break;
+            }
+        }
+    }
+
+    let mut res = interpreters
+        .into_iter()
+        .map(|(_, mut interpreter)| {
+            interpreter.constructors.truncate(INTERPRETER_MAX_CHOICES);
+            interpreter.methods.truncate(INTERPRETER_MAX_CHOICES);
+            interpreter
+        })
+        .filter(|interpreter| !interpreter.methods.is_empty())
+        .collect::<Vec<_>>();
+    res.sort_by(|x, y| x.type_name.cmp(&y.type_name));
+    res
+}
+
+/// 解码参数并调用函数，返回(解码参数的语句, 调用表达式)
+/// 第一个参数由state_arg给出的话，从第二个参数开始解码
+fn decode_and_call(
+    graph: &ApiGraph<'_>,
+    function_index: usize,
+    state_arg: Option<String>,
+    indent: &String,
+) -> (String, String) {
+    let api_function = &graph.api_functions[function_index];
+    let mut decode_lines = String::new();
+    let mut args = Vec::new();
+    let skip = if let Some(state_arg) = state_arg {
+        args.push(state_arg);
+        1
+    } else {
+        0
+    };
+    for (j, param) in api_function.inputs.iter().enumerate().skip(skip) {
+        //find_interpreters里已经检查过所有参数都能解码
+        let (method, call_type) = decodable_param(param, graph).unwrap();
+        let arg_name = format!("_arg{}", j);
+        let mut_tag = if api_util::_need_mut_tag(&call_type) { "mut " } else { "" };
+        decode_lines.push_str(
+            format!("{}let {}{} = _input.{}()?;\n", indent, mut_tag, arg_name, method).as_str(),
+        );
+        args.push(call_type._to_call_string(&arg_name, graph.cache, &graph.full_name_map));
+    }
+    let call = match graph.struct_literals.get(&api_function.full_name) {
+        Some(field_names) => {
+            api_util::_struct_literal_string(&api_function.full_name, field_names, &args)
+        }
+        //解释器的函数返回Option，解析失败时用?结束
+        None => match graph.from_str_producers.get(&api_function.full_name) {
+            Some(type_name) => format!("({}).parse::<{}>().ok()?", args[0], type_name),
+            None => match graph.const_producers.get(&api_function.full_name) {
+                Some(expression) => expression.clone(),
+                None => format!("{}({})", api_function._call_path(), args.join(", ")),
+            },
+        },
+    };
+    (decode_lines, call)
+}
+
+/// 生成一个类型的解释器fuzz target
+pub(crate) fn interpreter_test_file(graph: &ApiGraph<'_>, interpreter: &InterpreterInfo) -> String {
+    let mut res = String::new();
+    res.push_str("#[macro_use]\n");
+    res.push_str("extern crate afl;\n");
+    res.push_str(format!("extern crate {};\n\n", graph._crate_name).as_str());
+    res.push_str(format!("// interpreter for {}\n", interpreter.type_name).as_str());
+    res.push_str(input_reader(graph).as_str());
+
+    let indent = "        ".to_string();
+    let need_mut = interpreter.methods.iter().any(|(_, state_use)| *state_use != StateUse::Ref);
+    let mut_tag = if need_mut { "mut " } else { "" };
+    res.push_str("fn _interpret(data: &[u8]) -> Option<()> {\n");
+    res.push_str("    let mut _input = _Input { data, offset: 0 };\n");
+
+    //构造函数
+    res.push_str(
+        format!(
+            "    let {}_state = match (_input._u8()? as usize) % {} {{\n",
+            mut_tag,
+            interpreter.constructors.len()
+        )
+        .as_str(),
+    );
+    for (choice, constructor) in interpreter.constructors.iter().enumerate() {
+        let (decode_lines, call) = decode_and_call(graph, *constructor, None, &indent);
+        let output_type = graph.api_functions[*constructor].output.as_ref().unwrap();
+        let unwrap_string =
+            match PreludeType::from_type(output_type, graph.cache, &graph.full_name_map) {
+                PreludeType::NotPrelude(..) => "",
+                PreludeType::PreludeOption(..) => "?",
+                PreludeType::PreludeResult { .. } => ".ok()?",
+            };
+        res.push_str(format!("        {} => {{\n", choice).as_str());
+        res.push_str(decode_lines.as_str());
+        res.push_str(format!("{}{}{}\n", indent, call, unwrap_string).as_str());
+        res.push_str("        }\n");
+    }
+    res.push_str("        _ => unreachable!(),\n");
+    res.push_str("    };\n");
+
+    //方法
+    res.push_str(
+        format!(
+            "    for _ in 0..{} {{\n        match (_input._u8()? as usize) % {} {{\n",
+            INTERPRETER_MAX_OPERATIONS,
+            interpreter.methods.len()
+        )
+        .as_str(),
+    );
+    let method_indent = "                ".to_string();
+    for (choice, (method, state_use)) in interpreter.methods.iter().enumerate() {
+        let state_arg = match state_use {
+            StateUse::Ref => "&_state",
+            StateUse::MutRef => "&mut _state",
+            StateUse::Replace => "_state",
+        };
+        let (decode_lines, call) =
+            decode_and_call(graph, *method, Some(state_arg.to_string()), &method_indent);
+        res.push_str(format!("            {} => {{\n", choice).as_str());
+        res.push_str(decode_lines.as_str());
+        match state_use {
+            StateUse::Replace => {
+                res.push_str(format!("{}_state = {};\n", method_indent, call).as_str())
+            }
+            _ => res.push_str(format!("{}let _ = {};\n", method_indent, call).as_str()),
+        }
+        res.push_str("            }\n");
+    }
+    res.push_str("            _ => unreachable!(),\n");
+    res.push_str("        }\n");
+    res.push_str("    }\n");
+    res.push_str("    Some(())\n");
+    res.push_str("}\n\n");
+
+    res.push_str("fn main() {\n");
+    res.push_str("    fuzz!(|data: &[u8]| {\n");
+    res.push_str("        let _ = _interpret(data);\n");
+    res.push_str("    });\n");
+    res.push_str("}\n");
+    res
+}
+
+/// 按顺序解码输入的辅助结构，输入用完时返回None，解释器随之结束
+/// &str和&[u8]前面有两个字节的长度，长度受size_caps的限制
+fn input_reader(graph: &ApiGraph<'_>) -> String {
+    let size_caps = &graph.emit_options.size_caps;
+    let max_len = if size_caps.enabled { size_caps.max_decoded_len } else { u16::MAX as usize };
+    format!(
+        "struct _Input<'a> {{
+    data: &'a [u8],
+    offset: usize,
+}}
+
+macro_rules! _read_number {{
+    ($($name:ident: $t:ty),*) => {{
+        $(fn $name(&mut self) -> Option<$t> {{
+            let bytes = self._take(std::mem::size_of::<$t>())?;
+            Some(<$t>::from_le_bytes(bytes.try_into().ok()?))
+        }})*
+    }};
+}}
+
+#[allow(dead_code)]
+impl<'a> _Input<'a> {{
+    fn _take(&mut self, len: usize) -> Option<&'a [u8]> {{
+        if self.data.len() - self.offset < len {{
+            return None;
+        }}
+        let res = &self.data[self.offset..self.offset + len];
+        self.offset += len;
+        Some(res)
+    }}
+    _read_number!(_u8: u8, _i8: i8, _u16: u16, _i16: i16, _u32: u32, _i32: i32, _u64: u64,
+        _i64: i64, _u128: u128, _i128: i128, _usize: usize, _isize: isize, _f32: f32, _f64: f64);
+    fn _bool(&mut self) -> Option<bool> {{
+        Some(self._u8()? % 2 == 0)
+    }}
+    fn _char(&mut self) -> Option<char> {{
+        let char_data = self._take(4)?;
+        let valid_length = match std::str::from_utf8(char_data) {{
+            Ok(_) => 4,
+            Err(e) => e.valid_up_to(),
+        }};
+        std::str::from_utf8(&char_data[..valid_length]).ok()?.chars().next()
+    }}
+    fn _bytes(&mut self) -> Option<&'a [u8]> {{
+        let len = (self._u16()? as usize).min({max_len});
+        self._take(len)
+    }}
+    fn _str(&mut self) -> Option<&'a str> {{
+        std::str::from_utf8(self._bytes()?).ok()
+    }}
+}}
+
+",
+        max_len = max_len
+    )
+}
@@ -0,0 +1,68 @@
+//! 用户提供的不变式断言
+//! 配置是 类型全路径 -> 断言表达式，表达式里用`x`指代产生的值（类型是`&T`），比如
+//!     alloc::vec::Vec  |  x.len() <= x.capacity()
+//! 生成测试文件的时候，每个产生该类型的语句后面都会插入`assert!(...)`，
+//! 这样生成的fuzz target不仅能发现crash，还能检查不变式
+
+use crate::clean::{self};
+use crate::formats::cache::Cache;
+use crate::fuzz_targets_gen::file_util;
+use crate::fuzz_targets_gen::impl_util::FullNameMap;
+use crate::fuzz_targets_gen::prelude_type::{self, PreludeType};
+use rustc_data_structures::fx::FxHashMap;
+
+// 默认的不变式，左边是类型的全路径，右边是断言表达式
+lazy_static! {
+    static ref DEFAULT_INVARIANTS: FxHashMap<&'static str, &'static str> = {
+        let mut m = FxHashMap::default();
+        m.insert("alloc::vec::Vec", "x.len() <= x.capacity()");
+        m.insert("alloc::string::String", "x.len() <= x.capacity()");
+        m
+    };
+}
+
+/// 默认的不变式
+pub(crate) fn default_invariants() -> FxHashMap<String, Vec<String>> {
+    let mut res = FxHashMap::default();
+    for (type_path, assertion) in DEFAULT_INVARIANTS.iter() {
+        res.entry(type_path.to_string()).or_insert_with(Vec::new).push(assertion.to_string());
+    }
+    res
+}
+
+/// 从文件读取不变式，每行的格式是 `类型全路径  |  断言表达式`
+/// 文件不存在的话什么都不做
+pub(crate) fn load_invariants_from_file(
+    file_path: &str,
+    invariants: &mut FxHashMap<String, Vec<String>>,
+) {
+    for (type_path, assertion) in file_util::read_config_pairs(file_path) {
+        invariants.entry(type_path).or_insert_with(Vec::new).push(assertion);
+    }
+}
+
+/// 获得一个类型去掉引用之后的全路径，Option和Result取里面的类型
+pub(crate) fn type_full_path(
+    type_: &clean::Type,
+    cache: &Cache,
+    full_name_map: &FullNameMap,
+) -> Option<String> {
+    match type_ {
+        clean::Type::BorrowedRef { type_, .. } => type_full_path(type_, cache, full_name_map),
+        clean::Type::Path { .. } => {
+            if prelude_type::_prelude_type_need_special_dealing(type_, cache, full_name_map) {
+                let final_type =
+                    PreludeType::from_type(type_, cache, full_name_map)._get_final_type();
+                return type_full_path(&final_type, cache, full_name_map);
+            }
+            let def_id = type_.def_id(cache)?;
+            if let Some(full_name) = full_name_map._get_full_name(def_id) {
+                return Some(full_name.clone());
+            }
+            let (paths, _) = cache.paths.get(&def_id).or(cache.external_paths.get(&def_id))?;
+            Some(paths.iter().map(|x| x.to_string()).collect::<Vec<String>>().join("::"))
+        }
+        clean::Type::Primitive(primitive_type) => Some(primitive_type.as_sym().to_string()),
+        _ => None,
+    }
+}
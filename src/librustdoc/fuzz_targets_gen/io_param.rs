@@ -0,0 +1,77 @@
+//! Read/Write参数
+//! 编解码、解析类的crate很多API的参数是`R: Read`、`impl Read`、`&mut dyn Read`或者对应的Write，
+//! 以前泛型参数找不到满足Read的候选类型，trait object也不能fuzz，这些API都到不了。这里：
+//! 1. Read（以及BufRead、Seek）参数用`std::io::Cursor::new(data_part)`包装一段fuzz输入
+//! 2. Write参数用`Vec::<u8>::new()`，不消耗输入
+//! 泛型参数（包括impl Trait）在generic_candidate::instantiations里替换成带原来bound的ImplTrait，
+//! fuzzable_call_type遇到这样的ImplTrait或者dyn Read/dyn Write时按上面的方式构造
+
+use crate::clean;
+use crate::fuzz_targets_gen::fuzz_type::FuzzableCallType;
+use rustc_hir::TraitBoundModifier;
+
+/// 读取的trait，Cursor<&[u8]>都实现了
+static READER_TRAITS: [&'static str; 3] = ["Read", "BufRead", "Seek"];
+static WRITER_TRAITS: [&'static str; 1] = ["Write"];
+/// Cursor<&[u8]>和Vec<u8>都实现的其他trait，出现在bound里不影响
+static OTHER_TRAITS: [&'static str; 6] = ["Sized", "Send", "Sync", "Unpin", "Debug", "Clone"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum IoRole {
+    Reader,
+    Writer,
+}
+
+/// 一组trait是读还是写，有别的trait、同时要求读写或者trait是本crate定义的时候返回None
+fn io_role_of<'a>(traits: impl Iterator<Item = &'a clean::Path>) -> Option<IoRole> {
+    let mut role = None;
+    for trait_ in traits {
+        if trait_.def_id().is_local() {
+            return None;
+        }
+        let name = trait_.last();
+        let trait_role = if READER_TRAITS.contains(&name.as_str()) {
+            IoRole::Reader
+        } else if WRITER_TRAITS.contains(&name.as_str()) {
+            IoRole::Writer
+        } else if OTHER_TRAITS.contains(&name.as_str()) {
+            continue;
+        } else {
+            return None;
+        };
+        if role.map_or(false, |role| role != trait_role) {
+            return None;
+        }
+        role = Some(trait_role);
+    }
+    role
+}
+
+/// 泛型参数的bound是不是只要求Read或者Write，`?Sized`和生命周期的约束不用管
+pub(crate) fn io_role(bounds: &[&clean::GenericBound]) -> Option<IoRole> {
+    io_role_of(bounds.iter().filter_map(|bound| match bound {
+        clean::GenericBound::TraitBound(_, TraitBoundModifier::Maybe) => None,
+        clean::GenericBound::TraitBound(poly_trait, _) => Some(&poly_trait.trait_),
+        clean::GenericBound::Outlives(..) => None,
+    }))
+}
+
+/// 替换Read/Write泛型参数的类型，保留原来的bound，见fuzzable_call_type
+pub(crate) fn substituted_type(bounds: &[&clean::GenericBound]) -> clean::Type {
+    clean::Type::ImplTrait(bounds.iter().map(|bound| (*bound).clone()).collect())
+}
+
+/// impl Read、dyn Read和对应的Write，其他类型返回None
+pub(crate) fn io_call_type(type_: &clean::Type) -> Option<FuzzableCallType> {
+    let role = match type_ {
+        clean::Type::ImplTrait(bounds) => io_role(&bounds.iter().collect::<Vec<_>>())?,
+        clean::Type::DynTrait(poly_traits, _) => {
+            io_role_of(poly_traits.iter().map(|poly_trait| &poly_trait.trait_))?
+        }
+        _ => return None,
+    };
+    match role {
+        IoRole::Reader => Some(FuzzableCallType::Reader),
+        IoRole::Writer => Some(FuzzableCallType::Writer),
+    }
+}
@@ -0,0 +1,54 @@
+//! 收集API时按属性跳过的item
+//! 1. `#[doc(hidden)]`：crate不希望用户直接使用，通常是给宏展开用的内部函数，
+//!    函数自己、所在的模块/trait/impl，或者impl的类型标了都算
+//! 2. `#[deprecated]`：已经生效的弃用，函数自己或者impl的类型标了都算，`since`是以后版本的不算
+//! 分别可以用--include-doc-hidden和--include-deprecated保留
+
+use crate::clean;
+use rustc_hir::def::DefKind;
+use rustc_hir::def_id::DefId;
+use rustc_middle::middle::stability;
+use rustc_middle::ty::{DefIdTree, TyCtxt};
+
+/// impl块的类型，不是ADT的时候返回None
+fn impl_self_type(tcx: TyCtxt<'_>, def_id: DefId) -> Option<DefId> {
+    if tcx.def_kind(def_id) != DefKind::Impl {
+        return None;
+    }
+    tcx.type_of(def_id).ty_adt_def().map(|adt_def| adt_def.did())
+}
+
+fn is_deprecated(tcx: TyCtxt<'_>, def_id: DefId) -> bool {
+    tcx.lookup_deprecation(def_id).map_or(false, |depr| stability::deprecation_in_effect(&depr))
+}
+
+/// item或者它外面的模块、trait、impl（以及impl的类型）有没有标`#[doc(hidden)]`
+pub(crate) fn is_doc_hidden(tcx: TyCtxt<'_>, item: &clean::Item) -> bool {
+    item.item_id.as_def_id().map_or(false, |def_id| is_doc_hidden_def(tcx, def_id))
+}
+
+pub(crate) fn is_doc_hidden_def(tcx: TyCtxt<'_>, def_id: DefId) -> bool {
+    let mut current = Some(def_id);
+    while let Some(def_id) = current {
+        if tcx.is_doc_hidden(def_id)
+            || impl_self_type(tcx, def_id).map_or(false, |adt| tcx.is_doc_hidden(adt))
+        {
+            return true;
+        }
+        current = tcx.opt_parent(def_id);
+    }
+    false
+}
+
+/// item自己或者所在impl的类型是否已经弃用
+pub(crate) fn is_deprecated_item(tcx: TyCtxt<'_>, item: &clean::Item) -> bool {
+    item.item_id.as_def_id().map_or(false, |def_id| is_deprecated_def(tcx, def_id))
+}
+
+pub(crate) fn is_deprecated_def(tcx: TyCtxt<'_>, def_id: DefId) -> bool {
+    is_deprecated(tcx, def_id)
+        || tcx
+            .opt_parent(def_id)
+            .and_then(|parent| impl_self_type(tcx, parent))
+            .map_or(false, |adt| is_deprecated(tcx, adt))
+}
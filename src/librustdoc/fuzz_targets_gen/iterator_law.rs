@@ -0,0 +1,76 @@
+//! 返回迭代器的API，在生成的测试文件里检查迭代器需要满足的约定
+//! 1. size_hint给出的上下界要包含实际的长度
+//! 2. 实现了FusedIterator的话，next()返回None之后一直返回None
+//! 3. 实现了Clone的话，count()的结果和手动迭代的次数一致
+//! 手动迭代最多走ITERATOR_LAW_MAX_STEPS步，走不完的（可能是无限迭代器）不做检查
+//! 返回值没有被使用、又不在上面检查范围里的迭代器（`impl Iterator`、包在Option/Result里的）
+//! 也要消耗掉，迭代器是惰性的，不调用next的话里面的逻辑一步都不会执行，见consume_iterator_expression
+
+pub(crate) static ITERATOR_TRAIT: &'static str = "core::iter::traits::iterator::Iterator";
+pub(crate) static FUSED_ITERATOR_TRAIT: &'static str = "core::iter::traits::marker::FusedIterator";
+pub(crate) static CLONE_TRAIT: &'static str = "core::clone::Clone";
+
+static ITERATOR_LAW_MAX_STEPS: usize = 65536;
+
+/// 消耗没有被使用的迭代器，wrapped表示迭代器包在Option或者Result里，
+/// 和手动迭代一样最多走ITERATOR_LAW_MAX_STEPS步
+pub(crate) fn consume_iterator_expression(iterator_expr: &String, wrapped: bool) -> String {
+    if wrapped {
+        format!("({}).map(|_iter| _iter.take({}).count())", iterator_expr, ITERATOR_LAW_MAX_STEPS)
+    } else {
+        format!("({}).take({}).count()", iterator_expr, ITERATOR_LAW_MAX_STEPS)
+    }
+}
+
+/// 检查迭代器约定的语句块
+/// iterator_expr是迭代器的表达式，会被move到块里面；cloneable和fused表示迭代器实现的trait
+pub(crate) fn iterator_law_lines(
+    iterator_expr: &String,
+    iterator_name: &String,
+    cloneable: bool,
+    fused: bool,
+    indent: &String,
+) -> String {
+    let inner_indent = format!("{}    ", indent);
+    let mut lines = Vec::new();
+    lines.push(format!("let mut _iter = {};", iterator_expr));
+    if cloneable {
+        lines.push("let _iter_clone = _iter.clone();".to_string());
+    }
+    lines.push("let (_lower, _upper) = _iter.size_hint();".to_string());
+    lines.push("let mut _steps = 0usize;".to_string());
+    lines.push("let mut _finished = false;".to_string());
+    lines.push(format!(
+        "while _steps < {} {{ if _iter.next().is_none() {{ _finished = true; break; }} _steps += 1; }}",
+        ITERATOR_LAW_MAX_STEPS
+    ));
+    lines.push("if _finished {".to_string());
+    lines.push(format!(
+        "    assert!(_lower <= _steps, \"{}: size_hint lower bound {{}} > length {{}}\", _lower, _steps);",
+        iterator_name
+    ));
+    lines.push(format!(
+        "    if let Some(_upper) = _upper {{ assert!(_steps <= _upper, \"{}: size_hint upper bound {{}} < length {{}}\", _upper, _steps); }}",
+        iterator_name
+    ));
+    if fused {
+        lines.push(format!(
+            "    assert!(_iter.next().is_none(), \"{}: next() returned Some after None\");",
+            iterator_name
+        ));
+    }
+    if cloneable {
+        lines.push(format!(
+            "    assert_eq!(_iter_clone.count(), _steps, \"{}: count() disagrees with manual iteration\");",
+            iterator_name
+        ));
+    }
+    lines.push("}".to_string());
+
+    let mut res = format!("{}{{\n", indent);
+    for line in lines {
+        res.push_str(format!("{}{}\n", inner_indent, line).as_str());
+    }
+    res.push_str(format!("{}}}\n", indent).as_str());
+    res
+}
@@ -0,0 +1,596 @@
+//! 从`rustdoc --output-format json`的输出构建ApiGraph
+//! 不需要在待测crate的源码上运行修改过的rustdoc，可以直接分析任意版本crate的json文档
+//! json里的Id没有对应的DefId，这里为每个Id分配一个假的DefId，再按照in-process的方式填好Cache和FullNameMap，
+//! 之后找依赖、生成序列和in-process完全一样
+//! 目前的限制：
+//!     1. dyn Trait、impl Trait、函数指针、关联类型等类型不支持，包含这些类型的函数会被跳过
+//!     2. 泛型参数只保留名字，不保留trait bound
+//!     3. 解析json以及之后的所有操作都需要在rustc_span的session globals里进行（比如用`rustc_span::create_default_session_globals_then`包起来）
+//!     4. json里没有当前的rustc版本，`since`是以后版本的`#[deprecated]`也当作已经弃用；
+//!        impl块不知道自己在哪个模块里，模块上的`#[doc(hidden)]`只对模块里的函数生效
+
+use crate::clean::{self, PrimitiveType};
+use crate::formats::cache::Cache;
+use crate::formats::item_type::ItemType;
+use crate::fuzz_targets_gen::analyzer::GenerationConfig;
+use crate::fuzz_targets_gen::api_function::{ApiAbi, ApiAsyncness, ApiFunction, ApiUnsafety};
+use crate::fuzz_targets_gen::api_graph::ApiGraph;
+use crate::fuzz_targets_gen::impl_util::FullNameMap;
+use crate::fuzz_targets_gen::prelude_type;
+use rustc_data_structures::fx::FxHashMap;
+use rustc_hir::def::{DefKind, Res};
+use rustc_hir::def_id::{CrateNum, DefId, DefIndex, CRATE_DEF_ID, LOCAL_CRATE};
+use rustc_hir::Mutability;
+use rustc_middle::ty::Visibility;
+use rustc_span::Symbol;
+use rustdoc_json_types as types;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use thin_vec::ThinVec;
+
+/// 从json文档中解析出来的crate，ApiGraph会借用这里的Cache
+pub struct JsonCrate {
+    crate_name: String,
+    /// json文档的哈希，代替tcx.crate_hash作为图缓存的文件名，见graph_cache.rs
+    crate_hash: String,
+    cache: Cache,
+    full_name_map: FullNameMap,
+    mods: Vec<(String, Visibility)>,
+    type_trait_impls: Vec<(String, String)>,
+    functions: Vec<JsonFunction>,
+}
+
+/// json里的函数，以及收集API时按属性跳过它要用到的信息，见item_attributes.rs
+struct JsonFunction {
+    api_function: ApiFunction,
+    /// 函数自己或者impl的类型标了`#[deprecated]`
+    deprecated: bool,
+    /// 函数自己、所在的模块/impl，或者impl的类型标了`#[doc(hidden)]`
+    doc_hidden: bool,
+}
+
+impl JsonCrate {
+    /// 解析`rustdoc --output-format json`的输出
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        let krate: types::Crate =
+            serde_json::from_str(json).map_err(|e| format!("invalid rustdoc json: {}", e))?;
+        if krate.format_version != types::FORMAT_VERSION {
+            return Err(format!(
+                "unsupported rustdoc json format version {}, expected {}",
+                krate.format_version,
+                types::FORMAT_VERSION
+            ));
+        }
+        let mut hasher = DefaultHasher::new();
+        json.hash(&mut hasher);
+        let mut res = JsonConverter::new(&krate).convert();
+        res.crate_hash = format!("json-{:016x}", hasher.finish());
+        Ok(res)
+    }
+
+    pub fn crate_name(&self) -> &str {
+        &self.crate_name
+    }
+
+    pub(crate) fn crate_hash(&self) -> &str {
+        &self.crate_hash
+    }
+
+    /// 构建ApiGraph并加入函数，和in-process一样按config跳过弃用的和`#[doc(hidden)]`的函数，
+    /// 其他的过滤和找依赖由调用者完成
+    pub(crate) fn build_api_graph(&self, config: &GenerationConfig) -> ApiGraph<'_> {
+        let mut graph = ApiGraph::new(&self.crate_name, &self.cache);
+        graph.include_deprecated = config.include_deprecated;
+        graph.include_doc_hidden = config.include_doc_hidden;
+        if let Some(names) = &config.generic_candidates {
+            graph.set_generic_candidates(names);
+        }
+        graph.set_full_name_map(&self.full_name_map);
+        for (mod_name, visibility) in &self.mods {
+            graph.add_mod_visibility(mod_name, visibility, None);
+        }
+        for (type_full_name, trait_full_name) in &self.type_trait_impls {
+            graph.add_type_trait_impl(type_full_name, trait_full_name);
+        }
+        for function in &self.functions {
+            if (!graph.include_deprecated && function.deprecated)
+                || (!graph.include_doc_hidden && function.doc_hidden)
+            {
+                continue;
+            }
+            graph.add_api_function(function.api_function.clone());
+        }
+        graph
+    }
+}
+
+/// 把json里的item转换成Cache、FullNameMap和ApiFunction
+struct JsonConverter<'j> {
+    krate: &'j types::Crate,
+    def_ids: FxHashMap<types::Id, DefId>,
+    next_index: usize,
+    res: JsonCrate,
+}
+
+impl<'j> JsonConverter<'j> {
+    fn new(krate: &'j types::Crate) -> Self {
+        let crate_name =
+            krate.index.get(&krate.root).and_then(|root| root.name.clone()).unwrap_or_default();
+        JsonConverter {
+            krate,
+            def_ids: FxHashMap::default(),
+            next_index: 0,
+            res: JsonCrate {
+                crate_name,
+                crate_hash: String::new(),
+                cache: Cache::default(),
+                full_name_map: FullNameMap::new(),
+                mods: Vec::new(),
+                type_trait_impls: Vec::new(),
+                functions: Vec::new(),
+            },
+        }
+    }
+
+    fn convert(mut self) -> JsonCrate {
+        self.extract_paths();
+        let root = self.krate.root.clone();
+        let crate_name = self.res.crate_name.clone();
+        self.extract_module(&root, &crate_name, false);
+        self.extract_impls();
+        self.res
+    }
+
+    /// 为json里的Id分配DefId，同一个Id总是得到同一个DefId
+    fn def_id(&mut self, id: &types::Id) -> DefId {
+        if let Some(def_id) = self.def_ids.get(id) {
+            return *def_id;
+        }
+        let krate = match self.krate.paths.get(id) {
+            Some(summary) => CrateNum::from_u32(summary.crate_id),
+            None => LOCAL_CRATE,
+        };
+        let def_id = DefId { krate, index: DefIndex::from_usize(self.next_index) };
+        self.next_index += 1;
+        self.def_ids.insert(id.clone(), def_id);
+        def_id
+    }
+
+    /// 和extract_impls_from_cache一样：当前crate的路径都记录下来，外部crate只记录prelude类型
+    fn extract_paths(&mut self) {
+        let krate = self.krate;
+        for (id, summary) in &krate.paths {
+            let item_type = match item_type_of(&summary.kind) {
+                Some(item_type) => item_type,
+                None => continue,
+            };
+            let def_id = self.def_id(id);
+            let symbols = summary.path.iter().map(|name| Symbol::intern(name)).collect::<Vec<_>>();
+            let full_name = summary.path.join("::");
+            if summary.crate_id == 0 {
+                self.res.full_name_map.push_mapping(def_id, &full_name, item_type);
+                self.res.cache.paths.insert(def_id, (symbols, item_type));
+            } else {
+                if prelude_type::is_preluded_type(&full_name) {
+                    self.res.full_name_map.push_mapping(def_id, &full_name, item_type);
+                }
+                self.res.cache.external_paths.insert(def_id, (symbols, item_type));
+            }
+        }
+    }
+
+    /// 递归遍历模块，记录模块的可见性，并加入模块里的函数，parent_hidden表示外面的模块标了`#[doc(hidden)]`
+    fn extract_module(&mut self, id: &types::Id, mod_name: &String, parent_hidden: bool) {
+        let krate = self.krate;
+        let item = match krate.index.get(id) {
+            Some(item) => item,
+            None => return,
+        };
+        let module = match &item.inner {
+            types::ItemEnum::Module(module) => module,
+            _ => return,
+        };
+        //crate根模块总是可见的
+        let visibility =
+            if module.is_crate { Visibility::Public } else { visibility_of(&item.visibility) };
+        self.res.mods.push((mod_name.clone(), visibility));
+        let hidden = parent_hidden || is_doc_hidden(item);
+
+        for child_id in &module.items {
+            let child = match krate.index.get(child_id) {
+                Some(child) => child,
+                None => continue,
+            };
+            let child_name = match &child.name {
+                Some(name) => name,
+                None => continue,
+            };
+            let full_name = format!("{}::{}", mod_name, child_name);
+            match &child.inner {
+                types::ItemEnum::Module(_) => self.extract_module(child_id, &full_name, hidden),
+                types::ItemEnum::Function(function) => {
+                    let visibility = visibility_of(&child.visibility);
+                    if let Some(api_function) =
+                        self.convert_function(full_name, function, None, None, visibility)
+                    {
+                        self.res.functions.push(JsonFunction {
+                            api_function,
+                            deprecated: child.deprecation.is_some(),
+                            doc_hidden: hidden || is_doc_hidden(child),
+                        });
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    /// 和_analyse_impl一样，把impl块里的方法加入图中
+    fn extract_impls(&mut self) {
+        let krate = self.krate;
+        for item in krate.index.values() {
+            let impl_ = match &item.inner {
+                types::ItemEnum::Impl(impl_) if item.crate_id == 0 => impl_,
+                _ => continue,
+            };
+            //auto trait和blanket impl不是由当前crate写出来的
+            if impl_.synthetic || impl_.negative || impl_.blanket_impl.is_some() {
+                continue;
+            }
+            let type_full_name = match &impl_.for_ {
+                types::Type::ResolvedPath(path) => {
+                    let def_id = self.def_id(&path.id);
+                    self.res.full_name_map._get_full_name(def_id).cloned()
+                }
+                _ => None,
+            };
+            let self_type_item = match &impl_.for_ {
+                types::Type::ResolvedPath(path) => krate.index.get(&path.id),
+                _ => None,
+            };
+            let type_deprecated =
+                self_type_item.map_or(false, |type_item| type_item.deprecation.is_some());
+            let impl_hidden = is_doc_hidden(item)
+                || self_type_item.map_or(false, |type_item| is_doc_hidden(type_item));
+            let trait_full_name = match &impl_.trait_ {
+                Some(trait_) => {
+                    let def_id = self.def_id(&trait_.id);
+                    if let (Some(type_full_name), Some(summary)) =
+                        (&type_full_name, krate.paths.get(&trait_.id))
+                    {
+                        self.res
+                            .type_trait_impls
+                            .push((type_full_name.clone(), summary.path.join("::")));
+                    }
+                    match self.res.full_name_map._get_full_name(def_id) {
+                        Some(trait_full_name) => Some(trait_full_name.clone()),
+                        //trait不在当前crate中
+                        None => continue,
+                    }
+                }
+                None => None,
+            };
+            let impl_type = match self.convert_type(&impl_.for_, None) {
+                Some(impl_type) => impl_type,
+                None => continue,
+            };
+
+            for method_id in &impl_.items {
+                let method = match krate.index.get(method_id) {
+                    Some(method) => method,
+                    None => continue,
+                };
+                let (method_name, function) = match (&method.name, &method.inner) {
+                    (Some(method_name), types::ItemEnum::Function(function)) => {
+                        (method_name, function)
+                    }
+                    _ => continue,
+                };
+                //使用全限定名称：type::f
+                //如果函数输入参数中含有self type，则使用trait name（也可以使用type name）
+                //如果函数输入参数中不含有self type，则使用type name
+                let has_self_param =
+                    function.decl.inputs.iter().any(|(_, input)| contains_self_type(input));
+                let method_type_name = if has_self_param {
+                    trait_full_name.as_ref().or(type_full_name.as_ref())
+                } else {
+                    type_full_name.as_ref()
+                };
+                let method_type_name = match method_type_name {
+                    Some(method_type_name) => method_type_name,
+                    None => continue,
+                };
+                //trait里的方法没有自己的可见性，姑且当作public
+                let visibility = match impl_.trait_ {
+                    Some(_) => Visibility::Public,
+                    None => visibility_of(&method.visibility),
+                };
+                if let Some(api_function) = self.convert_function(
+                    format!("{}::{}", method_type_name, method_name),
+                    function,
+                    Some(&impl_type),
+                    trait_full_name.clone(),
+                    visibility,
+                ) {
+                    self.res.functions.push(JsonFunction {
+                        api_function,
+                        deprecated: type_deprecated || method.deprecation.is_some(),
+                        doc_hidden: impl_hidden || is_doc_hidden(method),
+                    });
+                }
+            }
+        }
+    }
+
+    /// 转换函数签名，包含不支持的类型时返回None
+    fn convert_function(
+        &mut self,
+        full_name: String,
+        function: &types::Function,
+        self_type: Option<&clean::Type>,
+        trait_full_path: Option<String>,
+        visibility: Visibility,
+    ) -> Option<ApiFunction> {
+        let mut inputs = Vec::new();
+        for (_, input) in &function.decl.inputs {
+            inputs.push(self.convert_type(input, self_type)?);
+        }
+        let output = match &function.decl.output {
+            Some(output) => Some(self.convert_type(output, self_type)?),
+            None => None,
+        };
+        let mut _generics =
+            clean::Generics { params: ThinVec::new(), where_predicates: ThinVec::new() };
+        for param in &function.generics.params {
+            let kind = match &param.kind {
+                types::GenericParamDefKind::Lifetime { .. } => {
+                    clean::GenericParamDefKind::Lifetime { outlives: Vec::new() }
+                }
+                types::GenericParamDefKind::Type { synthetic, .. } => {
+                    let did = self.def_id(&types::Id(format!("{}::{}", full_name, param.name)));
+                    clean::GenericParamDefKind::Type {
+                        did,
+                        bounds: Vec::new(),
+                        default: None,
+                        synthetic: *synthetic,
+                    }
+                }
+                //const泛型参数暂时不支持
+                types::GenericParamDefKind::Const { .. } => return None,
+            };
+            _generics
+                .params
+                .push(clean::GenericParamDef { name: Symbol::intern(&param.name), kind });
+        }
+        let _unsafe_tag =
+            if function.header.unsafe_ { ApiUnsafety::Unsafe } else { ApiUnsafety::Normal };
+        let _abi = match function.header.abi {
+            types::Abi::Rust => ApiAbi::Rust,
+            _ => ApiAbi::Foreign,
+        };
+        let _asyncness =
+            if function.header.async_ { ApiAsyncness::Async } else { ApiAsyncness::NotAsync };
+        Some(ApiFunction {
+            full_name,
+            _generics,
+            generic_substitutions: FxHashMap::default(),
+            inputs,
+            output,
+            _trait_full_path: trait_full_path,
+            _unsafe_tag,
+            _abi,
+            _asyncness,
+            visibility,
+            span: None,
+            def_id: None,
+            _uses_unsafe: false,
+            complexity: None,
+            trait_dispatch: None,
+        })
+    }
+
+    /// 把json里的类型转换成clean::Type，Self替换成self_type，不支持的类型返回None
+    fn convert_type(
+        &mut self,
+        type_: &types::Type,
+        self_type: Option<&clean::Type>,
+    ) -> Option<clean::Type> {
+        let res = match type_ {
+            types::Type::ResolvedPath(path) => {
+                clean::Type::Path { path: self.convert_path(path, self_type)? }
+            }
+            types::Type::Generic(name) if name == "Self" => self_type?.clone(),
+            types::Type::Generic(name) => clean::Type::Generic(Symbol::intern(name)),
+            types::Type::Primitive(name) => {
+                clean::Type::Primitive(PrimitiveType::from_symbol(Symbol::intern(name))?)
+            }
+            types::Type::Tuple(elements) => {
+                let mut res = Vec::new();
+                for type_ in elements {
+                    res.push(self.convert_type(type_, self_type)?);
+                }
+                clean::Type::Tuple(res)
+            }
+            types::Type::Slice(type_) => {
+                clean::Type::Slice(Box::new(self.convert_type(type_, self_type)?))
+            }
+            types::Type::Array { type_, len } => clean::Type::Array(
+                Box::new(self.convert_type(type_, self_type)?),
+                len.clone().into_boxed_str(),
+            ),
+            types::Type::RawPointer { mutable, type_ } => clean::Type::RawPointer(
+                mutability_of(*mutable),
+                Box::new(self.convert_type(type_, self_type)?),
+            ),
+            types::Type::BorrowedRef { lifetime, mutable, type_ } => clean::Type::BorrowedRef {
+                lifetime: lifetime
+                    .as_ref()
+                    .map(|lifetime| clean::Lifetime(Symbol::intern(lifetime))),
+                mutability: mutability_of(*mutable),
+                type_: Box::new(self.convert_type(type_, self_type)?),
+            },
+            types::Type::DynTrait(_)
+            | types::Type::FunctionPointer(_)
+            | types::Type::ImplTrait(_)
+            | types::Type::Infer
+            | types::Type::QualifiedPath { .. } => return None,
+        };
+        Some(res)
+    }
+
+    /// 路径的每一段来自paths里的全路径，泛型参数放在最后一段上
+    fn convert_path(
+        &mut self,
+        path: &types::Path,
+        self_type: Option<&clean::Type>,
+    ) -> Option<clean::Path> {
+        let def_id = self.def_id(&path.id);
+        let krate = self.krate;
+        let (names, def_kind) = match krate.paths.get(&path.id) {
+            Some(summary) => (summary.path.clone(), def_kind_of(&summary.kind)),
+            None => (vec![path.name.clone()], DefKind::Struct),
+        };
+        let args = match &path.args {
+            Some(args) => self.convert_generic_args(args, self_type)?,
+            None => clean::GenericArgs::AngleBracketed {
+                args: Vec::new().into_boxed_slice(),
+                bindings: ThinVec::new(),
+            },
+        };
+        let mut segments = names
+            .iter()
+            .map(|name| clean::PathSegment {
+                name: Symbol::intern(name),
+                args: clean::GenericArgs::AngleBracketed {
+                    args: Vec::new().into_boxed_slice(),
+                    bindings: ThinVec::new(),
+                },
+            })
+            .collect::<ThinVec<_>>();
+        if let Some(last_segment) = segments.last_mut() {
+            last_segment.args = args;
+        }
+        Some(clean::Path { res: Res::Def(def_kind, def_id), segments })
+    }
+
+    /// 关联类型的约束（比如Iterator<Item = T>）暂时丢掉
+    fn convert_generic_args(
+        &mut self,
+        args: &types::GenericArgs,
+        self_type: Option<&clean::Type>,
+    ) -> Option<clean::GenericArgs> {
+        let res = match args {
+            types::GenericArgs::AngleBracketed { args, .. } => {
+                let mut res = Vec::new();
+                for arg in args {
+                    res.push(match arg {
+                        types::GenericArg::Lifetime(lifetime) => {
+                            clean::GenericArg::Lifetime(clean::Lifetime(Symbol::intern(lifetime)))
+                        }
+                        types::GenericArg::Type(type_) => {
+                            clean::GenericArg::Type(self.convert_type(type_, self_type)?)
+                        }
+                        types::GenericArg::Const(_) | types::GenericArg::Infer => {
+                            clean::GenericArg::Infer
+                        }
+                    });
+                }
+                clean::GenericArgs::AngleBracketed {
+                    args: res.into_boxed_slice(),
+                    bindings: ThinVec::new(),
+                }
+            }
+            types::GenericArgs::Parenthesized { inputs, output } => {
+                let mut res = Vec::new();
+                for input in inputs {
+                    res.push(self.convert_type(input, self_type)?);
+                }
+                let output = match output {
+                    Some(output) => Some(Box::new(self.convert_type(output, self_type)?)),
+                    None => None,
+                };
+                clean::GenericArgs::Parenthesized { inputs: res.into_boxed_slice(), output }
+            }
+        };
+        Some(res)
+    }
+}
+
+/// item有没有标`#[doc(hidden)]`，json里的属性是`#[doc(hidden)]`或者`#[doc(hidden, inline)]`这样的字符串
+fn is_doc_hidden(item: &types::Item) -> bool {
+    item.attrs.iter().any(|attr| {
+        attr.strip_prefix("#[doc(")
+            .and_then(|attr| attr.strip_suffix(")]"))
+            .map_or(false, |args| args.split(',').any(|arg| arg.trim() == "hidden"))
+    })
+}
+
+/// 参数里是否用到了Self，和impl_util里的is_param_self_type一样只看引用和泛型参数
+fn contains_self_type(type_: &types::Type) -> bool {
+    match type_ {
+        types::Type::Generic(name) => name == "Self",
+        types::Type::BorrowedRef { type_, .. } => contains_self_type(type_),
+        types::Type::ResolvedPath(path) => match path.args.as_deref() {
+            Some(types::GenericArgs::AngleBracketed { args, .. }) => args.iter().any(|arg| {
+                if let types::GenericArg::Type(type_) = arg {
+                    contains_self_type(type_)
+                } else {
+                    false
+                }
+            }),
+            Some(types::GenericArgs::Parenthesized { inputs, output }) => {
+                inputs.iter().any(contains_self_type)
+                    || output.as_ref().map_or(false, contains_self_type)
+            }
+            None => false,
+        },
+        _ => false,
+    }
+}
+
+fn visibility_of(visibility: &types::Visibility) -> Visibility {
+    match visibility {
+        types::Visibility::Public => Visibility::Public,
+        //pub(crate)、pub(in path)以及私有的，对外都不可见
+        types::Visibility::Default
+        | types::Visibility::Crate
+        | types::Visibility::Restricted { .. } => Visibility::Restricted(CRATE_DEF_ID),
+    }
+}
+
+fn mutability_of(mutable: bool) -> Mutability {
+    if mutable { Mutability::Mut } else { Mutability::Not }
+}
+
+fn item_type_of(kind: &types::ItemKind) -> Option<ItemType> {
+    let item_type = match kind {
+        types::ItemKind::Module => ItemType::Module,
+        types::ItemKind::Struct => ItemType::Struct,
+        types::ItemKind::Union => ItemType::Union,
+        types::ItemKind::Enum => ItemType::Enum,
+        types::ItemKind::Variant => ItemType::Variant,
+        types::ItemKind::Function => ItemType::Function,
+        types::ItemKind::Typedef => ItemType::Typedef,
+        types::ItemKind::Constant => ItemType::Constant,
+        types::ItemKind::Trait => ItemType::Trait,
+        types::ItemKind::TraitAlias => ItemType::TraitAlias,
+        types::ItemKind::Static => ItemType::Static,
+        types::ItemKind::ForeignType => ItemType::ForeignType,
+        types::ItemKind::Macro => ItemType::Macro,
+        types::ItemKind::Primitive => ItemType::Primitive,
+        _ => return None,
+    };
+    Some(item_type)
+}
+
+fn def_kind_of(kind: &types::ItemKind) -> DefKind {
+    match kind {
+        types::ItemKind::Enum => DefKind::Enum,
+        types::ItemKind::Union => DefKind::Union,
+        types::ItemKind::Trait => DefKind::Trait,
+        types::ItemKind::TraitAlias => DefKind::TraitAlias,
+        types::ItemKind::Typedef => DefKind::TyAlias,
+        types::ItemKind::ForeignType => DefKind::ForeignTy,
+        _ => DefKind::Struct,
+    }
+}
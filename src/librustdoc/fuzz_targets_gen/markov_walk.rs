@@ -0,0 +1,173 @@
+//! 从语料学习的一阶马尔可夫链生成序列
+//! _UseRealWorld只会重放语料里原样出现过的序列，这里从seq-dedup.ans统计API之间的转移次数
+//! （按序列的频率加权），然后按转移概率随机游走，生成语料里没有原样出现、但是看起来合理的序列
+//! 每一步仍然用is_fun_satisfied检查依赖，采到的API加不进去的时候从剩下的后继里重新采样
+
+use crate::fuzz_targets_gen::api_graph::{ApiGraph, ApiType};
+use crate::fuzz_targets_gen::api_sequence::ApiSequence;
+use crate::fuzz_targets_gen::error::FuzzGenError;
+use crate::fuzz_targets_gen::file_util;
+use crate::fuzz_targets_gen::progress::Progress;
+use rand::thread_rng;
+use rand::Rng;
+use rustc_data_structures::fx::FxHashMap;
+use std::fs;
+
+/// 从语料统计出来的转移次数，API用在api_functions中的下标表示
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TransitionModel {
+    /// 序列第一个API的次数
+    pub(crate) starts: FxHashMap<usize, usize>,
+    /// 前一个API -> (后一个API -> 次数)
+    pub(crate) transitions: FxHashMap<usize, FxHashMap<usize, usize>>,
+}
+
+impl TransitionModel {
+    /// 读取语料里的所有序列，每行的格式是`序号|次数|func1 func2 ...`，
+    /// 语料里的API在api_graph里有多个同名函数（比如泛型实例化）的时候每个都算一次
+    pub(crate) fn from_corpus(
+        api_graph: &ApiGraph<'_>,
+        corpus_path: &std::path::PathBuf,
+    ) -> Result<Self, FuzzGenError> {
+        let mut name_to_indexes: FxHashMap<&str, Vec<usize>> = FxHashMap::default();
+        for (index, api_function) in api_graph.api_functions.iter().enumerate() {
+            name_to_indexes.entry(api_function.full_name.as_str()).or_default().push(index);
+        }
+        let no_index = Vec::new();
+        let indexes_of = |name: &str| name_to_indexes.get(name).unwrap_or(&no_index);
+
+        let mut model = TransitionModel::default();
+        for seq_file in file_util::corpus_sequence_files(corpus_path)? {
+            let seq_file_path = seq_file.display().to_string();
+            let content = fs::read_to_string(&seq_file)
+                .map_err(|error| FuzzGenError::Io { path: seq_file_path.clone(), error })?;
+            for (line_index, line) in content.lines().enumerate() {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let malformed = || FuzzGenError::MalformedLine {
+                    path: seq_file_path.clone(),
+                    line: line_index + 1,
+                    content: line.to_string(),
+                };
+                let fields = line.split("|").collect::<Vec<_>>();
+                let freq: String = fields
+                    .get(1)
+                    .ok_or_else(malformed)?
+                    .chars()
+                    .filter(|c| c.is_digit(10))
+                    .collect();
+                let freq = freq.parse::<usize>().map_err(|_| malformed())?;
+                let functions = fields
+                    .last()
+                    .ok_or_else(malformed)?
+                    .split(" ")
+                    .filter(|x| x.len() > 1)
+                    .collect::<Vec<_>>();
+                if let Some(first) = functions.first() {
+                    for index in indexes_of(first) {
+                        *model.starts.entry(*index).or_default() += freq;
+                    }
+                }
+                for window in functions.windows(2) {
+                    for former in indexes_of(window[0]) {
+                        let successors = model.transitions.entry(*former).or_default();
+                        for latter in indexes_of(window[1]) {
+                            *successors.entry(*latter).or_default() += freq;
+                        }
+                    }
+                }
+            }
+        }
+        Ok(model)
+    }
+}
+
+/// 按次数加权随机选一个，选中的会从candidates里删掉，这样加不进序列的时候可以接着从剩下的里面选
+fn sample_weighted(candidates: &mut Vec<(usize, usize)>, rng: &mut impl Rng) -> Option<usize> {
+    let total: usize = candidates.iter().map(|(_, count)| *count).sum();
+    if total == 0 {
+        return None;
+    }
+    let mut chosen = rng.gen_range(0, total);
+    let position = candidates
+        .iter()
+        .position(|(_, count)| {
+            if chosen < *count {
+                return true;
+            }
+            chosen = chosen - *count;
+            false
+        })
+        .unwrap();
+    Some(candidates.remove(position).0)
+}
+
+/// 从distribution里按概率依次尝试，返回第一个能加入sequence的API组成的新序列
+fn extend_with_sample(
+    api_graph: &ApiGraph<'_>,
+    distribution: Option<&FxHashMap<usize, usize>>,
+    sequence: &ApiSequence,
+    rng: &mut impl Rng,
+) -> Option<(usize, ApiSequence)> {
+    let mut candidates = distribution?.iter().map(|(index, count)| (*index, *count)).collect();
+    while let Some(chosen_fun_index) = sample_weighted(&mut candidates, rng) {
+        if let Some(new_sequence) =
+            api_graph.is_fun_satisfied(&ApiType::BareFunction, chosen_fun_index, sequence)
+        {
+            return Some((chosen_fun_index, new_sequence));
+        }
+    }
+    None
+}
+
+/// 游走walks次，每次从起始分布出发，按转移概率加API，直到没有能加入的后继、
+/// 长度到了max_len或者到了终结函数，走出来的序列（长度至少为1）加到api_sequences里
+pub(crate) fn markov_walk(
+    api_graph: &mut ApiGraph<'_>,
+    model: &TransitionModel,
+    walks: usize,
+    max_len: usize,
+) {
+    api_graph.api_sequences.clear();
+    api_graph.reset_visited();
+    if model.starts.is_empty() {
+        println!("no corpus sequence starts with a known API, markov walk generates nothing");
+        return;
+    }
+
+    let mut rng = thread_rng();
+    let mut progress = Progress::new("markov walk", Some(walks));
+    for _ in 0..walks {
+        progress.inc(1);
+        let mut sequence = ApiSequence::new();
+        let mut last_function = None;
+        while sequence.len() < max_len {
+            let distribution = match last_function {
+                None => Some(&model.starts),
+                Some(index) => model.transitions.get(&index),
+            };
+            let Some((chosen_fun_index, new_sequence)) =
+                extend_with_sample(api_graph, distribution, &sequence, &mut rng)
+            else {
+                break;
+            };
+            api_graph.api_functions_visited[chosen_fun_index] = true;
+            sequence = new_sequence;
+            last_function = Some(chosen_fun_index);
+            if api_graph.is_sequence_ended(&sequence, false) {
+                break;
+            }
+        }
+        if sequence.len() > 0 {
+            api_graph.api_sequences.push(sequence);
+            progress.set_sequences(api_graph.api_sequences.len());
+        }
+    }
+    progress.finish();
+    println!(
+        "markov walk generated {} sequences, visited {} functions",
+        api_graph.api_sequences.len(),
+        api_graph._visited_nodes_num()
+    );
+}
@@ -0,0 +1,172 @@
+//! MaybeUninit槽位和出参
+//! 底层的API常用`&mut MaybeUninit<T>`或者`*mut T`作为出参，由被调用的函数把结果写进去。
+//! 序列里一般没有函数返回MaybeUninit，这些API以前都到不了。--allow-unsafe-synthesis打开时，
+//! 给这样的参数类型加上两个合成函数：
+//! 1. `MaybeUninit::<T>::uninit`：没有参数，生成`let mut _local0 = std::mem::MaybeUninit::<T>::uninit();`，
+//!    `&mut MaybeUninit<T>`参数传`&mut _local0`，`*mut T`参数传`_local0.as_mut_ptr()`
+//! 2. `MaybeUninit::<T>::assume_init`：unsafe函数，把写过的槽位变成T，给后面的调用用。
+//!    槽位要先被可变借用过（传给了某个出参）才能加入序列，见reads_uninitialized_slot
+//! 被调用的函数不一定真的写了槽位，assume_init可能是未定义行为，所以默认不打开
+
+use crate::clean;
+use crate::formats::cache::Cache;
+use crate::fuzz_targets_gen::api_function::{ApiAbi, ApiAsyncness, ApiFunction, ApiUnsafety};
+use crate::fuzz_targets_gen::api_graph::ApiGraph;
+use crate::fuzz_targets_gen::api_sequence::{ApiCall, ApiSequence, ParamType};
+use crate::fuzz_targets_gen::api_util;
+use crate::fuzz_targets_gen::call_type::CallType;
+use crate::fuzz_targets_gen::emit_options::EmitOptions;
+use crate::fuzz_targets_gen::generic_candidate;
+use crate::fuzz_targets_gen::impl_util::FullNameMap;
+use rustc_data_structures::fx::FxHashMap;
+use rustc_hir::Mutability;
+use rustc_middle::ty::Visibility;
+use thin_vec::ThinVec;
+
+/// 定义的路径，用来在文档缓存里找到MaybeUninit构造`MaybeUninit<T>`
+static MAYBE_UNINIT_DEFINITION_PATH: &'static str = "core::mem::maybe_uninit::MaybeUninit";
+
+/// 类型是`std::mem::MaybeUninit<T>`的话返回T
+pub(crate) fn uninit_inner_type<'a>(
+    type_: &'a clean::Type,
+    cache: &Cache,
+    full_name_map: &FullNameMap,
+) -> Option<&'a clean::Type> {
+    let clean::Type::Path { path } = type_ else {
+        return None;
+    };
+    if api_util::_def_path_name(path.def_id(), cache, full_name_map)? != "std::mem::MaybeUninit" {
+        return None;
+    }
+    let Some(clean::GenericArgs::AngleBracketed { args, .. }) =
+        path.segments.last().map(|segment| &segment.args)
+    else {
+        return None;
+    };
+    match &args[..] {
+        [clean::GenericArg::Type(inner_type)] => Some(inner_type),
+        _ => None,
+    }
+}
+
+/// 出参需要的槽位：(T的类型名, `MaybeUninit<T>`, T)
+fn uninit_slot(
+    input_type: &clean::Type,
+    cache: &Cache,
+    full_name_map: &FullNameMap,
+    emit_options: &EmitOptions,
+) -> Option<(String, clean::Type, clean::Type)> {
+    let (slot_type, inner_type) = match input_type {
+        clean::Type::BorrowedRef { mutability: Mutability::Mut, type_, .. } => {
+            let inner_type = uninit_inner_type(type_, cache, full_name_map)?;
+            ((**type_).clone(), inner_type.clone())
+        }
+        //可以fuzz的类型已经能从输入构造出来传指针，不需要槽位
+        clean::Type::RawPointer(Mutability::Mut, type_)
+            if !api_util::is_fuzzable_type(type_, cache, full_name_map, emit_options, None) =>
+        {
+            let slot_type = generic_candidate::path_type(
+                MAYBE_UNINIT_DEFINITION_PATH,
+                vec![(**type_).clone()],
+                cache,
+            )?;
+            (slot_type, (**type_).clone())
+        }
+        _ => return None,
+    };
+    if api_util::_is_generic_type(&inner_type) {
+        return None;
+    }
+    //带生命周期参数的类型名不能直接写在路径里；`*mut c_void`一般是不透明的用户数据，不是出参
+    let type_name = api_util::_type_name(&inner_type, cache, full_name_map);
+    if type_name.contains('\'') || type_name == "std::ffi::c_void" {
+        return None;
+    }
+    Some((type_name, slot_type, inner_type))
+}
+
+fn synthetic_function(
+    full_name: String,
+    inputs: Vec<clean::Type>,
+    output: clean::Type,
+    unsafety: ApiUnsafety,
+) -> ApiFunction {
+    ApiFunction {
+        full_name,
+        _generics: clean::Generics { params: ThinVec::new(), where_predicates: ThinVec::new() },
+        generic_substitutions: FxHashMap::default(),
+        inputs,
+        output: Some(output),
+        _trait_full_path: None,
+        _unsafe_tag: unsafety,
+        _abi: ApiAbi::Rust,
+        _asyncness: ApiAsyncness::NotAsync,
+        visibility: Visibility::Public,
+        span: None,
+        def_id: None,
+        _uses_unsafe: false,
+        complexity: None,
+        trait_dispatch: None,
+    }
+}
+
+/// 给出参的类型加上uninit和assume_init两个合成函数，要在filter_functions之后、find_all_dependencies之前调用
+pub(crate) fn add_uninit_slots(api_graph: &mut ApiGraph<'_>) {
+    let cache = api_graph.cache;
+    let mut slots: Vec<(String, clean::Type, clean::Type)> = Vec::new();
+    for api_function in &api_graph.api_functions {
+        for input_type in &api_function.inputs {
+            let Some(slot) = uninit_slot(input_type, cache, &api_graph.full_name_map, &api_graph.emit_options) else {
+                continue;
+            };
+            if !slots.iter().any(|(type_name, ..)| *type_name == slot.0) {
+                slots.push(slot);
+            }
+        }
+    }
+    let added = slots.len();
+    for (type_name, slot_type, inner_type) in slots {
+        api_graph.add_api_function(synthetic_function(
+            format!("std::mem::MaybeUninit::<{}>::uninit", type_name),
+            Vec::new(),
+            slot_type.clone(),
+            ApiUnsafety::Normal,
+        ));
+        let assume_init = format!("std::mem::MaybeUninit::<{}>::assume_init", type_name);
+        api_graph.assume_init_functions.insert(assume_init.clone());
+        api_graph.add_api_function(synthetic_function(
+            assume_init,
+            vec![slot_type],
+            inner_type,
+            ApiUnsafety::Unsafe,
+        ));
+    }
+    println!("{} uninit slots added", added);
+}
+
+/// 槽位传给出参时的调用方式
+fn writes_slot(call_type: &CallType) -> bool {
+    matches!(call_type, CallType::_MutBorrowedRef(..) | CallType::_UninitAsMutPtr(..))
+}
+
+/// 新加入的调用是assume_init，但是它的槽位在序列里还没有传给过任何出参
+pub(crate) fn reads_uninitialized_slot(
+    api_graph: &ApiGraph<'_>,
+    sequence: &ApiSequence,
+    api_call: &ApiCall,
+) -> bool {
+    let function = &api_graph.api_functions[api_call.func.1];
+    if !api_graph.assume_init_functions.contains(&function.full_name) {
+        return false;
+    }
+    api_call.params.iter().any(|(param_type, index, _)| {
+        *param_type == ParamType::_FunctionReturn
+            && !sequence.functions[*index + 1..].iter().any(|later_call| {
+                later_call.params.iter().any(|(later_type, later_index, call_type)| {
+                    *later_type == ParamType::_FunctionReturn
+                        && *later_index == *index
+                        && writes_slot(call_type)
+                })
+            })
+    })
+}
@@ -0,0 +1,223 @@
+//! 蒙特卡洛树搜索生成序列
+//! 树上的状态是部分序列，动作是能用is_fun_satisfied加到序列末尾的API，根节点是空序列
+//! 每轮迭代：
+//!     1. 选择：从根往下，每次选UCT值最大的子节点，直到遇到还有动作没试过的节点
+//!     2. 扩展：随机试一个没试过的动作，得到新的子节点
+//!     3. 模拟：从新节点随机往后加API，直到max_len或者加不进去
+//!     4. 回传：模拟得到的序列相对于已经选出来的序列新覆盖的节点和边数就是奖励，沿路径加回去
+//! 奖励大于0的序列加到api_sequences里。和BFS不同，每轮只展开一个节点，API多的时候也不会爆炸
+
+use crate::fuzz_targets_gen::api_graph::{ApiGraph, ApiType};
+use crate::fuzz_targets_gen::api_sequence::ApiSequence;
+use crate::fuzz_targets_gen::progress::Progress;
+use rand::rngs::ThreadRng;
+use rand::thread_rng;
+use rand::Rng;
+use rustc_data_structures::fx::FxHashSet;
+
+/// UCT里探索项的系数
+static EXPLORATION: f64 = 1.414;
+/// 模拟时每一步最多随机试这么多个API
+static MAX_ROLLOUT_ATTEMPTS: usize = 10;
+
+struct TreeNode {
+    sequence: ApiSequence,
+    parent: Option<usize>,
+    children: Vec<usize>,
+    /// 还没试过的动作，第一次访问的时候才计算
+    untried: Option<Vec<usize>>,
+    visits: usize,
+    total_reward: f64,
+}
+
+impl TreeNode {
+    fn new(sequence: ApiSequence, parent: Option<usize>) -> Self {
+        TreeNode {
+            sequence,
+            parent,
+            children: Vec::new(),
+            untried: None,
+            visits: 0,
+            total_reward: 0.0,
+        }
+    }
+}
+
+struct Search<'g, 'a> {
+    api_graph: &'g ApiGraph<'a>,
+    rng: ThreadRng,
+    max_len: usize,
+    nodes: Vec<TreeNode>,
+    covered_nodes: FxHashSet<usize>,
+    covered_edges: FxHashSet<usize>,
+    archived: Vec<ApiSequence>,
+}
+
+impl<'g, 'a> Search<'g, 'a> {
+    /// 序列末尾能加的API，序列已经到了max_len的话没有动作
+    fn actions(&self, sequence: &ApiSequence) -> Vec<usize> {
+        if sequence.len() >= self.max_len {
+            return Vec::new();
+        }
+        (0..self.api_graph.api_functions.len())
+            .filter(|function_index| {
+                self.api_graph
+                    .is_fun_satisfied(&ApiType::BareFunction, *function_index, sequence)
+                    .is_some()
+            })
+            .collect()
+    }
+
+    fn uct(&self, parent_visits: usize, child: usize) -> f64 {
+        let node = &self.nodes[child];
+        if node.visits == 0 {
+            return f64::INFINITY;
+        }
+        node.total_reward / node.visits as f64
+            + EXPLORATION * ((parent_visits as f64).ln() / node.visits as f64).sqrt()
+    }
+
+    /// 选择：往下走到一个还有动作没试过、或者没有子节点的节点
+    fn select(&mut self) -> usize {
+        let mut current = 0;
+        loop {
+            if self.nodes[current].untried.is_none() {
+                let actions = self.actions(&self.nodes[current].sequence);
+                self.nodes[current].untried = Some(actions);
+            }
+            let node = &self.nodes[current];
+            if !node.untried.as_ref().unwrap().is_empty() || node.children.is_empty() {
+                return current;
+            }
+            let parent_visits = node.visits;
+            current = *node
+                .children
+                .iter()
+                .max_by(|x, y| {
+                    self.uct(parent_visits, **x).partial_cmp(&self.uct(parent_visits, **y)).unwrap()
+                })
+                .unwrap();
+        }
+    }
+
+    /// 扩展：随机选一个没试过的动作，返回新的子节点，没有动作可试的话返回自己
+    fn expand(&mut self, current: usize) -> usize {
+        loop {
+            let untried = self.nodes[current].untried.as_mut().unwrap();
+            if untried.is_empty() {
+                return current;
+            }
+            let action = untried.swap_remove(self.rng.gen_range(0, untried.len()));
+            let sequence = &self.nodes[current].sequence;
+            if let Some(new_sequence) =
+                self.api_graph.is_fun_satisfied(&ApiType::BareFunction, action, sequence)
+            {
+                let child = self.nodes.len();
+                self.nodes.push(TreeNode::new(new_sequence, Some(current)));
+                self.nodes[current].children.push(child);
+                return child;
+            }
+        }
+    }
+
+    /// 模拟：随机往后加API
+    fn rollout(&mut self, sequence: &ApiSequence) -> ApiSequence {
+        let mut sequence = sequence.clone();
+        let function_len = self.api_graph.api_functions.len();
+        'rollout: while sequence.len() < self.max_len {
+            for _ in 0..MAX_ROLLOUT_ATTEMPTS {
+                let chosen_fun_index = self.rng.gen_range(0, function_len);
+                if let Some(new_sequence) = self.api_graph.is_fun_satisfied(
+                    &ApiType::BareFunction,
+                    chosen_fun_index,
+                    &sequence,
+                ) {
+                    sequence = new_sequence;
+                    continue 'rollout;
+                }
+            }
+            break;
+        }
+        sequence
+    }
+
+    /// 新覆盖的节点和边数，大于0并且可以fuzz的话加到存档里
+    fn reward(&mut self, sequence: &ApiSequence) -> f64 {
+        let nodes = self.api_graph._api_nodes(sequence);
+        let new_nodes = nodes.iter().filter(|node| !self.covered_nodes.contains(*node)).count();
+        let new_edges = sequence
+            ._covered_dependencies
+            .iter()
+            .filter(|edge| !self.covered_edges.contains(*edge))
+            .count();
+        let reward = (new_nodes + new_edges) as f64;
+        if reward > 0.0
+            && !sequence._has_no_fuzzables()
+            && !sequence._contains_dead_code_except_last_one(self.api_graph)
+        {
+            self.covered_nodes.extend(nodes);
+            self.covered_edges.extend(sequence._covered_dependencies.iter().copied());
+            self.archived.push(sequence.clone());
+        }
+        reward
+    }
+
+    fn backpropagate(&mut self, leaf: usize, reward: f64) {
+        let mut current = Some(leaf);
+        while let Some(index) = current {
+            let node = &mut self.nodes[index];
+            node.visits = node.visits + 1;
+            node.total_reward = node.total_reward + reward;
+            current = node.parent;
+        }
+    }
+}
+
+/// 迭代iterations轮，新覆盖了节点或者边的序列放到api_sequences里
+pub(crate) fn mcts(api_graph: &mut ApiGraph<'_>, iterations: usize, max_len: usize) {
+    api_graph.api_sequences.clear();
+    api_graph.reset_visited();
+    if api_graph.api_functions.is_empty() || max_len == 0 {
+        return;
+    }
+
+    let mut search = Search {
+        api_graph: &*api_graph,
+        rng: thread_rng(),
+        max_len,
+        nodes: vec![TreeNode::new(ApiSequence::new(), None)],
+        covered_nodes: FxHashSet::default(),
+        covered_edges: FxHashSet::default(),
+        archived: Vec::new(),
+    };
+    let mut progress = Progress::new("mcts", Some(iterations));
+    for _ in 0..iterations {
+        progress.inc(1);
+        let selected = search.select();
+        let leaf = search.expand(selected);
+        let leaf_sequence = search.nodes[leaf].sequence.clone();
+        let simulated = search.rollout(&leaf_sequence);
+        let reward = search.reward(&simulated);
+        search.backpropagate(leaf, reward);
+        progress.set_sequences(search.archived.len());
+        //根节点的动作都试过了，而且整棵树都没有东西可展开
+        if leaf == 0 && search.nodes[0].children.is_empty() {
+            break;
+        }
+    }
+    progress.finish();
+
+    println!(
+        "mcts expanded {} states, archived {} sequences covering {} nodes and {} edges",
+        search.nodes.len(),
+        search.archived.len(),
+        search.covered_nodes.len(),
+        search.covered_edges.len()
+    );
+    let covered_nodes = search.covered_nodes;
+    let archived = search.archived;
+    for index in covered_nodes {
+        api_graph.api_functions_visited[index] = true;
+    }
+    api_graph.api_sequences = archived;
+}
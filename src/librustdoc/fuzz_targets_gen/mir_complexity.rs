@@ -0,0 +1,70 @@
+//! 从MIR计算每个API的复杂度
+//! 按节点数选序列的时候，一个只返回字段的getter和一个几百行的解析函数算一样多，
+//! fuzz的时间很容易浪费在简单的函数上。这里用optimized_mir统计：
+//!     1. 基本块数（不算unwind用的cleanup块）
+//!     2. 分支数：每个SwitchInt有n个出口就算n-1个分支
+//!     3. 控制流图里有没有环（循环）
+//! 结果放在ApiFunction::complexity里，SequenceSelection::Complexity按它选序列
+
+use crate::fuzz_targets_gen::api_graph::ApiGraph;
+use rustc_hir::def::DefKind;
+use rustc_hir::def_id::DefId;
+use rustc_middle::mir::{self, TerminatorKind};
+use rustc_middle::ty::TyCtxt;
+
+/// 有循环的函数额外加的分数
+static LOOP_SCORE: usize = 10;
+
+/// 一个函数的复杂度
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct ApiComplexity {
+    pub(crate) basic_blocks: usize,
+    pub(crate) branches: usize,
+    pub(crate) has_loop: bool,
+}
+
+impl ApiComplexity {
+    fn from_mir(body: &mir::Body<'_>) -> Self {
+        let mut complexity = ApiComplexity::default();
+        for block_data in body.basic_blocks.iter() {
+            if block_data.is_cleanup {
+                continue;
+            }
+            complexity.basic_blocks = complexity.basic_blocks + 1;
+            if let TerminatorKind::SwitchInt { ref targets, .. } = block_data.terminator().kind {
+                complexity.branches = complexity.branches + targets.all_targets().len() - 1;
+            }
+        }
+        complexity.has_loop = body.basic_blocks.is_cfg_cyclic();
+        complexity
+    }
+
+    /// 基本块数 + 分支数，有循环的再加LOOP_SCORE
+    pub(crate) fn score(&self) -> usize {
+        self.basic_blocks + self.branches + if self.has_loop { LOOP_SCORE } else { 0 }
+    }
+}
+
+/// 本crate定义、有MIR的函数的复杂度，其他的返回None
+pub(crate) fn function_complexity(tcx: TyCtxt<'_>, def_id: DefId) -> Option<ApiComplexity> {
+    if !def_id.is_local()
+        || !matches!(tcx.def_kind(def_id), DefKind::Fn | DefKind::AssocFn)
+        || !tcx.is_mir_available(def_id)
+    {
+        return None;
+    }
+    Some(ApiComplexity::from_mir(tcx.optimized_mir(def_id)))
+}
+
+/// 设置api_functions里每个函数的complexity
+pub(crate) fn compute_complexities(tcx: TyCtxt<'_>, api_graph: &mut ApiGraph<'_>) {
+    let mut computed_number = 0;
+    for api_function in api_graph.api_functions.iter_mut() {
+        api_function.complexity =
+            api_function.def_id.and_then(|def_id| function_complexity(tcx, def_id));
+        if api_function.complexity.is_some() {
+            computed_number = computed_number + 1;
+        }
+    }
+    println!("computed the MIR complexity of {} api functions", computed_number);
+}
@@ -1,20 +1,83 @@
 //mod afl_function_util;
 //mod afl_param_util;
+mod afl_dict;
 mod afl_util;
+pub mod analyzer;
 mod api_function;
 mod api_graph;
+mod api_pattern;
 mod api_sequence;
 mod api_util;
+mod async_runtime;
+mod backend;
+mod beam_search;
+mod bolero_util;
+mod builder;
 mod call_type;
+mod cargo_project;
+mod collection_param;
+mod const_producer;
 mod context;
+mod conversion;
+mod corpus_seed;
+mod diagnostics;
+mod emit_options;
+mod encoding;
+mod error;
+mod explain;
 mod extract_dep;
 mod extract_info;
+mod ffi;
 mod file_util;
+mod foreign_trait;
+mod fries_config;
+pub(crate) mod fuzz_options;
 mod fuzz_type;
+mod generic_candidate;
 mod generic_function;
+mod genetic;
+mod global_state;
+mod graph_cache;
+mod graph_export;
+mod graph_query;
+mod greedy_selection;
 mod impl_util;
+mod internal_mode;
+mod interpreter;
+mod invariant;
+mod io_param;
+mod item_attributes;
+mod iterator_law;
+mod json_frontend;
+mod markov_walk;
+mod maybe_uninit;
+mod mcts;
+mod mir_complexity;
 mod mod_visibility;
+mod numeric_cast;
+mod opaque_type;
+mod oracle;
+mod panic_filter;
+mod path_param;
 mod prelude_type;
+mod progress;
 mod replay_util;
+mod sequence_builder;
+mod sequence_serde;
+mod size_cap;
+mod time_param;
+mod timing;
+mod traversal;
+mod unsafe_usage;
+mod unwrap_strategy;
+mod watchdog;
 
+#[cfg(test)]
+mod tests;
+
+pub use analyzer::{Analyzer, GeneratedTargets, GenerationConfig, SelectedSequences};
+pub use error::FuzzGenError;
+pub use json_frontend::JsonCrate;
+pub use sequence_builder::SequenceBuilder;
 pub(crate) use context::Context;
+pub(crate) use file_util::read_target_index;
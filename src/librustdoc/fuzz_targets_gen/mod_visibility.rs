@@ -1,12 +1,25 @@
 //! 这里定义了模块的可见性，用于通过可见性来过滤函数
-//! FIXME:  get_invisible_mod，后续可以优化
+//! 还会解析`pub use`重导出，记录每个item从crate外面能访问到的最短路径
 
-use rustc_data_structures::fx::FxHashMap;
-use rustc_middle::ty::Visibility;
+use crate::fuzz_targets_gen::impl_util::FullNameMap;
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
+use rustc_hir::def::{DefKind, Res};
+use rustc_hir::def_id::{LocalDefId, CRATE_DEF_ID, LOCAL_CRATE};
+use rustc_middle::ty::{DefIdTree, TyCtxt, Visibility};
+use std::collections::VecDeque;
+
+/// 模块和类型的可见性。pub(crate)、pub(super)、pub(in path)和私有在rustc里都是Restricted(模块)，
+/// 表示只有这个模块和它的子模块可以访问，这里按模块路径判断从某个模块能不能访问，
+/// 从crate外面（viewer为None）只能访问pub的
 #[derive(Debug, Clone)]
 pub struct ModVisibity {
     pub crate_name: String,
+    /// 模块全路径 -> 模块的可见性
     pub inner: FxHashMap<String, Visibility>,
+    /// 模块的did -> 模块全路径，用来找Restricted的可见范围
+    mod_paths: FxHashMap<LocalDefId, String>,
+    /// 类型和trait的全路径 -> 可见性，方法的全路径以类型或者trait开头，也要检查它们
+    items: FxHashMap<String, Visibility>,
 }
 
 impl ModVisibity {
@@ -14,83 +27,140 @@ impl ModVisibity {
     pub fn new(crate_name_: &String) -> Self {
         let crate_name = crate_name_.clone();
         let inner = FxHashMap::default();
-        ModVisibity { crate_name, inner }
+        let mut mod_paths = FxHashMap::default();
+        mod_paths.insert(CRATE_DEF_ID, crate_name.clone());
+        ModVisibity { crate_name, inner, mod_paths, items: FxHashMap::default() }
     }
 
-    /// 添加一个新模块，同时标记它的可见性
-    pub fn add_one_mod(&mut self, mod_name: &String, visibility: &Visibility) {
+    /// 添加一个新模块，同时标记它的可见性。知道模块did的时候也记下来，Restricted(did)才能找到范围
+    pub fn add_one_mod(
+        &mut self,
+        mod_name: &String,
+        visibility: &Visibility,
+        def_id: Option<LocalDefId>,
+    ) {
         self.inner.insert(mod_name.clone(), visibility.clone());
-    }
-
-    /// 获取所有对外不可见的模块
-    /// FIXME:  按照父子模块来做的，但实际上好像并无必要
-    pub fn get_invisible_mods(&self) -> Vec<String> {
-        let mod_number = self.inner.len();
-
-        if !self.inner.contains_key(&self.crate_name) {
-            panic!("No crate mod");
+        if let Some(def_id) = def_id {
+            self.mod_paths.insert(def_id, mod_name.clone());
         }
-        //论文框架
-        //title
-        //简介 别人做什么 没做 我为什么比他们好
-        //背景 技术特点
-        //技术流程 流程图 分模块
-        //如何实验
+    }
 
-        // 存入已经处理过的mod
-        let mut new_mod_visibility = FxHashMap::default();
+    /// 添加一个类型或者trait的可见性
+    pub fn add_one_item(&mut self, item_name: &String, visibility: &Visibility) {
+        self.items.insert(item_name.clone(), *visibility);
+    }
 
-        // 根模块肯定可见
-        new_mod_visibility.insert(self.crate_name.clone(), true);
-        for _ in 0..mod_number {
-            //对于每一个模块
-            for (mod_name, visibility) in &self.inner {
-                if new_mod_visibility.contains_key(mod_name) {
-                    //如果已经获得了该模块的可见性，就跳过
-                    continue;
+    /// 从viewer模块能否访问可见性为visibility的item，viewer为None表示crate外面
+    pub fn is_visible_from(&self, visibility: &Visibility, viewer: Option<&str>) -> bool {
+        match visibility {
+            Visibility::Public => true,
+            Visibility::Restricted(module) => match (viewer, self.mod_paths.get(module)) {
+                (Some(viewer), Some(mod_path)) => {
+                    viewer == mod_path.as_str()
+                        || viewer.starts_with(format!("{}::", mod_path).as_str())
                 }
+                _ => false,
+            },
+        }
+    }
 
-                //要先获得parent模块的可见性
-                let parent_mod_name = get_parent_mod_name(mod_name).unwrap();
-
-                let parent_visibility = match new_mod_visibility.get(&parent_mod_name) {
-                    Some(v) => v,
-                    None => {
-                        //要先获得parent模块的可见性，如果没找到parent mod的可见性，就continue
-                        continue;
-                    }
-                };
-
-                //如果模块可见，并且自己可见，就是true
-                if Visibility::Public == *visibility && *parent_visibility {
-                    new_mod_visibility.insert(mod_name.clone(), true);
-                } else {
-                    new_mod_visibility.insert(mod_name.clone(), false);
-                }
-            }
+    /// 可见性的写法，用在提示里
+    pub fn describe(&self, visibility: &Visibility) -> String {
+        match visibility {
+            Visibility::Public => "pub".to_string(),
+            Visibility::Restricted(module) if *module == CRATE_DEF_ID => "pub(crate)".to_string(),
+            Visibility::Restricted(module) => match self.mod_paths.get(module) {
+                Some(mod_path) => format!("pub(in {})", mod_path),
+                None => "restricted".to_string(),
+            },
+        }
+    }
 
-            if new_mod_visibility.len() == mod_number {
-                break;
+    /// full_name外面的模块、类型和trait里第一个从viewer访问不到的，返回它的路径和可见性，
+    /// 不检查full_name自己（函数的可见性另外看）
+    pub fn invisible_ancestor(
+        &self,
+        full_name: &str,
+        viewer: Option<&str>,
+    ) -> Option<(String, Visibility)> {
+        let segments: Vec<&str> = full_name.split("::").collect();
+        //crate根模块肯定可见，从第一层子模块开始
+        for len in 2..segments.len() {
+            let prefix = segments[..len].join("::");
+            let visibility = match self.inner.get(&prefix).or_else(|| self.items.get(&prefix)) {
+                Some(visibility) => visibility,
+                None => continue,
+            };
+            if !self.is_visible_from(visibility, viewer) {
+                return Some((prefix, *visibility));
             }
         }
+        None
+    }
 
-        let mut res = Vec::new();
-        for (mod_name, visibility) in &new_mod_visibility {
-            if !*visibility {
-                res.push(mod_name.clone());
+    /// 和invisible_ancestor一样，但是也检查path自己，用于trait的路径
+    pub fn invisible_path(&self, path: &str, viewer: Option<&str>) -> Option<(String, Visibility)> {
+        if let Some(found) = self.invisible_ancestor(path, viewer) {
+            return Some(found);
+        }
+        match self.items.get(path) {
+            Some(visibility) if !self.is_visible_from(visibility, viewer) => {
+                Some((path.to_string(), *visibility))
             }
+            _ => None,
         }
-        res
     }
 }
 
-/// 辅助函数，获取父模块
-pub fn get_parent_mod_name(mod_name: &String) -> Option<String> {
-    if !mod_name.contains("::") {
-        return None;
+/// 从crate根开始，只经过公开的模块，记录本crate每个函数和类型的最短公开路径（放在full_name_map.public_paths里）。
+/// 除了定义的位置，还会沿着`pub use`重导出找（重命名的用新名字），比如私有模块inner里的Foo
+/// 在根模块`pub use inner::Foo`之后路径是crate::Foo；重导出的模块里的item也会按新的模块路径记录。
+/// 按层广度优先遍历，先遇到的模块路径最短，每个模块只展开一次，循环重导出也不会死循环
+pub(crate) fn record_public_paths(tcx: TyCtxt<'_>, full_name_map: &mut FullNameMap) {
+    let mut visited = FxHashSet::default();
+    let mut queue = VecDeque::new();
+    queue.push_back((CRATE_DEF_ID.to_def_id(), tcx.crate_name(LOCAL_CRATE).to_string()));
+    while let Some((module, mod_path)) = queue.pop_front() {
+        let Some(local_module) = module.as_local() else {
+            continue;
+        };
+        if !visited.insert(module) {
+            continue;
+        }
+        //模块里直接定义的公开item，函数体里定义的item的parent不是模块，没有路径
+        let mut children = Vec::new();
+        for item_id in tcx.hir().module_items(local_module) {
+            let def_id = item_id.owner_id.to_def_id();
+            if tcx.opt_parent(def_id) != Some(module) || !tcx.visibility(def_id).is_public() {
+                continue;
+            }
+            if let Some(name) = tcx.opt_item_name(def_id) {
+                children.push((name, tcx.def_kind(def_id), def_id));
+            }
+        }
+        //公开的重导出，包括glob重导出的每个item
+        for child in tcx.module_reexports(local_module).unwrap_or(&[]) {
+            if let Res::Def(def_kind, def_id) = child.res {
+                if child.vis.is_public() && def_id.is_local() {
+                    children.push((child.ident.name, def_kind, def_id));
+                }
+            }
+        }
+        for (name, def_kind, def_id) in children {
+            let path = format!("{}::{}", mod_path, name);
+            match def_kind {
+                DefKind::Mod => queue.push_back((def_id, path)),
+                DefKind::Fn
+                | DefKind::Struct
+                | DefKind::Enum
+                | DefKind::Union
+                | DefKind::Trait
+                | DefKind::TyAlias
+                | DefKind::Const
+                | DefKind::Static(_) => full_name_map.push_public_path(def_id, path),
+                _ => {}
+            }
+        }
     }
-    let mut mod_split: Vec<&str> = mod_name.as_str().split("::").collect();
-    mod_split.pop();
-    let parent_mod_name = mod_split.join("::");
-    Some(parent_mod_name)
+    println!("recorded public paths of {} items", full_name_map.public_paths.len());
 }
@@ -0,0 +1,191 @@
+//! 整数类型之间的依赖边
+//! 返回整数的函数是终结函数，整数参数又总是从fuzz输入解码，所以`len()`返回的usize
+//! 没法传给需要u32的参数，数值密集的crate里很多有意义的序列都生成不出来。
+//! 这里给整数返回值到整数参数加上依赖边，宽度不同的时候转换一下：
+//! 1. Checked：`TryInto::<u32>::try_into(x)`，超出范围时和Err一样结束这次执行，默认
+//! 2. As：`x as u32`，直接截断
+//! 3. Off：不加整数的依赖边，和以前一样
+//! 序列里还没被用到的整数返回值会优先传给后面的整数参数，见ApiGraph::is_fun_satisfied
+
+use crate::clean::{self, PrimitiveType};
+use crate::fuzz_targets_gen::api_graph::{ApiDependency, ApiGraph, ApiType};
+use crate::fuzz_targets_gen::api_sequence::{ApiCall, ApiSequence, ParamType};
+use crate::fuzz_targets_gen::call_type::CallType;
+
+/// 整数宽度不同时怎么转换
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NumericCast {
+    #[default]
+    Checked,
+    As,
+    Off,
+}
+
+impl NumericCast {
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "checked" => Some(NumericCast::Checked),
+            "as" => Some(NumericCast::As),
+            "off" => Some(NumericCast::Off),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            NumericCast::Checked => "checked",
+            NumericCast::As => "as",
+            NumericCast::Off => "off",
+        }
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        *self != NumericCast::Off
+    }
+}
+
+/// 是否是整数类型
+pub(crate) fn is_integer(type_: &clean::Type) -> bool {
+    matches!(
+        type_,
+        clean::Type::Primitive(
+            PrimitiveType::Isize
+                | PrimitiveType::I8
+                | PrimitiveType::I16
+                | PrimitiveType::I32
+                | PrimitiveType::I64
+                | PrimitiveType::I128
+                | PrimitiveType::Usize
+                | PrimitiveType::U8
+                | PrimitiveType::U16
+                | PrimitiveType::U32
+                | PrimitiveType::U64
+                | PrimitiveType::U128
+        )
+    )
+}
+
+/// 整数输出到整数输入的call type，类型一样的时候直接用
+pub(crate) fn numeric_call_type(
+    output_type: &clean::Type,
+    input_type: &clean::Type,
+    numeric_cast: NumericCast,
+) -> Option<CallType> {
+    if !numeric_cast.is_enabled() || !is_integer(output_type) || !is_integer(input_type) {
+        return None;
+    }
+    if output_type == input_type {
+        return Some(CallType::_DirectCall);
+    }
+    let clean::Type::Primitive(input_primitive) = input_type else {
+        return None;
+    };
+    let target = input_primitive.as_sym().to_string();
+    Some(CallType::_NumericCast(target, numeric_cast == NumericCast::Checked))
+}
+
+/// 给返回整数的函数加上到所有整数参数的依赖边，find_all_dependencies跳过了这些终结函数
+pub(crate) fn add_numeric_dependencies(api_graph: &mut ApiGraph<'_>) {
+    if !api_graph.numeric_cast.is_enabled() {
+        return;
+    }
+    let mut dependencies = Vec::new();
+    for (i, first_fun) in api_graph.api_functions.iter().enumerate() {
+        let Some(output_type) = &first_fun.output else {
+            continue;
+        };
+        if !is_integer(output_type) || first_fun._is_diverging() {
+            continue;
+        }
+        for (j, second_fun) in api_graph.api_functions.iter().enumerate() {
+            for (k, input_type) in second_fun.inputs.iter().enumerate() {
+                if let Some(call_type) =
+                    numeric_call_type(output_type, input_type, api_graph.numeric_cast)
+                {
+                    dependencies.push(ApiDependency {
+                        output_fun: (ApiType::BareFunction, i),
+                        input_fun: (ApiType::BareFunction, j),
+                        input_param_index: k,
+                        call_type,
+                    });
+                }
+            }
+        }
+    }
+    println!("add {} numeric dependencies", dependencies.len());
+    for dependency in dependencies {
+        api_graph.push_dependency(dependency);
+    }
+}
+
+/// 序列里第index个调用返回整数，并且返回值还没被用到（包括正在构造的api_call）
+fn is_pending_producer(
+    api_graph: &ApiGraph<'_>,
+    sequence: &ApiSequence,
+    api_call: &ApiCall,
+    index: usize,
+) -> bool {
+    let function = &api_graph.api_functions[sequence.functions[index].func.1];
+    if !function.output.as_ref().map_or(false, is_integer) {
+        return false;
+    }
+    let is_used = |call: &ApiCall| {
+        call.params.iter().any(|(param_type, param_index, _)| {
+            *param_type == ParamType::_FunctionReturn && *param_index == index
+        })
+    };
+    !sequence.functions.iter().any(is_used) && !is_used(api_call)
+}
+
+/// 给input_fun_index的第param_index个整数参数找一个还没被用到的整数返回值，离得近的优先，
+/// 返回(序列里的下标, 依赖边的下标)
+pub(crate) fn pending_producer(
+    api_graph: &ApiGraph<'_>,
+    sequence: &ApiSequence,
+    api_call: &ApiCall,
+    input_fun_index: usize,
+    param_index: usize,
+) -> Option<(usize, usize)> {
+    if !api_graph.numeric_cast.is_enabled() {
+        return None;
+    }
+    (0..sequence.functions.len()).rev().find_map(|index| {
+        if !is_pending_producer(api_graph, sequence, api_call, index) {
+            return None;
+        }
+        let (api_type, output_index) = &sequence.functions[index].func;
+        api_graph
+            .check_dependency(
+                api_type,
+                *output_index,
+                &ApiType::BareFunction,
+                input_fun_index,
+                param_index,
+            )
+            .map(|dependency_index| (index, dependency_index))
+    })
+}
+
+/// 序列的最后一个调用是返回整数的终结函数，后面还可以接用上这个返回值的调用
+pub(crate) fn ends_with_producer(api_graph: &ApiGraph<'_>, sequence: &ApiSequence) -> bool {
+    if !api_graph.numeric_cast.is_enabled() {
+        return false;
+    }
+    let Some(last_call) = sequence.functions.last() else {
+        return false;
+    };
+    let function = &api_graph.api_functions[last_call.func.1];
+    function.output.as_ref().map_or(false, is_integer)
+        && function._is_end_function(api_graph.cache, &api_graph.full_name_map, false)
+}
+
+/// 序列以返回整数的终结函数结尾的时候，新加入的调用必须用上它的返回值，
+/// 否则序列在这里就该结束（和以前终结函数的处理一样），不然会多出很多含有死代码的序列
+pub(crate) fn leaves_pending_producer(
+    api_graph: &ApiGraph<'_>,
+    sequence: &ApiSequence,
+    api_call: &ApiCall,
+) -> bool {
+    ends_with_producer(api_graph, sequence)
+        && is_pending_producer(api_graph, sequence, api_call, sequence.functions.len() - 1)
+}
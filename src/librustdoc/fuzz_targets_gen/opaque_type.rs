@@ -0,0 +1,77 @@
+//! 返回值位置的impl Trait（不透明类型）
+//! 返回`impl Iterator<Item = T>`这类类型的函数以前不能给别的函数提供参数，很多依赖边因此断掉。
+//! 调用者只能通过bound里的trait使用不透明类型的值，所以只连两种依赖：
+//! 1. 参数也是impl Trait（已经被name_impl_trait_params换成了synthetic泛型参数），并且参数的bound都出现在返回值的bound里
+//! 2. 返回值的bound里有Iterator、Read、Write的时候，foreign_trait.rs为这个不透明类型加的辅助函数，
+//!    辅助函数本身是泛型的，可以直接接受不透明类型的值
+
+use crate::clean::{self};
+use crate::formats::cache::Cache;
+use crate::fuzz_targets_gen::api_function::ApiFunction;
+use crate::fuzz_targets_gen::call_type::CallType;
+use crate::fuzz_targets_gen::generic_candidate;
+use itertools::Itertools;
+use rustc_hir::{Mutability, TraitBoundModifier};
+
+/// 不透明类型的返回值能否作为input_fun第input_index个参数，以及怎么传入
+pub(crate) fn opaque_call_type(
+    output_type: &clean::Type,
+    input_fun: &ApiFunction,
+    input_index: usize,
+) -> CallType {
+    let clean::Type::ImplTrait(output_bounds) = output_type else {
+        return CallType::_NotCompatible;
+    };
+    let (inner_type, mutability) = match &input_fun.inputs[input_index] {
+        clean::Type::BorrowedRef { mutability, type_, .. } => (&**type_, Some(*mutability)),
+        input_type => (input_type, None),
+    };
+    let compatible = match inner_type {
+        //辅助函数的参数就是这个不透明类型
+        clean::Type::ImplTrait(..) => inner_type == output_type,
+        clean::Type::Generic(name) => {
+            input_fun
+                ._generics
+                .params
+                .iter()
+                .any(|param| param.name == *name && param.is_synthetic_type_param())
+                && generic_candidate::bounds_of(&input_fun._generics, *name).iter().all(|bound| {
+                    match bound {
+                        clean::GenericBound::TraitBound(_, TraitBoundModifier::Maybe)
+                        | clean::GenericBound::Outlives(..) => true,
+                        clean::GenericBound::TraitBound(..) => output_bounds.contains(*bound),
+                    }
+                })
+        }
+        _ => false,
+    };
+    if !compatible {
+        return CallType::_NotCompatible;
+    }
+    match mutability {
+        None => CallType::_DirectCall,
+        Some(Mutability::Mut) => CallType::_MutBorrowedRef(Box::new(CallType::_DirectCall)),
+        Some(Mutability::Not) => CallType::_BorrowedRef(Box::new(CallType::_DirectCall)),
+    }
+}
+
+/// 不透明类型bound里的trait的全路径，trait可能来自外部crate
+pub(crate) fn bound_trait_paths(output_type: &clean::Type, cache: &Cache) -> Vec<String> {
+    let clean::Type::ImplTrait(bounds) = output_type else {
+        return Vec::new();
+    };
+    bounds
+        .iter()
+        .filter_map(|bound| match bound {
+            clean::GenericBound::TraitBound(poly_trait, _) => {
+                let trait_def_id = poly_trait.trait_.def_id();
+                let (path, _) = cache
+                    .paths
+                    .get(&trait_def_id)
+                    .or_else(|| cache.external_paths.get(&trait_def_id))?;
+                Some(path.iter().map(|symbol| symbol.as_str()).join("::"))
+            }
+            clean::GenericBound::Outlives(..) => None,
+        })
+        .collect()
+}
@@ -0,0 +1,18 @@
+//! 差分测试的oracle
+//! 配置是 被测API全路径 -> 参考实现的函数路径，比如
+//!     my_crate::fast_sort  |  my_crate::naive_sort
+//! 参考实现需要和被测API有相同的参数列表，返回值需要实现PartialEq和Debug。
+//! 生成测试文件的时候，被测API的参数全部来自fuzz输入时，会把参数复制一份交给参考实现，
+//! 然后断言返回值（以及通过`&mut`修改的参数）一致，这样可以发现不会crash的逻辑错误。
+//! 没有内置的oracle，都从配置文件读取，生成的代码见ApiSequence::_oracle_check_lines
+
+use crate::fuzz_targets_gen::file_util;
+use rustc_data_structures::fx::FxHashMap;
+
+/// 从文件读取oracle，每行的格式是 `被测API全路径  |  参考实现的函数路径`
+/// 文件不存在的话什么都不做
+pub(crate) fn load_oracles_from_file(file_path: &str, oracles: &mut FxHashMap<String, String>) {
+    for (target, reference) in file_util::read_config_pairs(file_path) {
+        oracles.insert(target, reference);
+    }
+}
@@ -0,0 +1,135 @@
+//! 捕获panic的测试函数
+//! 很多API在文档里写明了会panic（比如下标越界、参数不满足前提条件），fuzz的时候这些panic会淹没真正的问题。
+//! 开启之后测试函数的函数体放到`std::panic::catch_unwind`里，按panic的消息分类，
+//! 只有用户关心的类别才打印消息并abort，交给fuzz引擎当成crash，其他的类别直接忽略。
+//! afl和libfuzzer都会装自己的panic hook，在panic的时候直接abort，所以测试函数开头先换成什么都不做的hook
+
+/// panic消息的类别
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) enum PanicClass {
+    /// assert!、assert_eq!等断言失败
+    Assertion,
+    /// 下标或者切片范围越界，包括字符串的字符边界
+    Index,
+    /// debug模式下的算术溢出
+    Overflow,
+    /// 对None或者Err调用unwrap
+    Unwrap,
+    /// 其他的panic，通常是panic!或者expect
+    Explicit,
+}
+
+impl PanicClass {
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "assertion" => Some(PanicClass::Assertion),
+            "index" => Some(PanicClass::Index),
+            "overflow" => Some(PanicClass::Overflow),
+            "unwrap" => Some(PanicClass::Unwrap),
+            "explicit" => Some(PanicClass::Explicit),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            PanicClass::Assertion => "assertion",
+            PanicClass::Index => "index",
+            PanicClass::Overflow => "overflow",
+            PanicClass::Unwrap => "unwrap",
+            PanicClass::Explicit => "explicit",
+        }
+    }
+}
+
+/// 逗号分隔的类别列表，`none`表示所有的panic都忽略
+pub(crate) fn parse_panic_classes(list: &str) -> Result<Vec<PanicClass>, String> {
+    let mut classes = Vec::new();
+    for name in list.split(',').map(|name| name.trim()).filter(|name| !name.is_empty()) {
+        if name == "none" {
+            continue;
+        }
+        match PanicClass::from_name(name) {
+            Some(class) if !classes.contains(&class) => classes.push(class),
+            Some(_) => {}
+            None => return Err(name.to_string()),
+        }
+    }
+    Ok(classes)
+}
+
+/// 安装hook和重新抛出panic的辅助函数，classes是需要当成crash的类别
+pub(crate) fn _panic_filter_helper_functions(classes: &[PanicClass]) -> String {
+    let class_names =
+        classes.iter().map(|class| format!("\"{}\"", class.name())).collect::<Vec<_>>().join(", ");
+    format!(
+        "const _CRASH_PANIC_CLASSES: &[&str] = &[{}];\n{}",
+        class_names,
+        _panic_filter_functions()
+    )
+}
+
+fn _panic_filter_functions() -> &'static str {
+    "fn _install_panic_hook() {
+    static _PANIC_HOOK: std::sync::Once = std::sync::Once::new();
+    _PANIC_HOOK.call_once(|| std::panic::set_hook(Box::new(|_| {})));
+}
+
+fn _panic_class(message: &str) -> &'static str {
+    if message.starts_with(\"assertion\") {
+        \"assertion\"
+    } else if message.contains(\"index out of bounds\")
+        || message.contains(\"range start index\")
+        || message.contains(\"range end index\")
+        || message.contains(\"slice index starts at\")
+        || message.contains(\"is not a char boundary\")
+    {
+        \"index\"
+    } else if message.contains(\"with overflow\") {
+        \"overflow\"
+    } else if message.starts_with(\"called `Option::unwrap()`\")
+        || message.starts_with(\"called `Result::unwrap()`\")
+    {
+        \"unwrap\"
+    } else {
+        \"explicit\"
+    }
+}
+
+fn _reraise_panic<T: Default>(payload: Box<dyn std::any::Any + Send>) -> T {
+    let message = if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        String::new()
+    };
+    let class = _panic_class(&message);
+    if _CRASH_PANIC_CLASSES.contains(&class) {
+        eprintln!(\"panic ({}): {}\", class, message);
+        std::process::abort();
+    }
+    T::default()
+}\n"
+}
+
+/// 测试函数开头的语句，return_type是闭包的返回值类型（可以为空）
+pub(crate) fn _catch_unwind_prefix(indent: &str, return_type: &str) -> String {
+    format!(
+        "{indent}_install_panic_hook();\n\
+         {indent}match std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {return_type}{{\n",
+        indent = indent,
+        return_type = return_type
+    )
+}
+
+/// 测试函数结尾的语句，和_catch_unwind_prefix对应
+pub(crate) fn _catch_unwind_suffix(indent: &str) -> String {
+    format!(
+        "{indent}}})) {{\n\
+         {indent}    Ok(_res) => _res,\n\
+         {indent}    Err(_payload) => _reraise_panic(_payload),\n\
+         {indent}}}\n",
+        indent = indent
+    )
+}
@@ -0,0 +1,72 @@
+//! Path/PathBuf参数
+//! 读写文件的crate很多API的参数是`&Path`、`PathBuf`，以前这些参数没法从fuzz输入构造，
+//! 只能等序列里有别的函数返回PathBuf。--fuzz-path-mode选择怎么构造：
+//! 1. TempFile：fuzz输入写进一个临时文件，传入临时文件的路径，读文件的API就能拿到fuzz的内容。
+//!    每个参数用一个固定的文件（进程号加参数名），每次执行覆盖写入，不会越积越多
+//! 2. Str：fuzz输入直接作为路径字符串，适合只解析路径、不访问文件系统的API
+//! 3. Off：不构造，默认。Str模式下被测函数可能按fuzz出来的路径删除、改写任意文件
+//! 两种模式都先构造出PathBuf，`&Path`参数借用它，传参时Deref成`&Path`
+
+use crate::clean;
+use crate::fuzz_targets_gen::fuzz_type::FuzzableCallType;
+use rustc_span::symbol::sym;
+
+/// Path参数怎么构造
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PathMode {
+    #[default]
+    Off,
+    TempFile,
+    Str,
+}
+
+impl PathMode {
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "off" => Some(PathMode::Off),
+            "temp-file" => Some(PathMode::TempFile),
+            "string" => Some(PathMode::Str),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            PathMode::Off => "off",
+            PathMode::TempFile => "temp-file",
+            PathMode::Str => "string",
+        }
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        *self != PathMode::Off
+    }
+}
+
+/// std::path::Path或者PathBuf，按path_mode构造，其他类型返回None
+pub(crate) fn path_call_type(type_: &clean::Type, path_mode: PathMode) -> Option<FuzzableCallType> {
+    if !path_mode.is_enabled() {
+        return None;
+    }
+    let clean::Type::Path { path } = type_ else {
+        return None;
+    };
+    //本crate里同名的类型不算
+    if path.def_id().is_local() || !matches!(path.last(), sym::Path | sym::PathBuf) {
+        return None;
+    }
+    Some(FuzzableCallType::PathBuf(path_mode))
+}
+
+/// 把输入写进临时文件并返回路径的辅助函数，name区分同一个序列里的不同参数。
+/// 写入失败时和Err一样结束这次执行
+pub(crate) fn temp_file_helper_function() -> &'static str {
+    "fn _temp_file_path(name: &str, data: &[u8]) -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(format!(\"fries_{}{}\", std::process::id(), name));
+    if std::fs::write(&path, data).is_err() {
+        use std::process;
+        process::exit(0);
+    }
+    path
+}\n"
+}
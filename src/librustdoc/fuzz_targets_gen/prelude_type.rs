@@ -4,6 +4,7 @@
 use crate::fuzz_targets_gen::api_util;
 use crate::fuzz_targets_gen::call_type::CallType;
 use crate::fuzz_targets_gen::impl_util::FullNameMap;
+use crate::fuzz_targets_gen::path_param;
 use rustc_data_structures::fx::{FxHashMap, FxHashSet};
 
 // 这里是一些预定义的类型，左边是path，右边是名字
@@ -233,13 +234,25 @@ pub(crate) fn _prelude_type_need_special_dealing(
 pub(crate) enum _PreludeHelper {
     _ResultHelper,
     _OptionHelper,
+    _TempFileHelper,
+    _OsStringHelper,
 }
 
 impl _PreludeHelper {
     pub(crate) fn _from_call_type(call_type: &CallType) -> FxHashSet<_PreludeHelper> {
         match call_type {
-            CallType::_DirectCall | CallType::_NotCompatible | CallType::_AsConvert(_) => {
-                FxHashSet::default()
+            CallType::_DirectCall
+            | CallType::_NotCompatible
+            | CallType::_AsConvert(_)
+            | CallType::_CustomConvert(_)
+            | CallType::_NumericCast(_, false)
+            | CallType::_NewWriter
+            | CallType::_InstantNow => FxHashSet::default(),
+            //try_into的结果用_unwrap_result取出来
+            CallType::_NumericCast(_, true) => {
+                let mut helpers = FxHashSet::default();
+                helpers.insert(_PreludeHelper::_ResultHelper);
+                helpers
             }
             CallType::_BorrowedRef(inner_call_type)
             | CallType::_ConstRawPointer(inner_call_type, _)
@@ -248,9 +261,38 @@ pub(crate) fn _from_call_type(call_type: &CallType) -> FxHashSet<_PreludeHelper>
             | CallType::_Deref(inner_call_type)
             | CallType::_ToOption(inner_call_type)
             | CallType::_ToResult(inner_call_type)
-            | CallType::_UnsafeDeref(inner_call_type) => {
+            | CallType::_UnsafeDeref(inner_call_type)
+            | CallType::_DynCoerce(inner_call_type, _)
+            | CallType::_ToBox(inner_call_type)
+            | CallType::_ToRc(inner_call_type)
+            | CallType::_ToArc(inner_call_type)
+            | CallType::_PinNew(inner_call_type)
+            | CallType::_DerefCoerce(inner_call_type, _)
+            | CallType::_AsRef(inner_call_type)
+            | CallType::_Borrow(inner_call_type)
+            | CallType::_IntoConversion(inner_call_type, _)
+            | CallType::_CowBorrowed(inner_call_type)
+            | CallType::_CowOwned(inner_call_type)
+            | CallType::_ToPathBuf(inner_call_type)
+            | CallType::_ToCursor(inner_call_type)
+            | CallType::_ToDuration(inner_call_type)
+            | CallType::_ToSystemTime(inner_call_type)
+            | CallType::_ToOwned(inner_call_type)
+            | CallType::_ToVec(inner_call_type)
+            | CallType::_RefToRawPointer(inner_call_type, ..)
+            | CallType::_UninitAsMutPtr(inner_call_type) => {
                 _PreludeHelper::_from_call_type(&**inner_call_type)
             }
+            CallType::_TempFilePath(inner_call_type) => {
+                let mut inner_helpers = _PreludeHelper::_from_call_type(inner_call_type);
+                inner_helpers.insert(_PreludeHelper::_TempFileHelper);
+                inner_helpers
+            }
+            CallType::_ToOsString(inner_call_type) => {
+                let mut inner_helpers = _PreludeHelper::_from_call_type(inner_call_type);
+                inner_helpers.insert(_PreludeHelper::_OsStringHelper);
+                inner_helpers
+            }
             CallType::_UnwrapOption(inner_call_type) => {
                 let mut inner_helpers = _PreludeHelper::_from_call_type(inner_call_type);
                 inner_helpers.insert(_PreludeHelper::_OptionHelper);
@@ -268,6 +310,8 @@ pub(crate) fn _to_helper_function(&self) -> &'static str {
         match self {
             _PreludeHelper::_ResultHelper => _unwrap_result_function(),
             _PreludeHelper::_OptionHelper => _unwrap_option_function(),
+            _PreludeHelper::_TempFileHelper => path_param::temp_file_helper_function(),
+            _PreludeHelper::_OsStringHelper => _to_os_string_function(),
         }
     }
 }
@@ -295,3 +339,18 @@ fn _unwrap_option_function() -> &'static str {
     }
 }\n"
 }
+
+/// unix上OsString可以是任意字节，直接从fuzz输入构造；其他平台只能经过UTF-8，无效的部分换成U+FFFD
+fn _to_os_string_function() -> &'static str {
+    "fn _to_os_string(data: &[u8]) -> std::ffi::OsString {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        std::ffi::OsStr::from_bytes(data).to_os_string()
+    }
+    #[cfg(not(unix))]
+    {
+        std::ffi::OsString::from(String::from_utf8_lossy(data).into_owned())
+    }
+}\n"
+}
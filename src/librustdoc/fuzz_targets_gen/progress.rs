@@ -0,0 +1,146 @@
+//! 生成过程中各个阶段的进度
+//! 依赖分析、BFS、反向搜索、序列选择在大的crate上可能要跑很久，
+//! 每个阶段用一个Progress记录已经处理的函数数、已经生成的序列数，并估算剩余时间
+//! 输出方式由环境变量FRIES_PROGRESS决定，没有设置的时候标准输出是终端就用bar，否则用quiet：
+//! 1. bar：终端里原地刷新的一行进度条
+//! 2. quiet：机器可读的输出，每隔一段时间输出一行`progress | 阶段 | 已完成 | 总数 | 序列数 | 剩余秒数`，
+//!    重定向到文件或者在CI里运行的时候不会留下满屏的`\r`
+//! 3. off：不输出
+
+use std::io::{IsTerminal, Write};
+use std::time::{Duration, Instant};
+
+/// 进度条的宽度（字符数）
+static BAR_WIDTH: usize = 30;
+/// 进度条的刷新间隔
+static BAR_REFRESH_INTERVAL: Duration = Duration::from_millis(100);
+/// quiet模式下两行输出之间的最小间隔
+static QUIET_REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ProgressMode {
+    Bar,
+    Quiet,
+    Off,
+}
+
+lazy_static! {
+    static ref PROGRESS_MODE: ProgressMode = match std::env::var("FRIES_PROGRESS") {
+        Ok(mode) if mode == "quiet" => ProgressMode::Quiet,
+        Ok(mode) if mode == "off" => ProgressMode::Off,
+        Ok(mode) if mode == "bar" => ProgressMode::Bar,
+        _ if std::io::stdout().is_terminal() => ProgressMode::Bar,
+        _ => ProgressMode::Quiet,
+    };
+}
+
+/// 一个阶段的进度
+pub(crate) struct Progress {
+    phase: &'static str,
+    mode: ProgressMode,
+    /// 总的工作量，None表示事先不知道，此时不估算剩余时间
+    total: Option<usize>,
+    done: usize,
+    sequences: usize,
+    start: Instant,
+    last_report: Option<Instant>,
+}
+
+impl Progress {
+    pub(crate) fn new(phase: &'static str, total: Option<usize>) -> Self {
+        Progress {
+            phase,
+            mode: *PROGRESS_MODE,
+            total,
+            done: 0,
+            sequences: 0,
+            start: Instant::now(),
+            last_report: None,
+        }
+    }
+
+    /// 完成了n个单位的工作
+    pub(crate) fn inc(&mut self, n: usize) {
+        self.done += n;
+        self.report(false);
+    }
+
+    /// 更新目前生成的序列数（或依赖数）
+    pub(crate) fn set_sequences(&mut self, sequences: usize) {
+        self.sequences = sequences;
+        self.report(false);
+    }
+
+    /// 阶段结束，输出最后一次进度
+    pub(crate) fn finish(&mut self) {
+        if let Some(total) = self.total {
+            self.done = self.done.max(total);
+        }
+        self.report(true);
+        if self.mode == ProgressMode::Bar {
+            println!();
+        }
+    }
+
+    /// 根据已经用掉的时间和完成的比例估算剩余时间
+    fn eta(&self) -> Option<Duration> {
+        let total = self.total?;
+        if self.done == 0 || self.done >= total {
+            return None;
+        }
+        let elapsed = self.start.elapsed().as_secs_f64();
+        Some(Duration::from_secs_f64(elapsed / self.done as f64 * (total - self.done) as f64))
+    }
+
+    fn report(&mut self, force: bool) {
+        let interval = match self.mode {
+            ProgressMode::Off => return,
+            ProgressMode::Bar => BAR_REFRESH_INTERVAL,
+            ProgressMode::Quiet => QUIET_REFRESH_INTERVAL,
+        };
+        let now = Instant::now();
+        if !force {
+            if let Some(last_report) = self.last_report {
+                if now.duration_since(last_report) < interval {
+                    return;
+                }
+            }
+        }
+        self.last_report = Some(now);
+        let eta_secs = self.eta().map(|eta| eta.as_secs());
+        match self.mode {
+            ProgressMode::Bar => self.print_bar(eta_secs),
+            ProgressMode::Quiet => println!(
+                "progress | {} | {} | {} | {} | {}",
+                self.phase,
+                self.done,
+                self.total.map_or("-".to_string(), |total| total.to_string()),
+                self.sequences,
+                eta_secs.map_or("-".to_string(), |eta| eta.to_string()),
+            ),
+            ProgressMode::Off => {}
+        }
+    }
+
+    fn print_bar(&self, eta_secs: Option<u64>) {
+        let bar = match self.total {
+            Some(total) if total > 0 => {
+                let filled = (self.done.min(total) * BAR_WIDTH) / total;
+                format!(
+                    "[{}{}] {}/{}",
+                    "#".repeat(filled),
+                    "-".repeat(BAR_WIDTH - filled),
+                    self.done,
+                    total
+                )
+            }
+            _ => format!("{}", self.done),
+        };
+        let eta = match eta_secs {
+            Some(eta) => format!(", eta {}s", eta),
+            None => String::new(),
+        };
+        print!("\r{}: {} ({} sequences{})\x1b[K", self.phase, bar, self.sequences, eta);
+        let _ = std::io::stdout().flush();
+    }
+}
@@ -0,0 +1,137 @@
+//! 手写调用序列
+//! 用户给出一串API的全路径，以及可选的参数绑定（第几个参数用第几个调用的返回值），
+//! 逐个用is_fun_satisfied检查能否加入序列，得到可以直接生成测试文件的ApiSequence
+//! 用来补充搜索找不到、但是已知容易出问题的调用场景
+
+use crate::fuzz_targets_gen::api_graph::{ApiGraph, ApiType};
+use crate::fuzz_targets_gen::api_sequence::{ApiSequence, ParamType};
+use crate::fuzz_targets_gen::api_util;
+
+/// 序列里的一个调用：函数全路径，以及(参数下标, 提供返回值的调用下标)
+struct ManualCall {
+    path: String,
+    bindings: Vec<(usize, usize)>,
+}
+
+/// 逐个添加调用，最后用build检查并生成序列
+pub struct SequenceBuilder<'g, 'a> {
+    graph: &'g ApiGraph<'a>,
+    calls: Vec<ManualCall>,
+}
+
+impl<'g, 'a> SequenceBuilder<'g, 'a> {
+    pub(crate) fn new(graph: &'g ApiGraph<'a>) -> Self {
+        SequenceBuilder { graph, calls: Vec::new() }
+    }
+
+    /// 在序列末尾调用全路径为path的函数
+    pub fn call(mut self, path: &str) -> Self {
+        self.calls.push(ManualCall { path: path.to_string(), bindings: Vec::new() });
+        self
+    }
+
+    /// 最后一个调用的第param_index个参数使用第call_index个调用的返回值
+    /// 没有绑定的参数由is_fun_satisfied自己选择
+    pub fn bind(mut self, param_index: usize, call_index: usize) -> Self {
+        if let Some(last_call) = self.calls.last_mut() {
+            last_call.bindings.push((param_index, call_index));
+        }
+        self
+    }
+
+    /// 检查并生成序列，出错时返回出错的调用和原因
+    pub(crate) fn build(&self) -> Result<ApiSequence, String> {
+        let graph = self.graph;
+        let mut sequence = ApiSequence::new();
+        for (call_index, manual_call) in self.calls.iter().enumerate() {
+            let function_index = graph
+                .api_functions
+                .iter()
+                .position(|api_function| api_function.full_name == manual_call.path)
+                .ok_or_else(|| {
+                    format!("call {}: unknown function {}", call_index, manual_call.path)
+                })?;
+            let mut new_sequence = graph
+                .is_fun_satisfied(&ApiType::BareFunction, function_index, &sequence)
+                .ok_or_else(|| {
+                    format!("call {}: {} can not be added here", call_index, manual_call.path)
+                })?;
+            for (param_index, source_index) in &manual_call.bindings {
+                self.apply_binding(
+                    &sequence,
+                    &mut new_sequence,
+                    call_index,
+                    *param_index,
+                    *source_index,
+                )
+                .map_err(|e| format!("call {}: {}", call_index, e))?;
+            }
+            sequence = new_sequence;
+        }
+        Ok(sequence)
+    }
+
+    /// 把is_fun_satisfied选出的依赖换成用户指定的调用，并更新move和mut标记
+    fn apply_binding(
+        &self,
+        old_sequence: &ApiSequence,
+        new_sequence: &mut ApiSequence,
+        call_index: usize,
+        param_index: usize,
+        source_index: usize,
+    ) -> Result<(), String> {
+        let graph = self.graph;
+        if source_index >= call_index {
+            return Err(format!("param {} bound to a later call {}", param_index, source_index));
+        }
+        if old_sequence._is_moved(source_index) {
+            return Err(format!("return value of call {} is already moved", source_index));
+        }
+        let function_index = new_sequence.functions[call_index].func.1;
+        let (param_type, chosen_index, _) =
+            match new_sequence.functions[call_index].params.get(param_index) {
+                Some(param) => param.clone(),
+                None => return Err(format!("param {} out of range", param_index)),
+            };
+        if param_type != ParamType::_FunctionReturn {
+            return Err(format!("param {} is fuzzable and can not be bound", param_index));
+        }
+        let source_function = new_sequence.functions[source_index].func.1;
+        let dependency_index = graph
+            .check_dependency(
+                &ApiType::BareFunction,
+                source_function,
+                &ApiType::BareFunction,
+                function_index,
+                param_index,
+            )
+            .ok_or_else(|| {
+                format!(
+                    "return value of call {} can not be used as param {}",
+                    source_index, param_index
+                )
+            })?;
+        let call_type = graph.api_dependencies[dependency_index].call_type.clone();
+        let input_type = &graph.api_functions[function_index].inputs[param_index];
+
+        if api_util::_need_mut_tag(&call_type) {
+            new_sequence._insert_function_mut_tag(source_index);
+        }
+        if api_util::_move_condition(input_type, &call_type) {
+            new_sequence._insert_move_index(source_index);
+        }
+        new_sequence._add_dependency(dependency_index);
+        new_sequence.functions[call_index].params[param_index] =
+            (ParamType::_FunctionReturn, source_index, call_type);
+
+        //原来选的返回值如果只是被这个参数move掉，现在不再被move
+        let still_used =
+            new_sequence.functions[call_index].params.iter().any(|(param_type, index, _)| {
+                *param_type == ParamType::_FunctionReturn && *index == chosen_index
+            });
+        if chosen_index != source_index && !old_sequence._is_moved(chosen_index) && !still_used {
+            new_sequence._moved.remove(&chosen_index);
+        }
+        Ok(())
+    }
+}
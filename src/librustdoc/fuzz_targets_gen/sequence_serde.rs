@@ -0,0 +1,159 @@
+//! ApiSequence的导出和导入
+//! 选出来的序列可以导出成json，保存下来或者手动修改之后再导入，用来生成测试文件
+//! 序列里的函数用api_functions的下标表示，导出时每个调用旁边会附上函数的全路径：
+//! 导入时下标和全路径对不上的话，以全路径为准重新查找下标
+//! 裸指针指向的类型只保留基本类型，其他类型导入后变成`_`，交给编译器推断
+
+use crate::clean::{self, PrimitiveType};
+use crate::fuzz_targets_gen::api_graph::ApiGraph;
+use crate::fuzz_targets_gen::api_sequence::{ApiSequence, ParamType};
+use rustc_span::Symbol;
+use serde::{Deserialize, Serialize};
+
+/// PrimitiveType按名字序列化，比如"u8"、"str"
+pub(crate) mod primitive_type {
+    use super::*;
+    use serde::{Deserializer, Serializer};
+
+    pub(crate) fn serialize<S: Serializer>(
+        primitive_type: &PrimitiveType,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(primitive_type.as_sym().as_str())
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<PrimitiveType, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        PrimitiveType::from_symbol(Symbol::intern(&name))
+            .ok_or_else(|| serde::de::Error::custom(format!("unknown primitive type {}", name)))
+    }
+}
+
+/// 裸指针指向的类型：基本类型按名字序列化，其他类型序列化成null
+pub(crate) mod pointee_type {
+    use super::*;
+    use serde::{Deserializer, Serializer};
+
+    pub(crate) fn serialize<S: Serializer>(
+        type_: &clean::Type,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        match type_ {
+            clean::Type::Primitive(primitive_type) => {
+                serializer.serialize_some(primitive_type.as_sym().as_str())
+            }
+            _ => serializer.serialize_none(),
+        }
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<clean::Type, D::Error> {
+        match Option::<String>::deserialize(deserializer)? {
+            Some(name) => match PrimitiveType::from_symbol(Symbol::intern(&name)) {
+                Some(primitive_type) => Ok(clean::Type::Primitive(primitive_type)),
+                None => Err(serde::de::Error::custom(format!("unknown primitive type {}", name))),
+            },
+            None => Ok(clean::Type::Infer),
+        }
+    }
+}
+
+/// 导出的一个序列，functions是每个调用对应函数的全路径
+#[derive(Serialize, Deserialize)]
+pub(crate) struct ExportedSequence {
+    functions: Vec<String>,
+    sequence: ApiSequence,
+}
+
+/// 附上函数全路径的序列，graph_export.rs导出整个图的时候也用这个格式
+pub(crate) fn exported_sequences(
+    graph: &ApiGraph<'_>,
+    sequences: &[ApiSequence],
+) -> Vec<ExportedSequence> {
+    sequences
+        .iter()
+        .map(|sequence| ExportedSequence {
+            functions: sequence
+                .functions
+                .iter()
+                .map(|api_call| graph.api_functions[api_call.func.1].full_name.clone())
+                .collect(),
+            sequence: sequence.clone(),
+        })
+        .collect()
+}
+
+/// 把序列导出成json
+pub(crate) fn sequences_to_json(graph: &ApiGraph<'_>, sequences: &[ApiSequence]) -> String {
+    serde_json::to_string_pretty(&exported_sequences(graph, sequences)).unwrap()
+}
+
+/// 从json导入序列，并检查序列和graph是否一致
+pub(crate) fn sequences_from_json(
+    graph: &ApiGraph<'_>,
+    json: &str,
+) -> Result<Vec<ApiSequence>, String> {
+    let exported: Vec<ExportedSequence> =
+        serde_json::from_str(json).map_err(|e| format!("invalid sequence file: {}", e))?;
+    let mut res = Vec::new();
+    for (sequence_index, ExportedSequence { functions, mut sequence }) in
+        exported.into_iter().enumerate()
+    {
+        for (call_index, api_call) in sequence.functions.iter_mut().enumerate() {
+            let name = match functions.get(call_index) {
+                Some(name) => name,
+                None => continue,
+            };
+            let index_matches = graph
+                .api_functions
+                .get(api_call.func.1)
+                .map_or(false, |api_function| &api_function.full_name == name);
+            if index_matches {
+                continue;
+            }
+            api_call.func.1 = graph
+                .api_functions
+                .iter()
+                .position(|api_function| &api_function.full_name == name)
+                .ok_or_else(|| format!("sequence {}: unknown function {}", sequence_index, name))?;
+        }
+        check_sequence(graph, &sequence)
+            .map_err(|e| format!("sequence {}: {}", sequence_index, e))?;
+        res.push(sequence);
+    }
+    Ok(res)
+}
+
+/// 检查函数下标、参数个数以及参数引用的下标是否合法
+fn check_sequence(graph: &ApiGraph<'_>, sequence: &ApiSequence) -> Result<(), String> {
+    for (call_index, api_call) in sequence.functions.iter().enumerate() {
+        let api_function = graph.api_functions.get(api_call.func.1).ok_or_else(|| {
+            format!("call {}: function index {} out of range", call_index, api_call.func.1)
+        })?;
+        if api_function.inputs.len() != api_call.params.len() {
+            return Err(format!(
+                "call {}: {} takes {} params, got {}",
+                call_index,
+                api_function.full_name,
+                api_function.inputs.len(),
+                api_call.params.len()
+            ));
+        }
+        for (param_type, param_index, _) in &api_call.params {
+            let valid = match param_type {
+                ParamType::_FunctionReturn => *param_index < call_index,
+                ParamType::_FuzzableType => *param_index < sequence.fuzzable_params.len(),
+            };
+            if !valid {
+                return Err(format!(
+                    "call {}: param index {} of {:?} out of range",
+                    call_index, param_index, param_type
+                ));
+            }
+        }
+    }
+    Ok(())
+}
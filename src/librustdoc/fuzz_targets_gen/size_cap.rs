@@ -0,0 +1,149 @@
+//! 限制fuzz输入解码出来的长度和数值，避免fuzzer把时间都花在OOM/超时这种"crash"上
+//! 1. 动态长度的参数（&[T], &str）：长度不超过max_decoded_len
+//! 2. 传给容量、长度、深度之类API的整数参数：取值不超过max_numeric_value
+//! 被限制的参数会以注释的形式记录在生成的测试文件里
+//! 配置文件每行的格式是 `选项名  |  值`，比如
+//!     max_decoded_len  |  4096
+
+use crate::clean::PrimitiveType;
+use crate::fuzz_targets_gen::diagnostics::{self, WarningKind};
+use crate::fuzz_targets_gen::file_util;
+use crate::fuzz_targets_gen::fuzz_type::FuzzableType;
+use rustc_data_structures::fx::FxHashSet;
+
+// 启发式规则：函数名的最后一段包含这些字符串时，整数参数往往是长度、容量或者递归深度
+lazy_static! {
+    static ref SIZE_LIKE_NAME_PATTERNS: FxHashSet<&'static str> = {
+        let mut m = FxHashSet::default();
+        m.insert("capacity");
+        m.insert("reserve");
+        m.insert("repeat");
+        m.insert("resize");
+        m.insert("with_len");
+        m.insert("depth");
+        m.insert("recursion");
+        m.insert("limit");
+        m.insert("alloc");
+        m.insert("grow");
+        m.insert("pad");
+        m.insert("width");
+        m.insert("fill");
+        m
+    };
+}
+
+/// 解码出来的参数的上限
+#[derive(Debug, Clone)]
+pub(crate) struct SizeCaps {
+    /// 是否开启限制
+    pub(crate) enabled: bool,
+    /// &[T]和&str的最大长度
+    pub(crate) max_decoded_len: usize,
+    /// 长度、容量之类的整数参数的最大值
+    pub(crate) max_numeric_value: u64,
+}
+
+impl SizeCaps {
+    pub(crate) fn new() -> Self {
+        SizeCaps { enabled: true, max_decoded_len: 4096, max_numeric_value: 65536 }
+    }
+}
+
+/// 从文件读取限制，文件不存在的话使用默认值
+pub(crate) fn load_size_caps_from_file(file_path: &str, size_caps: &mut SizeCaps) {
+    for (key, value) in file_util::read_config_pairs(file_path) {
+        let parsed = match key.as_str() {
+            "enabled" => value.parse().map(|x| size_caps.enabled = x).is_ok(),
+            "max_decoded_len" => value.parse().map(|x| size_caps.max_decoded_len = x).is_ok(),
+            "max_numeric_value" => value.parse().map(|x| size_caps.max_numeric_value = x).is_ok(),
+            _ => false,
+        };
+        if !parsed {
+            diagnostics::warn(
+                WarningKind::Degraded,
+                None,
+                format!("invalid size cap in {}: {}  |  {}, ignored", file_path, key, value),
+            );
+        }
+    }
+}
+
+/// 函数名看起来像是接收长度、容量、深度参数的函数
+pub(crate) fn is_size_like_function(full_name: &String) -> bool {
+    let last_segment = match full_name.rsplit("::").next() {
+        Some(segment) => segment,
+        None => return false,
+    };
+    SIZE_LIKE_NAME_PATTERNS.iter().any(|pattern| last_segment.contains(pattern))
+}
+
+/// 整数类型能表示的最大值，不是整数的话返回None
+fn integer_max_value(primitive_type: &PrimitiveType) -> Option<u64> {
+    match primitive_type {
+        PrimitiveType::U8 => Some(u8::MAX as u64),
+        PrimitiveType::I8 => Some(i8::MAX as u64),
+        PrimitiveType::U16 => Some(u16::MAX as u64),
+        PrimitiveType::I16 => Some(i16::MAX as u64),
+        PrimitiveType::U32 => Some(u32::MAX as u64),
+        PrimitiveType::I32 => Some(i32::MAX as u64),
+        PrimitiveType::U64 | PrimitiveType::Usize | PrimitiveType::U128 => Some(u64::MAX),
+        PrimitiveType::I64 | PrimitiveType::Isize | PrimitiveType::I128 => Some(i64::MAX as u64),
+        _ => None,
+    }
+}
+
+/// 生成限制一个参数的语句，用同名变量覆盖原来的参数
+/// 返回(语句, 记录在测试文件里的说明)，不需要限制的话返回None
+pub(crate) fn cap_param_statement(
+    size_caps: &SizeCaps,
+    fuzzable_type: &FuzzableType,
+    param_name: &String,
+    need_mut: bool,
+    size_like: bool,
+) -> Option<(String, String)> {
+    if !size_caps.enabled {
+        return None;
+    }
+    let mut_tag = if need_mut { "mut " } else { "" };
+    let max_len = size_caps.max_decoded_len;
+    match fuzzable_type {
+        FuzzableType::RefSlice(_) => Some((
+            format!(
+                "let {mut_tag}{name} = &{name}[..{name}.len().min({max_len})];",
+                mut_tag = mut_tag,
+                name = param_name,
+                max_len = max_len
+            ),
+            format!("{}.len() <= {}", param_name, max_len),
+        )),
+        FuzzableType::RefStr => Some((
+            //按字节截断可能落在字符中间，需要往前找到字符边界
+            format!(
+                "let {mut_tag}{name} = {{ let mut end = {name}.len().min({max_len}); \
+                while !{name}.is_char_boundary(end) {{ end -= 1; }} &{name}[..end] }};",
+                mut_tag = mut_tag,
+                name = param_name,
+                max_len = max_len
+            ),
+            format!("{}.len() <= {}", param_name, max_len),
+        )),
+        FuzzableType::Primitive(primitive_type) if size_like => {
+            let type_max = integer_max_value(primitive_type)?;
+            let max_value = size_caps.max_numeric_value;
+            //类型本身就放不下上限，没有必要限制
+            if max_value >= type_max {
+                return None;
+            }
+            Some((
+                format!(
+                    "let {mut_tag}{name} = {name} % {modulus};",
+                    mut_tag = mut_tag,
+                    name = param_name,
+                    modulus = max_value + 1
+                ),
+                format!("|{}| <= {}", param_name, max_value),
+            ))
+        }
+        _ => None,
+    }
+}
@@ -0,0 +1,124 @@
+//! 生成的测试文件的快照测试
+//! fixtures/fixture.json是一个很小的crate（源码见fixtures/fixture.rs）的rustdoc json文档，
+//! 对每个后端（afl、replay、libfuzzer）和每种特性（mut标记、unsafe、动态长度参数、泛型实例化）手写一个序列，
+//! 生成的代码和fixtures下的文件比较。修改emitter之后用`UPDATE_EXPECT=1`重新生成快照，在review时看diff
+
+use crate::fuzz_targets_gen::analyzer::{Analyzer, GenerationConfig};
+use crate::fuzz_targets_gen::api_graph::ApiGraph;
+use crate::fuzz_targets_gen::api_sequence::ApiSequence;
+use crate::fuzz_targets_gen::json_frontend::JsonCrate;
+use crate::fuzz_targets_gen::sequence_builder::SequenceBuilder;
+use expect_test::expect_file;
+use rustc_span::create_default_session_globals_then;
+
+/// 解析fixture，构建API图之后交给f，traversal/tests.rs也用它
+pub(super) fn with_fixture_graph(f: impl FnOnce(&mut ApiGraph<'_>)) {
+    create_default_session_globals_then(|| {
+        let json_crate = JsonCrate::from_json(include_str!("fixtures/fixture.json")).unwrap();
+        let mut analyzer = Analyzer::from_rustdoc_json(&json_crate, GenerationConfig::default());
+        f(analyzer.api_graph_mut());
+    });
+}
+
+fn build_sequence<'g, 'a>(
+    graph: &'g ApiGraph<'a>,
+    f: impl FnOnce(SequenceBuilder<'g, 'a>) -> SequenceBuilder<'g, 'a>,
+) -> ApiSequence {
+    f(SequenceBuilder::new(graph)).build().unwrap()
+}
+
+/// 新建Buffer，用&mut self调用push，再调用len，Buffer需要mut标记
+#[test]
+fn test_mut_tag_sequence() {
+    with_fixture_graph(|graph| {
+        let sequence = build_sequence(graph, |builder| {
+            builder
+                .call("fixture::Buffer::new")
+                .call("fixture::Buffer::push")
+                .bind(0, 0)
+                .call("fixture::Buffer::len")
+                .bind(0, 0)
+        });
+        expect_file!["fixtures/mut_tag_afl.rs"].assert_eq(&sequence._to_afl_test_file(graph, 0));
+        expect_file!["fixtures/mut_tag_replay.rs"]
+            .assert_eq(&sequence._to_replay_crash_file(graph, 0));
+        expect_file!["fixtures/mut_tag_libfuzzer.rs"]
+            .assert_eq(&sequence._to_libfuzzer_test_file(graph, 0));
+    });
+}
+
+/// 调用unsafe方法，只有这个调用包在unsafe块里
+#[test]
+fn test_unsafe_sequence() {
+    with_fixture_graph(|graph| {
+        let sequence = build_sequence(graph, |builder| {
+            builder.call("fixture::Buffer::new").call("fixture::Buffer::get_unchecked").bind(0, 0)
+        });
+        expect_file!["fixtures/unsafe_afl.rs"].assert_eq(&sequence._to_afl_test_file(graph, 1));
+    });
+}
+
+/// 两个动态长度的参数（&[u8]和&str），按长度前缀依次读取剩下的输入
+#[test]
+fn test_dynamic_param_sequence() {
+    with_fixture_graph(|graph| {
+        let sequence = build_sequence(graph, |builder| builder.call("fixture::checksum"));
+        expect_file!["fixtures/dynamic_param_afl.rs"]
+            .assert_eq(&sequence._to_afl_test_file(graph, 2));
+    });
+}
+
+/// 配置了参考实现的API只调用一次，参考实现用复制出来的参数，返回值交给原来的语句
+#[test]
+fn test_oracle_sequence() {
+    with_fixture_graph(|graph| {
+        graph
+            .emit_options
+            .oracles
+            .insert("fixture::checksum".to_string(), "naive_checksum".to_string());
+        let sequence = build_sequence(graph, |builder| builder.call("fixture::checksum"));
+        expect_file!["fixtures/oracle_afl.rs"].assert_eq(&sequence._to_afl_test_file(graph, 3));
+    });
+}
+
+/// json前端和in-process一样默认跳过弃用的和`#[doc(hidden)]`的函数，打开选项之后保留
+#[test]
+fn test_json_attribute_filters() {
+    let mut json: serde_json::Value =
+        serde_json::from_str(include_str!("fixtures/fixture.json")).unwrap();
+    json["index"]["0:2"]["deprecation"] = serde_json::json!({ "since": null, "note": null });
+    json["index"]["0:7"]["attrs"] = serde_json::json!(["#[doc(hidden)]"]);
+    create_default_session_globals_then(|| {
+        let json_crate = JsonCrate::from_json(&json.to_string()).unwrap();
+        let filtered = ["fixture::checksum", "fixture::Buffer::get_unchecked"];
+
+        let names =
+            Analyzer::from_rustdoc_json(&json_crate, GenerationConfig::default()).function_names();
+        assert!(names.iter().any(|name| name == "fixture::Buffer::new"));
+        assert!(!names.iter().any(|name| filtered.contains(&name.as_str())));
+
+        let config =
+            GenerationConfig::default().with_include_deprecated(true).with_include_doc_hidden(true);
+        let names = Analyzer::from_rustdoc_json(&json_crate, config).function_names();
+        assert!(filtered.iter().all(|filtered| names.iter().any(|name| name == filtered)));
+    });
+}
+
+/// 打开support_generic之后，泛型函数用候选类型实例化，调用和参数都换成候选类型
+#[test]
+fn test_generic_sequence() {
+    create_default_session_globals_then(|| {
+        let json_crate = JsonCrate::from_json(include_str!("fixtures/fixture.json")).unwrap();
+        let names =
+            Analyzer::from_rustdoc_json(&json_crate, GenerationConfig::default()).function_names();
+        assert!(!names.iter().any(|name| name == "fixture::pick"));
+
+        let config = GenerationConfig::default()
+            .with_support_generic(true)
+            .with_generic_candidates(Some(vec!["u8".to_string()]));
+        let mut analyzer = Analyzer::from_rustdoc_json(&json_crate, config);
+        let graph = analyzer.api_graph_mut();
+        let sequence = build_sequence(graph, |builder| builder.call("fixture::pick"));
+        expect_file!["fixtures/generic_afl.rs"].assert_eq(&sequence._to_afl_test_file(graph, 4));
+    });
+}
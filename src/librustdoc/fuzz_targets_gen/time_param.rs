@@ -0,0 +1,38 @@
+//! Duration、SystemTime、Instant参数
+//! async、网络相关的crate很多API的参数是超时时间或者截止时间，以前这些参数没法从fuzz输入构造，
+//! 序列里又很少有函数返回它们，整个API就用不上。这里：
+//! 1. Duration用`Duration::from_millis(u64)`
+//! 2. SystemTime用`SystemTime::UNIX_EPOCH + Duration::from_millis(u64)`，u64毫秒不会加溢出
+//! 3. Instant没法从数值构造，用`Instant::now()`，不消耗输入
+//! 按定义的全路径判断，chrono::Duration这样同名的类型不算
+
+use crate::clean;
+use crate::formats::cache::Cache;
+use crate::fuzz_targets_gen::api_util;
+use crate::fuzz_targets_gen::fuzz_type::FuzzableCallType;
+use crate::fuzz_targets_gen::impl_util::FullNameMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TimeKind {
+    Duration,
+    SystemTime,
+    Instant,
+}
+
+/// std::time下的Duration、SystemTime、Instant，其他类型返回None
+pub(crate) fn time_call_type(
+    type_: &clean::Type,
+    cache: &Cache,
+    full_name_map: &FullNameMap,
+) -> Option<FuzzableCallType> {
+    let clean::Type::Path { path } = type_ else {
+        return None;
+    };
+    let kind = match api_util::_def_path_name(path.def_id(), cache, full_name_map)?.as_str() {
+        "std::time::Duration" => TimeKind::Duration,
+        "std::time::SystemTime" => TimeKind::SystemTime,
+        "std::time::Instant" => TimeKind::Instant,
+        _ => return None,
+    };
+    Some(FuzzableCallType::Time(kind))
+}
@@ -0,0 +1,81 @@
+//! 每个阶段花费的时间
+//! 记录构建API图、依赖分析、每一次遍历、序列选择、生成测试文件的墙上时间和CPU时间，
+//! 生成结束之后和其他统计信息一起输出，并写到测试目录下的phase_timing.txt，用来对比不同版本的性能
+//! CPU时间从/proc/self/stat读取，其他平台上没有CPU时间
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// /proc/self/stat里utime和stime的单位，Linux上固定是1/100秒
+static CLOCK_TICKS_PER_SECOND: u64 = 100;
+
+/// 一个阶段的耗时
+#[derive(Debug, Clone)]
+pub(crate) struct PhaseTime {
+    pub(crate) phase: String,
+    pub(crate) wall: Duration,
+    pub(crate) cpu: Option<Duration>,
+}
+
+lazy_static! {
+    static ref PHASE_TIMES: Mutex<Vec<PhaseTime>> = Mutex::new(Vec::new());
+}
+
+/// 运行f并记录这个阶段的耗时，同名的阶段会分别记录
+pub(crate) fn time_phase<R>(phase: &str, f: impl FnOnce() -> R) -> R {
+    let wall_start = Instant::now();
+    let cpu_start = process_cpu_time();
+    let result = f();
+    let cpu = match (cpu_start, process_cpu_time()) {
+        (Some(start), Some(end)) => Some(end.saturating_sub(start)),
+        _ => None,
+    };
+    let phase_time = PhaseTime { phase: phase.to_string(), wall: wall_start.elapsed(), cpu };
+    if let Ok(mut phase_times) = PHASE_TIMES.lock() {
+        phase_times.push(phase_time);
+    }
+    result
+}
+
+/// 清空之前的记录，开始新的一次生成
+pub(crate) fn reset() {
+    if let Ok(mut phase_times) = PHASE_TIMES.lock() {
+        phase_times.clear();
+    }
+}
+
+pub(crate) fn phase_times() -> Vec<PhaseTime> {
+    match PHASE_TIMES.lock() {
+        Ok(phase_times) => phase_times.clone(),
+        Err(_) => Vec::new(),
+    }
+}
+
+/// 格式和其他配置文件一致：`阶段 | 墙上时间(ms) | CPU时间(ms)`，没有CPU时间的写成-
+pub(crate) fn timing_report() -> String {
+    let mut content = String::from("# phase | wall ms | cpu ms\n");
+    let mut total_wall = Duration::from_secs(0);
+    for phase_time in phase_times() {
+        total_wall += phase_time.wall;
+        let cpu = match phase_time.cpu {
+            Some(cpu) => cpu.as_millis().to_string(),
+            None => "-".to_string(),
+        };
+        content.push_str(
+            format!("{} | {} | {}\n", phase_time.phase, phase_time.wall.as_millis(), cpu).as_str(),
+        );
+    }
+    content.push_str(format!("# total | {}\n", total_wall.as_millis()).as_str());
+    content
+}
+
+/// 当前进程用掉的CPU时间（用户态+内核态）
+fn process_cpu_time() -> Option<Duration> {
+    let stat = std::fs::read_to_string("/proc/self/stat").ok()?;
+    // 第二个字段是括号里的进程名，可能包含空格，从最后一个')'之后开始数
+    let fields = stat.get(stat.rfind(')')? + 1..)?.split_whitespace().collect::<Vec<_>>();
+    // ')'之后第一个字段是state（总的第3个），utime和stime是总的第14、15个
+    let utime = fields.get(11)?.parse::<u64>().ok()?;
+    let stime = fields.get(12)?.parse::<u64>().ok()?;
+    Some(Duration::from_millis((utime + stime) * 1000 / CLOCK_TICKS_PER_SECOND))
+}
@@ -0,0 +1,285 @@
+//! 生成调用序列的算法
+//! 每种算法实现TraversalStrategy，新的算法只需要加一个实现，不用修改ApiGraph
+//! GraphTraverseAlgorithm到具体实现的对应关系在strategy_for里
+
+use crate::fuzz_targets_gen::analyzer::GenerationConfig;
+use crate::fuzz_targets_gen::api_graph::{ApiGraph, GraphTraverseAlgorithm};
+use crate::fuzz_targets_gen::beam_search;
+use crate::fuzz_targets_gen::error::FuzzGenError;
+use crate::fuzz_targets_gen::file_util;
+use crate::fuzz_targets_gen::genetic::{self, GeneticConfig};
+use crate::fuzz_targets_gen::markov_walk::{self, TransitionModel};
+use crate::fuzz_targets_gen::mcts;
+use std::fmt;
+
+//BFS序列的最大长度：即为函数的数量,或者自定义
+static BFS_MAX_LEN: usize = 5;
+//random walk end point的最大步数
+static RANDOM_WALK_MAX_SIZE: usize = 100000;
+//try deep sequence number
+static MAX_SEQUENCE_NUMBER: usize = 100000;
+//markov walk的游走次数
+static MARKOV_WALK_STEPS: usize = 10000;
+//遗传算法的种群大小和代数
+static GENETIC_POPULATION_SIZE: usize = 100;
+static GENETIC_GENERATIONS: usize = 50;
+//蒙特卡洛树搜索的迭代次数
+static MCTS_ITERATIONS: usize = 5000;
+//束搜索每层保留的序列数
+static BEAM_WIDTH: usize = 50;
+
+/// 生成调用序列的算法，生成的序列放在graph.api_sequences里
+/// 缺少算法需要的输入（比如语料文件）时返回错误，由调用者决定跳过这个阶段
+pub(crate) trait TraversalStrategy: fmt::Debug {
+    fn generate(
+        &mut self,
+        graph: &mut ApiGraph<'_>,
+        cfg: &GenerationConfig,
+    ) -> Result<(), FuzzGenError>;
+}
+
+/// BFS，可以选择在终结函数处停止，以及fast mode
+#[derive(Debug)]
+pub(crate) struct Bfs {
+    pub(crate) stop_at_end_function: bool,
+    pub(crate) fast_mode: bool,
+}
+
+impl TraversalStrategy for Bfs {
+    fn generate(
+        &mut self,
+        graph: &mut ApiGraph<'_>,
+        cfg: &GenerationConfig,
+    ) -> Result<(), FuzzGenError> {
+        match (self.stop_at_end_function, self.fast_mode) {
+            (false, false) => println!("using bfs"),
+            (false, true) => println!("using fastbfs"),
+            (true, false) => println!("using bfs end point"),
+            (true, true) => println!("using fast bfs end point"),
+        }
+        let max_len = cfg.bfs_max_len.unwrap_or(BFS_MAX_LEN);
+        graph.bfs(max_len, self.stop_at_end_function, self.fast_mode);
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct TryDeepBfs;
+
+impl TraversalStrategy for TryDeepBfs {
+    fn generate(
+        &mut self,
+        graph: &mut ApiGraph<'_>,
+        _cfg: &GenerationConfig,
+    ) -> Result<(), FuzzGenError> {
+        println!("using try deep bfs");
+        graph._try_deep_bfs(MAX_SEQUENCE_NUMBER);
+        Ok(())
+    }
+}
+
+/// 随机游走，end point模式下走到终结函数就停止
+#[derive(Debug)]
+pub(crate) struct RandomWalk {
+    pub(crate) stop_at_end_function: bool,
+}
+
+impl TraversalStrategy for RandomWalk {
+    fn generate(
+        &mut self,
+        graph: &mut ApiGraph<'_>,
+        cfg: &GenerationConfig,
+    ) -> Result<(), FuzzGenError> {
+        if self.stop_at_end_function {
+            println!("using random walk end point");
+            //no depth bound
+            graph.random_walk(cfg.walk_steps.unwrap_or(RANDOM_WALK_MAX_SIZE), true, 0);
+        } else {
+            println!("using random walk");
+            graph.random_walk(cfg.walk_steps.unwrap_or(cfg.max_num), false, cfg.max_len);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct DirectBackwardSearch;
+
+impl TraversalStrategy for DirectBackwardSearch {
+    fn generate(
+        &mut self,
+        graph: &mut ApiGraph<'_>,
+        _cfg: &GenerationConfig,
+    ) -> Result<(), FuzzGenError> {
+        println!("using backward search");
+        graph.api_sequences.clear();
+        graph.reset_visited();
+        graph._try_to_cover_unvisited_nodes();
+        Ok(())
+    }
+}
+
+/// 当前的方法，使用解析出来的sequence
+#[derive(Debug)]
+pub(crate) struct UseRealWorld {
+    pub(crate) lib_name: String,
+}
+
+impl TraversalStrategy for UseRealWorld {
+    fn generate(
+        &mut self,
+        graph: &mut ApiGraph<'_>,
+        cfg: &GenerationConfig,
+    ) -> Result<(), FuzzGenError> {
+        println!("using realworld to generate");
+        graph.my_method(
+            &self.lib_name,
+            cfg.max_num,
+            cfg.max_len,
+            cfg.support_generic,
+            cfg.corpus_path.as_deref(),
+        )
+    }
+}
+
+#[derive(Debug)]
+pub(crate) struct Fudge {
+    pub(crate) lib_name: String,
+}
+
+impl TraversalStrategy for Fudge {
+    fn generate(
+        &mut self,
+        graph: &mut ApiGraph<'_>,
+        cfg: &GenerationConfig,
+    ) -> Result<(), FuzzGenError> {
+        println!("using realworld to generate");
+        graph.fudge(&self.lib_name, cfg.corpus_path.as_deref())
+    }
+}
+
+/// 按语料里API的转移概率随机游走
+#[derive(Debug)]
+pub(crate) struct MarkovWalk {
+    pub(crate) lib_name: String,
+}
+
+impl TraversalStrategy for MarkovWalk {
+    fn generate(
+        &mut self,
+        graph: &mut ApiGraph<'_>,
+        cfg: &GenerationConfig,
+    ) -> Result<(), FuzzGenError> {
+        println!("using markov walk");
+        let default_corpus = format!(
+            "{}/{}/seq-dedup.ans",
+            file_util::experiment_root(),
+            self.lib_name.replace("-", "_")
+        );
+        let corpus_path = file_util::corpus_path(cfg.corpus_path.as_deref(), default_corpus);
+        let model = TransitionModel::from_corpus(graph, &corpus_path)?;
+        markov_walk::markov_walk(
+            graph,
+            &model,
+            cfg.walk_steps.unwrap_or(MARKOV_WALK_STEPS),
+            cfg.max_len,
+        );
+        Ok(())
+    }
+}
+
+/// 遗传算法，适应度里用到语料里API的频率
+#[derive(Debug)]
+pub(crate) struct Genetic {
+    pub(crate) lib_name: String,
+}
+
+impl TraversalStrategy for Genetic {
+    fn generate(
+        &mut self,
+        graph: &mut ApiGraph<'_>,
+        cfg: &GenerationConfig,
+    ) -> Result<(), FuzzGenError> {
+        println!("using genetic algorithm");
+        graph.load_function_frequencies(&self.lib_name, cfg.corpus_path.as_deref());
+        let config = GeneticConfig {
+            population_size: cfg.population_size.unwrap_or(GENETIC_POPULATION_SIZE),
+            generations: cfg.generations.unwrap_or(GENETIC_GENERATIONS),
+            max_len: cfg.max_len,
+        };
+        genetic::evolve(graph, &config);
+        Ok(())
+    }
+}
+
+/// 蒙特卡洛树搜索，迭代次数用walk_steps设置
+#[derive(Debug)]
+pub(crate) struct Mcts;
+
+impl TraversalStrategy for Mcts {
+    fn generate(
+        &mut self,
+        graph: &mut ApiGraph<'_>,
+        cfg: &GenerationConfig,
+    ) -> Result<(), FuzzGenError> {
+        println!("using monte carlo tree search");
+        mcts::mcts(graph, cfg.walk_steps.unwrap_or(MCTS_ITERATIONS), cfg.max_len);
+        Ok(())
+    }
+}
+
+/// 束搜索，深度是max_len
+#[derive(Debug)]
+pub(crate) struct BeamSearch;
+
+impl TraversalStrategy for BeamSearch {
+    fn generate(
+        &mut self,
+        graph: &mut ApiGraph<'_>,
+        cfg: &GenerationConfig,
+    ) -> Result<(), FuzzGenError> {
+        println!("using beam search");
+        beam_search::beam_search(graph, cfg.beam_width.unwrap_or(BEAM_WIDTH), cfg.max_len);
+        Ok(())
+    }
+}
+
+/// GraphTraverseAlgorithm对应的算法
+pub(crate) fn strategy_for(
+    algorithm: GraphTraverseAlgorithm,
+    lib_name: &str,
+) -> Box<dyn TraversalStrategy> {
+    match algorithm {
+        GraphTraverseAlgorithm::_Bfs => {
+            Box::new(Bfs { stop_at_end_function: false, fast_mode: false })
+        }
+        GraphTraverseAlgorithm::_FastBfs => {
+            Box::new(Bfs { stop_at_end_function: false, fast_mode: true })
+        }
+        GraphTraverseAlgorithm::_BfsEndPoint | GraphTraverseAlgorithm::_Default => {
+            Box::new(Bfs { stop_at_end_function: true, fast_mode: false })
+        }
+        GraphTraverseAlgorithm::_FastBfsEndPoint => {
+            Box::new(Bfs { stop_at_end_function: true, fast_mode: true })
+        }
+        GraphTraverseAlgorithm::_TryDeepBfs => Box::new(TryDeepBfs),
+        GraphTraverseAlgorithm::_RandomWalkEndPoint => {
+            Box::new(RandomWalk { stop_at_end_function: true })
+        }
+        GraphTraverseAlgorithm::_DirectBackwardSearch => Box::new(DirectBackwardSearch),
+        GraphTraverseAlgorithm::_RandomWalk => Box::new(RandomWalk { stop_at_end_function: false }),
+        GraphTraverseAlgorithm::_UseRealWorld => {
+            Box::new(UseRealWorld { lib_name: lib_name.to_string() })
+        }
+        GraphTraverseAlgorithm::_Fudge => Box::new(Fudge { lib_name: lib_name.to_string() }),
+        GraphTraverseAlgorithm::_MarkovWalk => {
+            Box::new(MarkovWalk { lib_name: lib_name.to_string() })
+        }
+        GraphTraverseAlgorithm::_Genetic => Box::new(Genetic { lib_name: lib_name.to_string() }),
+        GraphTraverseAlgorithm::_Mcts => Box::new(Mcts),
+        GraphTraverseAlgorithm::_BeamSearch => Box::new(BeamSearch),
+    }
+}
+
+#[cfg(test)]
+mod tests;
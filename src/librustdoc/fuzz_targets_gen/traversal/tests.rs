@@ -0,0 +1,74 @@
+//! 在fixture的API图上检查strategy_for的对应关系，以及bfs和random walk确实能生成序列
+
+use super::{strategy_for, Bfs, RandomWalk, TraversalStrategy};
+use crate::fuzz_targets_gen::analyzer::GenerationConfig;
+use crate::fuzz_targets_gen::api_graph::{ApiGraph, GraphTraverseAlgorithm};
+use crate::fuzz_targets_gen::tests::with_fixture_graph;
+
+/// 序列里调用的函数名
+fn sequence_names(graph: &ApiGraph<'_>) -> Vec<Vec<String>> {
+    graph
+        .api_sequences
+        .iter()
+        .map(|sequence| {
+            sequence
+                .functions
+                .iter()
+                .map(|call| graph.api_functions[call.func.1].full_name.clone())
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn test_strategy_for_dispatch() {
+    let cases = [
+        (GraphTraverseAlgorithm::_Bfs, "Bfs { stop_at_end_function: false, fast_mode: false }"),
+        (GraphTraverseAlgorithm::_FastBfs, "Bfs { stop_at_end_function: false, fast_mode: true }"),
+        (
+            GraphTraverseAlgorithm::_BfsEndPoint,
+            "Bfs { stop_at_end_function: true, fast_mode: false }",
+        ),
+        (GraphTraverseAlgorithm::_Default, "Bfs { stop_at_end_function: true, fast_mode: false }"),
+        (
+            GraphTraverseAlgorithm::_FastBfsEndPoint,
+            "Bfs { stop_at_end_function: true, fast_mode: true }",
+        ),
+        (GraphTraverseAlgorithm::_RandomWalk, "RandomWalk { stop_at_end_function: false }"),
+        (GraphTraverseAlgorithm::_RandomWalkEndPoint, "RandomWalk { stop_at_end_function: true }"),
+        (GraphTraverseAlgorithm::_TryDeepBfs, "TryDeepBfs"),
+        (GraphTraverseAlgorithm::_DirectBackwardSearch, "DirectBackwardSearch"),
+        (GraphTraverseAlgorithm::_MarkovWalk, "MarkovWalk { lib_name: \"fixture\" }"),
+        (GraphTraverseAlgorithm::_Mcts, "Mcts"),
+        (GraphTraverseAlgorithm::_BeamSearch, "BeamSearch"),
+    ];
+    for (algorithm, expected) in cases {
+        assert_eq!(format!("{:?}", strategy_for(algorithm, "fixture")), expected);
+    }
+}
+
+#[test]
+fn test_bfs_generates_sequences() {
+    with_fixture_graph(|graph| {
+        let cfg = GenerationConfig::default().with_bfs_max_len(Some(2));
+        let mut strategy = Bfs { stop_at_end_function: false, fast_mode: false };
+        strategy.generate(graph, &cfg).unwrap();
+
+        let names = sequence_names(graph);
+        assert!(!names.is_empty());
+        assert!(names.iter().all(|sequence| sequence.len() <= 2));
+        assert!(names.iter().any(|sequence| sequence[..]
+            == ["fixture::Buffer::new".to_string(), "fixture::Buffer::push".to_string()]));
+    });
+}
+
+#[test]
+fn test_random_walk_generates_sequences() {
+    with_fixture_graph(|graph| {
+        let cfg = GenerationConfig::default().with_max_len(2).with_walk_steps(Some(3));
+        let mut strategy = RandomWalk { stop_at_end_function: false };
+        strategy.generate(graph, &cfg).unwrap();
+
+        assert!(graph.api_sequences.iter().filter(|sequence| sequence.len() >= 2).count() > 3);
+    });
+}
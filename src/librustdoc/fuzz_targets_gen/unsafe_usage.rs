@@ -0,0 +1,168 @@
+//! 找出内部用到unsafe的API
+//! 内存安全的问题基本都出在unsafe代码里，开启--fuzz-prioritize-unsafe之后，选序列和遍历都会偏向这些API
+//! 一个函数用到unsafe是指：
+//!     1. 本身是unsafe fn，或者函数体（包括里面的闭包）里有用户写的unsafe块
+//!     2. 解引用裸指针、`&raw`取地址、转换成裸指针
+//!     3. 调用了满足1或2的本crate函数，可以间接调用多层（不分析trait对象和函数指针的调用）
+//! 用HIR和typeck的结果分析，不需要构建MIR
+
+use crate::fuzz_targets_gen::api_graph::ApiGraph;
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
+use rustc_hir::def::{DefKind, Res};
+use rustc_hir::def_id::{DefId, LocalDefId};
+use rustc_hir::{
+    self as hir,
+    intravisit::{self, Visitor},
+};
+use rustc_middle::hir::nested_filter;
+use rustc_middle::ty::{TyCtxt, TypeckResults};
+
+struct BodyVisitor<'tcx> {
+    tcx: TyCtxt<'tcx>,
+    typeck_results: &'tcx TypeckResults<'tcx>,
+    /// 函数体自己是否用到unsafe
+    uses_unsafe: bool,
+    /// 调用的本crate函数
+    callees: Vec<LocalDefId>,
+}
+
+impl<'tcx> BodyVisitor<'tcx> {
+    fn add_callee(&mut self, def_id: DefId) {
+        if let Some(local_def_id) = def_id.as_local() {
+            self.callees.push(local_def_id);
+        }
+    }
+}
+
+impl<'tcx> Visitor<'tcx> for BodyVisitor<'tcx> {
+    type NestedFilter = nested_filter::OnlyBodies;
+
+    fn nested_visit_map(&mut self) -> Self::Map {
+        self.tcx.hir()
+    }
+
+    fn visit_block(&mut self, b: &'tcx hir::Block<'tcx>) {
+        if b.rules == hir::BlockCheckMode::UnsafeBlock(hir::UnsafeSource::UserProvided) {
+            self.uses_unsafe = true;
+        }
+        intravisit::walk_block(self, b);
+    }
+
+    fn visit_expr(&mut self, ex: &'tcx hir::Expr<'tcx>) {
+        match ex.kind {
+            hir::ExprKind::Unary(hir::UnOp::Deref, inner)
+                if self.typeck_results.expr_ty_adjusted(inner).is_unsafe_ptr() =>
+            {
+                self.uses_unsafe = true;
+            }
+            hir::ExprKind::AddrOf(hir::BorrowKind::Raw, ..) => self.uses_unsafe = true,
+            hir::ExprKind::Cast(..) if self.typeck_results.expr_ty(ex).is_unsafe_ptr() => {
+                self.uses_unsafe = true;
+            }
+            hir::ExprKind::Call(callee, _) => {
+                if let hir::ExprKind::Path(ref qpath) = callee.kind {
+                    if let Res::Def(DefKind::Fn | DefKind::AssocFn, def_id) =
+                        self.typeck_results.qpath_res(qpath, callee.hir_id)
+                    {
+                        self.add_callee(def_id);
+                    }
+                }
+            }
+            hir::ExprKind::MethodCall(..) => {
+                if let Some(def_id) = self.typeck_results.type_dependent_def_id(ex.hir_id) {
+                    self.add_callee(def_id);
+                }
+            }
+            _ => {}
+        }
+        intravisit::walk_expr(self, ex);
+    }
+}
+
+struct UnsafeAnalysis<'tcx> {
+    tcx: TyCtxt<'tcx>,
+    /// 函数 -> (函数体自己是否用到unsafe, 调用的本crate函数)
+    bodies: FxHashMap<LocalDefId, (bool, Vec<LocalDefId>)>,
+    /// 函数 -> 是否直接或者间接用到unsafe
+    results: FxHashMap<LocalDefId, bool>,
+}
+
+impl<'tcx> UnsafeAnalysis<'tcx> {
+    /// 分析一个函数体，没有函数体的（比如trait里没有默认实现的方法）当成没有用到unsafe
+    fn body_usage(&mut self, def_id: LocalDefId) -> (bool, Vec<LocalDefId>) {
+        if let Some(usage) = self.bodies.get(&def_id) {
+            return usage.clone();
+        }
+        let tcx = self.tcx;
+        let usage = match tcx.hir().maybe_body_owned_by(def_id) {
+            Some(body_id) if matches!(tcx.def_kind(def_id), DefKind::Fn | DefKind::AssocFn) => {
+                let mut visitor = BodyVisitor {
+                    tcx,
+                    typeck_results: tcx.typeck(def_id),
+                    uses_unsafe: tcx.fn_sig(def_id).unsafety() == hir::Unsafety::Unsafe,
+                    callees: Vec::new(),
+                };
+                visitor.visit_body(tcx.hir().body(body_id));
+                (visitor.uses_unsafe, visitor.callees)
+            }
+            _ => (false, Vec::new()),
+        };
+        self.bodies.insert(def_id, usage.clone());
+        usage
+    }
+
+    /// 沿着本crate的调用图往下找，能走到函数体自己用到unsafe的函数就返回true
+    fn uses_unsafe(&mut self, def_id: LocalDefId) -> bool {
+        if let Some(result) = self.results.get(&def_id) {
+            return *result;
+        }
+        let mut visited = FxHashSet::default();
+        let mut worklist = vec![def_id];
+        let mut result = false;
+        while let Some(current) = worklist.pop() {
+            if !visited.insert(current) {
+                continue;
+            }
+            match self.results.get(&current) {
+                Some(true) => {
+                    result = true;
+                    break;
+                }
+                //已经知道从它出发走不到unsafe，不用再往下找
+                Some(false) => continue,
+                None => {}
+            }
+            let (direct, callees) = self.body_usage(current);
+            if direct {
+                result = true;
+                break;
+            }
+            worklist.extend(callees);
+        }
+        //没有找到的话，走过的函数都走不到unsafe
+        if !result {
+            for visited_def_id in visited {
+                self.results.insert(visited_def_id, false);
+            }
+        }
+        self.results.insert(def_id, result);
+        result
+    }
+}
+
+/// 设置api_functions里每个函数的_uses_unsafe，没有def_id或者不是本crate定义的函数都是false
+pub(crate) fn mark_unsafe_functions(tcx: TyCtxt<'_>, api_graph: &mut ApiGraph<'_>) {
+    let mut analysis =
+        UnsafeAnalysis { tcx, bodies: FxHashMap::default(), results: FxHashMap::default() };
+    let mut unsafe_function_number = 0;
+    for api_function in api_graph.api_functions.iter_mut() {
+        api_function._uses_unsafe = api_function
+            .def_id
+            .and_then(|def_id| def_id.as_local())
+            .map_or(false, |def_id| analysis.uses_unsafe(def_id));
+        if api_function._uses_unsafe {
+            unsafe_function_number = unsafe_function_number + 1;
+        }
+    }
+    println!("{} api functions use unsafe internally", unsafe_function_number);
+}
@@ -0,0 +1,126 @@
+//! 返回Option/Result的调用怎么取出里面的值
+//! 依赖边上已经去掉了Option/Result（见find_all_dependencies），所以生成代码时在产生它的语句上就要取出来。
+//! 以前是Err/None时直接process::exit(0)，libfuzzer和afl的persistent模式下会把整个进程退出，
+//! 换成unwrap又会把正常的错误返回当成crash报出来，所以默认提前结束这次执行：
+//! 1. Return：`let Ok(x) = ... else { return; };`，默认
+//! 2. Unwrap：`.unwrap()`，想把错误返回也当成bug的时候用
+//! 3. Try：测试函数返回`Option<()>`，用`?`提前返回
+//!
+//! 返回Option的调用另外有NonePolicy，None不一定是错误，也可以换成默认值继续执行
+
+/// 取出Option/Result里的值的方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum UnwrapStrategy {
+    #[default]
+    Return,
+    Unwrap,
+    Try,
+}
+
+impl UnwrapStrategy {
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "return" => Some(UnwrapStrategy::Return),
+            "unwrap" => Some(UnwrapStrategy::Unwrap),
+            "try" => Some(UnwrapStrategy::Try),
+            _ => None,
+        }
+    }
+
+    /// 测试函数的返回值类型，只有Try需要
+    pub(crate) fn return_type(&self) -> &'static str {
+        match self {
+            UnwrapStrategy::Try => "-> Option<()> ",
+            _ => "",
+        }
+    }
+
+    /// 测试函数的最后一个表达式，和return_type对应
+    pub(crate) fn tail_expression(&self) -> &'static str {
+        match self {
+            UnwrapStrategy::Try => "Some(())\n",
+            _ => "",
+        }
+    }
+
+    /// 产生Option/Result的语句，let_string是`let mut _local0 = `或者`let _ = `
+    pub(crate) fn let_statement(
+        &self,
+        let_string: &str,
+        call_string: &str,
+        is_result: bool,
+    ) -> String {
+        match self {
+            UnwrapStrategy::Return => {
+                let pattern =
+                    let_string.trim_start_matches("let ").trim_end_matches(" = ").to_string();
+                let variant = if is_result { "Ok" } else { "Some" };
+                format!("let {}({}) = {} else {{ return; }};\n", variant, pattern, call_string)
+            }
+            UnwrapStrategy::Unwrap => format!("{}{}.unwrap();\n", let_string, call_string),
+            UnwrapStrategy::Try => {
+                let to_option = if is_result { ".ok()" } else { "" };
+                format!("{}{}{}?;\n", let_string, call_string, to_option)
+            }
+        }
+    }
+
+    /// 解析失败时结束这次执行，用于FromStr的合成生产者
+    pub(crate) fn parse_expression(&self, param: &str, type_name: &str) -> String {
+        match self {
+            UnwrapStrategy::Return => {
+                format!(
+                    "match ({}).parse::<{}>() {{ Ok(v) => v, Err(_) => return }}",
+                    param, type_name
+                )
+            }
+            UnwrapStrategy::Unwrap => format!("({}).parse::<{}>().unwrap()", param, type_name),
+            UnwrapStrategy::Try => format!("({}).parse::<{}>().ok()?", param, type_name),
+        }
+    }
+}
+
+/// 返回Option的调用遇到None时的处理方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum NonePolicy {
+    /// 和Result一样提前返回，测试函数用Try的时候写成`?`
+    #[default]
+    Return,
+    Unwrap,
+    /// `.unwrap_or_default()`，里面的类型没有实现Default的时候退回到Return
+    Default,
+}
+
+impl NonePolicy {
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "return" => Some(NonePolicy::Return),
+            "unwrap" => Some(NonePolicy::Unwrap),
+            "default" => Some(NonePolicy::Default),
+            _ => None,
+        }
+    }
+
+    /// 产生Option的语句，implements_default表示Option里的类型实现了Default
+    pub(crate) fn let_statement(
+        &self,
+        let_string: &str,
+        call_string: &str,
+        unwrap_strategy: UnwrapStrategy,
+        implements_default: bool,
+    ) -> String {
+        match self {
+            NonePolicy::Unwrap => format!("{}{}.unwrap();\n", let_string, call_string),
+            NonePolicy::Default if implements_default => {
+                format!("{}{}.unwrap_or_default();\n", let_string, call_string)
+            }
+            _ => {
+                let early_return = match unwrap_strategy {
+                    UnwrapStrategy::Try => UnwrapStrategy::Try,
+                    _ => UnwrapStrategy::Return,
+                };
+                early_return.let_statement(let_string, call_string, false)
+            }
+        }
+    }
+}
@@ -0,0 +1,97 @@
+//! 生成的测试文件里每个API调用的watchdog（--call-timeout）
+//! 每个语句开始前调用`_watchdog_enter`记录当前语句，后台线程发现同一个语句
+//! 执行超过了超时时间，就打印出卡住的语句然后abort，避免一个卡住的API拖住整个fuzz过程。
+//! 另外还有整个序列的超时（--target-timeout）：测试函数的函数体放到scoped线程里执行，
+//! 调用线程等待结束信号，超时就abort，afl会把它当成hang
+
+/// watchdog的辅助函数，timeout_ms是每个调用的超时时间（毫秒）
+pub(crate) fn _watchdog_helper_functions(timeout_ms: u64) -> String {
+    format!("const _WATCHDOG_TIMEOUT_MS: u64 = {};\n{}", timeout_ms, _watchdog_functions())
+}
+
+fn _watchdog_functions() -> &'static str {
+    "static _WATCHDOG_START: std::sync::Once = std::sync::Once::new();
+static _WATCHDOG_EPOCH: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+static _WATCHDOG_STATEMENT: std::sync::atomic::AtomicUsize =
+    std::sync::atomic::AtomicUsize::new(usize::MAX);
+static _WATCHDOG_NAME: std::sync::Mutex<&'static str> = std::sync::Mutex::new(\"\");
+
+fn _watchdog_enter(statement: usize, name: &'static str) {
+    use std::sync::atomic::Ordering;
+    _WATCHDOG_START.call_once(|| {
+        std::thread::spawn(|| {
+            let mut last_epoch = usize::MAX;
+            loop {
+                std::thread::sleep(std::time::Duration::from_millis(_WATCHDOG_TIMEOUT_MS));
+                let epoch = _WATCHDOG_EPOCH.load(Ordering::SeqCst);
+                let statement = _WATCHDOG_STATEMENT.load(Ordering::SeqCst);
+                if statement != usize::MAX && epoch == last_epoch {
+                    let name = match _WATCHDOG_NAME.lock() {
+                        Ok(name) => *name,
+                        Err(_) => \"\",
+                    };
+                    eprintln!(\"watchdog: statement {} ({}) timed out after {} ms\",
+                        statement, name, _WATCHDOG_TIMEOUT_MS);
+                    std::process::abort();
+                }
+                last_epoch = epoch;
+            }
+        });
+    });
+    if let Ok(mut current_name) = _WATCHDOG_NAME.lock() {
+        *current_name = name;
+    }
+    _WATCHDOG_STATEMENT.store(statement, Ordering::SeqCst);
+    _WATCHDOG_EPOCH.fetch_add(1, Ordering::SeqCst);
+}
+
+fn _watchdog_exit() {
+    use std::sync::atomic::Ordering;
+    _WATCHDOG_STATEMENT.store(usize::MAX, Ordering::SeqCst);
+    _WATCHDOG_EPOCH.fetch_add(1, Ordering::SeqCst);
+}\n"
+}
+
+/// 第i个语句开始之前的watchdog语句
+pub(crate) fn _watchdog_enter_line(statement_index: usize, api_full_name: &String) -> String {
+    format!("_watchdog_enter({}, \"{}\");\n", statement_index, api_full_name)
+}
+
+/// 所有语句执行完之后的watchdog语句
+pub(crate) fn _watchdog_exit_line() -> &'static str {
+    "_watchdog_exit();\n"
+}
+
+/// 整个序列超时的辅助函数，timeout_ms是整个测试函数的超时时间（毫秒）
+/// 函数体panic的时候结束信号的发送端被drop，join之后把panic原样抛出
+pub(crate) fn _target_timeout_helper_functions(timeout_ms: u64) -> String {
+    format!("const _TARGET_TIMEOUT_MS: u64 = {};\n{}", timeout_ms, _target_timeout_functions())
+}
+
+fn _target_timeout_functions() -> &'static str {
+    "fn _run_with_timeout<T: Send, F: FnOnce() -> T + Send>(f: F) -> T {
+    let (done_sender, done_receiver) = std::sync::mpsc::channel::<()>();
+    std::thread::scope(|scope| {
+        let handle = scope.spawn(move || {
+            let res = f();
+            let _ = done_sender.send(());
+            res
+        });
+        let timeout = std::time::Duration::from_millis(_TARGET_TIMEOUT_MS);
+        if let Err(std::sync::mpsc::RecvTimeoutError::Timeout) = done_receiver.recv_timeout(timeout) {
+            eprintln!(\"target timed out after {} ms\", _TARGET_TIMEOUT_MS);
+            std::process::abort();
+        }
+        match handle.join() {
+            Ok(res) => res,
+            Err(payload) => std::panic::resume_unwind(payload),
+        }
+    })
+}\n"
+}
+
+/// 测试函数的函数体外面包上_run_with_timeout，return_type是闭包的返回值类型（可以为空），
+/// 结尾是`})`
+pub(crate) fn _run_with_timeout_prefix(indent: &str, return_type: &str) -> String {
+    format!("{}_run_with_timeout(|| {}{{\n", indent, return_type)
+}
@@ -28,6 +28,7 @@
 use crate::formats::cache::Cache;
 use crate::formats::item_type::ItemType;
 use crate::formats::FormatRenderer;
+use crate::fuzz_targets_gen;
 use crate::html::escape::Escape;
 use crate::html::format::{join_with_double_colon, Buffer};
 use crate::html::markdown::{self, plain_text_summary, ErrorCodes, IdMap};
@@ -127,6 +128,8 @@ pub(crate) struct SharedContext<'tcx> {
     pub(crate) cache: Cache,
 
     pub(crate) call_locations: AllCallLocations,
+    /// 每个函数被哪些FRIES生成的fuzz target覆盖，key是函数的全路径
+    pub(crate) fuzz_targets: FxHashMap<String, Vec<String>>,
 }
 
 impl SharedContext<'_> {
@@ -521,6 +524,7 @@ fn init(
             span_correspondance_map: matches,
             cache,
             call_locations,
+            fuzz_targets: fuzz_targets_gen::read_target_index(krate.name(tcx).as_str()),
         };
 
         let dst = output;
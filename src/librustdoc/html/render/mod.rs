@@ -1508,6 +1508,16 @@ fn doc_impl_item(
                 );
             }
         }
+        if render_method_item && item_type == ItemType::Method {
+            let parent_path = parent
+                .item_id
+                .as_def_id()
+                .and_then(|did| cx.cache().paths.get(&did))
+                .map(|(path, _)| join_with_double_colon(path));
+            if let Some(parent_path) = parent_path {
+                render_fuzz_targets(&mut doc_buffer, cx, &format!("{}::{}", parent_path, name));
+            }
+        }
         let w = if short_documented && trait_.is_some() { interesting } else { boring };
 
         let toggled = !doc_buffer.is_empty();
@@ -2811,6 +2821,29 @@ fn collect_paths_for_type(first_ty: clean::Type, cache: &Cache) -> Vec<String> {
     out
 }
 
+/// FRIES：列出覆盖这个函数的fuzz target，full_name和生成fuzz target时的函数全路径一致
+fn render_fuzz_targets(w: &mut Buffer, cx: &mut Context<'_>, full_name: &str) {
+    let shared = Rc::clone(&cx.shared);
+    let Some(fuzz_targets) = shared.fuzz_targets.get(full_name) else { return };
+
+    let id = cx.derive_id("fuzz-targets".to_string());
+    write!(
+        w,
+        "<div class=\"docblock fuzz-target-list\">\
+           <h5 id=\"{id}\"><a href=\"#{id}\">Fuzz targets</a></h5>\
+           <p>{count} fuzz target{s} cover{verb} this function</p>\
+           <ul>",
+        id = id,
+        count = fuzz_targets.len(),
+        s = if fuzz_targets.len() == 1 { "" } else { "s" },
+        verb = if fuzz_targets.len() == 1 { "s" } else { "" },
+    );
+    for fuzz_target in fuzz_targets {
+        write!(w, "<li><code>{}</code></li>", Escape(fuzz_target));
+    }
+    w.write_str("</ul></div>");
+}
+
 const MAX_FULL_EXAMPLES: usize = 5;
 const NUM_VISIBLE_LINES: usize = 10;
 
@@ -19,7 +19,7 @@
     collect_paths_for_type, document, ensure_trailing_slash, get_filtered_impls_for_reference,
     item_ty_to_section, notable_traits_button, notable_traits_json, render_all_impls,
     render_assoc_item, render_assoc_items, render_attributes_in_code, render_attributes_in_pre,
-    render_impl, render_rightside, render_stability_since_raw,
+    render_fuzz_targets, render_impl, render_rightside, render_stability_since_raw,
     render_stability_since_raw_with_extra, AssocItemLink, Context, ImplRenderingParameters,
 };
 use crate::clean;
@@ -552,6 +552,8 @@ fn item_function(w: &mut Buffer, cx: &mut Context<'_>, it: &clean::Item, f: &cle
         });
     });
     document(w, cx, it, None, HeadingOffset::H2);
+    let full_name = format!("{}::{}", join_with_double_colon(&cx.current), name);
+    render_fuzz_targets(w, cx, &full_name);
 }
 
 fn item_trait(w: &mut Buffer, cx: &mut Context<'_>, it: &clean::Item, t: &clean::Trait) {
@@ -118,7 +118,8 @@ macro_rules! map {
 pub mod html;
 mod json;
 
-mod fuzz_targets_gen;
+// 外部工具通过fuzz_targets_gen::Analyzer驱动fuzz target的生成
+pub mod fuzz_targets_gen;
 
 pub(crate) mod lint;
 mod markdown;
@@ -616,6 +617,255 @@ fn opts() -> Vec<RustcOptGroup> {
                 "path to function call information (for displaying examples in the documentation)",
             )
         }),
+        // FRIES生成fuzz target的选项，见fuzz_targets_gen/fuzz_options.rs
+        stable("fuzz-explain", |o| {
+            o.optopt(
+                "",
+                "fuzz-explain",
+                "explain why no generated sequence reaches the given API",
+                "PATH",
+            )
+        }),
+        stable("fuzz-algorithm", |o| {
+            o.optopt(
+                "",
+                "fuzz-algorithm",
+                "the algorithm used to generate API sequences",
+                "[real-world|bfs|fast-bfs|bfs-end-point|random-walk|random-walk-end-point|...]",
+            )
+        }),
+        stable("fuzz-selection", |o| {
+            o.optopt(
+                "",
+                "fuzz-selection",
+                "how to select fuzz targets from the generated sequences \
+                (defaults to the one of the generation algorithm)",
+                "[first|heuristic|random|pair-coverage|set-cover|complexity]",
+            )
+        }),
+        stable("fuzz-max-num", |o| {
+            o.optopt("", "fuzz-max-num", "the maximum number of selected sequences", "N")
+        }),
+        stable("fuzz-max-len", |o| {
+            o.optopt("", "fuzz-max-len", "the maximum length (and BFS depth) of sequences", "N")
+        }),
+        stable("fuzz-walk-steps", |o| {
+            o.optopt(
+                "",
+                "fuzz-walk-steps",
+                "the number of steps of random walk algorithms (and MCTS iterations)",
+                "N",
+            )
+        }),
+        stable("fuzz-population", |o| {
+            o.optopt("", "fuzz-population", "the population size of the genetic algorithm", "N")
+        }),
+        stable("fuzz-generations", |o| {
+            o.optopt(
+                "",
+                "fuzz-generations",
+                "the number of generations of the genetic algorithm",
+                "N",
+            )
+        }),
+        stable("fuzz-beam-width", |o| {
+            o.optopt(
+                "",
+                "fuzz-beam-width",
+                "the number of sequences kept per length by beam search",
+                "N",
+            )
+        }),
+        stable("fuzz-frequency-exponent", |o| {
+            o.optopt(
+                "",
+                "fuzz-frequency-exponent",
+                "the exponent applied to corpus API frequencies when selecting sequences \
+                (0 ignores frequencies, defaults to 1)",
+                "X",
+            )
+        }),
+        stable("fuzz-prioritize-unsafe", |o| {
+            o.optflag(
+                "",
+                "fuzz-prioritize-unsafe",
+                "prefer APIs that use unsafe code internally when generating and selecting sequences",
+            )
+        }),
+        stable("fuzz-keep-trivial-accessors", |o| {
+            o.optflag(
+                "",
+                "fuzz-keep-trivial-accessors",
+                "keep one-line getters/setters in the API graph instead of filtering them out",
+            )
+        }),
+        stable("fuzz-include", |o| {
+            o.optmulti(
+                "",
+                "fuzz-include",
+                "only generate targets for APIs whose full path matches the glob pattern",
+                "PATTERN",
+            )
+        }),
+        stable("fuzz-exclude", |o| {
+            o.optmulti(
+                "",
+                "fuzz-exclude",
+                "do not generate targets for APIs whose full path matches the glob pattern",
+                "PATTERN",
+            )
+        }),
+        stable("include-deprecated", |o| {
+            o.optflag("", "include-deprecated", "also generate targets for deprecated APIs")
+        }),
+        stable("include-doc-hidden", |o| {
+            o.optflag(
+                "",
+                "include-doc-hidden",
+                "also generate targets for #[doc(hidden)] APIs (implies --document-hidden-items)",
+            )
+        }),
+        stable("fuzz-numeric-cast", |o| {
+            o.optopt(
+                "",
+                "fuzz-numeric-cast",
+                "how integer results are passed to integer parameters of another width \
+                (defaults to checked try_into; off disables integer dependencies)",
+                "checked|as|off",
+            )
+        }),
+        stable("fuzz-path-mode", |o| {
+            o.optopt(
+                "",
+                "fuzz-path-mode",
+                "how Path and PathBuf parameters are built from the fuzz input: write it into a \
+                temporary file and pass the file's path, or use it as the path string \
+                (defaults to off)",
+                "off|temp-file|string",
+            )
+        }),
+        stable("fuzz-float-corners", |o| {
+            o.optflag(
+                "",
+                "fuzz-float-corners",
+                "decode an extra byte before each f32/f64 parameter that occasionally selects \
+                 NaN, infinities, subnormals and other special values",
+            )
+        }),
+        stable("allow-unsafe-synthesis", |o| {
+            o.optflag(
+                "",
+                "allow-unsafe-synthesis",
+                "pass uninitialized MaybeUninit slots to `&mut MaybeUninit<T>` and `*mut T` \
+                 out-parameters and `assume_init` them for later calls, which is undefined \
+                 behavior if the callee did not write the slot",
+            )
+        }),
+        stable("fuzz-internal", |o| {
+            o.optflag(
+                "",
+                "fuzz-internal",
+                "generate fuzz targets inside the tested crate so that pub(crate) APIs are also \
+                 fuzzed (implies --document-private-items)",
+            )
+        }),
+        stable("corpus-path", |o| {
+            o.optopt(
+                "",
+                "corpus-path",
+                "a mined sequence corpus file, or a directory of them \
+                (defaults to $FRIES_CORPUS_PATH, then the experiment root)",
+                "PATH",
+            )
+        }),
+        stable("load-graph", |o| {
+            o.optflag(
+                "",
+                "load-graph",
+                "reuse the API dependency graph cached by --save-graph for the same crate hash",
+            )
+        }),
+        stable("save-graph", |o| {
+            o.optflag("", "save-graph", "cache the API dependency graph, keyed by crate hash")
+        }),
+        stable("async-runtime", |o| {
+            o.optopt(
+                "",
+                "async-runtime",
+                "the runtime that drives targets calling async fns (defaults to futures)",
+                "futures|tokio",
+            )
+        }),
+        stable("fuzz-backend", |o| {
+            o.optmulti(
+                "",
+                "fuzz-backend",
+                "also emit targets for another fuzzing engine, besides the AFL ones",
+                "honggfuzz|bolero",
+            )
+        }),
+        stable("fuzz-encoding", |o| {
+            o.optmulti(
+                "",
+                "fuzz-encoding",
+                "emit a target for each sequence with this input encoding (defaults to raw)",
+                "raw|lossy|arbitrary",
+            )
+        }),
+        stable("fuzz-export-json", |o| {
+            o.optflag(
+                "",
+                "fuzz-export-json",
+                "also export the API functions, dependencies and selected sequences as JSON",
+            )
+        }),
+        stable("fuzz-interpreter", |o| {
+            o.optflag(
+                "",
+                "fuzz-interpreter",
+                "also emit one target per type that decodes the call order from the input",
+            )
+        }),
+        stable("fuzz-unwrap", |o| {
+            o.optopt(
+                "",
+                "fuzz-unwrap",
+                "how targets handle Err/None from a call (defaults to returning early)",
+                "return|unwrap|try",
+            )
+        }),
+        stable("fuzz-none", |o| {
+            o.optopt(
+                "",
+                "fuzz-none",
+                "how targets handle None from an Option-returning call (defaults to returning early)",
+                "return|unwrap|default",
+            )
+        }),
+        stable("fuzz-catch-panics", |o| {
+            o.optopt(
+                "",
+                "fuzz-catch-panics",
+                "catch panics in targets and only report the listed classes as crashes",
+                "assertion,index,overflow,unwrap,explicit|none",
+            )
+        }),
+        stable("target-timeout", |o| {
+            o.optopt(
+                "",
+                "target-timeout",
+                "abort a generated target whose call sequence runs longer than this",
+                "MILLISECONDS",
+            )
+        }),
+        stable("call-timeout", |o| {
+            o.optopt(
+                "",
+                "call-timeout",
+                "abort a generated target when a single API call runs longer than this",
+                "MILLISECONDS",
+            )
+        }),
         // deprecated / removed options
         unstable("disable-minification", |o| o.optflagmulti("", "disable-minification", "removed")),
         stable("plugin-path", |o| {
@@ -856,7 +1106,8 @@ fn main_args(at_args: &[String]) -> MainResult {
                 }
 
                 info!("going to format");
-                /*match output_format {
+                // html文档会读取上一次生成fuzz target时写出的索引，在函数页面上列出覆盖它的fuzz target
+                match output_format {
                     config::OutputFormat::Html => sess.time("render_html", || {
                         run_renderer::<html::render::Context<'_>>(krate, render_opts, cache, tcx)
                     }),
@@ -871,11 +1122,7 @@ fn main_args(at_args: &[String]) -> MainResult {
                             tcx,
                         )
                     }),
-                }*/
-
-                sess.time("target_generator", || {
-                    run_renderer::<fuzz_targets_gen::Context<'_>>(krate, render_opts, cache, tcx)
-                })
+                }
             })
         })
     })
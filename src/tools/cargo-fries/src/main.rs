@@ -0,0 +1,313 @@
+//! cargo fries：一条命令完成从待测crate到fuzz工程的全部步骤
+//! 1. 用FRIES的rustdoc对当前crate运行`cargo doc`，生成afl的fuzz target
+//! 2. rustdoc会把测试目录写成一个cargo工程，每个target一个bin（见librustdoc/fuzz_targets_gen/cargo_project.rs），
+//!    后面的步骤都在这个工程里进行
+//! 3. 指定--build时用`cargo afl build`编译fuzz工程
+//! 4. 指定--fuzz时编译之后运行afl-fuzz，见runner.rs
+//! 5. 指定--triage时用afl-cmin/afl-tmin整理crash，再用replay程序解码，见triage.rs
+//! 6. 指定--fuzz-ci时清掉上一次的结果，每个target只运行给定的秒数，
+//!    发现crash就打印解码之后的重现过程并返回非零的退出码，用于PR的CI
+//!
+//! 用法：
+//!     cargo fries [--rustdoc <path>] [--experiment-root <dir>] [--manifest-path <path>]
+//!                 [--build] [--fuzz <seconds>] [--jobs <n>] [--triage] [--fuzz-ci <seconds>]
+//!                 [--corpus-path <path>]
+
+mod runner;
+mod triage;
+
+use std::env;
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{self, Command};
+use std::time::Duration;
+
+const USAGE: &str = "usage: cargo fries [--rustdoc <path>] [--experiment-root <dir>] \
+                     [--manifest-path <path>] [--build] [--fuzz <seconds>] [--jobs <n>] \
+                     [--triage] [--fuzz-ci <seconds>] [--corpus-path <path>]";
+
+/// 和librustdoc/fuzz_targets_gen/file_util.rs里的默认值保持一致
+const DEFAULT_EXPERIMENT_ROOT: &str = "/home/yxz/workspace/fuzz/experiment_root";
+/// --fuzz-ci发现crash时的退出码，和参数错误(2)、运行出错(1)区分开
+const CI_CRASH_EXIT_CODE: i32 = 3;
+
+struct Options {
+    /// FRIES编译出来的rustdoc，默认使用环境变量FRIES_RUSTDOC，再没有就用PATH里的rustdoc
+    rustdoc: String,
+    /// 生成的fuzz target和配置文件所在的目录
+    experiment_root: PathBuf,
+    /// 待测crate的Cargo.toml
+    manifest_path: PathBuf,
+    /// 是否编译fuzz工程
+    build: bool,
+    /// 运行afl-fuzz的总时间（秒），None表示不运行
+    fuzz_budget: Option<u64>,
+    /// 同时运行的afl-fuzz实例数
+    jobs: usize,
+    /// 是否整理fuzz工程out目录里已有的crash
+    triage: bool,
+    /// CI模式下每个target运行的秒数
+    ci_secs: Option<u64>,
+    /// 序列语料（文件或者目录），通过FRIES_CORPUS_PATH传给rustdoc
+    corpus_path: Option<PathBuf>,
+}
+
+fn main() {
+    let options = match parse_args(env::args().skip(1).collect()) {
+        Ok(options) => options,
+        Err(e) => {
+            eprintln!("error: {}\n{}", e, USAGE);
+            process::exit(2);
+        }
+    };
+    match run(&options) {
+        Ok(0) => {}
+        Ok(crashes) => {
+            eprintln!("cargo fries: {} crashes found", crashes);
+            process::exit(CI_CRASH_EXIT_CODE);
+        }
+        Err(e) => {
+            eprintln!("error: {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+fn parse_args(mut args: Vec<String>) -> Result<Options, String> {
+    //作为cargo子命令运行时，第一个参数是"fries"
+    if args.first().map(|arg| arg.as_str()) == Some("fries") {
+        args.remove(0);
+    }
+    let mut options = Options {
+        rustdoc: env::var("FRIES_RUSTDOC").unwrap_or_else(|_| "rustdoc".to_string()),
+        experiment_root: PathBuf::from(
+            env::var("FRIES_EXPERIMENT_ROOT")
+                .unwrap_or_else(|_| DEFAULT_EXPERIMENT_ROOT.to_string()),
+        ),
+        manifest_path: PathBuf::from("Cargo.toml"),
+        build: false,
+        fuzz_budget: None,
+        jobs: 1,
+        triage: false,
+        ci_secs: None,
+        corpus_path: None,
+    };
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| args.next().ok_or_else(|| format!("{} needs a value", name));
+        match arg.as_str() {
+            "--rustdoc" => options.rustdoc = value("--rustdoc")?,
+            "--experiment-root" => {
+                options.experiment_root = PathBuf::from(value("--experiment-root")?)
+            }
+            "--manifest-path" => options.manifest_path = PathBuf::from(value("--manifest-path")?),
+            "--build" => options.build = true,
+            "--fuzz" => {
+                let budget = value("--fuzz")?;
+                options.fuzz_budget =
+                    Some(budget.parse().map_err(|_| format!("invalid --fuzz {}", budget))?);
+            }
+            "--jobs" => {
+                let jobs = value("--jobs")?;
+                options.jobs = jobs.parse().map_err(|_| format!("invalid --jobs {}", jobs))?;
+            }
+            "--triage" => options.triage = true,
+            "--fuzz-ci" => {
+                let secs = value("--fuzz-ci")?;
+                options.ci_secs =
+                    Some(secs.parse().map_err(|_| format!("invalid --fuzz-ci {}", secs))?);
+            }
+            "--corpus-path" => {
+                let corpus_path = PathBuf::from(value("--corpus-path")?);
+                //rustdoc在待测crate的目录下运行，相对路径要先转成绝对路径
+                options.corpus_path = Some(
+                    fs::canonicalize(&corpus_path)
+                        .map_err(|e| format!("can not find {}: {}", corpus_path.display(), e))?,
+                );
+            }
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                process::exit(0);
+            }
+            _ => return Err(format!("unknown argument {}", arg)),
+        }
+    }
+    Ok(options)
+}
+
+/// 返回CI模式下发现的crash数，其他模式总是0
+fn run(options: &Options) -> Result<usize, String> {
+    let manifest_path = fs::canonicalize(&options.manifest_path)
+        .map_err(|e| format!("can not find {}: {}", options.manifest_path.display(), e))?;
+    let crate_dir = manifest_path.parent().unwrap().to_path_buf();
+    let (package_name, lib_name) = read_package_names(&manifest_path)?;
+    println!("cargo fries: {} (lib {})", package_name, lib_name);
+
+    generate_targets(options, &crate_dir, &lib_name)?;
+
+    //file_util里的目录名用的是把下划线换成横线的crate名
+    let out_dir = options
+        .experiment_root
+        .join(lib_name.replace("_", "-"))
+        .join("fuzz_file_dir")
+        .join("real_world_afl_work");
+    let project_manifest = out_dir.join("Cargo.toml");
+    if !project_manifest.is_file() {
+        return Err(format!("no fuzz project generated in {}", out_dir.display()));
+    }
+    let targets_dir = out_dir.join("afl_files");
+    let targets = collect_targets(&targets_dir)?;
+    if targets.is_empty() {
+        return Err(format!("no fuzz target generated in {}", targets_dir.display()));
+    }
+    println!("cargo fries: {} fuzz targets in {}", targets.len(), targets_dir.display());
+    //bin的名字就是文件名，和cargo_project.rs里的写法一致
+    let bin_names = targets
+        .iter()
+        .map(|target| target.file_stem().unwrap().to_str().unwrap().to_string())
+        .collect::<Vec<_>>();
+
+    //运行afl-fuzz之前一定要先编译
+    if options.build || options.fuzz_budget.is_some() || options.triage || options.ci_secs.is_some()
+    {
+        run_command(
+            Command::new(cargo()).args(["afl", "build", "--release"]).current_dir(&out_dir),
+        )?;
+    }
+    if let Some(secs) = options.ci_secs {
+        return run_ci(&out_dir, &cargo(), &bin_names, secs, options.jobs);
+    }
+    if let Some(budget) = options.fuzz_budget {
+        let slice = runner::time_slice(budget, options.jobs, bin_names.len());
+        let results = runner::run_campaign(&out_dir, &cargo(), &bin_names, slice, options.jobs)?;
+        let report_path = out_dir.join("report.txt");
+        runner::write_report(&report_path, &results)?;
+        println!("cargo fries: report written to {}", report_path.display());
+    }
+    if options.triage {
+        triage::triage_all(&out_dir, &cargo(), &bin_names)?;
+    }
+    Ok(0)
+}
+
+/// CI模式：只统计这一次运行发现的crash，所以先清掉上一次的结果
+fn run_ci(
+    out_dir: &Path,
+    cargo: &str,
+    bin_names: &[String],
+    secs: u64,
+    jobs: usize,
+) -> Result<usize, String> {
+    for dir in [out_dir.join("out"), out_dir.join("triage")] {
+        if dir.is_dir() {
+            fs::remove_dir_all(&dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+        }
+    }
+    let slice = Duration::from_secs(secs);
+    let results = runner::run_campaign(out_dir, cargo, bin_names, slice, jobs)?;
+    let report_path = out_dir.join("report.txt");
+    runner::write_report(&report_path, &results)?;
+    let crashed_targets = results
+        .iter()
+        .filter(|result| result.crashes > 0)
+        .map(|result| result.target.clone())
+        .collect::<Vec<_>>();
+    if crashed_targets.is_empty() {
+        println!("cargo fries: no crash in {} targets", results.len());
+        return Ok(0);
+    }
+    let summaries = triage::triage_all(out_dir, cargo, &crashed_targets)?;
+    for summary in &summaries {
+        println!("==== {} | {}", summary.target, summary.input.display());
+        println!("{}", summary.replay_output.trim_end());
+    }
+    //afl-cmin可能把所有输入都去掉，至少按一个crash算
+    Ok(summaries.len().max(1))
+}
+
+/// 用FRIES的rustdoc运行cargo doc，rustdoc默认的输出格式就是fuzz target（见config.rs的OutputFormat）
+/// target dir不能叫target：fuzz_targets_gen会把target/doc当作语料库程序来解析
+fn generate_targets(options: &Options, crate_dir: &Path, lib_name: &str) -> Result<(), String> {
+    let target_dir = crate_dir.join("target").join("fries");
+    //cargo doc的输出是最新的话不会再运行rustdoc，所以先清掉上一次的文档
+    run_command(
+        Command::new(cargo())
+            .args(["clean", "--doc", "--target-dir"])
+            .arg(&target_dir)
+            .current_dir(crate_dir),
+    )?;
+    let mut doc = Command::new(cargo());
+    doc.args(["doc", "--lib", "--no-deps", "--target-dir"])
+        .arg(&target_dir)
+        .env("RUSTDOC", &options.rustdoc)
+        .env("FRIES_EXPERIMENT_ROOT", &options.experiment_root)
+        .env("FRIES_TARGET_CRATE", lib_name)
+        .current_dir(crate_dir);
+    if let Some(corpus_path) = &options.corpus_path {
+        doc.env("FRIES_CORPUS_PATH", corpus_path);
+    }
+    run_command(&mut doc)
+}
+
+fn collect_targets(targets_dir: &Path) -> Result<Vec<PathBuf>, String> {
+    let mut targets = Vec::new();
+    collect_targets_into(targets_dir, &mut targets)?;
+    targets.sort();
+    Ok(targets)
+}
+
+/// 生成的文件按模块放在子目录里，文件名本身已经包含模块路径，不会重名
+fn collect_targets_into(dir: &Path, targets: &mut Vec<PathBuf>) -> Result<(), String> {
+    let entries =
+        fs::read_dir(dir).map_err(|e| format!("can not read {}: {}", dir.display(), e))?;
+    for path in entries.filter_map(|entry| entry.ok().map(|entry| entry.path())) {
+        if path.is_dir() {
+            collect_targets_into(&path, targets)?;
+        } else if path.extension() == Some(OsStr::new("rs")) {
+            targets.push(path);
+        }
+    }
+    Ok(())
+}
+
+/// 从Cargo.toml里读出package名和lib名，只处理`name = "..."`这种最常见的写法
+fn read_package_names(manifest_path: &Path) -> Result<(String, String), String> {
+    let content = fs::read_to_string(manifest_path)
+        .map_err(|e| format!("can not read {}: {}", manifest_path.display(), e))?;
+    let mut section = String::new();
+    let mut package_name = None;
+    let mut lib_name = None;
+    for line in content.lines() {
+        let line = line.trim();
+        if line.starts_with('[') {
+            section = line.to_string();
+            continue;
+        }
+        let value = match line.split_once('=') {
+            Some((key, value)) if key.trim() == "name" => value.trim().trim_matches('"'),
+            _ => continue,
+        };
+        match section.as_str() {
+            "[package]" => package_name = Some(value.to_string()),
+            "[lib]" => lib_name = Some(value.to_string()),
+            _ => {}
+        }
+    }
+    let package_name =
+        package_name.ok_or_else(|| format!("no package name in {}", manifest_path.display()))?;
+    let lib_name = lib_name.unwrap_or_else(|| package_name.replace("-", "_"));
+    Ok((package_name, lib_name))
+}
+
+fn cargo() -> String {
+    env::var("CARGO").unwrap_or_else(|_| "cargo".to_string())
+}
+
+fn run_command(command: &mut Command) -> Result<(), String> {
+    let status = command.status().map_err(|e| format!("failed to run {:?}: {}", command, e))?;
+    if status.success() { Ok(()) } else { Err(format!("{:?} failed with {}", command, status)) }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,167 @@
+//! 在生成的fuzz工程上运行afl-fuzz
+//! 总的时间预算平均分给每个target，同时最多运行jobs个afl-fuzz实例，
+//! 每个target结束之后统计crashes和hangs，最后按target名字写出汇总报告
+
+use std::fs;
+use std::path::Path;
+use std::process::{Child, Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// 每个target最少运行的秒数，预算太少时也保证afl能完成初始化
+const MIN_TIME_SLICE_SECS: u64 = 10;
+/// 检查afl-fuzz实例是否结束的间隔
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// 一个fuzz target的运行结果
+pub struct TargetResult {
+    pub target: String,
+    pub crashes: usize,
+    pub hangs: usize,
+    pub run_time: Duration,
+    pub success: bool,
+}
+
+/// 时间片：budget_secs是所有target加起来的总时间，jobs个实例并行
+pub fn time_slice(budget_secs: u64, jobs: usize, target_number: usize) -> Duration {
+    if target_number == 0 {
+        return Duration::from_secs(0);
+    }
+    let secs = budget_secs * jobs.max(1) as u64 / target_number as u64;
+    Duration::from_secs(secs.max(MIN_TIME_SLICE_SECS))
+}
+
+/// 对fuzz工程里的每个target运行afl-fuzz，每个target运行slice，返回每个target的结果
+pub fn run_campaign(
+    project_dir: &Path,
+    cargo: &str,
+    targets: &[String],
+    slice: Duration,
+    jobs: usize,
+) -> Result<Vec<TargetResult>, String> {
+    let input_dir = project_dir.join("in");
+    let output_dir = project_dir.join("out");
+    ensure_seed(&input_dir)?;
+    fs::create_dir_all(&output_dir).map_err(|e| format!("{}: {}", output_dir.display(), e))?;
+    println!(
+        "cargo fries: fuzzing {} targets, {} parallel slots, {}s per target",
+        targets.len(),
+        jobs,
+        slice.as_secs()
+    );
+
+    let mut pending = targets.iter().collect::<Vec<_>>();
+    pending.reverse();
+    let mut running: Vec<(String, Child, Instant)> = Vec::new();
+    let mut results = Vec::new();
+    while !pending.is_empty() || !running.is_empty() {
+        while running.len() < jobs.max(1) {
+            let target = match pending.pop() {
+                Some(target) => target,
+                None => break,
+            };
+            let child = Command::new(cargo)
+                .args(["afl", "fuzz", "-i"])
+                .arg(&input_dir)
+                .arg("-o")
+                .arg(output_dir.join(target))
+                .args(["-V", slice.as_secs().to_string().as_str(), "--"])
+                .arg(project_dir.join("target").join("release").join(target))
+                .env("AFL_NO_UI", "1")
+                .stdout(Stdio::null())
+                .current_dir(project_dir)
+                .spawn()
+                .map_err(|e| format!("failed to start afl-fuzz for {}: {}", target, e))?;
+            println!("cargo fries: start {}", target);
+            running.push((target.clone(), child, Instant::now()));
+        }
+
+        let mut index = 0;
+        while index < running.len() {
+            let status = running[index]
+                .1
+                .try_wait()
+                .map_err(|e| format!("failed to wait for {}: {}", running[index].0, e))?;
+            match status {
+                Some(status) => {
+                    let (target, _, start) = running.remove(index);
+                    let result =
+                        collect_result(&output_dir, &target, start.elapsed(), status.success());
+                    println!(
+                        "cargo fries: finish {} ({} crashes, {} hangs)",
+                        target, result.crashes, result.hangs
+                    );
+                    results.push(result);
+                }
+                None => index += 1,
+            }
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+    //target的名字以模块路径开头，排序之后同一个模块的target挨在一起
+    results.sort_by(|x, y| x.target.cmp(&y.target));
+    Ok(results)
+}
+
+/// 把结果写成`target | crashes | hangs | 运行时间 | 状态`，和FRIES其他文件的格式一致
+pub fn write_report(report_path: &Path, results: &[TargetResult]) -> Result<(), String> {
+    let mut content = String::from("# target | crashes | hangs | seconds | status\n");
+    for result in results {
+        content.push_str(&format!(
+            "{} | {} | {} | {} | {}\n",
+            result.target,
+            result.crashes,
+            result.hangs,
+            result.run_time.as_secs(),
+            if result.success { "ok" } else { "failed" }
+        ));
+    }
+    let total_crashes: usize = results.iter().map(|result| result.crashes).sum();
+    let total_hangs: usize = results.iter().map(|result| result.hangs).sum();
+    content.push_str(&format!("# total | {} crashes | {} hangs\n", total_crashes, total_hangs));
+    fs::write(report_path, content).map_err(|e| format!("{}: {}", report_path.display(), e))
+}
+
+/// afl-fuzz需要至少一个种子
+fn ensure_seed(input_dir: &Path) -> Result<(), String> {
+    fs::create_dir_all(input_dir).map_err(|e| format!("{}: {}", input_dir.display(), e))?;
+    let has_seed = fs::read_dir(input_dir)
+        .map_err(|e| format!("{}: {}", input_dir.display(), e))?
+        .next()
+        .is_some();
+    if !has_seed {
+        let seed = input_dir.join("seed");
+        fs::write(&seed, [0u8; 64]).map_err(|e| format!("{}: {}", seed.display(), e))?;
+    }
+    Ok(())
+}
+
+fn collect_result(
+    output_dir: &Path,
+    target: &str,
+    run_time: Duration,
+    success: bool,
+) -> TargetResult {
+    let instance_dir = output_dir.join(target).join("default");
+    TargetResult {
+        target: target.to_string(),
+        crashes: count_findings(&instance_dir.join("crashes")),
+        hangs: count_findings(&instance_dir.join("hangs")),
+        run_time,
+        success,
+    }
+}
+
+/// afl的crashes和hangs目录里除了README.txt，每个文件是一个发现
+fn count_findings(dir: &Path) -> usize {
+    match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_name() != "README.txt")
+            .count(),
+        Err(_) => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests;
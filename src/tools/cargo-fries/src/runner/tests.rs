@@ -0,0 +1,100 @@
+//! 时间片的计算，以及在手写的afl输出目录上统计结果、写报告
+
+use super::{TargetResult, collect_result, ensure_seed, run_campaign, time_slice, write_report};
+use crate::tests::scratch_dir;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+/// afl-fuzz在out/{target}/default下的crashes和hangs目录，README.txt不算发现
+fn write_findings(output_dir: &Path, target: &str, crashes: usize, hangs: usize) {
+    let instance_dir = output_dir.join(target).join("default");
+    for (name, count) in [("crashes", crashes), ("hangs", hangs)] {
+        let dir = instance_dir.join(name);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("README.txt"), "Command line used to find this crash").unwrap();
+        for index in 0..count {
+            fs::write(dir.join(format!("id:{:06},sig:06", index)), [index as u8]).unwrap();
+        }
+    }
+}
+
+#[test]
+fn test_time_slice() {
+    assert_eq!(time_slice(600, 2, 3), Duration::from_secs(400));
+    assert_eq!(time_slice(600, 0, 3), Duration::from_secs(200));
+    assert_eq!(time_slice(20, 1, 5), Duration::from_secs(10));
+    assert_eq!(time_slice(600, 4, 0), Duration::from_secs(0));
+}
+
+#[test]
+fn test_collect_result() {
+    let output_dir = scratch_dir("collect-result");
+    write_findings(&output_dir, "fuzz_regex_new_01", 2, 1);
+
+    let result = collect_result(&output_dir, "fuzz_regex_new_01", Duration::from_secs(30), true);
+    assert_eq!((result.crashes, result.hangs), (2, 1));
+    let result = collect_result(&output_dir, "fuzz_regex_new_02", Duration::from_secs(30), false);
+    assert_eq!((result.crashes, result.hangs), (0, 0));
+    assert!(!result.success);
+}
+
+#[test]
+fn test_write_report() {
+    let dir = scratch_dir("write-report");
+    let report_path = dir.join("report.txt");
+    let results = [
+        TargetResult {
+            target: "fuzz_regex_new_01".to_string(),
+            crashes: 2,
+            hangs: 0,
+            run_time: Duration::from_millis(30500),
+            success: true,
+        },
+        TargetResult {
+            target: "fuzz_regex_split_02".to_string(),
+            crashes: 1,
+            hangs: 3,
+            run_time: Duration::from_secs(12),
+            success: false,
+        },
+    ];
+    write_report(&report_path, &results).unwrap();
+    assert_eq!(
+        fs::read_to_string(&report_path).unwrap(),
+        "# target | crashes | hangs | seconds | status\n\
+         fuzz_regex_new_01 | 2 | 0 | 30 | ok\n\
+         fuzz_regex_split_02 | 1 | 3 | 12 | failed\n\
+         # total | 3 crashes | 3 hangs\n"
+    );
+}
+
+/// 没有种子时写一个，已经有种子时不动
+#[test]
+fn test_ensure_seed() {
+    let input_dir = scratch_dir("ensure-seed").join("in");
+    ensure_seed(&input_dir).unwrap();
+    assert_eq!(fs::read(input_dir.join("seed")).unwrap(), vec![0u8; 64]);
+
+    fs::remove_file(input_dir.join("seed")).unwrap();
+    fs::write(input_dir.join("corpus_00"), "abc").unwrap();
+    ensure_seed(&input_dir).unwrap();
+    assert!(!input_dir.join("seed").exists());
+}
+
+/// 用`true`和`false`代替cargo，检查并行调度之后每个target都有结果，并且按名字排序
+#[cfg(unix)]
+#[test]
+fn test_run_campaign() {
+    let project_dir = scratch_dir("run-campaign");
+    let targets = ["fuzz_b_02", "fuzz_a_01", "fuzz_c_03"].map(String::from);
+
+    let results = run_campaign(&project_dir, "true", &targets, Duration::from_secs(1), 2).unwrap();
+    let names = results.iter().map(|result| result.target.as_str()).collect::<Vec<_>>();
+    assert_eq!(names, ["fuzz_a_01", "fuzz_b_02", "fuzz_c_03"]);
+    assert!(results.iter().all(|result| result.success && result.crashes == 0));
+    assert!(project_dir.join("in").join("seed").is_file());
+
+    let results = run_campaign(&project_dir, "false", &targets, Duration::from_secs(1), 1).unwrap();
+    assert!(results.iter().all(|result| !result.success));
+}
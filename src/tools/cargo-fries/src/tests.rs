@@ -0,0 +1,178 @@
+//! 命令行参数和待测crate信息的解析，以及CI模式的退出码
+
+use super::{collect_targets, parse_args, read_package_names, run_ci};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+/// 每个测试自己的临时目录，已经存在的话先清空，runner/tests.rs和triage/tests.rs也用它
+pub(crate) fn scratch_dir(name: &str) -> PathBuf {
+    let dir = env::temp_dir().join(format!("cargo-fries-{}-{}", process::id(), name));
+    if dir.exists() {
+        fs::remove_dir_all(&dir).unwrap();
+    }
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// 代替cargo afl的shell脚本，写在工程目录下，用`sh`作为cargo运行（`sh afl fuzz ...`）。
+/// fuzz只在名字里有split的target上留下一个crash，cmin按内容去重，tmin只保留第一个字节
+pub(crate) const FAKE_AFL: &str = r#"case "$1" in
+fuzz)
+    case "$5" in
+    *split*)
+        mkdir -p "$5/default/crashes"
+        printf CRASH > "$5/default/crashes/id:000000,sig:06"
+        ;;
+    esac
+    ;;
+cmin)
+    mkdir -p "$6"
+    for input in "$4"/id*; do
+        duplicate=
+        for kept in "$6"/id*; do
+            if [ -f "$kept" ] && cmp -s "$input" "$kept"; then duplicate=1; fi
+        done
+        if [ -z "$duplicate" ]; then cp "$input" "$6"/; fi
+    done
+    ;;
+tmin)
+    head -c 1 "$3" > "$5"
+    ;;
+esac
+"#;
+
+fn args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+fn parse_error(arguments: &[&str]) -> String {
+    match parse_args(args(arguments)) {
+        Ok(_) => panic!("{:?} should be rejected", arguments),
+        Err(e) => e,
+    }
+}
+
+#[test]
+fn test_parse_args() {
+    let options = parse_args(args(&[
+        "fries",
+        "--rustdoc",
+        "/opt/fries/bin/rustdoc",
+        "--experiment-root",
+        "/data/experiment_root",
+        "--manifest-path",
+        "regex/Cargo.toml",
+        "--build",
+        "--fuzz",
+        "600",
+        "--jobs",
+        "4",
+        "--triage",
+    ]))
+    .unwrap();
+    assert_eq!(options.rustdoc, "/opt/fries/bin/rustdoc");
+    assert_eq!(options.experiment_root, Path::new("/data/experiment_root"));
+    assert_eq!(options.manifest_path, Path::new("regex/Cargo.toml"));
+    assert!(options.build);
+    assert_eq!(options.fuzz_budget, Some(600));
+    assert_eq!(options.jobs, 4);
+    assert!(options.triage);
+
+    let options = parse_args(args(&["fries", "--fuzz-ci", "30", "--jobs", "2"])).unwrap();
+    assert_eq!(options.ci_secs, Some(30));
+    assert_eq!(options.fuzz_budget, None);
+}
+
+/// 直接运行cargo-fries时没有开头的"fries"
+#[test]
+fn test_parse_args_defaults() {
+    let options = parse_args(args(&[])).unwrap();
+    assert_eq!(options.manifest_path, Path::new("Cargo.toml"));
+    assert!(!options.build);
+    assert_eq!(options.fuzz_budget, None);
+    assert_eq!(options.jobs, 1);
+    assert!(!options.triage);
+    assert_eq!(options.ci_secs, None);
+    assert_eq!(options.corpus_path, None);
+}
+
+#[test]
+fn test_parse_args_errors() {
+    assert_eq!(parse_error(&["fries", "--jobs"]), "--jobs needs a value");
+    assert_eq!(parse_error(&["--fuzz", "ten"]), "invalid --fuzz ten");
+    assert_eq!(parse_error(&["--jobs", "-1"]), "invalid --jobs -1");
+    assert_eq!(parse_error(&["--fuzz-ci"]), "--fuzz-ci needs a value");
+    assert_eq!(parse_error(&["--fuzz-ci", "1m"]), "invalid --fuzz-ci 1m");
+    assert_eq!(parse_error(&["--build", "--release"]), "unknown argument --release");
+}
+
+#[test]
+fn test_read_package_names() {
+    let dir = scratch_dir("package-names");
+    let manifest_path = dir.join("Cargo.toml");
+
+    fs::write(
+        &manifest_path,
+        "[package]\nname = \"serde-json\"\nversion = \"1.0.0\"\n\n[dependencies]\nname = \"1\"\n",
+    )
+    .unwrap();
+    assert_eq!(
+        read_package_names(&manifest_path).unwrap(),
+        ("serde-json".to_string(), "serde_json".to_string())
+    );
+
+    fs::write(&manifest_path, "[package]\nname = \"regex-crate\"\n\n[lib]\nname = \"regex\"\n")
+        .unwrap();
+    assert_eq!(
+        read_package_names(&manifest_path).unwrap(),
+        ("regex-crate".to_string(), "regex".to_string())
+    );
+
+    fs::write(&manifest_path, "[workspace]\nmembers = [\"a\"]\n").unwrap();
+    assert!(read_package_names(&manifest_path).unwrap_err().starts_with("no package name"));
+}
+
+/// 按模块放在子目录里的target都要找到，不是.rs的文件跳过
+#[test]
+fn test_collect_targets() {
+    let dir = scratch_dir("collect-targets");
+    fs::create_dir_all(dir.join("bytes")).unwrap();
+    for file in ["fuzz_regex_new_01.rs", "bytes/fuzz_bytes_regex_new_02.rs", "bytes/notes.txt"] {
+        fs::write(dir.join(file), "").unwrap();
+    }
+    assert_eq!(
+        collect_targets(&dir).unwrap(),
+        vec![dir.join("bytes/fuzz_bytes_regex_new_02.rs"), dir.join("fuzz_regex_new_01.rs")]
+    );
+    assert!(collect_targets(&dir.join("missing")).is_err());
+}
+
+/// CI模式只统计这一次运行的crash：上一次留下的结果先清掉，没有新crash时返回0
+#[cfg(unix)]
+#[test]
+fn test_run_ci() {
+    let project_dir = scratch_dir("run-ci");
+    fs::write(project_dir.join("afl"), FAKE_AFL).unwrap();
+    let release_dir = project_dir.join("target").join("release");
+    fs::create_dir_all(&release_dir).unwrap();
+    std::os::unix::fs::symlink("/bin/cat", release_dir.join("replay_regex_split_02")).unwrap();
+    let stale_crashes = project_dir.join("out/fuzz_regex_new_01/default/crashes");
+    fs::create_dir_all(&stale_crashes).unwrap();
+    fs::write(stale_crashes.join("id:000000,sig:06"), "STALE").unwrap();
+
+    let targets = ["fuzz_regex_new_01".to_string()];
+    assert_eq!(run_ci(&project_dir, "sh", &targets, 1, 1), Ok(0));
+    assert!(!stale_crashes.exists());
+
+    let targets = ["fuzz_regex_new_01", "fuzz_regex_split_02"].map(String::from);
+    assert_eq!(run_ci(&project_dir, "sh", &targets, 1, 2), Ok(1));
+    assert_eq!(
+        fs::read_to_string(project_dir.join("triage/summary.txt")).unwrap(),
+        format!(
+            "==== fuzz_regex_split_02 | {}\nC\n",
+            project_dir.join("triage/fuzz_regex_split_02/tmin/id:000000,sig:06").display()
+        )
+    );
+}
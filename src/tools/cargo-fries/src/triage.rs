@@ -0,0 +1,148 @@
+//! fuzz结束之后整理crash
+//! 每个target：afl-cmin去掉重复的crash，afl-tmin最小化剩下的每个输入，
+//! 再用FRIES生成的replay程序（replay_xxx，会打印解码出来的数据和调用过程）运行最小化的输入，
+//! 把输出写成人可以直接阅读的summary
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// 一个crash整理之后的结果
+pub struct CrashSummary {
+    pub target: String,
+    /// 最小化之后的输入
+    pub input: PathBuf,
+    /// replay程序的输出
+    pub replay_output: String,
+}
+
+/// fuzz_xxx_01对应的replay程序是replay_xxx_01
+pub fn replay_bin_of(target: &str) -> String {
+    match target.strip_prefix("fuzz_") {
+        Some(rest) => format!("replay_{}", rest),
+        None => format!("replay_{}", target),
+    }
+}
+
+/// 整理fuzz工程里所有target的crash，结果写到triage目录下
+pub fn triage_all(
+    project_dir: &Path,
+    cargo: &str,
+    targets: &[String],
+) -> Result<Vec<CrashSummary>, String> {
+    let mut summaries = Vec::new();
+    for target in targets {
+        summaries.extend(triage_target(project_dir, cargo, target)?);
+    }
+    let summary_path = project_dir.join("triage").join("summary.txt");
+    write_summary(&summary_path, &summaries)?;
+    println!(
+        "cargo fries: {} crashes after triage, see {}",
+        summaries.len(),
+        summary_path.display()
+    );
+    Ok(summaries)
+}
+
+fn triage_target(
+    project_dir: &Path,
+    cargo: &str,
+    target: &str,
+) -> Result<Vec<CrashSummary>, String> {
+    let crashes_dir = project_dir.join("out").join(target).join("default").join("crashes");
+    if crash_files(&crashes_dir).is_empty() {
+        return Ok(Vec::new());
+    }
+    let release_dir = project_dir.join("target").join("release");
+    let target_bin = release_dir.join(target);
+    let triage_dir = project_dir.join("triage").join(target);
+    let cmin_dir = triage_dir.join("cmin");
+    let tmin_dir = triage_dir.join("tmin");
+    for dir in [&cmin_dir, &tmin_dir] {
+        if dir.is_dir() {
+            fs::remove_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+        }
+    }
+    fs::create_dir_all(&tmin_dir).map_err(|e| format!("{}: {}", tmin_dir.display(), e))?;
+
+    //-C：只保留crash的输入
+    let cmin_status = Command::new(cargo)
+        .args(["afl", "cmin", "-C", "-i"])
+        .arg(&crashes_dir)
+        .arg("-o")
+        .arg(&cmin_dir)
+        .arg("--")
+        .arg(&target_bin)
+        .current_dir(project_dir)
+        .status();
+    let cmin_ok = matches!(cmin_status, Ok(status) if status.success());
+    //afl-cmin失败的话退回到原始的crash
+    let inputs = if cmin_ok { crash_files(&cmin_dir) } else { crash_files(&crashes_dir) };
+
+    let mut summaries = Vec::new();
+    for input in inputs {
+        let minimized = tmin_dir.join(input.file_name().unwrap());
+        let tmin_status = Command::new(cargo)
+            .args(["afl", "tmin", "-i"])
+            .arg(&input)
+            .arg("-o")
+            .arg(&minimized)
+            .arg("--")
+            .arg(&target_bin)
+            .current_dir(project_dir)
+            .status();
+        let tmin_ok = matches!(tmin_status, Ok(status) if status.success());
+        let minimized = if tmin_ok { minimized } else { input };
+        let replay_output = replay(&release_dir.join(replay_bin_of(target)), &minimized);
+        summaries.push(CrashSummary {
+            target: target.to_string(),
+            input: minimized,
+            replay_output,
+        });
+    }
+    Ok(summaries)
+}
+
+/// 运行replay程序，返回stdout和stderr
+pub fn replay(replay_bin: &Path, input: &Path) -> String {
+    match Command::new(replay_bin).arg(input).output() {
+        Ok(output) => format!(
+            "{}{}",
+            String::from_utf8_lossy(&output.stdout),
+            String::from_utf8_lossy(&output.stderr)
+        ),
+        Err(e) => format!("failed to run {}: {}\n", replay_bin.display(), e),
+    }
+}
+
+fn write_summary(summary_path: &Path, summaries: &[CrashSummary]) -> Result<(), String> {
+    let mut content = String::new();
+    for summary in summaries {
+        content.push_str(&format!(
+            "==== {} | {}\n{}\n",
+            summary.target,
+            summary.input.display(),
+            summary.replay_output.trim_end()
+        ));
+    }
+    if let Some(parent) = summary_path.parent() {
+        fs::create_dir_all(parent).map_err(|e| format!("{}: {}", parent.display(), e))?;
+    }
+    fs::write(summary_path, content).map_err(|e| format!("{}: {}", summary_path.display(), e))
+}
+
+/// afl输出目录里除了README.txt的文件，按文件名排序
+fn crash_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files = match fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_file() && path.file_name().unwrap() != "README.txt")
+            .collect::<Vec<_>>(),
+        Err(_) => Vec::new(),
+    };
+    files.sort();
+    files
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,78 @@
+//! 在手写的afl输出目录上整理crash
+//! 假的cargo见crate::tests::FAKE_AFL，replay程序换成cat，直接输出最小化之后的输入
+
+use super::{crash_files, replay_bin_of, triage_all};
+use crate::tests::{FAKE_AFL, scratch_dir};
+use std::fs;
+use std::path::Path;
+
+/// 在out/{target}/default/crashes下写入crash，每个元素是一个输入的内容
+fn write_crashes(project_dir: &Path, target: &str, crashes: &[&str]) {
+    let crashes_dir = project_dir.join("out").join(target).join("default").join("crashes");
+    fs::create_dir_all(&crashes_dir).unwrap();
+    fs::write(crashes_dir.join("README.txt"), "Command line used to find this crash").unwrap();
+    for (index, content) in crashes.iter().enumerate() {
+        fs::write(crashes_dir.join(format!("id:{:06},sig:06", index)), content).unwrap();
+    }
+}
+
+#[test]
+fn test_replay_bin_of() {
+    assert_eq!(replay_bin_of("fuzz_regex_new_01"), "replay_regex_new_01");
+    assert_eq!(replay_bin_of("regex_new_01"), "replay_regex_new_01");
+}
+
+#[test]
+fn test_crash_files() {
+    let project_dir = scratch_dir("crash-files");
+    write_crashes(&project_dir, "fuzz_regex_new_01", &["b", "a"]);
+    let crashes_dir = project_dir.join("out/fuzz_regex_new_01/default/crashes");
+    assert_eq!(
+        crash_files(&crashes_dir),
+        vec![crashes_dir.join("id:000000,sig:06"), crashes_dir.join("id:000001,sig:06")]
+    );
+    assert!(crash_files(&project_dir.join("missing")).is_empty());
+}
+
+/// 重复的crash被cmin去掉，剩下的用tmin最小化之后交给replay程序
+#[cfg(unix)]
+#[test]
+fn test_triage_deduplicates_crashes() {
+    let project_dir = scratch_dir("triage-dedup");
+    fs::write(project_dir.join("afl"), FAKE_AFL).unwrap();
+    let release_dir = project_dir.join("target").join("release");
+    fs::create_dir_all(&release_dir).unwrap();
+    std::os::unix::fs::symlink("/bin/cat", release_dir.join("replay_regex_new_01")).unwrap();
+    write_crashes(&project_dir, "fuzz_regex_new_01", &["AAAA", "AAAA", "BBBB"]);
+    let targets = ["fuzz_regex_new_01", "fuzz_regex_split_02"].map(String::from);
+
+    let summaries = triage_all(&project_dir, "sh", &targets).unwrap();
+    let tmin_dir = project_dir.join("triage/fuzz_regex_new_01/tmin");
+    let inputs = summaries.iter().map(|summary| summary.input.clone()).collect::<Vec<_>>();
+    assert_eq!(inputs, vec![tmin_dir.join("id:000000,sig:06"), tmin_dir.join("id:000002,sig:06")]);
+    let outputs =
+        summaries.iter().map(|summary| summary.replay_output.as_str()).collect::<Vec<_>>();
+    assert_eq!(outputs, ["A", "B"]);
+    assert_eq!(
+        fs::read_to_string(project_dir.join("triage/summary.txt")).unwrap(),
+        format!(
+            "==== fuzz_regex_new_01 | {}\nA\n==== fuzz_regex_new_01 | {}\nB\n",
+            inputs[0].display(),
+            inputs[1].display()
+        )
+    );
+}
+
+/// afl-cmin和afl-tmin运行不了的时候保留所有原始的crash
+#[test]
+fn test_triage_without_afl() {
+    let project_dir = scratch_dir("triage-without-afl");
+    write_crashes(&project_dir, "fuzz_regex_new_01", &["AAAA", "AAAA"]);
+    let targets = ["fuzz_regex_new_01".to_string()];
+
+    let summaries = triage_all(&project_dir, "/nonexistent/cargo", &targets).unwrap();
+    let crashes_dir = project_dir.join("out/fuzz_regex_new_01/default/crashes");
+    let inputs = summaries.iter().map(|summary| summary.input.clone()).collect::<Vec<_>>();
+    assert_eq!(inputs, crash_files(&crashes_dir));
+    assert!(summaries.iter().all(|summary| summary.replay_output.starts_with("failed to run")));
+}